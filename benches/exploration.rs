@@ -0,0 +1,25 @@
+//! End-to-end exploration benchmarks for each of the
+//! `haybale::bench_support` scenarios. Run with
+//! `cargo bench --features bench-support`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use haybale::bench_support::{project, run_scenario, SCENARIOS};
+use haybale::config::NullPointerChecking;
+use haybale::Config;
+
+fn exploration(c: &mut Criterion) {
+    let mut group = c.benchmark_group("exploration");
+    for scenario in SCENARIOS {
+        // parse the module up front, so it isn't part of the first sample
+        project(scenario);
+        group.bench_function(scenario.name, |b| b.iter(|| {
+            let mut config = Config::default();
+            config.null_pointer_checking = NullPointerChecking::None;
+            run_scenario(scenario.name, config)
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, exploration);
+criterion_main!(benches);
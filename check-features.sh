@@ -0,0 +1,15 @@
+#!/bin/sh
+
+# Check that haybale and its tests build with no optional features, with each
+# optional feature on its own, with some common combinations, and with all of
+# them. Needs the same LLVM and Boolector setup as a normal build.
+
+set -e
+cargo check --all-targets --no-default-features
+for feature in fp parallel taint serde-results bench-support; do
+  cargo check --all-targets --no-default-features --features "$feature"
+done
+cargo check --all-targets --no-default-features --features "taint serde-results"
+cargo check --all-targets --no-default-features --features "fp taint"
+cargo check --all-targets
+cargo check --all-targets --all-features
@@ -1,6 +1,8 @@
 use crate::memory::Memory;
+use llvm_ir::Type;
 use log::{debug, warn};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
 
 /// An extremely simple bump-allocator which never frees
 #[derive(Clone)]
@@ -9,16 +11,27 @@ pub struct Alloc {
     cursor: u64,
 
     /// Map from allocation address to its size in bits
-    sizes: HashMap<u64, u64>,
+    sizes: BTreeMap<u64, u64>,
+
+    /// Map from allocation address to a description of what was allocated
+    /// there (e.g., the name of a global), and its `Type` if known
+    labels: HashMap<u64, (String, Option<Type>)>,
+
+    /// Ranges of addresses which we must not allocate
+    reserved: Vec<Range<u64>>,
 }
 
 impl Alloc {
     pub const ALLOC_START: u64 = 0x1000_0000;  // we allocate from this address upwards
 
-    pub fn new() -> Self {
+    /// A new `Alloc`, which will never allocate addresses in any of the given
+    /// `reserved` ranges
+    pub fn new(reserved: Vec<Range<u64>>) -> Self {
         Self {
             cursor: Self::ALLOC_START,
-            sizes: HashMap::new(),
+            sizes: BTreeMap::new(),
+            labels: HashMap::new(),
+            reserved,
         }
     }
 
@@ -40,13 +53,20 @@ impl Alloc {
             }
             bytes
         };
-        let current_offset_bytes = self.cursor % cell_bytes;
-        let bytes_remaining_in_cell = cell_bytes - current_offset_bytes;
-        if bytes > bytes_remaining_in_cell {
-            self.cursor += bytes_remaining_in_cell;
-            assert_eq!(self.cursor % cell_bytes, 0);
-        }
-        let rval = self.cursor;
+        let rval = loop {
+            let current_offset_bytes = self.cursor % cell_bytes;
+            let bytes_remaining_in_cell = cell_bytes - current_offset_bytes;
+            if bytes > bytes_remaining_in_cell {
+                self.cursor += bytes_remaining_in_cell;
+                assert_eq!(self.cursor % cell_bytes, 0);
+            }
+            let end = self.cursor + std::cmp::max(bytes, 1);
+            match self.reserved.iter().find(|range| range.start < end && self.cursor < range.end) {
+                // skip past the reserved range, to the next cell boundary
+                Some(range) => self.cursor = range.end.div_ceil(cell_bytes) * cell_bytes,
+                None => break self.cursor,
+            }
+        };
         self.cursor += bytes;
         self.sizes.insert(rval, bits);
         debug!("Allocated {} bits at 0x{:x}", bits, rval);
@@ -58,4 +78,41 @@ impl Alloc {
     pub fn get_allocation_size(&self, addr: impl Into<u64>) -> Option<u64> {
         self.sizes.get(&addr.into()).copied()
     }
+
+    /// Attach a description (and optionally a `Type`) to the allocation at the
+    /// given address, replacing any existing one
+    pub fn label(&mut self, addr: u64, label: String, ty: Option<Type>) {
+        self.labels.insert(addr, (label, ty));
+    }
+
+    /// Get the description and `Type` (if any) attached to the allocation at
+    /// the given address with `label()`
+    pub fn get_label(&self, addr: u64) -> Option<&(String, Option<Type>)> {
+        self.labels.get(&addr)
+    }
+
+    /// Get the address at or above which all future allocations will be made
+    pub fn next_address(&self) -> u64 {
+        self.cursor
+    }
+
+    /// Iterate over the allocations starting at or above the given address,
+    /// in address order, as pairs of start address and size in bits
+    pub fn allocations_from(&self, addr: u64) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.sizes.range(addr ..).map(|(&start, &bits)| (start, bits))
+    }
+
+    /// Find the allocation containing the given address, returning its start
+    /// address and its size in bits. Addresses one past the end of an
+    /// allocation count as part of it, unless they are the start of another
+    /// allocation.
+    pub fn find_allocation(&self, addr: u64) -> Option<(u64, u64)> {
+        let (&start, &bits) = self.sizes.range(..= addr).next_back()?;
+        let bytes = bits.div_ceil(u64::from(Memory::BITS_IN_BYTE));
+        if addr - start <= bytes {
+            Some((start, bits))
+        } else {
+            None
+        }
+    }
 }
@@ -2,14 +2,16 @@
 //! These may be useful in implementing hooks for various functions that
 //! perform memory allocation.
 
-use crate::backend::Backend;
+use crate::backend::*;
 use crate::error::*;
+use crate::layout::{get_offset_constant_index, is_union_type, size_opaque_aware, POINTER_SIZE_BITS};
+use crate::project::Project;
 use crate::state::State;
 use llvm_ir::*;
 use log::warn;
 
 /// Assume that allocations never exceed this size.
-const MAX_ALLOCATION_SIZE_BYTES: u64 = 1 << 20;
+pub(crate) const MAX_ALLOCATION_SIZE_BYTES: u64 = 1 << 20;
 
 /// Allocate a number of bytes given by the `Operand`.
 ///
@@ -23,7 +25,10 @@ pub fn malloc<B: Backend>(state: &mut State<B>, num_bytes: &Operand) -> Result<B
             Since this allocation is constant-sized, it's fine in this case, but does draw into question the assumption.", num_bytes, MAX_ALLOCATION_SIZE_BYTES);
     }
     let num_bits = num_bytes * 8;
-    Ok(state.allocate(num_bits))
+    let addr = state.allocate(num_bits);
+    label_heap_allocation(state, &addr, "malloc")?;
+    state.mark_uninitialized(&addr, num_bits as u32)?;
+    Ok(addr)
 }
 
 /// Allocate a number of bytes given by the `Operand`.
@@ -39,6 +44,7 @@ pub fn zalloc<B: Backend>(state: &mut State<B>, num_bytes: &Operand) -> Result<B
     }
     let num_bits = num_bytes * 8;
     let addr = state.allocate(num_bits);
+    label_heap_allocation(state, &addr, "zalloc")?;
     state.write(&addr, state.zero(num_bits as u32))?;
     Ok(addr)
 }
@@ -59,38 +65,249 @@ pub fn calloc<B: Backend>(state: &mut State<B>, a: &Operand, b: &Operand) -> Res
     }
     let num_bits = num_bytes * 8;
     let addr = state.allocate(num_bits);
+    label_heap_allocation(state, &addr, "calloc")?;
     state.write(&addr, state.zero(num_bits as u32))?;
     Ok(addr)
 }
 
+/// Label the allocation at `addr` with the name of the allocating function and
+/// the current location, e.g. `malloc at x.c:10`; see
+/// [`State::label_allocation()`](../struct.State.html#method.label_allocation)
+fn label_heap_allocation<B: Backend>(state: &mut State<B>, addr: &B::BV, what: &str) -> Result<()> {
+    let loc = match state.cur_loc.source_loc {
+        Some(source_loc) => source_loc.to_string(),
+        None => state.cur_loc.to_string_no_module(),
+    };
+    state.label_allocation(addr, format!("{} at {}", what, loc), None)
+}
+
 /// Reallocate the given `addr` to be at least the number of bytes given by the `Operand`.
 ///
+/// If the allocation moves, the contents of the old allocation (up to the new
+/// size) are copied to the new one, and the old one is `free()`d; see
+/// [`State::free()`](../struct.State.html#method.free). Whether shrinking
+/// moves the allocation is controlled by
+/// [`Config.realloc_always_moves`](../config/struct.Config.html#structfield.realloc_always_moves).
+/// As in C, reallocating `NULL` is the same as `malloc()`.
+///
 /// Returns the address of the allocation, which may or may not be the same
 /// address which was passed in.
 pub fn realloc<B: Backend>(state: &mut State<B>, addr: &Operand, num_bytes: &Operand) -> Result<B::BV> {
     let addr = state.operand_to_bv(addr)?;
+    if addr.as_u64() == Some(0) {
+        return malloc(state, num_bytes);
+    }
     // As in `malloc()`, note that allocating too much doesn't hurt anything
-    let new_size = try_as_u64(num_bytes).unwrap_or(MAX_ALLOCATION_SIZE_BYTES);
+    let new_size = state.operand_to_bv(num_bytes)?.as_u64().unwrap_or(MAX_ALLOCATION_SIZE_BYTES);
     if new_size > MAX_ALLOCATION_SIZE_BYTES {
         warn!("warning: encountered an allocation of {} bytes, greater than the assumed max of {}. \
             Since this allocation is constant-sized, it's fine in this case, but does draw into question the assumption.", new_size, MAX_ALLOCATION_SIZE_BYTES);
     }
-    let old_size = state.get_allocation_size(&addr)?.ok_or_else(|| Error::OtherError("realloc: failed to get old allocation size".to_owned()))?;
-    if new_size <= old_size {
+    let new_bits = new_size * 8;
+    let old_bits = state.get_allocation_size(&addr)?.ok_or_else(|| Error::OtherError("realloc: failed to get old allocation size".to_owned()))?;
+    if new_bits <= old_bits && !state.config.realloc_always_moves {
         // We treat this as a no-op. You get to keep the larger old_size region you already had.
         Ok(addr)
     } else {
         // Make a new allocation
-        let new_addr = state.allocate(new_size);
-        // Copy the contents of the old allocation
-        let contents = state.read(&addr, old_size as u32)?;
-        state.write(&new_addr, contents)?;
-        // We don't free(), as our allocator won't ever reuse allocated addresses anyway.
-        // So, we can just return
+        let new_addr = state.allocate(new_bits);
+        label_heap_allocation(state, &new_addr, "realloc")?;
+        state.mark_uninitialized(&new_addr, new_bits as u32)?;
+        // Copy the contents of the old allocation, as far as they fit, along
+        // with which of them were initialized
+        let copy_bits = std::cmp::min(old_bits, new_bits);
+        if copy_bits > 0 {
+            let contents = state.read_possibly_uninitialized(&addr, copy_bits as u32)?;
+            state.write(&new_addr, contents)?;
+            state.copy_initialization(&new_addr, &addr, copy_bits as u32)?;
+        }
+        state.free(&addr)?;
         Ok(new_addr)
     }
 }
 
+/// How the innermost pointers of a structure allocated by
+/// [`alloc_recursive()`](fn.alloc_recursive.html) are terminated
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecursionEnd {
+    /// The pointers are NULL
+    Null,
+    /// The pointers point back to the node with the given index (in the order
+    /// returned by `alloc_recursive()`), creating a cycle
+    CycleTo(usize),
+}
+
+/// Allocate a recursive data structure (for instance, a linked list or a
+/// tree) made of nodes of the given `NamedStructType`, `depth` levels deep.
+///
+/// Each field of the struct which is a pointer to the same struct type is
+/// pointed at a freshly-allocated child node, except in the nodes at the last
+/// level, where those fields are set according to `end`. All other fields are
+/// left unconstrained.
+///
+/// Returns the addresses of all the nodes, in preorder; the first is the root.
+/// So for a linked list, the addresses are in list order, and there are
+/// exactly `depth` of them.
+///
+/// This is useful for setting up pointer parameters of a function before
+/// executing it, e.g. by constraining the parameter to equal the root. Since
+/// the last-level pointers are then genuinely NULL (with `RecursionEnd::Null`),
+/// code which checks for NULL before following them is fine, while code which
+/// follows them unconditionally will hit `Error::NullPointerDereference` as
+/// usual (unless `Config.null_pointer_checking` is `None`).
+pub fn alloc_recursive<B: Backend>(state: &mut State<B>, project: &Project, ty: &Type, depth: usize, end: RecursionEnd) -> Result<Vec<B::BV>> {
+    if depth == 0 {
+        return Err(Error::OtherError("alloc_recursive: depth must be at least 1".to_owned()));
+    }
+    let inner = match ty {
+        Type::NamedStructType { name, .. } => project.get_inner_struct_type_from_named(ty)
+            .ok_or_else(|| Error::OtherError(format!("alloc_recursive: struct type {:?} is opaque", name)))?,
+        _ => return Err(Error::OtherError(format!("alloc_recursive: expected a named struct type, got {:?}", ty))),
+    };
+    let inner: Type = inner.read().unwrap().clone();
+    let num_fields = match &inner {
+        Type::StructType { element_types, .. } => element_types.len(),
+        _ => return Err(Error::MalformedInstruction(format!("Expected NamedStructType inner type to be a StructType, but got {:?}", inner))),
+    };
+    // byte offsets of the fields which point to another node
+    let mut link_offsets = vec![];
+    for i in 0 .. num_fields {
+        let (offset, field_ty) = get_offset_constant_index(&inner, i)?;
+        if let Type::PointerType { pointee_type, .. } = &field_ty {
+            if **pointee_type == *ty {
+                link_offsets.push(offset as u64);
+            }
+        }
+    }
+    let node_bits = size_opaque_aware(&inner, project)
+        .ok_or_else(|| Error::OtherError(format!("alloc_recursive: can't get the size of {:?}", ty)))?;
+
+    let mut nodes = vec![];
+    let mut last_links = vec![];
+    alloc_node(state, node_bits as u64, &link_offsets, depth, &mut nodes, &mut last_links)?;
+    let end = match end {
+        RecursionEnd::Null => state.zero(POINTER_SIZE_BITS as u32),
+        RecursionEnd::CycleTo(idx) => nodes.get(idx).cloned()
+            .ok_or_else(|| Error::OtherError(format!("alloc_recursive: can't cycle back to node {}, there are only {} nodes", idx, nodes.len())))?,
+    };
+    for link in last_links {
+        state.write(&link, end.clone())?;
+    }
+    Ok(nodes)
+}
+
+/// Allocate one node for `alloc_recursive()`, and (recursively) its children,
+/// if it has `levels_left > 1`. The addresses of the link fields in the
+/// last-level nodes are pushed to `last_links` rather than filled in.
+fn alloc_node<B: Backend>(
+    state: &mut State<B>,
+    node_bits: u64,
+    link_offsets: &[u64],
+    levels_left: usize,
+    nodes: &mut Vec<B::BV>,
+    last_links: &mut Vec<B::BV>,
+) -> Result<B::BV> {
+    let node = state.allocate(node_bits);
+    nodes.push(node.clone());
+    for &offset in link_offsets {
+        let link = node.add(&state.bv_from_u64(offset, POINTER_SIZE_BITS as u32));
+        if levels_left > 1 {
+            let child = alloc_node(state, node_bits, link_offsets, levels_left - 1, nodes, last_links)?;
+            state.write(&link, child)?;
+        } else {
+            last_links.push(link);
+        }
+    }
+    Ok(node)
+}
+
+/// Describes the initial contents of a value, for
+/// [`alloc_arg()`](fn.alloc_arg.html)
+#[derive(Clone, PartialEq, Debug)]
+pub enum ArgSpec {
+    /// Leave the value unconstrained
+    Unconstrained,
+    /// A constant integer. Only valid for integer types.
+    Int(u64),
+    /// A struct, with one `ArgSpec` for each of its fields, in order. Not
+    /// valid for unions (as detected by
+    /// [`layout::is_union_type()`](../layout/fn.is_union_type.html)), whose
+    /// lowered fields don't correspond to their members; use `Union` instead.
+    Struct(Vec<ArgSpec>),
+    /// A union, initialized through the member of type `member`, whose
+    /// contents are given by `value`. That member starts at the beginning of
+    /// the union; any bytes past its end are left unconstrained. Since our
+    /// memory model is agnostic to the types of reads, reading the union
+    /// through any other member then sees the same bytes, reinterpreted.
+    ///
+    /// `member` can be any type no larger than the union, not only the one
+    /// `clang` kept in the lowered struct. This is also valid for types which
+    /// `is_union_type()` doesn't recognize, explicitly marking them as unions.
+    Union { member: Type, value: Box<ArgSpec> },
+}
+
+/// Allocate memory for a value of the given `Type`, and initialize it as
+/// described by `spec`.
+///
+/// Returns the address of the newly-allocated memory. Like
+/// [`alloc_recursive()`](fn.alloc_recursive.html), this is useful for setting
+/// up pointer parameters of a function before executing it.
+pub fn alloc_arg<B: Backend>(state: &mut State<B>, project: &Project, ty: &Type, spec: &ArgSpec) -> Result<B::BV> {
+    let bits = size_opaque_aware(ty, project)
+        .ok_or_else(|| Error::OtherError(format!("alloc_arg: can't get the size of {:?}", ty)))?;
+    let addr = state.allocate(bits as u64);
+    write_arg(state, project, &addr, ty, spec)?;
+    Ok(addr)
+}
+
+/// Initialize the value of the given `Type` at `addr` as described by `spec`
+fn write_arg<B: Backend>(state: &mut State<B>, project: &Project, addr: &B::BV, ty: &Type, spec: &ArgSpec) -> Result<()> {
+    match spec {
+        ArgSpec::Unconstrained => Ok(()),
+        ArgSpec::Int(value) => match ty {
+            Type::IntegerType { bits } => {
+                let bv = state.bv_from_u64(*value, *bits);
+                state.write(addr, bv)
+            },
+            _ => Err(Error::OtherError(format!("alloc_arg: ArgSpec::Int is only valid for integer types, got {:?}", ty))),
+        },
+        ArgSpec::Struct(fields) => {
+            if is_union_type(ty) {
+                return Err(Error::OtherError(format!("alloc_arg: {:?} is a union; initialize it through one of its members with ArgSpec::Union", ty)));
+            }
+            let inner: Type = match ty {
+                Type::NamedStructType { name, .. } => project.get_inner_struct_type_from_named(ty)
+                    .ok_or_else(|| Error::OtherError(format!("alloc_arg: struct type {:?} is opaque", name)))?
+                    .read().unwrap().clone(),
+                _ => ty.clone(),
+            };
+            let num_fields = match &inner {
+                Type::StructType { element_types, .. } => element_types.len(),
+                _ => return Err(Error::OtherError(format!("alloc_arg: ArgSpec::Struct is only valid for struct types, got {:?}", ty))),
+            };
+            if fields.len() != num_fields {
+                return Err(Error::OtherError(format!("alloc_arg: struct type {:?} has {} fields, but the ArgSpec has {}", ty, num_fields, fields.len())));
+            }
+            for (i, field) in fields.iter().enumerate() {
+                let (offset, field_ty) = get_offset_constant_index(&inner, i)?;
+                let field_addr = addr.add(&state.bv_from_u64(offset as u64, POINTER_SIZE_BITS as u32));
+                write_arg(state, project, &field_addr, &field_ty, field)?;
+            }
+            Ok(())
+        },
+        ArgSpec::Union { member, value } => {
+            let union_bits = size_opaque_aware(ty, project)
+                .ok_or_else(|| Error::OtherError(format!("alloc_arg: can't get the size of {:?}", ty)))?;
+            let member_bits = size_opaque_aware(member, project)
+                .ok_or_else(|| Error::OtherError(format!("alloc_arg: can't get the size of {:?}", member)))?;
+            if member_bits > union_bits {
+                return Err(Error::OtherError(format!("alloc_arg: member {:?} ({} bits) doesn't fit in union {:?} ({} bits)", member, member_bits, ty, union_bits)));
+            }
+            write_arg(state, project, addr, member, value)
+        },
+    }
+}
+
 /// Try to interpret the `Operand` as a constant integer, and if so, return the value as a `u64`.
 /// (But don't try too hard - as of this writing, doesn't even try to evaluate constant expressions.)
 fn try_as_u64(op: &Operand) -> Option<u64> {
@@ -99,3 +316,535 @@ fn try_as_u64(op: &Operand) -> Option<u64> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::config::Config;
+    use crate::return_value::ReturnValue;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, RwLock};
+
+    /// `struct node { int val; struct node *next; }`, along with the `Arc`
+    /// which must be kept alive for the type to remain valid
+    fn node_type() -> (Type, Arc<RwLock<Type>>) {
+        let arc = Arc::new(RwLock::new(Type::VoidType));
+        let node = Type::NamedStructType { name: "struct.node".to_owned(), ty: Some(Arc::downgrade(&arc)) };
+        *arc.write().unwrap() = Type::StructType {
+            element_types: vec![Type::i32(), Type::pointer_to(node.clone())],
+            is_packed: false,
+        };
+        (node, arc)
+    }
+
+    /// A function `int sum(struct node *n)` which sums the `val`s of the first
+    /// `levels` nodes of the list, following `next` without checking for NULL
+    fn list_sum(node: &Type, levels: usize) -> Function {
+        let node_ptr = Type::pointer_to(node.clone());
+        let i32_const = |value| int_const(32, value);
+        let mut func = blank_function("sum", vec![Name::from("entry")]);
+        func.parameters.push(param("p0", node_ptr.clone()));
+        func.return_type = Type::i32();
+        let instrs = &mut func.basic_blocks[0].instrs;
+        for i in 0 .. levels {
+            instrs.push(Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: local(format!("p{}", i), node_ptr.clone()),
+                indices: vec![i32_const(0), i32_const(0)],
+                dest: Name::from(format!("valp{}", i)),
+                in_bounds: true,
+                debugloc: None,
+            }));
+            instrs.push(load(local(format!("valp{}", i), Type::pointer_to(Type::i32())), format!("val{}", i)));
+            instrs.push(Instruction::Add(instruction::Add {
+                operand0: if i == 0 { i32_const(0) } else { local(format!("sum{}", i - 1), Type::i32()) },
+                operand1: local(format!("val{}", i), Type::i32()),
+                dest: Name::from(format!("sum{}", i)),
+                debugloc: None,
+            }));
+            instrs.push(Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: local(format!("p{}", i), node_ptr.clone()),
+                indices: vec![i32_const(0), i32_const(1)],
+                dest: Name::from(format!("nextp{}", i)),
+                in_bounds: true,
+                debugloc: None,
+            }));
+            instrs.push(Instruction::Load(instruction::Load {
+                address: local(format!("nextp{}", i), Type::pointer_to(node_ptr.clone())),
+                dest: Name::from(format!("p{}", i + 1)),
+                volatile: false,
+                atomicity: None,
+                alignment: 8,
+                debugloc: None,
+            }));
+        }
+        func.basic_blocks[0].term = ret(local(format!("sum{}", levels - 1), Type::i32()));
+        func
+    }
+
+    fn project_with_node_type(func: Function, arc: Arc<RwLock<Type>>) -> Project {
+        let mut named_struct_types = HashMap::new();
+        named_struct_types.insert("struct.node".to_owned(), Some(arc));
+        let mut module = module_with_functions("test_mod", vec![func]);
+        module.named_struct_types = named_struct_types;
+        Project::from_module(module)
+    }
+
+    /// Set up the `sum` function's parameter as a list of depth 3
+    fn setup_list<'p>(project: &'p Project, node: &Type, end: RecursionEnd) -> Result<(ExecutionManager<'p, BtorBackend>, Vec<<BtorBackend as Backend>::BV>)> {
+        let mut em: ExecutionManager<BtorBackend> = symex_function("sum", project, Config::default());
+        let param = em.param_bvs()[0].clone();
+        let nodes = alloc_recursive(em.mut_state(), project, node, 3, end)?;
+        param._eq(&nodes[0]).assert();
+        Ok((em, nodes))
+    }
+
+    #[test]
+    fn list_of_depth_3() -> Result<()> {
+        let (node, arc) = node_type();
+        let project = project_with_node_type(list_sum(&node, 3), arc);
+        let (mut em, nodes) = setup_list(&project, &node, RecursionEnd::Null)?;
+        assert_eq!(nodes.len(), 3);
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(retval))) => retval,
+            other => panic!("Expected a return value, got {:?}", other),
+        };
+        let state = em.state();
+        // the witness includes a value for each node, and they add up to the result
+        let vals = nodes.iter().map(|node| state.read(node, 32)).collect::<Result<Vec<_>>>()?;
+        let sum = vals[0].add(&vals[1]).add(&vals[2]);
+        assert!(!state.sat_with_extra_constraints(std::iter::once(&retval._ne(&sum)))?);
+        for val in &vals {
+            assert!(state.get_a_solution_for_bv(val)?.is_some());
+        }
+        // the last node's `next` is NULL
+        let last_next = state.read(&nodes[2].add(&state.bv_from_u64(4, 64)), 64)?;
+        assert_eq!(last_next.as_u64(), Some(0));
+        Ok(())
+    }
+
+    #[test]
+    fn unguarded_deref_past_end() -> Result<()> {
+        let (node, arc) = node_type();
+        let project = project_with_node_type(list_sum(&node, 4), arc);
+        let (mut em, _) = setup_list(&project, &node, RecursionEnd::Null)?;
+        match em.next() {
+            Some(Err(Error::NullPointerDereference)) => {},
+            other => panic!("Expected a null-pointer dereference, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cyclic_list() -> Result<()> {
+        let (node, arc) = node_type();
+        // with a cycle back to the head, summing 4 nodes reads the head twice
+        let project = project_with_node_type(list_sum(&node, 4), arc);
+        let (mut em, nodes) = setup_list(&project, &node, RecursionEnd::CycleTo(0))?;
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(retval))) => retval,
+            other => panic!("Expected a return value, got {:?}", other),
+        };
+        let state = em.state();
+        let vals = nodes.iter().map(|node| state.read(node, 32)).collect::<Result<Vec<_>>>()?;
+        let sum = vals[0].add(&vals[1]).add(&vals[2]).add(&vals[0]);
+        assert!(!state.sat_with_extra_constraints(std::iter::once(&retval._ne(&sum)))?);
+        Ok(())
+    }
+
+
+    /// A named struct type with the given name and fields, along with the
+    /// `Arc` which must be kept alive for the type to remain valid
+    fn named_struct_type(name: &str, element_types: Vec<Type>) -> (Type, Arc<RwLock<Type>>) {
+        let arc = Arc::new(RwLock::new(Type::StructType { element_types, is_packed: false }));
+        (Type::NamedStructType { name: name.to_owned(), ty: Some(Arc::downgrade(&arc)) }, arc)
+    }
+
+    /// `union U { uint32_t word; uint8_t bytes[4]; }`, which `clang` lowers to
+    /// `%union.U = type { i32 }`, and the function
+    /// `uint8_t third_byte(union U *u) { return u->bytes[2]; }`
+    fn union_and_third_byte() -> (Type, Project, Arc<RwLock<Type>>) {
+        let (union, arc) = named_struct_type("union.U", vec![Type::i32()]);
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let mut func = blank_function("third_byte", vec![Name::from("entry")]);
+        func.parameters.push(param("u", Type::pointer_to(union.clone())));
+        func.return_type = Type::i8();
+        func.basic_blocks[0].instrs = vec![
+            Instruction::BitCast(instruction::BitCast {
+                operand: local("u", Type::pointer_to(union.clone())),
+                to_type: i8_ptr.clone(),
+                dest: Name::from("bytes"),
+                debugloc: None,
+            }),
+            Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: local("bytes", i8_ptr.clone()),
+                indices: vec![int_const(64, 2)],
+                dest: Name::from("bytep"),
+                in_bounds: true,
+                debugloc: None,
+            }),
+            load(local("bytep", i8_ptr), "byte"),
+        ];
+        func.basic_blocks[0].term = ret(local("byte", Type::i8()));
+        let mut module = module_with_functions("test_mod", vec![func]);
+        module.named_struct_types.insert("union.U".to_owned(), Some(arc.clone()));
+        (union, Project::from_module(module), arc)
+    }
+
+    #[test]
+    fn union_initialized_through_a_member() -> Result<()> {
+        let (union, project, _arc) = union_and_third_byte();
+        assert!(is_union_type(&union));
+        let mut em: ExecutionManager<BtorBackend> = symex_function("third_byte", &project, Config::default());
+        let param = em.param_bvs()[0].clone();
+        // initialize the union through its `word` member
+        let spec = ArgSpec::Union { member: Type::i32(), value: Box::new(ArgSpec::Int(0xdead_beef)) };
+        let addr = alloc_arg(em.mut_state(), &project, &union, &spec)?;
+        param._eq(&addr).assert();
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(retval))) => retval,
+            other => panic!("Expected a return value, got {:?}", other),
+        };
+        let state = em.state();
+        // reading through the `bytes` member sees the same bytes (we are little-endian)
+        assert!(!state.sat_with_extra_constraints(std::iter::once(&retval._ne(&state.bv_from_u64(0xad, 8))))?);
+        for (i, &expected) in [0xef, 0xbe, 0xad, 0xde].iter().enumerate() {
+            let byte = state.read(&addr.add(&state.bv_from_u64(i as u64, 64)), 8)?;
+            assert_eq!(byte.as_u64(), Some(expected));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn union_detection_and_arg_specs() -> Result<()> {
+        // `union V { uint32_t word; uint8_t bytes[8]; }`, lowered with padding
+        let (padded, padded_arc) = named_struct_type("union.V", vec![Type::i32(), Type::ArrayType { element_type: Box::new(Type::i8()), num_elements: 4 }]);
+        // named like a union, but two real fields: not what `clang` emits for a union
+        let (not_union, not_union_arc) = named_struct_type("union.W", vec![Type::i32(), Type::i32()]);
+        let opaque = Type::NamedStructType { name: "union.X".to_owned(), ty: None };
+        let (pair, pair_arc) = named_struct_type("struct.Pair", vec![Type::i32(), Type::i32()]);
+        assert!(is_union_type(&padded));
+        assert!(!is_union_type(&not_union));
+        assert!(is_union_type(&opaque));
+        assert!(!is_union_type(&pair));
+
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.basic_blocks[0].term = ret_void();
+        let mut module = module_with_functions("test_mod", vec![func]);
+        module.named_struct_types.insert("union.V".to_owned(), Some(padded_arc.clone()));
+        module.named_struct_types.insert("union.W".to_owned(), Some(not_union_arc.clone()));
+        module.named_struct_types.insert("struct.Pair".to_owned(), Some(pair_arc.clone()));
+        let project = Project::from_module(module);
+        let mut state = blank_state(&project, "f");
+
+        // a union can't be initialized field-by-field
+        let fields = ArgSpec::Struct(vec![ArgSpec::Int(1), ArgSpec::Unconstrained]);
+        assert!(alloc_arg(&mut state, &project, &padded, &fields).is_err());
+        // but it can through any member that fits, including one larger than the lowered field
+        let whole = ArgSpec::Union { member: Type::i64(), value: Box::new(ArgSpec::Int(0x0102_0304_0506_0708)) };
+        let addr = alloc_arg(&mut state, &project, &padded, &whole)?;
+        assert_eq!(state.read(&addr.add(&state.bv_from_u64(4, 64)), 32)?.as_u64(), Some(0x0102_0304));
+        let too_big = ArgSpec::Union { member: Type::IntegerType { bits: 128 }, value: Box::new(ArgSpec::Unconstrained) };
+        assert!(alloc_arg(&mut state, &project, &padded, &too_big).is_err());
+
+        // a struct which isn't detected as a union is initialized field-by-field,
+        // or can be explicitly treated as a union
+        let addr = alloc_arg(&mut state, &project, &pair, &ArgSpec::Struct(vec![ArgSpec::Int(1), ArgSpec::Int(2)]))?;
+        assert_eq!(state.read(&addr, 64)?.as_u64(), Some(0x2_0000_0001));
+        let addr = alloc_arg(&mut state, &project, &not_union, &whole)?;
+        assert_eq!(state.read(&addr, 64)?.as_u64(), Some(0x0102_0304_0506_0708));
+        Ok(())
+    }
+
+    /// ```c
+    /// int roundtrip(int x) {
+    ///     int *p = malloc(sizeof(int));
+    ///     if (!p) return -1;
+    ///     *p = x;
+    ///     int v = *p;
+    ///     free(p); /* and free(p) again, if `double_free` */
+    ///     return v;
+    /// }
+    /// ```
+    fn roundtrip(double_free: bool) -> Function {
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let p = local("p", i8_ptr.clone());
+        let q = local("q", Type::pointer_to(Type::i32()));
+        let null = Operand::ConstantOperand(Constant::Null(i8_ptr.clone()));
+        let free = call("free", Type::VoidType, vec![p.clone()], None);
+
+        let mut func = blank_function("roundtrip", vec![Name::from("entry"), Name::from("fail"), Name::from("ok")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            call("malloc", i8_ptr.clone(), vec![int_const(64, 4)], Some("p")),
+            icmp(IntPredicate::EQ, p.clone(), null, "failed"),
+        ];
+        func.basic_blocks[0].term = condbr("failed", "fail", "ok");
+        func.basic_blocks[1].term = ret(int_const(32, (-1_i32) as u32 as u64));
+        func.basic_blocks[2].instrs = vec![
+            Instruction::BitCast(instruction::BitCast { operand: p, to_type: q.get_type(), dest: Name::from("q"), debugloc: None }),
+            store(q.clone(), local("x", Type::i32())),
+            load(q, "v"),
+            free.clone(),
+        ];
+        if double_free {
+            func.basic_blocks[2].instrs.push(free);
+        }
+        func.basic_blocks[2].term = ret(local("v", Type::i32()));
+        func
+    }
+
+    #[test]
+    fn malloc_store_load_free() {
+        let project = blank_project("test_mod", roundtrip(false));
+        let mut em: ExecutionManager<BtorBackend> = symex_function("roundtrip", &project, Config::default());
+        let x = em.param_bvs()[0].clone();
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(retval))) => retval,
+            other => panic!("Expected a return value, got {:?}", other),
+        };
+        assert!(!em.state().sat_with_extra_constraints(std::iter::once(&retval._ne(&x))).unwrap());
+        // malloc() is assumed to succeed, so that's the only path
+        assert!(em.next().is_none());
+    }
+
+    #[test]
+    fn malloc_can_fail() {
+        let project = blank_project("test_mod", roundtrip(false));
+        let config = Config { malloc_can_fail: true, ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("roundtrip", &project, config);
+        let mut failures = 0;
+        let mut paths = 0;
+        while let Some(retval) = em.next() {
+            match retval {
+                Ok(ReturnValue::Return(retval)) => {
+                    paths += 1;
+                    if em.state().bvs_must_be_equal(&retval, &em.state().bv_from_i32(-1, 32)).unwrap() {
+                        failures += 1;
+                    }
+                },
+                other => panic!("Expected a return value, got {:?}", other),
+            }
+        }
+        assert_eq!(paths, 2);
+        assert_eq!(failures, 1);
+    }
+
+    fn checking<'p>() -> Config<'p, BtorBackend> {
+        Config { detect_double_frees: true, ..Config::default() }
+    }
+
+    #[test]
+    fn double_free() {
+        let project = blank_project("test_mod", roundtrip(true));
+        let mut em: ExecutionManager<BtorBackend> = symex_function("roundtrip", &project, checking());
+        match em.next() {
+            Some(Err(Error::DoubleFree { first_free, second_free, .. })) => {
+                assert_eq!(first_free, "free at roundtrip, bb ok, instr 3");
+                assert_eq!(second_free, "free at roundtrip, bb ok, instr 4");
+            },
+            other => panic!("Expected a double free, got {:?}", other),
+        }
+
+        // without checking (the default), the second free() is harmless
+        let mut em: ExecutionManager<BtorBackend> = symex_function("roundtrip", &project, Config::default());
+        match em.next() {
+            Some(Ok(ReturnValue::Return(_))) => {},
+            other => panic!("Expected a return value, got {:?}", other),
+        }
+    }
+
+    /// ```c
+    /// uint64_t grow(uint64_t x) {
+    ///     uint64_t *p = malloc(8);
+    ///     *p = x;
+    ///     uint64_t *r = realloc(p, new_size);
+    ///     /* free(p), if `free_old` */
+    ///     return *r;
+    /// }
+    /// ```
+    fn grow(new_size: u64, free_old: bool) -> Function {
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let i64_ptr = Type::pointer_to(Type::i64());
+        let i64_const = |value| int_const(64, value);
+
+        let mut func = blank_function("grow", vec![Name::from("entry")]);
+        func.parameters.push(param("x", Type::i64()));
+        func.return_type = Type::i64();
+        let instrs = &mut func.basic_blocks[0].instrs;
+        instrs.push(call("malloc", i8_ptr.clone(), vec![i64_const(8)], Some("p")));
+        instrs.push(Instruction::BitCast(instruction::BitCast { operand: local("p", i8_ptr.clone()), to_type: i64_ptr.clone(), dest: Name::from("q"), debugloc: None }));
+        instrs.push(store(local("q", i64_ptr.clone()), local("x", Type::i64())));
+        instrs.push(call("realloc", i8_ptr.clone(), vec![local("p", i8_ptr.clone()), i64_const(new_size)], Some("r")));
+        if free_old {
+            instrs.push(call("free", Type::VoidType, vec![local("p", i8_ptr.clone())], None));
+        }
+        instrs.push(Instruction::BitCast(instruction::BitCast { operand: local("r", i8_ptr), to_type: i64_ptr.clone(), dest: Name::from("s"), debugloc: None }));
+        instrs.push(load(local("s", i64_ptr), "v"));
+        func.basic_blocks[0].term = ret(local("v", Type::i64()));
+        func
+    }
+
+    #[test]
+    fn realloc_preserves_contents() {
+        for (new_size, always_moves) in [(16, false), (8, false), (8, true)] {
+            let project = blank_project("test_mod", grow(new_size, false));
+            let config = Config { realloc_always_moves: always_moves, ..Config::default() };
+            let mut em: ExecutionManager<BtorBackend> = symex_function("grow", &project, config);
+            let x = em.param_bvs()[0].clone();
+            let retval = match em.next() {
+                Some(Ok(ReturnValue::Return(retval))) => retval,
+                other => panic!("Expected a return value, got {:?}", other),
+            };
+            assert!(!em.state().sat_with_extra_constraints(std::iter::once(&retval._ne(&x))).unwrap());
+        }
+    }
+
+    #[test]
+    fn realloc_frees_old_allocation() {
+        // growing moves the allocation, so freeing the old pointer afterwards is a double free
+        let project = blank_project("test_mod", grow(16, true));
+        let mut em: ExecutionManager<BtorBackend> = symex_function("grow", &project, checking());
+        match em.next() {
+            Some(Err(Error::DoubleFree { .. })) => {},
+            other => panic!("Expected a double free, got {:?}", other),
+        }
+
+        // shrinking keeps the same pointer, unless `realloc_always_moves`
+        let project = blank_project("test_mod", grow(8, true));
+        let mut em: ExecutionManager<BtorBackend> = symex_function("grow", &project, checking());
+        match em.next() {
+            Some(Ok(ReturnValue::Return(_))) => {},
+            other => panic!("Expected a return value, got {:?}", other),
+        }
+        let config = Config { realloc_always_moves: true, ..checking() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("grow", &project, config);
+        match em.next() {
+            Some(Err(Error::DoubleFree { .. })) => {},
+            other => panic!("Expected a double free, got {:?}", other),
+        }
+    }
+
+    /// `free(NULL); char c; free(&c);`
+    fn free_null_and_stack() -> Function {
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.basic_blocks[0].instrs = vec![
+            call("free", Type::VoidType, vec![Operand::ConstantOperand(Constant::Null(i8_ptr.clone()))], None),
+            Instruction::Alloca(instruction::Alloca {
+                allocated_type: Type::i8(),
+                num_elements: int_const(32, 1),
+                dest: Name::from("c"),
+                alignment: 1,
+                debugloc: None,
+            }),
+            call("free", Type::VoidType, vec![local("c", i8_ptr)], None),
+        ];
+        func.basic_blocks[0].term = ret_void();
+        func
+    }
+
+    #[test]
+    fn invalid_free() {
+        // freeing NULL is fine, but freeing a stack slot isn't
+        let project = blank_project("test_mod", free_null_and_stack());
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, checking());
+        match em.next() {
+            Some(Err(Error::InvalidFree { site, .. })) => assert_eq!(site, "free at f, bb entry, instr 2"),
+            other => panic!("Expected an invalid free, got {:?}", other),
+        }
+
+        // without checking, both are ignored
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, Config::default());
+        match em.next() {
+            Some(Ok(ReturnValue::ReturnVoid)) => {},
+            other => panic!("Expected a void return, got {:?}", other),
+        }
+    }
+
+    /// ```c
+    /// void f(bool c) {
+    ///     char *p = malloc(4);
+    ///     char *q = malloc(4);
+    ///     free(p);
+    ///     free(c ? p : q);
+    /// }
+    /// ```
+    fn maybe_double_free() -> Function {
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.parameters.push(param("c", Type::bool()));
+        func.basic_blocks[0].instrs = vec![
+            call("malloc", i8_ptr.clone(), vec![int_const(64, 4)], Some("p")),
+            call("malloc", i8_ptr.clone(), vec![int_const(64, 4)], Some("q")),
+            call("free", Type::VoidType, vec![local("p", i8_ptr.clone())], None),
+            Instruction::Select(instruction::Select {
+                condition: local("c", Type::bool()),
+                true_value: local("p", i8_ptr.clone()),
+                false_value: local("q", i8_ptr.clone()),
+                dest: Name::from("r"),
+                debugloc: None,
+            }),
+            call("free", Type::VoidType, vec![local("r", i8_ptr)], None),
+        ];
+        func.basic_blocks[0].term = ret_void();
+        func
+    }
+
+    #[test]
+    fn symbolic_double_free() {
+        // the path where `r` is `p` reports the double free, without ending the
+        // path where it's `q`
+        let project = blank_project("test_mod", maybe_double_free());
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, checking());
+        let c = em.param_bvs()[0].clone();
+        match em.next() {
+            Some(Ok(ReturnValue::ReturnVoid)) => assert!(em.state().bvs_must_be_equal(&c, &em.state().bv_from_bool(false)).unwrap()),
+            other => panic!("Expected a void return, got {:?}", other),
+        }
+        match em.next() {
+            Some(Err(Error::DoubleFree { first_free, second_free, .. })) => {
+                assert_eq!(first_free, "free at f, bb entry, instr 2");
+                assert_eq!(second_free, "free at f, bb entry, instr 4");
+            },
+            other => panic!("Expected a double free, got {:?}", other),
+        }
+        assert!(em.next().is_none());
+    }
+
+    /// `void f(bool c) { char *p = malloc(4); if (c) free(p); }`
+    fn maybe_free() -> Function {
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("free"), Name::from("done")]);
+        func.parameters.push(param("c", Type::bool()));
+        func.basic_blocks[0].instrs.push(call("malloc", i8_ptr.clone(), vec![int_const(64, 4)], Some("p")));
+        func.basic_blocks[0].term = condbr("c", "free", "done");
+        func.basic_blocks[1].instrs.push(call("free", Type::VoidType, vec![local("p", i8_ptr)], None));
+        func.basic_blocks[1].term = br("done");
+        func.basic_blocks[2].term = ret_void();
+        func
+    }
+
+    #[test]
+    fn memory_leaks() {
+        let project = blank_project("test_mod", maybe_free());
+        let paths_notes = |config| {
+            let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+            let mut notes = vec![];
+            while let Some(retval) = em.next() {
+                assert_eq!(retval, Ok(ReturnValue::ReturnVoid));
+                notes.push(em.state().path_notes().to_vec());
+            }
+            notes.sort();
+            notes
+        };
+        let config = Config { report_memory_leaks: true, ..Config::default() };
+        assert_eq!(paths_notes(config), vec![vec![], vec!["memory leak: malloc at f, bb entry, instr 0 is never freed".to_owned()]]);
+
+        // leaks aren't reported by default
+        assert_eq!(paths_notes(Config::default()), vec![Vec::<String>::new(), vec![]]);
+    }
+}
@@ -0,0 +1,1053 @@
+//! Cheap analyses of a function, which use the solver but don't enumerate
+//! paths through the function.
+
+use crate::backend::*;
+use crate::config::{Config, Strictness};
+use crate::diagnosis;
+use crate::error::*;
+use crate::layout::{size, size_opaque_aware};
+use crate::project::Project;
+use crate::state::{BBInstrIndex, Location, PathEntry};
+use crate::symex::{symex_function, ExecutionManager};
+use llvm_ir::{Constant, Function, Instruction, IntPredicate, Module, Name, Operand, Terminator};
+use llvm_ir::types::Typed;
+use log::debug;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// An approximation of the condition under which a basic block is reachable;
+/// see [`block_reaching_conditions()`](fn.block_reaching_conditions.html)
+#[derive(Clone, Debug)]
+pub struct ReachingCondition<V> {
+    /// A 1-bit `BV` which is true whenever the block is reachable
+    pub condition: V,
+    /// `true` if `condition` is unsatisfiable, meaning that the block can never
+    /// be reached
+    pub dead: bool,
+}
+
+/// For each basic block of the function with the given name, compute a
+/// condition (in terms of the function's parameters) under which the block
+/// is reachable, and check whether that condition is satisfiable.
+///
+/// Rather than enumerating paths, this computes each block's condition
+/// structurally, as the disjunction over its incoming edges of the
+/// predecessor's condition and the edge's branch condition, visiting blocks
+/// in reverse postorder and ignoring loop back-edges. Branch conditions are
+/// translated to `BV`s the same way full symex translates them, except that
+/// the results of loads, calls, `phi`s, and other instructions which aren't
+/// pure computations on their operands are left unconstrained.
+///
+/// The result over-approximates reachability: a block whose condition is
+/// unsatisfiable (given the `Config.preconditions`, if any) is genuinely
+/// dead, but a block whose condition is satisfiable may still be unreachable.
+/// This is much cheaper than exploring every path with
+/// [`symex_function()`](../fn.symex_function.html).
+pub fn block_reaching_conditions<'p, B: Backend>(
+    funcname: &str,
+    project: &'p Project,
+    config: Config<'p, B>,
+) -> Result<HashMap<Name, ReachingCondition<B::BV>>> {
+    let mut em = symex_function(funcname, project, config);
+    let func: &'p Function = em.state().cur_loc.func;
+    let preconditions_sat = em.assert_preconditions()?;
+
+    let (rpo, back_edges) = reverse_postorder(func);
+    let mut conditions: HashMap<&Name, B::BV> = HashMap::new();
+    // For each block, the conditions on each of its incoming (non-back) edges
+    let mut incoming: HashMap<&Name, Vec<B::BV>> = HashMap::new();
+    for (i, &bb_idx) in rpo.iter().enumerate() {
+        let bb = &func.basic_blocks[bb_idx];
+        let condition = if i == 0 {
+            em.state().bv_from_bool(true)
+        } else {
+            incoming.remove(&bb.name)
+                .into_iter()
+                .flatten()
+                .fold(em.state().bv_from_bool(false), |acc, edge| acc.or(&edge))
+        };
+
+        em.mut_state().cur_loc.move_to_start_of_bb(bb);
+        for inst in &bb.instrs {
+            if !em.symex_pure_instruction(inst)? {
+                if let Some(dest) = inst.try_get_result() {
+                    // leave the result unconstrained
+                    if let Some(bits) = size_opaque_aware(&inst.get_type(), project) {
+                        em.mut_state().new_bv_with_name(dest.clone(), bits as u32)?;
+                    }
+                }
+            }
+        }
+
+        for (succ, edge) in successor_edges(&em, &bb.term) {
+            if back_edges.contains(&(&bb.name, succ)) {
+                continue;
+            }
+            let edge_condition = match edge {
+                Some(edge) => condition.and(&edge),
+                None => condition.clone(),
+            };
+            incoming.entry(succ).or_default().push(edge_condition);
+        }
+        conditions.insert(&bb.name, condition);
+    }
+
+    let mut retval = HashMap::new();
+    for bb in &func.basic_blocks {
+        let condition = match conditions.remove(&bb.name) {
+            Some(condition) => condition,
+            None => em.state().bv_from_bool(false),  // not reachable from the entry block at all
+        };
+        let dead = !preconditions_sat || match condition.as_bool() {
+            Some(b) => !b,
+            None => !em.state().sat_with_extra_constraints(std::iter::once(&condition))?,
+        };
+        debug!("Block {} of {:?} is {}", bb.name, funcname, if dead { "dead" } else { "live" });
+        retval.insert(bb.name.clone(), ReachingCondition { condition, dead });
+    }
+    Ok(retval)
+}
+
+/// Identifies a function in a `Project`
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionId {
+    pub name: String,
+    /// Name of the module containing the function
+    pub module: String,
+}
+
+impl fmt::Display for FunctionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (in {})", self.name, self.module)
+    }
+}
+
+/// Result of [`function_equivalence_report()`](fn.function_equivalence_report.html)
+#[derive(Clone, Debug, Default)]
+pub struct EquivalenceReport {
+    /// Groups of (two or more) functions which were proven to compute the
+    /// same return value for all inputs
+    pub clusters: Vec<Vec<FunctionId>>,
+    /// Functions which weren't analyzed, along with the reason why
+    pub excluded: Vec<(FunctionId, String)>,
+}
+
+impl fmt::Display for EquivalenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, cluster) in self.clusters.iter().enumerate() {
+            let members: Vec<String> = cluster.iter().map(|id| id.to_string()).collect();
+            writeln!(f, "cluster {}: {}", i + 1, members.join(", "))?;
+        }
+        for (id, reason) in &self.excluded {
+            writeln!(f, "excluded {}: {}", id, reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Find groups of functions in the `Project` which are equivalent, i.e.,
+/// which return the same value as each other for all values of their
+/// parameters. This is useful for finding cloned or duplicated code, such as
+/// per-peripheral copies of a driver routine.
+///
+/// See [`function_equivalence_report()`](fn.function_equivalence_report.html)
+/// for details, and for the reasons functions were excluded from the analysis.
+pub fn find_equivalent_functions<'p, B: Backend>(
+    project: &'p Project,
+    config: Config<'p, B>,
+) -> Result<Vec<Vec<FunctionId>>> {
+    Ok(function_equivalence_report(project, config)?.clusters)
+}
+
+/// Group the functions in the `Project` by equivalence of their return
+/// values.
+///
+/// Each function is summarized as a single expression for its return value,
+/// in terms of its parameters, where the `i`th parameter of every function is
+/// the same symbol. The summary is built structurally, like
+/// [`block_reaching_conditions()`](fn.block_reaching_conditions.html) builds
+/// its conditions, merging the values returned on all paths. Functions are
+/// first bucketed by a hash of their signature and the kinds of instructions
+/// they contain; within each bucket, two functions are in the same cluster
+/// if the solver proves that their summaries can't differ.
+///
+/// Functions which touch memory, call other functions, contain loops, or
+/// return `void` are excluded, and reported in `EquivalenceReport.excluded`.
+pub fn function_equivalence_report<'p, B: Backend>(
+    project: &'p Project,
+    config: Config<'p, B>,
+) -> Result<EquivalenceReport> {
+    let mut report = EquivalenceReport::default();
+    let mut candidates = vec![];
+    for (func, module) in project.all_functions() {
+        let id = FunctionId { name: func.name.clone(), module: module.name.clone() };
+        match exclusion_reason(func) {
+            Some(reason) => report.excluded.push((id, reason)),
+            None => candidates.push((func, module, id)),
+        }
+    }
+    let first = match candidates.first() {
+        Some((func, _, _)) => func.name.clone(),
+        None => return Ok(report),
+    };
+    // all the summaries are built in this one `State`, so that they can be
+    // compared with each other
+    let mut em = symex_function(&first, project, config);
+    let mut params = HashMap::new();
+    // map from structural hash to the clusters in that bucket
+    let mut buckets: HashMap<u64, Vec<Cluster<B::BV>>> = HashMap::new();
+    for (func, module, id) in candidates {
+        let summary = match return_summary(&mut em, project, module, func, &mut params)? {
+            Ok(summary) => summary,
+            Err(reason) => {
+                report.excluded.push((id, reason));
+                continue;
+            },
+        };
+        let clusters = buckets.entry(structural_hash(func, project)).or_default();
+        let mut found = false;
+        for cluster in clusters.iter_mut() {
+            if cluster.summary.get_width() == summary.get_width()
+                && !em.state().sat_with_extra_constraints(std::iter::once(&cluster.summary._ne(&summary)))?
+            {
+                debug!("{} is equivalent to {}", id, cluster.members[0]);
+                cluster.members.push(id.clone());
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            clusters.push(Cluster { summary, members: vec![id] });
+        }
+    }
+    report.clusters = buckets.into_values()
+        .flatten()
+        .map(|mut cluster| {
+            cluster.members.sort();
+            cluster.members
+        })
+        .filter(|members| members.len() > 1)
+        .collect();
+    report.clusters.sort();
+    Ok(report)
+}
+
+/// A group of functions with equivalent summaries
+struct Cluster<V> {
+    /// Summary of the first member of the cluster
+    summary: V,
+    members: Vec<FunctionId>,
+}
+
+/// If the function can't be summarized by `return_summary()`, get the reason
+fn exclusion_reason(func: &Function) -> Option<String> {
+    if func.basic_blocks.is_empty() {
+        return Some("has no body".to_owned());
+    }
+    if func.return_type == llvm_ir::Type::VoidType {
+        return Some("returns void".to_owned());
+    }
+    for inst in func.basic_blocks.iter().flat_map(|bb| bb.instrs.iter()) {
+        let reason = match inst {
+            Instruction::Store(_) | Instruction::AtomicRMW(_) | Instruction::CmpXchg(_) | Instruction::Fence(_) => "writes memory",
+            Instruction::Load(_) => "reads memory",
+            Instruction::Alloca(_) => "allocates memory",
+            Instruction::Call(_) => "calls other functions",
+            _ => continue,
+        };
+        return Some(reason.to_owned());
+    }
+    if !reverse_postorder(func).1.is_empty() {
+        return Some("contains a loop".to_owned());
+    }
+    None
+}
+
+/// Hash of the function's signature and the kinds of instructions it
+/// contains (but not their order or operands)
+fn structural_hash(func: &Function, project: &Project) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    size_opaque_aware(&func.return_type, project).hash(&mut hasher);
+    for param in &func.parameters {
+        size_opaque_aware(&param.ty, project).hash(&mut hasher);
+    }
+    let mut kinds: Vec<u64> = func.basic_blocks.iter()
+        .flat_map(|bb| bb.instrs.iter())
+        .map(|inst| {
+            let mut hasher = DefaultHasher::new();
+            std::mem::discriminant(inst).hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    kinds.sort_unstable();
+    kinds.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Compute an expression for the return value of the function, in terms of
+/// its parameters, where the `i`th parameter of width `w` is the `BV` in
+/// `params` for `(i, w)` (which is created if it doesn't exist yet).
+///
+/// The function must not contain loops. Returns `Ok(Err(reason))` if the
+/// function can't be summarized for some other reason.
+fn return_summary<'p, B: Backend>(
+    em: &mut ExecutionManager<'p, B>,
+    project: &'p Project,
+    module: &'p Module,
+    func: &'p Function,
+    params: &mut HashMap<(usize, u32), B::BV>,
+) -> Result<std::result::Result<B::BV, String>> {
+    em.mut_state().cur_loc.module = module;
+    em.mut_state().cur_loc.func = func;
+    for (i, param) in func.parameters.iter().enumerate() {
+        let bits = match size_opaque_aware(&param.ty, project) {
+            Some(bits) => bits as u32,
+            None => return Ok(Err(format!("parameter {} has opaque type", i))),
+        };
+        match params.get(&(i, bits)) {
+            Some(bv) => em.mut_state().assign_bv_to_name(param.name.clone(), bv.clone())?,
+            None => {
+                let bv = em.mut_state().new_bv_with_name(param.name.clone(), bits)?;
+                params.insert((i, bits), bv);
+            },
+        }
+    }
+
+    let (rpo, _) = reverse_postorder(func);
+    // For each block, its predecessors, each with the condition for reaching
+    // the block from that predecessor
+    let mut incoming: HashMap<&Name, Vec<(&Name, B::BV)>> = HashMap::new();
+    let mut summary: Option<B::BV> = None;
+    for (i, &bb_idx) in rpo.iter().enumerate() {
+        let bb = &func.basic_blocks[bb_idx];
+        let edges = incoming.remove(&bb.name).unwrap_or_default();
+        let condition = if i == 0 {
+            em.state().bv_from_bool(true)
+        } else {
+            edges.iter().fold(em.state().bv_from_bool(false), |acc, (_, edge)| acc.or(edge))
+        };
+
+        em.mut_state().cur_loc.move_to_start_of_bb(bb);
+        for inst in &bb.instrs {
+            match inst {
+                Instruction::Phi(phi) => {
+                    let mut value: Option<B::BV> = None;
+                    for (op, pred) in &phi.incoming_values {
+                        let edge = edges.iter()
+                            .filter(|(from, _)| *from == pred)
+                            .fold(None, |acc: Option<B::BV>, (_, edge)| Some(match acc {
+                                Some(acc) => acc.or(edge),
+                                None => edge.clone(),
+                            }));
+                        if let Some(edge) = edge {
+                            let op = em.state().operand_to_bv(op)?;
+                            value = Some(match value {
+                                Some(value) => edge.cond_bv(&op, &value),
+                                None => op,
+                            });
+                        }
+                    }
+                    match value {
+                        Some(value) => em.mut_state().record_bv_result(phi, value)?,
+                        None => return Ok(Err(format!("phi {} has no reachable incoming edges", phi.dest))),
+                    }
+                },
+                _ => match em.symex_pure_instruction(inst) {
+                    Ok(true) => {},
+                    Ok(false) | Err(_) => return Ok(Err("contains an unsupported instruction".to_owned())),
+                },
+            }
+        }
+
+        match &bb.term {
+            Terminator::Ret(ret) => {
+                let value = match &ret.return_operand {
+                    Some(op) => em.state().operand_to_bv(op)?,
+                    None => return Ok(Err("returns void".to_owned())),
+                };
+                summary = Some(match summary {
+                    Some(summary) => condition.cond_bv(&value, &summary),
+                    None => value,
+                });
+            },
+            Terminator::Unreachable(_) => {},
+            Terminator::Br(_) | Terminator::CondBr(_) | Terminator::Switch(_) => {
+                for (succ, edge) in successor_edges(em, &bb.term) {
+                    let edge_condition = match edge {
+                        Some(edge) => condition.and(&edge),
+                        None => condition.clone(),
+                    };
+                    incoming.entry(succ).or_default().push((&bb.name, edge_condition));
+                }
+            },
+            _ => return Ok(Err("contains an unsupported terminator".to_owned())),
+        }
+    }
+    match summary {
+        Some(summary) => Ok(Ok(summary)),
+        None => Ok(Err("never returns".to_owned())),
+    }
+}
+
+/// A condition on the entry function's parameters under which a violation
+/// occurs; see
+/// [`Config.violation_conditions`](../config/struct.Config.html#structfield.violation_conditions)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ViolationCondition {
+    /// The conjuncts of the condition, in the syntax of
+    /// [`Config.preconditions`](../config/struct.Config.html#structfield.preconditions),
+    /// e.g. `["len > 512", "(flags & 8) != 0"]`. Empty if the violation occurs
+    /// for all inputs.
+    ///
+    /// Values which can't be expressed in terms of the parameters appear as
+    /// LLVM names, e.g. `%5`, and a part of the violation condition which
+    /// can't be expressed at all appears as the violation's message in angle
+    /// brackets.
+    pub conjuncts: Vec<String>,
+    /// Whether all the `conjuncts` are expressed in terms of the parameters
+    pub over_inputs: bool,
+    /// Whether the `max_queries` ran out before all the branch conditions had
+    /// been considered, so that some were kept without checking whether they
+    /// matter
+    pub truncated: bool,
+}
+
+impl ViolationCondition {
+    /// The condition as a single precondition, if it is expressed in terms of
+    /// the parameters; this can be used as one of the `Config.preconditions`
+    pub fn to_precondition(&self) -> Option<String> {
+        if !self.over_inputs {
+            None
+        } else if self.conjuncts.is_empty() {
+            Some("1".to_owned())
+        } else {
+            Some(self.conjuncts.iter().map(|c| format!("({})", c)).collect::<Vec<_>>().join(" && "))
+        }
+    }
+}
+
+impl fmt::Display for ViolationCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.conjuncts.is_empty() {
+            write!(f, "any input")
+        } else {
+            write!(f, "{}", self.conjuncts.join(" && "))
+        }
+    }
+}
+
+/// Compute the `ViolationCondition` for a violation reported by the checker
+/// named `checker` at `site`, on the given `path`, according to
+/// `config.violation_conditions`. `witness` is the violation's witness.
+///
+/// Returns `Ok(None)` if the violation isn't in the entry function itself, or
+/// if the checker doesn't report it when asked again.
+pub(crate) fn violation_condition<'p, B: Backend>(
+    project: &'p Project,
+    config: Config<'p, B>,
+    path: &[PathEntry<'p>],
+    site: &Location<'p>,
+    checker: &str,
+    message: &str,
+    witness: &[Option<u64>],
+) -> Result<Option<ViolationCondition>> {
+    let minimization = match config.violation_conditions {
+        Some(minimization) => minimization,
+        None => return Ok(None),
+    };
+    let func: &'p Function = site.func;
+    if path.first().is_none_or(|entry| entry.0.func.name != func.name) {
+        return Ok(None);
+    }
+    let site_idx = match site.instr {
+        BBInstrIndex::Instr(idx) => idx,
+        BBInstrIndex::Terminator => return Ok(None),
+    };
+    let checker = match config.extra_checkers.iter().find(|c| c.name() == checker) {
+        Some(checker) => Rc::clone(checker),
+        None => return Ok(None),
+    };
+    // the blocks of the function which the path passed through, in order
+    let mut blocks: Vec<&Name> = path.iter()
+        .filter(|entry| entry.0.func.name == func.name)
+        .map(|entry| &entry.0.bb.name)
+        .collect();
+    blocks.dedup();
+
+    let mut em = symex_function(&func.name, project, config);
+    if !em.assert_preconditions()? {
+        return Ok(None);
+    }
+    let (rpo, back_edges) = reverse_postorder(func);
+    let mut incoming: HashMap<&Name, Vec<B::BV>> = HashMap::new();
+    // the condition for taking each (non-back) edge, and its description
+    let mut edges: HashMap<(&Name, &Name), Described<B::BV>> = HashMap::new();
+    // the condition for reaching the site, and the violation condition there
+    let mut at_site: Option<(B::BV, B::BV)> = None;
+    'blocks: for (i, &bb_idx) in rpo.iter().enumerate() {
+        let bb = &func.basic_blocks[bb_idx];
+        let condition = if i == 0 {
+            em.state().bv_from_bool(true)
+        } else {
+            incoming.remove(&bb.name)
+                .into_iter()
+                .flatten()
+                .fold(em.state().bv_from_bool(false), |acc, edge| acc.or(&edge))
+        };
+        em.mut_state().cur_loc.move_to_start_of_bb(bb);
+        for (idx, inst) in bb.instrs.iter().enumerate() {
+            if bb.name == site.bb.name && idx == site_idx {
+                em.mut_state().cur_loc.instr = BBInstrIndex::Instr(idx);
+                let violation = match checker.check_instruction(inst, em.state())? {
+                    Some(violation) => Some(violation),
+                    None => match inst {
+                        Instruction::Call(call) => checker.check_call(call, em.state())?,
+                        Instruction::Load(load) => {
+                            let addr = em.state().operand_to_bv(&load.address)?;
+                            checker.check_memory_access(&addr, size(&load.get_type()) as u32, None, em.state())?
+                        },
+                        Instruction::Store(store) => {
+                            let addr = em.state().operand_to_bv(&store.address)?;
+                            let value = em.state().operand_to_bv(&store.value)?;
+                            checker.check_memory_access(&addr, value.get_width(), Some(&value), em.state())?
+                        },
+                        _ => None,
+                    },
+                };
+                match violation {
+                    Some(violation) => at_site = Some((condition, violation.condition)),
+                    None => return Ok(None),
+                }
+                break 'blocks;
+            }
+            if !em.symex_pure_instruction(inst)? {
+                if let Some(dest) = inst.try_get_result() {
+                    // leave the result unconstrained
+                    if let Some(bits) = size_opaque_aware(&inst.get_type(), project) {
+                        em.mut_state().new_bv_with_name(dest.clone(), bits as u32)?;
+                    }
+                }
+            }
+        }
+        for (succ, edge) in successor_edges(&em, &bb.term) {
+            if back_edges.contains(&(&bb.name, succ)) {
+                continue;
+            }
+            let edge_condition = match edge {
+                Some(edge) => {
+                    if let Some(description) = describe_edge(func, &bb.term, succ) {
+                        edges.insert((&bb.name, succ), (edge.clone(), description));
+                    }
+                    condition.and(&edge)
+                },
+                None => condition.clone(),
+            };
+            incoming.entry(succ).or_default().push(edge_condition);
+        }
+    }
+    let (reaching, violated) = match at_site {
+        Some(at_site) => at_site,
+        None => return Ok(None),
+    };
+    let target = reaching.and(&violated);
+
+    // the branch conditions on the path, oldest first
+    let mut conjuncts: Vec<Described<B::BV>> = vec![];
+    for pair in blocks.windows(2) {
+        if let Some(edge) = edges.remove(&(pair[0], pair[1])) {
+            conjuncts.push(edge);
+        }
+    }
+    let start_queries = em.state().solver_query_count();
+    let budget_left = |em: &ExecutionManager<'p, B>| em.state().solver_query_count() - start_queries < minimization.max_queries;
+    // do the `premises` imply the `conclusion`?
+    let implies = |em: &ExecutionManager<'p, B>, premises: &[&B::BV], conclusion: &B::BV| -> Result<bool> {
+        let negated = conclusion.not();
+        Ok(!em.state().sat_with_extra_constraints(premises.iter().copied().chain(std::iter::once(&negated)))?)
+    };
+    let mut truncated = false;
+    let mut kept: Vec<bool> = vec![true; conjuncts.len()];
+    for i in 0 .. conjuncts.len() {
+        if !budget_left(&em) {
+            truncated = true;
+            break;
+        }
+        let drop = match minimization.strictness {
+            Strictness::Sufficient => {
+                let others: Vec<&B::BV> = conjuncts.iter().enumerate()
+                    .filter(|&(j, _)| j != i && kept[j])
+                    .map(|(_, (bv, _))| bv)
+                    .chain(std::iter::once(&violated))
+                    .collect();
+                implies(&em, &others, &target)?
+            },
+            Strictness::Necessary => !implies(&em, &[&target], &conjuncts[i].0)?,
+        };
+        if drop {
+            kept[i] = false;
+        }
+    }
+    let survivors: Vec<&Described<B::BV>> = conjuncts.iter().zip(&kept).filter(|(_, &kept)| kept).map(|(c, _)| c).collect();
+    let mut described: Vec<(String, bool)> = survivors.iter().map(|(_, description)| description.clone()).collect();
+
+    // The violation condition itself is a `BV` from the checker, so we can
+    // only describe it by the parameter values it forces, if any
+    let survivor_bvs: Vec<&B::BV> = survivors.iter().map(|(bv, _)| bv).collect();
+    if !budget_left(&em) {
+        truncated = true;
+        described.push((format!("<{}>", message), false));
+    } else if !implies(&em, &survivor_bvs, &violated)? {
+        let mut forced: Vec<B::BV> = vec![];
+        let mut forced_descriptions: Vec<(String, bool)> = vec![];
+        let mut premises = survivor_bvs.clone();
+        premises.push(&target);
+        for (idx, ((param, bv), value)) in func.parameters.iter().zip(em.param_bvs()).zip(witness).enumerate() {
+            let value = match value {
+                Some(value) => *value,
+                None => continue,
+            };
+            if !budget_left(&em) {
+                truncated = true;
+                break;
+            }
+            let eq = bv._eq(&em.state().bv_from_u64(value, bv.get_width()));
+            if implies(&em, &premises, &eq)? {
+                let name = match &param.name {
+                    Name::Name(name) => name.to_string(),
+                    Name::Number(_) => format!("${}", idx),
+                };
+                forced.push(eq);
+                forced_descriptions.push((format!("{} == {}", name, value), true));
+            }
+        }
+        let mut premises = survivor_bvs.clone();
+        premises.extend(forced.iter());
+        if !forced.is_empty() && implies(&em, &premises, &violated)? {
+            described.extend(forced_descriptions);
+        } else {
+            described.push((format!("<{}>", message), false));
+        }
+    }
+    Ok(Some(ViolationCondition {
+        over_inputs: described.iter().all(|(_, over_inputs)| *over_inputs),
+        conjuncts: described.into_iter().map(|(text, _)| text).collect(),
+        truncated,
+    }))
+}
+
+/// A condition, along with its description and whether that is in terms of
+/// the parameters, as from `describe_value()`
+type Described<V> = (V, (String, bool));
+
+/// Describe the condition for taking the edge from a block with the given
+/// terminator to `succ`, as for `describe_value()`. Returns `None` if the
+/// edge is unconditional.
+fn describe_edge(func: &Function, term: &Terminator, succ: &Name) -> Option<(String, bool)> {
+    match term {
+        Terminator::CondBr(condbr) if condbr.true_dest == condbr.false_dest => None,
+        Terminator::CondBr(condbr) => Some(describe_condition(func, &condbr.condition, &condbr.true_dest == succ, DESCRIPTION_DEPTH)),
+        Terminator::Switch(switch) => {
+            let (operand, over_inputs) = describe_value(func, &switch.operand, DESCRIPTION_DEPTH);
+            let cases: Vec<String> = switch.dests.iter()
+                .filter_map(|(c, _)| match c {
+                    Constant::Int { value, .. } => Some(value.to_string()),
+                    _ => None,
+                })
+                .collect();
+            if switch.default_dest == *succ {
+                let text = cases.iter().map(|c| format!("{} != {}", operand, c)).collect::<Vec<_>>().join(" && ");
+                Some((text, over_inputs))
+            } else {
+                let text = switch.dests.iter()
+                    .filter(|(_, dest)| dest == succ)
+                    .filter_map(|(c, _)| match c {
+                        Constant::Int { value, .. } => Some(format!("{} == {}", operand, value)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" || ");
+                Some((text, over_inputs))
+            }
+        },
+        _ => None,
+    }
+}
+
+/// How many instructions deep `describe_value()` looks when describing a
+/// branch condition
+const DESCRIPTION_DEPTH: usize = 8;
+
+/// Describe the condition that the `i1` operand `cond` has the given `value`,
+/// as for `describe_value()`, looking through the `icmp` which produced it
+/// if possible
+fn describe_condition(func: &Function, cond: &Operand, value: bool, depth: usize) -> (String, bool) {
+    if let Some(Instruction::ICmp(icmp)) = defining_instruction(func, cond) {
+        let predicate = if value { icmp.predicate } else { diagnosis::negate(icmp.predicate) };
+        return describe_icmp(func, predicate, &icmp.operand0, &icmp.operand1, depth);
+    }
+    let (text, over_inputs) = describe_value(func, cond, depth);
+    if value {
+        (text, over_inputs)
+    } else {
+        (format!("!{}", text), over_inputs)
+    }
+}
+
+fn describe_icmp(func: &Function, predicate: IntPredicate, op0: &Operand, op1: &Operand, depth: usize) -> (String, bool) {
+    let signed = matches!(predicate, IntPredicate::SGT | IntPredicate::SGE | IntPredicate::SLT | IntPredicate::SLE);
+    let (a, a_over_inputs) = describe_value(func, op0, depth.saturating_sub(1));
+    let (b, b_over_inputs) = describe_value(func, op1, depth.saturating_sub(1));
+    let (a, b) = if signed {
+        let bits = size(&op0.get_type());
+        (format!("(i{}){}", bits, a), format!("(i{}){}", bits, b))
+    } else {
+        (a, b)
+    };
+    (format!("{} {} {}", a, diagnosis::predicate_symbol(predicate), b), a_over_inputs && b_over_inputs)
+}
+
+/// Describe the value of `op` in the syntax of `Config.preconditions`, in
+/// terms of the function's parameters, by looking through the pure
+/// computations which produce it, up to `depth` instructions deep. Values
+/// which can't be described this way appear as LLVM names, e.g. `%5`, and
+/// make the second element of the result `false`.
+///
+/// The description is always of an unsigned value.
+fn describe_value(func: &Function, op: &Operand, depth: usize) -> (String, bool) {
+    let name = match op {
+        Operand::ConstantOperand(Constant::Int { value, .. }) => return (value.to_string(), true),
+        Operand::ConstantOperand(Constant::Null(_)) => return ("0".to_owned(), true),
+        Operand::ConstantOperand(c) => return (format!("{:?}", c), false),
+        Operand::LocalOperand { name, .. } => name,
+        Operand::MetadataOperand => return ("<metadata>".to_owned(), false),
+    };
+    if let Some(idx) = func.parameters.iter().position(|param| &param.name == name) {
+        return match name {
+            Name::Name(name) => (name.to_string(), true),
+            Name::Number(_) => (format!("${}", idx), true),
+        };
+    }
+    let opaque = || match name {
+        Name::Name(name) => (format!("%{}", name), false),
+        Name::Number(n) => (format!("%{}", n), false),
+    };
+    let inst = match defining_instruction(func, op) {
+        Some(inst) if depth > 0 => inst,
+        _ => return opaque(),
+    };
+    let describe = |op: &Operand| describe_value(func, op, depth - 1);
+    let binary = |op0: &Operand, symbol: &str, op1: &Operand| {
+        let ((a, a_over_inputs), (b, b_over_inputs)) = (describe(op0), describe(op1));
+        (format!("({} {} {})", a, symbol, b), a_over_inputs && b_over_inputs)
+    };
+    let signed_binary = |op0: &Operand, symbol: &str, op1: &Operand| {
+        let bits = size(&op0.get_type());
+        let ((a, a_over_inputs), (b, b_over_inputs)) = (describe(op0), describe(op1));
+        (format!("(u{})((i{}){} {} (i{}){})", bits, bits, a, symbol, bits, b), a_over_inputs && b_over_inputs)
+    };
+    match inst {
+        Instruction::Add(i) => binary(&i.operand0, "+", &i.operand1),
+        Instruction::Sub(i) => binary(&i.operand0, "-", &i.operand1),
+        Instruction::Mul(i) => binary(&i.operand0, "*", &i.operand1),
+        Instruction::UDiv(i) => binary(&i.operand0, "/", &i.operand1),
+        Instruction::URem(i) => binary(&i.operand0, "%", &i.operand1),
+        Instruction::SDiv(i) => signed_binary(&i.operand0, "/", &i.operand1),
+        Instruction::SRem(i) => signed_binary(&i.operand0, "%", &i.operand1),
+        Instruction::And(i) => binary(&i.operand0, "&", &i.operand1),
+        Instruction::Or(i) => binary(&i.operand0, "|", &i.operand1),
+        Instruction::Xor(i) => binary(&i.operand0, "^", &i.operand1),
+        Instruction::Shl(i) => binary(&i.operand0, "<<", &i.operand1),
+        Instruction::LShr(i) => binary(&i.operand0, ">>", &i.operand1),
+        Instruction::AShr(i) => signed_binary(&i.operand0, ">>", &i.operand1),
+        Instruction::ICmp(i) => {
+            let (text, over_inputs) = describe_icmp(func, i.predicate, &i.operand0, &i.operand1, depth);
+            (format!("({})", text), over_inputs)
+        },
+        Instruction::ZExt(i) => {
+            let (text, over_inputs) = describe(&i.operand);
+            (format!("(u{}){}", size(&i.to_type), text), over_inputs)
+        },
+        Instruction::SExt(i) => {
+            let (text, over_inputs) = describe(&i.operand);
+            let to_bits = size(&i.to_type);
+            (format!("(u{})(i{})(i{}){}", to_bits, to_bits, size(&i.operand.get_type()), text), over_inputs)
+        },
+        Instruction::Trunc(i) => {
+            let (text, over_inputs) = describe(&i.operand);
+            (format!("(u{}){}", size(&i.to_type), text), over_inputs)
+        },
+        Instruction::BitCast(i) => describe(&i.operand),
+        Instruction::PtrToInt(i) => describe(&i.operand),
+        Instruction::IntToPtr(i) => describe(&i.operand),
+        _ => opaque(),
+    }
+}
+
+/// Get the instruction in `func` which produces the local operand `op`, if any
+fn defining_instruction<'f>(func: &'f Function, op: &Operand) -> Option<&'f Instruction> {
+    match op {
+        Operand::LocalOperand { name, .. } => func.basic_blocks.iter()
+            .flat_map(|bb| bb.instrs.iter())
+            .find(|inst| inst.try_get_result() == Some(name)),
+        _ => None,
+    }
+}
+
+/// Get the successors of a block with the given terminator, each along with
+/// the condition (if any) for taking that edge.
+///
+/// If the terminator's condition can't be translated to a `BV`, its edges are
+/// treated as unconditional.
+fn successor_edges<'t, 'p, B: Backend>(em: &ExecutionManager<'p, B>, term: &'t Terminator) -> Vec<(&'t Name, Option<B::BV>)> {
+    match term {
+        Terminator::CondBr(condbr) => match em.state().operand_to_bv(&condbr.condition) {
+            Ok(cond) => vec![(&condbr.true_dest, Some(cond.clone())), (&condbr.false_dest, Some(cond.not()))],
+            Err(_) => vec![(&condbr.true_dest, None), (&condbr.false_dest, None)],
+        },
+        Terminator::Switch(switch) => match em.state().operand_to_bv(&switch.operand) {
+            Ok(val) => {
+                let mut edges = vec![];
+                let mut default = em.state().bv_from_bool(true);
+                for (c, dest) in &switch.dests {
+                    match em.state().const_to_bv(c) {
+                        Ok(c) => {
+                            let eq = val._eq(&c);
+                            default = default.and(&eq.not());
+                            edges.push((dest, Some(eq)));
+                        },
+                        Err(_) => edges.push((dest, None)),
+                    }
+                }
+                edges.push((&switch.default_dest, Some(default)));
+                edges
+            },
+            Err(_) => successors(term).into_iter().map(|dest| (dest, None)).collect(),
+        },
+        _ => successors(term).into_iter().map(|dest| (dest, None)).collect(),
+    }
+}
+
+/// Get the successors of a block with the given terminator
+fn successors(term: &Terminator) -> Vec<&Name> {
+    match term {
+        Terminator::Br(br) => vec![&br.dest],
+        Terminator::CondBr(condbr) => vec![&condbr.true_dest, &condbr.false_dest],
+        Terminator::Switch(switch) => switch.dests.iter().map(|(_, dest)| dest).chain(std::iter::once(&switch.default_dest)).collect(),
+        Terminator::IndirectBr(ibr) => ibr.possible_dests.iter().collect(),
+        Terminator::Invoke(invoke) => vec![&invoke.return_label, &invoke.exception_label],
+        Terminator::CleanupRet(cr) => cr.unwind_dest.iter().collect(),
+        Terminator::CatchRet(cr) => vec![&cr.successor],
+        Terminator::CatchSwitch(cs) => cs.catch_handlers.iter().chain(cs.default_unwind_dest.iter()).collect(),
+        Terminator::CallBr(cb) => vec![&cb.return_label],
+        Terminator::Ret(_) | Terminator::Resume(_) | Terminator::Unreachable(_) => vec![],
+    }
+}
+
+/// Get the back-edges of the function's control-flow graph, as for
+/// `reverse_postorder()`. For a reducible control-flow graph (which includes
+/// anything structured loops compile to), these are the edges from inside
+/// each loop back to its header.
+pub(crate) fn back_edges(func: &Function) -> HashSet<(&Name, &Name)> {
+    reverse_postorder(func).1
+}
+
+/// Get the indices of the blocks reachable from the entry block, in reverse
+/// postorder, along with the set of back-edges (edges to a block which is
+/// still on the DFS stack), as `(from, to)` pairs of block names
+fn reverse_postorder(func: &Function) -> (Vec<usize>, HashSet<(&Name, &Name)>) {
+    let indices: HashMap<&Name, usize> = func.basic_blocks.iter().enumerate().map(|(i, bb)| (&bb.name, i)).collect();
+    let mut postorder = vec![];
+    let mut back_edges = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut on_stack = HashSet::new();
+    // explicit DFS stack of (block index, index of next successor to visit)
+    let mut stack = vec![(0, 0)];
+    visited.insert(0);
+    on_stack.insert(0);
+    while let Some((bb_idx, succ_idx)) = stack.pop() {
+        let bb = &func.basic_blocks[bb_idx];
+        let succs = successors(&bb.term);
+        match succs.get(succ_idx) {
+            None => {
+                on_stack.remove(&bb_idx);
+                postorder.push(bb_idx);
+            },
+            Some(succ) => {
+                stack.push((bb_idx, succ_idx + 1));
+                let succ_bb_idx = indices[succ];
+                if on_stack.contains(&succ_bb_idx) {
+                    back_edges.insert((&bb.name, &func.basic_blocks[succ_bb_idx].name));
+                } else if visited.insert(succ_bb_idx) {
+                    on_stack.insert(succ_bb_idx);
+                    stack.push((succ_bb_idx, 0));
+                }
+            },
+        }
+    }
+    postorder.reverse();
+    (postorder, back_edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::project::Project;
+    use crate::test_utils::*;
+    use llvm_ir::*;
+
+    /// `x > 10` then `x < 5`: the block under both conditions is dead
+    fn contradictory_branches() -> Function {
+        let x = local("x", Type::i32());
+        let i32_const = |value| int_const(32, value);
+        let ret_i32 = |value| ret(i32_const(value));
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("big"), Name::from("dead"), Name::from("live"), Name::from("small")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::UGT, x.clone(), i32_const(10), "c1"));
+        func.basic_blocks[0].term = condbr("c1", "big", "small");
+        func.basic_blocks[1].instrs.push(icmp(IntPredicate::ULT, x, i32_const(5), "c2"));
+        func.basic_blocks[1].term = condbr("c2", "dead", "live");
+        func.basic_blocks[2].term = ret_i32(1);
+        func.basic_blocks[3].term = ret_i32(2);
+        func.basic_blocks[4].term = ret_i32(3);
+        func
+    }
+
+    #[test]
+    fn contradictory_branches_dead_block() -> Result<()> {
+        let project = blank_project("test_mod", contradictory_branches());
+        let conditions = block_reaching_conditions::<BtorBackend>("f", &project, Config::default())?;
+        assert_eq!(conditions.len(), 5);
+        let dead: HashSet<&str> = conditions.iter()
+            .filter(|(_, rc)| rc.dead)
+            .map(|(name, _)| match name {
+                Name::Name(name) => name.as_str(),
+                Name::Number(_) => panic!("unexpected numbered block"),
+            })
+            .collect();
+        assert_eq!(dead, vec!["dead"].into_iter().collect());
+        Ok(())
+    }
+
+    #[test]
+    fn preconditions_kill_blocks() -> Result<()> {
+        let project = blank_project("test_mod", contradictory_branches());
+        let config = Config {
+            preconditions: vec!["x == 3".to_owned()],
+            ..Config::default()
+        };
+        let conditions = block_reaching_conditions::<BtorBackend>("f", &project, config)?;
+        for (name, rc) in conditions {
+            let expect_dead = name != Name::from("entry") && name != Name::from("small");
+            assert_eq!(rc.dead, expect_dead, "wrong classification for block {}", name);
+        }
+        Ok(())
+    }
+
+    /// `return x + y * factor`, with the parameters named `a` and `b`
+    fn scaled_sum(name: &str, a: &str, b: &str, factor: u64) -> Function {
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let mut func = blank_function(name, vec![Name::from("entry")]);
+        func.parameters.push(param(a, Type::i32()));
+        func.parameters.push(param(b, Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            Instruction::Mul(instruction::Mul {
+                operand0: local_i32(b),
+                operand1: int_const(32, factor),
+                dest: Name::from("scaled"),
+                debugloc: None,
+            }),
+            Instruction::Add(instruction::Add {
+                operand0: local_i32(a),
+                operand1: local_i32("scaled"),
+                dest: Name::from("sum"),
+                debugloc: None,
+            }),
+        ];
+        func.basic_blocks[0].term = ret(local_i32("sum"));
+        func
+    }
+
+    #[test]
+    fn equivalent_functions() -> Result<()> {
+        let mut stores = scaled_sum("stores", "x", "y", 2);
+        stores.basic_blocks[0].instrs.push(store(Operand::ConstantOperand(Constant::Null(Type::pointer_to(Type::i32()))), local("sum", Type::i32())));
+        let project = project_with_functions("test_mod", vec![
+            scaled_sum("timer0_scale", "x", "y", 2),
+            scaled_sum("timer1_scale", "p", "q", 2),
+            scaled_sum("timer2_scale", "x", "y", 3),
+            stores,
+        ]);
+        let report = function_equivalence_report::<BtorBackend>(&project, Config::default())?;
+        let id = |name: &str| FunctionId { name: name.to_owned(), module: "test_mod".to_owned() };
+        assert_eq!(report.clusters, vec![vec![id("timer0_scale"), id("timer1_scale")]]);
+        assert_eq!(report.excluded, vec![(id("stores"), "writes memory".to_owned())]);
+        assert_eq!(find_equivalent_functions::<BtorBackend>(&project, Config::default())?, report.clusters);
+        Ok(())
+    }
+
+    /// `if (mode == 3) log(); if (len > 512) if (flags & 8) return 100 / (len - 600);`
+    fn two_conjunct_bug() -> Function {
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let i32_const = |value| int_const(32, value);
+        let mut func = blank_function("f", ["entry", "log", "check", "big", "flagged", "out"].iter().map(|&bb| Name::from(bb)).collect());
+        for name in &["mode", "len", "flags"] {
+            func.parameters.push(param(*name, Type::i32()));
+        }
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::EQ, local_i32("mode"), i32_const(3), "verbose"));
+        func.basic_blocks[0].term = condbr("verbose", "log", "check");
+        func.basic_blocks[1].term = br("check");
+        func.basic_blocks[2].instrs.push(icmp(IntPredicate::UGT, local_i32("len"), i32_const(512), "long"));
+        func.basic_blocks[2].term = condbr("long", "big", "out");
+        func.basic_blocks[3].instrs = vec![
+            Instruction::And(instruction::And { operand0: local_i32("flags"), operand1: i32_const(8), dest: Name::from("bit3"), debugloc: None }),
+            icmp(IntPredicate::NE, local_i32("bit3"), i32_const(0), "set"),
+        ];
+        func.basic_blocks[3].term = condbr("set", "flagged", "out");
+        func.basic_blocks[4].instrs = vec![
+            Instruction::Sub(instruction::Sub { operand0: local_i32("len"), operand1: i32_const(600), dest: Name::from("d"), debugloc: None }),
+            Instruction::UDiv(instruction::UDiv { operand0: i32_const(100), operand1: local_i32("d"), dest: Name::from("q"), debugloc: None }),
+        ];
+        func.basic_blocks[4].term = ret(local_i32("q"));
+        func.basic_blocks[5].term = ret(i32_const(0));
+        func
+    }
+
+    fn violation_condition_with(project: &Project, strictness: Strictness) -> ViolationCondition {
+        let config = Config {
+            extra_checkers: vec![Rc::new(crate::checkers::DivisionByZero)],
+            violation_conditions: Some(crate::config::ConditionMinimization { strictness, max_queries: 64 }),
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", project, config);
+        while em.next().is_some() {}
+        assert_eq!(em.violations().len(), 1);
+        em.violations()[0].condition.clone().expect("Expected a violation condition")
+    }
+
+    #[test]
+    fn sufficient_violation_condition() {
+        let project = blank_project("test_mod", two_conjunct_bug());
+        let condition = violation_condition_with(&project, Strictness::Sufficient);
+        // `mode == 3` doesn't matter, and `len > 512` is implied by `len == 600`
+        assert_eq!(condition.conjuncts, vec!["(flags & 8) != 0", "len == 600"]);
+        assert!(condition.over_inputs);
+        assert!(!condition.truncated);
+        assert_eq!(condition.to_precondition().as_deref(), Some("((flags & 8) != 0) && (len == 600)"));
+    }
+
+    #[test]
+    fn necessary_violation_condition() {
+        let project = blank_project("test_mod", two_conjunct_bug());
+        let condition = violation_condition_with(&project, Strictness::Necessary);
+        assert_eq!(condition.conjuncts, vec!["len > 512", "(flags & 8) != 0", "len == 600"]);
+        assert!(condition.over_inputs);
+    }
+}
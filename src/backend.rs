@@ -112,6 +112,7 @@ pub trait BV: Clone + PartialEq + Eq + fmt::Debug {
     fn is_const(&self) -> bool;
     fn has_same_width(&self, other: &Self) -> bool;
     fn assert(&self) -> Result<()>;
+    fn assume(&self);
     fn is_failed_assumption(&self) -> bool;
     fn _eq(&self, other: &Self) -> Self;
     fn _ne(&self, other: &Self) -> Self;
@@ -182,6 +183,21 @@ pub trait BV: Clone + PartialEq + Eq + fmt::Debug {
         }
     }
 
+    /// Zero-extend or truncate a `BV` to the specified number of bits, as LLVM's
+    /// `ptrtoint` and `inttoptr` do.
+    /// The input `BV` can be any size; if it is already the desired size, this
+    /// function is a no-op.
+    ///
+    /// A default implementation is provided in terms of the other trait methods.
+    fn zero_extend_or_truncate_to_bits(&self, bits: u32) -> Self {
+        let cur_bits = self.get_width();
+        if cur_bits > bits {
+            self.slice(bits - 1, 0)
+        } else {
+            self.zero_extend_to_bits(bits)
+        }
+    }
+
     /// Sign-extend a `BV` to the specified number of bits.
     /// The input `BV` can be already the desired size (in which case this function is a no-op)
     /// or smaller (in which case this function will extend),
@@ -423,6 +439,9 @@ impl BV for boolector::BV<Rc<Btor>> {
         self.assert();
         Ok(())
     }
+    fn assume(&self) {
+        self.assume()
+    }
     fn is_failed_assumption(&self) -> bool {
         self.is_failed_assumption()
     }
@@ -648,3 +667,80 @@ impl Backend for SimpleMemoryBackend {
     type BV = boolector::BV<Rc<Btor>>;
     type Memory = crate::simple_memory::Memory;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::{BBInstrIndex, Location, State};
+    use crate::test_utils::*;
+    use llvm_ir::Name;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+    /// A `Memory` which counts the writes made to it, and otherwise defers
+    /// to `simple_memory::Memory`
+    #[derive(PartialEq, Eq, Clone)]
+    struct CountingMemory(crate::simple_memory::Memory);
+
+    impl Memory for CountingMemory {
+        type SolverRef = Rc<Btor>;
+        type Index = boolector::BV<Rc<Btor>>;
+        type Value = boolector::BV<Rc<Btor>>;
+
+        fn new_uninitialized(btor: Rc<Btor>, null_detection: bool, name: Option<&str>) -> Self {
+            CountingMemory(crate::simple_memory::Memory::new_uninitialized(btor, null_detection, name))
+        }
+        fn new_zero_initialized(btor: Rc<Btor>, null_detection: bool, name: Option<&str>) -> Self {
+            CountingMemory(crate::simple_memory::Memory::new_zero_initialized(btor, null_detection, name))
+        }
+        fn read(&self, index: &Self::Index, bits: u32) -> Result<Self::Value> {
+            self.0.read(index, bits)
+        }
+        fn write(&mut self, index: &Self::Index, value: Self::Value) -> Result<()> {
+            WRITES.fetch_add(1, Ordering::SeqCst);
+            self.0.write(index, value)
+        }
+        fn get_solver(&self) -> Rc<Btor> {
+            self.0.get_solver()
+        }
+        fn change_solver(&mut self, new_btor: Rc<Btor>) {
+            self.0.change_solver(new_btor)
+        }
+    }
+
+    #[derive(Clone)]
+    struct CountingBackend;
+
+    impl Backend for CountingBackend {
+        type SolverRef = Rc<Btor>;
+        type BV = boolector::BV<Rc<Btor>>;
+        type Memory = CountingMemory;
+    }
+
+    /// `State` should work with any `Backend`, not just the ones provided here
+    #[test]
+    fn state_with_custom_backend() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let (func, module) = project.get_func_by_name("test_func").unwrap();
+        let start_loc = Location {
+            module,
+            func,
+            bb: &func.basic_blocks[0],
+            instr: BBInstrIndex::Instr(0),
+            source_loc: None,
+        };
+        let mut state: State<CountingBackend> = State::new(&project, start_loc, Config::default());
+
+        let writes_before = WRITES.load(Ordering::SeqCst);
+        let addr = state.allocate(64_u64);
+        let val = state.bv_from_u64(0x1234, 64);
+        state.write(&addr, val.clone())?;
+        assert_eq!(WRITES.load(Ordering::SeqCst), writes_before + 1);
+        let read = state.read(&addr, 64)?;
+        assert!(state.bvs_must_be_equal(&read, &val)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+//! Representative workloads for benchmarking exploration, drawn from the
+//! modules in `tests/bcfiles`. These back the `benches/` suite, and can also be
+//! used in tests bounding the work done on known CFGs.
+//!
+//! Only available with the `bench-support` feature.
+
+use crate::backend::BtorBackend;
+use crate::config::Config;
+use crate::project::Project;
+use crate::stats::Stats;
+use crate::symex::{symex_function, ExecutionManager};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A benchmark scenario: a function in one of the modules in `tests/bcfiles`
+pub struct Scenario {
+    pub name: &'static str,
+    /// Path of the module, relative to the crate root
+    pub bcfile: &'static str,
+    pub funcname: &'static str,
+}
+
+/// All of the scenarios, in increasing order of (rough) cost
+pub const SCENARIOS: &[Scenario] = &[
+    // straight-line arithmetic
+    Scenario { name: "straight_line", bcfile: "tests/bcfiles/basic.bc", funcname: "binops" },
+    // branchy diamond CFG
+    Scenario { name: "diamond", bcfile: "tests/bcfiles/basic.bc", funcname: "has_switch" },
+    // loop, unrolled up to `Config.loop_bound` times
+    Scenario { name: "bounded_loop", bcfile: "tests/bcfiles/loop.bc", funcname: "while_loop" },
+    // memory-heavy: loads and stores through a symbolic pointer. Use
+    // `NullPointerChecking::None`, or the path ends in an error.
+    Scenario { name: "memory", bcfile: "tests/bcfiles/memory.bc", funcname: "pointer_arith" },
+    // interprocedural chain of calls
+    Scenario { name: "interprocedural", bcfile: "tests/bcfiles/call.bc", funcname: "nested_caller" },
+];
+
+/// Get the scenario with the given name, if there is one
+pub fn scenario(name: &str) -> Option<&'static Scenario> {
+    SCENARIOS.iter().find(|scenario| scenario.name == name)
+}
+
+thread_local! {
+    /// Parsed modules, so that repeated runs of a scenario (as in a benchmark)
+    /// measure exploration and not parsing. These live for the rest of the
+    /// program, as the `Stats` of each run borrow from them.
+    static PROJECTS: RefCell<HashMap<&'static str, &'static Project>> = RefCell::new(HashMap::new());
+}
+
+/// Get the `Project` for the given scenario, parsing it the first time
+pub fn project(scenario: &Scenario) -> &'static Project {
+    PROJECTS.with(|projects| {
+        *projects.borrow_mut().entry(scenario.bcfile).or_insert_with(|| {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(scenario.bcfile);
+            let project = Project::from_bc_path(&path)
+                .unwrap_or_else(|e| panic!("Failed to parse module {:?}: {}", path, e));
+            Box::leak(Box::new(project))
+        })
+    })
+}
+
+/// Explore every path of the scenario with the given name, with the given
+/// `Config`, and return the `Stats` afterwards. Paths which end in an error
+/// (e.g., exceeding the loop bound) are skipped.
+///
+/// Panics if there is no scenario with that name.
+pub fn run_scenario(name: &str, config: Config<'static, BtorBackend>) -> Stats<'static> {
+    let scenario = scenario(name).unwrap_or_else(|| panic!("No scenario named {:?}", name));
+    let mut em: ExecutionManager<BtorBackend> = symex_function(scenario.funcname, project(scenario), config);
+    for _ in em.by_ref() {}
+    em.stats().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NullPointerChecking;
+
+    /// Generous upper bounds on the solver queries for each scenario. A large
+    /// regression in the number of queries shows up here even when it isn't
+    /// obvious from the timings.
+    #[test]
+    fn solver_query_bounds() {
+        for &(name, max_queries) in &[
+            ("straight_line", 10),
+            ("diamond", 100),
+            ("bounded_loop", 200),
+            ("memory", 200),
+            ("interprocedural", 20),
+        ] {
+            // `pointer_arith` dereferences pointers derived from a parameter, which may be NULL
+            let config = Config { null_pointer_checking: NullPointerChecking::None, ..Config::default() };
+            let stats = run_scenario(name, config);
+            assert!(stats.paths() >= 1, "scenario {}", name);
+            assert!(stats.solver_queries() <= max_queries, "scenario {}: {} solver queries", name, stats.solver_queries());
+            assert!(stats.values_computed() > 0, "scenario {}", name);
+        }
+        assert_eq!(run_scenario("straight_line", Config::default()).paths(), 1);
+        // one path per case of the `switch`, including the default
+        assert_eq!(run_scenario("diamond", Config::default()).paths(), 7);
+    }
+
+    #[test]
+    fn scenario_names() {
+        assert!(SCENARIOS.iter().all(|s| scenario(s.name).is_some()));
+        assert!(scenario("no_such_scenario").is_none());
+    }
+}
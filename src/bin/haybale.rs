@@ -0,0 +1,235 @@
+//! Command-line interface to a few of `haybale`'s non-symbolic utilities.
+//!
+//! Usage:
+//!
+//! ```text
+//! haybale interface <bitcode file> [--type NAME]
+//! haybale find-zero <bitcode file> <function> [--loop-bound N]
+//! haybale stack <bitcode file> --entry <function>
+//! haybale equiv <bitcode file>
+//! haybale repro <bitcode file>...
+//! haybale verify-repro <bundle file> <bitcode file>...
+//! ```
+
+use haybale::{Config, ExecutionManager, Project};
+use haybale::backend::BtorBackend;
+use haybale::repro::ReproBundle;
+use std::process::exit;
+
+const USAGE: &str = "usage: haybale interface <bitcode file> [--type NAME]
+       haybale find-zero <bitcode file> <function> [--loop-bound N]
+       haybale stack <bitcode file> --entry <function>
+       haybale equiv <bitcode file>
+       haybale repro <bitcode file>...
+       haybale verify-repro <bundle file> <bitcode file>...";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("interface") => interface(&args[1 ..]),
+        Some("find-zero") => find_zero(&args[1 ..]),
+        Some("stack") => stack(&args[1 ..]),
+        Some("equiv") => equiv(&args[1 ..]),
+        Some("repro") => repro(&args[1 ..]),
+        Some("verify-repro") => verify_repro(&args[1 ..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        },
+    }
+}
+
+/// `haybale interface <bitcode file> [--type NAME]`: print the `InterfaceReport`
+/// for the given bitcode file, or just the layout of the named struct type
+fn interface(args: &[String]) {
+    let (path, typename) = match args {
+        [path] => (path, None),
+        [path, flag, name] if flag == "--type" => (path, Some(name)),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        },
+    };
+    let proj = Project::from_bc_path(path).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", path, e);
+        exit(1);
+    });
+    let report = proj.interface_report();
+    match typename {
+        None => print!("{}", report),
+        Some(name) => match report.get_struct(name) {
+            Some(layout) => print!("{}", layout),
+            None => {
+                eprintln!("No named struct type {:?} found in {}", name, path);
+                exit(1);
+            },
+        },
+    }
+}
+
+/// `haybale find-zero <bitcode file> <function> [--loop-bound N]`: print
+/// inputs for which the function returns zero, along with a summary of the
+/// search
+fn find_zero(args: &[String]) {
+    let (path, funcname, loop_bound) = match args {
+        [path, funcname] => (path, funcname, None),
+        [path, funcname, flag, bound] if flag == "--loop-bound" => match bound.parse::<usize>() {
+            Ok(bound) => (path, funcname, Some(bound)),
+            Err(_) => {
+                eprintln!("Invalid loop bound {:?}", bound);
+                exit(2);
+            },
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        },
+    };
+    let proj = Project::from_bc_path(path).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", path, e);
+        exit(1);
+    });
+    match proj.get_func_by_name(funcname) {
+        None => {
+            eprintln!("No function named {:?} found in {}", funcname, path);
+            exit(1);
+        },
+        Some((func, _)) if haybale::interface::is_coroutine(func) => {
+            println!("{} is a coroutine, which is not supported; skipping it", funcname);
+            exit(3);
+        },
+        Some(_) => {},
+    }
+    let mut config = Config::default();
+    if let Some(loop_bound) = loop_bound {
+        config.loop_bound = loop_bound;
+    }
+    let outcome = haybale::find_zero_of_func(funcname, &proj, config).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        exit(1);
+    });
+    match &outcome.answer {
+        Some(inputs) => {
+            let inputs: Vec<String> = inputs.iter().map(|i| i.to_string()).collect();
+            println!("{} returns 0 for inputs: {}", funcname, inputs.join(", "));
+        },
+        None if outcome.summary.is_complete() => println!("{} can never return 0", funcname),
+        None => println!("found no inputs for which {} returns 0, but the search was incomplete", funcname),
+    }
+    print!("{}", outcome.summary);
+}
+
+/// `haybale stack <bitcode file> --entry <function>`: print the largest stack
+/// usage on any path through the function, along with inputs and a path which
+/// reach it
+fn stack(args: &[String]) {
+    let (path, funcname) = match args {
+        [path, flag, funcname] if flag == "--entry" => (path, funcname),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        },
+    };
+    let proj = Project::from_bc_path(path).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", path, e);
+        exit(1);
+    });
+    if proj.get_func_by_name(funcname).is_none() {
+        eprintln!("No function named {:?} found in {}", funcname, path);
+        exit(1);
+    }
+    let mut em: ExecutionManager<BtorBackend> = haybale::symex_function(funcname, &proj, Config::default());
+    // paths which end in errors still count, up to the point of the error
+    while em.next().is_some() {}
+    match em.max_stack_usage() {
+        None => println!("{} has no feasible paths", funcname),
+        Some(usage) => {
+            println!("max stack usage of {}: {} bytes{}", funcname, usage.bytes,
+                if usage.lower_bound_only { " (lower bound: some paths were cut off)" } else { "" });
+            let inputs: Vec<String> = usage.inputs.iter().map(|i| match i {
+                Some(i) => i.to_string(),
+                None => "?".to_owned(),
+            }).collect();
+            println!("reached with inputs: {}", inputs.join(", "));
+            println!("along the path:");
+            for entry in &usage.path {
+                println!("  {:?}", entry);
+            }
+        },
+    }
+}
+
+/// `haybale equiv <bitcode file>`: print groups of functions which are
+/// equivalent to each other, and the functions which couldn't be analyzed
+fn equiv(args: &[String]) {
+    let path = match args {
+        [path] => path,
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        },
+    };
+    let proj = Project::from_bc_path(path).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", path, e);
+        exit(1);
+    });
+    let report = haybale::analyze::function_equivalence_report::<BtorBackend>(&proj, Config::default()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        exit(1);
+    });
+    if report.clusters.is_empty() {
+        println!("no equivalent functions found");
+    }
+    print!("{}", report);
+}
+
+/// Parse the given bitcode files into one `Project`, or exit with an error
+fn project_from_paths(paths: &[String]) -> Project {
+    if paths.is_empty() {
+        eprintln!("{}", USAGE);
+        exit(2);
+    }
+    Project::from_bc_paths(paths).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", paths.join(", "), e);
+        exit(1);
+    })
+}
+
+/// `haybale repro <bitcode file>...`: print the `ReproBundle` describing an
+/// analysis of the given bitcode files with the default `Config`, which the
+/// other commands use
+fn repro(args: &[String]) {
+    let proj = project_from_paths(args);
+    print!("{}", ReproBundle::capture(&Config::<BtorBackend>::default(), &proj));
+}
+
+/// `haybale verify-repro <bundle file> <bitcode file>...`: check the given
+/// bitcode files and the default `Config` against a bundle saved from
+/// `haybale repro`, printing each difference. Exits with status 1 if there
+/// are any.
+fn verify_repro(args: &[String]) {
+    let (bundle_path, paths) = match args {
+        [bundle_path, paths @ ..] => (bundle_path, paths),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        },
+    };
+    let bundle = std::fs::read_to_string(bundle_path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| ReproBundle::parse(&contents))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to read {}: {}", bundle_path, e);
+            exit(1);
+        });
+    let proj = project_from_paths(paths);
+    let mismatches = bundle.verify(&Config::<BtorBackend>::default(), &proj);
+    if mismatches.is_empty() {
+        println!("matches {}", bundle_path);
+    } else {
+        for mismatch in &mismatches {
+            println!("{}", mismatch);
+        }
+        exit(1);
+    }
+}
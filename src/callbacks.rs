@@ -44,6 +44,11 @@ impl<'p, B: Backend> Callbacks<'p, B> {
     pub fn add_terminator_callback(&mut self, cb: impl Fn(&'p llvm_ir::Terminator, &State<B>) -> Result<()> + 'p) {
         self.terminator_callbacks.push(Rc::new(cb))
     }
+
+    /// Are there no callbacks at all?
+    pub(crate) fn is_empty(&self) -> bool {
+        self.instruction_callbacks.is_empty() && self.terminator_callbacks.is_empty()
+    }
 }
 
 impl<'p, B: Backend> Default for Callbacks<'p, B> {
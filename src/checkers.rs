@@ -0,0 +1,1391 @@
+//! Custom checkers, which report violations of user-defined rules (e.g.,
+//! "a DMA length must be a multiple of 4") during symbolic execution
+
+use crate::analyze::ViolationCondition;
+use crate::backend::*;
+use crate::config::{Approximation, Config};
+use crate::error::Result;
+use crate::handles::HandleState;
+use crate::project::Project;
+use crate::search_outcome::{ExplorationSummary, LimitHit, SearchOutcome, SearchTimer};
+use crate::state::{BBInstrIndex, State};
+use crate::symex::symex_function;
+use either::Either;
+use llvm_ir::{instruction, Constant, Function, Instruction, Name, Operand, Terminator};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A checker for some rule, which `haybale` consults as it executes each
+/// path. Register checkers with
+/// [`Config.extra_checkers`](../config/struct.Config.html#structfield.extra_checkers).
+///
+/// Each method may return a `CustomViolation` whose `condition` is true
+/// exactly when the rule is violated. If the condition is satisfiable on the
+/// current path, `haybale` records a [`Violation`](struct.Violation.html),
+/// including inputs which trigger it; see
+/// [`ExecutionManager::violations()`](../struct.ExecutionManager.html#method.violations).
+/// Violations don't end the path.
+///
+/// All methods have default implementations which never report a violation,
+/// so checkers only need to implement the ones they're interested in.
+pub trait ViolationChecker<'p, B: Backend> {
+    /// A short name identifying this checker in reports
+    fn name(&self) -> &str;
+
+    /// An optional identifier for this version of the checker, such as
+    /// `"v2"`, recorded in a [`ReproBundle`](../repro/struct.ReproBundle.html)
+    /// so that changes to the checker can be noticed
+    fn id(&self) -> Option<&str> {
+        None
+    }
+
+    /// Called before processing each LLVM non-terminator instruction
+    fn check_instruction(&self, _inst: &'p Instruction, _state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        Ok(None)
+    }
+
+    /// Called before each load or store, with the address and the size of the
+    /// access in bits, and for stores, the value being stored
+    fn check_memory_access(&self, _addr: &B::BV, _bits: u32, _stored: Option<&B::BV>, _state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        Ok(None)
+    }
+
+    /// Called before each call. Use `state.operand_to_bv()` to get the values
+    /// of the arguments.
+    fn check_call(&self, _call: &'p instruction::Call, _state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        Ok(None)
+    }
+}
+
+/// A possible violation reported by a `ViolationChecker`
+pub struct CustomViolation<V> {
+    /// A 1-bit `BV` which is true exactly when the rule is violated
+    pub condition: V,
+    /// Description of the violation
+    pub message: String,
+}
+
+/// A violation found by a `ViolationChecker`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Violation {
+    /// The `name()` of the checker which reported the violation
+    pub checker: String,
+    /// The `message` the checker reported
+    pub message: String,
+    /// Where the violation occurs: the source location if available, or else
+    /// the LLVM location
+    pub location: String,
+    /// Values of the function's parameters which trigger the violation.
+    /// Entries are `None` for parameters wider than 64 bits.
+    pub witness: Vec<Option<u64>>,
+    /// For each entry of `witness`, the name of its enumerator (or
+    /// enumerators, for bitflags), if the parameter is one of the
+    /// [`Config.enum_params`](../config/struct.Config.html#structfield.enum_params)
+    pub witness_enumerators: Vec<Option<String>>,
+    /// Whether the violation was found with an approximate model of memory
+    /// (see [`Config.approximation`](../config/struct.Config.html#structfield.approximation)),
+    /// in which case it may not be possible in reality
+    pub approximate: bool,
+    /// A simplified condition on the function's parameters under which the
+    /// violation occurs, if
+    /// [`Config.violation_conditions`](../config/struct.Config.html#structfield.violation_conditions)
+    /// is set and the violation is in the entry function itself
+    pub condition: Option<ViolationCondition>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let witness: Vec<String> = self.witness.iter().zip(self.witness_enumerators.iter().chain(std::iter::repeat(&None))).map(|(w, name)| match (w, name) {
+            (Some(w), Some(name)) => format!("{} ({})", w, name),
+            (Some(w), None) => w.to_string(),
+            (None, _) => "?".to_owned(),
+        }).collect();
+        write!(f, "[{}] {} at {}, with inputs {}", self.checker, self.message, self.location, witness.join(", "))?;
+        if let Some(condition) = &self.condition {
+            write!(f, ", under the condition {}", condition)?;
+        }
+        if self.approximate {
+            write!(f, " (approximate)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A `Violation`, along with the level of iterative deepening at which it was
+/// first found; see
+/// [`Config.iterative_deepening`](../config/struct.Config.html#structfield.iterative_deepening)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Finding {
+    pub violation: Violation,
+    /// Index of the first level (in the `DeepeningSchedule`) which found the
+    /// violation. Always `0` if `Config.iterative_deepening` is `None`.
+    pub level: usize,
+}
+
+/// Explore all paths through the function with the given name, and collect
+/// the violations found by the `Config.extra_checkers`.
+///
+/// If `Config.iterative_deepening` is set, this explores the function once
+/// for each level of the `DeepeningSchedule`, keeping the (deduplicated)
+/// findings of all levels, and stopping early if a level completes without
+/// hitting any bound. The `summary` of the returned `SearchOutcome` describes
+/// the last level which was run, so it reports whether that level was
+/// bound-limited; its `elapsed` time covers all levels.
+///
+/// Paths which end in an error other than exceeding a bound or a solver
+/// failure are reported as `Err`, as in
+/// [`find_zero_of_func()`](../fn.find_zero_of_func.html).
+pub fn find_violations<'p, B: Backend>(
+    funcname: &str,
+    project: &'p Project,
+    config: Config<'p, B>,
+) -> std::result::Result<SearchOutcome<Vec<Finding>>, String> {
+    let timer = SearchTimer::start();
+    let schedule = config.iterative_deepening.clone();
+    let num_levels = schedule.as_ref().map_or(1, |schedule| std::cmp::max(schedule.num_levels(), 1));
+    let mut findings: Vec<Finding> = vec![];
+    let mut summary = ExplorationSummary::default();
+    for level in 0 .. num_levels {
+        let mut level_config = config.clone();
+        let mut deadline = None;
+        if let Some(schedule) = &schedule {
+            let (loop_bound, max_callstack_depth) = schedule.bounds_for_level(level, config.loop_bound, config.max_callstack_depth);
+            level_config.loop_bound = loop_bound;
+            level_config.max_callstack_depth = max_callstack_depth;
+            if let Some(budget) = schedule.time_budget {
+                // this level's share of the remaining time is proportional to its loop bound
+                let remaining = budget.checked_sub(timer.elapsed()).unwrap_or_default();
+                let remaining_weight: usize = (level .. num_levels)
+                    .map(|l| std::cmp::max(schedule.bounds_for_level(l, config.loop_bound, config.max_callstack_depth).0, 1))
+                    .sum();
+                let share = remaining.as_secs_f64() * std::cmp::max(loop_bound, 1) as f64 / remaining_weight as f64;
+                deadline = Some(Instant::now() + Duration::from_secs_f64(share));
+            }
+        }
+        let max_callstack_depth = level_config.max_callstack_depth;
+        let mut em = symex_function(funcname, project, level_config);
+        summary = ExplorationSummary::default();
+        while let Some(result) = em.next() {
+            match result {
+                Ok(_) => summary.paths_completed += 1,
+                Err(e) => if !summary.record_error(&e) {
+                    return Err(em.state().full_error_message_with_context(e));
+                },
+            }
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                summary.hit(LimitHit::TimeBudget);
+                break;
+            }
+        }
+        summary.solver_stats = em.state().solver_stats();
+        if em.calls_skipped_at_max_callstack_depth() > 0 {
+            if let Some(depth) = max_callstack_depth {
+                summary.hit(LimitHit::CallstackDepth(depth));
+            }
+        }
+        for violation in em.violations() {
+            // the same violation may be found with a different witness at a later level
+            let is_new = !findings.iter().any(|finding| {
+                (&finding.violation.checker, &finding.violation.location, &finding.violation.message)
+                    == (&violation.checker, &violation.location, &violation.message)
+            });
+            if is_new {
+                findings.push(Finding { violation: violation.clone(), level });
+            }
+        }
+        if summary.is_complete() {
+            break;
+        }
+    }
+    Ok(timer.finish(findings, summary))
+}
+
+/// Result of [`scan_functions()`](fn.scan_functions.html) for one function
+/// which had approximate findings
+#[derive(Clone, Debug)]
+pub struct ScanResult {
+    pub funcname: String,
+    /// What the approximate pass found, or why it failed
+    pub approximate: std::result::Result<Vec<Finding>, String>,
+    /// What the precise pass found, as from
+    /// [`find_violations()`](fn.find_violations.html)
+    pub precise: std::result::Result<SearchOutcome<Vec<Finding>>, String>,
+}
+
+/// Find violations in many functions in two phases: first explore each
+/// function with
+/// [`Approximation::HavocMemory`](../config/enum.Approximation.html), which
+/// is cheap but imprecise; then explore only the functions which had any
+/// findings (or whose approximate pass failed) again with
+/// `Approximation::Precise`, using
+/// [`find_violations()`](fn.find_violations.html).
+///
+/// Returns a `ScanResult` for each function which needed the precise pass.
+/// Functions with no approximate findings are left out. The `approximation`
+/// set in the `config` is ignored.
+pub fn scan_functions<'p, B: Backend>(
+    funcnames: &[&str],
+    project: &'p Project,
+    config: Config<'p, B>,
+) -> Vec<ScanResult> {
+    let mut results = vec![];
+    for funcname in funcnames {
+        let approximate = find_violations(funcname, project, Config { approximation: Approximation::HavocMemory, ..config.clone() })
+            .map(|outcome| outcome.answer);
+        if approximate.as_ref().is_ok_and(|findings| findings.is_empty()) {
+            continue;
+        }
+        let precise = find_violations(funcname, project, Config { approximation: Approximation::Precise, ..config.clone() });
+        results.push(ScanResult { funcname: funcname.to_string(), approximate, precise });
+    }
+    results
+}
+
+/// A built-in checker which reports integer division (or remainder) by zero
+pub struct DivisionByZero;
+
+impl<'p, B: Backend> ViolationChecker<'p, B> for DivisionByZero {
+    fn name(&self) -> &str {
+        "division-by-zero"
+    }
+
+    fn check_instruction(&self, inst: &'p Instruction, state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        let divisor: &Operand = match inst {
+            Instruction::UDiv(div) => &div.operand1,
+            Instruction::SDiv(div) => &div.operand1,
+            Instruction::URem(rem) => &rem.operand1,
+            Instruction::SRem(rem) => &rem.operand1,
+            _ => return Ok(None),
+        };
+        let divisor = state.operand_to_bv(divisor)?;
+        Ok(Some(CustomViolation {
+            condition: divisor._eq(&state.zero(divisor.get_width())),
+            message: "division by zero".to_owned(),
+        }))
+    }
+}
+
+/// A built-in checker which reports signed division (or remainder) of the
+/// most negative value by `-1`, e.g. `INT_MIN / -1`. The quotient isn't
+/// representable, so this is immediate undefined behavior in LLVM (and
+/// typically traps at runtime), like division by zero. Use this alongside
+/// [`DivisionByZero`](struct.DivisionByZero.html).
+pub struct SignedDivisionOverflow;
+
+impl<'p, B: Backend> ViolationChecker<'p, B> for SignedDivisionOverflow {
+    fn name(&self) -> &str {
+        "signed-division-overflow"
+    }
+
+    fn check_instruction(&self, inst: &'p Instruction, state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        let (dividend, divisor): (&Operand, &Operand) = match inst {
+            Instruction::SDiv(div) => (&div.operand0, &div.operand1),
+            Instruction::SRem(rem) => (&rem.operand0, &rem.operand1),
+            _ => return Ok(None),
+        };
+        let dividend = state.operand_to_bv(dividend)?;
+        let divisor = state.operand_to_bv(divisor)?;
+        let width = dividend.get_width();
+        let int_min = state.one(1).concat(&state.zero(width - 1));
+        Ok(Some(CustomViolation {
+            condition: dividend._eq(&int_min).and(&divisor._eq(&state.ones(width))),
+            message: "signed division overflow".to_owned(),
+        }))
+    }
+}
+
+/// A built-in checker which reports calls to the `llvm.*.with.overflow`
+/// intrinsics whose overflow flag is never checked, when the operation can
+/// overflow on the current path. This catches checked arithmetic where the
+/// branch on the flag was lost (e.g. in a refactor), so that the possibly
+/// wrapped result is used anyway.
+///
+/// The flag counts as checked if it is extracted and then (possibly after
+/// being combined with other values) used as the condition of a branch,
+/// `switch`, or `select`, or passed to a call, stored, or returned. Which
+/// flags are checked is determined once per function, by a scan of its
+/// instructions.
+#[derive(Default)]
+pub struct IgnoredOverflowFlag {
+    /// For each function we've scanned (keyed by its address), the `dest`s of
+    /// the `with.overflow` calls whose flags are never checked
+    unchecked: RefCell<HashMap<usize, HashSet<Name>>>,
+}
+
+impl IgnoredOverflowFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// The `with.overflow` intrinsics, by the operation they perform
+const WITH_OVERFLOW_OPS: [&str; 6] = ["uadd", "sadd", "usub", "ssub", "umul", "smul"];
+
+/// If the given call is to a `with.overflow` intrinsic, get the name of the
+/// intrinsic and the operation it performs (one of `WITH_OVERFLOW_OPS`)
+fn with_overflow_op(call: &instruction::Call) -> Option<(&str, &'static str)> {
+    match &call.function {
+        Either::Right(Operand::ConstantOperand(Constant::GlobalReference { name: Name::Name(name), .. })) => WITH_OVERFLOW_OPS.iter()
+            .find(|op| name.starts_with(&format!("llvm.{}.with.overflow", op)))
+            .map(|op| (name.as_str(), *op)),
+        _ => None,
+    }
+}
+
+/// Is the overflow flag in the result of the `with.overflow` call with the
+/// given `dest` ever checked in `func`? See notes on `IgnoredOverflowFlag`.
+fn overflow_flag_is_checked(func: &Function, dest: &Name) -> bool {
+    // names which hold the flag, or the whole result of the call
+    let mut tainted: HashSet<&Name> = std::iter::once(dest).collect();
+    loop {
+        let mut newly_tainted = vec![];
+        for bb in &func.basic_blocks {
+            let uses = |op: &Operand| matches!(op, Operand::LocalOperand { name, .. } if tainted.contains(name));
+            for inst in &bb.instrs {
+                let (flows, checked) = match inst {
+                    Instruction::ExtractValue(ev) => (uses(&ev.aggregate) && ev.indices == [1], false),
+                    Instruction::And(i) => (uses(&i.operand0) || uses(&i.operand1), false),
+                    Instruction::Or(i) => (uses(&i.operand0) || uses(&i.operand1), false),
+                    Instruction::Xor(i) => (uses(&i.operand0) || uses(&i.operand1), false),
+                    Instruction::ICmp(i) => (uses(&i.operand0) || uses(&i.operand1), false),
+                    Instruction::ZExt(i) => (uses(&i.operand), false),
+                    Instruction::SExt(i) => (uses(&i.operand), false),
+                    Instruction::Trunc(i) => (uses(&i.operand), false),
+                    Instruction::Phi(phi) => (phi.incoming_values.iter().any(|(op, _)| uses(op)), false),
+                    Instruction::Select(select) => (uses(&select.true_value) || uses(&select.false_value), uses(&select.condition)),
+                    Instruction::Call(call) => {
+                        let uses_arg = call.arguments.iter().any(|(op, _)| uses(op));
+                        let is_expect = matches!(&call.function, Either::Right(Operand::ConstantOperand(Constant::GlobalReference { name: Name::Name(name), .. })) if name.starts_with("llvm.expect"));
+                        if is_expect { (uses_arg, false) } else { (false, uses_arg) }
+                    },
+                    Instruction::Store(store) => (false, uses(&store.value)),
+                    Instruction::InsertValue(iv) => (false, uses(&iv.element)),
+                    _ => (false, false),
+                };
+                if checked {
+                    return true;
+                }
+                if flows {
+                    if let Some(dest) = inst.try_get_result() {
+                        if !tainted.contains(dest) {
+                            newly_tainted.push(dest);
+                        }
+                    }
+                }
+            }
+            let checked = match &bb.term {
+                Terminator::CondBr(condbr) => uses(&condbr.condition),
+                Terminator::Switch(switch) => uses(&switch.operand),
+                Terminator::Ret(ret) => ret.return_operand.as_ref().is_some_and(uses),
+                _ => false,
+            };
+            if checked {
+                return true;
+            }
+        }
+        if newly_tainted.is_empty() {
+            return false;
+        }
+        tainted.extend(newly_tainted);
+    }
+}
+
+impl<'p, B: Backend> ViolationChecker<'p, B> for IgnoredOverflowFlag {
+    fn name(&self) -> &str {
+        "ignored-overflow-flag"
+    }
+
+    fn check_call(&self, call: &'p instruction::Call, state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        let (funcname, op) = match with_overflow_op(call) {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        let dest = match &call.dest {
+            Some(dest) => dest,
+            None => return Ok(None),  // the result isn't used at all, so neither is the wrapped value
+        };
+        let func = state.cur_loc.func;
+        let unchecked = self.unchecked.borrow_mut()
+            .entry(func as *const Function as usize)
+            .or_insert_with(|| {
+                func.basic_blocks.iter()
+                    .flat_map(|bb| bb.instrs.iter())
+                    .filter_map(|inst| match inst {
+                        Instruction::Call(call) if with_overflow_op(call).is_some() => call.dest.as_ref(),
+                        _ => None,
+                    })
+                    .filter(|dest| !overflow_flag_is_checked(func, dest))
+                    .cloned()
+                    .collect()
+            })
+            .contains(dest);
+        if !unchecked || call.arguments.len() != 2 {
+            return Ok(None);
+        }
+        let a = state.operand_to_bv(&call.arguments[0].0)?;
+        let b = state.operand_to_bv(&call.arguments[1].0)?;
+        let overflow = match op {
+            "uadd" => a.uaddo(&b),
+            "sadd" => a.saddo(&b),
+            "usub" => a.usubo(&b),
+            "ssub" => a.ssubo(&b),
+            "umul" => a.umulo(&b),
+            _ => a.smulo(&b),
+        };
+        Ok(Some(CustomViolation {
+            condition: overflow,
+            message: format!("the overflow flag of {} is never checked, but the operation may overflow", funcname),
+        }))
+    }
+}
+
+/// A built-in checker which reports uses of handles (file descriptors and
+/// `FILE*`s) after they are closed, including closing them twice. It relies
+/// on the handles tracked by hooks such as the ones installed by
+/// [`FunctionHooks::add_handle_hooks()`](../function_hooks/struct.FunctionHooks.html#method.add_handle_hooks).
+///
+/// Calls to the standard C I/O functions are checked, by the argument which
+/// holds the handle.
+pub struct HandleLifecycle;
+
+/// Functions which take a handle, and the index of the argument holding it
+const HANDLE_ARGUMENTS: [(&str, usize); 20] = [
+    ("fclose", 0), ("close", 0),
+    ("read", 0), ("write", 0), ("lseek", 0), ("fsync", 0), ("fstat", 1),
+    ("fread", 3), ("fwrite", 3), ("fgets", 2), ("fputs", 1), ("fgetc", 0), ("fputc", 1),
+    ("fprintf", 0), ("fscanf", 0), ("fflush", 0), ("fseek", 0), ("ftell", 0), ("feof", 0), ("ferror", 0),
+];
+
+impl<'p, B: Backend> ViolationChecker<'p, B> for HandleLifecycle {
+    fn name(&self) -> &str {
+        "handle-lifecycle"
+    }
+
+    fn check_call(&self, call: &'p instruction::Call, state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        let funcname = match &call.function {
+            Either::Right(Operand::ConstantOperand(Constant::GlobalReference { name: Name::Name(name), .. })) => name.as_str(),
+            _ => return Ok(None),
+        };
+        let arg = match HANDLE_ARGUMENTS.iter().find(|(name, _)| *name == funcname) {
+            Some((_, idx)) => match call.arguments.get(*idx) {
+                Some((arg, _)) => state.operand_to_bv(arg)?,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        for handle in state.handles().iter().filter(|handle| handle.state == HandleState::Closed) {
+            let is_handle = arg._eq(&state.bv_from_u64(handle.value, arg.get_width()));
+            if state.sat_with_extra_constraints(std::iter::once(&is_handle))? {
+                let what = if funcname == "fclose" || funcname == "close" { "double close" } else { "use after close" };
+                return Ok(Some(CustomViolation {
+                    condition: is_handle,
+                    message: format!("{} of {} in call to {}", what, handle, funcname),
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// A built-in checker which reports loads and stores which may not lie
+/// entirely within the allocation their pointer is based on.
+///
+/// When the address comes from a `getelementptr` (possibly through several of
+/// them, or casts) of a pointer with a single value, the access must lie
+/// within the allocation that pointer points into, so overrunning into an
+/// adjacent allocation is reported too. Otherwise, the access must lie within
+/// some live allocation (see
+/// [`State::live_allocations()`](../struct.State.html#method.live_allocations))
+/// or one of the
+/// [`Config.mapped_address_ranges`](../config/struct.Config.html#structfield.mapped_address_ranges),
+/// and an overrun which lands entirely inside another allocation isn't
+/// reported. Accesses to symbolic addresses cost a solver query each, which
+/// is why this isn't enabled by default.
+pub struct OutOfBounds;
+
+/// Get the value of the pointer which the address of the current load or
+/// store is derived from by `getelementptr`s and casts, if there are any
+fn address_base<'p, B: Backend>(state: &State<'p, B>) -> Result<Option<B::BV>> {
+    let func = state.cur_loc.func;
+    let mut addr = match state.cur_loc.instr {
+        BBInstrIndex::Instr(idx) => match state.cur_loc.bb.instrs.get(idx) {
+            Some(Instruction::Load(load)) => &load.address,
+            Some(Instruction::Store(store)) => &store.address,
+            _ => return Ok(None),
+        },
+        BBInstrIndex::Terminator => return Ok(None),
+    };
+    let mut derived = false;
+    loop {
+        match addr {
+            Operand::LocalOperand { name, .. } => match func.basic_blocks.iter().flat_map(|bb| bb.instrs.iter()).find(|inst| inst.try_get_result() == Some(name)) {
+                Some(Instruction::GetElementPtr(gep)) => addr = &gep.address,
+                Some(Instruction::BitCast(bitcast)) => addr = &bitcast.operand,
+                _ => break,
+            },
+            Operand::ConstantOperand(Constant::GetElementPtr(gep)) => return state.const_to_bv(&gep.address).map(Some),
+            _ => break,
+        }
+        derived = true;
+    }
+    if derived {
+        state.operand_to_bv(addr).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+impl<'p, B: Backend> ViolationChecker<'p, B> for OutOfBounds {
+    fn name(&self) -> &str {
+        "out-of-bounds"
+    }
+
+    fn check_memory_access(&self, addr: &B::BV, bits: u32, _stored: Option<&B::BV>, state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        let bytes = u64::from(bits).div_ceil(8);
+        if bytes == 0 {
+            return Ok(None);
+        }
+        // the allocation the pointer is based on, if we know it
+        let base = address_base(state)?
+            .and_then(|base| base.as_u64())
+            .and_then(|base| state.find_allocation(base));
+        // valid ranges of addresses, as (start, length in bytes)
+        let ranges: Vec<(u64, u64)> = match base {
+            Some((start, bits)) => vec![(start, bits.div_ceil(8))],
+            None => state.live_allocations().into_iter()
+                .map(|(start, bits)| (start, bits.div_ceil(8)))
+                .chain(state.config.mapped_address_ranges.iter().map(|range| (range.start, range.end.saturating_sub(range.start))))
+                .collect(),
+        };
+        let ranges = ranges.into_iter().filter(|(_, len)| *len >= bytes);
+        let condition = match addr.as_u64() {
+            Some(a) => {
+                if ranges.into_iter().any(|(start, len)| a >= start && a - start <= len - bytes) {
+                    return Ok(None);
+                }
+                state.bv_from_bool(true)
+            },
+            None => {
+                let width = addr.get_width();
+                let in_bounds = ranges.map(|(start, len)| {
+                    addr.ugte(&state.bv_from_u64(start, width))
+                        .and(&addr.ulte(&state.bv_from_u64(start + (len - bytes), width)))
+                }).reduce(|a, b| a.or(&b));
+                match in_bounds {
+                    Some(in_bounds) => in_bounds.not(),
+                    None => state.bv_from_bool(true),
+                }
+            },
+        };
+        // name the allocation the pointer is based on, or points into (or one past)
+        let target = match base {
+            Some((start, _)) => state.describe_pointer(start),
+            None => addr.as_u64().and_then(|a| state.describe_pointer(a)),
+        };
+        let message = match target {
+            Some(target) => format!("out-of-bounds access of {} bytes via a pointer into {}", bytes, target.allocation),
+            None => format!("out-of-bounds access of {} bytes", bytes),
+        };
+        Ok(Some(CustomViolation { condition, message }))
+    }
+}
+
+/// A built-in checker which reports loads and stores which may access an
+/// allocation after it was `free()`d, or a stack slot after it went out of
+/// scope (at an `llvm.lifetime.end`, or when its function returned); see
+/// [`State::freed_allocations()`](../struct.State.html#method.freed_allocations).
+///
+/// Each freed allocation which a symbolic address may point into costs a
+/// solver query, so this isn't enabled by default.
+pub struct UseAfterFree;
+
+impl<'p, B: Backend> ViolationChecker<'p, B> for UseAfterFree {
+    fn name(&self) -> &str {
+        "use-after-free"
+    }
+
+    fn check_memory_access(&self, addr: &B::BV, bits: u32, _stored: Option<&B::BV>, state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        let bytes = std::cmp::max(u64::from(bits).div_ceil(8), 1);
+        let width = addr.get_width();
+        for (start, alloc_bits, site) in state.freed_allocations() {
+            let end = start + alloc_bits.div_ceil(8);
+            // the access overlaps the allocation: `addr < end && addr + bytes > start`
+            let overlaps = match addr.as_u64() {
+                Some(a) if a < end && a + bytes > start => state.bv_from_bool(true),
+                Some(_) => continue,
+                None => {
+                    let overlaps = addr.ult(&state.bv_from_u64(end, width))
+                        .and(&addr.ugte(&state.bv_from_u64(start.saturating_sub(bytes - 1), width)));
+                    if !state.sat_with_extra_constraints(std::iter::once(&overlaps))? {
+                        continue;
+                    }
+                    overlaps
+                },
+            };
+            let allocation = match state.describe_pointer(start) {
+                Some(target) => target.allocation,
+                None => format!("the allocation at 0x{:x}", start),
+            };
+            return Ok(Some(CustomViolation {
+                condition: overlaps,
+                message: format!("use after free of {}, freed by {}", allocation, site),
+            }));
+        }
+        Ok(None)
+    }
+}
+
+/// A built-in checker which reports loads which may read memory that was
+/// never written on the current path; see
+/// [`Config.detect_uninitialized_reads`](../config/struct.Config.html#structfield.detect_uninitialized_reads),
+/// without which this never reports anything.
+pub struct UninitializedRead;
+
+impl<'p, B: Backend> ViolationChecker<'p, B> for UninitializedRead {
+    fn name(&self) -> &str {
+        "uninitialized-read"
+    }
+
+    fn check_memory_access(&self, addr: &B::BV, bits: u32, stored: Option<&B::BV>, state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        if stored.is_some() {
+            return Ok(None);
+        }
+        match state.uninitialized_read_condition(addr, bits)? {
+            Some(condition) => {
+                if !state.sat_with_extra_constraints(std::iter::once(&condition))? {
+                    return Ok(None);
+                }
+                let message = state.describe_uninitialized_read(addr, bits, &condition)?;
+                Ok(Some(CustomViolation { condition, message }))
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// A built-in checker for code compiled with stack protectors or UBSan. It
+/// reports reachable calls to `__stack_chk_fail()`, which stack-protected
+/// functions call when their stack guard has been overwritten, and to the
+/// UBSan handlers (`__ubsan_handle_*`), which instrumented code calls exactly
+/// when one of its checks fails.
+///
+/// `haybale` gives an undefined `__stack_chk_guard` an arbitrary value, so
+/// that `__stack_chk_fail()` is reachable only if the guard's copy on the
+/// stack can actually be overwritten. The sanitizer runtime functions
+/// themselves are handled by built-in hooks: UBSan handlers and ASan runtime
+/// calls do nothing (except `__asan_memcpy()` and friends, which do their
+/// job), while `__stack_chk_fail()` and the `_abort` UBSan handlers end the
+/// path.
+pub struct SanitizerChecks;
+
+impl<'p, B: Backend> ViolationChecker<'p, B> for SanitizerChecks {
+    fn name(&self) -> &str {
+        "sanitizer"
+    }
+
+    fn check_call(&self, call: &'p instruction::Call, state: &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>> {
+        let funcname = match &call.function {
+            Either::Right(Operand::ConstantOperand(Constant::GlobalReference { name: Name::Name(name), .. })) => name.as_str(),
+            _ => return Ok(None),
+        };
+        let message = if funcname == "__stack_chk_fail" {
+            "stack buffer overflow: the stack protector's guard was overwritten".to_owned()
+        } else if let Some(handler) = funcname.strip_prefix("__ubsan_handle_") {
+            match ubsan_violation(handler) {
+                Some(kind) => format!("{} (detected by {})", kind, funcname),
+                None => return Ok(None),
+            }
+        } else {
+            return Ok(None);
+        };
+        // the call is reached only when the check failed, so the path condition is the violation condition
+        Ok(Some(CustomViolation { condition: state.bv_from_bool(true), message }))
+    }
+}
+
+/// Describe the undefined behavior detected by the UBSan handler with the
+/// given name (without the `__ubsan_handle_` prefix), or `None` if calling it
+/// doesn't mean a check failed
+fn ubsan_violation(handler: &str) -> Option<&'static str> {
+    let check = handler.trim_end_matches("_abort").trim_end_matches("_minimal");
+    let check = check.trim_end_matches("_v1");
+    Some(match check {
+        "add_overflow" | "sub_overflow" | "mul_overflow" | "negate_overflow" => "signed integer overflow",
+        "divrem_overflow" => "division by zero or overflowing division",
+        "shift_out_of_bounds" => "shift amount out of bounds",
+        "out_of_bounds" => "array index out of bounds",
+        "type_mismatch" => "null or misaligned pointer access",
+        "pointer_overflow" => "pointer arithmetic overflow",
+        "load_invalid_value" => "load of an invalid value",
+        "builtin_unreachable" => "unreachable code reached",
+        "missing_return" => "end of a value-returning function reached without a return",
+        "nonnull_arg" | "nonnull_return" | "nullability_arg" | "nullability_return" => "null where non-null is required",
+        "float_cast_overflow" => "floating-point to integer conversion overflow",
+        "implicit_conversion" => "implicit conversion changed the value",
+        "invalid_builtin" => "invalid argument to a builtin",
+        "vla_bound_not_positive" => "variable-length array bound is not positive",
+        "alignment_assumption" => "alignment assumption violated",
+        "function_type_mismatch" | "cfi_check_fail" | "cfi_bad_type" => "call through a pointer of the wrong type",
+        // the slow path of a check, not a failure
+        "dynamic_type_cache_miss" => return None,
+        _ => "undefined behavior",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::config::Config;
+    use crate::project::Project;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::*;
+    use std::rc::Rc;
+
+    /// Flags stores of the constant `0xDEAD`
+    struct StoresDead;
+
+    impl<'p> ViolationChecker<'p, BtorBackend> for StoresDead {
+        fn name(&self) -> &str {
+            "stores-dead"
+        }
+
+        fn check_memory_access(&self, _addr: &<BtorBackend as Backend>::BV, _bits: u32, stored: Option<&<BtorBackend as Backend>::BV>, state: &State<'p, BtorBackend>) -> Result<Option<CustomViolation<<BtorBackend as Backend>::BV>>> {
+            Ok(stored.map(|stored| CustomViolation {
+                condition: stored._eq(&state.bv_from_u64(0xDEAD, stored.get_width())),
+                message: "stored 0xDEAD".to_owned(),
+            }))
+        }
+    }
+
+    /// `int *p = alloca; *p = x; return 100 / x;`
+    fn store_and_divide() -> Function {
+        let x = local("x", Type::i32());
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            alloca(Type::i32(), int_const(32, 1), "p"),
+            store(local("p", Type::pointer_to(Type::i32())), x.clone()),
+            Instruction::UDiv(instruction::UDiv {
+                operand0: int_const(32, 100),
+                operand1: x,
+                dest: Name::from("q"),
+                debugloc: None,
+            }),
+        ];
+        func.basic_blocks[0].term = ret(local("q", Type::i32()));
+        func
+    }
+
+    #[test]
+    fn custom_checker() {
+        let project = blank_project("test_mod", store_and_divide());
+        let config = Config {
+            extra_checkers: vec![Rc::new(StoresDead)],
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        assert_eq!(em.violations(), &[Violation {
+            checker: "stores-dead".to_owned(),
+            message: "stored 0xDEAD".to_owned(),
+            location: "f, bb entry, instr 1".to_owned(),
+            witness: vec![Some(0xDEAD)],
+            witness_enumerators: vec![None],
+            approximate: false,
+            condition: None,
+        }]);
+    }
+
+    /// `int *p = alloca; *p = value; return 100 / *p;`
+    fn divide_by_stored(name: &str, value: Operand) -> Function {
+        let mut func = blank_function(name, vec![Name::from("entry")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        let p = local("p", Type::pointer_to(Type::i32()));
+        func.basic_blocks[0].instrs = vec![
+            alloca(Type::i32(), int_const(32, 1), "p"),
+            store(p.clone(), value),
+            load(p, "y"),
+            Instruction::UDiv(instruction::UDiv {
+                operand0: int_const(32, 100),
+                operand1: local("y", Type::i32()),
+                dest: Name::from("q"),
+                debugloc: None,
+            }),
+        ];
+        func.basic_blocks[0].term = ret(local("q", Type::i32()));
+        func
+    }
+
+    #[test]
+    fn havoc_memory() {
+        let project = blank_project("test_mod", divide_by_stored("f", int_const(32, 5)));
+        let config = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            approximation: crate::config::Approximation::HavocMemory,
+            ..Config::default()
+        };
+        // the stored 5 is forgotten, so the division looks like it may be by zero
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        assert_eq!(em.violations().len(), 1);
+        assert!(em.violations()[0].approximate);
+        assert!(em.violations()[0].to_string().ends_with("(approximate)"));
+    }
+
+    #[test]
+    fn two_phase_scan() {
+        let x = local("x", Type::i32());
+        let five = int_const(32, 5);
+        let mut no_division = blank_function("no_division", vec![Name::from("entry")]);
+        no_division.basic_blocks[0].term = ret_void();
+        let project = project_with_functions("test_mod", vec![divide_by_stored("stored_x", x), divide_by_stored("stored_five", five), no_division]);
+        let config: Config<BtorBackend> = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            ..Config::default()
+        };
+        let results = scan_functions(&["stored_x", "stored_five", "no_division"], &project, config);
+        // both divisions are flagged by the approximate pass ...
+        let funcnames: Vec<&str> = results.iter().map(|result| result.funcname.as_str()).collect();
+        assert_eq!(funcnames, ["stored_x", "stored_five"]);
+        // ... but only one is confirmed by the precise pass
+        let confirmed = results[0].precise.as_ref().unwrap();
+        assert_eq!(confirmed.answer.len(), 1);
+        assert!(!confirmed.answer[0].violation.approximate);
+        assert_eq!(confirmed.answer[0].violation.witness, vec![Some(0)]);
+        assert!(results[1].precise.as_ref().unwrap().answer.is_empty());
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let project = blank_project("test_mod", store_and_divide());
+        let config = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        let violations = em.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].location, "f, bb entry, instr 2");
+        assert_eq!(violations[0].witness, vec![Some(0)]);
+    }
+
+    /// `return a % b;` (signed)
+    fn signed_remainder(b: Operand) -> Function {
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.parameters.push(param("a", Type::i32()));
+        func.parameters.push(param("b", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![Instruction::SRem(instruction::SRem {
+            operand0: local("a", Type::i32()),
+            operand1: b,
+            dest: Name::from("r"),
+            debugloc: None,
+        })];
+        func.basic_blocks[0].term = ret(local("r", Type::i32()));
+        func
+    }
+
+    #[test]
+    fn signed_division_overflow() {
+        let violations = |b: Operand| {
+            let project = blank_project("test_mod", signed_remainder(b));
+            let config = Config {
+                extra_checkers: vec![Rc::new(SignedDivisionOverflow)],
+                ..Config::default()
+            };
+            let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+            while em.next().is_some() {}
+            em.violations().to_vec()
+        };
+        let violations_b = violations(local("b", Type::i32()));
+        assert_eq!(violations_b.len(), 1);
+        assert_eq!(violations_b[0].checker, "signed-division-overflow");
+        assert_eq!(violations_b[0].witness, vec![Some(0x8000_0000), Some(0xffff_ffff)]);
+        // dividing by a constant other than -1 can't overflow
+        assert!(violations(int_const(32, 2)).is_empty());
+    }
+
+    #[test]
+    fn shrunk_witnesses() {
+        // with an unused `i128` parameter, which can't be shrunk
+        let mut func = signed_remainder(local("b", Type::i32()));
+        func.parameters.push(param("wide", Type::IntegerType { bits: 128 }));
+        let project = blank_project("test_mod", func);
+        let config = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            shrink_witnesses: Some(100),
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        let violations = em.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].witness, vec![Some(0), Some(0), None]);
+    }
+
+    #[test]
+    fn enum_param() {
+        use crate::enums::{EnumType, Enumerator};
+        let project = blank_project("test_mod", store_and_divide());
+        let analyze = |enumerators: Vec<Enumerator>| {
+            let config = Config {
+                extra_checkers: vec![Rc::new(DivisionByZero)],
+                enum_params: std::iter::once(("x".to_owned(), EnumType::new("enum mode", enumerators))).collect(),
+                ..Config::default()
+            };
+            let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+            while em.next().is_some() {}
+            em.violations().to_vec()
+        };
+        // with `x` restricted to legal values, it can only be zero if an enumerator is zero
+        assert!(analyze(vec![Enumerator::new("SLOW", 1), Enumerator::new("FAST", 2), Enumerator::new("TURBO", 3)]).is_empty());
+        let violations = analyze(vec![Enumerator::new("OFF", 0), Enumerator::new("SLOW", 1), Enumerator::new("FAST", 2)]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].witness, vec![Some(0)]);
+        assert_eq!(violations[0].witness_enumerators, vec![Some("OFF".to_owned())]);
+        assert!(violations[0].to_string().ends_with("with inputs 0 (OFF)"));
+    }
+
+    #[test]
+    fn no_checkers_by_default() {
+        let project = blank_project("test_mod", store_and_divide());
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, Config::default());
+        while em.next().is_some() {}
+        assert!(em.violations().is_empty());
+    }
+
+    /// `(sum, overflow) = sadd.with.overflow(x, y)`, then `return sum`. If
+    /// `check`, the overflow flag is first branched on, returning -1 on overflow.
+    fn checked_add(check: bool) -> Function {
+        let result_ty = Type::StructType { element_types: vec![Type::i32(), Type::bool()], is_packed: false };
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("overflow")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.parameters.push(param("y", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            call("llvm.sadd.with.overflow.i32", result_ty.clone(), vec![local("x", Type::i32()), local("y", Type::i32())], Some("result")),
+            Instruction::ExtractValue(instruction::ExtractValue {
+                aggregate: local("result", result_ty.clone()),
+                indices: vec![0],
+                dest: Name::from("sum"),
+                debugloc: None,
+            }),
+        ];
+        if check {
+            func.basic_blocks[0].instrs.push(Instruction::ExtractValue(instruction::ExtractValue {
+                aggregate: local("result", result_ty),
+                indices: vec![1],
+                dest: Name::from("flag"),
+                debugloc: None,
+            }));
+            func.basic_blocks.push(BasicBlock::new(Name::from("ok")));
+            func.basic_blocks[0].term = condbr("flag", "overflow", "ok");
+            func.basic_blocks[2].term = ret(local("sum", Type::i32()));
+        } else {
+            func.basic_blocks[0].term = ret(local("sum", Type::i32()));
+        }
+        func.basic_blocks[1].term = ret(int_const(32, (-1_i32) as u32 as u64));
+        func
+    }
+
+    #[test]
+    fn ignored_overflow_flag() {
+        let project = blank_project("test_mod", checked_add(true));
+        let config = Config {
+            extra_checkers: vec![Rc::new(IgnoredOverflowFlag::new())],
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        assert!(em.violations().is_empty());
+
+        let project = blank_project("test_mod", checked_add(false));
+        let config = Config {
+            extra_checkers: vec![Rc::new(IgnoredOverflowFlag::new())],
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        let violations = em.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].checker, "ignored-overflow-flag");
+        assert_eq!(violations[0].location, "f, bb entry, instr 0");
+        let x = violations[0].witness[0].unwrap() as u32 as i32 as i64;
+        let y = violations[0].witness[1].unwrap() as u32 as i32 as i64;
+        assert!(x + y > i64::from(i32::MAX) || x + y < i64::from(i32::MIN));
+    }
+
+    /// `i = 0; do { i++; } while (i < x); return 100 / (i - 5);`
+    fn divide_after_loop() -> Function {
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let i32_const = |value| int_const(32, value);
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("loop"), Name::from("exit")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].term = br("loop");
+        func.basic_blocks[1].instrs = vec![
+            Instruction::Phi(instruction::Phi {
+                incoming_values: vec![(i32_const(0), Name::from("entry")), (local_i32("next"), Name::from("loop"))],
+                dest: Name::from("i"),
+                to_type: Type::i32(),
+                debugloc: None,
+            }),
+            Instruction::Add(instruction::Add { operand0: local_i32("i"), operand1: i32_const(1), dest: Name::from("next"), debugloc: None }),
+            icmp(IntPredicate::ULT, local_i32("next"), local_i32("x"), "more"),
+        ];
+        func.basic_blocks[1].term = condbr("more", "loop", "exit");
+        func.basic_blocks[2].instrs = vec![
+            Instruction::Sub(instruction::Sub { operand0: local_i32("next"), operand1: i32_const(5), dest: Name::from("d"), debugloc: None }),
+            Instruction::UDiv(instruction::UDiv { operand0: i32_const(100), operand1: local_i32("d"), dest: Name::from("q"), debugloc: None }),
+        ];
+        func.basic_blocks[2].term = ret(local_i32("q"));
+        func
+    }
+
+    #[test]
+    fn iterative_deepening() {
+        let project = blank_project("test_mod", divide_after_loop());
+        let config = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            iterative_deepening: Some(crate::config::DeepeningSchedule {
+                loop_bounds: vec![2, 8],
+                callstack_depths: vec![],
+                time_budget: None,
+            }),
+            ..Config::default()
+        };
+        let outcome = find_violations::<BtorBackend>("f", &project, config).unwrap();
+        assert_eq!(outcome.answer.len(), 1);
+        assert_eq!(outcome.answer[0].level, 1);
+        assert_eq!(outcome.answer[0].violation.location, "f, bb exit, instr 1");
+        assert_eq!(outcome.answer[0].violation.witness, vec![Some(5)]);
+        // paths with more than 8 iterations were still cut off
+        assert!(!outcome.summary.is_complete());
+        assert_eq!(outcome.summary.limits_hit, vec![crate::search_outcome::LimitHit::LoopBound(8)]);
+
+        // the first level alone doesn't find it
+        let config = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            loop_bound: 2,
+            ..Config::default()
+        };
+        let outcome = find_violations::<BtorBackend>("f", &project, config).unwrap();
+        assert!(outcome.answer.is_empty());
+    }
+
+    /// A module `src/main.c` with `f(x) { return vendor_div(x); }`, and a
+    /// module `vendor/lib.c` with `vendor_div(x) { return 100 / x; }`
+    fn main_and_vendor() -> Project {
+        let x = local("x", Type::i32());
+        let module = |name: &str, source_file_name: &str, func: Function| Module {
+            source_file_name: source_file_name.to_owned(),
+            ..module_with_functions(name, vec![func])
+        };
+        let ret = |name: &str| ret(local(name, Type::i32()));
+
+        let mut f = blank_function("f", vec![Name::from("entry")]);
+        f.parameters.push(param("x", Type::i32()));
+        f.return_type = Type::i32();
+        f.basic_blocks[0].instrs.push(call("vendor_div", Type::i32(), vec![x.clone()], Some("r")));
+        f.basic_blocks[0].term = ret("r");
+
+        let mut vendor_div = blank_function("vendor_div", vec![Name::from("entry")]);
+        vendor_div.parameters.push(param("x", Type::i32()));
+        vendor_div.return_type = Type::i32();
+        vendor_div.basic_blocks[0].instrs.push(Instruction::UDiv(instruction::UDiv {
+            operand0: int_const(32, 100),
+            operand1: x,
+            dest: Name::from("q"),
+            debugloc: Some(DebugLoc { line: 3, col: Some(12), filename: "vendor/lib.c".to_owned(), directory: None }),
+        }));
+        vendor_div.basic_blocks[0].term = ret("q");
+
+        Project::from_modules(vec![module("main", "src/main.c", f), module("vendor", "vendor/lib.c", vendor_div)])
+    }
+
+    #[test]
+    fn descend_filter() {
+        let project = main_and_vendor();
+        let config = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            descend_filter: Some(Rc::new(|_: &Function, module: &Module| !module.source_file_name.starts_with("vendor/"))),
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        assert_eq!(em.stats().count("vendor_div", &Name::from("entry"), crate::state::BBInstrIndex::Instr(0)), 0);
+        assert!(em.violations().is_empty());
+        assert_eq!(em.suppressed_violations(), 0);
+    }
+
+    #[test]
+    fn report_filter() {
+        let project = main_and_vendor();
+        let config = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            report_filter: crate::config::ReportFilter { include: vec![], exclude: vec!["vendor/*.c".to_owned()] },
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        assert!(em.violations().is_empty());
+        assert_eq!(em.suppressed_violations(), 1);
+
+        let config = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            report_filter: crate::config::ReportFilter { include: vec!["vendor_*".to_owned()], exclude: vec![] },
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        assert_eq!(em.violations().len(), 1);
+        assert_eq!(em.violations()[0].location, "vendor/lib.c, line 3, col 12");
+        assert_eq!(em.suppressed_violations(), 0);
+    }
+
+    /// `char buf[4]; long slot = __stack_chk_guard; buf[index] = 'A';`, then
+    /// the stack protector's check of `slot` against the guard
+    fn stack_protected(index: u64) -> Project {
+        let guard_ty = Type::pointer_to(Type::i64());
+        let buf_ty = Type::pointer_to(Type::ArrayType { element_type: Box::new(Type::i8()), num_elements: 4 });
+        let guard = Operand::ConstantOperand(Constant::GlobalReference { name: Name::from("__stack_chk_guard"), ty: guard_ty.clone() });
+        let slot = local("slot", guard_ty.clone());
+        let alloca = |allocated_type: Type, dest: &str| alloca(allocated_type, int_const(32, 1), dest);
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("ok"), Name::from("fail")]);
+        func.basic_blocks[0].instrs = vec![
+            alloca(Type::ArrayType { element_type: Box::new(Type::i8()), num_elements: 4 }, "buf"),
+            alloca(Type::i64(), "slot"),
+            load(guard.clone(), "canary"),
+            store(slot.clone(), local("canary", Type::i64())),
+            Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: local("buf", buf_ty),
+                indices: vec![int_const(64, 0), int_const(64, index)],
+                dest: Name::from("elt"),
+                in_bounds: false,
+                debugloc: None,
+            }),
+            store(local("elt", Type::pointer_to(Type::i8())), int_const(8, 65)),
+            load(guard, "expected"),
+            load(slot, "actual"),
+            icmp(IntPredicate::EQ, local("expected", Type::i64()), local("actual", Type::i64()), "intact"),
+        ];
+        func.basic_blocks[0].term = condbr("intact", "ok", "fail");
+        func.basic_blocks[1].term = ret_void();
+        func.basic_blocks[2].instrs = vec![call("__stack_chk_fail", Type::VoidType, vec![], None)];
+        func.basic_blocks[2].term = Terminator::Unreachable(terminator::Unreachable { debugloc: None });
+        project_with_globals("test_mod", vec![func], vec![module::GlobalVariable {
+            alignment: 8,
+            ..global_var("__stack_chk_guard", guard_ty, None)
+        }])
+    }
+
+    #[test]
+    fn stack_protector() {
+        // `buf[8]` lands in `slot`, the next cell over
+        let overflowing = stack_protected(8);
+        let in_bounds = stack_protected(3);
+        let config = || Config {
+            extra_checkers: vec![Rc::new(SanitizerChecks)],
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &overflowing, config());
+        while em.next().is_some() {}
+        assert_eq!(em.violations().len(), 1);
+        assert_eq!(em.violations()[0].checker, "sanitizer");
+        assert!(em.violations()[0].message.starts_with("stack buffer overflow"));
+
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &in_bounds, config());
+        while em.next().is_some() {}
+        assert!(em.violations().is_empty());
+    }
+
+    /// `int f(int idx) { int buf[4], next[4]; return buf[idx & mask]; }`
+    fn index_array(mask: u64) -> Project {
+        let arr_ty = Type::ArrayType { element_type: Box::new(Type::i32()), num_elements: 4 };
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.parameters.push(param("idx", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            alloca(arr_ty.clone(), int_const(32, 1), "buf"),
+            alloca(arr_ty.clone(), int_const(32, 1), "next"),
+            Instruction::And(instruction::And {
+                operand0: local("idx", Type::i32()),
+                operand1: int_const(32, mask),
+                dest: Name::from("i"),
+                debugloc: None,
+            }),
+            Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: local("buf", Type::pointer_to(arr_ty)),
+                indices: vec![int_const(32, 0), local("i", Type::i32())],
+                dest: Name::from("p"),
+                in_bounds: true,
+                debugloc: None,
+            }),
+            load(local("p", Type::pointer_to(Type::i32())), "v"),
+        ];
+        func.basic_blocks[0].term = ret(local("v", Type::i32()));
+        blank_project("test_mod", func)
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let config = || Config {
+            extra_checkers: vec![Rc::new(OutOfBounds)],
+            ..Config::default()
+        };
+        let unmasked = index_array(0xFFFF_FFFF);
+        let masked = index_array(3);
+        let into_next = index_array(7);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &unmasked, config());
+        while em.next().is_some() {}
+        assert_eq!(em.violations().len(), 1);
+        let violation = &em.violations()[0];
+        assert_eq!(violation.checker, "out-of-bounds");
+        assert_eq!(violation.location, "f, bb entry, instr 4");
+        assert!(violation.message.starts_with("out-of-bounds access of 4 bytes"));
+        assert!(violation.witness[0].unwrap() >= 4);
+
+        // `buf[4]` through `buf[7]` are inside `next`, but still out of bounds of `buf`
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &into_next, config());
+        while em.next().is_some() {}
+        assert_eq!(em.violations().len(), 1);
+        assert!(em.violations()[0].witness[0].unwrap() & 7 >= 4);
+
+        // `idx & 3` is always a valid index
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &masked, config());
+        while em.next().is_some() {}
+        assert!(em.violations().is_empty());
+    }
+
+    /// ```c
+    /// char *p = malloc(4);
+    /// char *copy = p;
+    /// free(p);
+    /// char *q = malloc(4);
+    /// return *q + *copy;
+    /// ```
+    fn read_after_free() -> Project {
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let four = int_const(64, 4);
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.return_type = Type::i8();
+        func.basic_blocks[0].instrs = vec![
+            call("malloc", i8_ptr.clone(), vec![four.clone()], Some("p")),
+            Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: local("p", i8_ptr.clone()),
+                indices: vec![int_const(64, 0)],
+                dest: Name::from("copy"),
+                in_bounds: true,
+                debugloc: None,
+            }),
+            call("free", Type::VoidType, vec![local("p", i8_ptr.clone())], None),
+            call("malloc", i8_ptr.clone(), vec![four], Some("q")),
+            load(local("q", i8_ptr.clone()), "x"),
+            load(local("copy", i8_ptr.clone()), "y"),
+            Instruction::Add(instruction::Add {
+                operand0: local("x", Type::i8()),
+                operand1: local("y", Type::i8()),
+                dest: Name::from("sum"),
+                debugloc: None,
+            }),
+        ];
+        func.basic_blocks[0].term = ret(local("sum", Type::i8()));
+        blank_project("test_mod", func)
+    }
+
+    /// `char *g() { char c; return &c; }`, and `char f() { return *g(); }`
+    fn read_dead_stack_slot() -> Project {
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let mut g = blank_function("g", vec![Name::from("entry")]);
+        g.return_type = i8_ptr.clone();
+        g.basic_blocks[0].instrs = vec![Instruction::Alloca(instruction::Alloca {
+            allocated_type: Type::i8(),
+            num_elements: int_const(32, 1),
+            dest: Name::from("c"),
+            alignment: 1,
+            debugloc: None,
+        })];
+        g.basic_blocks[0].term = ret(local("c", i8_ptr.clone()));
+        let mut f = blank_function("f", vec![Name::from("entry")]);
+        f.return_type = Type::i8();
+        f.basic_blocks[0].instrs = vec![call("g", i8_ptr.clone(), vec![], Some("p")), load(local("p", i8_ptr.clone()), "x")];
+        f.basic_blocks[0].term = ret(local("x", Type::i8()));
+        project_with_functions("test_mod", vec![f, g])
+    }
+
+    #[test]
+    fn use_after_free() {
+        let config = || Config {
+            extra_checkers: vec![Rc::new(UseAfterFree)],
+            ..Config::default()
+        };
+        // reading the new allocation is fine, but reading through the copy of the freed pointer isn't
+        let heap = read_after_free();
+        let stack = read_dead_stack_slot();
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &heap, config());
+        while em.next().is_some() {}
+        assert_eq!(em.violations().len(), 1);
+        let violation = &em.violations()[0];
+        assert_eq!(violation.checker, "use-after-free");
+        assert_eq!(violation.location, "f, bb entry, instr 5");
+        assert_eq!(violation.message, "use after free of malloc at f, bb entry, instr 0, freed by free at f, bb entry, instr 2");
+
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &stack, config());
+        while em.next().is_some() {}
+        assert_eq!(em.violations().len(), 1);
+        assert_eq!(em.violations()[0].message, "use after free of %c, freed by return from g");
+    }
+
+    /// `int f() { int x; [x = 3;] return x; }`
+    fn read_local(store_first: bool) -> Project {
+        let i32_ptr = Type::pointer_to(Type::i32());
+        let x = local("x", i32_ptr);
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(alloca(Type::i32(), int_const(32, 1), "x"));
+        if store_first {
+            func.basic_blocks[0].instrs.push(store(x.clone(), int_const(32, 3)));
+        }
+        func.basic_blocks[0].instrs.push(load(x, "v"));
+        func.basic_blocks[0].term = ret(local("v", Type::i32()));
+        blank_project("test_mod", func)
+    }
+
+    #[test]
+    fn uninitialized_read() {
+        let config = || Config {
+            detect_uninitialized_reads: true,
+            extra_checkers: vec![Rc::new(UninitializedRead)],
+            ..Config::default()
+        };
+        let uninitialized = read_local(false);
+        let initialized = read_local(true);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &uninitialized, config());
+        while em.next().is_some() {}
+        assert_eq!(em.violations().len(), 1);
+        let violation = &em.violations()[0];
+        assert_eq!(violation.checker, "uninitialized-read");
+        assert_eq!(violation.location, "f, bb entry, instr 1");
+        assert_eq!(violation.message, "uninitialized read of 4 bytes at %x");
+
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &initialized, config());
+        while em.next().is_some() {}
+        assert!(em.violations().is_empty());
+    }
+
+    #[test]
+    fn ubsan_handlers() {
+        assert_eq!(ubsan_violation("add_overflow"), Some("signed integer overflow"));
+        assert_eq!(ubsan_violation("out_of_bounds_abort"), Some("array index out of bounds"));
+        assert_eq!(ubsan_violation("type_mismatch_v1_abort"), Some("null or misaligned pointer access"));
+        assert_eq!(ubsan_violation("shift_out_of_bounds_minimal"), Some("shift amount out of bounds"));
+        assert_eq!(ubsan_violation("dynamic_type_cache_miss"), None);
+    }
+}
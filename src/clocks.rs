@@ -0,0 +1,180 @@
+//! Modeling of clocks and counters which the program reads, such as
+//! `clock_gettime()`, so that code comparing timestamps (timeouts, rate
+//! limits, and so on) gets explored in both directions
+
+use std::fmt;
+
+/// A clock which may be read with
+/// [`State::read_clock()`](../struct.State.html#method.read_clock), for
+/// instance by the hooks installed by
+/// [`FunctionHooks::add_time_hooks()`](../function_hooks/struct.FunctionHooks.html#method.add_time_hooks).
+///
+/// Each reading is a fresh symbolic value. Readings are tracked per path:
+/// they are restored on backtracking.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Clock {
+    /// `CLOCK_MONOTONIC` and friends, in nanoseconds. Each reading is no
+    /// earlier than the previous reading on the path, and no more than
+    /// [`Config.max_time_step`](../config/struct.Config.html#structfield.max_time_step)
+    /// later.
+    Monotonic,
+    /// `CLOCK_REALTIME`, as read by `time()` and `gettimeofday()` as well, in
+    /// nanoseconds since the epoch. The wall clock may be set at any time, so
+    /// each reading is unconstrained: it may even be earlier than the previous
+    /// one.
+    Realtime,
+    /// The CPU time used by the process, as read by `clock()` and
+    /// `CLOCK_PROCESS_CPUTIME_ID`, in nanoseconds. Constrained like
+    /// `Monotonic`, but independently of it.
+    ProcessCpu,
+    /// The processor's cycle counter (`llvm.readcyclecounter` or `rdtsc`).
+    /// Each reading is no smaller than the previous one, but may be
+    /// arbitrarily larger.
+    Cycles,
+}
+
+impl Clock {
+    /// The clock with the given `clockid_t`, using Linux's numbering
+    pub fn from_clockid(clockid: u64) -> Self {
+        match clockid {
+            0 | 5 | 8 | 11 => Clock::Realtime, // CLOCK_REALTIME, _COARSE, _ALARM, CLOCK_TAI
+            2 | 3 => Clock::ProcessCpu, // CLOCK_PROCESS_CPUTIME_ID, CLOCK_THREAD_CPUTIME_ID
+            _ => Clock::Monotonic, // CLOCK_MONOTONIC, _RAW, _COARSE, CLOCK_BOOTTIME, ...
+        }
+    }
+
+    /// Must readings of this clock be nondecreasing along a path?
+    pub fn is_monotonic(self) -> bool {
+        self != Clock::Realtime
+    }
+
+    /// Does `Config.max_time_step` bound the difference between consecutive
+    /// readings of this clock?
+    pub(crate) fn is_bounded(self) -> bool {
+        self == Clock::Monotonic || self == Clock::ProcessCpu
+    }
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Clock::Monotonic => write!(f, "monotonic"),
+            Clock::Realtime => write!(f, "realtime"),
+            Clock::ProcessCpu => write!(f, "process_cpu"),
+            Clock::Cycles => write!(f, "cycles"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::BtorBackend;
+    use crate::config::Config;
+    use crate::error::Error;
+    use crate::function_hooks::generic_stub_hook;
+    use crate::return_value::ReturnValue;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::*;
+    use std::time::Duration;
+
+    /// ```c
+    /// struct timespec ts;
+    /// clock_gettime(clockid, &ts);
+    /// time_t start = ts.tv_sec;
+    /// while (!try_once()) {
+    ///     clock_gettime(clockid, &ts);
+    ///     if (ts.tv_sec < start) return 2;  // time went backwards
+    ///     if (ts.tv_sec - start >= 5) return 1;  // timed out
+    /// }
+    /// return 0;
+    /// ```
+    fn retry_with_timeout(clockid: u64) -> Function {
+        let ts = local("ts", Type::pointer_to(Type::i64()));
+        let clockid = int_const(32, clockid);
+        let i64_const = |value| int_const(64, value);
+        let ret_i32 = |value| ret(int_const(32, value));
+        let load = |dest: &str| load(ts.clone(), dest);
+
+        let mut func = blank_function("f", ["entry", "loop", "check", "backwards", "elapsed", "timeout", "success"].iter().map(|&bb| Name::from(bb)).collect());
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            Instruction::Alloca(instruction::Alloca {
+                allocated_type: Type::i64(),
+                num_elements: int_const(32, 2),
+                dest: Name::from("ts"),
+                alignment: 8,
+                debugloc: None,
+            }),
+            call("clock_gettime", Type::i32(), vec![clockid.clone(), ts.clone()], None),
+            load("start"),
+        ];
+        func.basic_blocks[0].term = br("loop");
+        func.basic_blocks[1].instrs = vec![
+            call("try_once", Type::i32(), vec![], Some("ok")),
+            icmp(IntPredicate::NE, local("ok", Type::i32()), int_const(32, 0), "succeeded"),
+        ];
+        func.basic_blocks[1].term = condbr("succeeded", "success", "check");
+        func.basic_blocks[2].instrs = vec![
+            call("clock_gettime", Type::i32(), vec![clockid, ts.clone()], None),
+            load("now"),
+            icmp(IntPredicate::SLT, local("now", Type::i64()), local("start", Type::i64()), "went_backwards"),
+        ];
+        func.basic_blocks[2].term = condbr("went_backwards", "backwards", "elapsed");
+        func.basic_blocks[3].term = ret_i32(2);
+        func.basic_blocks[4].instrs = vec![
+            Instruction::Sub(instruction::Sub { operand0: local("now", Type::i64()), operand1: local("start", Type::i64()), dest: Name::from("el"), debugloc: None }),
+            icmp(IntPredicate::SGE, local("el", Type::i64()), i64_const(5), "timed_out"),
+        ];
+        func.basic_blocks[4].term = condbr("timed_out", "timeout", "loop");
+        func.basic_blocks[5].term = ret_i32(1);
+        func.basic_blocks[6].term = ret_i32(0);
+        func
+    }
+
+    fn config<'p>(max_time_step: Option<Duration>) -> Config<'p, BtorBackend> {
+        let mut config = Config { loop_bound: 3, max_time_step, ..Config::default() };
+        config.function_hooks.add_time_hooks();
+        config.function_hooks.add("try_once", &generic_stub_hook);
+        config
+    }
+
+    /// Get the distinct values returned on paths which didn't exceed the loop bound
+    fn returned_values(em: &mut ExecutionManager<BtorBackend>) -> Vec<u64> {
+        let mut values = vec![];
+        for retval in em.by_ref() {
+            match retval {
+                Ok(ReturnValue::Return(bv)) => values.push(bv.as_u64().unwrap()),
+                Err(Error::LoopBoundExceeded(_)) => {},
+                r => panic!("Unexpected return value {:?}", r),
+            }
+        }
+        values.sort();
+        values.dedup();
+        values
+    }
+
+    #[test]
+    fn monotonic_retry_with_timeout() {
+        let project = blank_project("test_mod", retry_with_timeout(1));  // CLOCK_MONOTONIC
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config(None));
+        // both succeeding and timing out are reachable, but time never goes backwards
+        assert_eq!(returned_values(&mut em), vec![0, 1]);
+    }
+
+    #[test]
+    fn realtime_may_jump_backwards() {
+        let project = blank_project("test_mod", retry_with_timeout(0));  // CLOCK_REALTIME
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config(None));
+        assert_eq!(returned_values(&mut em), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn bounded_time_step() {
+        // with at most one second between readings, and at most 3 retries,
+        // the 5-second timeout can't be reached
+        let project = blank_project("test_mod", retry_with_timeout(1));
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config(Some(Duration::from_secs(1))));
+        assert_eq!(returned_values(&mut em), vec![0]);
+    }
+}
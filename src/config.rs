@@ -2,10 +2,20 @@
 
 use crate::backend::Backend;
 use crate::callbacks::Callbacks;
+use crate::checkers::ViolationChecker;
 pub use crate::demangling::Demangling;
+use crate::enums::EnumType;
 use crate::function_hooks::FunctionHooks;
+use crate::layout::ByteOrder;
+use crate::middleware::{ConstraintCtx, MiddlewareAction};
+use crate::rewrite::ExprRewriter;
 use crate::watchpoints::Watchpoint;
+use llvm_ir::{Function, Module};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
 use std::time::Duration;
 
 /// Various settings which affect how the symbolic execution is performed.
@@ -29,6 +39,20 @@ pub struct Config<'p, B> where B: Backend {
     /// Default is `10`.
     pub loop_bound: usize,
 
+    /// If `Some`, the maximum number of iterations of any one loop on any one
+    /// path. Unlike `loop_bound`, this counts iterations of each run of the
+    /// loop separately: a path which enters a loop (including an inner loop
+    /// entered again by an outer loop, or a loop in a function called again)
+    /// starts a fresh count. A loop is recognized by the back-edge to its
+    /// header in the control-flow graph. A path which would exceed the limit
+    /// ends with `Error::LoopBoundExceeded`, just like one exceeding
+    /// `loop_bound`.
+    ///
+    /// `loop_bound` still applies in addition to this setting.
+    ///
+    /// Default is `None`.
+    pub max_loop_iterations: Option<usize>,
+
     /// Maximum callstack depth to allow when symbolically executing.
     /// If symbolic execution encounters a call which would result in a
     /// stack depth exceeding this number, and the call is not hooked (see
@@ -58,22 +82,150 @@ pub struct Config<'p, B> where B: Backend {
     /// Default is `None`.
     pub max_callstack_depth: Option<usize>,
 
+    /// Number of bytes of stack to count for each function call, in addition
+    /// to the function's `alloca`s, when computing stack usage (see
+    /// [`ExecutionManager::max_stack_usage()`](../struct.ExecutionManager.html#method.max_stack_usage)).
+    /// This should account for the return address, saved registers, and so
+    /// on, as appropriate for the target.
+    ///
+    /// Default is `16`.
+    pub stack_frame_overhead: u64,
+
     /// Maximum amount of time to allow for any single solver query.
     ///
     /// If `Some`, any solver query lasting longer than the given limit will
-    /// be killed.  This will result in an `Error::SolverError` for that path.
+    /// be killed.  This will result in an `Error::SolverError` for that path,
+    /// except for queries deciding which sides of a conditional branch are
+    /// feasible: there, a side whose feasibility is unknown is explored anyway.
     ///
     /// If `None`, there will be no time limit for solver queries.
     ///
     /// Default is 300 seconds (5 minutes).
     pub solver_query_timeout: Option<Duration>,
 
+    /// Seed for `haybale`'s own random choices (currently, just the order of
+    /// `BacktrackingOrder::Random`). It is also passed on to Boolector as its
+    /// `Seed` option, which is 32 bits.
+    ///
+    /// Given the same `Project`, `Config`, and `random_seed`, paths are
+    /// explored in the same order, and the same solutions are found along
+    /// them (e.g., the values returned by `find_zero_of_func()`). `haybale`
+    /// makes no promise about how changing the seed affects which of several
+    /// valid solutions Boolector reports.
+    ///
+    /// Default is `0`.
+    pub random_seed: u32,
+
+    /// The order in which pending backtracking points are resumed, that is,
+    /// in which the remaining paths are explored; see
+    /// [`BacktrackingOrder`](enum.BacktrackingOrder.html).
+    ///
+    /// With any order other than `DepthFirst`, resuming a backtracking point
+    /// only brings back the constraints on the path it was saved on which are
+    /// listed by
+    /// [`State::path_constraints()`](../struct.State.html#method.path_constraints).
+    /// Constraints asserted directly with `BV::assert()` are not listed there,
+    /// so these orders can't be used together with function hooks or
+    /// callbacks other than `haybale`'s own: the `ExecutionManager` returns an
+    /// `Error::OtherError` in that case. (`haybale`'s own hooks, and the
+    /// conditions returned by [`checkers`](../checkers/index.html), go through
+    /// [`State::assert_constraint()`](../struct.State.html#method.assert_constraint)
+    /// and are fine.) Also, `ConstraintCtx.shared_depth` is always 0 with
+    /// these orders, so an [`SmtTee`](../middleware/struct.SmtTee.html) only
+    /// records each path's constraints from the point where it resumed.
+    ///
+    /// Default is `BacktrackingOrder::DepthFirst`.
+    pub backtracking_order: BacktrackingOrder,
+
+    /// How the solver keeps the constraints of pending backtracking points
+    /// apart from those of the current path; see
+    /// [`Checkpointing`](enum.Checkpointing.html).
+    ///
+    /// `Checkpointing::AssumptionLiterals` is only used if there are no
+    /// function hooks or callbacks other than `haybale`'s own, since
+    /// constraints which those assert directly with `BV::assert()` would
+    /// otherwise be lost on backtracking. Otherwise, this falls back to
+    /// `Checkpointing::SolverFrames`.
+    ///
+    /// Default is `Checkpointing::AssumptionLiterals`.
+    pub checkpointing: Checkpointing,
+
+    /// How precisely should we model memory? See
+    /// [`Approximation`](enum.Approximation.html).
+    ///
+    /// Default is `Approximation::Precise`.
+    pub approximation: Approximation,
+
     /// Should we check each memory access for possible `NULL` dereference,
     /// and if so, how should we report any errors?
     ///
     /// Default is `NullPointerChecking::Simple`.
     pub null_pointer_checking: NullPointerChecking,
 
+    /// How should we treat `undef` operands?
+    /// See [`UndefValues`](enum.UndefValues.html).
+    ///
+    /// Default is `UndefValues::Unconstrained`.
+    pub undef_values: UndefValues,
+
+    /// Rounding mode for floating-point arithmetic and conversions.
+    /// LLVM's floating-point instructions always round to nearest, ties to
+    /// even, unless they are constrained intrinsics; the other modes are
+    /// useful for exploring code which changes the rounding mode at runtime.
+    ///
+    /// Only available with the `fp` feature.
+    ///
+    /// Default is `RoundingMode::NearestTiesToEven`.
+    #[cfg(feature = "fp")]
+    pub fp_rounding_mode: RoundingMode,
+
+    /// How to compute `llvm.fmuladd`, which LLVM allows to be either a fused
+    /// multiply-add or a multiply followed by an add. Which one the native
+    /// code does depends on the target and compiler flags, and matters for
+    /// whether a witness reproduces natively. `llvm.fma` is always fused.
+    ///
+    /// Fast-math flags (`nnan`, `ninf`, and so on) are not taken into account,
+    /// here or for any other floating-point instruction: `llvm-ir` 0.5 doesn't
+    /// expose them. So NaN and infinite values are always considered possible,
+    /// even where the flags say the native code may assume they don't occur.
+    ///
+    /// Only available with the `fp` feature.
+    ///
+    /// Default is `FmaSemantics::Fused`.
+    #[cfg(feature = "fp")]
+    pub fma_semantics: FmaSemantics,
+
+    /// Byte order of multi-byte values in memory, used by `load` and `store`
+    /// instructions and for the initial contents of globals.
+    ///
+    /// Default is `None` - that is, use the byte order specified by the
+    /// `Project`'s data layout, or little-endian if it doesn't specify one.
+    pub byte_order: Option<ByteOrder>,
+
+    /// Ranges of addresses which are invalid to access. If
+    /// `null_pointer_checking` is enabled, an access which may fall in one of
+    /// these ranges is reported as `Error::NullPointerDereference`, the same
+    /// way a `NULL` dereference is. Addresses in `mapped_address_ranges` are
+    /// excluded from these ranges.
+    ///
+    /// An access at the literal address `0` is always reported (unless `0` is
+    /// in `mapped_address_ranges`), even if this list is empty.
+    ///
+    /// The allocator never hands out addresses in these ranges.
+    ///
+    /// Default is `[0 .. 1]`, i.e., just `NULL` itself.
+    pub invalid_address_ranges: Vec<Range<u64>>,
+
+    /// Ranges of addresses which are valid to access even though they fall in
+    /// one of the `invalid_address_ranges`, such as a vector table or RAM
+    /// mapped at address `0` on a microcontroller, or memory-mapped I/O.
+    ///
+    /// The allocator never hands out addresses in these ranges either, since
+    /// they are assumed to belong to the target.
+    ///
+    /// Default is empty.
+    pub mapped_address_ranges: Vec<Range<u64>>,
+
     /// When encountering a `memcpy`, `memset`, or `memmove` with multiple
     /// possible lengths, how (if at all) should we concretize the length?
     ///
@@ -94,6 +246,114 @@ pub struct Config<'p, B> where B: Backend {
     /// Default is `None` - that is, no limit.
     pub max_memcpy_length: Option<u64>,
 
+    /// When encountering a `load` or `store` whose address has multiple
+    /// possible values, how (if at all) should we concretize the address?
+    ///
+    /// Default is `AddressResolution::Symbolic` - that is, no concretization.
+    pub address_resolution: AddressResolution,
+
+    /// Should the default `malloc()`, `calloc()`, and `realloc()` hooks
+    /// consider the possibility that the allocation fails?
+    ///
+    /// If `true`, each of these calls may return `NULL`, so that the code
+    /// which handles the failure is explored too. If `false`, allocations are
+    /// assumed to always succeed.
+    ///
+    /// Default is `false`.
+    pub malloc_can_fail: bool,
+
+    /// Should the default `realloc()` hook move the allocation to a new
+    /// address even when it is shrunk (or kept the same size)? C allows
+    /// `realloc()` to either move the allocation or return the same pointer.
+    ///
+    /// If `false`, only growing an allocation moves it. If `true`, every
+    /// `realloc()` of a non-`NULL` pointer moves the allocation, so the old
+    /// pointer is always `free()`d.
+    ///
+    /// Default is `false`.
+    pub realloc_always_moves: bool,
+
+    /// Should the default `free()` hook report freeing the same allocation
+    /// twice on a path, or freeing something other than a heap allocation?
+    /// If so, doing that results in an `Error::DoubleFree` or
+    /// `Error::InvalidFree` respectively, ending that path.
+    ///
+    /// A pointer with several possible values, some of which have already
+    /// been freed, splits the path: those values are explored on their own
+    /// path (which then reports the `Error::DoubleFree`), and the current path
+    /// continues with the pointer constrained to the others.
+    ///
+    /// Either way, `free()`ing `NULL` is allowed, and the default hooks never
+    /// reuse freed memory.
+    ///
+    /// Default is `false`.
+    pub detect_double_frees: bool,
+
+    /// Should heap allocations which are still live when a path returns from
+    /// the top-level function be reported as memory leaks? If so, each one
+    /// gets a [path note](../struct.State.html#method.path_notes) such as
+    /// `memory leak: malloc at x.c:10 is never freed`.
+    ///
+    /// For now, every allocation made by the default `malloc()`, `calloc()`,
+    /// or `realloc()` hooks and not `free()`d on the path counts, even if the
+    /// caller could still free it, e.g. via the return value.
+    ///
+    /// Default is `false`.
+    pub report_memory_leaks: bool,
+
+    /// Should reads of memory which may never have been written on the
+    /// current path be reported? If so, the `State` tracks which bits of
+    /// memory are initialized, and
+    /// [`State::read()`](../struct.State.html#method.read) logs a warning,
+    /// naming the allocation and offset read from, whenever any of the bits it
+    /// reads may be uninitialized. To also report these reads as violations,
+    /// with inputs which trigger them, add
+    /// [`checkers::UninitializedRead`](../checkers/struct.UninitializedRead.html)
+    /// to the `extra_checkers`.
+    ///
+    /// `alloca`s, `malloc()`s, and the new part of a `realloc()`ed allocation
+    /// start out uninitialized; any write initializes memory, including
+    /// stores, `memset()`, `memcpy()`, and global variable initializers.
+    /// Memory the program didn't allocate, such as the buffers that pointer
+    /// parameters of the top-level function point to, counts as initialized
+    /// (with unconstrained contents), unless marked with
+    /// [`State::mark_uninitialized()`](../struct.State.html#method.mark_uninitialized).
+    ///
+    /// If `false`, memory which was never written just has unconstrained
+    /// contents.
+    ///
+    /// Default is `false`.
+    pub detect_uninitialized_reads: bool,
+
+    /// Maximum number of distinct functions a function pointer may point to
+    /// when it is called.
+    ///
+    /// If a called function pointer may point to more than one function (for
+    /// instance, a C++ virtual call where the object's dynamic type depends on
+    /// the inputs, so the vtable slot loaded from the object's vtable could hold
+    /// either of several overrides), we explore each possible callee on its own
+    /// path, with the function pointer constrained accordingly. If there are
+    /// more possible callees than this, the call results in an
+    /// `Error::OtherError`.
+    ///
+    /// Setting this to `1` means any function pointer with multiple possible
+    /// targets is an error.
+    ///
+    /// Default is `8`.
+    pub max_function_ptr_targets: usize,
+
+    /// Maximum amount by which the monotonic clocks may advance between two
+    /// consecutive readings on a path, for the hooks installed by
+    /// [`FunctionHooks::add_time_hooks()`](../function_hooks/struct.FunctionHooks.html#method.add_time_hooks).
+    ///
+    /// If `None`, each reading may be arbitrarily later than the previous one
+    /// (but never earlier). This doesn't apply to `CLOCK_REALTIME`, which may
+    /// jump anyway, or to the cycle counter; see
+    /// [`Clock`](../clocks/enum.Clock.html).
+    ///
+    /// Default is `None`.
+    pub max_time_step: Option<Duration>,
+
     /// `Error::Unsat` is an error type which is used internally, but may not be
     /// useful for `ExecutionManager.next()` to return to consumers. In most
     /// cases, consumers probably don't care about paths which were partially
@@ -129,6 +389,154 @@ pub struct Config<'p, B> where B: Backend {
     /// Default is `true`.
     pub trust_llvm_assumes: bool,
 
+    /// If `true`, whenever a branch direction or an `llvm.assume()` turns out
+    /// to be infeasible on the current path, record an
+    /// [`UnsatDiagnosis`](../diagnosis/struct.UnsatDiagnosis.html) explaining
+    /// which earlier condition on the path it likely conflicts with.
+    /// Diagnoses can be retrieved with
+    /// [`ExecutionManager::unsat_diagnoses()`](../struct.ExecutionManager.html#method.unsat_diagnoses).
+    ///
+    /// This costs an extra solver query for each `llvm.assume()`, plus some
+    /// bookkeeping for each branch.
+    ///
+    /// Default is `false`.
+    pub diagnose_unsat_paths: bool,
+
+    /// If the entry function's parameters total at most this many bits, then
+    /// rather than leaving them fully symbolic, the `ExecutionManager` will
+    /// enumerate every concrete value of the parameters, exploring all paths
+    /// for each one in turn. For functions with tiny input spaces (say, a
+    /// single `u8`), this gives exact, exhaustive coverage of the inputs.
+    ///
+    /// Each input is run concretely: the parameters are known constants on its
+    /// paths, so values computed from them are constants too, and branches on
+    /// them are decided without the solver. Inputs are taken one at a time,
+    /// each with a single solver check that it is consistent with the
+    /// `preconditions` and any other constraints already on the path.
+    ///
+    /// Paths are returned by the `ExecutionManager` just as they would be
+    /// otherwise, except that on each path, the parameters are constrained to
+    /// one particular concrete input.
+    /// [`ExecutionManager.exhaustive_input_count()`](../struct.ExecutionManager.html#method.exhaustive_input_count)
+    /// indicates whether this mode is in effect, and how many inputs have been
+    /// run. With `run_global_ctors`, the inputs are enumerated after the
+    /// constructors, separately on each path through them.
+    ///
+    /// Functions with any pointer parameters never use this mode, and neither
+    /// does any `backtracking_order` other than `DepthFirst`. Parameters
+    /// which have been made concrete (e.g., with
+    /// `state.overwrite_latest_version_of_bv()`) before the first call to
+    /// `next()` don't count towards the threshold. Since the number of inputs
+    /// is exponential in this setting, values larger than about `16` are not
+    /// recommended.
+    ///
+    /// A value of `0` disables this mode.
+    ///
+    /// Default is `0`.
+    pub exhaustive_threshold_bits: u32,
+
+    /// Preconditions on the entry function's inputs, written as C-like
+    /// expressions over the names of its parameters and of global variables,
+    /// for instance `"len <= 64 && (flags & 3) != 0"`. Only paths where all of
+    /// these hold are explored.
+    ///
+    /// The syntax is a small subset of C expressions:
+    ///   - identifiers, which refer to parameters of the entry function, or to
+    ///     global variables (meaning the global's value, not its address)
+    ///   - `$0`, `$1`, etc, the entry function's parameters by position, for
+    ///     when they don't have names
+    ///   - `len(s)`, the length of a Rust slice parameter `s` (which `rustc`
+    ///     passes as two parameters, `s.0` and `s.1`). For instance,
+    ///     `"idx < len(v)"` makes bounds checks of `v[idx]` infeasible, when
+    ///     the caller is known to guarantee that.
+    ///   - integer literals, in decimal or hex (`0x`)
+    ///   - unary `!`, `~`, and `-`
+    ///   - casts like `(i32)x` or `(u8)x`, which truncate or extend to the given
+    ///     width and make the value signed or unsigned respectively
+    ///   - binary `*`, `/`, `%`, `+`, `-`, `<<`, `>>`, `<`, `<=`, `>`, `>=`,
+    ///     `==`, `!=`, `&`, `^`, `|`, `&&`, and `||`, with the usual C precedence
+    ///   - parentheses
+    ///
+    /// Values are unsigned unless cast to a signed type, as in `(i32)x < 0`.
+    /// When two values of different widths meet, the narrower one is extended
+    /// (sign-extended if it is signed), and the operation is signed only if
+    /// both operands are. Literals take on the width and signedness of whatever
+    /// they are combined with.
+    ///
+    /// Preconditions are checked when the `ExecutionManager` produces its first
+    /// path; a precondition which fails to parse, or which refers to an unknown
+    /// name, results in an `Error::OtherError` at that point.
+    ///
+    /// Default is no preconditions.
+    pub preconditions: Vec<String>,
+
+    /// Parameters of the entry function which hold values of an enum type,
+    /// keyed by parameter name. Like `preconditions`, each of these restricts
+    /// exploration to paths where the parameter holds a legal value of its
+    /// type: one of the enumerators, or for bitflags (see
+    /// [`EnumType::is_flags()`](../enums/struct.EnumType.html#method.is_flags)),
+    /// any combination of the flags. Witnesses in `Violation`s name the
+    /// enumerator(s) for these parameters.
+    ///
+    /// Leave a parameter out to explore its illegal values too, for instance
+    /// to find out how the function copes with them.
+    ///
+    /// `haybale` can't read enum types from debug info, so these must be
+    /// supplied by the user.
+    ///
+    /// Default is no enum parameters.
+    pub enum_params: HashMap<String, EnumType>,
+
+    /// If `Some`, then witnesses reported to the user (the arguments returned
+    /// by [`find_zero_of_func()`](../fn.find_zero_of_func.html), and the
+    /// [`Violation::witness`](../checkers/struct.Violation.html#structfield.witness)es
+    /// found by checkers) are shrunk
+    /// before being reported: each value is set to zero if possible, or
+    /// otherwise moved as close to zero as we can, while still satisfying the
+    /// constraints of the path that produced it. The value here is the maximum
+    /// number of solver queries to spend on shrinking each witness; see
+    /// [`solver_utils::shrink_witness()`](../solver_utils/fn.shrink_witness.html).
+    ///
+    /// If `None`, witnesses are whatever solution the solver happens to give.
+    ///
+    /// Default is `None`.
+    pub shrink_witnesses: Option<usize>,
+
+    /// If `Some`, then each `Violation` found in the entry function itself
+    /// comes with a simplified condition on the function's parameters under
+    /// which it occurs (in
+    /// [`Violation.condition`](../checkers/struct.Violation.html#structfield.condition)),
+    /// computed from the conditions of the branches the path took, by
+    /// dropping those which don't matter; see
+    /// [`ConditionMinimization`](struct.ConditionMinimization.html).
+    ///
+    /// Default is `None`.
+    pub violation_conditions: Option<ConditionMinimization>,
+
+    /// If `true`, then before executing the entry function, each path first
+    /// executes the module constructors listed in `@llvm.global_ctors` (in
+    /// priority order), so that globals hold the values those constructors
+    /// compute rather than just their static initializers. The constructors
+    /// share memory and solver state with the entry function.
+    ///
+    /// Default is `false`.
+    pub run_global_ctors: bool,
+
+    /// When `run_global_ctors` is set, how to handle branches in the
+    /// constructors which could go either way.
+    ///
+    /// Default is `GlobalCtorPaths::Single`.
+    pub global_ctor_paths: GlobalCtorPaths,
+
+    /// When `run_global_ctors` is set, the maximum number of instructions
+    /// (including terminators, and instructions in functions they call) which
+    /// each constructor may execute on a path, so that a pathological
+    /// constructor can't consume the whole run. A path whose constructor
+    /// exceeds this results in an `Error::OtherError`.
+    ///
+    /// Default is `100_000`.
+    pub global_ctor_instruction_budget: u64,
+
     /// The set of currently active function hooks; see
     /// [`FunctionHooks`](../function_hooks/struct.FunctionHooks.html) for more details.
     ///
@@ -143,6 +551,100 @@ pub struct Config<'p, B> where B: Backend {
     /// Default is no callbacks.
     pub callbacks: Callbacks<'p, B>,
 
+    /// Additional checkers to consult on every path; see
+    /// [`ViolationChecker`](../checkers/trait.ViolationChecker.html). The
+    /// violations they find are available from
+    /// [`ExecutionManager::violations()`](../struct.ExecutionManager.html#method.violations).
+    ///
+    /// Default is no extra checkers.
+    pub extra_checkers: Vec<Rc<dyn ViolationChecker<'p, B> + 'p>>,
+
+    /// Which violations found by the `extra_checkers` to report, by the
+    /// source file or function they occur in; see
+    /// [`ReportFilter`](struct.ReportFilter.html). Violations which are
+    /// filtered out are only counted, in
+    /// [`ExecutionManager::suppressed_violations()`](../struct.ExecutionManager.html#method.suppressed_violations).
+    ///
+    /// Default is to report all violations.
+    pub report_filter: ReportFilter,
+
+    /// If `Some`, [`checkers::find_violations()`](../checkers/fn.find_violations.html)
+    /// runs its analysis repeatedly, with progressively larger bounds, as
+    /// described by the [`DeepeningSchedule`](struct.DeepeningSchedule.html).
+    ///
+    /// Default is `None`, i.e., analyze once, with the `loop_bound` and
+    /// `max_callstack_depth` of this `Config`.
+    pub iterative_deepening: Option<DeepeningSchedule>,
+
+    /// If `Some`, this is consulted before entering any function defined in
+    /// the `Project`. If it returns `false`, the function is treated as
+    /// external (as if it weren't defined in the `Project`): calls to it use
+    /// the default hook in `function_hooks` if there is one, and otherwise
+    /// return an unconstrained value. For instance, this can keep exploration
+    /// out of trusted third-party code.
+    ///
+    /// This doesn't apply to functions which have hooks, nor to the entry
+    /// function itself.
+    ///
+    /// Default is `None`, meaning every defined function may be entered.
+    pub descend_filter: Option<DescendFilter<'p>>,
+
+    /// If `Some`, every constraint the engine is about to assert on a path is
+    /// first passed to this, along with a
+    /// [`ConstraintCtx`](../middleware/struct.ConstraintCtx.html) saying where
+    /// it came from. The returned
+    /// [`MiddlewareAction`](../middleware/enum.MiddlewareAction.html) says
+    /// whether to assert it as-is, assert something else instead, drop it, or
+    /// abandon the path. This is intended for experimentation, such as
+    /// weakening constraints, or logging them with
+    /// [`SmtTee`](../middleware/struct.SmtTee.html).
+    ///
+    /// Dropping or replacing constraints generally makes the analysis
+    /// unsound: paths may be explored which aren't actually feasible.
+    ///
+    /// Default is `None`.
+    pub constraint_middleware: Option<ConstraintMiddleware<'p, B>>,
+
+    /// Rules for simplifying expressions of particular shapes as they are
+    /// built, such as domain-specific encodings which the solver handles
+    /// slowly; see [`ExprRewriter`](../rewrite/trait.ExprRewriter.html). Each
+    /// expression is offered to the rules in order, and the first `Rewrite`
+    /// returned is used.
+    ///
+    /// Default is no rules.
+    pub rewrite_rules: Vec<Rc<dyn ExprRewriter<'p, B> + 'p>>,
+
+    /// If `true`, each time one of the `rewrite_rules` applies, check with
+    /// the solver that the replacement equals the original expression under
+    /// the current constraints and the rule's assertions, and end the path
+    /// with an error if not. This is for debugging rules, and is slow.
+    ///
+    /// Default is `false`.
+    pub check_rewrites: bool,
+
+    /// If `true`, constraints which the solver's expression rewriting has
+    /// already reduced to a constant are handled without the solver: a
+    /// constraint which is always `true` isn't asserted at all, and once a
+    /// constraint which is always `false` has been asserted on a path,
+    /// satisfiability checks on that path report unsatisfiable without a
+    /// solver query.
+    ///
+    /// Default is `false`.
+    pub simplify_constraints: bool,
+
+    /// If `true`, remember the results of satisfiability checks (`check()`,
+    /// `sat()`, and their `_with_extra_constraints()` variants) until the
+    /// constraints change, so that repeating a check doesn't query the solver
+    /// again.
+    ///
+    /// Changes made through `State` methods (such as `assert_constraint()`
+    /// and backtracking) are noticed automatically, but constraints asserted
+    /// directly with `BV::assert()` are not: hooks which do that should call
+    /// `State::forget_cached_solver_results()` afterwards.
+    ///
+    /// Default is `false`.
+    pub cache_solver_results: bool,
+
     /// The initial memory watchpoints when a `State` is created (mapping from
     /// watchpoint name to the actual watchpoint).
     ///
@@ -152,6 +654,14 @@ pub struct Config<'p, B> where B: Backend {
     /// Default is no watchpoints.
     pub initial_mem_watchpoints: HashMap<String, Watchpoint>,
 
+    /// Should reads and writes which trigger a memory watchpoint be collected,
+    /// in addition to being logged? If so, they can be retrieved with
+    /// [`State::watchpoint_hits()`](../struct.State.html#method.watchpoint_hits),
+    /// along with the value read or written and the path they were made on.
+    ///
+    /// Default is `false`.
+    pub collect_watchpoint_hits: bool,
+
     /// Controls the (attempted) demangling of function names in error messages
     /// and backtraces.
     ///
@@ -222,6 +732,144 @@ pub enum NullPointerChecking {
     None,
 }
 
+/// Enum used for the `undef_values` option in `Config`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum UndefValues {
+    /// Each time an `undef` value is evaluated, it gets a fresh, unconstrained
+    /// value (named `undef_<n>`), so every possible value is considered.
+    /// `undef` pointers may point anywhere.
+    Unconstrained,
+
+    /// Any instruction with an `undef` operand results in
+    /// `Error::UndefUse`, ending that path. Useful for hunting undefined
+    /// behavior. `undef`s nested inside constant aggregates (e.g., padding in
+    /// a struct initializer) still get unconstrained values.
+    Error,
+}
+
+/// Enum used for the `fp_rounding_mode` option in `Config`: the IEEE 754
+/// rounding-direction attributes.
+#[cfg(feature = "fp")]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RoundingMode {
+    /// Round to the nearest representable value; on a tie, to the one with an
+    /// even least significant bit
+    NearestTiesToEven,
+    /// Round to the nearest representable value; on a tie, to the one with
+    /// the larger magnitude
+    NearestTiesToAway,
+    /// Round toward positive infinity
+    TowardPositive,
+    /// Round toward negative infinity
+    TowardNegative,
+    /// Round toward zero (truncate)
+    TowardZero,
+}
+
+/// Enum used for the `fma_semantics` option in `Config`.
+#[cfg(feature = "fp")]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum FmaSemantics {
+    /// Compute the exact product plus the addend, and round once
+    Fused,
+    /// Round the product, then round the sum, as separate `fmul` and `fadd`
+    /// instructions would
+    Separate,
+}
+
+/// Struct used for the `violation_conditions` option in `Config`.
+///
+/// The branch conditions on the path to a violation are recomputed in terms
+/// of the entry function's parameters, structurally, as in
+/// [`analyze::block_reaching_conditions()`](../analyze/fn.block_reaching_conditions.html):
+/// the results of loads, calls, and other instructions which aren't pure
+/// computations are unconstrained. Then branch conditions are dropped one at a
+/// time, oldest first, as long as what remains meets the `strictness`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ConditionMinimization {
+    pub strictness: Strictness,
+    /// Maximum number of solver queries to spend on each violation. Branch
+    /// conditions which haven't been considered when this runs out are kept.
+    pub max_queries: usize,
+}
+
+/// How strictly a violation condition must describe the violation; see
+/// [`ConditionMinimization`](struct.ConditionMinimization.html)
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Strictness {
+    /// Every input satisfying the condition reaches the violation (along
+    /// some path through the function) and triggers it. Branch conditions
+    /// which other paths to the violation don't share are dropped.
+    Sufficient,
+
+    /// The condition holds for every input which reaches the violation and
+    /// triggers it. Only branch conditions which are necessary for that are
+    /// kept, so the condition may be weaker than required to trigger the
+    /// violation.
+    Necessary,
+}
+
+/// Enum used for the `approximation` option in `Config`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Approximation {
+    /// Model memory precisely.
+    Precise,
+
+    /// Don't model memory at all: every load produces a fresh unconstrained
+    /// value, and stores have no effect. Checkers aren't asked about memory
+    /// accesses (`ViolationChecker::check_memory_access()`), but all other
+    /// checks still run, and the violations they report are marked
+    /// `approximate`.
+    ///
+    /// This makes exploration much cheaper, at the cost of false positives
+    /// (and possibly of paths which are infeasible in reality), so it's useful
+    /// for a first pass over many functions to find candidates for a precise
+    /// analysis; see
+    /// [`checkers::scan_functions()`](../checkers/fn.scan_functions.html).
+    HavocMemory,
+}
+
+/// The levels of iterative deepening to use in
+/// [`checkers::find_violations()`](../checkers/fn.find_violations.html); see
+/// [`Config.iterative_deepening`](struct.Config.html#structfield.iterative_deepening).
+///
+/// Each level is a complete analysis with the `loop_bound` and
+/// `max_callstack_depth` for that level. Deepening stops early once a level
+/// completes without hitting any bound, since its results are then complete.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeepeningSchedule {
+    /// The `loop_bound` for each level, e.g. `[2, 8, 32]`
+    pub loop_bounds: Vec<usize>,
+    /// The `max_callstack_depth` for each level, e.g. `[3, 6, 12]`. If this
+    /// is shorter than `loop_bounds`, its last entry is used for the
+    /// remaining levels; if it is empty, `Config.max_callstack_depth` is used
+    /// for all levels.
+    pub callstack_depths: Vec<usize>,
+    /// Total wall-clock time to spend across all levels, or `None` for no
+    /// limit. Each level gets a share of the remaining time proportional to
+    /// its loop bound, and is cut off if it exceeds its share; unused time
+    /// carries over to later levels.
+    pub time_budget: Option<Duration>,
+}
+
+impl DeepeningSchedule {
+    /// Number of levels in the schedule
+    pub fn num_levels(&self) -> usize {
+        std::cmp::max(self.loop_bounds.len(), self.callstack_depths.len())
+    }
+
+    /// The `loop_bound` and `max_callstack_depth` to use for the given level,
+    /// given the values from the `Config`
+    pub(crate) fn bounds_for_level(&self, level: usize, loop_bound: usize, max_callstack_depth: Option<usize>) -> (usize, Option<usize>) {
+        let pick = |values: &[usize], level: usize| values.get(level).or_else(|| values.last()).copied();
+        (
+            pick(&self.loop_bounds, level).unwrap_or(loop_bound),
+            pick(&self.callstack_depths, level).or(max_callstack_depth),
+        )
+    }
+}
+
 /// Enum used for the `concretize_memcpy_lengths` option in `Config`.
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum Concretize {
@@ -259,6 +907,146 @@ pub enum Concretize {
     /// The value will be permanently constrained to be this value (on this
     /// path), and other possibilities will not be considered.
     Minimum,
+
+    /// Explore each possible value on its own path, up to the given number of
+    /// values (the lowest ones, interpreted in an unsigned fashion); any
+    /// further values are not considered.
+    ///
+    /// On the current path, the value will be permanently constrained to be
+    /// the lowest possible value; each of the others gets a backtracking point
+    /// which re-executes the operation with the value constrained to it.
+    Fork(usize),
+}
+
+/// Enum used for the `address_resolution` option in `Config`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AddressResolution {
+    /// Have the solver fully consider all possible values of the address.
+    /// Memory is an array in the solver's theory of arrays, so this is sound,
+    /// but many accesses with symbolic addresses may lead to poor solver
+    /// performance.
+    Symbolic,
+
+    /// Pick one possible value of the address arbitrarily. The address will be
+    /// permanently constrained to be that value (on this path), and other
+    /// possibilities will not be considered.
+    ConcretizeToOne,
+
+    /// Pick up to the given number of possible values of the address, and
+    /// explore each on its own path, as for function pointers with multiple
+    /// possible targets. On each path, the address will be permanently
+    /// constrained to be the corresponding value. Any further possibilities
+    /// will not be considered.
+    ConcretizeUpTo(usize),
+}
+
+/// Enum used for the `global_ctor_paths` option in `Config`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GlobalCtorPaths {
+    /// Follow only the first feasible path through each constructor, so that
+    /// the entry function starts from a single initial state.
+    Single,
+
+    /// Follow all feasible paths through the constructors, so that each of
+    /// them continues into the entry function. The number of paths through the
+    /// entry function is multiplied accordingly.
+    All,
+}
+
+/// Enum used for the `backtracking_order` option in `Config`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BacktrackingOrder {
+    /// Resume the most recently saved backtracking point first, finishing
+    /// the paths through one branch before starting on the other. This is the
+    /// cheapest order, as the path resumed always extends part of the
+    /// current one.
+    DepthFirst,
+
+    /// Resume the earliest saved backtracking point first, so that branches
+    /// near the start of the function are explored before deeper ones.
+    BreadthFirst,
+
+    /// Resume a pending backtracking point chosen at random, using
+    /// `Config.random_seed`.
+    Random,
+
+    /// Resume the backtracking point with the fewest constraints on its path,
+    /// or the most recently saved of those with equally few.
+    FewestConstraintsFirst,
+}
+
+/// Enum used for the `checkpointing` option in `Config`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Checkpointing {
+    /// With `BacktrackingOrder::DepthFirst`, each pending backtracking point
+    /// holds one level of the solver's assertion stack, with the constraints
+    /// asserted on the current path since the point was saved. So with
+    /// thousands of pending points, the solver has thousands of levels.
+    /// With other orders, the current path's constraints since the first
+    /// backtracking point are in a single level, and resuming a point
+    /// re-asserts all of the constraints on its path.
+    SolverFrames,
+
+    /// Constraints on the current path are asserted in the form `g => c`,
+    /// where `g` is a fresh literal for each stretch of the path between
+    /// backtracking points, and implies the literal for the stretch before.
+    /// Only the current path's last literal is asserted, in a single level of
+    /// the solver's assertion stack above all the rest. Saving a backtracking
+    /// point just starts a new literal; resuming one replaces the asserted
+    /// literal by a fresh one implying the point's, after moving down the
+    /// constraints which later paths may still need from the replaced level.
+    /// So the solver's assertion stack stays at most two levels deep (see
+    /// [`SolverStats.peak_assertion_depth`](../stats/struct.SolverStats.html#structfield.peak_assertion_depth)),
+    /// however many points are pending, and in any `BacktrackingOrder`.
+    AssumptionLiterals,
+}
+
+/// Type used for the `descend_filter` option in `Config`: given a function
+/// defined in the `Project` and its `Module`, should we enter it?
+pub type DescendFilter<'p> = Rc<dyn Fn(&Function, &Module) -> bool + 'p>;
+
+/// Type used for the `constraint_middleware` option in `Config`: given a
+/// (1-bit) constraint the engine is about to assert, what should happen to it?
+pub type ConstraintMiddleware<'p, B> = Rc<dyn Fn(&ConstraintCtx, &<B as Backend>::BV) -> MiddlewareAction<<B as Backend>::BV> + 'p>;
+
+/// Struct used for the `report_filter` option in `Config`.
+///
+/// Each pattern matches either a function name (mangled or demangled), or a
+/// source file name (with or without its directory), and may use `*` to match
+/// any sequence of characters; for instance, `"vendor/*.c"` or
+/// `"parse_*"`. Violations without source location information can only be
+/// matched by function name.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReportFilter {
+    /// If nonempty, only report violations matching one of these patterns
+    pub include: Vec<String>,
+    /// Never report violations matching any of these patterns, even if they
+    /// match `include`
+    pub exclude: Vec<String>,
+}
+
+impl ReportFilter {
+    /// Should a violation in the given function (with the given demangled
+    /// name, if different) and source file be reported?
+    pub fn allows(&self, funcnames: &[&str], source_file: Option<&str>) -> bool {
+        let matches = |pattern: &String| {
+            funcnames.iter().any(|funcname| glob_match(pattern, funcname))
+                || source_file.is_some_and(|file| glob_match(pattern, file) || file.rsplit('/').next().is_some_and(|basename| glob_match(pattern, basename)))
+        };
+        (self.include.is_empty() || self.include.iter().any(matches)) && !self.exclude.iter().any(matches)
+    }
+}
+
+/// Does `text` match `pattern`, in which `*` matches any sequence of characters?
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == text,
+        Some(star) => {
+            let (prefix, rest) = (&pattern[.. star], &pattern[star + 1 ..]);
+            text.starts_with(prefix) && (prefix.len() ..= text.len()).any(|i| text.is_char_boundary(i) && glob_match(rest, &text[i ..]))
+        },
+    }
 }
 
 impl<'p, B: Backend> Config<'p, B> {
@@ -287,16 +1075,57 @@ impl<'p, B: Backend> Default for Config<'p, B> {
     fn default() -> Self {
         Self {
             loop_bound: 10,
+            max_loop_iterations: None,
             max_callstack_depth: None,
+            stack_frame_overhead: 16,
             solver_query_timeout: Some(Duration::from_secs(300)),
+            random_seed: 0,
+            backtracking_order: BacktrackingOrder::DepthFirst,
+            checkpointing: Checkpointing::AssumptionLiterals,
+            approximation: Approximation::Precise,
             null_pointer_checking: NullPointerChecking::Simple,
+            undef_values: UndefValues::Unconstrained,
+            #[cfg(feature = "fp")]
+            fp_rounding_mode: RoundingMode::NearestTiesToEven,
+            #[cfg(feature = "fp")]
+            fma_semantics: FmaSemantics::Fused,
+            byte_order: None,
+            invalid_address_ranges: vec![Range { start: 0, end: 1 }],
+            mapped_address_ranges: Vec::new(),
             concretize_memcpy_lengths: Concretize::Symbolic,
             max_memcpy_length: None,
+            address_resolution: AddressResolution::Symbolic,
+            malloc_can_fail: false,
+            realloc_always_moves: false,
+            detect_double_frees: false,
+            report_memory_leaks: false,
+            detect_uninitialized_reads: false,
+            max_function_ptr_targets: 8,
+            max_time_step: None,
             squash_unsats: true,
             trust_llvm_assumes: true,
+            diagnose_unsat_paths: false,
+            exhaustive_threshold_bits: 0,
+            preconditions: Vec::new(),
+            enum_params: HashMap::new(),
+            shrink_witnesses: None,
+            violation_conditions: None,
+            run_global_ctors: false,
+            global_ctor_paths: GlobalCtorPaths::Single,
+            global_ctor_instruction_budget: 100_000,
             function_hooks: FunctionHooks::default(),
             callbacks: Callbacks::default(),
+            extra_checkers: Vec::new(),
+            report_filter: ReportFilter::default(),
+            iterative_deepening: None,
+            descend_filter: None,
+            constraint_middleware: None,
+            rewrite_rules: Vec::new(),
+            check_rewrites: false,
+            simplify_constraints: false,
+            cache_solver_results: false,
             initial_mem_watchpoints: HashMap::new(),
+            collect_watchpoint_hits: false,
             demangling: None,
             print_source_info: true,
             print_module_name: true,
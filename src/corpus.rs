@@ -0,0 +1,211 @@
+//! Generating fuzzer corpus files (in the raw format used by libFuzzer and
+//! AFL) from the inputs which reach each path through a fuzz target.
+
+use crate::backend::*;
+use crate::config::Config;
+use crate::error::*;
+use crate::project::Project;
+use crate::state::State;
+use crate::symex::{symex_function, ExecutionManager};
+use llvm_ir::{Function, Type};
+use log::{info, warn};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+/// The maximum length, in bytes, of the inputs generated by
+/// [`generate_corpus()`](fn.generate_corpus.html). Paths which require longer
+/// inputs are not explored.
+pub const MAX_INPUT_LEN: u64 = 256;
+
+/// Check whether the function has the shape of a fuzz target, that is, whether
+/// it takes a single byte buffer as input, as a pointer to `i8` followed by an
+/// integer length. This is the shape of libFuzzer's `LLVMFuzzerTestOneInput()`,
+/// and also of Rust fuzz targets taking a `&[u8]`.
+///
+/// Returns `Err` with the reason if the function isn't fuzz-shaped.
+pub fn check_fuzz_target(func: &Function) -> std::result::Result<(), String> {
+    let types: Vec<&Type> = func.parameters.iter().map(|p| &p.ty).collect();
+    match types.as_slice() {
+        [Type::PointerType { pointee_type, .. }, Type::IntegerType { .. }] if **pointee_type == Type::i8() => Ok(()),
+        [_, _] => Err(format!("{:?} is not a fuzz target: expected parameters of type (i8*, <integer>), but got {:?}", func.name, types)),
+        _ => Err(format!("{:?} is not a fuzz target: expected 2 parameters (a byte buffer and its length), but it has {}", func.name, types.len())),
+    }
+}
+
+/// The symbolic input of a fuzz target: its initial buffer contents and length
+pub struct FuzzInput<V> {
+    /// The initial contents of the buffer, one 8-bit `BV` per byte, for the
+    /// first `MAX_INPUT_LEN` bytes
+    pub bytes: Vec<V>,
+    /// The length parameter
+    pub len: V,
+}
+
+impl<V: BV> FuzzInput<V> {
+    /// Set up the entry state of a fuzz target (see
+    /// [`check_fuzz_target()`](fn.check_fuzz_target.html)): point the buffer
+    /// parameter at a fresh allocation of `MAX_INPUT_LEN` bytes, and
+    /// constrain the length parameter to be at most `MAX_INPUT_LEN`.
+    ///
+    /// `param_bvs` are the `BV`s for the function's parameters, as from
+    /// `ExecutionManager::param_bvs()`.
+    pub fn new<'p, B: Backend<BV = V>>(state: &mut State<'p, B>, param_bvs: &[V]) -> Result<Self> {
+        let (data, len) = match param_bvs {
+            [data, len] => (data, len),
+            _ => return Err(Error::OtherError(format!("Expected a fuzz target to have 2 parameters, but got {}", param_bvs.len()))),
+        };
+        let buf = state.allocate(MAX_INPUT_LEN * 8);
+        data._eq(&buf).assert()?;
+        len.ulte(&state.bv_from_u64(MAX_INPUT_LEN, len.get_width())).assert()?;
+        state.forget_cached_solver_results();
+        let bytes = (0 .. MAX_INPUT_LEN)
+            .map(|i| state.read(&buf.add(&state.bv_from_u64(i, buf.get_width())), 8))
+            .collect::<Result<_>>()?;
+        Ok(Self { bytes, len: len.clone() })
+    }
+
+    /// Get concrete bytes for this input which are consistent with the
+    /// current constraints in the `State`; that is, the raw corpus-file form of
+    /// an input which reaches the current path.
+    ///
+    /// Returns `Ok(None)` if the current constraints are unsatisfiable.
+    pub fn to_corpus_bytes<'p, B: Backend<BV = V>>(&self, state: &State<'p, B>) -> Result<Option<Vec<u8>>> {
+        // solve for the length and all of the bytes at once, so that we only
+        // need one model
+        let all = self.bytes.iter().fold(self.len.clone(), |acc, byte| acc.concat(byte));
+        let solution = match state.get_a_solution_for_bv(&all)? {
+            Some(solution) => solution.disambiguate(),
+            None => return Ok(None),
+        };
+        let bits = solution.as_01x_str();
+        let len_width = self.len.get_width() as usize;
+        let len = u64::from_str_radix(&bits[.. len_width], 2)
+            .map_err(|e| Error::OtherError(format!("Failed to parse solution for input length: {}", e)))?;
+        let bytes = bits.as_bytes()[len_width ..]
+            .chunks(8)
+            .take(len as usize)
+            .map(|chunk| u8::from_str_radix(std::str::from_utf8(chunk).unwrap(), 2)
+                .map_err(|e| Error::OtherError(format!("Failed to parse solution for input byte: {}", e))))
+            .collect::<Result<_>>()?;
+        Ok(Some(bytes))
+    }
+}
+
+/// Explore paths through the fuzz target with the given name (see
+/// [`check_fuzz_target()`](fn.check_fuzz_target.html)), and for each path,
+/// write an input which reaches that path into a file in `out_dir`, in the raw
+/// format used by libFuzzer and AFL corpora. Inputs are at most
+/// [`MAX_INPUT_LEN`](constant.MAX_INPUT_LEN.html) bytes long.
+///
+/// Files are named by a hash of their contents, and inputs with the same
+/// contents as an earlier one are skipped. At most `max_files` files are
+/// written. Paths which end in an error (e.g., by exceeding the loop bound)
+/// are skipped too.
+///
+/// Returns the number of files written, or `Err` with a message if the
+/// function isn't fuzz-shaped or something else went wrong.
+pub fn generate_corpus<'p, B: Backend>(
+    funcname: &str,
+    project: &'p Project,
+    config: Config<'p, B>,
+    out_dir: impl AsRef<Path>,
+    max_files: usize,
+) -> std::result::Result<usize, String> {
+    let (func, _) = project.get_func_by_name(funcname).ok_or_else(|| format!("Failed to find function named {:?}", funcname))?;
+    check_fuzz_target(func)?;
+    let out_dir = out_dir.as_ref();
+    std::fs::create_dir_all(out_dir).map_err(|e| format!("Failed to create directory {}: {}", out_dir.display(), e))?;
+
+    let mut em: ExecutionManager<B> = symex_function(funcname, project, config);
+    let param_bvs = em.param_bvs().clone();
+    let input = FuzzInput::new(em.mut_state(), &param_bvs)?;
+
+    let mut seen = HashSet::new();
+    while seen.len() < max_files {
+        match em.next() {
+            None => break,
+            Some(Err(e)) => {
+                warn!("Skipping path which ended in error: {}", e);
+                continue;
+            },
+            Some(Ok(_)) => {},
+        }
+        let bytes = match input.to_corpus_bytes(em.state())? {
+            Some(bytes) => bytes,
+            None => continue,
+        };
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        let hash = hasher.finish();
+        if seen.insert(hash) {
+            let path = out_dir.join(format!("{:016x}", hash));
+            info!("Writing corpus file {}", path.display());
+            std::fs::write(&path, &bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(seen.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::test_utils::*;
+    use llvm_ir::*;
+
+    /// A fuzz target which returns 1 if `size > 1 && data[1] == 'A'`, else 0
+    fn fuzz_target() -> Function {
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let ret_i32 = |value| ret(int_const(32, value));
+        let mut func = blank_function("LLVMFuzzerTestOneInput", vec![Name::from("entry"), Name::from("check"), Name::from("yes"), Name::from("no")]);
+        func.parameters.push(param("data", i8_ptr.clone()));
+        func.parameters.push(param("size", Type::i64()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::UGT, local("size", Type::i64()), int_const(64, 1), "long_enough"));
+        func.basic_blocks[0].term = condbr("long_enough", "check", "no");
+        func.basic_blocks[1].instrs = vec![
+            Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: local("data", i8_ptr.clone()),
+                indices: vec![int_const(64, 1)],
+                dest: Name::from("p"),
+                in_bounds: true,
+                debugloc: None,
+            }),
+            load(local("p", i8_ptr), "byte"),
+            icmp(IntPredicate::EQ, local("byte", Type::i8()), int_const(8, u64::from(b'A')), "is_a"),
+        ];
+        func.basic_blocks[1].term = condbr("is_a", "yes", "no");
+        func.basic_blocks[2].term = ret_i32(1);
+        func.basic_blocks[3].term = ret_i32(0);
+        func
+    }
+
+    #[test]
+    fn not_fuzz_shaped() {
+        let func = blank_function("f", vec![Name::from("entry")]);
+        assert!(check_fuzz_target(&func).is_err());
+        assert!(check_fuzz_target(&fuzz_target()).is_ok());
+    }
+
+    #[test]
+    fn corpus_covers_each_path() {
+        let project = blank_project("test_mod", fuzz_target());
+        let out_dir = std::env::temp_dir().join(format!("haybale_corpus_test_{}", std::process::id()));
+        let written = generate_corpus::<BtorBackend>("LLVMFuzzerTestOneInput", &project, Config::default(), &out_dir, 10)
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(written, 3);
+        let inputs: Vec<Vec<u8>> = std::fs::read_dir(&out_dir).unwrap()
+            .map(|entry| std::fs::read(entry.unwrap().path()).unwrap())
+            .collect();
+        std::fs::remove_dir_all(&out_dir).unwrap();
+        assert_eq!(inputs.len(), 3);
+        // replaying the inputs covers each of the three paths once
+        let too_short = inputs.iter().filter(|bytes| bytes.len() <= 1).count();
+        let has_a = inputs.iter().filter(|bytes| bytes.len() > 1 && bytes[1] == b'A').count();
+        let no_a = inputs.iter().filter(|bytes| bytes.len() > 1 && bytes[1] != b'A').count();
+        assert_eq!((too_short, has_a, no_a), (1, 1, 1));
+        assert!(inputs.iter().all(|bytes| bytes.len() as u64 <= MAX_INPUT_LEN));
+    }
+}
@@ -0,0 +1,211 @@
+//! Diagnostics explaining why paths were found to be infeasible; see
+//! [`Config.diagnose_unsat_paths`](../config/struct.Config.html#structfield.diagnose_unsat_paths).
+
+use crate::state::Location;
+use llvm_ir::{Constant, Function, Instruction, IntPredicate, Name, Operand};
+use std::fmt;
+
+/// At most this many `UnsatDiagnosis`es are recorded per `ExecutionManager`;
+/// later infeasible paths are not diagnosed
+pub const MAX_UNSAT_DIAGNOSES: usize = 64;
+
+/// A condition which was assumed on some path
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PathCondition {
+    /// What kind of instruction imposed the condition, e.g. `"branch"` or
+    /// `"assume"`
+    pub kind: &'static str,
+    /// Where the condition was imposed: the source location if available, or
+    /// else the LLVM location
+    pub location: String,
+    /// The condition, pretty-printed, e.g. `%len > 512`
+    pub condition: String,
+    /// The LLVM values the condition refers to
+    pub(crate) operands: Vec<Name>,
+}
+
+/// An explanation of why some condition couldn't hold on a path, killing that
+/// path (or direction of a branch)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct UnsatDiagnosis {
+    /// The condition which couldn't hold
+    pub infeasible: PathCondition,
+    /// The earlier condition on the path which most likely conflicts with
+    /// `infeasible`, if one could be identified.
+    ///
+    /// This is a heuristic: it is the most recently imposed condition which
+    /// refers to any of the same LLVM values as `infeasible`, or the most
+    /// recently imposed condition overall if none do. The conflict may also
+    /// involve other conditions in `path_conditions`.
+    pub culprit: Option<PathCondition>,
+    /// All of the conditions imposed on the path before `infeasible`, oldest
+    /// first
+    pub path_conditions: Vec<PathCondition>,
+}
+
+impl fmt::Display for PathCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.location)
+    }
+}
+
+impl fmt::Display for UnsatDiagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} requires {}", self.infeasible, self.infeasible.condition)?;
+        match &self.culprit {
+            Some(culprit) => write!(f, ", conflicting with {} ({})", culprit, culprit.condition),
+            None => write!(f, ", which is unsatisfiable on its own"),
+        }
+    }
+}
+
+impl UnsatDiagnosis {
+    pub(crate) fn new(infeasible: PathCondition, path_conditions: Vec<PathCondition>) -> Self {
+        let culprit = path_conditions.iter().rev()
+            .find(|cond| cond.operands.iter().any(|op| infeasible.operands.contains(op)))
+            .or_else(|| path_conditions.last())
+            .cloned();
+        Self { infeasible, culprit, path_conditions }
+    }
+}
+
+impl PathCondition {
+    /// The condition that the `i1` operand `cond` has the given `value`, at
+    /// the given `Location`
+    pub(crate) fn new(kind: &'static str, loc: &Location, cond: &Operand, value: bool) -> Self {
+        let location = match loc.source_loc {
+            Some(source_loc) => source_loc.to_string(),
+            None => loc.to_string_no_module(),
+        };
+        let (condition, operands) = describe_condition(loc.func, cond, value);
+        Self { kind, location, condition, operands }
+    }
+}
+
+/// Pretty-print the condition that the `i1` operand `cond` has the given
+/// `value`, looking through the `icmp` which produced it if possible. Also
+/// returns the LLVM values the condition refers to.
+fn describe_condition(func: &Function, cond: &Operand, value: bool) -> (String, Vec<Name>) {
+    if let Operand::LocalOperand { name, .. } = cond {
+        let icmp = func.basic_blocks.iter()
+            .flat_map(|bb| bb.instrs.iter())
+            .find_map(|inst| match inst {
+                Instruction::ICmp(icmp) if &icmp.dest == name => Some(icmp),
+                _ => None,
+            });
+        if let Some(icmp) = icmp {
+            let predicate = if value { icmp.predicate } else { negate(icmp.predicate) };
+            let signed = matches!(predicate, IntPredicate::SGT | IntPredicate::SGE | IntPredicate::SLT | IntPredicate::SLE);
+            let operands = [&icmp.operand0, &icmp.operand1].iter()
+                .filter_map(|op| match op {
+                    Operand::LocalOperand { name, .. } => Some(name.clone()),
+                    _ => None,
+                })
+                .collect();
+            let condition = format!("{} {} {}", describe_operand(&icmp.operand0, signed), predicate_symbol(predicate), describe_operand(&icmp.operand1, signed));
+            return (condition, operands);
+        }
+    }
+    let operands = match cond {
+        Operand::LocalOperand { name, .. } => vec![name.clone()],
+        _ => vec![],
+    };
+    let condition = if value { describe_operand(cond, false) } else { format!("!{}", describe_operand(cond, false)) };
+    (condition, operands)
+}
+
+fn describe_operand(op: &Operand, signed: bool) -> String {
+    match op {
+        Operand::LocalOperand { name: Name::Name(name), .. } => format!("%{}", name),
+        Operand::LocalOperand { name: Name::Number(n), .. } => format!("%{}", n),
+        Operand::ConstantOperand(Constant::Int { bits, value }) if signed && *bits < 64 && *bits > 0 => {
+            // sign-extend to 64 bits
+            let shift = 64 - bits;
+            format!("{}", ((*value << shift) as i64) >> shift)
+        },
+        Operand::ConstantOperand(Constant::Int { bits: 64, value }) if signed => format!("{}", *value as i64),
+        Operand::ConstantOperand(Constant::Int { value, .. }) => format!("{}", value),
+        op => format!("{:?}", op),
+    }
+}
+
+pub(crate) fn negate(predicate: IntPredicate) -> IntPredicate {
+    match predicate {
+        IntPredicate::EQ => IntPredicate::NE,
+        IntPredicate::NE => IntPredicate::EQ,
+        IntPredicate::UGT => IntPredicate::ULE,
+        IntPredicate::UGE => IntPredicate::ULT,
+        IntPredicate::ULT => IntPredicate::UGE,
+        IntPredicate::ULE => IntPredicate::UGT,
+        IntPredicate::SGT => IntPredicate::SLE,
+        IntPredicate::SGE => IntPredicate::SLT,
+        IntPredicate::SLT => IntPredicate::SGE,
+        IntPredicate::SLE => IntPredicate::SGT,
+    }
+}
+
+pub(crate) fn predicate_symbol(predicate: IntPredicate) -> &'static str {
+    match predicate {
+        IntPredicate::EQ => "==",
+        IntPredicate::NE => "!=",
+        IntPredicate::UGT | IntPredicate::SGT => ">",
+        IntPredicate::UGE | IntPredicate::SGE => ">=",
+        IntPredicate::ULT | IntPredicate::SLT => "<",
+        IntPredicate::ULE | IntPredicate::SLE => "<=",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::config::Config;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::*;
+
+    /// `assume(len <= 128); if (len > 512) return 1; else return 0;`
+    fn contradiction() -> Function {
+        let len = local("len", Type::i32());
+        let i32_const = |value| int_const(32, value);
+        let cond = |name: &str| local(name, Type::bool());
+        let ret_i32 = |value| ret(i32_const(value));
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("big"), Name::from("small")]);
+        func.parameters.push(param("len", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            icmp(IntPredicate::ULE, len.clone(), i32_const(128), "short"),
+            call("llvm.assume", Type::VoidType, vec![cond("short")], None),
+            icmp(IntPredicate::UGT, len, i32_const(512), "long"),
+        ];
+        func.basic_blocks[0].term = condbr("long", "big", "small");
+        func.basic_blocks[1].term = ret_i32(1);
+        func.basic_blocks[2].term = ret_i32(0);
+        func
+    }
+
+    #[test]
+    fn explains_contradiction() {
+        let project = blank_project("test_mod", contradiction());
+        let config = Config {
+            diagnose_unsat_paths: true,
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        while em.next().is_some() {}
+        let diagnoses = em.unsat_diagnoses();
+        assert_eq!(diagnoses.len(), 1);
+        assert_eq!(
+            diagnoses[0].to_string(),
+            "branch at f, bb entry, terminator requires %len > 512, conflicting with assume at f, bb entry, instr 1 (%len <= 128)",
+        );
+    }
+
+    #[test]
+    fn off_by_default() {
+        let project = blank_project("test_mod", contradiction());
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, Config::default());
+        while em.next().is_some() {}
+        assert!(em.unsat_diagnoses().is_empty());
+    }
+}
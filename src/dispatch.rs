@@ -0,0 +1,292 @@
+//! Analyzing each handler of a table-driven command dispatcher, such as the
+//! command processor of a firmware image; see
+//! [`analyze_dispatch_table()`](fn.analyze_dispatch_table.html).
+
+use crate::backend::Backend;
+use crate::checkers::{find_violations, Finding};
+use crate::config::Config;
+use crate::project::Project;
+use crate::search_outcome::SearchOutcome;
+use llvm_ir::{Constant, Name};
+use log::info;
+use std::fmt;
+
+/// Describes a dispatch table: a global array of structs, each of which holds
+/// an opcode, a pointer to the function which handles that opcode, and the
+/// bounds on the length of the command buffer the handler accepts.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DispatchSpec {
+    /// Name of the global variable holding the table
+    pub table: String,
+    /// Index of the field (in each entry) holding the opcode
+    pub opcode_field: usize,
+    /// Index of the field holding the pointer to the handler function
+    pub handler_field: usize,
+    /// Index of the field holding the minimum buffer length
+    pub min_len_field: usize,
+    /// Index of the field holding the maximum buffer length
+    pub max_len_field: usize,
+    /// Index of the handler parameter which receives the buffer length
+    pub len_param: usize,
+}
+
+/// One entry of a dispatch table, as read from the table's initializer
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DispatchEntry {
+    pub opcode: u64,
+    /// Name of the handler function
+    pub handler: String,
+    pub min_len: u64,
+    pub max_len: u64,
+}
+
+/// The results of analyzing the handler of one `DispatchEntry`
+#[derive(Clone, Debug)]
+pub struct HandlerReport {
+    pub entry: DispatchEntry,
+    /// The violations found in the handler, as by
+    /// [`find_violations()`](../checkers/fn.find_violations.html), or an
+    /// error message if the analysis failed
+    pub outcome: Result<SearchOutcome<Vec<Finding>>, String>,
+}
+
+/// Error returned by
+/// [`analyze_dispatch_table()`](fn.analyze_dispatch_table.html) when the
+/// table can't be read according to the `DispatchSpec`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum DispatchError {
+    /// There is no global variable with the table's name
+    TableNotFound(String),
+    /// The table has no initializer in the `Project`, e.g. because it is only
+    /// declared, or is initialized at runtime
+    NoInitializer(String),
+    /// The table's initializer isn't a constant array of structs
+    NotAnArrayOfStructs(String),
+    /// The entry with the given index has no field with the given index
+    FieldOutOfRange { entry: usize, field: usize },
+    /// The given field of the given entry isn't of the expected kind (an
+    /// integer constant, or a reference to a function)
+    NonConstantField { entry: usize, field: usize },
+    /// The given handler isn't defined in the `Project`
+    HandlerNotFound(String),
+    /// The given handler doesn't have the `DispatchSpec.len_param`
+    LenParamOutOfRange(String),
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DispatchError::TableNotFound(table) => write!(f, "no global variable named {:?}", table),
+            DispatchError::NoInitializer(table) => write!(f, "dispatch table {:?} has no initializer", table),
+            DispatchError::NotAnArrayOfStructs(table) => write!(f, "dispatch table {:?} isn't a constant array of structs", table),
+            DispatchError::FieldOutOfRange { entry, field } => write!(f, "entry {} of the dispatch table has no field {}", entry, field),
+            DispatchError::NonConstantField { entry, field } => write!(f, "field {} of entry {} of the dispatch table isn't a constant of the expected kind", field, entry),
+            DispatchError::HandlerNotFound(handler) => write!(f, "handler {:?} isn't defined in the project", handler),
+            DispatchError::LenParamOutOfRange(handler) => write!(f, "handler {:?} has no length parameter at the given index", handler),
+        }
+    }
+}
+
+impl std::error::Error for DispatchError {}
+
+/// Read the entries of the dispatch table described by `spec`.
+///
+/// Entries whose handler is null (such as a terminating sentinel entry) are
+/// skipped.
+pub fn read_dispatch_table(project: &Project, spec: &DispatchSpec) -> Result<Vec<DispatchEntry>, DispatchError> {
+    let (var, _) = project.all_global_vars()
+        .find(|(var, _)| var.name == Name::from(spec.table.as_str()))
+        .ok_or_else(|| DispatchError::TableNotFound(spec.table.clone()))?;
+    let elements = match &var.initializer {
+        None => return Err(DispatchError::NoInitializer(spec.table.clone())),
+        Some(Constant::Array { elements, .. }) => elements,
+        Some(_) => return Err(DispatchError::NotAnArrayOfStructs(spec.table.clone())),
+    };
+    let mut entries = vec![];
+    for (idx, element) in elements.iter().enumerate() {
+        let values = match element {
+            Constant::Struct { values, .. } => values,
+            _ => return Err(DispatchError::NotAnArrayOfStructs(spec.table.clone())),
+        };
+        let get_field = |field: usize| values.get(field).ok_or(DispatchError::FieldOutOfRange { entry: idx, field });
+        let int_field = |field: usize| match get_field(field)? {
+            Constant::Int { value, .. } => Ok(*value),
+            _ => Err(DispatchError::NonConstantField { entry: idx, field }),
+        };
+        let handler = match strip_bitcasts(get_field(spec.handler_field)?) {
+            Constant::Null(_) => continue,
+            Constant::GlobalReference { name: Name::Name(name), .. } => name.clone(),
+            _ => return Err(DispatchError::NonConstantField { entry: idx, field: spec.handler_field }),
+        };
+        entries.push(DispatchEntry {
+            opcode: int_field(spec.opcode_field)?,
+            handler,
+            min_len: int_field(spec.min_len_field)?,
+            max_len: int_field(spec.max_len_field)?,
+        });
+    }
+    Ok(entries)
+}
+
+fn strip_bitcasts(constant: &Constant) -> &Constant {
+    match constant {
+        Constant::BitCast(bitcast) => strip_bitcasts(&bitcast.operand),
+        constant => constant,
+    }
+}
+
+/// For each entry of the dispatch table described by `spec`, run
+/// [`find_violations()`](../checkers/fn.find_violations.html) on its handler,
+/// with the handler's length parameter constrained to the entry's bounds (in
+/// addition to any `Config.preconditions`). Returns a report for each entry,
+/// in table order.
+///
+/// Fails up front, without analyzing anything, if the table can't be read
+/// according to `spec`, or if any of its handlers is missing.
+pub fn analyze_dispatch_table<'p, B: Backend>(
+    project: &'p Project,
+    spec: &DispatchSpec,
+    config: Config<'p, B>,
+) -> Result<Vec<HandlerReport>, DispatchError> {
+    let entries = read_dispatch_table(project, spec)?;
+    for entry in &entries {
+        let (func, _) = project.get_func_by_name(&entry.handler).ok_or_else(|| DispatchError::HandlerNotFound(entry.handler.clone()))?;
+        if func.parameters.len() <= spec.len_param {
+            return Err(DispatchError::LenParamOutOfRange(entry.handler.clone()));
+        }
+    }
+    Ok(entries.into_iter().map(|entry| {
+        info!("Analyzing handler {:?} for opcode {:#x}", entry.handler, entry.opcode);
+        let mut entry_config = config.clone();
+        entry_config.preconditions.push(format!("${} >= {} && ${} <= {}", spec.len_param, entry.min_len, spec.len_param, entry.max_len));
+        let outcome = find_violations(&entry.handler, project, entry_config);
+        HandlerReport { entry, outcome }
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::checkers::DivisionByZero;
+    use crate::test_utils::*;
+    use llvm_ir::module::GlobalVariable;
+    use llvm_ir::{instruction, Function, Instruction, Type};
+    use std::rc::Rc;
+
+    /// `int name(char *buf, int len) { return 100 / (len - divisor); }`
+    fn handler(name: &str, divisor: u64) -> Function {
+        let mut func = blank_function(name, vec![Name::from("entry")]);
+        func.parameters.push(param(Name::Number(0), Type::pointer_to(Type::i8())));
+        func.parameters.push(param(Name::Number(1), Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            Instruction::Sub(instruction::Sub {
+                operand0: local(Name::Number(1), Type::i32()),
+                operand1: int_const(32, divisor),
+                dest: Name::from("d"),
+                debugloc: None,
+            }),
+            Instruction::UDiv(instruction::UDiv {
+                operand0: int_const(32, 100),
+                operand1: local("d", Type::i32()),
+                dest: Name::from("q"),
+                debugloc: None,
+            }),
+        ];
+        func.basic_blocks[0].term = ret(local("q", Type::i32()));
+        func
+    }
+
+    fn handler_ty() -> Type {
+        Type::pointer_to(Type::FuncType {
+            result_type: Box::new(Type::i32()),
+            param_types: vec![Type::pointer_to(Type::i8()), Type::i32()],
+            is_var_arg: false,
+        })
+    }
+
+    /// `struct { u8 opcode; int (*handler)(char *, int); int min_len; int max_len; }`
+    fn entry_ty() -> Type {
+        Type::StructType { element_types: vec![Type::i8(), handler_ty(), Type::i32(), Type::i32()], is_packed: false }
+    }
+
+    fn entry(opcode: u64, handler: Option<&str>, min_len: u64, max_len: u64) -> Constant {
+        let handler_ty = handler_ty();
+        Constant::Struct {
+            name: None,
+            values: vec![
+                Constant::Int { bits: 8, value: opcode },
+                match handler {
+                    Some(handler) => Constant::GlobalReference { name: Name::from(handler), ty: handler_ty },
+                    None => Constant::Null(handler_ty),
+                },
+                Constant::Int { bits: 32, value: min_len },
+                Constant::Int { bits: 32, value: max_len },
+            ],
+            is_packed: false,
+        }
+    }
+
+    /// Three handlers, in a table with a null sentinel entry. Only
+    /// `handle_write` can divide by zero, and only at its maximum length.
+    fn project(table: Option<Constant>) -> Project {
+        let commands = GlobalVariable {
+            is_constant: true,
+            alignment: 8,
+            ..global_var("commands", Type::pointer_to(Type::ArrayType { element_type: Box::new(entry_ty()), num_elements: 4 }), table)
+        };
+        project_with_globals("test_mod", vec![handler("handle_read", 64), handler("handle_write", 16), handler("handle_reset", 1)], vec![commands])
+    }
+
+    fn table() -> Constant {
+        Constant::Array {
+            element_type: entry_ty(),
+            elements: vec![
+                entry(0x01, Some("handle_read"), 1, 8),
+                entry(0x02, Some("handle_write"), 4, 16),
+                entry(0x03, Some("handle_reset"), 0, 0),
+                entry(0, None, 0, 0),
+            ],
+        }
+    }
+
+    fn spec() -> DispatchSpec {
+        DispatchSpec { table: "commands".to_owned(), opcode_field: 0, handler_field: 1, min_len_field: 2, max_len_field: 3, len_param: 1 }
+    }
+
+    #[test]
+    fn findings_per_opcode() {
+        let project = project(Some(table()));
+        let config: Config<BtorBackend> = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            ..Config::default()
+        };
+        let reports = analyze_dispatch_table(&project, &spec(), config).unwrap();
+        let opcodes: Vec<u64> = reports.iter().map(|report| report.entry.opcode).collect();
+        assert_eq!(opcodes, vec![0x01, 0x02, 0x03]);
+        for report in &reports {
+            let findings = &report.outcome.as_ref().unwrap().answer;
+            if report.entry.opcode == 0x02 {
+                assert_eq!(findings.len(), 1);
+                assert_eq!(findings[0].violation.witness[1], Some(16));
+            } else {
+                assert!(findings.is_empty(), "unexpected findings for opcode {}: {:?}", report.entry.opcode, findings);
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_specs() {
+        let config = || -> Config<BtorBackend> { Config::default() };
+        let project_without_initializer = project(None);
+        assert_eq!(analyze_dispatch_table(&project_without_initializer, &spec(), config()).unwrap_err(), DispatchError::NoInitializer("commands".to_owned()));
+        let project = project(Some(table()));
+        let missing = DispatchSpec { table: "cmds".to_owned(), ..spec() };
+        assert_eq!(analyze_dispatch_table(&project, &missing, config()).unwrap_err(), DispatchError::TableNotFound("cmds".to_owned()));
+        let out_of_range = DispatchSpec { max_len_field: 4, ..spec() };
+        assert_eq!(analyze_dispatch_table(&project, &out_of_range, config()).unwrap_err(), DispatchError::FieldOutOfRange { entry: 0, field: 4 });
+        let swapped = DispatchSpec { opcode_field: 1, handler_field: 0, ..spec() };
+        assert_eq!(analyze_dispatch_table(&project, &swapped, config()).unwrap_err(), DispatchError::NonConstantField { entry: 0, field: 0 });
+    }
+}
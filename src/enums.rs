@@ -0,0 +1,126 @@
+//! Enum types whose legal values are known, for constraining parameters to
+//! those values and naming them in reports; see
+//! [`Config.enum_params`](../config/struct.Config.html#structfield.enum_params).
+
+use crate::backend::BV;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An enum type, described by its enumerators
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EnumType {
+    /// Name of the type, e.g. `"enum color"`
+    pub name: String,
+    pub enumerators: Vec<Enumerator>,
+}
+
+/// One of the named values of an `EnumType`
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Enumerator {
+    pub name: String,
+    pub value: u64,
+}
+
+impl Enumerator {
+    pub fn new(name: impl Into<String>, value: u64) -> Self {
+        Self { name: name.into(), value }
+    }
+}
+
+impl EnumType {
+    pub fn new(name: impl Into<String>, enumerators: Vec<Enumerator>) -> Self {
+        Self { name: name.into(), enumerators }
+    }
+
+    /// Is this a set of bitflags? That is, are all of its (nonzero)
+    /// enumerators powers of two, with at least three of them? (With fewer,
+    /// e.g. `{ A = 0, B = 1, C = 2 }`, it's more likely an ordinary enum.)
+    ///
+    /// A bitflags value may be any combination of the flags, not just one of
+    /// the enumerators.
+    pub fn is_flags(&self) -> bool {
+        let flags = self.enumerators.iter().filter(|e| e.value != 0);
+        flags.clone().count() >= 3 && flags.clone().all(|e| e.value.is_power_of_two())
+    }
+
+    /// Is `value` a legal value of this type?
+    pub fn contains(&self, value: u64) -> bool {
+        if self.is_flags() {
+            value & !self.all_flags() == 0
+        } else {
+            self.enumerators.iter().any(|e| e.value == value)
+        }
+    }
+
+    /// Describe a value of this type by its enumerator name(s), e.g. `RED`,
+    /// or `READ | WRITE` for bitflags. Returns `None` if `value` isn't legal.
+    pub fn describe(&self, value: u64) -> Option<String> {
+        if !self.contains(value) {
+            None
+        } else if let Some(e) = self.enumerators.iter().find(|e| e.value == value) {
+            Some(e.name.clone())
+        } else {
+            // a combination of flags
+            let names: Vec<&str> = self.enumerators.iter()
+                .filter(|e| e.value != 0 && value & e.value != 0)
+                .map(|e| e.name.as_str())
+                .collect();
+            Some(names.join(" | "))
+        }
+    }
+
+    /// The OR of all the enumerators
+    fn all_flags(&self) -> u64 {
+        self.enumerators.iter().fold(0, |acc, e| acc | e.value)
+    }
+
+    /// Get a 1-bit `BV` which is true exactly when `bv` holds a legal value of
+    /// this type
+    pub(crate) fn membership_bv<V: BV>(&self, bv: &V) -> V {
+        let solver = bv.get_solver();
+        let width = bv.get_width();
+        if self.is_flags() {
+            bv.and(&V::from_u64(solver.clone(), !self.all_flags(), width))._eq(&V::zero(solver, width))
+        } else {
+            self.enumerators.iter()
+                .map(|e| bv._eq(&V::from_u64(solver.clone(), e.value, width)))
+                .reduce(|a, b| a.or(&b))
+                .unwrap_or_else(|| V::from_bool(solver, false))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color() -> EnumType {
+        EnumType::new("enum color", vec![Enumerator::new("RED", 0), Enumerator::new("GREEN", 1), Enumerator::new("BLUE", 2)])
+    }
+
+    fn perms() -> EnumType {
+        EnumType::new("enum perms", vec![Enumerator::new("NONE", 0), Enumerator::new("READ", 1), Enumerator::new("WRITE", 2), Enumerator::new("EXEC", 4)])
+    }
+
+    #[test]
+    fn plain_enum() {
+        let color = color();
+        assert!(!color.is_flags());
+        assert!(color.contains(2));
+        assert!(!color.contains(3));
+        assert_eq!(color.describe(1), Some("GREEN".to_owned()));
+        assert_eq!(color.describe(0x7f3b9c21), None);
+    }
+
+    #[test]
+    fn bitflags() {
+        let perms = perms();
+        assert!(perms.is_flags());
+        assert!(perms.contains(7));
+        assert!(!perms.contains(8));
+        assert_eq!(perms.describe(0), Some("NONE".to_owned()));
+        assert_eq!(perms.describe(5), Some("READ | EXEC".to_owned()));
+    }
+}
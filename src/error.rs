@@ -13,8 +13,9 @@ pub enum Error {
     /// This error type is used internally, but (by default) isn't exposed to consumers of `ExecutionManager`;
     /// see [`Config.squash_unsats`](config/struct.Config.html#structfield.squash_unsats).
     Unsat,
-    /// The current path has exceeded the configured `loop_bound` (see [`Config`](config/struct.Config.html)).
-    /// (The `usize` here indicates the value of the configured `loop_bound`.)
+    /// The current path has exceeded the configured `loop_bound` or
+    /// `max_loop_iterations` (see [`Config`](config/struct.Config.html)).
+    /// (The `usize` here indicates the value of the setting which was exceeded.)
     LoopBoundExceeded(usize),
     /// The current path has attempted to dereference a null pointer (or
     /// more precisely, a pointer for which `NULL` is a possible value)
@@ -38,6 +39,40 @@ pub enum Error {
     /// function return type: for instance, a value of the wrong size.
     /// The `String` here just describes the error
     HookReturnValueMismatch(String),
+    /// An instruction referred to an LLVM value for which no `BV` has been
+    /// created on this path. This indicates a bug (in `haybale` or in how it was
+    /// driven), but only breaks the current path.
+    UnmappedValue {
+        /// The name of the LLVM value
+        name: String,
+        /// The function the value was looked up in
+        funcname: String,
+    },
+    /// An instruction had an `undef` operand; only reported with
+    /// [`UndefValues::Error`](config/enum.UndefValues.html#variant.Error)
+    UndefUse,
+    /// The current path may `free()` an allocation which is no longer live:
+    /// one it has already freed, or a stack slot which went out of scope.
+    /// Only reported with
+    /// [`Config.detect_double_frees`](config/struct.Config.html#structfield.detect_double_frees).
+    DoubleFree {
+        /// The start address of the allocation
+        addr: u64,
+        /// Where the allocation was first freed, e.g. `free at x.c:10`
+        first_free: String,
+        /// Where it is freed again
+        second_free: String,
+    },
+    /// The current path `free()`s an address which isn't the start of a heap
+    /// allocation: for instance, a pointer into the middle of an allocation,
+    /// or to a stack slot or global variable. Only reported with
+    /// [`Config.detect_double_frees`](config/struct.Config.html#structfield.detect_double_frees).
+    InvalidFree {
+        /// The address being freed
+        addr: u64,
+        /// Where it is freed
+        site: String,
+    },
     /// Some kind of error which doesn't fall into one of the above categories.
     /// The `String` here describes the error
     OtherError(String),
@@ -49,7 +84,7 @@ impl fmt::Display for Error {
             Error::Unsat =>
                 write!(f, "`Unsat`: the current state or path is unsat"),
             Error::LoopBoundExceeded(bound) =>
-                write!(f, "`LoopBoundExceeded`: the current path has exceeded the configured `loop_bound`, which was {}", bound),
+                write!(f, "`LoopBoundExceeded`: the current path has exceeded the configured `loop_bound` or `max_loop_iterations`, which was {}", bound),
             Error::NullPointerDereference =>
                 write!(f, "`NullPointerDereference`: the current path has attempted to dereference a null pointer"),
             Error::FunctionNotFound(funcname) =>
@@ -66,6 +101,14 @@ impl fmt::Display for Error {
                 write!(f, "`FailedToResolveFunctionPointer`: Can't resolve a symbolically-valued function pointer, because one possible solution for it ({:#x}) points to something that's not a function", solution),
             Error::HookReturnValueMismatch(details) =>
                 write!(f, "`HookReturnValueMismatch`: {}", details),
+            Error::UnmappedValue { name, funcname } =>
+                write!(f, "`UnmappedValue`: failed to find a value for {} in function {:?}", name, funcname),
+            Error::UndefUse =>
+                write!(f, "`UndefUse`: encountered an instruction with an `undef` operand"),
+            Error::DoubleFree { addr, first_free, second_free } =>
+                write!(f, "`DoubleFree`: the allocation at 0x{:x} was freed by {}, and again by {}", addr, first_free, second_free),
+            Error::InvalidFree { addr, site } =>
+                write!(f, "`InvalidFree`: 0x{:x}, which is not the start of a heap allocation, is freed by {}", addr, site),
             Error::OtherError(details) =>
                 write!(f, "`OtherError`: {}", details),
         }
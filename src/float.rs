@@ -0,0 +1,639 @@
+//! Bit-precise IEEE 754 floating-point operations.
+//!
+//! Floating-point values are represented the same way as everywhere else in
+//! `haybale`: as `BV`s holding their bit patterns. Boolector has no
+//! floating-point theory, so the operations here are built out of bitvector
+//! operations, following the usual softfloat approach: unpack the operands,
+//! compute an exact (or exact-enough) intermediate result, and round it once.
+
+use crate::backend::BV;
+use crate::config::RoundingMode;
+use crate::error::*;
+use llvm_ir::constant::Float;
+use llvm_ir::types::FPType;
+use llvm_ir::FPPredicate;
+use std::cmp::max;
+
+/// Width of the (signed) `BV`s holding unbiased exponents of intermediate
+/// results. Wide enough for the products and quotients of any supported
+/// format, even after normalizing subnormals.
+const EXP_WIDTH: u32 = 24;
+
+/// An IEEE 754 binary interchange format
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct FloatFormat {
+    /// Number of bits in the exponent field
+    pub exp_bits: u32,
+    /// Number of bits of precision, including the implicit leading bit
+    pub precision: u32,
+}
+
+impl FloatFormat {
+    pub const HALF: Self = Self { exp_bits: 5, precision: 11 };
+    pub const SINGLE: Self = Self { exp_bits: 8, precision: 24 };
+    pub const DOUBLE: Self = Self { exp_bits: 11, precision: 53 };
+    pub const QUAD: Self = Self { exp_bits: 15, precision: 113 };
+
+    /// Get the format of the given LLVM floating-point type.
+    /// `x86_fp80` and `ppc_fp128` aren't IEEE interchange formats, and aren't
+    /// supported.
+    pub fn from_fptype(fpt: FPType) -> Result<Self> {
+        match fpt {
+            FPType::Half => Ok(Self::HALF),
+            FPType::Single => Ok(Self::SINGLE),
+            FPType::Double => Ok(Self::DOUBLE),
+            FPType::FP128 => Ok(Self::QUAD),
+            FPType::X86_FP80 | FPType::PPC_FP128 => Err(Error::UnsupportedInstruction(format!("floating-point operations on {:?}", fpt))),
+        }
+    }
+
+    /// Total width of values of this format, in bits
+    pub fn width(self) -> u32 {
+        self.exp_bits + self.precision
+    }
+
+    fn bias(self) -> i64 {
+        (1 << (self.exp_bits - 1)) - 1
+    }
+
+    /// Exponent of the smallest normal values (and of the subnormals)
+    fn emin(self) -> i64 {
+        1 - self.bias()
+    }
+
+    /// Exponent of the largest finite values
+    fn emax(self) -> i64 {
+        self.bias()
+    }
+}
+
+/// Get the bit pattern of the floating-point constant, as a `BV`.
+/// Only `float` and `double` constants are supported, as `llvm-ir` doesn't
+/// provide the values of other kinds.
+pub fn float_const<V: BV>(solver: V::SolverRef, f: &Float) -> Result<V> {
+    match f {
+        Float::Single(f) => Ok(V::from_u32(solver, f.to_bits(), 32)),
+        Float::Double(f) => Ok(V::from_u64(solver, f.to_bits(), 64)),
+        f => Err(Error::UnsupportedInstruction(format!("floating-point constant {:?}", f))),
+    }
+}
+
+/// The fields of an unpacked floating-point value
+struct Unpacked<V> {
+    /// 1 bit, set for negative values
+    sign: V,
+    is_nan: V,
+    is_inf: V,
+    is_zero: V,
+    /// Unbiased exponent, `EXP_WIDTH` bits, signed. Meaningless for NaNs and
+    /// infinities.
+    exp: V,
+    /// `precision` bits, including the implicit leading bit (which is `0` for
+    /// zeroes and subnormals). The magnitude of the value is
+    /// `sig * 2^(exp - (precision - 1))`.
+    sig: V,
+}
+
+fn unpack<V: BV>(x: &V, fmt: FloatFormat) -> Unpacked<V> {
+    let solver = x.get_solver();
+    let width = fmt.width();
+    let frac_bits = fmt.precision - 1;
+    let exp_field = x.slice(width - 2, frac_bits);
+    let frac = x.slice(frac_bits - 1, 0);
+    let exp_zero = exp_field._eq(&V::zero(solver.clone(), fmt.exp_bits));
+    let exp_ones = exp_field._eq(&V::ones(solver.clone(), fmt.exp_bits));
+    let frac_zero = frac._eq(&V::zero(solver.clone(), frac_bits));
+    // subnormals have the same exponent as the smallest normals
+    let biased = exp_zero.cond_bv(&V::one(solver.clone(), fmt.exp_bits), &exp_field);
+    Unpacked {
+        sign: x.slice(width - 1, width - 1),
+        is_nan: exp_ones.and(&frac_zero.not()),
+        is_inf: exp_ones.and(&frac_zero),
+        is_zero: exp_zero.and(&frac_zero),
+        exp: biased.zero_extend_to_bits(EXP_WIDTH).sub(&V::from_i64(solver, fmt.bias(), EXP_WIDTH)),
+        sig: exp_zero.not().concat(&frac),
+    }
+}
+
+/// The quiet NaN we produce. LLVM doesn't specify NaN payloads or signs.
+fn nan<V: BV>(solver: V::SolverRef, fmt: FloatFormat) -> V {
+    V::zero(solver.clone(), 1)
+        .concat(&V::ones(solver.clone(), fmt.exp_bits + 1))
+        .concat(&V::zero(solver, fmt.precision - 2))
+}
+
+fn infinity<V: BV>(sign: &V, fmt: FloatFormat) -> V {
+    let solver = sign.get_solver();
+    sign.concat(&V::ones(solver.clone(), fmt.exp_bits)).concat(&V::zero(solver, fmt.precision - 1))
+}
+
+fn zero<V: BV>(sign: &V, fmt: FloatFormat) -> V {
+    sign.concat(&V::zero(sign.get_solver(), fmt.width() - 1))
+}
+
+fn max_finite<V: BV>(sign: &V, fmt: FloatFormat) -> V {
+    let solver = sign.get_solver();
+    sign.concat(&V::ones(solver.clone(), fmt.exp_bits - 1))
+        .concat(&V::zero(solver.clone(), 1))
+        .concat(&V::ones(solver, fmt.precision - 1))
+}
+
+/// The number of leading zeroes in `m`, as an `EXP_WIDTH`-bit `BV`
+fn leading_zeros<V: BV>(m: &V) -> V {
+    let solver = m.get_solver();
+    let width = m.get_width();
+    let mut lz = V::from_u32(solver.clone(), width, EXP_WIDTH);
+    // from the least significant bit up, so that the highest set bit wins
+    for i in 0 .. width {
+        lz = m.slice(i, i).cond_bv(&V::from_u32(solver.clone(), width - 1 - i, EXP_WIDTH), &lz);
+    }
+    lz
+}
+
+/// Clamp the signed `EXP_WIDTH`-bit `amount` to `0 ..= limit`, and resize it to
+/// `limit` bits, for use as a shift amount on a `limit`-bit `BV`
+fn shift_amount<V: BV>(amount: &V, limit: u32) -> V {
+    let solver = amount.get_solver();
+    let zero = V::zero(solver.clone(), EXP_WIDTH);
+    let limit_bv = V::from_u32(solver, limit, EXP_WIDTH);
+    amount.slt(&zero).cond_bv(&zero, &amount.sgt(&limit_bv).cond_bv(&limit_bv, amount))
+        .zero_extend_or_truncate_to_bits(limit)
+}
+
+/// Shift `m` right by `amount` (of the same width as `m`), also returning
+/// whether any set bits were shifted out
+fn shift_right_sticky<V: BV>(m: &V, amount: &V) -> (V, V) {
+    let solver = m.get_solver();
+    let width = m.get_width();
+    let lost_bits = m.and(&V::ones(solver.clone(), width).sll(amount).not());
+    (m.srl(amount), lost_bits._ne(&V::zero(solver, width)))
+}
+
+/// Round the value `(-1)^sign * m / 2^(width(m) - 1) * 2^exp` to the given
+/// format, and pack it. `m` must have at least `precision + 2` bits. `sticky`
+/// indicates whether the exact value has further set bits below `m`. If `m` is
+/// zero (and `sticky` is not set), the result is a zero of the given sign.
+fn round<V: BV>(sign: &V, exp: &V, m: &V, sticky: &V, fmt: FloatFormat, rm: RoundingMode) -> V {
+    let solver = m.get_solver();
+    let width = m.get_width();
+    let p = fmt.precision;
+    assert!(width >= p + 2);
+
+    // normalize, so that the leading bit is set
+    let lz = leading_zeros(m);
+    let m = m.sll(&lz.zero_extend_or_truncate_to_bits(width));
+    let exp = exp.sub(&lz);
+
+    // below the normal range, we keep fewer bits of precision
+    let emin = V::from_i64(solver.clone(), fmt.emin(), EXP_WIDTH);
+    let tiny = exp.slt(&emin);
+    let (m, lost) = shift_right_sticky(&m, &shift_amount(&emin.sub(&exp), width));
+    let exp = tiny.cond_bv(&emin, &exp);
+
+    let kept = m.slice(width - 1, width - p);
+    let guard = m.slice(width - p - 1, width - p - 1);
+    let rest = m.slice(width - p - 2, 0)._ne(&V::zero(solver.clone(), width - p - 1)).or(&lost).or(sticky);
+    let inexact = guard.or(&rest);
+    let round_up = match rm {
+        RoundingMode::NearestTiesToEven => guard.and(&rest.or(&kept.slice(0, 0))),
+        RoundingMode::NearestTiesToAway => guard,
+        RoundingMode::TowardPositive => sign.not().and(&inexact),
+        RoundingMode::TowardNegative => sign.and(&inexact),
+        RoundingMode::TowardZero => V::zero(solver.clone(), 1),
+    };
+    let rounded = kept.zext(1).add(&round_up.zext(p));
+    let carry = rounded.slice(p, p);
+    let sig = carry.cond_bv(&rounded.slice(p, 1), &rounded.slice(p - 1, 0));
+    let exp = carry.cond_bv(&exp.inc(), &exp);
+
+    // a value which is still subnormal after rounding has the zero exponent
+    let biased = exp.add(&V::from_i64(solver.clone(), fmt.bias(), EXP_WIDTH)).slice(fmt.exp_bits - 1, 0);
+    let exp_field = sig.slice(p - 1, p - 1).cond_bv(&biased, &V::zero(solver.clone(), fmt.exp_bits));
+    let packed = sign.concat(&exp_field).concat(&sig.slice(p - 2, 0));
+
+    let overflowed = match rm {
+        RoundingMode::NearestTiesToEven | RoundingMode::NearestTiesToAway => infinity(sign, fmt),
+        RoundingMode::TowardPositive => sign.cond_bv(&max_finite(sign, fmt), &infinity(sign, fmt)),
+        RoundingMode::TowardNegative => sign.cond_bv(&infinity(sign, fmt), &max_finite(sign, fmt)),
+        RoundingMode::TowardZero => max_finite(sign, fmt),
+    };
+    exp.sgt(&V::from_i64(solver, fmt.emax(), EXP_WIDTH)).cond_bv(&overflowed, &packed)
+}
+
+/// `a + b`
+pub fn fadd<V: BV>(a: &V, b: &V, fmt: FloatFormat, rm: RoundingMode) -> V {
+    let solver = a.get_solver();
+    let p = fmt.precision;
+    let ua = unpack(a, fmt);
+    let ub = unpack(b, fmt);
+
+    // order the operands so that `x` has the larger magnitude. For non-NaNs,
+    // magnitudes compare the same as their bit patterns do.
+    let width = fmt.width();
+    let swap = a.slice(width - 2, 0).ult(&b.slice(width - 2, 0));
+    let pick = |fa: &V, fb: &V| (swap.cond_bv(fb, fa), swap.cond_bv(fa, fb));
+    let (x_sign, y_sign) = pick(&ua.sign, &ub.sign);
+    let (x_exp, y_exp) = pick(&ua.exp, &ub.exp);
+    let (x_sig, y_sig) = pick(&ua.sig, &ub.sig);
+
+    // align `y` with `x`, keeping guard, round, and sticky bits, plus a bit
+    // on top for a carry
+    let extra = 3;
+    let mw = p + extra + 1;
+    let xs = x_sig.zext(1).concat(&V::zero(solver.clone(), extra));
+    let ys = y_sig.zext(1).concat(&V::zero(solver.clone(), extra));
+    let (ys, lost) = shift_right_sticky(&ys, &shift_amount(&x_exp.sub(&y_exp), mw));
+    let ys = ys.or(&lost.zext(mw - 1));
+    let same_sign = x_sign._eq(&y_sign);
+    let sum = same_sign.cond_bv(&xs.add(&ys), &xs.sub(&ys));
+    let result = round(&x_sign, &x_exp.inc(), &sum, &V::zero(solver.clone(), 1), fmt, rm);
+
+    // an exact zero is positive, unless both operands are negative (or we
+    // round toward negative)
+    let zero_sign = same_sign.cond_bv(&x_sign, &V::from_bool(solver.clone(), rm == RoundingMode::TowardNegative));
+    let result = sum._eq(&V::zero(solver.clone(), mw)).cond_bv(&zero(&zero_sign, fmt), &result);
+
+    let is_nan = ua.is_nan.or(&ub.is_nan).or(&ua.is_inf.and(&ub.is_inf).and(&ua.sign._ne(&ub.sign)));
+    let result = ua.is_inf.cond_bv(a, &ub.is_inf.cond_bv(b, &result));
+    is_nan.cond_bv(&nan(solver, fmt), &result)
+}
+
+/// `a - b`
+pub fn fsub<V: BV>(a: &V, b: &V, fmt: FloatFormat, rm: RoundingMode) -> V {
+    fadd(a, &fneg(b, fmt), fmt, rm)
+}
+
+/// `a * b`
+pub fn fmul<V: BV>(a: &V, b: &V, fmt: FloatFormat, rm: RoundingMode) -> V {
+    let solver = a.get_solver();
+    let p = fmt.precision;
+    let ua = unpack(a, fmt);
+    let ub = unpack(b, fmt);
+    let sign = ua.sign.xor(&ub.sign);
+
+    // the product of the significands is exact in `2p` bits
+    let product = ua.sig.zext(p).mul(&ub.sig.zext(p));
+    let result = round(&sign, &ua.exp.add(&ub.exp).inc(), &product, &V::zero(solver.clone(), 1), fmt, rm);
+
+    let is_nan = ua.is_nan.or(&ub.is_nan).or(&ua.is_inf.and(&ub.is_zero)).or(&ua.is_zero.and(&ub.is_inf));
+    let result = ua.is_zero.or(&ub.is_zero).cond_bv(&zero(&sign, fmt), &result);
+    let result = ua.is_inf.or(&ub.is_inf).cond_bv(&infinity(&sign, fmt), &result);
+    is_nan.cond_bv(&nan(solver, fmt), &result)
+}
+
+/// `a * b + c`, with a single rounding
+pub fn ffma<V: BV>(a: &V, b: &V, c: &V, fmt: FloatFormat, rm: RoundingMode) -> V {
+    let solver = a.get_solver();
+    let p = fmt.precision;
+    let ua = unpack(a, fmt);
+    let ub = unpack(b, fmt);
+    let uc = unpack(c, fmt);
+    let product_sign = ua.sign.xor(&ub.sign);
+
+    // the exact product, and `c` widened to match, both in `2p` bits and
+    // normalized, so that their magnitudes compare by exponent, then by
+    // significand
+    let (product, product_exp) = normalize(&ua.sig.zext(p).mul(&ub.sig.zext(p)), &ua.exp.add(&ub.exp).inc());
+    let (addend, addend_exp) = normalize(&uc.sig.concat(&V::zero(solver.clone(), p)), &uc.exp);
+
+    // from here on, this is `fadd` on the wider values
+    let swap = product_exp.slt(&addend_exp).or(&product_exp._eq(&addend_exp).and(&product.ult(&addend)));
+    let pick = |fa: &V, fb: &V| (swap.cond_bv(fb, fa), swap.cond_bv(fa, fb));
+    let (x_sign, y_sign) = pick(&product_sign, &uc.sign);
+    let (x_exp, y_exp) = pick(&product_exp, &addend_exp);
+    let (x_sig, y_sig) = pick(&product, &addend);
+
+    let extra = 3;
+    let mw = 2 * p + extra + 1;
+    let xs = x_sig.zext(1).concat(&V::zero(solver.clone(), extra));
+    let ys = y_sig.zext(1).concat(&V::zero(solver.clone(), extra));
+    let (ys, lost) = shift_right_sticky(&ys, &shift_amount(&x_exp.sub(&y_exp), mw));
+    let ys = ys.or(&lost.zext(mw - 1));
+    let same_sign = x_sign._eq(&y_sign);
+    let sum = same_sign.cond_bv(&xs.add(&ys), &xs.sub(&ys));
+    let result = round(&x_sign, &x_exp.inc(), &sum, &V::zero(solver.clone(), 1), fmt, rm);
+    let zero_sign = same_sign.cond_bv(&x_sign, &V::from_bool(solver.clone(), rm == RoundingMode::TowardNegative));
+    let result = sum._eq(&V::zero(solver.clone(), mw)).cond_bv(&zero(&zero_sign, fmt), &result);
+
+    // with a zero operand, the exact result is just the other one (and
+    // `fadd` and `fmul` get the signs of zeroes right)
+    let product_is_zero = ua.is_zero.or(&ub.is_zero);
+    let result = uc.is_zero.cond_bv(&fmul(a, b, fmt, rm), &result);
+    let result = product_is_zero.cond_bv(&fadd(&zero(&product_sign, fmt), c, fmt, rm), &result);
+
+    let product_is_inf = ua.is_inf.or(&ub.is_inf);
+    let is_nan = ua.is_nan.or(&ub.is_nan).or(&uc.is_nan)
+        .or(&product_is_inf.and(&product_is_zero))
+        .or(&product_is_inf.and(&uc.is_inf).and(&product_sign._ne(&uc.sign)));
+    let result = uc.is_inf.cond_bv(c, &result);
+    let result = product_is_inf.cond_bv(&infinity(&product_sign, fmt), &result);
+    is_nan.cond_bv(&nan(solver, fmt), &result)
+}
+
+/// Shift the (nonzero) significand left until its leading bit is set,
+/// adjusting the exponent to match
+fn normalize<V: BV>(sig: &V, exp: &V) -> (V, V) {
+    let lz = leading_zeros(sig);
+    (sig.sll(&lz.zero_extend_or_truncate_to_bits(sig.get_width())), exp.sub(&lz))
+}
+
+/// `a / b`
+pub fn fdiv<V: BV>(a: &V, b: &V, fmt: FloatFormat, rm: RoundingMode) -> V {
+    let solver = a.get_solver();
+    let p = fmt.precision;
+    let ua = unpack(a, fmt);
+    let ub = unpack(b, fmt);
+    let sign = ua.sign.xor(&ub.sign);
+
+    // with both significands normalized, the quotient of these has at least
+    // `p + 2` significant bits, and the remainder tells us whether it's exact
+    let (sig_a, exp_a) = normalize(&ua.sig, &ua.exp);
+    let (sig_b, exp_b) = normalize(&ub.sig, &ub.exp);
+    let width = 2 * p + 3;
+    let numerator = sig_a.concat(&V::zero(solver.clone(), p + 2)).zext(1);
+    let denominator = sig_b.zext(p + 3);
+    let quotient = numerator.udiv(&denominator);
+    let sticky = numerator.urem(&denominator)._ne(&V::zero(solver.clone(), width));
+    let exp = exp_a.sub(&exp_b).add(&V::from_u32(solver.clone(), p, EXP_WIDTH));
+    let result = round(&sign, &exp, &quotient, &sticky, fmt, rm);
+
+    let is_nan = ua.is_nan.or(&ub.is_nan).or(&ua.is_zero.and(&ub.is_zero)).or(&ua.is_inf.and(&ub.is_inf));
+    let result = ua.is_zero.or(&ub.is_inf).cond_bv(&zero(&sign, fmt), &result);
+    let result = ua.is_inf.or(&ub.is_zero).cond_bv(&infinity(&sign, fmt), &result);
+    is_nan.cond_bv(&nan(solver, fmt), &result)
+}
+
+/// `-a`. Like LLVM's `fneg`, this just flips the sign bit, even of NaNs.
+pub fn fneg<V: BV>(a: &V, fmt: FloatFormat) -> V {
+    let width = fmt.width();
+    a.xor(&V::one(a.get_solver(), 1).concat(&V::zero(a.get_solver(), width - 1)))
+}
+
+/// Compare `a` and `b` with the given predicate, producing a 1-bit `BV`.
+/// The unordered predicates (and `une`) hold whenever either operand is a NaN;
+/// the ordered ones don't.
+pub fn fcmp<V: BV>(a: &V, b: &V, fmt: FloatFormat, predicate: FPPredicate) -> V {
+    let solver = a.get_solver();
+    let width = fmt.width();
+    let ua = unpack(a, fmt);
+    let ub = unpack(b, fmt);
+    let unordered = ua.is_nan.or(&ub.is_nan);
+    let ordered = unordered.not();
+    let both_zero = ua.is_zero.and(&ub.is_zero);
+    let eq = a._eq(b).or(&both_zero);
+    // mapping negative values to their complement, and flipping the sign bit
+    // of positive ones, makes their order the unsigned order of the results
+    let sign_bit = V::one(solver.clone(), 1).concat(&V::zero(solver.clone(), width - 1));
+    let key = |x: &V, sign: &V| x.xor(&sign.cond_bv(&V::ones(solver.clone(), width), &sign_bit));
+    let (key_a, key_b) = (key(a, &ua.sign), key(b, &ub.sign));
+    let lt = key_a.ult(&key_b).and(&both_zero.not());
+    let gt = key_a.ugt(&key_b).and(&both_zero.not());
+    match predicate {
+        FPPredicate::False => V::zero(solver, 1),
+        FPPredicate::OEQ => ordered.and(&eq),
+        FPPredicate::OGT => ordered.and(&gt),
+        FPPredicate::OGE => ordered.and(&gt.or(&eq)),
+        FPPredicate::OLT => ordered.and(&lt),
+        FPPredicate::OLE => ordered.and(&lt.or(&eq)),
+        FPPredicate::ONE => ordered.and(&eq.not()),
+        FPPredicate::ORD => ordered,
+        FPPredicate::UNO => unordered,
+        FPPredicate::UEQ => unordered.or(&eq),
+        FPPredicate::UGT => unordered.or(&gt),
+        FPPredicate::UGE => unordered.or(&gt).or(&eq),
+        FPPredicate::ULT => unordered.or(&lt),
+        FPPredicate::ULE => unordered.or(&lt).or(&eq),
+        FPPredicate::UNE => unordered.or(&eq.not()),
+        FPPredicate::True => V::one(solver, 1),
+    }
+}
+
+/// Convert `a` to an integer of the given width, rounding toward zero, as
+/// LLVM's `fptosi` and `fptoui` do. For NaNs, infinities, and values out of
+/// range of the integer type, LLVM gives a poison value; we give some
+/// arbitrary (but deterministic) value.
+pub fn fp_to_int<V: BV>(a: &V, fmt: FloatFormat, bits: u32) -> V {
+    let solver = a.get_solver();
+    let p = fmt.precision;
+    let ua = unpack(a, fmt);
+    // the magnitude is `sig * 2^(exp - (p - 1))`
+    let width = bits + p + 1;
+    let sig = ua.sig.zero_extend_to_bits(width);
+    let shift = ua.exp.sub(&V::from_u32(solver.clone(), p - 1, EXP_WIDTH));
+    let magnitude = shift.slt(&V::zero(solver.clone(), EXP_WIDTH)).cond_bv(
+        &sig.srl(&shift_amount(&shift.neg(), width)),
+        &sig.sll(&shift_amount(&shift, width)),
+    );
+    ua.sign.cond_bv(&magnitude.neg(), &magnitude).slice(bits - 1, 0)
+}
+
+/// Convert the integer `a` to the given format, treating it as signed or
+/// unsigned, as LLVM's `sitofp` and `uitofp` do
+pub fn int_to_fp<V: BV>(a: &V, signed: bool, fmt: FloatFormat, rm: RoundingMode) -> V {
+    let solver = a.get_solver();
+    let bits = a.get_width();
+    let (sign, magnitude) = if signed {
+        let sign = a.slice(bits - 1, bits - 1);
+        // for the most negative value, the negation is the same bits, which
+        // is the right magnitude when treated as unsigned
+        (sign.clone(), sign.cond_bv(&a.neg(), a))
+    } else {
+        (V::zero(solver.clone(), 1), a.clone())
+    };
+    let width = max(bits, fmt.precision) + 2;
+    round(&sign, &V::from_u32(solver.clone(), width - 1, EXP_WIDTH), &magnitude.zero_extend_to_bits(width), &V::zero(solver, 1), fmt, rm)
+}
+
+/// Convert `a` from one format to another, as LLVM's `fpext` and `fptrunc` do
+pub fn fp_convert<V: BV>(a: &V, from: FloatFormat, to: FloatFormat, rm: RoundingMode) -> V {
+    let solver = a.get_solver();
+    let ua = unpack(a, from);
+    let width = max(from.precision, to.precision) + 2;
+    let m = ua.sig.concat(&V::zero(solver.clone(), width - from.precision));
+    let result = round(&ua.sign, &ua.exp, &m, &V::zero(solver.clone(), 1), to, rm);
+    let result = ua.is_zero.cond_bv(&zero(&ua.sign, to), &result);
+    let result = ua.is_inf.cond_bv(&infinity(&ua.sign, to), &result);
+    ua.is_nan.cond_bv(&nan(solver, to), &result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SolverRef;
+    use crate::solver_utils::max_possible_solution_for_bv_as_u64;
+    use boolector::Btor;
+    use std::rc::Rc;
+
+    type BV = <Rc<Btor> as SolverRef>::BV;
+
+    const RNE: RoundingMode = RoundingMode::NearestTiesToEven;
+
+    fn eval(bv: &BV) -> u64 {
+        max_possible_solution_for_bv_as_u64(bv.get_btor(), bv).unwrap().expect("Expected a solution")
+    }
+
+    fn f32_bv(btor: &Rc<Btor>, f: f32) -> BV {
+        BV::from_u32(btor.clone(), f.to_bits(), 32)
+    }
+
+    fn f64_bv(btor: &Rc<Btor>, f: f64) -> BV {
+        BV::from_u64(btor.clone(), f.to_bits(), 64)
+    }
+
+    /// Check that the result is the native result, treating all NaNs as equal
+    fn assert_f32_eq(result: &BV, expected: f32) {
+        let result = f32::from_bits(eval(result) as u32);
+        assert!(result.to_bits() == expected.to_bits() || (result.is_nan() && expected.is_nan()), "got {:?}, expected {:?}", result, expected);
+    }
+
+    fn assert_f64_eq(result: &BV, expected: f64) {
+        let result = f64::from_bits(eval(result));
+        assert!(result.to_bits() == expected.to_bits() || (result.is_nan() && expected.is_nan()), "got {:?}, expected {:?}", result, expected);
+    }
+
+    const INTERESTING_F32S: &[f32] = &[
+        0.0, -0.0, 1.0, -1.0, 1.5, 0.1, 3.0, 1e-40, -1e-40, 1.1754942e-38, f32::MIN_POSITIVE,
+        f32::MAX, -f32::MAX, 16_777_217.0, f32::INFINITY, f32::NEG_INFINITY, f32::NAN,
+    ];
+
+    const INTERESTING_F64S: &[f64] = &[
+        0.0, -0.0, 1.0, -1.0, 0.1, 1.0 / 3.0, 5e-324, -2.5e-310, f64::MIN_POSITIVE,
+        f64::MAX, -1e308, 9_007_199_254_740_993.0, f64::INFINITY, f64::NEG_INFINITY, f64::NAN,
+    ];
+
+    #[test]
+    fn arithmetic_matches_native() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        for &a in INTERESTING_F32S {
+            for &b in INTERESTING_F32S {
+                let (x, y) = (f32_bv(&btor, a), f32_bv(&btor, b));
+                assert_f32_eq(&fadd(&x, &y, FloatFormat::SINGLE, RNE), a + b);
+                assert_f32_eq(&fsub(&x, &y, FloatFormat::SINGLE, RNE), a - b);
+                assert_f32_eq(&fmul(&x, &y, FloatFormat::SINGLE, RNE), a * b);
+                assert_f32_eq(&fdiv(&x, &y, FloatFormat::SINGLE, RNE), a / b);
+            }
+            assert_f32_eq(&fneg(&f32_bv(&btor, a), FloatFormat::SINGLE), -a);
+        }
+        for &a in INTERESTING_F64S {
+            for &b in INTERESTING_F64S {
+                let (x, y) = (f64_bv(&btor, a), f64_bv(&btor, b));
+                assert_f64_eq(&fadd(&x, &y, FloatFormat::DOUBLE, RNE), a + b);
+                assert_f64_eq(&fsub(&x, &y, FloatFormat::DOUBLE, RNE), a - b);
+                assert_f64_eq(&fmul(&x, &y, FloatFormat::DOUBLE, RNE), a * b);
+                assert_f64_eq(&fdiv(&x, &y, FloatFormat::DOUBLE, RNE), a / b);
+            }
+        }
+    }
+
+    #[test]
+    fn fma_matches_native() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        for &a in INTERESTING_F32S {
+            for &b in INTERESTING_F32S {
+                for &c in &[0.0, -0.0, 1.0, -1.5, 1e-40, f32::MAX, f32::INFINITY, f32::NAN, -a * b] {
+                    let result = ffma(&f32_bv(&btor, a), &f32_bv(&btor, b), &f32_bv(&btor, c), FloatFormat::SINGLE, RNE);
+                    assert_f32_eq(&result, a.mul_add(b, c));
+                }
+            }
+        }
+        for &a in INTERESTING_F64S {
+            for &b in INTERESTING_F64S {
+                for &c in &[0.0, -0.0, -1.0, 5e-324, -f64::MAX, f64::NEG_INFINITY, -a * b] {
+                    let result = ffma(&f64_bv(&btor, a), &f64_bv(&btor, b), &f64_bv(&btor, c), FloatFormat::DOUBLE, RNE);
+                    assert_f64_eq(&result, a.mul_add(b, c));
+                }
+            }
+        }
+
+        // the product isn't rounded before the add
+        let (a, b, c) = (f64_bv(&btor, 0.1), f64_bv(&btor, 10.0), f64_bv(&btor, -1.0));
+        assert_f64_eq(&ffma(&a, &b, &c, FloatFormat::DOUBLE, RNE), 0.1_f64.mul_add(10.0, -1.0));
+        assert_f64_eq(&fadd(&fmul(&a, &b, FloatFormat::DOUBLE, RNE), &c, FloatFormat::DOUBLE, RNE), 0.0);
+    }
+
+    #[test]
+    fn rounding_modes() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        // 1 + 2^-24 is exactly halfway between 1 and the next float up
+        let one = f32_bv(&btor, 1.0);
+        let half_ulp = f32_bv(&btor, f32::EPSILON / 2.0);
+        let next_up = 1.0 + f32::EPSILON;
+        let add = |a: &BV, b: &BV, rm| fadd(a, b, FloatFormat::SINGLE, rm);
+        assert_f32_eq(&add(&one, &half_ulp, RoundingMode::NearestTiesToEven), 1.0);
+        assert_f32_eq(&add(&one, &half_ulp, RoundingMode::NearestTiesToAway), next_up);
+        assert_f32_eq(&add(&one, &half_ulp, RoundingMode::TowardPositive), next_up);
+        assert_f32_eq(&add(&one, &half_ulp, RoundingMode::TowardNegative), 1.0);
+        assert_f32_eq(&add(&one, &half_ulp, RoundingMode::TowardZero), 1.0);
+        let minus_one = f32_bv(&btor, -1.0);
+        let minus_half_ulp = f32_bv(&btor, -f32::EPSILON / 2.0);
+        assert_f32_eq(&add(&minus_one, &minus_half_ulp, RoundingMode::TowardNegative), -next_up);
+        assert_f32_eq(&add(&minus_one, &minus_half_ulp, RoundingMode::TowardZero), -1.0);
+
+        // overflow goes to infinity or to the largest finite value, depending on the mode
+        let max = f32_bv(&btor, f32::MAX);
+        assert_f32_eq(&add(&max, &max, RoundingMode::NearestTiesToEven), f32::INFINITY);
+        assert_f32_eq(&add(&max, &max, RoundingMode::TowardZero), f32::MAX);
+        assert_f32_eq(&add(&max, &max, RoundingMode::TowardNegative), f32::MAX);
+
+        // x - x is -0.0 only when rounding toward negative
+        assert_f32_eq(&fsub(&one, &one, FloatFormat::SINGLE, RoundingMode::NearestTiesToEven), 0.0);
+        assert_f32_eq(&fsub(&one, &one, FloatFormat::SINGLE, RoundingMode::TowardNegative), -0.0);
+    }
+
+    #[test]
+    fn comparisons() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        let cmp = |a: f64, b: f64, predicate| eval(&fcmp(&f64_bv(&btor, a), &f64_bv(&btor, b), FloatFormat::DOUBLE, predicate)) == 1;
+        for &a in INTERESTING_F64S {
+            for &b in INTERESTING_F64S {
+                let unordered = a.is_nan() || b.is_nan();
+                assert_eq!(cmp(a, b, FPPredicate::OEQ), a == b, "{:?} oeq {:?}", a, b);
+                assert_eq!(cmp(a, b, FPPredicate::UNE), a != b, "{:?} une {:?}", a, b);
+                assert_eq!(cmp(a, b, FPPredicate::OLT), a < b, "{:?} olt {:?}", a, b);
+                assert_eq!(cmp(a, b, FPPredicate::OGE), a >= b, "{:?} oge {:?}", a, b);
+                assert_eq!(cmp(a, b, FPPredicate::ULE), unordered || a <= b, "{:?} ule {:?}", a, b);
+                assert_eq!(cmp(a, b, FPPredicate::ONE), !unordered && a != b, "{:?} one {:?}", a, b);
+                assert_eq!(cmp(a, b, FPPredicate::UNO), unordered, "{:?} uno {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+
+        // fptosi / fptoui round toward zero
+        let to_int = |f: f64, bits| eval(&fp_to_int(&f64_bv(&btor, f), FloatFormat::DOUBLE, bits));
+        assert_eq!(to_int(2.9, 32), 2);
+        assert_eq!(to_int(-2.9, 32) as u32 as i32, -2);
+        assert_eq!(to_int(0.5, 8), 0);
+        assert_eq!(to_int(-0.0, 64), 0);
+        assert_eq!(to_int(4_294_967_295.0, 32), 4_294_967_295);
+        assert_eq!(to_int(-9_223_372_036_854_775_808.0, 64) as i64, i64::MIN);
+
+        // sitofp / uitofp
+        let from_int = |i: u64, bits, signed| int_to_fp(&BV::from_u64(btor.clone(), i, bits), signed, FloatFormat::SINGLE, RNE);
+        assert_f32_eq(&from_int(7, 32, true), 7.0);
+        assert_f32_eq(&from_int(-7_i32 as u32 as u64, 32, true), -7.0);
+        assert_f32_eq(&from_int(-7_i32 as u32 as u64, 32, false), 4_294_967_289_u32 as f32);
+        assert_f32_eq(&from_int(i32::MIN as u32 as u64, 32, true), i32::MIN as f32);
+        assert_f32_eq(&from_int(16_777_217, 32, true), 16_777_217_i32 as f32);
+        assert_f32_eq(&from_int(u64::MAX, 64, false), u64::MAX as f32);
+        assert_f32_eq(&from_int(0, 8, true), 0.0);
+
+        // fpext / fptrunc
+        for &f in INTERESTING_F32S {
+            assert_f64_eq(&fp_convert(&f32_bv(&btor, f), FloatFormat::SINGLE, FloatFormat::DOUBLE, RNE), f64::from(f));
+        }
+        for &f in INTERESTING_F64S {
+            assert_f32_eq(&fp_convert(&f64_bv(&btor, f), FloatFormat::DOUBLE, FloatFormat::SINGLE, RNE), f as f32);
+        }
+    }
+
+    #[test]
+    fn unsupported_formats() {
+        assert_eq!(FloatFormat::from_fptype(FPType::Double), Ok(FloatFormat::DOUBLE));
+        assert!(FloatFormat::from_fptype(FPType::X86_FP80).is_err());
+        assert!(FloatFormat::from_fptype(FPType::PPC_FP128).is_err());
+    }
+}
@@ -9,7 +9,7 @@ use crate::project::Project;
 use crate::return_value::*;
 use crate::state::State;
 use either::Either;
-use llvm_ir::{Name, Operand, Type, Typed, instruction::InlineAssembly};
+use llvm_ir::{Constant, Name, Operand, Type, Typed, instruction::InlineAssembly};
 use llvm_ir::function::{CallingConvention, FunctionAttribute, ParameterAttribute};
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
@@ -60,6 +60,12 @@ pub struct FunctionHooks<'p, B: Backend + 'p> {
     cpp_demangled_hooks: HashMap<String, FunctionHook<'p, B>>,
     rust_demangled_hooks: HashMap<String, FunctionHook<'p, B>>,
 
+    /// Hooks for all functions whose (Rust) demangled names start with a given
+    /// prefix, as (prefix, hook) pairs. These have lower priority than any of
+    /// the hooks for specific functions above. If several prefixes match, the
+    /// longest one wins.
+    rust_demangled_prefix_hooks: Vec<(String, FunctionHook<'p, B>)>,
+
     /// Hook (if any) to use for calls to inline assembly.
     /// This one hook will handle all calls to any inline assembly, regardless of
     /// the contents; it is responsible for inspecting the contents and acting
@@ -85,6 +91,10 @@ pub struct FunctionHooks<'p, B: Backend + 'p> {
 
     /// For internal use in creating unique `id`s for `FunctionHook`s
     cur_id: usize,
+
+    /// Whether hooks being added now are `haybale`'s own (e.g., from
+    /// `FunctionHooks::default()`), rather than the user's
+    adding_builtins: bool,
 }
 
 /// An `Argument` represents a single argument to a called function, together
@@ -148,19 +158,31 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
             hooks: HashMap::new(),
             cpp_demangled_hooks: HashMap::new(),
             rust_demangled_hooks: HashMap::new(),
+            rust_demangled_prefix_hooks: Vec::new(),
             inline_asm_hook: None,
             default_hook: None,
             cur_id: 0,
+            adding_builtins: false,
         }
     }
 
+    /// Create a `FunctionHook` with a fresh `id`
+    fn new_hook<H>(&mut self, hook: &'p H) -> FunctionHook<'p, B>
+        where H: Fn(&'p Project, &mut State<'p, B>, &'p dyn IsCall) -> Result<ReturnValue<B::BV>>
+    {
+        let mut hook = FunctionHook::new(self.cur_id, hook);
+        hook.builtin = self.adding_builtins;
+        self.cur_id += 1;
+        hook
+    }
+
     /// Adds a function hook. The `hook` will be executed instead of the body of
     /// the `hooked_function`.
     pub fn add<H>(&mut self, hooked_function: impl Into<String>, hook: &'p H)
         where H: Fn(&'p Project, &mut State<'p, B>, &'p dyn IsCall) -> Result<ReturnValue<B::BV>>
     {
-        self.hooks.insert(hooked_function.into(), FunctionHook::new(self.cur_id, hook));
-        self.cur_id += 1;
+        let hook = self.new_hook(hook);
+        self.hooks.insert(hooked_function.into(), hook);
     }
 
     /// Exactly like `add()`, but takes the (C++) _demangled_ name of the function
@@ -168,8 +190,8 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
     pub fn add_cpp_demangled<H>(&mut self, hooked_function: impl Into<String>, hook: &'p H)
         where H: Fn(&'p Project, &mut State<'p, B>, &'p dyn IsCall) -> Result<ReturnValue<B::BV>>
     {
-        self.cpp_demangled_hooks.insert(hooked_function.into(), FunctionHook::new(self.cur_id, hook));
-        self.cur_id += 1;
+        let hook = self.new_hook(hook);
+        self.cpp_demangled_hooks.insert(hooked_function.into(), hook);
     }
 
     /// Exactly like `add()`, but takes the (Rust) _demangled_ name of the function
@@ -177,8 +199,20 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
     pub fn add_rust_demangled<H>(&mut self, hooked_function: impl Into<String>, hook: &'p H)
         where H: Fn(&'p Project, &mut State<'p, B>, &'p dyn IsCall) -> Result<ReturnValue<B::BV>>
     {
-        self.rust_demangled_hooks.insert(hooked_function.into(), FunctionHook::new(self.cur_id, hook));
-        self.cur_id += 1;
+        let hook = self.new_hook(hook);
+        self.rust_demangled_hooks.insert(hooked_function.into(), hook);
+    }
+
+    /// Like `add_rust_demangled()`, but hooks every function whose (Rust)
+    /// demangled name starts with the given prefix, such as `"core::fmt::"`.
+    /// Hooks for specific functions take priority over prefix hooks.
+    pub fn add_rust_demangled_prefix<H>(&mut self, prefix: impl Into<String>, hook: &'p H)
+        where H: Fn(&'p Project, &mut State<'p, B>, &'p dyn IsCall) -> Result<ReturnValue<B::BV>>
+    {
+        let prefix = prefix.into();
+        self.rust_demangled_prefix_hooks.retain(|(p, _)| *p != prefix);
+        let hook = self.new_hook(hook);
+        self.rust_demangled_prefix_hooks.push((prefix, hook));
     }
 
     /// Add a hook to be used for calls to inline assembly.
@@ -200,18 +234,8 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
     pub fn add_inline_asm_hook<H>(&mut self, hook: &'p H) -> bool
         where H: Fn(&'p Project, &mut State<'p, B>, &'p dyn IsCall) -> Result<ReturnValue<B::BV>>
     {
-        match &mut self.inline_asm_hook {
-            h@Some(_) => {
-                *h = Some(FunctionHook::new(self.cur_id, hook));
-                self.cur_id += 1;
-                true
-            },
-            h@None => {
-                *h = Some(FunctionHook::new(self.cur_id, hook));
-                self.cur_id += 1;
-                false
-            },
-        }
+        let hook = self.new_hook(hook);
+        self.inline_asm_hook.replace(hook).is_some()
     }
 
     /// Add a hook to be used if no other definition or hook is found for the
@@ -224,18 +248,56 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
     pub fn add_default_hook<H>(&mut self, hook: &'p H) -> bool
         where H: Fn(&'p Project, &mut State<'p, B>, &'p dyn IsCall) -> Result<ReturnValue<B::BV>>
     {
-        match &mut self.default_hook {
-            h@Some(_) => {
-                *h = Some(FunctionHook::new(self.cur_id, hook));
-                self.cur_id += 1;
-                true
-            },
-            h@None => {
-                *h = Some(FunctionHook::new(self.cur_id, hook));
-                self.cur_id += 1;
-                false
-            },
-        }
+        let hook = self.new_hook(hook);
+        self.default_hook.replace(hook).is_some()
+    }
+
+    /// Add hooks for `fopen()`, `open()`, `fclose()`, and `close()`, which
+    /// track the handles they return (see
+    /// [`State::handles()`](../struct.State.html#method.handles)). Opening may
+    /// fail, returning `NULL` or `-1`; closing always succeeds.
+    ///
+    /// Use these along with the
+    /// [`HandleLifecycle`](../checkers/struct.HandleLifecycle.html) checker to
+    /// report uses of handles after they are closed. Handles which are still
+    /// open when the top-level function returns are reported in the
+    /// [`State::path_notes()`](../struct.State.html#method.path_notes).
+    ///
+    /// Functions which read or write through a handle (`fread()`, `write()`,
+    /// etc) aren't hooked; hook them as appropriate, for instance with
+    /// [`generic_stub_hook`](fn.generic_stub_hook.html).
+    pub fn add_handle_hooks(&mut self) {
+        let adding_builtins = std::mem::replace(&mut self.adding_builtins, true);
+        self.add("fopen", &hooks::handles::fopen_hook);
+        self.add("open", &hooks::handles::open_hook);
+        self.add("fclose", &hooks::handles::fclose_hook);
+        self.add("close", &hooks::handles::close_hook);
+        self.adding_builtins = adding_builtins;
+    }
+
+    /// Add hooks for `time()`, `clock_gettime()`, `gettimeofday()`, `clock()`,
+    /// and the cycle counter intrinsics, which return fresh symbolic readings
+    /// of the corresponding [`Clock`](../clocks/enum.Clock.html) (see
+    /// [`State::read_clock()`](../struct.State.html#method.read_clock)).
+    ///
+    /// Each reading of `CLOCK_MONOTONIC` is no earlier than the previous one on
+    /// the same path, and at most
+    /// [`Config.max_time_step`](../config/struct.Config.html#structfield.max_time_step)
+    /// later. So both outcomes of a timeout check are explored, while branches
+    /// which require time to go backwards are infeasible. `CLOCK_REALTIME`
+    /// (and therefore `time()` and `gettimeofday()`) may jump either way.
+    ///
+    /// These assume the LP64 layouts of `struct timespec` and `struct
+    /// timeval`, i.e., two 8-byte fields each.
+    pub fn add_time_hooks(&mut self) {
+        let adding_builtins = std::mem::replace(&mut self.adding_builtins, true);
+        self.add("time", &hooks::time::time_hook);
+        self.add("clock_gettime", &hooks::time::clock_gettime_hook);
+        self.add("gettimeofday", &hooks::time::gettimeofday_hook);
+        self.add("clock", &hooks::time::clock_hook);
+        self.add("llvm.readcyclecounter", &hooks::time::readcyclecounter_hook);
+        self.add("llvm.x86.rdtsc", &hooks::time::readcyclecounter_hook);
+        self.adding_builtins = adding_builtins;
     }
 
     /// Removes the function hook for the given function, which was added with
@@ -258,6 +320,14 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
         self.rust_demangled_hooks.remove(hooked_function);
     }
 
+    /// Removes the prefix hook for the given prefix, which was added with
+    /// [`add_rust_demangled_prefix()`](struct.FunctionHooks.html#method.add_rust_demangled_prefix).
+    /// Functions with that prefix will no longer be hooked (unless they are
+    /// hooked individually).
+    pub fn remove_rust_demangled_prefix(&mut self, prefix: &str) {
+        self.rust_demangled_prefix_hooks.retain(|(p, _)| p != prefix);
+    }
+
     /// Removes the function hook used for calls to inline assembly, which was
     /// added with [`add_inline_asm_hook()`]. Calls to inline assembly will no
     /// longer be hooked, and thus will result in errors, until the next call to
@@ -282,7 +352,10 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
     /// Iterate over all function hooks, as (function name, hook) pairs.
     /// Function names may include both mangled and demangled names.
     pub(crate) fn get_all_hooks(&self) -> impl Iterator<Item = (&String, &FunctionHook<'p, B>)> {
-        self.hooks.iter().chain(self.cpp_demangled_hooks.iter()).chain(self.rust_demangled_hooks.iter())
+        self.hooks.iter()
+            .chain(self.cpp_demangled_hooks.iter())
+            .chain(self.rust_demangled_hooks.iter())
+            .chain(self.rust_demangled_prefix_hooks.iter().map(|(prefix, hook)| (prefix, hook)))
     }
 
     /// Get the `FunctionHook` active for the given `funcname`, or `None` if
@@ -298,6 +371,16 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
                 demangling::try_cpp_demangle(funcname)
                     .and_then(|demangled| self.cpp_demangled_hooks.get(&demangled))
             })
+            .or_else(|| {
+                if self.rust_demangled_prefix_hooks.is_empty() {
+                    return None;
+                }
+                let demangled = demangling::try_rust_demangle(funcname)?;
+                self.rust_demangled_prefix_hooks.iter()
+                    .filter(|(prefix, _)| demangled.starts_with(prefix.as_str()))
+                    .max_by_key(|(prefix, _)| prefix.len())
+                    .map(|(_, hook)| hook)
+            })
     }
 
     /// Get the `FunctionHook` used for calls to inline assembly, if there is one.
@@ -315,6 +398,48 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
         self.default_hook.as_ref()
     }
 
+    /// Attach an identifier to the hook for the given function, as named when
+    /// it was added with `add()`, `add_cpp_demangled()`, `add_rust_demangled()`,
+    /// or `add_rust_demangled_prefix()`. The identifier is only used to
+    /// describe the hook, in the `manifest()`; for instance, a version string
+    /// such as `"my_malloc v2"` lets a
+    /// [`ReproBundle`](../repro/struct.ReproBundle.html) notice that the hook
+    /// has changed.
+    ///
+    /// Returns `false` if there is no such hook.
+    pub fn set_hook_id(&mut self, hooked_function: &str, id: impl Into<String>) -> bool {
+        let hook = if self.hooks.contains_key(hooked_function) {
+            self.hooks.get_mut(hooked_function)
+        } else if self.cpp_demangled_hooks.contains_key(hooked_function) {
+            self.cpp_demangled_hooks.get_mut(hooked_function)
+        } else if self.rust_demangled_hooks.contains_key(hooked_function) {
+            self.rust_demangled_hooks.get_mut(hooked_function)
+        } else {
+            self.rust_demangled_prefix_hooks.iter_mut().find(|(prefix, _)| prefix == hooked_function).map(|(_, hook)| hook)
+        };
+        match hook {
+            Some(hook) => {
+                hook.user_id = Some(id.into());
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Describe all of the hooks, sorted by `HookEntry.hooked`
+    pub fn manifest(&self) -> Vec<HookEntry> {
+        let entry = |hooked: String, hook: &FunctionHook<'p, B>| HookEntry { hooked, id: hook.user_id.clone() };
+        let mut entries: Vec<HookEntry> = self.hooks.iter().map(|(name, hook)| entry(name.clone(), hook))
+            .chain(self.cpp_demangled_hooks.iter().map(|(name, hook)| entry(format!("cpp:{}", name), hook)))
+            .chain(self.rust_demangled_hooks.iter().map(|(name, hook)| entry(format!("rust:{}", name), hook)))
+            .chain(self.rust_demangled_prefix_hooks.iter().map(|(prefix, hook)| entry(format!("rust prefix:{}", prefix), hook)))
+            .chain(self.inline_asm_hook.iter().map(|hook| entry("<inline asm>".to_owned(), hook)))
+            .chain(self.default_hook.iter().map(|hook| entry("<default>".to_owned(), hook)))
+            .collect();
+        entries.sort_by(|a, b| a.hooked.cmp(&b.hooked));
+        entries
+    }
+
     /// Determine whether there is an active hook for the given `funcname`
     pub fn is_hooked(&self, funcname: &str) -> bool {
         self.get_hook_for(funcname).is_some()
@@ -331,6 +456,29 @@ impl<'p, B: Backend + 'p> FunctionHooks<'p, B> {
     pub fn has_default_hook(&self) -> bool {
         self.default_hook.is_some()
     }
+
+    /// Are there any hooks other than `haybale`'s own (those from
+    /// `FunctionHooks::default()`, `add_handle_hooks()`, and `add_time_hooks()`)?
+    pub fn has_custom_hooks(&self) -> bool {
+        self.get_all_hooks().map(|(_, hook)| hook)
+            .chain(self.inline_asm_hook.iter())
+            .chain(self.default_hook.iter())
+            .any(|hook| !hook.builtin)
+    }
+}
+
+/// Describes one of the hooks in a `FunctionHooks`; see
+/// [`FunctionHooks::manifest()`](struct.FunctionHooks.html#method.manifest)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct HookEntry {
+    /// What the hook applies to: the function name, prefixed with `cpp:` or
+    /// `rust:` for demangled names, or `rust prefix:` for prefix hooks; or
+    /// `<inline asm>` or `<default>`
+    pub hooked: String,
+    /// The identifier attached with
+    /// [`FunctionHooks::set_hook_id()`](struct.FunctionHooks.html#method.set_hook_id),
+    /// if any
+    pub id: Option<String>,
 }
 
 impl<'p, B: Backend + 'p> Default for FunctionHooks<'p, B> {
@@ -340,6 +488,12 @@ impl<'p, B: Backend + 'p> Default for FunctionHooks<'p, B> {
     /// functions such as `__cxa_throw()` and `__cxa_allocate_exception()`,
     /// and a few other C and Rust standard library functions.)
     ///
+    /// This also stubs out Rust's formatting machinery (every function whose
+    /// demangled name starts with `core::fmt::`) with
+    /// [`fmt_stub_hook`](fn.fmt_stub_hook.html), since formatting is rarely
+    /// relevant to an analysis but adds many paths. To execute formatting code
+    /// instead, use `remove_rust_demangled_prefix("core::fmt::")`.
+    ///
     /// If you don't want these hooks, you can use
     /// [`FunctionHooks::remove_function_hook()`](struct.FunctionHooks.html#method.remove_function_hook)
     /// to remove individual hooks, or you can use
@@ -347,6 +501,7 @@ impl<'p, B: Backend + 'p> Default for FunctionHooks<'p, B> {
     /// comes with no predefined hooks.
     fn default() -> Self {
         let mut fhooks = Self::new();
+        fhooks.adding_builtins = true;
         fhooks.add("malloc", &hooks::allocation::malloc_hook);
         fhooks.add("calloc", &hooks::allocation::calloc_hook);
         fhooks.add("realloc", &hooks::allocation::realloc_hook);
@@ -364,6 +519,8 @@ impl<'p, B: Backend + 'p> Default for FunctionHooks<'p, B> {
         fhooks.add_rust_demangled("core::slice::slice_index_len_fail", &abort_hook);
         fhooks.add_rust_demangled("core::slice::slice_index_order_fail", &abort_hook);
         fhooks.add_rust_demangled("core::slice::slice_index_overflow_fail", &abort_hook);
+        fhooks.add_rust_demangled_prefix("core::fmt::", &fmt_stub_hook);
+        fhooks.adding_builtins = false;
         fhooks
     }
 }
@@ -381,11 +538,17 @@ pub(crate) struct FunctionHook<'p, B: Backend> {
     /// A unique id, used for nothing except equality comparisons between `FunctionHook`s.
     /// This `id` should be globally unique across all created `FunctionHook`s.
     id: usize,
+
+    /// Identifier attached by the user with `FunctionHooks::set_hook_id()`
+    user_id: Option<String>,
+
+    /// Whether this is one of `haybale`'s own hooks, rather than the user's
+    builtin: bool,
 }
 
 impl<'p, B: Backend> Clone for FunctionHook<'p, B> {
     fn clone(&self) -> Self {
-        Self { hook: self.hook.clone(), id: self.id }
+        Self { hook: self.hook.clone(), id: self.id, user_id: self.user_id.clone(), builtin: self.builtin }
     }
 }
 
@@ -407,7 +570,7 @@ impl<'p, B: Backend> FunctionHook<'p, B> {
     /// `id`: A unique id, used for nothing except equality comparisons between `FunctionHook`s.
     /// This `id` should be globally unique across all created `FunctionHook`s.
     pub fn new(id: usize, f: &'p dyn Fn(&'p Project, &mut State<'p, B>, &'p dyn IsCall) -> Result<ReturnValue<B::BV>>) -> Self {
-        Self { hook: Rc::new(f), id }
+        Self { hook: Rc::new(f), id, user_id: None, builtin: false }
     }
 
     pub fn call_hook(&self, proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
@@ -436,6 +599,36 @@ pub fn generic_stub_hook<B: Backend>(
     }
 }
 
+/// This hook stands in for functions in Rust's formatting machinery
+/// (`core::fmt::write()`, `Formatter::pad()`, etc), without executing them.
+/// Rather than write anything, it records a note on the path (see
+/// [`State::path_notes()`](../struct.State.html#method.path_notes)).
+///
+/// Functions returning an integer return 0, which means success for
+/// functions returning `fmt::Result`. Void functions simply return, and
+/// functions returning anything else return an unconstrained value.
+pub fn fmt_stub_hook<B: Backend>(
+    _proj: &Project,
+    state: &mut State<B>,
+    call: &dyn IsCall,
+) -> Result<ReturnValue<B::BV>> {
+    let funcname = match call.get_called_func() {
+        Either::Right(Operand::ConstantOperand(Constant::GlobalReference { name: Name::Name(name), .. })) => demangling::rust_demangle_or_id(name),
+        _ => "a formatting function".to_owned(),
+    };
+    let note = format!("skipped formatting: call to {} at {}", funcname, state.cur_loc.to_string_no_module());
+    state.add_path_note(note);
+    match call.get_type() {
+        Type::VoidType => Ok(ReturnValue::ReturnVoid),
+        Type::IntegerType { bits } => Ok(ReturnValue::Return(state.zero(bits))),
+        ty => {
+            let width = layout::size(&ty);
+            let bv = state.new_bv_with_name(Name::from("fmt_stub_hook_retval"), width as u32)?;
+            Ok(ReturnValue::Return(bv))
+        },
+    }
+}
+
 /// This hook ignores the function arguments and returns `ReturnValue::Abort`.
 /// It is suitable for hooking functions such as C's `exit()` which abort the
 /// program and never return.
@@ -446,3 +639,123 @@ pub fn abort_hook<B: Backend>(
 ) -> Result<ReturnValue<B::BV>> {
     Ok(ReturnValue::Abort)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::config::Config;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::*;
+    use std::time::Instant;
+
+    const WRITE: &str = "_ZN4core3fmt5write17h0123456789abcdefE";
+
+    /// Stand-in for `core::fmt::write()`, with `2^diamonds` paths through it
+    fn fmt_write(diamonds: u64) -> Function {
+        let v = local("v", Type::i32());
+        let mut bbnames = vec![];
+        for k in 0 .. diamonds {
+            bbnames.extend(vec![Name::from(format!("bb{}", k)), Name::from(format!("l{}", k)), Name::from(format!("r{}", k))]);
+        }
+        bbnames.push(Name::from(format!("bb{}", diamonds)));
+        let mut func = blank_function(WRITE, bbnames);
+        func.parameters.push(param("v", Type::i32()));
+        func.return_type = Type::bool();
+        for k in 0 .. diamonds {
+            let next = Name::from(format!("bb{}", k + 1));
+            let bbs = &mut func.basic_blocks[3 * k as usize .. 3 * k as usize + 3];
+            bbs[0].instrs = vec![
+                Instruction::And(instruction::And {
+                    operand0: v.clone(),
+                    operand1: int_const(32, 1 << k),
+                    dest: Name::from(format!("bit{}", k)),
+                    debugloc: None,
+                }),
+                icmp(IntPredicate::EQ, local(format!("bit{}", k), Type::i32()), int_const(32, 0), format!("c{}", k)),
+            ];
+            bbs[0].term = Terminator::CondBr(terminator::CondBr {
+                condition: local(format!("c{}", k), Type::bool()),
+                true_dest: bbs[1].name.clone(),
+                false_dest: bbs[2].name.clone(),
+                debugloc: None,
+            });
+            bbs[1].term = Terminator::Br(terminator::Br { dest: next.clone(), debugloc: None });
+            bbs[2].term = Terminator::Br(terminator::Br { dest: next, debugloc: None });
+        }
+        func.basic_blocks.last_mut().unwrap().term = ret(int_const(1, 0));
+        func
+    }
+
+    /// `fn f(x: u32) -> u32`, which formats only in its error branch
+    fn formats_on_error() -> Function {
+        let x = local("x", Type::i32());
+        let ret_i32 = |value| ret(int_const(32, value));
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("err"), Name::from("ok")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::UGT, x.clone(), int_const(32, 10), "bad"));
+        func.basic_blocks[0].term = condbr("bad", "err", "ok");
+        func.basic_blocks[1].instrs.push(call(WRITE, Type::bool(), vec![x], Some("r")));
+        func.basic_blocks[1].term = ret_i32(1);
+        func.basic_blocks[2].term = ret_i32(0);
+        func
+    }
+
+    fn project() -> Project {
+        project_with_functions("test_mod", vec![formats_on_error(), fmt_write(8)])
+    }
+
+    /// Explore all paths through `f`, returning the number of paths, the
+    /// notes on each path, and the number of instructions executed
+    fn explore<'p>(project: &'p Project, config: Config<'p, BtorBackend>) -> (usize, Vec<Vec<String>>, u64) {
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", project, config);
+        let mut notes = vec![];
+        while let Some(retval) = em.next() {
+            retval.unwrap();
+            notes.push(em.state().path_notes().to_vec());
+        }
+        let instructions = em.stats().instruction_counts().iter().map(|c| c.count).sum();
+        (notes.len(), notes, instructions)
+    }
+
+    /// Without the stubs, the formatting machinery is explored
+    fn unstubbed_config<'p>() -> Config<'p, BtorBackend> {
+        let mut config = Config::default();
+        config.function_hooks.remove_rust_demangled_prefix("core::fmt::");
+        config
+    }
+
+    #[test]
+    fn fmt_stubs() {
+        let project = project();
+        let (stubbed_paths, notes, stubbed_instructions) = explore(&project, Config::default());
+        assert_eq!(stubbed_paths, 2);
+        let mut notes: Vec<String> = notes.into_iter().flatten().collect();
+        notes.sort();
+        assert_eq!(notes, vec!["skipped formatting: call to core::fmt::write at f, bb err, instr 0".to_owned()]);
+        // just `f`'s own 2 + 2 + 1 instructions and terminators
+        assert_eq!(stubbed_instructions, 5);
+
+        let (full_paths, _, full_instructions) = explore(&project, unstubbed_config());
+        assert_eq!(full_paths, 256 + 1);
+        assert!(full_instructions > 100 * stubbed_instructions, "expected the formatting machinery to be executed, but only {} instructions were", full_instructions);
+    }
+
+    /// Compare the time taken to explore `f` with and without the stubs.
+    /// Run with `cargo test -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn fmt_stubs_timing() {
+        let project = project();
+        let time = |config| {
+            let start = Instant::now();
+            explore(&project, config);
+            start.elapsed()
+        };
+        let stubbed_time = time(Config::default());
+        let full_time = time(unstubbed_config());
+        println!("stubbed: {:?}; full: {:?}", stubbed_time, full_time);
+    }
+}
@@ -209,6 +209,21 @@ impl<'p, B: Backend> GlobalAllocations<'p, B> {
         self.allocate_global(var, module, allocation);
     }
 
+    /// `var`: a global variable *declaration* which has no definition in the
+    /// `Project`, but whose contents the caller has already written at `addr`
+    ///
+    /// `module`: `Module` in which the declaration appears
+    ///
+    /// The global variable will be considered already initialized.
+    pub fn allocate_declared_global_var(&mut self, var: &'p GlobalVariable, module: &'p Module, addr: B::BV) {
+        let ty = match &var.ty {
+            Type::PointerType { pointee_type, .. } => (**pointee_type).clone(),
+            ty => panic!("Global variable has non-pointer type {:?}", ty),
+        };
+        let allocation = GlobalAllocation::GlobalVariable { addr, initializer: Constant::Undef(ty), initialized: Cell::new(true) };
+        self.allocate_global(var, module, allocation);
+    }
+
     /// `func`: a function definition
     ///
     /// `module`: `Module` in which the definition appears
@@ -311,7 +326,10 @@ impl<'p, B: Backend> GlobalAllocations<'p, B> {
                 warn!("Global {:?} has 'appending' linkage type, which is not supported. Any attempted use of this global will result in an error.", global.get_name());
                 AllocationResult::NoAllocate
             }
-            _ => unimplemented!("Linkage type {:?}", global.get_linkage()),
+            linkage => {
+                warn!("Global {:?} has linkage type {:?}, which is not supported. Any attempted use of this global will result in an error.", global.get_name(), linkage);
+                AllocationResult::NoAllocate
+            },
         }
     }
 
@@ -0,0 +1,152 @@
+//! Tracking of the lifecycle of file descriptors and other handles (such as
+//! `FILE*`s), for reporting leaks and uses after close
+
+use std::fmt;
+
+/// Whether a tracked handle is currently open
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum HandleState {
+    Open,
+    Closed,
+}
+
+/// A handle returned by a hook such as the ones installed by
+/// [`FunctionHooks::add_handle_hooks()`](../function_hooks/struct.FunctionHooks.html#method.add_handle_hooks),
+/// and recorded with
+/// [`State::open_handle()`](../struct.State.html#method.open_handle).
+///
+/// Handles are tracked per path: they are restored on backtracking.
+#[derive(Clone, Debug)]
+pub struct Handle<V> {
+    /// The value the hook returned. This may be either the handle itself, or
+    /// a value indicating failure (such as `NULL` or `-1`).
+    pub returned: V,
+    /// The value of the handle, if opening it succeeded. Each handle opened on
+    /// a path gets a distinct value, so uses of the handle are identified by
+    /// comparing against this, even if the handle was stored to memory and
+    /// loaded back in between.
+    pub value: u64,
+    pub state: HandleState,
+    /// Where the handle was opened, e.g. `"fopen at x.c:10"`
+    pub site: String,
+}
+
+impl<V> fmt::Display for Handle<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "handle 0x{:x} (opened by {})", self.value, self.site)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::BtorBackend;
+    use crate::checkers::HandleLifecycle;
+    use crate::config::Config;
+    use crate::function_hooks::generic_stub_hook;
+    use crate::return_value::ReturnValue;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::*;
+    use std::rc::Rc;
+
+    /// ```c
+    /// FILE *f = fopen(NULL, NULL);
+    /// if (!f) return -1;
+    /// if (fread(NULL, 1, 1, f) == 0) { /* fclose(f), if `close_on_error` */ return -2; }
+    /// fclose(f);
+    /// return 0;
+    /// ```
+    fn read_file(close_on_error: bool) -> Function {
+        let file_ty = Type::pointer_to(Type::i8());
+        let f = local("f", file_ty.clone());
+        let null = Operand::ConstantOperand(Constant::Null(file_ty.clone()));
+        let i32_const = |value| int_const(32, value);
+        let ret_i32 = |value| ret(i32_const(value));
+        let fclose = call("fclose", Type::i32(), vec![f.clone()], None);
+
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("fail"), Name::from("opened"), Name::from("error"), Name::from("done")]);
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            call("fopen", file_ty.clone(), vec![null.clone(), null.clone()], Some("f")),
+            icmp(IntPredicate::EQ, f.clone(), null.clone(), "failed"),
+        ];
+        func.basic_blocks[0].term = condbr("failed", "fail", "opened");
+        func.basic_blocks[1].term = ret_i32((-1_i32) as u32 as u64);
+        func.basic_blocks[2].instrs = vec![
+            call("fread", Type::i32(), vec![null, i32_const(1), i32_const(1), f], Some("n")),
+            icmp(IntPredicate::EQ, local("n", Type::i32()), i32_const(0), "empty"),
+        ];
+        func.basic_blocks[2].term = condbr("empty", "error", "done");
+        if close_on_error {
+            func.basic_blocks[3].instrs.push(fclose.clone());
+        }
+        func.basic_blocks[3].term = ret_i32((-2_i32) as u32 as u64);
+        func.basic_blocks[4].instrs.push(fclose);
+        func.basic_blocks[4].term = ret_i32(0);
+        func
+    }
+
+    fn config<'p>() -> Config<'p, BtorBackend> {
+        let mut config = Config {
+            extra_checkers: vec![Rc::new(HandleLifecycle)],
+            ..Config::default()
+        };
+        config.function_hooks.add_handle_hooks();
+        config.function_hooks.add("fread", &generic_stub_hook);
+        config
+    }
+
+    /// Get the return value and the path notes of each path
+    fn paths(em: &mut ExecutionManager<BtorBackend>) -> Vec<(u64, Vec<String>)> {
+        let mut paths = vec![];
+        while let Some(retval) = em.next() {
+            match retval.unwrap() {
+                ReturnValue::Return(bv) => paths.push((bv.as_u64().unwrap(), em.state().path_notes().to_vec())),
+                r => panic!("Unexpected return value {:?}", r),
+            }
+        }
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn leak_on_early_error_return() {
+        let project = blank_project("test_mod", read_file(false));
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config());
+        let paths = paths(&mut em);
+        assert_eq!(paths.len(), 3);
+        let leaky: Vec<_> = paths.iter().filter(|(_, notes)| !notes.is_empty()).collect();
+        assert_eq!(leaky.len(), 1);
+        assert_eq!(leaky[0].0, (-2_i32) as u32 as u64);
+        assert_eq!(leaky[0].1.len(), 1);
+        assert!(leaky[0].1[0].starts_with("resource leak: handle"));
+        assert!(leaky[0].1[0].contains("opened by fopen at f, bb entry, instr 0"));
+        assert!(em.violations().is_empty());
+    }
+
+    #[test]
+    fn closed_on_every_path() {
+        let project = blank_project("test_mod", read_file(true));
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config());
+        let paths = paths(&mut em);
+        assert_eq!(paths.len(), 3);
+        assert!(paths.iter().all(|(_, notes)| notes.is_empty()));
+        assert!(em.violations().is_empty());
+    }
+
+    #[test]
+    fn double_close() {
+        let mut func = read_file(true);
+        // close again after the first close in the "done" block
+        let fclose = func.basic_blocks[4].instrs[0].clone();
+        func.basic_blocks[4].instrs.push(fclose);
+        let project = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config());
+        while em.next().is_some() {}
+        let violations = em.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].checker, "handle-lifecycle");
+        assert_eq!(violations[0].location, "f, bb done, instr 1");
+        assert!(violations[0].message.starts_with("double close of handle"));
+    }
+}
@@ -0,0 +1,379 @@
+//! A structural view of the heap on the current path, for visualizing pointer
+//! structures; see
+//! [`State::heap_graph()`](../struct.State.html#method.heap_graph).
+
+use crate::layout::{fp_size, size_opaque_aware, POINTER_SIZE_BITS};
+use crate::project::Project;
+use crate::PointerTarget;
+use llvm_ir::Type;
+use std::fmt::{self, Write};
+
+/// At most this many fields are shown for each allocation
+pub const MAX_FIELDS: usize = 32;
+
+/// The allocations made on a path, their contents in one solution of the
+/// path's constraints, and the pointers between them
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct HeapGraph {
+    /// In address order
+    pub nodes: Vec<HeapNode>,
+    pub edges: Vec<HeapEdge>,
+}
+
+/// One allocation in a `HeapGraph`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct HeapNode {
+    /// The allocation's label (see
+    /// [`State::label_allocation()`](../struct.State.html#method.label_allocation)),
+    /// or its address if it has none
+    pub allocation: String,
+    /// Size of the allocation in bytes
+    pub bytes: u64,
+    /// If the allocation's type is known, one field per scalar member;
+    /// otherwise one per pointer-sized word
+    pub fields: Vec<HeapField>,
+    /// Were fields beyond the first `MAX_FIELDS` left out
+    pub truncated: bool,
+}
+
+/// One field of a `HeapNode`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct HeapField {
+    /// Offset into the allocation, in bytes
+    pub offset: u64,
+    /// Size in bits
+    pub bits: u32,
+    /// If the allocation's type is known, the struct fields and array elements
+    /// leading to this field, as in
+    /// [`layout::field_path()`](../layout/fn.field_path.html)
+    pub path: Option<String>,
+    pub value: FieldValue,
+}
+
+/// The value of a `HeapField` in the solution
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum FieldValue {
+    /// A non-pointer value. Fields wider than 64 bits are split into 64-bit
+    /// fields.
+    Scalar(u64),
+    /// A null pointer
+    Null,
+    /// A non-null pointer; the corresponding `HeapEdge` says where it points
+    Pointer(u64),
+}
+
+/// A pointer stored in a field of a `HeapNode`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct HeapEdge {
+    /// Index of the `HeapNode` containing the pointer
+    pub from: usize,
+    /// Index of the pointer's `HeapField` in that node
+    pub field: usize,
+    pub to: EdgeTarget,
+    /// Does this edge close a cycle, i.e., lead back to a node from which
+    /// `from` is reachable
+    pub cycle: bool,
+}
+
+/// Where a `HeapEdge` points
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum EdgeTarget {
+    /// Into the `HeapNode` with the given index
+    Node(usize, PointerTarget),
+    /// Into an allocation which isn't part of the graph, such as a global
+    /// variable or a function
+    Outside(PointerTarget),
+    /// Not into any allocation. Only fields known to have pointer type are
+    /// reported this way; other values are assumed not to be pointers unless
+    /// they point into an allocation.
+    Dangling(u64),
+}
+
+impl HeapField {
+    fn name(&self) -> String {
+        match &self.path {
+            Some(path) if !path.is_empty() => path.clone(),
+            _ => format!("+{}", self.offset),
+        }
+    }
+}
+
+impl HeapGraph {
+    /// Mark the edges which close cycles: those found leading back to a node
+    /// on the current depth-first search stack
+    pub(crate) fn mark_cycles(&mut self) {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Visit { New, OnStack, Done }
+        let mut visits = vec![Visit::New; self.nodes.len()];
+        for root in 0 .. self.nodes.len() {
+            if visits[root] != Visit::New {
+                continue;
+            }
+            // stack of (node, index into self.edges to resume from)
+            let mut stack = vec![(root, 0)];
+            visits[root] = Visit::OnStack;
+            while let Some((node, next_edge)) = stack.pop() {
+                let found = (next_edge .. self.edges.len()).find(|&i| self.edges[i].from == node);
+                match found {
+                    None => visits[node] = Visit::Done,
+                    Some(i) => {
+                        stack.push((node, i + 1));
+                        if let EdgeTarget::Node(to, _) = self.edges[i].to {
+                            match visits[to] {
+                                Visit::OnStack => self.edges[i].cycle = true,
+                                Visit::New => {
+                                    visits[to] = Visit::OnStack;
+                                    stack.push((to, 0));
+                                },
+                                Visit::Done => {},
+                            }
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    /// Render the graph in Graphviz DOT format.
+    ///
+    /// Each node is a record listing its fields. Edges which close a cycle are
+    /// drawn bold and blue; dangling pointers are drawn dashed and red, to a
+    /// red node giving the address.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        self.write_dot(&mut dot).expect("writing to a String can't fail");
+        dot
+    }
+
+    fn write_dot(&self, out: &mut String) -> fmt::Result {
+        writeln!(out, "digraph heap {{")?;
+        writeln!(out, "  node [shape=record];")?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let mut label = escape(&format!("{} ({} bytes)", node.allocation, node.bytes));
+            for (j, field) in node.fields.iter().enumerate() {
+                let value = match &field.value {
+                    FieldValue::Scalar(value) => value.to_string(),
+                    FieldValue::Null => "NULL".to_owned(),
+                    FieldValue::Pointer(addr) => match self.edges.iter().find(|e| e.from == i && e.field == j).map(|e| &e.to) {
+                        Some(EdgeTarget::Node(_, target)) | Some(EdgeTarget::Outside(target)) => format!("&{}", target),
+                        _ => format!("0x{:x}", addr),
+                    },
+                };
+                write!(label, "|<f{}> {}", j, escape(&format!("{} = {}", field.name(), value)))?;
+            }
+            if node.truncated {
+                label.push_str("|...");
+            }
+            writeln!(out, "  n{} [label=\"{{{}}}\"];", i, label)?;
+        }
+        for (k, edge) in self.edges.iter().enumerate() {
+            let from = format!("n{}:f{}", edge.from, edge.field);
+            let style = if edge.cycle { " [color=blue, style=bold]" } else { "" };
+            match &edge.to {
+                EdgeTarget::Node(to, _) => writeln!(out, "  {} -> n{}{};", from, to, style)?,
+                EdgeTarget::Outside(target) => {
+                    writeln!(out, "  x{} [shape=plaintext, label=\"{}\"];", k, escape(&target.allocation))?;
+                    writeln!(out, "  {} -> x{}{};", from, k, style)?;
+                },
+                EdgeTarget::Dangling(addr) => {
+                    writeln!(out, "  x{} [shape=plaintext, fontcolor=red, label=\"dangling 0x{:x}\"];", k, addr)?;
+                    writeln!(out, "  {} -> x{} [color=red, style=dashed];", from, k)?;
+                },
+            }
+        }
+        writeln!(out, "}}")
+    }
+}
+
+/// Escape characters which are special in DOT record labels
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "{}|<>\"\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Where a field of a `HeapNode` is, before its value is known
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) struct FieldLayout {
+    pub(crate) offset: u64,
+    pub(crate) bits: u32,
+    pub(crate) path: Option<String>,
+    /// Is the field known to be a pointer
+    pub(crate) pointer: bool,
+}
+
+/// Lay out the fields of an allocation of `bits` bits and the given `Type`
+/// (if known), returning at most `MAX_FIELDS` fields and whether any were
+/// left out (including any after an opaque struct).
+///
+/// If the `Type` is unknown or entirely opaque, the allocation is split into
+/// pointer-sized words.
+pub(crate) fn field_layout(ty: Option<&Type>, bits: u64, proj: &Project) -> (Vec<FieldLayout>, bool) {
+    let mut fields = Vec::new();
+    let complete = match ty {
+        Some(ty) => typed_fields(ty, 0, String::new(), proj, &mut fields),
+        None => false,
+    };
+    if complete || !fields.is_empty() {
+        // a type we could lay out, at least in part
+        let truncated = !complete;
+        fields.truncate(MAX_FIELDS);
+        return (fields, truncated);
+    }
+    let word = POINTER_SIZE_BITS as u64;
+    let mut offset_bits = 0;
+    while offset_bits < bits && fields.len() <= MAX_FIELDS {
+        let field_bits = std::cmp::min(word, bits - offset_bits);
+        fields.push(FieldLayout { offset: offset_bits / 8, bits: field_bits as u32, path: None, pointer: false });
+        offset_bits += field_bits;
+    }
+    let truncated = fields.len() > MAX_FIELDS;
+    fields.truncate(MAX_FIELDS);
+    (fields, truncated)
+}
+
+/// Push the scalar fields of `ty`, starting `offset_bits` into the
+/// allocation, onto `fields`. Stops early, returning `false`, once there are
+/// more than `MAX_FIELDS` fields.
+fn typed_fields(ty: &Type, offset_bits: u64, path: String, proj: &Project, fields: &mut Vec<FieldLayout>) -> bool {
+    if fields.len() > MAX_FIELDS {
+        return false;
+    }
+    let leaf = |bits: u64, pointer: bool, fields: &mut Vec<FieldLayout>| {
+        let chunks = bits.div_ceil(64);
+        for chunk in 0 .. chunks {
+            let chunk_path = if chunk == 0 { path.clone() } else { format!("{}+{}", path, chunk * 8) };
+            fields.push(FieldLayout {
+                offset: offset_bits / 8 + chunk * 8,
+                bits: std::cmp::min(64, bits - chunk * 64) as u32,
+                path: Some(chunk_path),
+                pointer,
+            });
+        }
+        true
+    };
+    match ty {
+        Type::IntegerType { bits } => leaf(u64::from(*bits), false, fields),
+        Type::FPType(fpt) => leaf(fp_size(*fpt) as u64, false, fields),
+        Type::PointerType { .. } => leaf(POINTER_SIZE_BITS as u64, true, fields),
+        Type::ArrayType { element_type, num_elements } | Type::VectorType { element_type, num_elements } => {
+            let el_bits = match size_opaque_aware(element_type, proj) {
+                Some(bits) => bits as u64,
+                None => return false,
+            };
+            (0 .. *num_elements as u64).all(|i| {
+                typed_fields(element_type, offset_bits + i * el_bits, format!("{}[{}]", path, i), proj, fields)
+            })
+        },
+        Type::StructType { element_types, .. } => {
+            let mut field_offset_bits = offset_bits;
+            element_types.iter().enumerate().all(|(i, field_ty)| {
+                let complete = typed_fields(field_ty, field_offset_bits, format!("{}.{}", path, i), proj, fields);
+                field_offset_bits += size_opaque_aware(field_ty, proj).unwrap_or(0) as u64;
+                complete
+            })
+        },
+        Type::NamedStructType { .. } => match proj.get_inner_struct_type_from_named(ty) {
+            Some(inner) => {
+                let inner = inner.read().unwrap().clone();
+                typed_fields(&inner, offset_bits, path, proj, fields)
+            },
+            None => false,
+        },
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use crate::state::State;
+    use crate::backend::BtorBackend;
+    use crate::test_utils::*;
+    use llvm_ir::{Name, Type};
+
+    /// `struct node { int value; struct node *next; }`
+    fn node_type() -> Type {
+        Type::StructType {
+            element_types: vec![Type::i32(), Type::PointerType { pointee_type: Box::new(Type::i8()), addr_space: 0 }],
+            is_packed: false,
+        }
+    }
+
+    /// Allocate a linked list with the given values, labeling the nodes
+    /// `node0`, `node1`, etc. The last node's `next` is `last_next`, or if
+    /// that's `None`, points to the first node.
+    fn linked_list(state: &mut State<BtorBackend>, values: &[u32], last_next: Option<u64>) -> Result<Vec<u64>> {
+        let nodes: Vec<u64> = values.iter().enumerate().map(|(i, _)| {
+            let node = state.allocate(96_u64);
+            state.label_allocation(&node, format!("node{}", i), Some(node_type()))?;
+            Ok(node.as_u64().unwrap())
+        }).collect::<Result<_>>()?;
+        for (i, value) in values.iter().enumerate() {
+            let next = nodes.get(i + 1).copied().or(last_next).unwrap_or(nodes[0]);
+            state.write(&state.bv_from_u64(nodes[i], 64), state.bv_from_u32(*value, 32))?;
+            state.write(&state.bv_from_u64(nodes[i] + 4, 64), state.bv_from_u64(next, 64))?;
+        }
+        Ok(nodes)
+    }
+
+    #[test]
+    fn list() -> Result<()> {
+        let project = blank_project("test_mod", blank_function("test_func", vec![Name::from("test_bb")]));
+        let mut state = blank_state(&project, "test_func");
+        linked_list(&mut state, &[10, 20, 30], Some(0))?;
+        let graph = state.heap_graph()?;
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.nodes[1].fields[0], HeapField { offset: 0, bits: 32, path: Some(".0".to_owned()), value: FieldValue::Scalar(20) });
+        assert_eq!(graph.nodes[2].fields[1].value, FieldValue::Null);
+        assert_eq!(graph.edges.len(), 2);
+        assert!(graph.edges.iter().all(|e| !e.cycle));
+        let dot = graph.to_dot();
+        assert!(dot.contains("n0 [label=\"{node0 (12 bytes)|<f0> .0 = 10|<f1> .1 = &node1 (node1.0)}\"];"), "{}", dot);
+        assert!(dot.contains("n2 [label=\"{node2 (12 bytes)|<f0> .0 = 30|<f1> .1 = NULL}\"];"), "{}", dot);
+        assert!(dot.contains("  n0:f1 -> n1;\n  n1:f1 -> n2;\n"), "{}", dot);
+        Ok(())
+    }
+
+    #[test]
+    fn cyclic_list() -> Result<()> {
+        let project = blank_project("test_mod", blank_function("test_func", vec![Name::from("test_bb")]));
+        let mut state = blank_state(&project, "test_func");
+        linked_list(&mut state, &[1, 2, 3], None)?;
+        let graph = state.heap_graph()?;
+        assert_eq!(graph.edges.iter().map(|e| e.cycle).collect::<Vec<_>>(), vec![false, false, true]);
+        assert!(graph.to_dot().contains("  n2:f1 -> n0 [color=blue, style=bold];\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn dangling_pointer() -> Result<()> {
+        let project = blank_project("test_mod", blank_function("test_func", vec![Name::from("test_bb")]));
+        let mut state = blank_state(&project, "test_func");
+        linked_list(&mut state, &[1, 2], Some(0xdead_0000))?;
+        let graph = state.heap_graph()?;
+        assert_eq!(graph.edges[1].to, EdgeTarget::Dangling(0xdead_0000));
+        let dot = graph.to_dot();
+        assert!(dot.contains("x1 [shape=plaintext, fontcolor=red, label=\"dangling 0xdead0000\"];"), "{}", dot);
+        assert!(dot.contains("n1:f1 -> x1 [color=red, style=dashed];"), "{}", dot);
+        Ok(())
+    }
+
+    #[test]
+    fn untyped_allocation() {
+        let project = blank_project("test_mod", blank_function("test_func", vec![Name::from("test_bb")]));
+        let (fields, truncated) = field_layout(None, 160, &project);
+        assert!(!truncated);
+        assert_eq!(fields.iter().map(|f| (f.offset, f.bits)).collect::<Vec<_>>(), vec![(0, 64), (8, 64), (16, 32)]);
+        let (fields, truncated) = field_layout(Some(&Type::ArrayType { element_type: Box::new(Type::i8()), num_elements: 100 }), 800, &project);
+        assert!(truncated);
+        assert_eq!(fields.len(), MAX_FIELDS);
+        assert_eq!(fields[3].path.as_deref(), Some("[3]"));
+    }
+}
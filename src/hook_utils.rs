@@ -4,11 +4,11 @@
 use crate::backend::{Backend, BV};
 use crate::config::Concretize;
 use crate::error::*;
+use crate::middleware::ConstraintOrigin;
 use crate::solver_utils::PossibleSolutions;
 use crate::state::State;
 use llvm_ir::Operand;
 use log::{debug, info, warn};
-use reduce::Reduce;
 use std::convert::TryFrom;
 
 /// Set `num_bytes` bytes of memory at address `addr` each to the value `val`.
@@ -36,7 +36,8 @@ pub fn memset_bv<B: Backend>(state: &mut State<B>, addr: &B::BV, val: &B::BV, nu
         val.clone()
     };
 
-    match get_memcpy_length(state, num_bytes, &state.config.concretize_memcpy_lengths)? {
+    let concretize = state.config.concretize_memcpy_lengths.clone();
+    match get_memcpy_length(state, num_bytes, &concretize)? {
         MemcpyLength::Concrete(0) => debug!("Ignoring a memset of size 0 bytes"),
         MemcpyLength::Concrete(length_bytes) => {
             debug!("Processing a memset of size {} bytes", length_bytes);
@@ -92,12 +93,13 @@ pub fn memcpy<B: Backend>(state: &mut State<B>, dest: &Operand, src: &Operand, n
 
 /// Just like `memcpy()` above, but takes `BV`s instead of `Operand`s for its arguments.
 pub fn memcpy_bv<B: Backend>(state: &mut State<B>, dest: &B::BV, src: &B::BV, num_bytes: &B::BV) -> Result<B::BV> {
-    match get_memcpy_length(state, &num_bytes, &state.config.concretize_memcpy_lengths)? {
+    let concretize = state.config.concretize_memcpy_lengths.clone();
+    match get_memcpy_length(state, &num_bytes, &concretize)? {
         MemcpyLength::Concrete(0) => debug!("Ignoring a memcpy or memmove of size 0 bytes"),
         MemcpyLength::Concrete(length_bytes) => {
             debug!("Processing a memcpy or memmove of size {} bytes", length_bytes);
             // Do the operation as just one large read and one large write; let the memory choose the most efficient way to implement these.
-            let val = state.read(&src, length_bytes as u32 * 8)?;
+            let val = state.read_possibly_uninitialized(&src, length_bytes as u32 * 8)?;
             state.write(&dest, val)?;
         },
         MemcpyLength::Symbolic => {
@@ -107,15 +109,20 @@ pub fn memcpy_bv<B: Backend>(state: &mut State<B>, dest: &B::BV, src: &B::BV, nu
             } else {
                 info!("Processing a memcpy or memmove of symbolic size, up to {} bytes", max_num_bytes);
             }
+            // Read all of the source bytes before writing any, so that this is
+            // correct even if the source and destination overlap (memmove)
             let mut src_addr = src.clone();
+            let mut src_vals = Vec::new();
+            for _ in 0 ..= max_num_bytes {
+                src_vals.push(state.read_possibly_uninitialized(&src_addr, 8)?);
+                src_addr = src_addr.inc();
+            }
             let mut dest_addr = dest.clone();
             let mut bytes_written = state.zero(num_bytes.get_width());
-            for _ in 0 ..= max_num_bytes {
-                let src_val = state.read(&src_addr, 8)?;
-                let dst_val = state.read(&dest_addr, 8)?;
+            for src_val in src_vals {
+                let dst_val = state.read_possibly_uninitialized(&dest_addr, 8)?;
                 let should_write = num_bytes.ugt(&bytes_written);
                 state.write(&dest_addr, should_write.cond_bv(&src_val, &dst_val))?;
-                src_addr = src_addr.inc();
                 dest_addr = dest_addr.inc();
                 bytes_written = bytes_written.inc();
             }
@@ -137,7 +144,7 @@ enum MemcpyLength {
 /// that should be performed, considering the given `Concretize` option.
 ///
 /// Also accounts for the `max_memcpy_length` option in `state.config`.
-fn get_memcpy_length<B: Backend>(state: &State<B>, num_bytes: &B::BV, concretize: &Concretize) -> Result<MemcpyLength> {
+fn get_memcpy_length<B: Backend>(state: &mut State<B>, num_bytes: &B::BV, concretize: &Concretize) -> Result<MemcpyLength> {
     match state.get_possible_solutions_for_bv(num_bytes, 1)? {
         PossibleSolutions::Exactly(v) => {
             let single_val = v.iter().next().ok_or(Error::Unsat)?.as_u64().unwrap();
@@ -152,22 +159,26 @@ fn get_memcpy_length<B: Backend>(state: &State<B>, num_bytes: &B::BV, concretize
             if let Some(max_memcpy_length) = state.config.max_memcpy_length {
                 let max_memcpy_length_bv = state.bv_from_u64(max_memcpy_length, num_bytes.get_width());
                 if !state.sat_with_extra_constraints(std::iter::once(&num_bytes.ulte(&max_memcpy_length_bv)))? {
-                    let arbitrary_val = v.iter().next().unwrap().as_u64().unwrap();
+                    // report the smallest, so that the message is deterministic
+                    let arbitrary_val = v.iter().map(|val| val.as_u64().unwrap()).min().unwrap();
                     return Err(Error::OtherError(format!("Encountered a memcpy/memset/memmove with multiple possible lengths, but all of them are larger than max_memcpy_length {} bytes. One possible length is {} bytes.", max_memcpy_length, arbitrary_val)));
                 }
                 if state.sat_with_extra_constraints(std::iter::once(&num_bytes.ugt(&max_memcpy_length_bv)))? {
                     warn!("Encountered a memcpy/memset/memmove with multiple possible lengths, some of which are larger than max_memcpy_length {} bytes. Constraining the length to be at most {} bytes.", max_memcpy_length, max_memcpy_length);
-                    num_bytes.ulte(&max_memcpy_length_bv).assert()?;
+                    state.assert_constraint(ConstraintOrigin::Concretization, &num_bytes.ulte(&max_memcpy_length_bv))?;
                 }
             }
             let num_bytes_concrete = match concretize {
                 Concretize::Arbitrary => {
                     match state.config.max_memcpy_length {
-                        None => v.iter().next().unwrap().as_u64().unwrap(),
+                        // choose the smallest of the solutions we have, rather than
+                        // whichever comes first in the `HashSet`, so that the choice
+                        // is deterministic
+                        None => v.iter().map(|val| val.as_u64().unwrap()).min().unwrap(),
                         Some(max_memcpy_length) => {
-                            match v.iter().map(|val| val.as_u64().unwrap()).find(|val| *val <= max_memcpy_length) {
+                            match v.iter().map(|val| val.as_u64().unwrap()).filter(|val| *val <= max_memcpy_length).min() {
                                 Some(val) => val,
-                                None => state.get_a_solution_for_bv(num_bytes)?.unwrap().as_u64().unwrap()
+                                None => state.get_a_u64_solution_for_bv(num_bytes)?.ok_or(Error::Unsat)?
                             }
                         },
                     }
@@ -184,12 +195,156 @@ fn get_memcpy_length<B: Backend>(state: &State<B>, num_bytes: &B::BV, concretize
                         return get_memcpy_length(state, num_bytes, &**backup);
                     }
                 },
+                Concretize::Fork(n) => {
+                    let n = std::cmp::max(*n, 1);
+                    let mut lengths: Vec<u64> = match state.get_possible_solutions_for_bv(num_bytes, n)?.as_u64_solutions().unwrap() {
+                        PossibleSolutions::Exactly(v) => v.into_iter().collect(),
+                        PossibleSolutions::AtLeast(v) => v.into_iter().collect(),
+                    };
+                    lengths.sort_unstable();
+                    lengths.truncate(n);
+                    let (&first, others) = lengths.split_first().ok_or(Error::Unsat)?;
+                    for other in others.iter().rev() {
+                        state.save_backtracking_point_at_location(
+                            state.cur_loc.clone(),
+                            num_bytes._eq(&state.bv_from_u64(*other, num_bytes.get_width())),
+                        );
+                    }
+                    first
+                },
                 Concretize::Symbolic => return Ok(MemcpyLength::Symbolic),
             };
             info!("Encountered a memcpy/memset/memmove with multiple possible lengths; according to the concretization policy {:?}, chose a length of {} bytes and will constrain the length argument to be {} going forward", concretize, num_bytes_concrete, num_bytes_concrete);
             // actually constrain that `num_bytes` has to now be equal to our chosen concrete value
-            num_bytes._eq(&state.bv_from_u64(num_bytes_concrete, num_bytes.get_width())).assert()?;
+            if state.assert_constraint(ConstraintOrigin::Concretization, &num_bytes._eq(&state.bv_from_u64(num_bytes_concrete, num_bytes.get_width())))? {
+                state.note_known_constant(num_bytes, num_bytes_concrete);
+            }
             Ok(MemcpyLength::Concrete(num_bytes_concrete))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver_utils::SolutionCount;
+    use crate::test_utils::*;
+    use llvm_ir::Name;
+
+    #[test]
+    fn memcpy_struct() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        // copy a `struct { int a, b, c; }`
+        let src = state.allocate(96_u64);
+        let dest = state.allocate(96_u64);
+        let contents = state.new_bv_with_name(Name::from("contents"), 96)?;
+        state.write(&src, contents.clone())?;
+        let len = state.bv_from_u64(12, 64);
+        memcpy_bv(&mut state, &dest, &src, &len)?;
+        let copied = state.read(&dest, 96)?;
+        assert!(state.bvs_must_be_equal(&copied, &contents)?);
+        Ok(())
+    }
+
+    #[test]
+    fn memset_array() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        // zero-initialize an `int[4]`
+        let arr = state.allocate(128_u64);
+        let garbage = state.new_bv_with_name(Name::from("garbage"), 128)?;
+        state.write(&arr, garbage)?;
+        let (zero, len) = (state.zero(8), state.bv_from_u64(16, 64));
+        memset_bv(&mut state, &arr, &zero, &len)?;
+        let contents = state.read(&arr, 128)?;
+        assert!(state.bvs_must_be_equal(&contents, &state.zero(128))?);
+
+        // a symbolic byte value is repeated
+        let byte = state.new_bv_with_name(Name::from("byte"), 32)?;
+        let len = state.bv_from_u64(3, 64);
+        memset_bv(&mut state, &arr, &byte, &len)?;
+        let contents = state.read(&arr, 32)?;
+        let low_byte = byte.slice(7, 0);
+        let expected = state.zero(8).concat(&low_byte).concat(&low_byte).concat(&low_byte);
+        assert!(state.bvs_must_be_equal(&contents, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn overlapping_memmove() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+        let buf = state.allocate(64_u64);
+        let dest = buf.add(&state.bv_from_u64(2, 64));
+        // bytes 01 02 03 04 05 06 07 08, moved 2 bytes forward
+        let expected = state.bv_from_u64(0x0807_0403_0201_0201, 64);
+
+        // with a concrete length
+        state.write(&buf, state.bv_from_u64(0x0807_0605_0403_0201, 64))?;
+        let len = state.bv_from_u64(4, 64);
+        memcpy_bv(&mut state, &dest, &buf, &len)?;
+        let contents = state.read(&buf, 64)?;
+        assert!(state.bvs_must_be_equal(&contents, &expected)?);
+
+        // with a symbolic length, which may be 4
+        state.write(&buf, state.bv_from_u64(0x0807_0605_0403_0201, 64))?;
+        let len = state.new_bv_with_name(Name::from("len"), 64)?;
+        len.ulte(&state.bv_from_u64(4, 64)).assert();
+        memcpy_bv(&mut state, &dest, &buf, &len)?;
+        let contents = state.read(&buf, 64)?;
+        let len_is_4 = len._eq(&state.bv_from_u64(4, 64));
+        assert!(state.sat_with_extra_constraints(std::iter::once(&len_is_4))?);
+        assert!(!state.sat_with_extra_constraints([len_is_4, contents._ne(&expected)].iter())?);
+        Ok(())
+    }
+
+    #[test]
+    fn symbolic_length_memcpy() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+        let src = state.allocate(32_u64);
+        let dest = state.allocate(32_u64);
+        state.write(&src, state.bv_from_u64(0x0403_0201, 32))?;
+        let old = state.new_bv_with_name(Name::from("old"), 32)?;
+        state.write(&dest, old.clone())?;
+        let n = state.new_bv_with_name(Name::from("n"), 64)?;
+        n.ulte(&state.bv_from_u64(4, 64)).assert();
+
+        memcpy_bv(&mut state, &dest, &src, &n)?;
+        // the length is not concretized, so each byte is copied only if `i < n`
+        assert_eq!(state.get_possible_solutions_for_bv(&n, 5)?.count(), SolutionCount::Exactly(5));
+        for i in 0 .. 4 {
+            let byte = state.read(&dest.add(&state.bv_from_u64(i, 64)), 8)?;
+            let src_byte = state.bv_from_u64(i + 1, 8);
+            let old_byte = old.slice(8 * i as u32 + 7, 8 * i as u32);
+            let expected = n.ugt(&state.bv_from_u64(i, 64)).cond_bv(&src_byte, &old_byte);
+            assert!(state.bvs_must_be_equal(&byte, &expected)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn forked_length_memset() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+        state.config.concretize_memcpy_lengths = Concretize::Fork(8);
+        let buf = state.allocate(32_u64);
+        let n = state.new_bv_with_name(Name::from("n"), 64)?;
+        n.ulte(&state.bv_from_u64(2, 64)).assert();
+
+        let zero = state.zero(8);
+        memset_bv(&mut state, &buf, &zero, &n)?;
+        // this path gets the lowest length, and the others are left for later
+        assert!(state.bvs_must_be_equal(&n, &state.zero(64))?);
+        assert_eq!(state.count_backtracking_points(), 2);
+        Ok(())
+    }
+}
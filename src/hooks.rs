@@ -1,3 +1,5 @@
 pub mod allocation;
 pub mod exceptions;
+pub mod handles;
 pub mod intrinsics;
+pub mod time;
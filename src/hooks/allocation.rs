@@ -1,12 +1,13 @@
 //! Default hooks for malloc-related functions
 
-use crate::backend::Backend;
+use crate::backend::{Backend, BV};
 use crate::error::*;
 use crate::function_hooks::IsCall;
 use crate::alloc_utils;
 use crate::project::Project;
 use crate::return_value::*;
 use crate::state::State;
+use crate::tags::Tag;
 use llvm_ir::*;
 
 pub fn malloc_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
@@ -21,8 +22,26 @@ pub fn malloc_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p
         ty => return Err(Error::OtherError(format!("malloc_hook: expected return type to be a pointer type, but got {:?}", ty))),
     };
 
+    let size = state.operand_to_bv(bytes)?;
+    if state.get_tag(&size).is_none() {
+        let tag = Tag::at("allocation size", "malloc", &state.cur_loc);
+        state.tag_bv(&size, tag);
+    }
     let addr = alloc_utils::malloc(state, bytes)?;
-    Ok(ReturnValue::Return(addr))
+    let returned = maybe_fail("malloc", state, addr.clone())?;
+    state.record_heap_allocation(&addr, &returned);
+    Ok(ReturnValue::Return(returned))
+}
+
+/// If `Config.malloc_can_fail` is set, return either `addr` or `NULL`;
+/// otherwise just `addr`
+fn maybe_fail<'p, B: Backend + 'p>(what: &str, state: &mut State<'p, B>, addr: B::BV) -> Result<B::BV> {
+    if !state.config.malloc_can_fail {
+        return Ok(addr);
+    }
+    let succeeded = state.new_bv_with_name(Name::from(format!("{}_succeeded", what)), 1)?;
+    let null = state.zero(addr.get_width());
+    Ok(succeeded.cond_bv(&addr, &null))
 }
 
 pub fn calloc_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
@@ -43,12 +62,22 @@ pub fn calloc_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p
     };
 
     let addr = alloc_utils::calloc(state, num, size)?;
-    Ok(ReturnValue::Return(addr))
+    let returned = maybe_fail("calloc", state, addr.clone())?;
+    state.record_heap_allocation(&addr, &returned);
+    Ok(ReturnValue::Return(returned))
 }
 
-pub fn free_hook<'p, B: Backend + 'p>(_proj: &'p Project, _state: &mut State<'p, B>, _call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
-    // The simplest implementation of free() is a no-op.
-    // Our allocator won't ever reuse allocated addresses anyway.
+pub fn free_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 1);
+    let addr = &call.get_arguments()[0].0;
+    match addr.get_type() {
+        Type::PointerType { .. } => {},
+        ty => return Err(Error::OtherError(format!("free_hook: expected argument to be a pointer type, but got {:?}", ty))),
+    };
+    // Our allocator won't ever reuse allocated addresses, so all we do is
+    // record the free(), for detecting double frees.
+    let addr = state.operand_to_bv(addr)?;
+    state.free(&addr)?;
     Ok(ReturnValue::ReturnVoid)
 }
 
@@ -70,5 +99,7 @@ pub fn realloc_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'
     };
 
     let addr = alloc_utils::realloc(state, addr, new_size)?;
-    Ok(ReturnValue::Return(addr))
+    let returned = maybe_fail("realloc", state, addr.clone())?;
+    state.record_heap_allocation(&addr, &returned);
+    Ok(ReturnValue::Return(returned))
 }
@@ -0,0 +1,79 @@
+//! Hooks for functions which open and close handles (file descriptors and
+//! `FILE*`s); see `FunctionHooks::add_handle_hooks()`
+
+use crate::backend::{Backend, BV};
+use crate::error::*;
+use crate::function_hooks::IsCall;
+use crate::layout;
+use crate::project::Project;
+use crate::return_value::*;
+use crate::state::State;
+use llvm_ir::*;
+
+/// Description of the current location, e.g. `"fopen at x.c:10"`
+fn site<B: Backend>(what: &str, state: &State<B>) -> String {
+    let loc = match state.cur_loc.source_loc {
+        Some(source_loc) => source_loc.to_string(),
+        None => state.cur_loc.to_string_no_module(),
+    };
+    format!("{} at {}", what, loc)
+}
+
+/// Return either a new handle or `failure`, and track the handle
+fn open<'p, B: Backend + 'p>(what: &str, state: &mut State<'p, B>, value: u64, failure: B::BV) -> Result<ReturnValue<B::BV>> {
+    let succeeded = state.new_bv_with_name(Name::from(format!("{}_succeeded", what)), 1)?;
+    let returned = succeeded.cond_bv(&state.bv_from_u64(value, failure.get_width()), &failure);
+    let site = site(what, state);
+    state.open_handle(returned.clone(), value, site);
+    Ok(ReturnValue::Return(returned))
+}
+
+/// Close the handle which the first argument refers to, if we're tracking it
+fn close<'p, B: Backend + 'p>(state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    let handle = state.operand_to_bv(&call.get_arguments()[0].0)?;
+    state.close_handle(&handle)?;
+    match call.get_type() {
+        Type::VoidType => Ok(ReturnValue::ReturnVoid),
+        ty => Ok(ReturnValue::Return(state.zero(layout::size(&ty) as u32))),
+    }
+}
+
+/// `FILE *fopen(const char *path, const char *mode)`: either fails and
+/// returns `NULL`, or returns a new `FILE*`
+pub fn fopen_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    match call.get_type() {
+        Type::PointerType { .. } => {},
+        ty => return Err(Error::OtherError(format!("fopen_hook: expected return type to be a pointer type, but got {:?}", ty))),
+    };
+    let file = state.allocate(64_u64);
+    let site = site("fopen", state);
+    state.label_allocation(&file, format!("FILE from {}", site), None)?;
+    let value = file.as_u64().expect("allocate() should return a constant address");
+    let null = state.zero(file.get_width());
+    open("fopen", state, value, null)
+}
+
+/// `int open(const char *path, int flags, ...)`: either fails and returns
+/// `-1`, or returns a new file descriptor
+pub fn open_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    let bits = match call.get_type() {
+        Type::IntegerType { bits } => bits,
+        ty => return Err(Error::OtherError(format!("open_hook: expected return type to be an integer type, but got {:?}", ty))),
+    };
+    // 0, 1, and 2 are the standard streams; each handle on the path gets a distinct number after that
+    let fd = 3 + state.handles().len() as u64;
+    let minus_one = state.ones(bits);
+    open("open", state, fd, minus_one)
+}
+
+/// `int fclose(FILE *f)`: closes the handle and returns 0
+pub fn fclose_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 1);
+    close(state, call)
+}
+
+/// `int close(int fd)`: closes the handle and returns 0
+pub fn close_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 1);
+    close(state, call)
+}
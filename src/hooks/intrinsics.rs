@@ -1,17 +1,24 @@
 //! Default hooks for some LLVM intrinsics
 
 use crate::backend::{Backend, BV};
+#[cfg(feature = "fp")]
+use crate::config::FmaSemantics;
 use crate::error::*;
+#[cfg(feature = "fp")]
+use crate::float::{self, FloatFormat};
 use crate::function_hooks::IsCall;
 use crate::layout;
+use crate::middleware::ConstraintOrigin;
 use crate::project::Project;
 use crate::return_value::ReturnValue;
+use crate::rewrite::Expr;
 use crate::state::State;
 use crate::hook_utils;
 use llvm_ir::{Type, Typed};
 
 pub fn symex_memset<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
-    assert_eq!(call.get_arguments().len(), 4);
+    // 3 arguments for `memset()`-like functions, 4 for `llvm.memset` and `__memset_chk()`
+    assert!(call.get_arguments().len() >= 3);
     let addr = &call.get_arguments()[0].0;
     let val = &call.get_arguments()[1].0;
     let num_bytes = &call.get_arguments()[2].0;
@@ -54,12 +61,12 @@ pub fn symex_bswap<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>,
     }
 
     let arg = state.operand_to_bv(arg)?;
-    match argty {
+    let swapped = match argty {
         Type::IntegerType { bits: 16 } => {
             assert_eq!(arg.get_width(), 16);
             let high_byte = arg.slice(15, 8);
             let low_byte = arg.slice(7, 0);
-            Ok(ReturnValue::Return(low_byte.concat(&high_byte)))
+            low_byte.concat(&high_byte)
         },
         Type::IntegerType { bits: 32 } => {
             assert_eq!(arg.get_width(), 32);
@@ -67,9 +74,7 @@ pub fn symex_bswap<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>,
             let byte_1 = arg.slice(15, 8);
             let byte_2 = arg.slice(23, 16);
             let byte_3 = arg.slice(31, 24);
-            Ok(ReturnValue::Return(
-                byte_0.concat(&byte_1).concat(&byte_2).concat(&byte_3)
-            ))
+            byte_0.concat(&byte_1).concat(&byte_2).concat(&byte_3)
         },
         Type::IntegerType { bits: 48 } => {
             assert_eq!(arg.get_width(), 48);
@@ -79,9 +84,7 @@ pub fn symex_bswap<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>,
             let byte_3 = arg.slice(31, 24);
             let byte_4 = arg.slice(39, 32);
             let byte_5 = arg.slice(47, 40);
-            Ok(ReturnValue::Return(
-                byte_0.concat(&byte_1).concat(&byte_2).concat(&byte_3).concat(&byte_4).concat(&byte_5)
-            ))
+            byte_0.concat(&byte_1).concat(&byte_2).concat(&byte_3).concat(&byte_4).concat(&byte_5)
         },
         Type::IntegerType { bits: 64 } => {
             assert_eq!(arg.get_width(), 64);
@@ -93,12 +96,11 @@ pub fn symex_bswap<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>,
             let byte_5 = arg.slice(47, 40);
             let byte_6 = arg.slice(55, 48);
             let byte_7 = arg.slice(63, 56);
-            Ok(ReturnValue::Return(
-                byte_0.concat(&byte_1).concat(&byte_2).concat(&byte_3).concat(&byte_4).concat(&byte_5).concat(&byte_6).concat(&byte_7)
-            ))
+            byte_0.concat(&byte_1).concat(&byte_2).concat(&byte_3).concat(&byte_4).concat(&byte_5).concat(&byte_6).concat(&byte_7)
         },
-        _ => Err(Error::UnsupportedInstruction(format!("llvm.bswap with argument type {:?}", argty))),
-    }
+        _ => return Err(Error::UnsupportedInstruction(format!("llvm.bswap with argument type {:?}", argty))),
+    };
+    Ok(ReturnValue::Return(state.rewrite(Expr::Bswap(arg), swapped)?))
 }
 
 pub fn symex_objectsize<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
@@ -121,7 +123,14 @@ pub fn symex_assume<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>
     }
 
     if state.config.trust_llvm_assumes {
-        state.operand_to_bv(arg)?.assert()?;
+        let bv = state.operand_to_bv(arg)?;
+        if state.config.diagnose_unsat_paths && !state.sat_with_extra_constraints(std::iter::once(&bv))? {
+            state.diagnose_infeasible_condition("assume", arg, true);
+        }
+        if state.assert_constraint(ConstraintOrigin::Assume, &bv)? {
+            state.note_condition(&bv, true);
+        }
+        state.log_path_condition("assume", arg, true);
     } else {
         // just ignore the assume
     }
@@ -129,6 +138,20 @@ pub fn symex_assume<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>
     Ok(ReturnValue::ReturnVoid)
 }
 
+pub fn symex_lifetime_start<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 2);
+    let ptr = state.operand_to_bv(&call.get_arguments()[1].0)?;
+    state.start_lifetime(&ptr)?;
+    Ok(ReturnValue::ReturnVoid)
+}
+
+pub fn symex_lifetime_end<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 2);
+    let ptr = state.operand_to_bv(&call.get_arguments()[1].0)?;
+    state.end_lifetime(&ptr)?;
+    Ok(ReturnValue::ReturnVoid)
+}
+
 pub fn symex_uadd_with_overflow<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>, call: &dyn IsCall) -> Result<ReturnValue<B::BV>> {
     assert_eq!(call.get_arguments().len(), 2);
     let arg0 = &call.get_arguments()[0].0;
@@ -287,6 +310,53 @@ pub fn symex_ssub_sat<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p,
     Ok(ReturnValue::Return(arg0.ssubs(&arg1)))
 }
 
+#[cfg(feature = "fp")]
+pub fn symex_fma<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>, call: &dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    fused_multiply_add(state, call, FmaSemantics::Fused)
+}
+
+#[cfg(feature = "fp")]
+pub fn symex_fmuladd<'p, B: Backend>(_proj: &'p Project, state: &mut State<'p, B>, call: &dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    let semantics = state.config.fma_semantics;
+    fused_multiply_add(state, call, semantics)
+}
+
+/// `a * b + c`, on floats or vectors of floats, for `llvm.fma` and `llvm.fmuladd`
+#[cfg(feature = "fp")]
+fn fused_multiply_add<'p, B: Backend>(state: &mut State<'p, B>, call: &dyn IsCall, semantics: FmaSemantics) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 3);
+    let ty = call.get_arguments()[0].0.get_type();
+    if call.get_arguments().iter().any(|(arg, _)| arg.get_type() != ty) {
+        return Err(Error::OtherError(format!("fused_multiply_add: expected arguments to be of the same type, but got types {:?}", call.get_arguments().iter().map(|(arg, _)| arg.get_type()).collect::<Vec<_>>())));
+    }
+    let (fmt, num_elements) = match &ty {
+        Type::FPType(fpt) => (FloatFormat::from_fptype(*fpt)?, 1),
+        Type::VectorType { element_type, num_elements } => match &**element_type {
+            Type::FPType(fpt) => (FloatFormat::from_fptype(*fpt)?, *num_elements as u32),
+            ty => return Err(Error::OtherError(format!("fused_multiply_add: expected a vector of floating-point values, but elements are type {:?}", ty))),
+        },
+        ty => return Err(Error::OtherError(format!("fused_multiply_add: expected floating-point arguments, but got type {:?}", ty))),
+    };
+
+    let a = state.operand_to_bv(&call.get_arguments()[0].0)?;
+    let b = state.operand_to_bv(&call.get_arguments()[1].0)?;
+    let c = state.operand_to_bv(&call.get_arguments()[2].0)?;
+    let rm = state.config.fp_rounding_mode;
+    let width = fmt.width();
+    let result = (0 .. num_elements)
+        .map(|i| {
+            let (a, b, c) = (a.slice((i+1)*width - 1, i*width), b.slice((i+1)*width - 1, i*width), c.slice((i+1)*width - 1, i*width));
+            match semantics {
+                FmaSemantics::Fused => float::ffma(&a, &b, &c, fmt, rm),
+                FmaSemantics::Separate => float::fadd(&float::fmul(&a, &b, fmt, rm), &c, fmt, rm),
+            }
+        })
+        .reduce(|a, b| b.concat(&a))
+        .ok_or_else(|| Error::MalformedInstruction("fused multiply-add on vectors with 0 elements".to_owned()))?;
+
+    Ok(ReturnValue::Return(result))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -332,9 +402,9 @@ mod tests {
         let project = blank_project("test_mod", blank_function("test_func", vec![Name::from("test_bb")]));
         let mut state = blank_state(&project, "test_func");
 
-        let four = Operand::ConstantOperand(Constant::Int { bits: 8, value: 4 });
-        let sixty_four = Operand::ConstantOperand(Constant::Int { bits: 8, value: 64 });
-        let one_hundred = Operand::ConstantOperand(Constant::Int { bits: 8, value: 100 });
+        let four = int_const(8, 4);
+        let sixty_four = int_const(8, 64);
+        let one_hundred = int_const(8, 100);
 
         {
             let call = DummyCall::new_twoarg_call(four.clone(), one_hundred.clone());
@@ -368,9 +438,9 @@ mod tests {
         let project = blank_project("test_mod", blank_function("test_func", vec![Name::from("test_bb")]));
         let mut state = blank_state(&project, "test_func");
 
-        let four = Operand::ConstantOperand(Constant::Int { bits: 8, value: 4 });
-        let eight = Operand::ConstantOperand(Constant::Int { bits: 8, value: 8 });
-        let sixty_four = Operand::ConstantOperand(Constant::Int { bits: 8, value: 64 });
+        let four = int_const(8, 4);
+        let eight = int_const(8, 8);
+        let sixty_four = int_const(8, 64);
 
         {
             let call = DummyCall::new_twoarg_call(four.clone(), eight.clone());
@@ -405,9 +475,9 @@ mod tests {
         let mut state = blank_state(&project, "test_func");
 
         // these are the examples from the LLVM 9 docs
-        let two = Operand::ConstantOperand(Constant::Int { bits: 4, value: 2 });
-        let one = Operand::ConstantOperand(Constant::Int { bits: 4, value: 1 });
-        let six = Operand::ConstantOperand(Constant::Int { bits: 4, value: 6 });
+        let two = int_const(4, 2);
+        let one = int_const(4, 1);
+        let six = int_const(4, 6);
 
         let call = DummyCall::new_twoarg_call(two.clone(), one.clone());
         match symex_usub_sat(&project, &mut state, &call).unwrap() {
@@ -432,12 +502,12 @@ mod tests {
         let mut state = blank_state(&project, "test_func");
 
         // these are the examples from the LLVM 9 docs
-        let one = Operand::ConstantOperand(Constant::Int { bits: 4, value: 1 });
-        let two = Operand::ConstantOperand(Constant::Int { bits: 4, value: 2 });
-        let five = Operand::ConstantOperand(Constant::Int { bits: 4, value: 5 });
-        let six = Operand::ConstantOperand(Constant::Int { bits: 4, value: 6 });
-        let minusfour = Operand::ConstantOperand(Constant::Int { bits: 4, value: (-4_i64) as u64 });
-        let minusfive = Operand::ConstantOperand(Constant::Int { bits: 4, value: (-5_i64) as u64 });
+        let one = int_const(4, 1);
+        let two = int_const(4, 2);
+        let five = int_const(4, 5);
+        let six = int_const(4, 6);
+        let minusfour = int_const(4, (-4_i64) as u64);
+        let minusfive = int_const(4, (-5_i64) as u64);
 
         let call = DummyCall::new_twoarg_call(one.clone(), two.clone());
         match symex_sadd_sat(&project, &mut state, &call).unwrap() {
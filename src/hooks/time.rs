@@ -0,0 +1,112 @@
+//! Hooks for functions which read clocks and counters; see
+//! `FunctionHooks::add_time_hooks()`
+
+use crate::backend::{Backend, BV};
+use crate::clocks::Clock;
+use crate::error::*;
+use crate::function_hooks::IsCall;
+use crate::layout;
+use crate::project::Project;
+use crate::return_value::*;
+use crate::state::State;
+use llvm_ir::*;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// Get the bitwidth of the hooked function's integer return type
+fn return_bits(funcname: &str, call: &dyn IsCall) -> Result<u32> {
+    match call.get_type() {
+        Type::IntegerType { bits } => Ok(bits),
+        ty => Err(Error::OtherError(format!("{}: expected return type to be an integer type, but got {:?}", funcname, ty))),
+    }
+}
+
+/// Truncate or zero-extend a 64-bit `bv` to `bits`
+fn resize<V: BV>(bv: V, bits: u32) -> V {
+    if bits < 64 {
+        bv.slice(bits - 1, 0)
+    } else {
+        bv.zero_extend_to_bits(bits)
+    }
+}
+
+/// Write the given 64-bit fields to consecutive 8-byte slots starting at
+/// `ptr`, unless `ptr` must be `NULL`
+fn write_fields<'p, B: Backend + 'p>(state: &mut State<'p, B>, ptr: &B::BV, fields: Vec<B::BV>) -> Result<()> {
+    if state.bvs_must_be_equal(ptr, &state.zero(ptr.get_width()))? {
+        return Ok(());
+    }
+    for (i, field) in fields.into_iter().enumerate() {
+        let addr = ptr.add(&state.bv_from_u64(8 * i as u64, ptr.get_width()));
+        state.write(&addr, field)?;
+    }
+    Ok(())
+}
+
+/// Split a reading in nanoseconds into seconds and the remainder in units of
+/// `per_sec` (e.g. microseconds for `1_000_000`)
+fn split<'p, B: Backend + 'p>(state: &State<'p, B>, nanos: &B::BV, per_sec: u64) -> (B::BV, B::BV) {
+    let secs = nanos.udiv(&state.bv_from_u64(NANOS_PER_SEC, 64));
+    let frac = nanos.urem(&state.bv_from_u64(NANOS_PER_SEC, 64)).udiv(&state.bv_from_u64(NANOS_PER_SEC / per_sec, 64));
+    (secs, frac)
+}
+
+/// `time_t time(time_t *tloc)`: reads `CLOCK_REALTIME` in seconds, also
+/// storing the result to `tloc` if it isn't `NULL`
+pub fn time_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 1);
+    let bits = return_bits("time_hook", call)?;
+    let tloc = state.operand_to_bv(&call.get_arguments()[0].0)?;
+    let nanos = state.read_clock(Clock::Realtime)?;
+    let (secs, _) = split(state, &nanos, NANOS_PER_SEC);
+    write_fields(state, &tloc, vec![secs.clone()])?;
+    Ok(ReturnValue::Return(resize(secs, bits)))
+}
+
+/// `int clock_gettime(clockid_t clk_id, struct timespec *tp)`: reads the clock
+/// with the given id, storing `tv_sec` and `tv_nsec` to `tp`, and returns 0.
+///
+/// A symbolic `clk_id` is treated as `CLOCK_REALTIME`, the least constrained
+/// clock.
+pub fn clock_gettime_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 2);
+    let bits = return_bits("clock_gettime_hook", call)?;
+    let clk_id = state.operand_to_bv(&call.get_arguments()[0].0)?;
+    let tp = state.operand_to_bv(&call.get_arguments()[1].0)?;
+    let clock = clk_id.as_u64().map_or(Clock::Realtime, Clock::from_clockid);
+    let nanos = state.read_clock(clock)?;
+    let (secs, nsecs) = split(state, &nanos, NANOS_PER_SEC);
+    write_fields(state, &tp, vec![secs, nsecs])?;
+    Ok(ReturnValue::Return(state.zero(bits)))
+}
+
+/// `int gettimeofday(struct timeval *tv, struct timezone *tz)`: reads
+/// `CLOCK_REALTIME`, storing `tv_sec` and `tv_usec` to `tv`, and returns 0.
+/// `tz` is ignored.
+pub fn gettimeofday_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 2);
+    let bits = return_bits("gettimeofday_hook", call)?;
+    let tv = state.operand_to_bv(&call.get_arguments()[0].0)?;
+    let nanos = state.read_clock(Clock::Realtime)?;
+    let (secs, usecs) = split(state, &nanos, 1_000_000);
+    write_fields(state, &tv, vec![secs, usecs])?;
+    Ok(ReturnValue::Return(state.zero(bits)))
+}
+
+/// `clock_t clock(void)`: reads the process's CPU time, in units of
+/// `CLOCKS_PER_SEC` (which POSIX requires to be one million)
+pub fn clock_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 0);
+    let bits = return_bits("clock_hook", call)?;
+    let nanos = state.read_clock(Clock::ProcessCpu)?;
+    let ticks = nanos.udiv(&state.bv_from_u64(NANOS_PER_SEC / 1_000_000, 64));
+    Ok(ReturnValue::Return(resize(ticks, bits)))
+}
+
+/// `llvm.readcyclecounter` and `llvm.x86.rdtsc`: read the cycle counter
+pub fn readcyclecounter_hook<'p, B: Backend + 'p>(_proj: &'p Project, state: &mut State<'p, B>, call: &'p dyn IsCall) -> Result<ReturnValue<B::BV>> {
+    assert_eq!(call.get_arguments().len(), 0);
+    let bits = layout::size(&call.get_type()) as u32;
+    let cycles = state.read_clock(Clock::Cycles)?;
+    Ok(ReturnValue::Return(resize(cycles, bits)))
+}
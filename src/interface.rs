@@ -0,0 +1,701 @@
+//! A "binary interface" report describing the types, globals, and function
+//! signatures in a `Project`, as `haybale` sees them.
+//!
+//! This is mostly useful when writing hooks, preconditions, or other code which
+//! needs to know where things live in memory, without having to decode struct
+//! layouts from the LLVM IR by hand.
+
+use crate::layout::{is_union_type, DataLayout};
+use crate::project::Project;
+use either::Either;
+use llvm_ir::{Constant, Function, Instruction, Module, Name, Operand, Type};
+use llvm_ir::function::ParameterAttribute;
+use llvm_ir::types::FPType;
+use llvm_ir::module::GlobalVariable;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// A report of the types, global variables, and function signatures in a
+/// `Project`.
+///
+/// Get one with [`Project::interface_report()`](../struct.Project.html#method.interface_report).
+/// `InterfaceReport` implements `Display`, which produces a readable text dump;
+/// with the `serde` feature enabled, it can also be serialized (e.g., to JSON).
+///
+/// All sizes and offsets are according to the data layout of the module the
+/// type, global, or function comes from (see
+/// [`DataLayout`](../layout/struct.DataLayout.html)), so they match what
+/// native code compiled from that module expects: struct members start at a
+/// multiple of their ABI alignment, and structs are padded out to a multiple
+/// of their own alignment. Note that this is not how `haybale` lays out
+/// values during symbolic execution, where struct members are placed
+/// consecutively with no implicit padding.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InterfaceReport {
+    /// One entry for each named struct type in the `Project`, sorted by name
+    pub structs: Vec<StructLayout>,
+    /// One entry for each global variable in each module of the `Project`
+    pub globals: Vec<GlobalInfo>,
+    /// One entry for each function defined in each module of the `Project`
+    pub functions: Vec<FunctionSignature>,
+}
+
+/// The layout of a named struct type.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StructLayout {
+    /// Name of the struct type, e.g. `struct.Foo`
+    pub name: String,
+    /// Name of the module the definition was taken from. If the struct type is
+    /// defined in multiple modules, one non-opaque definition is chosen
+    /// arbitrarily.
+    pub module: String,
+    /// Total size of the struct in bits, or `None` if the struct (or some
+    /// member of it) is opaque, i.e., has no definition anywhere in the
+    /// `Project`.
+    pub size_bits: Option<usize>,
+    /// Whether the struct type is opaque, i.e., has no definition anywhere in
+    /// the `Project`. Opaque structs have no `fields`.
+    pub is_opaque: bool,
+    /// Whether the LLVM struct type is marked packed
+    pub is_packed: bool,
+    /// Whether this struct type appears to be a lowered C union. See
+    /// [`layout::is_union_type()`](../layout/fn.is_union_type.html).
+    pub is_union: bool,
+    /// The members of the struct, in order.
+    ///
+    /// Note that C bitfields are not visible in LLVM IR: `clang` lowers a run
+    /// of bitfields into one or more integer (or byte-array) "storage units",
+    /// and those storage units are what appear here.
+    pub fields: Vec<FieldLayout>,
+}
+
+/// The layout of a single member of a struct.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldLayout {
+    /// Index of the member within its containing struct
+    pub index: usize,
+    /// The member's type, in LLVM syntax
+    pub ty: String,
+    /// Offset of the member in bits from the start of the outermost struct
+    /// being described, or `None` if it follows an opaque member
+    pub offset_bits: Option<usize>,
+    /// Size of the member in bits, or `None` if it is (or contains) an opaque
+    /// struct
+    pub size_bits: Option<usize>,
+    /// If this member is itself a struct, its members (expanded one level
+    /// only). Offsets of these are still relative to the outermost struct.
+    pub subfields: Vec<FieldLayout>,
+}
+
+/// Information about a single global variable.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GlobalInfo {
+    /// Name of the global variable
+    pub name: String,
+    /// Name of the module containing this global variable
+    pub module: String,
+    /// Type of the global variable (not the type of a pointer to it), in LLVM
+    /// syntax
+    pub ty: String,
+    /// Size of the global variable in bits, or `None` if it has opaque type
+    pub size_bits: Option<usize>,
+    /// LLVM linkage type, e.g. `External` or `Internal`
+    pub linkage: String,
+    /// Whether the global variable is marked `constant`
+    pub is_constant: bool,
+    /// Whether this module provides an initializer (i.e., a definition) for
+    /// the global variable
+    pub has_initializer: bool,
+}
+
+/// The signature of a single function.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FunctionSignature {
+    /// Name of the function (mangled, if applicable)
+    pub name: String,
+    /// Name of the module containing this function
+    pub module: String,
+    /// Return type, in LLVM syntax
+    pub return_type: String,
+    /// Attributes on the return value. See notes on `ParameterInfo.attributes`.
+    pub return_attributes: Vec<String>,
+    /// The function's parameters, in order
+    pub parameters: Vec<ParameterInfo>,
+    /// Whether the function is variadic
+    pub is_var_arg: bool,
+    /// LLVM linkage type, e.g. `External` or `Internal`
+    pub linkage: String,
+    /// Whether the function is an unsplit coroutine (see
+    /// [`is_coroutine()`](fn.is_coroutine.html)), which `haybale` can't
+    /// analyze
+    pub is_coroutine: bool,
+}
+
+/// Information about a single function parameter.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParameterInfo {
+    /// Name of the parameter in the LLVM IR (often just a number)
+    pub name: String,
+    /// Type of the parameter, in LLVM syntax
+    pub ty: String,
+    /// Size of the parameter in bits, or `None` if it has opaque type
+    pub size_bits: Option<usize>,
+    /// Parameter attributes. The LLVM C API only exposes enum attributes (such
+    /// as `nonnull` or `dereferenceable(8)`) by their numeric attribute kind, so
+    /// these appear as e.g. `#35` or `#9(8)`. String attributes appear as
+    /// `"kind"` or `"kind"="value"`.
+    pub attributes: Vec<String>,
+}
+
+impl InterfaceReport {
+    /// Construct the `InterfaceReport` for the given `Project`.
+    ///
+    /// Most users will want [`Project::interface_report()`](../struct.Project.html#method.interface_report)
+    /// instead, which does the same thing.
+    pub fn new(proj: &Project) -> Self {
+        let struct_names: BTreeSet<&String> = proj.all_named_struct_types().map(|(name, _, _)| name).collect();
+        Self {
+            structs: struct_names.into_iter().map(|name| StructLayout::new(name, proj)).collect(),
+            globals: proj.all_global_vars().map(|(var, module)| GlobalInfo::new(var, module, proj)).collect(),
+            functions: proj.all_functions().map(|(func, module)| FunctionSignature::new(func, module, proj)).collect(),
+        }
+    }
+
+    /// Get the layout of the named struct type with the given name, if it
+    /// exists in the report. The leading `%` is optional.
+    pub fn get_struct(&self, name: &str) -> Option<&StructLayout> {
+        let name = name.trim_start_matches('%');
+        self.structs.iter().find(|s| s.name == name)
+    }
+}
+
+impl StructLayout {
+    fn new(name: &str, proj: &Project) -> Self {
+        let (def, module) = proj.get_named_struct_type_by_name(name)
+            .unwrap_or_else(|| panic!("Struct {:?} was listed in the Project, but not found by name", name));
+        let is_union = is_union_type(&Type::NamedStructType {
+            name: name.to_owned(),
+            ty: def.as_ref().map(Arc::downgrade),
+        });
+        match def {
+            None => Self {
+                name: name.to_owned(),
+                module: module.name.clone(),
+                size_bits: None,
+                is_opaque: true,
+                is_packed: false,
+                is_union,
+                fields: vec![],
+            },
+            Some(arc) => {
+                let ty: &Type = &arc.read().unwrap();
+                let is_packed = match ty {
+                    Type::StructType { is_packed, .. } => *is_packed,
+                    _ => false,
+                };
+                let layout = DataLayout::from_data_layout(&module.data_layout);
+                Self {
+                    name: name.to_owned(),
+                    module: module.name.clone(),
+                    size_bits: layout.size_bits(ty, proj),
+                    is_opaque: false,
+                    is_packed,
+                    is_union,
+                    fields: fields_of(ty, Some(0), &layout, proj, 1),
+                }
+            },
+        }
+    }
+}
+
+/// Get the `FieldLayout`s for the members of the given struct type (which may
+/// be either a `StructType` or `NamedStructType`), starting at the given base
+/// offset, according to the given `DataLayout`. Member structs are expanded up
+/// to `depth` more levels.
+///
+/// Returns an empty `Vec` if `ty` is not a struct type, or is an opaque struct
+/// type.
+fn fields_of(ty: &Type, base_offset_bits: Option<usize>, layout: &DataLayout, proj: &Project, depth: usize) -> Vec<FieldLayout> {
+    match ty {
+        Type::NamedStructType { .. } => match proj.get_inner_struct_type_from_named(ty) {
+            Some(arc) => fields_of(&arc.read().unwrap(), base_offset_bits, layout, proj, depth),
+            None => vec![],
+        },
+        Type::StructType { element_types, is_packed } => {
+            let offsets = layout.member_offsets_bits(element_types, *is_packed, proj);
+            element_types.iter().zip(offsets).enumerate().map(|(index, (el_ty, offset))| {
+                let offset_bits = match (base_offset_bits, offset) {
+                    (Some(base), Some(offset)) => Some(base + offset),
+                    _ => None,
+                };
+                FieldLayout {
+                    index,
+                    ty: type_to_string(el_ty),
+                    offset_bits,
+                    size_bits: layout.size_bits(el_ty, proj),
+                    subfields: if depth > 0 { fields_of(el_ty, offset_bits, layout, proj, depth - 1) } else { vec![] },
+                }
+            }).collect()
+        },
+        _ => vec![],
+    }
+}
+
+impl GlobalInfo {
+    fn new(var: &GlobalVariable, module: &Module, proj: &Project) -> Self {
+        // `var.ty` is the type of a pointer to the global variable
+        let ty = match &var.ty {
+            Type::PointerType { pointee_type, .. } => &**pointee_type,
+            ty => ty,
+        };
+        Self {
+            name: name_to_string(&var.name),
+            module: module.name.clone(),
+            ty: type_to_string(ty),
+            size_bits: DataLayout::from_data_layout(&module.data_layout).size_bits(ty, proj),
+            linkage: format!("{:?}", var.linkage),
+            is_constant: var.is_constant,
+            has_initializer: var.initializer.is_some(),
+        }
+    }
+}
+
+impl FunctionSignature {
+    fn new(func: &Function, module: &Module, proj: &Project) -> Self {
+        let layout = DataLayout::from_data_layout(&module.data_layout);
+        Self {
+            name: func.name.clone(),
+            module: module.name.clone(),
+            return_type: type_to_string(&func.return_type),
+            return_attributes: func.return_attributes.iter().map(attribute_to_string).collect(),
+            parameters: func.parameters.iter().map(|param| ParameterInfo {
+                name: name_to_string(&param.name),
+                ty: type_to_string(&param.ty),
+                size_bits: layout.size_bits(&param.ty, proj),
+                attributes: param.attributes.iter().map(attribute_to_string).collect(),
+            }).collect(),
+            is_var_arg: func.is_var_arg,
+            linkage: format!("{:?}", func.linkage),
+            is_coroutine: is_coroutine(func),
+        }
+    }
+}
+
+/// Is the function a coroutine which hasn't been split yet, that is, does it
+/// call any of the `llvm.coro.*` intrinsics? Async Rust functions and C++20
+/// coroutines look like this before the coroutine lowering passes have run.
+///
+/// `haybale` doesn't support these intrinsics, so symbolically executing such
+/// a function ends in `Error::UnsupportedInstruction` once it reaches one.
+/// (The resume and destroy functions produced by coroutine splitting are
+/// ordinary functions taking a pointer to the coroutine frame, and can be
+/// analyzed like any other.)
+pub fn is_coroutine(func: &Function) -> bool {
+    func.basic_blocks.iter()
+        .flat_map(|bb| bb.instrs.iter())
+        .any(|inst| match inst {
+            Instruction::Call(call) => match &call.function {
+                Either::Right(Operand::ConstantOperand(Constant::GlobalReference { name: Name::Name(name), .. })) => name.starts_with("llvm.coro."),
+                _ => false,
+            },
+            _ => false,
+        })
+}
+
+/// Render a `Name` without LLVM's sigil or quoting
+fn name_to_string(name: &Name) -> String {
+    match name {
+        Name::Name(s) => s.clone(),
+        Name::Number(n) => n.to_string(),
+    }
+}
+
+/// Render a `ParameterAttribute`.
+///
+/// The LLVM C API only exposes enum attributes by their numeric kind, so those
+/// are rendered as e.g. `#35`, or `#9(8)` if they carry a value. String
+/// attributes are rendered as `"kind"` or `"kind"="value"`.
+fn attribute_to_string(attr: &ParameterAttribute) -> String {
+    match attr {
+        ParameterAttribute::EnumAttribute { kind, value: None } => format!("#{}", kind),
+        ParameterAttribute::EnumAttribute { kind, value: Some(value) } => format!("#{}({})", kind, value),
+        ParameterAttribute::StringAttribute { kind, value } if value.is_empty() => format!("{:?}", kind),
+        ParameterAttribute::StringAttribute { kind, value } => format!("{:?}={:?}", kind, value),
+    }
+}
+
+/// Render a `Type` in (approximately) LLVM's own syntax
+pub(crate) fn type_to_string(ty: &Type) -> String {
+    match ty {
+        Type::VoidType => "void".to_owned(),
+        Type::IntegerType { bits } => format!("i{}", bits),
+        Type::PointerType { pointee_type, .. } => format!("{}*", type_to_string(pointee_type)),
+        Type::FPType(fpt) => match fpt {
+            FPType::Half => "half",
+            FPType::Single => "float",
+            FPType::Double => "double",
+            FPType::FP128 => "fp128",
+            FPType::X86_FP80 => "x86_fp80",
+            FPType::PPC_FP128 => "ppc_fp128",
+        }.to_owned(),
+        Type::FuncType { result_type, param_types, is_var_arg } => {
+            let mut params: Vec<String> = param_types.iter().map(type_to_string).collect();
+            if *is_var_arg {
+                params.push("...".to_owned());
+            }
+            format!("{} ({})", type_to_string(result_type), params.join(", "))
+        },
+        Type::VectorType { element_type, num_elements } => format!("<{} x {}>", num_elements, type_to_string(element_type)),
+        Type::ArrayType { element_type, num_elements } => format!("[{} x {}]", num_elements, type_to_string(element_type)),
+        Type::StructType { element_types, is_packed } => {
+            let elements: Vec<String> = element_types.iter().map(type_to_string).collect();
+            if *is_packed {
+                format!("<{{ {} }}>", elements.join(", "))
+            } else {
+                format!("{{ {} }}", elements.join(", "))
+            }
+        },
+        Type::NamedStructType { name, .. } => format!("%{}", name),
+        Type::X86_MMXType => "x86_mmx".to_owned(),
+        Type::MetadataType => "metadata".to_owned(),
+        Type::LabelType => "label".to_owned(),
+        Type::TokenType => "token".to_owned(),
+    }
+}
+
+/// Describe a size in bits as a number of bytes if it's a whole number of
+/// bytes, or a number of bits otherwise
+fn pretty_size(size_bits: Option<usize>) -> String {
+    match size_bits {
+        None => "unknown size".to_owned(),
+        Some(8) => "1 byte".to_owned(),
+        Some(bits) if bits % 8 == 0 => format!("{} bytes", bits / 8),
+        Some(bits) => format!("{} bits", bits),
+    }
+}
+
+/// Describe an offset in bits as a number of bytes if it's a whole number of
+/// bytes, or a number of bits otherwise
+fn pretty_offset(offset_bits: Option<usize>) -> String {
+    match offset_bits {
+        None => "+?".to_owned(),
+        Some(bits) if bits % 8 == 0 => format!("+{}", bits / 8),
+        Some(bits) => format!("+{}b", bits),
+    }
+}
+
+impl fmt::Display for StructLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.is_union { "union" } else { "struct" };
+        if self.is_opaque {
+            return writeln!(f, "{} %{}: opaque (no definition in the Project)", kind, self.name);
+        }
+        let packed = if self.is_packed { ", packed" } else { "" };
+        writeln!(f, "{} %{}: {}{}", kind, self.name, pretty_size(self.size_bits), packed)?;
+        for field in &self.fields {
+            write_field(f, field, 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_field(f: &mut fmt::Formatter, field: &FieldLayout, indent: usize) -> fmt::Result {
+    writeln!(f, "{:indent$}[{}] {:<6} {} ({})", "", field.index, pretty_offset(field.offset_bits), field.ty, pretty_size(field.size_bits), indent = indent * 2)?;
+    for subfield in &field.subfields {
+        write_field(f, subfield, indent + 1)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for GlobalInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "@{}: {} ({}), linkage {}", self.name, self.ty, pretty_size(self.size_bits), self.linkage)?;
+        if self.is_constant {
+            write!(f, ", constant")?;
+        }
+        if !self.has_initializer {
+            write!(f, ", declaration only")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FunctionSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut params: Vec<String> = self.parameters.iter().map(|p| {
+            if p.attributes.is_empty() {
+                format!("{} %{}", p.ty, p.name)
+            } else {
+                format!("{} {} %{}", p.ty, p.attributes.join(" "), p.name)
+            }
+        }).collect();
+        if self.is_var_arg {
+            params.push("...".to_owned());
+        }
+        write!(f, "{}(", self.name)?;
+        write!(f, "{}", params.join(", "))?;
+        write!(f, ") -> {}", self.return_type)?;
+        if !self.return_attributes.is_empty() {
+            write!(f, " {}", self.return_attributes.join(" "))?;
+        }
+        write!(f, ", linkage {}", self.linkage)?;
+        if self.is_coroutine {
+            write!(f, ", coroutine (unsupported)")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for InterfaceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Types:")?;
+        for s in &self.structs {
+            write!(f, "{}", s)?;
+        }
+        writeln!(f, "\nGlobals:")?;
+        for g in &self.globals {
+            writeln!(f, "  {} (module {})", g, g.module)?;
+        }
+        writeln!(f, "\nFunctions:")?;
+        for func in &self.functions {
+            writeln!(f, "  {} (module {})", func, func.module)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use llvm_ir::function::Parameter;
+    use llvm_ir::module::Linkage;
+    use std::collections::HashMap;
+    use std::sync::{Arc, RwLock};
+
+    fn named_struct(name: &str, arc: &Arc<RwLock<Type>>) -> Type {
+        Type::NamedStructType { name: name.to_owned(), ty: Some(Arc::downgrade(arc)) }
+    }
+
+    /// Builds a `Project` with a single module, modeling roughly what `clang`
+    /// produces for the following C:
+    ///
+    /// ```c
+    /// struct Inner { char b; int a; };
+    /// union U { int i; char c[8]; };
+    /// struct Bits { unsigned x : 3; unsigned y : 5; short z; };
+    /// struct Outer { struct Inner in; union U u; struct Opaque *o; };
+    /// const struct Outer g;
+    /// int f(struct Outer *nonnull p, ...);
+    /// ```
+    fn project_and_structs() -> (Project, Vec<Arc<RwLock<Type>>>) {
+        let inner = Arc::new(RwLock::new(Type::StructType {
+            element_types: vec![Type::i8(), Type::i32()],
+            is_packed: false,
+        }));
+        let union = Arc::new(RwLock::new(Type::StructType {
+            element_types: vec![Type::i64()],
+            is_packed: false,
+        }));
+        // clang lowers the two bitfields into a single i8 storage unit, and
+        // leaves the padding byte before `z` implicit
+        let bits = Arc::new(RwLock::new(Type::StructType {
+            element_types: vec![Type::i8(), Type::i16()],
+            is_packed: false,
+        }));
+        let outer = Arc::new(RwLock::new(Type::StructType {
+            element_types: vec![
+                named_struct("struct.Inner", &inner),
+                named_struct("union.U", &union),
+                Type::pointer_to(Type::NamedStructType { name: "struct.Opaque".to_owned(), ty: None }),
+            ],
+            is_packed: false,
+        }));
+        let mut named_struct_types = HashMap::new();
+        named_struct_types.insert("struct.Inner".to_owned(), Some(inner.clone()));
+        named_struct_types.insert("union.U".to_owned(), Some(union.clone()));
+        named_struct_types.insert("struct.Bits".to_owned(), Some(bits.clone()));
+        named_struct_types.insert("struct.Outer".to_owned(), Some(outer.clone()));
+        named_struct_types.insert("struct.Opaque".to_owned(), None);
+
+        let mut func = Function::new("f");
+        func.return_type = Type::i32();
+        func.is_var_arg = true;
+        func.linkage = Linkage::External;
+        func.parameters.push(Parameter {
+            name: Name::Number(0),
+            ty: Type::pointer_to(named_struct("struct.Outer", &outer)),
+            attributes: vec![ParameterAttribute::EnumAttribute { kind: 35, value: None }],
+        });
+
+        let global = GlobalVariable {
+            is_constant: true,
+            alignment: 8,
+            ..global_var("g", Type::pointer_to(named_struct("struct.Outer", &outer)), None)
+        };
+
+        let mut module = module_with_globals("interface_test", vec![func], vec![global]);
+        module.named_struct_types = named_struct_types;
+        module.data_layout = "e-m:o-i64:64-f80:128-n8:16:32:64-S128".to_owned();
+        (Project::from_module(module), vec![inner, union, bits, outer])
+    }
+
+    #[test]
+    fn struct_layouts() {
+        let (proj, _structs) = project_and_structs();
+        let report = proj.interface_report();
+        assert_eq!(report.structs.len(), 5);
+
+        let outer = report.get_struct("%struct.Outer").expect("Failed to find struct.Outer");
+        assert_eq!(outer.size_bits, Some((8 + 8 + 8) * 8));
+        assert_eq!(outer.fields.len(), 3);
+        assert_eq!(outer.fields[1].offset_bits, Some(8 * 8));
+        assert_eq!(outer.fields[2].ty, "%struct.Opaque*");
+        // nested structs are expanded one level, with offsets relative to the outer struct
+        let inner_fields = &outer.fields[0].subfields;
+        assert_eq!(inner_fields.len(), 2);
+        assert_eq!(inner_fields[1].offset_bits, Some(4 * 8));
+        assert_eq!(inner_fields[1].size_bits, Some(32));
+        assert!(inner_fields[1].subfields.is_empty());
+
+        // the i32 is aligned to 4 bytes, with padding after the i8
+        let inner = report.get_struct("struct.Inner").expect("Failed to find struct.Inner");
+        assert_eq!(inner.size_bits, Some(64));
+        assert_eq!(inner.fields[0].offset_bits, Some(0));
+        assert_eq!(inner.fields[0].size_bits, Some(8));
+        assert_eq!(inner.fields[1].offset_bits, Some(32));
+
+        let union = report.get_struct("union.U").expect("Failed to find union.U");
+        assert!(union.is_union);
+        assert_eq!(union.size_bits, Some(64));
+
+        let opaque = report.get_struct("struct.Opaque").expect("Failed to find struct.Opaque");
+        assert!(opaque.is_opaque);
+        assert_eq!(opaque.size_bits, None);
+        assert!(opaque.fields.is_empty());
+    }
+
+    #[test]
+    fn globals_and_functions() {
+        let (proj, _structs) = project_and_structs();
+        let report = proj.interface_report();
+        assert_eq!(report.globals.len(), 1);
+        let g = &report.globals[0];
+        assert_eq!(g.ty, "%struct.Outer");
+        assert_eq!(g.size_bits, Some(24 * 8));
+        assert!(g.is_constant);
+        assert!(!g.has_initializer);
+
+        assert_eq!(report.functions.len(), 1);
+        let f = &report.functions[0];
+        assert_eq!(f.return_type, "i32");
+        assert!(f.is_var_arg);
+        assert_eq!(f.parameters[0].size_bits, Some(64));
+        assert_eq!(f.parameters[0].attributes, vec!["#35".to_owned()]);
+    }
+
+    #[test]
+    fn data_layouts() {
+        let proj = blank_project("data_layout_test", blank_function("f", vec![Name::from("entry")]));
+        let i8_i64 = Type::StructType { element_types: vec![Type::i8(), Type::i64()], is_packed: false };
+        let packed = Type::StructType { element_types: vec![Type::i8(), Type::i64()], is_packed: true };
+        let array = Type::ArrayType { element_type: Box::new(Type::StructType { element_types: vec![Type::i32(), Type::i8()], is_packed: false }), num_elements: 3 };
+
+        // LLVM's defaults align i64 to only 4 bytes
+        let default = DataLayout::from_data_layout("");
+        assert_eq!(default.member_offsets_bits(&[Type::i8(), Type::i64()], false, &proj), vec![Some(0), Some(32), Some(96)]);
+        assert_eq!(default.size_bits(&i8_i64, &proj), Some(96));
+
+        let x86_64 = DataLayout::from_data_layout("e-m:e-i64:64-f80:128-n8:16:32:64-S128");
+        assert_eq!(x86_64.member_offsets_bits(&[Type::i8(), Type::i64()], false, &proj), vec![Some(0), Some(64), Some(128)]);
+        assert_eq!(x86_64.size_bits(&i8_i64, &proj), Some(128));
+        assert_eq!(x86_64.abi_alignment_bits(&i8_i64, &proj), Some(64));
+        assert_eq!(x86_64.size_bits(&packed, &proj), Some(72));
+        assert_eq!(x86_64.abi_alignment_bits(&packed, &proj), Some(8));
+        // each array element is padded out to the struct's alignment
+        assert_eq!(x86_64.size_bits(&array, &proj), Some(3 * 64));
+        // f80 is stored in 10 bytes, but each one takes up 16 in an array
+        assert_eq!(x86_64.size_bits(&Type::FPType(FPType::X86_FP80), &proj), Some(80));
+        assert_eq!(x86_64.alloc_size_bits(&Type::FPType(FPType::X86_FP80), &proj), Some(128));
+
+        let i386 = DataLayout::from_data_layout("e-m:e-p:32:32-f64:32:64-f80:32-n8:16:32-S128");
+        assert_eq!(i386.size_bits(&Type::pointer_to(Type::i8()), &proj), Some(32));
+        assert_eq!(i386.member_offsets_bits(&[Type::i8(), Type::double()], false, &proj), vec![Some(0), Some(32), Some(96)]);
+    }
+
+    /// A module with an unsplit coroutine (calling `llvm.coro.id`, as an async
+    /// fn would before coroutine lowering) and an ordinary function
+    fn project_with_coroutine() -> Project {
+        let mut coro = blank_function("async_fn", vec![Name::from("entry")]);
+        coro.return_type = Type::i32();
+        coro.basic_blocks[0].instrs.push(call("llvm.coro.id", Type::TokenType, vec![], Some("id")));
+        coro.basic_blocks[0].term = ret(int_const(32, 0));
+        let mut normal = blank_function("normal_fn", vec![Name::from("entry")]);
+        normal.return_type = Type::i32();
+        normal.basic_blocks[0].term = ret(int_const(32, 0));
+        project_with_functions("coro_test", vec![coro, normal])
+    }
+
+    #[test]
+    fn coroutines() {
+        use crate::backend::BtorBackend;
+        use crate::error::Error;
+        use crate::symex::{symex_function, ExecutionManager};
+        use crate::Config;
+
+        let proj = project_with_coroutine();
+        let report = proj.interface_report();
+        let flagged: Vec<(&str, bool)> = report.functions.iter().map(|f| (f.name.as_str(), f.is_coroutine)).collect();
+        assert_eq!(flagged, vec![("async_fn", true), ("normal_fn", false)]);
+
+        // the ordinary function still analyzes cleanly
+        let mut em: ExecutionManager<BtorBackend> = symex_function("normal_fn", &proj, Config::default());
+        assert!(matches!(em.next(), Some(Ok(_))));
+        // while the coroutine is reported as unsupported
+        let mut em: ExecutionManager<BtorBackend> = symex_function("async_fn", &proj, Config::default());
+        match em.next() {
+            Some(Err(Error::UnsupportedInstruction(msg))) => assert!(msg.contains("coroutines"), "unexpected message {:?}", msg),
+            res => panic!("Expected an UnsupportedInstruction error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn display_snapshot() {
+        let (proj, _structs) = project_and_structs();
+        let report = proj.interface_report();
+        assert_eq!(format!("{}", report), "\
+Types:
+struct %struct.Bits: 4 bytes
+  [0] +0     i8 (1 byte)
+  [1] +2     i16 (2 bytes)
+struct %struct.Inner: 8 bytes
+  [0] +0     i8 (1 byte)
+  [1] +4     i32 (4 bytes)
+struct %struct.Opaque: opaque (no definition in the Project)
+struct %struct.Outer: 24 bytes
+  [0] +0     %struct.Inner (8 bytes)
+    [0] +0     i8 (1 byte)
+    [1] +4     i32 (4 bytes)
+  [1] +8     %union.U (8 bytes)
+    [0] +8     i64 (8 bytes)
+  [2] +16    %struct.Opaque* (8 bytes)
+union %union.U: 8 bytes
+  [0] +0     i64 (8 bytes)
+
+Globals:
+  @g: %struct.Outer (24 bytes), linkage External, constant, declaration only (module interface_test)
+
+Functions:
+  f(%struct.Outer* #35 %0, ...) -> i32, linkage External (module interface_test)
+");
+    }
+}
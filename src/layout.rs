@@ -56,6 +56,101 @@ pub fn size_opaque_aware(ty: &Type, proj: &Project) -> Option<usize> {
     }
 }
 
+/// Describe the element at the given offset (in _bytes_) into a value of the
+/// given `Type`, as a path of struct field indices and array indices, e.g.
+/// `.1[3]` for element 3 of an array which is field 1 of a struct. The path
+/// stops at the innermost element containing the offset, so for offsets into
+/// the middle of a scalar, the path describes that scalar.
+///
+/// Returns `None` if the offset is not inside the `Type` (including if it is
+/// one past the end), or if the `Type` contains structs which have no
+/// definition in the entire `Project`.
+pub fn field_path(ty: &Type, offset_bytes: u64, proj: &Project) -> Option<String> {
+    let mut path = String::new();
+    let mut ty = ty.clone();
+    let mut offset_bits = offset_bytes * 8;
+    if offset_bits >= size_opaque_aware(&ty, proj)? as u64 {
+        return None;
+    }
+    loop {
+        let inner = match &ty {
+            Type::NamedStructType { .. } => proj.get_inner_struct_type_from_named(&ty)?.read().unwrap().clone(),
+            _ => ty.clone(),
+        };
+        ty = match inner {
+            Type::ArrayType { element_type, .. } | Type::VectorType { element_type, .. } => {
+                let el_size_bits = size_opaque_aware(&element_type, proj)? as u64;
+                if el_size_bits == 0 {
+                    break;
+                }
+                let index = offset_bits / el_size_bits;
+                path.push_str(&format!("[{}]", index));
+                offset_bits -= index * el_size_bits;
+                *element_type
+            },
+            Type::StructType { element_types, .. } => {
+                let mut field_start_bits = 0;
+                let mut field = None;
+                for (index, field_ty) in element_types.into_iter().enumerate() {
+                    let field_size_bits = size_opaque_aware(&field_ty, proj)? as u64;
+                    if offset_bits < field_start_bits + field_size_bits {
+                        field = Some((index, field_ty));
+                        break;
+                    }
+                    field_start_bits += field_size_bits;
+                }
+                let (index, field_ty) = field?;
+                path.push_str(&format!(".{}", index));
+                offset_bits -= field_start_bits;
+                field_ty
+            },
+            _ => break,
+        };
+    }
+    Some(path)
+}
+
+/// Returns `true` if the `Type` appears to be a C union.
+///
+/// LLVM has no union types: `clang` lowers a union to a named struct type
+/// called `union.<name>` (or `union.anon` for anonymous unions), whose single
+/// member is the union's most-aligned member, optionally followed by an array
+/// of `i8` padding bringing it up to the size of the union's largest member.
+/// So, `size()` of such a type is already the size of the largest member, and
+/// since our memory model is agnostic to the types of reads and writes,
+/// reading a union through any member type reinterprets the same bytes.
+///
+/// This checks for both the name and that pattern of members; if the
+/// definition isn't available (e.g. the type is opaque here), only the name
+/// is checked. It always returns `false` for types other than
+/// `NamedStructType`. Code which knows better can mark a type as a union
+/// explicitly, as with
+/// [`ArgSpec::Union`](../alloc_utils/enum.ArgSpec.html#variant.Union).
+pub fn is_union_type(ty: &Type) -> bool {
+    match ty {
+        Type::NamedStructType { name, ty } => {
+            name.starts_with("union.") && match ty.as_ref().and_then(|weak| weak.upgrade()) {
+                Some(arc) => has_union_members(&arc.read().unwrap()),
+                None => true,
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Does the `StructType` have the members `clang` gives a lowered union: a
+/// single member, optionally followed by an array of `i8` padding
+fn has_union_members(ty: &Type) -> bool {
+    match ty {
+        Type::StructType { element_types, .. } => match element_types.as_slice() {
+            [_] => true,
+            [_, Type::ArrayType { element_type, .. }] => **element_type == Type::i8(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 /// Get the size of the `FPType`, in bits
 pub fn fp_size(fpt: FPType) -> usize {
     match fpt {
@@ -68,6 +163,295 @@ pub fn fp_size(fpt: FPType) -> usize {
     }
 }
 
+/// The order in which the bytes of multi-byte values are laid out in memory
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+impl ByteOrder {
+    /// Get the byte order specified by the given LLVM data layout string, or
+    /// `None` if it doesn't specify one
+    pub fn from_data_layout(data_layout: &str) -> Option<Self> {
+        data_layout.split('-').find_map(|spec| match spec {
+            "e" => Some(ByteOrder::LittleEndian),
+            "E" => Some(ByteOrder::BigEndian),
+            _ => None,
+        })
+    }
+}
+
+/// The sizes and alignments of types specified by an LLVM data layout string
+/// (`Module.data_layout`), for describing how native code lays out values.
+///
+/// This differs from `haybale`'s own layout (see `size()`), which places
+/// struct members and array elements back to back. Here, as in native code,
+/// each struct member (unless the struct is packed) starts at a multiple of
+/// its ABI alignment, and structs and array elements are padded out to a
+/// multiple of their alignment.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DataLayout {
+    /// `(size, ABI alignment)` in bits for integer types, sorted by size
+    int_alignments: Vec<(usize, usize)>,
+    /// `(size, ABI alignment)` in bits for floating-point types
+    float_alignments: Vec<(usize, usize)>,
+    /// `(size, ABI alignment)` in bits for vector types
+    vector_alignments: Vec<(usize, usize)>,
+    /// Minimum ABI alignment of structs, in bits
+    aggregate_alignment: usize,
+    pointer_size: usize,
+    pointer_alignment: usize,
+}
+
+impl Default for DataLayout {
+    /// LLVM's defaults, which apply to anything a data layout string doesn't
+    /// specify
+    fn default() -> Self {
+        Self {
+            int_alignments: vec![(1, 8), (8, 8), (16, 16), (32, 32), (64, 32)],
+            float_alignments: vec![(16, 16), (32, 32), (64, 64), (128, 128)],
+            vector_alignments: vec![(64, 64), (128, 128)],
+            aggregate_alignment: 8,
+            pointer_size: 64,
+            pointer_alignment: 64,
+        }
+    }
+}
+
+impl DataLayout {
+    /// Parse the given LLVM data layout string. Specifications which it
+    /// doesn't contain (or which don't parse) take LLVM's defaults, so the
+    /// empty string gives all the defaults. Only the sizes and alignments
+    /// of address space 0 pointers are used.
+    pub fn from_data_layout(data_layout: &str) -> Self {
+        fn set(alignments: &mut Vec<(usize, usize)>, size: usize, alignment: usize) {
+            alignments.retain(|&(s, _)| s != size);
+            alignments.push((size, alignment));
+            alignments.sort_unstable();
+        }
+        let mut layout = Self::default();
+        for spec in data_layout.split('-') {
+            let mut parts = spec.split(':');
+            let kind = parts.next().unwrap_or("");
+            let numbers: Option<Vec<usize>> = parts.map(|part| part.parse().ok()).collect();
+            let numbers = match numbers {
+                Some(numbers) if !numbers.is_empty() => numbers,
+                _ => continue,
+            };
+            let (first, rest) = kind.split_at(kind.len().min(1));
+            match (first, rest.parse::<usize>()) {
+                ("i", Ok(size)) => set(&mut layout.int_alignments, size, numbers[0]),
+                ("f", Ok(size)) => set(&mut layout.float_alignments, size, numbers[0]),
+                ("v", Ok(size)) => set(&mut layout.vector_alignments, size, numbers[0]),
+                // an ABI alignment of 0 means no minimum
+                ("a", _) if rest.is_empty() => layout.aggregate_alignment = numbers[0].max(8),
+                ("p", _) if (rest.is_empty() || rest == "0") && numbers.len() >= 2 => {
+                    layout.pointer_size = numbers[0];
+                    layout.pointer_alignment = numbers[1];
+                },
+                _ => {},
+            }
+        }
+        layout
+    }
+
+    /// Get the ABI alignment of the `Type`, in bits.
+    ///
+    /// Returns `None` for structs which have no definition in the entire
+    /// `Project` (or aggregates containing them), and for types which have no
+    /// size, such as functions.
+    pub fn abi_alignment_bits(&self, ty: &Type, proj: &Project) -> Option<usize> {
+        match ty {
+            // integers without an alignment of their own take the alignment
+            // of the next larger integer type listed, or else the largest
+            Type::IntegerType { bits } => {
+                let bits = *bits as usize;
+                self.int_alignments.iter().find(|&&(size, _)| size >= bits)
+                    .or_else(|| self.int_alignments.last())
+                    .map(|&(_, alignment)| alignment)
+            },
+            Type::PointerType { .. } => Some(self.pointer_alignment),
+            Type::X86_MMXType => Some(64),
+            Type::FPType(fpt) => {
+                let bits = fp_size(*fpt);
+                Some(Self::lookup(&self.float_alignments, bits).unwrap_or_else(|| natural_alignment(bits)))
+            },
+            Type::VectorType { .. } => {
+                let bits = self.size_bits(ty, proj)?;
+                Some(Self::lookup(&self.vector_alignments, bits).unwrap_or_else(|| natural_alignment(bits)))
+            },
+            Type::ArrayType { element_type, .. } => self.abi_alignment_bits(element_type, proj),
+            Type::StructType { is_packed: true, .. } => Some(8),
+            Type::StructType { element_types, is_packed: false } => element_types.iter()
+                .map(|ty| self.abi_alignment_bits(ty, proj))
+                .try_fold(self.aggregate_alignment, |max, alignment| Some(max.max(alignment?))),
+            ty@Type::NamedStructType { .. } => self.abi_alignment_bits(&proj.get_inner_struct_type_from_named(ty)?.read().unwrap(), proj),
+            _ => None,
+        }
+    }
+
+    fn lookup(alignments: &[(usize, usize)], size: usize) -> Option<usize> {
+        alignments.iter().find(|&&(s, _)| s == size).map(|&(_, alignment)| alignment)
+    }
+
+    /// Get the size of the `Type`, in bits, not counting any padding after
+    /// its end (as LLVM's `DataLayout::getTypeSizeInBits()`). Structs include
+    /// the padding between and after their members.
+    ///
+    /// Returns `None` in the same cases as `abi_alignment_bits()`.
+    pub fn size_bits(&self, ty: &Type, proj: &Project) -> Option<usize> {
+        match ty {
+            Type::IntegerType { bits } => Some(*bits as usize),
+            Type::PointerType { .. } => Some(self.pointer_size),
+            Type::X86_MMXType => Some(64),
+            Type::FPType(fpt) => Some(fp_size(*fpt)),
+            Type::VectorType { element_type, num_elements } => Some(self.size_bits(element_type, proj)? * num_elements),
+            Type::ArrayType { element_type, num_elements } => Some(self.alloc_size_bits(element_type, proj)? * num_elements),
+            Type::StructType { element_types, is_packed } => {
+                let end = self.member_offsets_bits(element_types, *is_packed, proj).last().copied()??;
+                round_up(end, self.abi_alignment_bits(ty, proj)?)
+            },
+            ty@Type::NamedStructType { .. } => self.size_bits(&proj.get_inner_struct_type_from_named(ty)?.read().unwrap(), proj),
+            _ => None,
+        }
+    }
+
+    /// Get the number of bits between successive values of the `Type` in an
+    /// array: its size rounded up to whole bytes, then to its ABI alignment
+    /// (as LLVM's `DataLayout::getTypeAllocSizeInBits()`).
+    ///
+    /// Returns `None` in the same cases as `abi_alignment_bits()`.
+    pub fn alloc_size_bits(&self, ty: &Type, proj: &Project) -> Option<usize> {
+        round_up(round_up(self.size_bits(ty, proj)?, 8)?, self.abi_alignment_bits(ty, proj)?)
+    }
+
+    /// Get the offsets in bits of the members of a struct with the given
+    /// member types, followed by the offset of the end of the last member.
+    /// Offsets following a member whose size or alignment is unknown (see
+    /// `abi_alignment_bits()`) are `None`.
+    pub fn member_offsets_bits(&self, element_types: &[Type], is_packed: bool, proj: &Project) -> Vec<Option<usize>> {
+        let mut offsets = Vec::with_capacity(element_types.len() + 1);
+        let mut end = Some(0);
+        for ty in element_types {
+            let offset = if is_packed {
+                end
+            } else {
+                end.and_then(|end| round_up(end, self.abi_alignment_bits(ty, proj)?))
+            };
+            offsets.push(offset);
+            end = offset.and_then(|offset| Some(offset + self.alloc_size_bits(ty, proj)?));
+        }
+        offsets.push(end);
+        offsets
+    }
+}
+
+/// Round `bits` up to a multiple of `alignment` (which, if `0`, is taken to
+/// be `1`). Returns `None` on overflow.
+fn round_up(bits: usize, alignment: usize) -> Option<usize> {
+    let alignment = alignment.max(1);
+    Some(bits.checked_add(alignment - 1)? / alignment * alignment)
+}
+
+/// The alignment LLVM gives types of the given size which the data layout
+/// doesn't mention: the size rounded up to a power of two bytes
+fn natural_alignment(bits: usize) -> usize {
+    round_up(bits, 8).unwrap_or(bits).next_power_of_two().max(8)
+}
+
+/// Get the number of bits a value of the `Type` occupies in memory with the
+/// given `ByteOrder`. This is `size_opaque_aware()`, except that with
+/// `ByteOrder::BigEndian`, a scalar wider than a byte but not a whole number
+/// of bytes (e.g., an `i12`) is rounded up to a whole number of bytes, so that
+/// it can be byte-swapped.
+///
+/// Returns `None` in the same cases as `size_opaque_aware()`.
+pub fn memory_size(ty: &Type, byte_order: ByteOrder, proj: &Project) -> Option<usize> {
+    let bits = size_opaque_aware(ty, proj)?;
+    match byte_order {
+        ByteOrder::BigEndian if is_scalar(ty) && bits > 8 => Some(bits.div_ceil(8) * 8),
+        _ => Some(bits),
+    }
+}
+
+/// Convert `bv`, a value of the `Type`, to its layout in memory with the given
+/// `ByteOrder`. Our `Memory`s are little-endian, so for
+/// `ByteOrder::BigEndian`, this reverses the bytes of each scalar element of
+/// the value (zero-extending a top-level scalar to `memory_size()` first).
+/// Scalars inside aggregates which aren't a whole number of bytes are left
+/// as they are.
+///
+/// The inverse is `from_memory_layout()`.
+pub fn to_memory_layout<V: BV>(bv: V, ty: &Type, byte_order: ByteOrder, proj: &Project) -> Result<V> {
+    if byte_order == ByteOrder::LittleEndian {
+        return Ok(bv);
+    }
+    let bits = memory_size(ty, byte_order, proj)
+        .ok_or_else(|| Error::OtherError(format!("Can't lay out a value of type {:?}, which is opaque in the entire Project", ty)))?;
+    let bv = if bv.get_width() < bits as u32 { bv.zext(bits as u32 - bv.get_width()) } else { bv };
+    swap_scalar_bytes(bv, ty, proj)
+}
+
+/// Convert `bv`, the `memory_size()` bits read from memory, back to a value
+/// of the `Type`; the inverse of `to_memory_layout()`.
+pub fn from_memory_layout<V: BV>(bv: V, ty: &Type, byte_order: ByteOrder, proj: &Project) -> Result<V> {
+    if byte_order == ByteOrder::LittleEndian {
+        return Ok(bv);
+    }
+    let bits = size_opaque_aware(ty, proj)
+        .ok_or_else(|| Error::OtherError(format!("Can't lay out a value of type {:?}, which is opaque in the entire Project", ty)))?;
+    let bv = swap_scalar_bytes(bv, ty, proj)?;
+    Ok(if bv.get_width() > bits as u32 { bv.slice(bits as u32 - 1, 0) } else { bv })
+}
+
+/// Is the `Type` a scalar (as opposed to an aggregate or a vector)?
+fn is_scalar(ty: &Type) -> bool {
+    matches!(ty, Type::IntegerType { .. } | Type::PointerType { .. } | Type::FPType(_))
+}
+
+/// Reverse the bytes of each scalar element of `bv`, a value of the `Type`
+/// (or, for a scalar, of its `memory_size()`)
+fn swap_scalar_bytes<V: BV>(bv: V, ty: &Type, proj: &Project) -> Result<V> {
+    let element_types: Vec<Type> = match ty {
+        Type::NamedStructType { .. } => {
+            let inner = proj.get_inner_struct_type_from_named(ty)
+                .ok_or_else(|| Error::OtherError(format!("Can't lay out a value of type {:?}, which is opaque in the entire Project", ty)))?;
+            let inner = inner.read().unwrap().clone();
+            return swap_scalar_bytes(bv, &inner, proj);
+        },
+        Type::StructType { element_types, .. } => element_types.clone(),
+        Type::ArrayType { element_type, num_elements } | Type::VectorType { element_type, num_elements } => {
+            vec![(**element_type).clone(); *num_elements]
+        },
+        _ if bv.get_width().is_multiple_of(8) && bv.get_width() > 8 => {
+            let num_bytes = bv.get_width() / 8;
+            return Ok((1 .. num_bytes)
+                .map(|i| bv.slice(8 * i + 7, 8 * i))
+                .fold(bv.slice(7, 0), |acc, byte| acc.concat(&byte)));
+        },
+        _ => return Ok(bv),
+    };
+    // elements are laid out starting from the low bits of `bv`
+    let mut low_bit = 0;
+    let mut swapped: Option<V> = None;
+    for element_type in &element_types {
+        let bits = size_opaque_aware(element_type, proj)
+            .ok_or_else(|| Error::OtherError(format!("Can't lay out a value of type {:?}, which is opaque in the entire Project", element_type)))? as u32;
+        if bits == 0 {
+            continue;
+        }
+        let element = swap_scalar_bytes(bv.slice(low_bit + bits - 1, low_bit), element_type, proj)?;
+        swapped = Some(match swapped {
+            None => element,
+            Some(lower) => element.concat(&lower),
+        });
+        low_bit += bits;
+    }
+    Ok(swapped.unwrap_or(bv))
+}
+
 /// Get the offset (in _bytes_) of the element at the given index, as well as the
 /// `Type` of the element at that index.
 //
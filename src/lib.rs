@@ -6,7 +6,9 @@
 #![doc(html_root_url = "https://PLSysSec.github.io/haybale")]
 
 use llvm_ir::{Type, Typed};
+use llvm_ir::types::FPType;
 use std::collections::HashSet;
+use std::fmt;
 
 mod project;
 pub use project::Project;
@@ -16,12 +18,20 @@ pub use symex::*;
 
 pub mod layout;
 use layout::*;
+pub mod interface;
+pub mod analyze;
+pub mod corpus;
+pub mod diagnosis;
+pub mod dispatch;
+pub mod link;
 
 pub mod config;
 pub use config::Config;
+mod precondition;
 mod demangling;
 pub mod function_hooks;
 pub mod callbacks;
+pub mod checkers;
 mod hooks;
 pub mod alloc_utils;
 pub mod hook_utils;
@@ -36,9 +46,27 @@ mod global_allocations;
 pub mod watchpoints;
 
 pub mod solver_utils;
-use solver_utils::PossibleSolutions;
+#[cfg(feature = "fp")]
+pub mod float;
+pub mod tags;
+pub mod enums;
+pub mod clocks;
+pub mod handles;
+pub mod heap_graph;
+pub mod middleware;
+pub mod repro;
+pub mod rewrite;
+pub mod stats;
+#[cfg(feature = "bench-support")]
+pub mod bench_support;
+#[cfg(feature = "taint")]
+pub mod taint;
+use solver_utils::{Evaluated, PossibleSolutions, SolverResult};
 mod return_value;
 pub use return_value::ReturnValue;
+mod search_outcome;
+pub use search_outcome::{ExplorationSummary, LimitHit, SearchOutcome};
+use search_outcome::SearchTimer;
 mod error;
 pub use error::*;
 
@@ -48,56 +76,150 @@ use backend::*;
 #[cfg(test)]
 mod test_utils;
 
-/// A simple enum describing either an integer value or a pointer
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+/// A simple enum describing either a boolean, an integer value, a
+/// floating-point value, or a pointer
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SolutionValue {
+    /// An `i1`
+    Bool(bool),
     I8(i8),
     I16(i16),
     I32(i32),
     I64(i64),
+    /// A `float`, as its bit pattern (so that NaNs compare equal to themselves)
+    F32(u32),
+    /// A `double`, as its bit pattern (so that NaNs compare equal to themselves)
+    F64(u64),
+    /// A pointer which doesn't point into any known allocation
     Ptr(u64),
+    /// A pointer into a known allocation. Unlike raw addresses, these are
+    /// stable across runs.
+    PtrInto(PointerTarget),
+    /// The value is unconstrained: any value will do
+    DontCare,
+}
+
+impl fmt::Display for SolutionValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolutionValue::Bool(b) => write!(f, "{}", b),
+            SolutionValue::I8(i) => write!(f, "{}", i),
+            SolutionValue::I16(i) => write!(f, "{}", i),
+            SolutionValue::I32(i) => write!(f, "{}", i),
+            SolutionValue::I64(i) => write!(f, "{}", i),
+            SolutionValue::F32(bits) => write!(f, "{:?}", f32::from_bits(*bits)),
+            SolutionValue::F64(bits) => write!(f, "{:?}", f64::from_bits(*bits)),
+            SolutionValue::Ptr(u) => write!(f, "{:#x}", u),
+            SolutionValue::PtrInto(target) => write!(f, "{}", target),
+            SolutionValue::DontCare => write!(f, "any value"),
+        }
+    }
 }
 
 impl SolutionValue {
-    pub fn unwrap_to_i8(self) -> i8 {
+    pub fn unwrap_to_bool(&self) -> bool {
         match self {
-            SolutionValue::I8(i) => i,
+            SolutionValue::Bool(b) => *b,
+            _ => panic!("unwrap_to_bool on {:?}", self),
+        }
+    }
+
+    pub fn unwrap_to_i8(&self) -> i8 {
+        match self {
+            SolutionValue::I8(i) => *i,
             _ => panic!("unwrap_to_i8 on {:?}", self),
         }
     }
 
-    pub fn unwrap_to_i16(self) -> i16 {
+    pub fn unwrap_to_i16(&self) -> i16 {
         match self {
-            SolutionValue::I16(i) => i,
+            SolutionValue::I16(i) => *i,
             _ => panic!("unwrap_to_i16 on {:?}", self),
         }
     }
 
-    pub fn unwrap_to_i32(self) -> i32 {
+    pub fn unwrap_to_i32(&self) -> i32 {
         match self {
-            SolutionValue::I32(i) => i,
+            SolutionValue::I32(i) => *i,
             _ => panic!("unwrap_to_i32 on {:?}", self),
         }
     }
 
-    pub fn unwrap_to_i64(self) -> i64 {
+    pub fn unwrap_to_i64(&self) -> i64 {
         match self {
-            SolutionValue::I64(i) => i,
+            SolutionValue::I64(i) => *i,
             _ => panic!("unwrap_to_i64 on {:?}", self),
         }
     }
 
-    pub fn unwrap_to_ptr(self) -> u64 {
+    pub fn unwrap_to_f32(&self) -> f32 {
+        match self {
+            SolutionValue::F32(bits) => f32::from_bits(*bits),
+            _ => panic!("unwrap_to_f32 on {:?}", self),
+        }
+    }
+
+    pub fn unwrap_to_f64(&self) -> f64 {
+        match self {
+            SolutionValue::F64(bits) => f64::from_bits(*bits),
+            _ => panic!("unwrap_to_f64 on {:?}", self),
+        }
+    }
+
+    pub fn unwrap_to_ptr(&self) -> u64 {
         match self {
-            SolutionValue::Ptr(u) => u,
+            SolutionValue::Ptr(u) => *u,
             _ => panic!("unwrap_to_ptr on {:?}", self),
         }
     }
+
+    pub fn unwrap_to_ptr_target(&self) -> &PointerTarget {
+        match self {
+            SolutionValue::PtrInto(target) => target,
+            _ => panic!("unwrap_to_ptr_target on {:?}", self),
+        }
+    }
+}
+
+/// Where a pointer points, in terms of the allocation it points into rather
+/// than its raw address; see
+/// [`State::describe_pointer()`](struct.State.html#method.describe_pointer)
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointerTarget {
+    /// Description of the allocation, e.g. `@buf` for a global, `%buf` for an
+    /// `alloca`, `malloc at x.c:10` for a heap allocation, or whatever label
+    /// was given to
+    /// [`State::label_allocation()`](struct.State.html#method.label_allocation)
+    pub allocation: String,
+    /// Offset into the allocation, in bytes. This may be equal to the size of
+    /// the allocation, for pointers one past the end.
+    pub offset: u64,
+    /// If the allocation's type is known, the struct fields and array
+    /// elements leading to `offset`, e.g. `.1[3]`; see
+    /// [`layout::field_path()`](layout/fn.field_path.html)
+    pub field_path: Option<String>,
+}
+
+impl fmt::Display for PointerTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.allocation)?;
+        if self.offset != 0 {
+            write!(f, " + {}", self.offset)?;
+        }
+        match &self.field_path {
+            Some(path) if !path.is_empty() => write!(f, " ({}{})", self.allocation, path),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Given a function, find values of its inputs such that it returns zero.
-/// Assumes that the function takes (some number of) integer and/or pointer
-/// arguments, and returns an integer.
+/// Assumes that the function takes (some number of) integer, `float`,
+/// `double`, and/or pointer arguments, and returns an integer or a `float` or
+/// `double`. For floating-point return values, either zero (positive or
+/// negative) counts.
 /// Pointer arguments will be assumed to be never NULL.
 ///
 /// `project`: The `Project` (set of LLVM modules) in which symbolic execution
@@ -105,8 +227,15 @@ impl SolutionValue {
 /// [`Config`](struct.Config.html)), we will try to enter calls to any functions
 /// defined in the `Project`.
 ///
-/// Returns `Ok(None)` if there are no values of the inputs such that the
-/// function returns zero.
+/// The `answer` of the returned `SearchOutcome` is `None` if no values of the
+/// inputs were found such that the function returns zero. Paths which exceed
+/// the `loop_bound`, or on which a solver query fails (e.g., times out), are
+/// skipped and counted in the `summary`; if the `summary` reports any such
+/// paths, then a `None` answer doesn't rule out that such values exist.
+///
+/// Parameters which don't affect whether the function returns zero (e.g.,
+/// because the function ignores them) may be reported as
+/// `SolutionValue::DontCare`.
 ///
 /// Note: `find_zero_of_func()` may be of some use itself, but also serves as an
 /// example of how you can use the other public functions in the crate.
@@ -114,61 +243,117 @@ pub fn find_zero_of_func<'p>(
     funcname: &str,
     project: &'p Project,
     config: Config<'p, BtorBackend>
-) -> std::result::Result<Option<Vec<SolutionValue>>, String> {
+) -> std::result::Result<SearchOutcome<Option<Vec<SolutionValue>>>, String> {
+    let timer = SearchTimer::start();
+    let mut summary = ExplorationSummary::default();
     let mut em: ExecutionManager<BtorBackend> = symex_function(funcname, project, config);
 
     // constrain pointer arguments to be not-null
     let (func, _) = project.get_func_by_name(funcname).unwrap_or_else(|| panic!("Failed to find function named {:?}", funcname));
+    let mut assumptions = vec![];
     for (param, bv) in func.parameters.iter().zip(em.param_bvs()) {
         if let Type::PointerType { .. } = param.get_type() {
             bv._ne(&em.state().zero(bv.get_width())).assert();
+            assumptions.push(format!("{} is not NULL", param.name));
         }
     }
+    em.state().forget_cached_solver_results();
 
     let returnwidth = size(&func.return_type);
     let zero = em.state().zero(returnwidth as u32);
+    let is_zero = |bvretval: &<BtorBackend as Backend>::BV| match &func.return_type {
+        #[cfg(feature = "fp")]
+        Type::FPType(fpt) => float::FloatFormat::from_fptype(*fpt)
+            .map(|fmt| float::fcmp(bvretval, &zero, fmt, llvm_ir::FPPredicate::OEQ)),
+        #[cfg(not(feature = "fp"))]
+        Type::FPType(_) => Err(Error::UnsupportedInstruction("comparing a floating-point return value to zero (floating-point support requires the `fp` feature)".to_owned())),
+        _ => Ok(bvretval._eq(&zero)),
+    };
     let mut found = false;
     while let Some(bvretval) = em.next() {
         match bvretval {
             Ok(ReturnValue::ReturnVoid) => panic!("Function shouldn't return void"),
-            Ok(ReturnValue::Throw(_)) => continue,  // we're looking for values that result in _returning_ zero, not _throwing_ zero
-            Ok(ReturnValue::Abort) => continue,
+            Ok(ReturnValue::Throw(_)) => summary.paths_completed += 1,  // we're looking for values that result in _returning_ zero, not _throwing_ zero
+            Ok(ReturnValue::Abort) => summary.paths_completed += 1,
             Ok(ReturnValue::Return(bvretval)) => {
+                summary.paths_completed += 1;
                 let state = em.mut_state();
-                bvretval._eq(&zero).assert();
-                if state.sat()? {
-                    found = true;
-                    break;
+                is_zero(&bvretval).map_err(|e| state.full_error_message_with_context(e))?.assert();
+                state.forget_cached_solver_results();
+                match state.check() {
+                    SolverResult::Sat => {
+                        found = true;
+                        break;
+                    },
+                    SolverResult::Unsat => {},
+                    // we couldn't determine whether this path returns zero;
+                    // report it as inconclusive rather than "no zero"
+                    SolverResult::Unknown => {
+                        summary.paths_inconclusive += 1;
+                        summary.hit(LimitHit::SolverQuery);
+                    },
                 }
             },
-            Err(Error::LoopBoundExceeded(_)) => continue,  // ignore paths that exceed the loop bound, keep looking
-            Err(e) => return Err(em.state().full_error_message_with_context(e)),
+            Err(e) => if !summary.record_error(&e) {
+                return Err(em.state().full_error_message_with_context(e));
+            },  // otherwise ignore paths that exceed the loop bound etc, and keep looking
         }
     }
 
     let param_bvs: Vec<_> = em.param_bvs().clone();
+    summary.solver_stats = em.state().solver_stats();
     let state = em.mut_state();
     if found {
         // in this case state.sat() must have passed
-        Ok(Some(func.parameters.iter().zip(param_bvs.iter()).map(|(p, bv)| {
-            let param_as_u64 = state.get_a_solution_for_bv(bv)?
-                .expect("since state.sat() passed, expected a solution for each var")
-                .as_u64()
-                .expect("parameter more than 64 bits wide");
+        let shrunk = match state.config.shrink_witnesses {
+            Some(budget) => state.shrink_witness(&param_bvs, budget)?,
+            None => None,
+        };
+        let answer = func.parameters.iter().zip(param_bvs.iter()).enumerate().map(|(i, (p, bv))| {
+            let param_as_u64 = match state.eval_bv(bv)? {
+                Evaluated::NoModel => return Err(Error::OtherError("since state.sat() passed, expected a solution for each var".to_owned())),
+                Evaluated::DontCare => return Ok(SolutionValue::DontCare),
+                Evaluated::Concrete(solution) => match shrunk.as_ref().and_then(|shrunk| shrunk[i]) {
+                    Some(value) => value,
+                    None => solution.as_u64()
+                        .ok_or_else(|| Error::OtherError(format!("parameter {} is more than 64 bits wide", p.name)))?,
+                },
+            };
             Ok(match &p.ty {
+                Type::IntegerType { bits: 1 } => SolutionValue::Bool(param_as_u64 != 0),
                 Type::IntegerType { bits: 8 } => SolutionValue::I8(param_as_u64 as i8),
                 Type::IntegerType { bits: 16 } => SolutionValue::I16(param_as_u64 as i16),
                 Type::IntegerType { bits: 32 } => SolutionValue::I32(param_as_u64 as i32),
                 Type::IntegerType { bits: 64 } => SolutionValue::I64(param_as_u64 as i64),
-                Type::PointerType { .. } => SolutionValue::Ptr(param_as_u64),
+                Type::FPType(FPType::Single) => SolutionValue::F32(param_as_u64 as u32),
+                Type::FPType(FPType::Double) => SolutionValue::F64(param_as_u64),
+                Type::PointerType { .. } => match state.describe_pointer(param_as_u64) {
+                    Some(target) => SolutionValue::PtrInto(target),
+                    None => SolutionValue::Ptr(param_as_u64),
+                },
                 ty => unimplemented!("Function parameter with type {:?}", ty)
             })
-        }).collect::<Result<_>>()?))
+        }).collect::<Result<_>>()?;
+        summary.assumptions = assumptions;
+        Ok(timer.finish(Some(answer), summary))
     } else {
-        Ok(None)
+        Ok(timer.finish(None, summary))
     }
 }
 
+/// Like [`find_zero_of_func()`](fn.find_zero_of_func.html), but returns just
+/// the answer, without the `ExplorationSummary`.
+///
+/// This is the signature `find_zero_of_func()` had in previous versions of
+/// `haybale`; it will be removed in a future release.
+pub fn find_zero_of_func_simple<'p>(
+    funcname: &str,
+    project: &'p Project,
+    config: Config<'p, BtorBackend>
+) -> std::result::Result<Option<Vec<SolutionValue>>, String> {
+    find_zero_of_func(funcname, project, config).map(|outcome| outcome.answer)
+}
+
 /// Get a description of the possible return values of a function, for given
 /// argument values.
 /// Considers all possible paths through the function given these arguments.
@@ -195,6 +380,13 @@ pub fn find_zero_of_func<'p>(
 /// If there are more than `n` possible solutions, this returns a
 /// `PossibleSolutions::AtLeast` containing at least `n+1` solutions.
 ///
+/// Paths which exceed the `loop_bound`, or on which a solver query fails (e.g.,
+/// times out), are skipped and counted in the `summary` of the returned
+/// `SearchOutcome`; if the `summary` reports any such paths, then the `answer`
+/// may be missing some possible values. Paths which end in any other error
+/// end the search with that error. Return values (and `thrown_size`) wider
+/// than 64 bits aren't supported, and are also an error.
+///
 /// Note: `get_possible_return_values_of_func()` may be of some use itself, but
 /// also serves as an example of how you can use the other public functions in
 /// the crate.
@@ -205,10 +397,21 @@ pub fn get_possible_return_values_of_func<'p>(
     config: Config<'p, BtorBackend>,
     thrown_size: Option<u32>,
     n: usize,
-) -> PossibleSolutions<ReturnValue<u64>> {
+) -> std::result::Result<SearchOutcome<PossibleSolutions<ReturnValue<u64>>>, String> {
+    let timer = SearchTimer::start();
+    let mut summary = ExplorationSummary::default();
+    let (func, _) = project.get_func_by_name(funcname).ok_or_else(|| format!("Failed to find function {:?}", funcname))?;
+    let return_width = size(&func.return_type);
+    if return_width > 64 {
+        return Err(format!("{} returns a {}-bit value, but only return values of up to 64 bits are supported", funcname, return_width));
+    }
+    if let Some(thrown_size) = thrown_size {
+        if thrown_size > 64 {
+            return Err(format!("thrown_size is {} bits, but only thrown values of up to 64 bits are supported", thrown_size));
+        }
+    }
     let mut em: ExecutionManager<BtorBackend> = symex_function(funcname, project, config);
 
-    let (func, _) = project.get_func_by_name(funcname).expect("Failed to find function");
     for (param, arg) in func.parameters.iter().zip(args.into_iter()) {
         if let Some(val) = arg {
             let val = em.state().bv_from_u64(val, size(&param.ty) as u32);
@@ -216,12 +419,16 @@ pub fn get_possible_return_values_of_func<'p>(
         }
     }
 
-    let return_width = size(&func.return_type);
     let mut candidate_values = HashSet::<ReturnValue<u64>>::new();
     let mut have_throw = false;  // is there at least one `ReturnValue::Throw` in the `candidate_values`
     while let Some(bvretval) = em.next() {
+        if bvretval.is_ok() {
+            summary.paths_completed += 1;
+        }
         match bvretval {
-            Err(e) => panic!("{}", em.state().full_error_message_with_context(e)),
+            Err(e) => if !summary.record_error(&e) {
+                return Err(em.state().full_error_message_with_context(e));
+            },
             Ok(ReturnValue::ReturnVoid) => {
                 candidate_values.insert(ReturnValue::ReturnVoid);
                 if candidate_values.len() > n {
@@ -242,15 +449,16 @@ pub fn get_possible_return_values_of_func<'p>(
                         bvretval._ne(&state.bv_from_u64(*candidate, return_width as u32)).assert();
                     }
                 }
-                match state.get_possible_solutions_for_bv(&bvretval, n).unwrap() {
+                state.forget_cached_solver_results();
+                match state.get_possible_solutions_for_bv(&bvretval, n)?.as_u64_solutions().ok_or("expected solutions of at most 64 bits")? {
                     PossibleSolutions::Exactly(v) => {
-                        candidate_values.extend(v.iter().map(|bvsol| ReturnValue::Return(bvsol.as_u64().unwrap())));
+                        candidate_values.extend(v.into_iter().map(ReturnValue::Return));
                         if candidate_values.len() > n {
                             break;
                         }
                     },
                     PossibleSolutions::AtLeast(v) => {
-                        candidate_values.extend(v.iter().map(|bvsol| ReturnValue::Return(bvsol.as_u64().unwrap())));
+                        candidate_values.extend(v.into_iter().map(ReturnValue::Return));
                         break;  // the total must be over n at this point
                     },
                 };
@@ -259,29 +467,32 @@ pub fn get_possible_return_values_of_func<'p>(
                 let state = em.mut_state();
                 match thrown_size {
                     None => if !have_throw {
-                        candidate_values.insert(ReturnValue::Throw(bvptr.as_u64().unwrap()));
+                        let ptr = state.get_a_u64_solution_for_bv(&bvptr)?
+                            .ok_or_else(|| Error::OtherError("expected a solution for the thrown pointer".to_owned()))?;
+                        candidate_values.insert(ReturnValue::Throw(ptr));
                         have_throw = true;
                         if candidate_values.len() > n {
                             break;
                         }
                     },
                     Some(thrown_size) => {
-                        let thrown_value = state.read(&bvptr, thrown_size).unwrap();
+                        let thrown_value = state.read(&bvptr, thrown_size)?;
                         // rule out all the thrown values we already have - we're interested in new values
                         for candidate in candidate_values.iter() {
                             if let ReturnValue::Throw(candidate) = candidate {
-                                thrown_value._ne(&state.bv_from_u64(*candidate, return_width as u32)).assert();
+                                thrown_value._ne(&state.bv_from_u64(*candidate, thrown_size)).assert();
                             }
                         }
-                        match state.get_possible_solutions_for_bv(&thrown_value, n).unwrap() {
+                        state.forget_cached_solver_results();
+                        match state.get_possible_solutions_for_bv(&thrown_value, n)?.as_u64_solutions().ok_or("expected solutions of at most 64 bits")? {
                             PossibleSolutions::Exactly(v) => {
-                                candidate_values.extend(v.iter().map(|bvsol| ReturnValue::Throw(bvsol.as_u64().unwrap())));
+                                candidate_values.extend(v.into_iter().map(ReturnValue::Throw));
                                 if candidate_values.len() > n {
                                     break;
                                 }
                             },
                             PossibleSolutions::AtLeast(v) => {
-                                candidate_values.extend(v.iter().map(|bvsol| ReturnValue::Throw(bvsol.as_u64().unwrap())));
+                                candidate_values.extend(v.into_iter().map(ReturnValue::Throw));
                                 break;  // the total must be over n at this point
                             }
                         }
@@ -290,9 +501,31 @@ pub fn get_possible_return_values_of_func<'p>(
             }
         }
     }
-    if candidate_values.len() > n {
+    let answer = if candidate_values.len() > n {
         PossibleSolutions::AtLeast(candidate_values)
     } else {
         PossibleSolutions::Exactly(candidate_values)
+    };
+    summary.solver_stats = em.state().solver_stats();
+    Ok(timer.finish(answer, summary))
+}
+
+/// Like [`get_possible_return_values_of_func()`](fn.get_possible_return_values_of_func.html),
+/// but returns just the answer, without the `ExplorationSummary`.
+///
+/// This is the signature `get_possible_return_values_of_func()` had in
+/// previous versions of `haybale`, which panicked on errors; it will be
+/// removed in a future release.
+pub fn get_possible_return_values_of_func_simple<'p>(
+    funcname: &str,
+    args: impl IntoIterator<Item = Option<u64>>,
+    project: &'p Project,
+    config: Config<'p, BtorBackend>,
+    thrown_size: Option<u32>,
+    n: usize,
+) -> PossibleSolutions<ReturnValue<u64>> {
+    match get_possible_return_values_of_func(funcname, args, project, config, thrown_size, n) {
+        Ok(outcome) => outcome.answer,
+        Err(e) => panic!("{}", e),
     }
 }
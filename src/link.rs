@@ -0,0 +1,591 @@
+//! Linking the modules of a `Project` into a single module, in the manner of
+//! `llvm-link`, while checking for conflicting definitions.
+//!
+//! See [`Project::link()`](../struct.Project.html#method.link).
+
+use either::Either;
+use llvm_ir::{Constant, DebugLoc, Function, Instruction, Module, Name, Operand, Terminator, Type};
+use llvm_ir::instruction;
+use llvm_ir::module::{GlobalAlias, GlobalVariable, Linkage};
+use llvm_ir::types::Typed;
+use log::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Error returned by [`Project::link()`](../struct.Project.html#method.link)
+/// when two modules contain genuinely conflicting definitions
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct LinkError {
+    /// What kind of conflict this is
+    pub kind: LinkErrorKind,
+    /// The name of the symbol (or named struct type) which has conflicting
+    /// definitions
+    pub symbol: String,
+    /// Where the first definition came from
+    pub first: SymbolLocation,
+    /// Where the conflicting definition came from
+    pub second: SymbolLocation,
+}
+
+/// The kinds of conflicts which can cause a [`LinkError`](struct.LinkError.html)
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LinkErrorKind {
+    /// The symbol has a non-discardable (e.g., `external`) definition in
+    /// both places
+    DuplicateDefinition,
+    /// The symbol has `_odr` definitions of different types in the two places,
+    /// violating the One Definition Rule
+    OdrViolation,
+    /// The named struct type has definitions with incompatible layouts in the
+    /// two places
+    StructLayoutMismatch,
+}
+
+/// Where a definition involved in a [`LinkError`](struct.LinkError.html)
+/// came from
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SymbolLocation {
+    /// Name of the LLVM module containing the definition
+    pub module: String,
+    /// Source location of the definition, if the module has debuginfo for it
+    pub source_loc: Option<String>,
+}
+
+impl fmt::Display for LinkErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinkErrorKind::DuplicateDefinition => write!(f, "duplicate definition"),
+            LinkErrorKind::OdrViolation => write!(f, "ODR violation: definitions have different types"),
+            LinkErrorKind::StructLayoutMismatch => write!(f, "named struct type has incompatible layouts"),
+        }
+    }
+}
+
+impl fmt::Display for SymbolLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.source_loc {
+            Some(source_loc) => write!(f, "module {:?} ({})", self.module, source_loc),
+            None => write!(f, "module {:?}", self.module),
+        }
+    }
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} of {:?}: first in {}, then in {}", self.kind, self.symbol, self.first, self.second)
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+/// Definitions of named struct types
+pub(crate) type StructTypeDefs = Vec<Arc<RwLock<Type>>>;
+
+/// Link the given modules into one.
+///
+/// Returns the linked module, along with the definitions of all named struct
+/// types from all of the input modules. Named struct types in the linked
+/// module only hold weak references to their definitions, so the caller must
+/// keep these alive for as long as it uses the linked module.
+#[allow(clippy::result_large_err)]  // link errors are rare and fatal, so there's no point boxing them
+pub(crate) fn link_modules(modules: &[Module]) -> Result<(Module, StructTypeDefs), LinkError> {
+    info!("Linking {} modules", modules.len());
+    let modules = rename_local_symbols(modules);
+
+    let mut linked = Module {
+        name: "linked".to_owned(),
+        source_file_name: modules.iter().map(|m| m.source_file_name.as_str()).collect::<Vec<_>>().join(", "),
+        data_layout: modules.first().map(|m| m.data_layout.clone()).unwrap_or_default(),
+        target_triple: modules.first().and_then(|m| m.target_triple.clone()),
+        functions: vec![],
+        global_vars: vec![],
+        global_aliases: vec![],
+        named_struct_types: HashMap::new(),
+        inline_assembly: modules.iter().map(|m| m.inline_assembly.as_str()).filter(|asm| !asm.is_empty()).collect::<Vec<_>>().join("\n"),
+    };
+    let mut struct_type_defs = vec![];
+
+    // for each symbol added to `linked` so far: its index in the relevant
+    // `Vec` of `linked`, and where it came from
+    let mut funcs: HashMap<String, (usize, SymbolLocation)> = HashMap::new();
+    let mut globals: HashMap<Name, (usize, SymbolLocation)> = HashMap::new();
+    let mut aliases: HashMap<Name, (usize, SymbolLocation)> = HashMap::new();
+    let mut structs: HashMap<String, String> = HashMap::new();  // struct name to module name
+
+    for module in modules {
+        for func in module.functions {
+            let loc = SymbolLocation { module: module.name.clone(), source_loc: func.debugloc.as_ref().map(DebugLoc::to_string) };
+            match funcs.get(&func.name) {
+                None => {
+                    funcs.insert(func.name.clone(), (linked.functions.len(), loc));
+                    linked.functions.push(func);
+                },
+                Some((idx, first_loc)) => {
+                    let first: &Function = &linked.functions[*idx];
+                    let replace = resolve(&func.name, (first.linkage, &first.get_type(), first_loc), (func.linkage, &func.get_type(), &loc))?;
+                    if replace {
+                        let idx = *idx;
+                        linked.functions[idx] = func;
+                        funcs.get_mut(&linked.functions[idx].name).unwrap().1 = loc;
+                    }
+                },
+            }
+        }
+        for var in module.global_vars {
+            let loc = SymbolLocation { module: module.name.clone(), source_loc: var.debugloc.as_ref().map(DebugLoc::to_string) };
+            match globals.get(&var.name) {
+                None => {
+                    globals.insert(var.name.clone(), (linked.global_vars.len(), loc));
+                    linked.global_vars.push(var);
+                },
+                Some((idx, first_loc)) => {
+                    let first: &GlobalVariable = &linked.global_vars[*idx];
+                    let replace = match (&first.initializer, &var.initializer) {
+                        (_, None) | (None, Some(_)) => {
+                            // at least one of these is just a declaration, which the definition resolves
+                            if !layout_compatible(&first.ty, &var.ty) {
+                                warn!("Global {} is declared in {} with type {:?}, but in {} with type {:?}", var.name, first_loc, first.ty, loc, var.ty);
+                            }
+                            first.initializer.is_none() && var.initializer.is_some()
+                        },
+                        (Some(_), Some(_)) => resolve(&name_to_string(&var.name), (first.linkage, &first.ty, first_loc), (var.linkage, &var.ty, &loc))?,
+                    };
+                    if replace {
+                        let idx = *idx;
+                        globals.get_mut(&var.name).unwrap().1 = loc;
+                        linked.global_vars[idx] = var;
+                    }
+                },
+            }
+        }
+        for alias in module.global_aliases {
+            let loc = SymbolLocation { module: module.name.clone(), source_loc: None };
+            match aliases.get(&alias.name) {
+                None => {
+                    aliases.insert(alias.name.clone(), (linked.global_aliases.len(), loc));
+                    linked.global_aliases.push(alias);
+                },
+                Some((idx, first_loc)) => {
+                    let first: &GlobalAlias = &linked.global_aliases[*idx];
+                    if resolve(&name_to_string(&alias.name), (first.linkage, &first.ty, first_loc), (alias.linkage, &alias.ty, &loc))? {
+                        let idx = *idx;
+                        aliases.get_mut(&alias.name).unwrap().1 = loc;
+                        linked.global_aliases[idx] = alias;
+                    }
+                },
+            }
+        }
+        for (name, def) in module.named_struct_types {
+            if let Some(arc) = &def {
+                struct_type_defs.push(arc.clone());
+            }
+            match (linked.named_struct_types.get(&name), def) {
+                (Some(Some(first)), Some(def)) => {
+                    let compatible = {
+                        let first: &Type = &first.read().unwrap();
+                        let def: &Type = &def.read().unwrap();
+                        layout_compatible(first, def)
+                    };
+                    if !compatible {
+                        return Err(LinkError {
+                            kind: LinkErrorKind::StructLayoutMismatch,
+                            first: SymbolLocation { module: structs[&name].clone(), source_loc: None },
+                            second: SymbolLocation { module: module.name.clone(), source_loc: None },
+                            symbol: name,
+                        });
+                    }
+                },
+                (Some(_), None) => {},  // opaque declaration: keep whatever we already have
+                (_, def) => {
+                    // first time seeing this struct, or replacing an opaque declaration with a definition
+                    structs.insert(name.clone(), module.name.clone());
+                    linked.named_struct_types.insert(name, def);
+                },
+            }
+        }
+    }
+
+    Ok((linked, struct_type_defs))
+}
+
+/// Given two definitions of the same symbol, each as (linkage, type, location),
+/// decide which one to keep.
+///
+/// Returns `true` if the second definition should replace the first, `false`
+/// if the first should be kept, or a `LinkError` if they genuinely conflict.
+#[allow(clippy::result_large_err)]  // link errors are rare and fatal, so there's no point boxing them
+fn resolve(symbol: &str, first: (Linkage, &Type, &SymbolLocation), second: (Linkage, &Type, &SymbolLocation)) -> Result<bool, LinkError> {
+    let (first_linkage, first_ty, first_loc) = first;
+    let (second_linkage, second_ty, second_loc) = second;
+    let conflict = |kind| LinkError {
+        kind,
+        symbol: symbol.to_owned(),
+        first: first_loc.clone(),
+        second: second_loc.clone(),
+    };
+    if is_odr(first_linkage) && is_odr(second_linkage) && first_ty != second_ty {
+        return Err(conflict(LinkErrorKind::OdrViolation));
+    }
+    match (is_discardable(first_linkage), is_discardable(second_linkage)) {
+        (false, false) => Err(conflict(LinkErrorKind::DuplicateDefinition)),
+        (true, false) => Ok(true),
+        (_, true) => {
+            if first_ty != second_ty {
+                warn!("Discarding definition of {:?} in {}, whose type {:?} differs from the type {:?} of the definition in {}", symbol, second_loc, second_ty, first_ty, first_loc);
+            }
+            Ok(false)
+        },
+    }
+}
+
+/// Is this a linkage for which duplicate definitions are allowed, with all
+/// but one of them being discarded?
+fn is_discardable(linkage: Linkage) -> bool {
+    matches!(linkage,
+        Linkage::LinkOnceAny
+        | Linkage::LinkOnceODR
+        | Linkage::LinkOnceODRAutoHide
+        | Linkage::WeakAny
+        | Linkage::WeakODR
+        | Linkage::ExternalWeak
+        | Linkage::Common
+        | Linkage::AvailableExternally
+        | Linkage::LinkerPrivateWeak
+    )
+}
+
+/// Is this a linkage which promises that all definitions are equivalent?
+fn is_odr(linkage: Linkage) -> bool {
+    matches!(linkage, Linkage::LinkOnceODR | Linkage::LinkOnceODRAutoHide | Linkage::WeakODR)
+}
+
+/// Is this a linkage for symbols which are not visible outside their module?
+fn is_local(linkage: Linkage) -> bool {
+    matches!(linkage, Linkage::Private | Linkage::Internal | Linkage::LinkerPrivate | Linkage::LinkerPrivateWeak)
+}
+
+/// Two types are layout-compatible if they are equal, or if they are
+/// aggregates with the same shape whose elements are pairwise
+/// layout-compatible. All pointers are layout-compatible with each other, and
+/// opaque struct types are layout-compatible with anything.
+///
+/// This is looser than `Type`'s `==`, which (for instance) considers
+/// `%union.anon` and `%union.anon.0` different even if they have the same
+/// contents.
+fn layout_compatible(a: &Type, b: &Type) -> bool {
+    match (a, b) {
+        _ if a == b => true,
+        (Type::PointerType { .. }, Type::PointerType { .. }) => true,
+        (Type::ArrayType { element_type: a, num_elements: n }, Type::ArrayType { element_type: b, num_elements: m })
+        | (Type::VectorType { element_type: a, num_elements: n }, Type::VectorType { element_type: b, num_elements: m })
+        => n == m && layout_compatible(a, b),
+        (Type::StructType { element_types: a, is_packed: a_packed }, Type::StructType { element_types: b, is_packed: b_packed }) => {
+            a_packed == b_packed
+                && a.len() == b.len()
+                && a.iter().zip(b.iter()).all(|(a, b)| layout_compatible(a, b))
+        },
+        (Type::NamedStructType { ty: Some(a), .. }, Type::NamedStructType { ty: Some(b), .. }) => {
+            match (a.upgrade(), b.upgrade()) {
+                (Some(a), Some(b)) => layout_compatible(&a.read().unwrap(), &b.read().unwrap()),
+                _ => true,
+            }
+        },
+        (Type::NamedStructType { ty: None, .. }, Type::NamedStructType { .. })
+        | (Type::NamedStructType { .. }, Type::NamedStructType { ty: None, .. })
+        => true,
+        _ => false,
+    }
+}
+
+fn name_to_string(name: &Name) -> String {
+    match name {
+        Name::Name(s) => s.clone(),
+        Name::Number(n) => n.to_string(),
+    }
+}
+
+/// Returns copies of the given modules, where module-local symbols have been
+/// renamed as necessary so that they don't clash with any other symbol in any
+/// of the modules.
+///
+/// Like `llvm-link`, we rename by appending a numeric suffix, so if two modules
+/// each have an internal function `helper`, the first keeps its name and the
+/// second's becomes `helper.1`.
+fn rename_local_symbols(modules: &[Module]) -> Vec<Module> {
+    let all_names: HashSet<Name> = modules.iter()
+        .flat_map(|m| m.functions.iter().map(|f| Name::from(f.name.clone()))
+            .chain(m.global_vars.iter().map(|v| v.name.clone()))
+            .chain(m.global_aliases.iter().map(|a| a.name.clone()))
+        )
+        .collect();
+    let mut taken: HashSet<Name> = modules.iter()
+        .flat_map(|m| m.functions.iter().filter(|f| !is_local(f.linkage)).map(|f| Name::from(f.name.clone()))
+            .chain(m.global_vars.iter().filter(|v| !is_local(v.linkage)).map(|v| v.name.clone()))
+            .chain(m.global_aliases.iter().filter(|a| !is_local(a.linkage)).map(|a| a.name.clone()))
+        )
+        .collect();
+
+    modules.iter().map(|module| {
+        let local_names = module.functions.iter().filter(|f| is_local(f.linkage)).map(|f| Name::from(f.name.clone()))
+            .chain(module.global_vars.iter().filter(|v| is_local(v.linkage)).map(|v| v.name.clone()))
+            .chain(module.global_aliases.iter().filter(|a| is_local(a.linkage)).map(|a| a.name.clone()));
+        let mut renames = HashMap::new();
+        for name in local_names {
+            if taken.contains(&name) {
+                let base = name_to_string(&name);
+                let new_name = (1..)
+                    .map(|i| Name::from(format!("{}.{}", base, i)))
+                    .find(|n| !taken.contains(n) && !all_names.contains(n))
+                    .unwrap();
+                info!("Renaming {} in module {:?} to {}", name, module.name, new_name);
+                taken.insert(new_name.clone());
+                renames.insert(name, new_name);
+            } else {
+                taken.insert(name);
+            }
+        }
+        let mut module = module.clone();
+        if !renames.is_empty() {
+            Renamer { renames: &renames }.rename_module(&mut module);
+        }
+        module
+    }).collect()
+}
+
+/// Renames global symbols, both where they are defined and everywhere they
+/// are referenced
+struct Renamer<'a> {
+    renames: &'a HashMap<Name, Name>,
+}
+
+impl<'a> Renamer<'a> {
+    fn rename_module(&self, module: &mut Module) {
+        for func in &mut module.functions {
+            if let Some(Name::Name(new_name)) = self.renames.get(&Name::from(func.name.clone())) {
+                func.name = new_name.clone();
+            }
+            self.rename_function(func);
+        }
+        for var in &mut module.global_vars {
+            self.rename_name(&mut var.name);
+            if let Some(initializer) = &mut var.initializer {
+                self.rename_constant(initializer);
+            }
+        }
+        for alias in &mut module.global_aliases {
+            self.rename_name(&mut alias.name);
+            self.rename_constant(&mut alias.aliasee);
+        }
+    }
+
+    fn rename_name(&self, name: &mut Name) {
+        if let Some(new_name) = self.renames.get(name) {
+            *name = new_name.clone();
+        }
+    }
+
+    fn rename_function(&self, func: &mut Function) {
+        if let Some(personality) = &mut func.personality_function {
+            self.rename_constant(personality);
+        }
+        for bb in &mut func.basic_blocks {
+            for instr in &mut bb.instrs {
+                self.rename_instruction(instr);
+            }
+            self.rename_terminator(&mut bb.term);
+        }
+    }
+
+    fn rename_instruction(&self, instr: &mut Instruction) {
+        match instr {
+            Instruction::Add(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::Sub(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::Mul(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::UDiv(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::SDiv(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::URem(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::SRem(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::And(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::Or(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::Xor(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::Shl(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::LShr(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::AShr(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::FAdd(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::FSub(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::FMul(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::FDiv(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::FRem(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::FNeg(i) => self.rename_operand(&mut i.operand),
+            Instruction::ExtractElement(i) => self.rename_operands(&mut [&mut i.vector, &mut i.index]),
+            Instruction::InsertElement(i) => self.rename_operands(&mut [&mut i.vector, &mut i.element, &mut i.index]),
+            Instruction::ShuffleVector(i) => {
+                self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]);
+                self.rename_constant(&mut i.mask);
+            },
+            Instruction::ExtractValue(i) => self.rename_operand(&mut i.aggregate),
+            Instruction::InsertValue(i) => self.rename_operands(&mut [&mut i.aggregate, &mut i.element]),
+            Instruction::Alloca(i) => self.rename_operand(&mut i.num_elements),
+            Instruction::Load(i) => self.rename_operand(&mut i.address),
+            Instruction::Store(i) => self.rename_operands(&mut [&mut i.address, &mut i.value]),
+            Instruction::Fence(_) => {},
+            Instruction::CmpXchg(i) => self.rename_operands(&mut [&mut i.address, &mut i.expected, &mut i.replacement]),
+            Instruction::AtomicRMW(i) => self.rename_operands(&mut [&mut i.address, &mut i.value]),
+            Instruction::GetElementPtr(i) => {
+                self.rename_operand(&mut i.address);
+                for index in &mut i.indices {
+                    self.rename_operand(index);
+                }
+            },
+            Instruction::Trunc(i) => self.rename_operand(&mut i.operand),
+            Instruction::ZExt(i) => self.rename_operand(&mut i.operand),
+            Instruction::SExt(i) => self.rename_operand(&mut i.operand),
+            Instruction::FPTrunc(i) => self.rename_operand(&mut i.operand),
+            Instruction::FPExt(i) => self.rename_operand(&mut i.operand),
+            Instruction::FPToUI(i) => self.rename_operand(&mut i.operand),
+            Instruction::FPToSI(i) => self.rename_operand(&mut i.operand),
+            Instruction::UIToFP(i) => self.rename_operand(&mut i.operand),
+            Instruction::SIToFP(i) => self.rename_operand(&mut i.operand),
+            Instruction::PtrToInt(i) => self.rename_operand(&mut i.operand),
+            Instruction::IntToPtr(i) => self.rename_operand(&mut i.operand),
+            Instruction::BitCast(i) => self.rename_operand(&mut i.operand),
+            Instruction::AddrSpaceCast(i) => self.rename_operand(&mut i.operand),
+            Instruction::ICmp(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::FCmp(i) => self.rename_operands(&mut [&mut i.operand0, &mut i.operand1]),
+            Instruction::Phi(i) => {
+                for (op, _) in &mut i.incoming_values {
+                    self.rename_operand(op);
+                }
+            },
+            Instruction::Select(i) => self.rename_operands(&mut [&mut i.condition, &mut i.true_value, &mut i.false_value]),
+            Instruction::Call(instruction::Call { function, arguments, .. }) => self.rename_call(function, arguments),
+            Instruction::VAArg(i) => self.rename_operand(&mut i.arg_list),
+            Instruction::LandingPad(_) => {},
+            Instruction::CatchPad(i) => {
+                self.rename_operand(&mut i.catch_switch);
+                for arg in &mut i.args {
+                    self.rename_operand(arg);
+                }
+            },
+            Instruction::CleanupPad(i) => {
+                self.rename_operand(&mut i.parent_pad);
+                for arg in &mut i.args {
+                    self.rename_operand(arg);
+                }
+            },
+        }
+    }
+
+    fn rename_terminator(&self, term: &mut Terminator) {
+        match term {
+            Terminator::Ret(t) => {
+                if let Some(op) = &mut t.return_operand {
+                    self.rename_operand(op);
+                }
+            },
+            Terminator::Br(_) | Terminator::Unreachable(_) => {},
+            Terminator::CondBr(t) => self.rename_operand(&mut t.condition),
+            Terminator::Switch(t) => {
+                self.rename_operand(&mut t.operand);
+                for (c, _) in &mut t.dests {
+                    self.rename_constant(c);
+                }
+            },
+            Terminator::IndirectBr(t) => self.rename_operand(&mut t.operand),
+            Terminator::Invoke(t) => self.rename_call(&mut t.function, &mut t.arguments),
+            Terminator::Resume(t) => self.rename_operand(&mut t.operand),
+            Terminator::CleanupRet(t) => self.rename_operand(&mut t.cleanup_pad),
+            Terminator::CatchRet(t) => self.rename_operand(&mut t.catch_pad),
+            Terminator::CatchSwitch(t) => self.rename_operand(&mut t.parent_pad),
+            Terminator::CallBr(t) => self.rename_call(&mut t.function, &mut t.arguments),
+        }
+    }
+
+    fn rename_call<A>(&self, function: &mut Either<instruction::InlineAssembly, Operand>, arguments: &mut Vec<(Operand, A)>) {
+        if let Either::Right(op) = function {
+            self.rename_operand(op);
+        }
+        for (arg, _) in arguments {
+            self.rename_operand(arg);
+        }
+    }
+
+    fn rename_operands(&self, ops: &mut [&mut Operand]) {
+        for op in ops {
+            self.rename_operand(op);
+        }
+    }
+
+    fn rename_operand(&self, op: &mut Operand) {
+        if let Operand::ConstantOperand(c) = op {
+            self.rename_constant(c);
+        }
+    }
+
+    fn rename_constants(&self, cs: &mut [&mut Constant]) {
+        for c in cs {
+            self.rename_constant(c);
+        }
+    }
+
+    fn rename_constant(&self, c: &mut Constant) {
+        match c {
+            Constant::GlobalReference { name, .. } => self.rename_name(name),
+            Constant::Struct { values, .. } => values.iter_mut().for_each(|c| self.rename_constant(c)),
+            Constant::Array { elements, .. } => elements.iter_mut().for_each(|c| self.rename_constant(c)),
+            Constant::Vector(elements) => elements.iter_mut().for_each(|c| self.rename_constant(c)),
+            Constant::Int { .. }
+            | Constant::Float(_)
+            | Constant::Null(_)
+            | Constant::AggregateZero(_)
+            | Constant::Undef(_)
+            | Constant::BlockAddress
+            | Constant::TokenNone
+            => {},
+            Constant::Add(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::Sub(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::Mul(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::UDiv(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::SDiv(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::URem(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::SRem(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::And(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::Or(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::Xor(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::Shl(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::LShr(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::AShr(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::FAdd(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::FSub(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::FMul(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::FDiv(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::FRem(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::ExtractElement(c) => self.rename_constants(&mut [&mut c.vector, &mut c.index]),
+            Constant::InsertElement(c) => self.rename_constants(&mut [&mut c.vector, &mut c.element, &mut c.index]),
+            Constant::ShuffleVector(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1, &mut c.mask]),
+            Constant::ExtractValue(c) => self.rename_constant(&mut c.aggregate),
+            Constant::InsertValue(c) => self.rename_constants(&mut [&mut c.aggregate, &mut c.element]),
+            Constant::GetElementPtr(c) => {
+                self.rename_constant(&mut c.address);
+                c.indices.iter_mut().for_each(|c| self.rename_constant(c));
+            },
+            Constant::Trunc(c) => self.rename_constant(&mut c.operand),
+            Constant::ZExt(c) => self.rename_constant(&mut c.operand),
+            Constant::SExt(c) => self.rename_constant(&mut c.operand),
+            Constant::FPTrunc(c) => self.rename_constant(&mut c.operand),
+            Constant::FPExt(c) => self.rename_constant(&mut c.operand),
+            Constant::FPToUI(c) => self.rename_constant(&mut c.operand),
+            Constant::FPToSI(c) => self.rename_constant(&mut c.operand),
+            Constant::UIToFP(c) => self.rename_constant(&mut c.operand),
+            Constant::SIToFP(c) => self.rename_constant(&mut c.operand),
+            Constant::PtrToInt(c) => self.rename_constant(&mut c.operand),
+            Constant::IntToPtr(c) => self.rename_constant(&mut c.operand),
+            Constant::BitCast(c) => self.rename_constant(&mut c.operand),
+            Constant::AddrSpaceCast(c) => self.rename_constant(&mut c.operand),
+            Constant::ICmp(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::FCmp(c) => self.rename_constants(&mut [&mut c.operand0, &mut c.operand1]),
+            Constant::Select(c) => self.rename_constants(&mut [&mut c.condition, &mut c.true_value, &mut c.false_value]),
+        }
+    }
+}
@@ -7,7 +7,6 @@ use crate::backend::SolverRef;
 use crate::error::*;
 use crate::solver_utils::bvs_can_be_equal;
 use log::debug;
-use reduce::Reduce;
 use std::convert::TryInto;
 use std::rc::Rc;
 
@@ -936,4 +935,36 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn union_member_reinterpretation() -> Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let btor = <Rc<Btor> as SolverRef>::new();
+        let mut mem = Memory::new_uninitialized(btor.clone(), true, None);
+
+        // Model `union { uint32_t word; uint8_t bytes[4]; }`: initialize the
+        // union through its `word` member
+        let addr = BV::from_u64(btor.clone(), 0x10004, Memory::INDEX_BITS);
+        mem.write(&addr, BV::from_u64(btor.clone(), 0xdead_beef, 32))?;
+
+        // Reading through the `bytes` member should see the same bytes
+        // (we are little-endian)
+        for (i, &expected) in [0xef, 0xbe, 0xad, 0xde].iter().enumerate() {
+            let byte_addr = BV::from_u64(btor.clone(), 0x10004 + i as u64, Memory::INDEX_BITS);
+            let read_bv = mem.read(&byte_addr, 8)?;
+            assert_eq!(solver_utils::sat(&btor), Ok(true));
+            let ps = solver_utils::get_possible_solutions_for_bv(btor.clone(), &read_bv, 1)?.as_u64_solutions().unwrap();
+            assert_eq!(ps, PossibleSolutions::Exactly(HashSet::from_iter(std::iter::once(expected))));
+        }
+
+        // And writing through the `bytes` member should be reflected in `word`
+        let byte_addr = BV::from_u64(btor.clone(), 0x10006, Memory::INDEX_BITS);
+        mem.write(&byte_addr, BV::from_u64(btor.clone(), 0x00, 8))?;
+        let read_bv = mem.read(&addr, 32)?;
+        assert_eq!(solver_utils::sat(&btor), Ok(true));
+        let ps = solver_utils::get_possible_solutions_for_bv(btor.clone(), &read_bv, 1)?.as_u64_solutions().unwrap();
+        assert_eq!(ps, PossibleSolutions::Exactly(HashSet::from_iter(std::iter::once(0xde00_beef))));
+
+        Ok(())
+    }
 }
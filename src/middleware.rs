@@ -0,0 +1,300 @@
+//! Intercepting the constraints the engine asserts on each path; see
+//! [`Config.constraint_middleware`](../config/struct.Config.html#structfield.constraint_middleware).
+
+use crate::backend::Backend;
+use crate::config::ConstraintMiddleware;
+use log::warn;
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Why the engine is asserting a constraint
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ConstraintOrigin {
+    /// Following one direction of a conditional branch or `switch`, including
+    /// when backtracking to a direction not taken earlier
+    BranchRefinement,
+    /// An `llvm.assume` intrinsic; see
+    /// [`Config.trust_llvm_assumes`](../config/struct.Config.html#structfield.trust_llvm_assumes)
+    Assume,
+    /// Fixing a symbolic value to one of its possible values, such as a
+    /// function pointer or a `memcpy` length
+    Concretization,
+    /// Temporarily assuming a checker's violation condition, in order to
+    /// produce a witness. This constraint is retracted again right afterwards.
+    ViolationCheck,
+    /// One of the `Config.preconditions`
+    Precondition,
+    /// An assertion justifying a rewrite by one of the `Config.rewrite_rules`
+    Rewrite,
+}
+
+/// Describes a constraint which the engine is about to assert
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ConstraintCtx {
+    pub origin: ConstraintOrigin,
+    /// Where the constraint is being asserted: the source location if
+    /// available, or else the LLVM location
+    pub location: String,
+    /// Identifies the current path: the number of times the engine has
+    /// backtracked so far. Paths are numbered from 0, in the order they are
+    /// explored.
+    pub path: usize,
+    /// The number of backtracking points saved at this moment
+    pub depth: usize,
+    /// The depth below which this path shares its constraints with earlier
+    /// paths: constraints asserted on earlier paths at a depth no greater than
+    /// this are also in effect on this one.
+    pub shared_depth: usize,
+}
+
+/// What to do with a constraint, as decided by a `ConstraintMiddleware`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum MiddlewareAction<V> {
+    /// Assert the constraint as-is
+    PassThrough,
+    /// Assert this (1-bit) constraint instead
+    Replace(V),
+    /// Don't assert anything. The omission is recorded in the
+    /// [path notes](../struct.State.html#method.path_notes).
+    Drop,
+    /// Abandon the current path with an error. The `String` here explains
+    /// why.
+    Abort(String),
+}
+
+impl fmt::Display for ConstraintOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstraintOrigin::BranchRefinement => write!(f, "branch refinement"),
+            ConstraintOrigin::Assume => write!(f, "assume"),
+            ConstraintOrigin::Concretization => write!(f, "concretization"),
+            ConstraintOrigin::ViolationCheck => write!(f, "violation check"),
+            ConstraintOrigin::Precondition => write!(f, "precondition"),
+            ConstraintOrigin::Rewrite => write!(f, "rewrite"),
+        }
+    }
+}
+
+/// A middleware which passes every constraint through unchanged, but also
+/// writes it into an SMT-LIB2 file for the current path, for replaying
+/// offline in other solvers.
+///
+/// Path `n` is written to `path{n}.smt2` in the given directory. Each file
+/// begins with the constraints that path shares with earlier paths, so each
+/// one stands on its own. Constraints are written as the solver dumps them,
+/// each preceded by a comment giving its origin and location; those with
+/// origin `ViolationCheck` are written between `(push 1)` and `(pop 1)`, since
+/// they are retracted again right away.
+///
+/// ```ignore
+/// let tee = SmtTee::new("smt-dump");
+/// config.constraint_middleware = Some(tee.middleware::<BtorBackend>());
+/// ```
+pub struct SmtTee {
+    dir: PathBuf,
+    inner: RefCell<TeeState>,
+}
+
+#[derive(Default)]
+struct TeeState {
+    /// The path currently being written, if any
+    path: Option<usize>,
+    /// Constraints on the current path so far, with the depth at which each
+    /// was asserted
+    asserted: Vec<(usize, String)>,
+    /// Files written so far, oldest first
+    files: Vec<PathBuf>,
+}
+
+impl SmtTee {
+    /// Write files into the given directory, which is created if necessary
+    pub fn new(dir: impl Into<PathBuf>) -> Rc<Self> {
+        Rc::new(Self { dir: dir.into(), inner: RefCell::new(TeeState::default()) })
+    }
+
+    /// Get a `ConstraintMiddleware` which writes into this `SmtTee`
+    pub fn middleware<'p, B: Backend>(self: &Rc<Self>) -> ConstraintMiddleware<'p, B> {
+        let tee = Rc::clone(self);
+        Rc::new(move |ctx, constraint| {
+            if let Err(e) = tee.record(ctx, &format!("{:?}", constraint)) {
+                warn!("SmtTee failed to write to {:?}: {}", tee.dir, e);
+            }
+            MiddlewareAction::PassThrough
+        })
+    }
+
+    /// Get the files written so far, oldest first
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.inner.borrow().files.clone()
+    }
+
+    fn record(&self, ctx: &ConstraintCtx, smt: &str) -> io::Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        let block = format!("; {} at {}\n{}\n", ctx.origin, ctx.location, smt.trim_end());
+        let file = self.dir.join(format!("path{}.smt2", ctx.path));
+        if inner.path != Some(ctx.path) {
+            // starting a new path: it shares the constraints asserted before
+            // the backtracking point it resumes from
+            inner.asserted.retain(|(depth, _)| *depth <= ctx.shared_depth);
+            fs::create_dir_all(&self.dir)?;
+            let prefix: String = inner.asserted.iter().map(|(_, block)| block.as_str()).collect();
+            fs::write(&file, prefix)?;
+            inner.path = Some(ctx.path);
+            inner.files.push(file.clone());
+        }
+        let mut out = OpenOptions::new().append(true).open(&file)?;
+        if ctx.origin == ConstraintOrigin::ViolationCheck {
+            write!(out, "(push 1)\n{}(pop 1)\n", block)
+        } else {
+            out.write_all(block.as_bytes())?;
+            inner.asserted.push((ctx.depth, block));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::config::Config;
+    use crate::return_value::ReturnValue;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::*;
+
+    /// `if (x > 5) return 1; else return 0;`
+    fn one_branch() -> Function {
+        let ret_i32 = |value| ret(int_const(32, value));
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("big"), Name::from("small")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::UGT, local("x", Type::i32()), int_const(32, 5), "cond"));
+        func.basic_blocks[0].term = condbr("cond", "big", "small");
+        func.basic_blocks[1].term = ret_i32(1);
+        func.basic_blocks[2].term = ret_i32(0);
+        func
+    }
+
+    #[test]
+    fn sees_branch_constraints() {
+        let project = blank_project("test_mod", one_branch());
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_by_middleware = Rc::clone(&seen);
+        let config: Config<BtorBackend> = Config {
+            constraint_middleware: Some(Rc::new(move |ctx: &ConstraintCtx, _: &_| {
+                seen_by_middleware.borrow_mut().push(ctx.clone());
+                MiddlewareAction::PassThrough
+            })),
+            ..Config::default()
+        };
+        let em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        assert_eq!(em.map(Result::unwrap).count(), 2);
+        let seen = seen.borrow();
+        assert_eq!(seen.len(), 2);
+        assert!(seen.iter().all(|ctx| ctx.origin == ConstraintOrigin::BranchRefinement));
+        assert_eq!(seen[0].location, "f, bb entry, terminator");
+        assert_eq!((seen[0].path, seen[0].depth), (0, 1));
+        // on backtracking, the constraint is asserted at the start of the other destination
+        assert_eq!(seen[1].location, "f, bb small, instr 0");
+        assert_eq!((seen[1].path, seen[1].depth), (1, 0));
+    }
+
+    #[test]
+    fn dropped_constraint_is_noted() {
+        let project = blank_project("test_mod", one_branch());
+        let config: Config<BtorBackend> = Config {
+            constraint_middleware: Some(Rc::new(|_: &ConstraintCtx, _: &_| MiddlewareAction::Drop)),
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        match em.next().unwrap().unwrap() {
+            ReturnValue::Return(bv) => assert_eq!(bv.as_u64(), Some(1)),
+            retval => panic!("unexpected return value {:?}", retval),
+        }
+        assert_eq!(em.state().path_notes(), ["constraint middleware dropped a branch refinement constraint at f, bb entry, terminator"]);
+        // with the constraint dropped, `x` is unconstrained on this path
+        let x = em.param_bvs()[0].clone();
+        assert!(em.state().bvs_can_be_equal(&x, &em.state().bv_from_u32(0, 32)).unwrap());
+    }
+
+    #[test]
+    fn aborted_path_is_an_error() {
+        let project = blank_project("test_mod", one_branch());
+        let config: Config<BtorBackend> = Config {
+            constraint_middleware: Some(Rc::new(|ctx: &ConstraintCtx, _: &_| {
+                if ctx.path == 0 {
+                    MiddlewareAction::Abort("not interested".to_owned())
+                } else {
+                    MiddlewareAction::PassThrough
+                }
+            })),
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        match em.next().unwrap() {
+            Err(crate::Error::OtherError(msg)) => assert!(msg.ends_with("not interested"), "unexpected message {:?}", msg),
+            other => panic!("expected the first path to be aborted, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn aborted_violation_check_leaves_other_paths_alone() {
+        // `if (x > 5) return 1; else return 100 / x;`
+        let mut func = one_branch();
+        func.basic_blocks[2].instrs.push(Instruction::UDiv(instruction::UDiv {
+            operand0: int_const(32, 100),
+            operand1: local("x", Type::i32()),
+            dest: Name::from("q"),
+            debugloc: None,
+        }));
+        func.basic_blocks[2].term = ret(local("q", Type::i32()));
+        let project = blank_project("test_mod", func);
+        let config: Config<BtorBackend> = Config {
+            constraint_middleware: Some(Rc::new(|ctx: &ConstraintCtx, _: &_| {
+                if ctx.origin == ConstraintOrigin::ViolationCheck {
+                    MiddlewareAction::Abort("not interested".to_owned())
+                } else {
+                    MiddlewareAction::PassThrough
+                }
+            })),
+            extra_checkers: vec![Rc::new(crate::checkers::DivisionByZero)],
+            ..Config::default()
+        };
+        let em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        let (returns, errors): (Vec<_>, Vec<_>) = em.partition(Result::is_ok);
+        assert_eq!(errors.len(), 1, "expected the division path to be aborted, got {:?}", errors);
+        // the aborted check doesn't leave its solver frame behind to constrain the other path
+        match &returns[..] {
+            [Ok(ReturnValue::Return(bv))] => assert_eq!(bv.as_u64(), Some(1)),
+            returns => panic!("expected the other path to return 1, got {:?}", returns),
+        }
+    }
+
+    #[test]
+    fn smt_tee_writes_a_file_per_path() {
+        let project = blank_project("test_mod", one_branch());
+        let dir = std::env::temp_dir().join(format!("haybale-smt-tee-{}", std::process::id()));
+        let tee = SmtTee::new(&dir);
+        let config: Config<BtorBackend> = Config {
+            constraint_middleware: Some(tee.middleware::<BtorBackend>()),
+            ..Config::default()
+        };
+        let em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        assert_eq!(em.map(Result::unwrap).count(), 2);
+        let files = tee.files();
+        assert_eq!(files, vec![dir.join("path0.smt2"), dir.join("path1.smt2")]);
+        let first = fs::read_to_string(&files[0]).unwrap();
+        assert!(first.starts_with("; branch refinement at f, bb entry, terminator\n"));
+        // the second path shares nothing with the first: its only constraint
+        // is the negated branch condition
+        let second = fs::read_to_string(&files[1]).unwrap();
+        assert!(second.starts_with("; branch refinement at f, bb small, instr 0\n"));
+        assert_eq!(second.matches("; ").count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,735 @@
+//! Parsing and compiling of preconditions written as C-like expressions; see
+//! [`Config.preconditions`](../config/struct.Config.html#structfield.preconditions)
+//! for the syntax.
+
+use llvm_ir::{Name, Operand, Type};
+use std::fmt;
+
+use crate::backend::*;
+use crate::error::*;
+use crate::layout::size;
+use crate::state::State;
+
+/// An error parsing or compiling a precondition, pointing at the offending token
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) struct PreconditionError {
+    message: String,
+    src: String,
+    /// Byte offset of the offending token in `src`
+    start: usize,
+    /// Length in bytes of the offending token (at least 1, for display purposes)
+    len: usize,
+}
+
+impl fmt::Display for PreconditionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        writeln!(f, "  {}", self.src)?;
+        write!(f, "  {}{}", " ".repeat(self.src[.. self.start].chars().count()), "^".repeat(self.len))
+    }
+}
+
+type ParseResult<T> = std::result::Result<T, PreconditionError>;
+
+impl From<PreconditionError> for Error {
+    fn from(e: PreconditionError) -> Error {
+        Error::OtherError(format!("invalid precondition: {}", e))
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Tok {
+    Ident,
+    Int(u64),
+    LParen,
+    RParen,
+    Not,
+    Tilde,
+    Star,
+    Slash,
+    Percent,
+    Plus,
+    Minus,
+    Shl,
+    Shr,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    EqEq,
+    Ne,
+    Amp,
+    Caret,
+    Pipe,
+    AndAnd,
+    OrOr,
+    End,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token {
+    tok: Tok,
+    start: usize,
+    len: usize,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum UnaryOp {
+    LogicalNot,
+    BitNot,
+    Neg,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum BinaryOp {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    Shl,
+    Shr,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    BitAnd,
+    BitXor,
+    BitOr,
+    LogicalAnd,
+    LogicalOr,
+}
+
+impl BinaryOp {
+    /// Binding strength; higher binds tighter
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 10,
+            BinaryOp::Add | BinaryOp::Sub => 9,
+            BinaryOp::Shl | BinaryOp::Shr => 8,
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 7,
+            BinaryOp::Eq | BinaryOp::Ne => 6,
+            BinaryOp::BitAnd => 5,
+            BinaryOp::BitXor => 4,
+            BinaryOp::BitOr => 3,
+            BinaryOp::LogicalAnd => 2,
+            BinaryOp::LogicalOr => 1,
+        }
+    }
+
+    fn from_tok(tok: Tok) -> Option<Self> {
+        match tok {
+            Tok::Star => Some(BinaryOp::Mul),
+            Tok::Slash => Some(BinaryOp::Div),
+            Tok::Percent => Some(BinaryOp::Rem),
+            Tok::Plus => Some(BinaryOp::Add),
+            Tok::Minus => Some(BinaryOp::Sub),
+            Tok::Shl => Some(BinaryOp::Shl),
+            Tok::Shr => Some(BinaryOp::Shr),
+            Tok::Lt => Some(BinaryOp::Lt),
+            Tok::Le => Some(BinaryOp::Le),
+            Tok::Gt => Some(BinaryOp::Gt),
+            Tok::Ge => Some(BinaryOp::Ge),
+            Tok::EqEq => Some(BinaryOp::Eq),
+            Tok::Ne => Some(BinaryOp::Ne),
+            Tok::Amp => Some(BinaryOp::BitAnd),
+            Tok::Caret => Some(BinaryOp::BitXor),
+            Tok::Pipe => Some(BinaryOp::BitOr),
+            Tok::AndAnd => Some(BinaryOp::LogicalAnd),
+            Tok::OrOr => Some(BinaryOp::LogicalOr),
+            _ => None,
+        }
+    }
+}
+
+/// A parsed precondition expression
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum Expr {
+    Int(u64),
+    /// Identifier, with the byte offset of its token in the source
+    Ident(String, usize),
+    /// `len(s)` for a slice parameter `s`, with the byte offset of the token
+    /// for `s` in the source
+    Len(String, usize),
+    Cast { signed: bool, bits: u32, expr: Box<Expr> },
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+fn tokenize(src: &str) -> ParseResult<Vec<Token>> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let tok = if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'.') {
+                i += 1;
+            }
+            Tok::Ident
+        } else if c == b'$' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+            // positional parameter, e.g. `$0`
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            Tok::Ident
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && bytes[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+            let text = &src[start .. i];
+            let parsed = if text.starts_with("0x") || text.starts_with("0X") {
+                u64::from_str_radix(&text[2 ..], 16)
+            } else {
+                text.parse()
+            };
+            match parsed {
+                Ok(value) => Tok::Int(value),
+                Err(_) => return Err(error(src, start, i - start, format!("invalid integer literal `{}`", text))),
+            }
+        } else {
+            let two_char_tok = match src.get(i .. i + 2) {
+                Some("<<") => Some(Tok::Shl),
+                Some(">>") => Some(Tok::Shr),
+                Some("<=") => Some(Tok::Le),
+                Some(">=") => Some(Tok::Ge),
+                Some("==") => Some(Tok::EqEq),
+                Some("!=") => Some(Tok::Ne),
+                Some("&&") => Some(Tok::AndAnd),
+                Some("||") => Some(Tok::OrOr),
+                _ => None,
+            };
+            match two_char_tok {
+                Some(tok) => {
+                    i += 2;
+                    tok
+                },
+                None => {
+                    i += 1;
+                    match c {
+                        b'(' => Tok::LParen,
+                        b')' => Tok::RParen,
+                        b'!' => Tok::Not,
+                        b'~' => Tok::Tilde,
+                        b'*' => Tok::Star,
+                        b'/' => Tok::Slash,
+                        b'%' => Tok::Percent,
+                        b'+' => Tok::Plus,
+                        b'-' => Tok::Minus,
+                        b'<' => Tok::Lt,
+                        b'>' => Tok::Gt,
+                        b'&' => Tok::Amp,
+                        b'^' => Tok::Caret,
+                        b'|' => Tok::Pipe,
+                        _ => {
+                            let ch = src[start ..].chars().next().unwrap();
+                            return Err(error(src, start, ch.len_utf8(), format!("unexpected character `{}`", ch)));
+                        },
+                    }
+                },
+            }
+        };
+        tokens.push(Token { tok, start, len: i - start });
+    }
+    tokens.push(Token { tok: Tok::End, start: src.len(), len: 0 });
+    Ok(tokens)
+}
+
+fn error(src: &str, start: usize, len: usize, message: String) -> PreconditionError {
+    PreconditionError {
+        message,
+        src: src.to_owned(),
+        start,
+        len: std::cmp::max(len, 1),
+    }
+}
+
+/// If `text` names a cast target type like `i32` or `u8`, returns its
+/// signedness and width
+fn parse_cast_type(text: &str) -> Option<(bool, u32)> {
+    let signed = match text.chars().next()? {
+        'i' => true,
+        'u' => false,
+        _ => return None,
+    };
+    match text[1 ..].parse() {
+        Ok(bits) if (1 ..= 64).contains(&bits) => Some((signed, bits)),
+        _ => None,
+    }
+}
+
+struct Parser<'s> {
+    src: &'s str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn peek(&self) -> Token {
+        self.tokens[self.pos]
+    }
+
+    fn peek_at(&self, offset: usize) -> Token {
+        self.tokens[std::cmp::min(self.pos + offset, self.tokens.len() - 1)]
+    }
+
+    fn text(&self, token: Token) -> &'s str {
+        &self.src[token.start .. token.start + token.len]
+    }
+
+    fn unexpected(&self, token: Token, expected: &str) -> PreconditionError {
+        let found = match token.tok {
+            Tok::End => "end of input".to_owned(),
+            _ => format!("`{}`", self.text(token)),
+        };
+        error(self.src, token.start, token.len, format!("expected {}, found {}", expected, found))
+    }
+
+    fn expr(&mut self, min_precedence: u8) -> ParseResult<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(op) = BinaryOp::from_tok(self.peek().tok) {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            // all binary operators are left-associative
+            let rhs = self.expr(op.precedence() + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> ParseResult<Expr> {
+        let token = self.peek();
+        let op = match token.tok {
+            Tok::Not => Some(UnaryOp::LogicalNot),
+            Tok::Tilde => Some(UnaryOp::BitNot),
+            Tok::Minus => Some(UnaryOp::Neg),
+            _ => None,
+        };
+        if let Some(op) = op {
+            self.pos += 1;
+            return Ok(Expr::Unary(op, Box::new(self.unary()?)));
+        }
+        if token.tok == Tok::LParen && self.peek_at(1).tok == Tok::Ident && self.peek_at(2).tok == Tok::RParen {
+            if let Some((signed, bits)) = parse_cast_type(self.text(self.peek_at(1))) {
+                self.pos += 3;
+                return Ok(Expr::Cast { signed, bits, expr: Box::new(self.unary()?) });
+            }
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> ParseResult<Expr> {
+        let token = self.peek();
+        match token.tok {
+            Tok::Int(value) => {
+                self.pos += 1;
+                Ok(Expr::Int(value))
+            },
+            Tok::Ident if self.peek_at(1).tok == Tok::LParen => {
+                if self.text(token) != "len" {
+                    return Err(error(self.src, token.start, token.len, format!("unknown function `{}`; the only function is `len`", self.text(token))));
+                }
+                let arg = self.peek_at(2);
+                if arg.tok != Tok::Ident {
+                    return Err(self.unexpected(arg, "the name of a slice parameter"));
+                }
+                if self.peek_at(3).tok != Tok::RParen {
+                    return Err(self.unexpected(self.peek_at(3), "`)`"));
+                }
+                self.pos += 4;
+                Ok(Expr::Len(self.text(arg).to_owned(), arg.start))
+            },
+            Tok::Ident => {
+                self.pos += 1;
+                Ok(Expr::Ident(self.text(token).to_owned(), token.start))
+            },
+            Tok::LParen => {
+                self.pos += 1;
+                let inner = self.expr(0)?;
+                match self.peek().tok {
+                    Tok::RParen => {
+                        self.pos += 1;
+                        Ok(inner)
+                    },
+                    _ => Err(self.unexpected(self.peek(), "`)`")),
+                }
+            },
+            _ => Err(self.unexpected(token, "an identifier, integer, or `(`")),
+        }
+    }
+}
+
+fn parse(src: &str) -> ParseResult<Expr> {
+    let mut parser = Parser { src, tokens: tokenize(src)?, pos: 0 };
+    let expr = parser.expr(0)?;
+    match parser.peek().tok {
+        Tok::End => Ok(expr),
+        _ => Err(parser.unexpected(parser.peek(), "an operator or end of input")),
+    }
+}
+
+/// A compiled subexpression. Literals stay unsized until they meet a sized value.
+enum Value<V> {
+    Int(u64),
+    BV { bv: V, signed: bool },
+}
+
+/// Compile the given precondition to a 1-bit `BV` which is true exactly when
+/// the precondition holds, for the function `state.cur_loc.func`.
+pub(crate) fn precondition_to_bv<B: Backend>(state: &State<B>, src: &str) -> Result<B::BV> {
+    let expr = parse(src)?;
+    let compiler = Compiler { state, src };
+    let value = compiler.compile(&expr)?;
+    Ok(compiler.to_bool(value))
+}
+
+struct Compiler<'a, 'p, B: Backend> {
+    state: &'a State<'p, B>,
+    src: &'a str,
+}
+
+impl<'a, 'p, B: Backend> Compiler<'a, 'p, B> {
+    fn compile(&self, expr: &Expr) -> Result<Value<B::BV>> {
+        match expr {
+            Expr::Int(value) => Ok(Value::Int(*value)),
+            Expr::Ident(name, start) => self.resolve(name, *start),
+            Expr::Len(slice, start) => {
+                // rustc passes a slice parameter `s` as two parameters, `s.0`
+                // (the pointer) and `s.1` (the length)
+                let len = format!("{}.1", slice);
+                let func = self.state.cur_loc.func;
+                let is_slice = func.parameters.iter().any(|p| p.name == Name::from(format!("{}.0", slice)))
+                    && func.parameters.iter().any(|p| p.name == Name::from(len.as_str()));
+                if !is_slice {
+                    return Err(error(self.src, *start, slice.len(), format!("`{}` is not a slice parameter (expected parameters `{}.0` and `{}`)", slice, slice, len)).into());
+                }
+                self.resolve(&len, *start)
+            },
+            Expr::Cast { signed, bits, expr } => {
+                let (bv, was_signed) = match self.compile(expr)? {
+                    Value::Int(value) => (self.state.bv_from_u64(value, 64), false),
+                    Value::BV { bv, signed } => (bv, signed),
+                };
+                let width = bv.get_width();
+                let bv = if width > *bits {
+                    bv.slice(*bits - 1, 0)
+                } else if was_signed {
+                    bv.sign_extend_to_bits(*bits)
+                } else {
+                    bv.zero_extend_to_bits(*bits)
+                };
+                Ok(Value::BV { bv, signed: *signed })
+            },
+            Expr::Unary(op, expr) => {
+                let value = self.compile(expr)?;
+                Ok(match (op, value) {
+                    (UnaryOp::LogicalNot, value) => Value::BV { bv: self.to_bool(value).not(), signed: false },
+                    (UnaryOp::BitNot, Value::Int(value)) => Value::Int(!value),
+                    (UnaryOp::BitNot, Value::BV { bv, signed }) => Value::BV { bv: bv.not(), signed },
+                    (UnaryOp::Neg, Value::Int(value)) => Value::Int(value.wrapping_neg()),
+                    (UnaryOp::Neg, Value::BV { bv, signed }) => Value::BV { bv: bv.neg(), signed },
+                })
+            },
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.compile(lhs)?;
+                let rhs = self.compile(rhs)?;
+                match op {
+                    BinaryOp::LogicalAnd => return Ok(Value::BV { bv: self.to_bool(lhs).and(&self.to_bool(rhs)), signed: false }),
+                    BinaryOp::LogicalOr => return Ok(Value::BV { bv: self.to_bool(lhs).or(&self.to_bool(rhs)), signed: false }),
+                    _ => {},
+                }
+                let (lhs, rhs, signed) = self.unify(lhs, rhs);
+                let bv = match op {
+                    BinaryOp::Mul => lhs.mul(&rhs),
+                    BinaryOp::Div => if signed { lhs.sdiv(&rhs) } else { lhs.udiv(&rhs) },
+                    BinaryOp::Rem => if signed { lhs.srem(&rhs) } else { lhs.urem(&rhs) },
+                    BinaryOp::Add => lhs.add(&rhs),
+                    BinaryOp::Sub => lhs.sub(&rhs),
+                    BinaryOp::Shl => lhs.sll(&rhs),
+                    BinaryOp::Shr => if signed { lhs.sra(&rhs) } else { lhs.srl(&rhs) },
+                    BinaryOp::BitAnd => lhs.and(&rhs),
+                    BinaryOp::BitXor => lhs.xor(&rhs),
+                    BinaryOp::BitOr => lhs.or(&rhs),
+                    BinaryOp::Lt => return Ok(Self::bool_value(if signed { lhs.slt(&rhs) } else { lhs.ult(&rhs) })),
+                    BinaryOp::Le => return Ok(Self::bool_value(if signed { lhs.slte(&rhs) } else { lhs.ulte(&rhs) })),
+                    BinaryOp::Gt => return Ok(Self::bool_value(if signed { lhs.sgt(&rhs) } else { lhs.ugt(&rhs) })),
+                    BinaryOp::Ge => return Ok(Self::bool_value(if signed { lhs.sgte(&rhs) } else { lhs.ugte(&rhs) })),
+                    BinaryOp::Eq => return Ok(Self::bool_value(lhs._eq(&rhs))),
+                    BinaryOp::Ne => return Ok(Self::bool_value(lhs._ne(&rhs))),
+                    BinaryOp::LogicalAnd | BinaryOp::LogicalOr => unreachable!(),
+                };
+                Ok(Value::BV { bv, signed })
+            },
+        }
+    }
+
+    fn bool_value(bv: B::BV) -> Value<B::BV> {
+        Value::BV { bv, signed: false }
+    }
+
+    /// Convert the value to a 1-bit `BV`, C-style: nonzero is true
+    fn to_bool(&self, value: Value<B::BV>) -> B::BV {
+        match value {
+            Value::Int(value) => self.state.bv_from_bool(value != 0),
+            Value::BV { bv, .. } if bv.get_width() == 1 => bv,
+            Value::BV { bv, .. } => bv._ne(&self.state.zero(bv.get_width())),
+        }
+    }
+
+    /// Bring two values to a common width, returning the two `BV`s and whether
+    /// the operation on them should be signed
+    fn unify(&self, lhs: Value<B::BV>, rhs: Value<B::BV>) -> (B::BV, B::BV, bool) {
+        match (lhs, rhs) {
+            (Value::Int(a), Value::Int(b)) => (self.state.bv_from_u64(a, 64), self.state.bv_from_u64(b, 64), false),
+            (Value::Int(a), Value::BV { bv, signed }) => (self.literal(a, bv.get_width()), bv, signed),
+            (Value::BV { bv, signed }, Value::Int(b)) => {
+                let width = bv.get_width();
+                (bv, self.literal(b, width), signed)
+            },
+            (Value::BV { bv: a, signed: a_signed }, Value::BV { bv: b, signed: b_signed }) => {
+                let width = std::cmp::max(a.get_width(), b.get_width());
+                let extend = |bv: B::BV, signed: bool| if signed { bv.sign_extend_to_bits(width) } else { bv.zero_extend_to_bits(width) };
+                (extend(a, a_signed), extend(b, b_signed), a_signed && b_signed)
+            },
+        }
+    }
+
+    /// A literal truncated to the given width
+    fn literal(&self, value: u64, width: u32) -> B::BV {
+        if width >= 64 {
+            self.state.bv_from_u64(value, 64).zero_extend_to_bits(width)
+        } else {
+            self.state.bv_from_u64(value & ((1 << width) - 1), width)
+        }
+    }
+
+    fn resolve(&self, name: &str, start: usize) -> Result<Value<B::BV>> {
+        let func = self.state.cur_loc.func;
+        let param = match name.strip_prefix('$') {
+            Some(idx) => match idx.parse::<usize>().ok().and_then(|idx| func.parameters.get(idx)) {
+                Some(param) => Some(param),
+                None => return Err(error(self.src, start, name.len(), format!("`{}` is out of range; {} has {} parameter(s)", name, func.name, func.parameters.len())).into()),
+            },
+            None => func.parameters.iter().find(|p| p.name == Name::from(name)),
+        };
+        if let Some(param) = param {
+            let bv = self.state.operand_to_bv(&Operand::LocalOperand { name: param.name.clone(), ty: param.ty.clone() })?;
+            return Ok(Value::BV { bv, signed: false });
+        }
+        let module = self.state.cur_loc.module;
+        if let Some(var) = module.global_vars.iter().find(|g| g.name == Name::from(name)) {
+            let pointee = match &var.ty {
+                Type::PointerType { pointee_type, .. } => &**pointee_type,
+                ty => return Err(Error::OtherError(format!("expected global variable {} to have pointer type, got {:?}", name, ty))),
+            };
+            let addr = self.state.const_to_bv(&llvm_ir::Constant::GlobalReference { name: var.name.clone(), ty: var.ty.clone() })?;
+            let bv = self.state.read(&addr, size(pointee) as u32)?;
+            return Ok(Value::BV { bv, signed: false });
+        }
+        let available = func.parameters.iter()
+            .map(|p| &p.name)
+            .chain(module.global_vars.iter().map(|g| &g.name))
+            .filter_map(|name| match name {
+                Name::Name(name) => Some(name.as_str()),
+                Name::Number(_) => None,
+            })
+            .collect::<Vec<&str>>();
+        let available = if available.is_empty() {
+            "there are no named parameters or globals".to_owned()
+        } else {
+            format!("available names are: {}", available.join(", "))
+        };
+        Err(error(self.src, start, name.len(), format!("unknown identifier `{}`; {}", name, available)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::config::Config;
+    use crate::function_hooks;
+    use crate::project::Project;
+    use crate::return_value::ReturnValue;
+    use crate::solver_utils::bvs_must_be_equal;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::function::Parameter;
+    use llvm_ir::{terminator, Constant, Function, IntPredicate, Terminator};
+
+    /// A `Project` with a function `f(x: i8, y: i32)` and a global `g: i32` initialized to 7
+    fn project() -> Project {
+        let mut func = blank_function("f", vec![Name::from("bb")]);
+        func.parameters.push(Parameter { name: Name::from("x"), ty: Type::i8(), attributes: vec![] });
+        func.parameters.push(Parameter { name: Name::from("y"), ty: Type::i32(), attributes: vec![] });
+        project_with_globals("test_mod", vec![func], vec![global_var("g", Type::pointer_to(Type::i32()), Some(Constant::Int { bits: 32, value: 7 }))])
+    }
+
+    fn param<'p>(state: &State<'p, BtorBackend>, name: &str, ty: Type) -> <BtorBackend as Backend>::BV {
+        state.operand_to_bv(&local(name, ty)).unwrap()
+    }
+
+    /// Assert that `precondition` is equivalent to `expected` for all values of the parameters
+    fn assert_equivalent<'p>(state: &State<'p, BtorBackend>, precondition: &str, expected: &<BtorBackend as Backend>::BV) {
+        let bv = precondition_to_bv(state, precondition).unwrap();
+        assert!(bvs_must_be_equal(&state.solver, &bv, expected).unwrap(), "precondition {:?} didn't compile as expected", precondition);
+    }
+
+    fn state_for(proj: &Project) -> State<'_, BtorBackend> {
+        let mut state = blank_state(proj, "f");
+        state.new_bv_with_name(Name::from("x"), 8).unwrap();
+        state.new_bv_with_name(Name::from("y"), 32).unwrap();
+        state
+    }
+
+    #[test]
+    fn precedence() {
+        let proj = project();
+        let state = state_for(&proj);
+        let y = param(&state, "y", Type::i32());
+        // `&` binds looser than `!=`, so this is `y & (3 != 0)`, i.e. `y & 1`
+        let one = state.one(32);
+        assert_equivalent(&state, "y & 3 != 0", &y.and(&one)._ne(&state.zero(32)));
+        // and `*` binds tighter than `+`
+        assert_equivalent(&state, "y == 1 + 2 * 3", &y._eq(&state.bv_from_u32(7, 32)));
+        // `&&` binds looser than `||` binds looser than comparisons
+        assert_equivalent(&state, "y <= 64 && (y & 3) != 0 || y == 100",
+            &y.ulte(&state.bv_from_u32(64, 32)).and(&y.and(&state.bv_from_u32(3, 32))._ne(&state.zero(32)))
+                .or(&y._eq(&state.bv_from_u32(100, 32))));
+        // subtraction is left-associative
+        assert_equivalent(&state, "y == 10 - 3 - 2", &y._eq(&state.bv_from_u32(5, 32)));
+    }
+
+    #[test]
+    fn signedness() {
+        let proj = project();
+        let state = state_for(&proj);
+        let x = param(&state, "x", Type::i8());
+        let y = param(&state, "y", Type::i32());
+        // unsigned by default, so `-1` is the largest value
+        assert_equivalent(&state, "y < -1", &y._ne(&state.ones(32)));
+        // with a cast, the comparison is signed
+        assert_equivalent(&state, "(i32)y < 0", &y.slt(&state.zero(32)));
+        // narrower signed values are sign-extended, unsigned ones zero-extended
+        assert_equivalent(&state, "(i8)x == (i32)y", &x.sext(24)._eq(&y));
+        assert_equivalent(&state, "x == y", &x.zext(24)._eq(&y));
+        // casts to narrower types truncate
+        assert_equivalent(&state, "(u8)y == 0", &y.slice(7, 0)._eq(&state.zero(8)));
+    }
+
+    #[test]
+    fn global_reference() {
+        let proj = project();
+        let state = state_for(&proj);
+        let y = param(&state, "y", Type::i32());
+        assert_equivalent(&state, "y > g", &y.ugt(&state.bv_from_u32(7, 32)));
+    }
+
+    #[test]
+    fn positional_parameter() {
+        let proj = project();
+        let state = state_for(&proj);
+        let y = param(&state, "y", Type::i32());
+        assert_equivalent(&state, "$1 > 3", &y.ugt(&state.bv_from_u32(3, 32)));
+        match precondition_to_bv(&state, "$2 > 3") {
+            Err(Error::OtherError(msg)) => assert!(msg.contains("`$2` is out of range; f has 2 parameter(s)"), "unexpected message {:?}", msg),
+            Err(e) => panic!("Expected an OtherError, got {:?}", e),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn unknown_identifier() {
+        let proj = project();
+        let state = state_for(&proj);
+        match precondition_to_bv(&state, "z > 3") {
+            Err(Error::OtherError(msg)) => assert!(msg.contains("available names are: x, y, g"), "unexpected message {:?}", msg),
+            Err(e) => panic!("Expected an OtherError, got {:?}", e),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn parse_error_message() {
+        let e = parse("len <= (64 && flags").unwrap_err();
+        assert_eq!(e.to_string(), "\
+expected `)`, found end of input
+  len <= (64 && flags
+                     ^");
+        let e = parse("len <= 64 & & flags").unwrap_err();
+        assert_eq!(e.to_string(), "\
+expected an identifier, integer, or `(`, found `&`
+  len <= 64 & & flags
+              ^");
+        let e = parse("len # 3").unwrap_err();
+        assert_eq!(e.to_string(), "\
+unexpected character `#`
+  len # 3
+      ^");
+    }
+
+    /// `fn get(v: &[i32], idx: usize) -> i32`, which panics unless `idx < v.len()`
+    fn slice_index() -> Function {
+        let local_i64 = |name: &str| local(name, Type::i64());
+        let mut func = blank_function("get", vec![Name::from("start"), Name::from("in_bounds"), Name::from("panic")]);
+        func.parameters.push(Parameter { name: Name::from("v.0"), ty: Type::pointer_to(Type::i32()), attributes: vec![] });
+        func.parameters.push(Parameter { name: Name::from("v.1"), ty: Type::i64(), attributes: vec![] });
+        func.parameters.push(Parameter { name: Name::from("idx"), ty: Type::i64(), attributes: vec![] });
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::ULT, local_i64("idx"), local_i64("v.1"), "ok"));
+        func.basic_blocks[0].term = condbr("ok", "in_bounds", "panic");
+        func.basic_blocks[1].term = ret(int_const(32, 1));
+        func.basic_blocks[2].instrs.push(call("panic_bounds_check", Type::VoidType, vec![], None));
+        func.basic_blocks[2].term = Terminator::Unreachable(terminator::Unreachable { debugloc: None });
+        func
+    }
+
+    #[test]
+    fn slice_length() {
+        let proj = blank_project("test_mod", slice_index());
+        let mut state = blank_state(&proj, "get");
+        state.new_bv_with_name(Name::from("v.0"), 64).unwrap();
+        state.new_bv_with_name(Name::from("v.1"), 64).unwrap();
+        state.new_bv_with_name(Name::from("idx"), 64).unwrap();
+        let len = param(&state, "v.1", Type::i64());
+        let idx = param(&state, "idx", Type::i64());
+        assert_equivalent(&state, "idx < len(v)", &idx.ult(&len));
+        match precondition_to_bv(&state, "len(idx) > 0") {
+            Err(Error::OtherError(msg)) => assert!(msg.contains("`idx` is not a slice parameter"), "unexpected message {:?}", msg),
+            Err(e) => panic!("Expected an OtherError, got {:?}", e),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    /// Does any path through `get()` panic, given the preconditions
+    fn may_panic(preconditions: Vec<String>) -> bool {
+        let proj = blank_project("test_mod", slice_index());
+        let mut config = Config {
+            preconditions,
+            ..Config::default()
+        };
+        config.function_hooks.add("panic_bounds_check", &function_hooks::abort_hook);
+        let em: ExecutionManager<BtorBackend> = symex_function("get", &proj, config);
+        let mut panics = false;
+        for retval in em {
+            panics |= retval.unwrap() == ReturnValue::Abort;
+        }
+        panics
+    }
+
+    #[test]
+    fn slice_relation_rules_out_bounds_panic() {
+        assert!(may_panic(vec![]));
+        assert!(!may_panic(vec!["idx < len(v)".to_owned()]));
+    }
+}
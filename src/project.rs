@@ -1,17 +1,30 @@
 use crate::demangling::try_cpp_demangle;
-use llvm_ir::{Function, Module, Type};
+use crate::interface::InterfaceReport;
+use crate::layout::ByteOrder;
+use crate::link::{link_modules, LinkError};
+use llvm_ir::{Constant, Function, Module, Name, Type};
 use llvm_ir::module::{GlobalAlias, GlobalVariable};
 use log::{info, warn};
 use rustc_demangle::demangle;
 use std::fs::DirEntry;
 use std::io;
-use std::path::Path;
+#[cfg(feature = "parallel")]
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
+#[cfg(feature = "parallel")]
+use std::thread;
 
 /// A `Project` is a collection of LLVM code to be explored,
 /// consisting of one or more LLVM modules.
 pub struct Project {
     modules: Vec<Module>,
+    /// Named struct type definitions which `modules` may refer to (weakly),
+    /// but which none of `modules` own. This is only nonempty for `Project`s
+    /// produced by [`link()`](struct.Project.html#method.link).
+    struct_type_defs: Vec<Arc<RwLock<Type>>>,
 }
 
 impl Project {
@@ -20,6 +33,7 @@ impl Project {
         info!("Parsing bitcode in file {}", path.as_ref().display());
         Ok(Self {
             modules: vec![Module::from_bc_path(path)?],
+            struct_type_defs: vec![],
         })
     }
 
@@ -31,6 +45,7 @@ impl Project {
                 .into_iter()
                 .map(|p| Module::from_bc_path(p.as_ref()))
                 .collect::<Result<Vec<_>,_>>()?,
+            struct_type_defs: vec![],
         })
     }
 
@@ -39,10 +54,28 @@ impl Project {
     ///
     /// All files in the directory which have the extension `extn` will
     /// be parsed and added to the `Project`.
+    ///
+    /// With the `parallel` feature, files are parsed in parallel, with one
+    /// thread per available CPU. The `Module`s are in the same order
+    /// regardless. If any files fail to parse, the error lists all of them.
     pub fn from_bc_dir(path: impl AsRef<Path>, extn: &str) -> Result<Self, io::Error> {
         info!("Parsing bitcode from directory {}", path.as_ref().display());
         Ok(Self {
-            modules: Self::modules_from_bc_dir(path, extn, |_| false)?,
+            modules: Self::modules_from_bc_dir(path, extn, |_| false, default_jobs())?,
+            struct_type_defs: vec![],
+        })
+    }
+
+    /// Like [`Project::from_bc_dir()`](struct.Project.html#method.from_bc_dir),
+    /// but parses the files using at most `jobs` threads.
+    ///
+    /// Only available with the `parallel` feature.
+    #[cfg(feature = "parallel")]
+    pub fn from_bc_dir_with_jobs(path: impl AsRef<Path>, extn: &str, jobs: usize) -> Result<Self, io::Error> {
+        info!("Parsing bitcode from directory {} with {} jobs", path.as_ref().display(), jobs);
+        Ok(Self {
+            modules: Self::modules_from_bc_dir(path, extn, |_| false, jobs)?,
+            struct_type_defs: vec![],
         })
     }
 
@@ -52,10 +85,15 @@ impl Project {
     /// All files in the directory which have the extension `extn`, except those
     /// for which the provided `exclude` closure returns `true`, will be parsed
     /// and added to the `Project`.
+    ///
+    /// Files are parsed as in
+    /// [`Project::from_bc_dir()`](struct.Project.html#method.from_bc_dir);
+    /// `exclude` is applied to all of the files first, on the calling thread.
     pub fn from_bc_dir_with_blacklist(path: impl AsRef<Path>, extn: &str, exclude: impl Fn(&Path) -> bool) -> Result<Self, io::Error> {
         info!("Parsing bitcode from directory {} with blacklist", path.as_ref().display());
         Ok(Self {
-            modules: Self::modules_from_bc_dir(path, extn, exclude)?,
+            modules: Self::modules_from_bc_dir(path, extn, exclude, default_jobs())?,
+            struct_type_defs: vec![],
         })
     }
 
@@ -71,7 +109,7 @@ impl Project {
     /// See [`Project::from_bc_dir()`](struct.Project.html#method.from_bc_dir).
     pub fn add_bc_dir(&mut self, path: impl AsRef<Path>, extn: &str) -> Result<(), io::Error> {
         info!("Parsing bitcode from directory {}", path.as_ref().display());
-        let modules = Self::modules_from_bc_dir(path, extn, |_| false)?;
+        let modules = Self::modules_from_bc_dir(path, extn, |_| false, default_jobs())?;
         self.modules.extend(modules);
         Ok(())
     }
@@ -80,7 +118,7 @@ impl Project {
     /// See [`Project::from_bc_dir_with_blacklist()`](struct.Project.html#method.from_bc_dir_with_blacklist).
     pub fn add_bc_dir_with_blacklist(&mut self, path: impl AsRef<Path>, extn: &str, exclude: impl Fn(&Path) -> bool) -> Result<(), io::Error> {
         info!("Parsing bitcode from directory {} with blacklist", path.as_ref().display());
-        let modules = Self::modules_from_bc_dir(path, extn, exclude)?;
+        let modules = Self::modules_from_bc_dir(path, extn, exclude, default_jobs())?;
         self.modules.extend(modules);
         Ok(())
     }
@@ -97,6 +135,50 @@ impl Project {
         self.modules.iter().map(|m| m.global_vars.iter().zip(std::iter::repeat(m))).flatten()
     }
 
+    /// Get the module constructors listed in `@llvm.global_ctors` in any of
+    /// the `Project`'s modules, in the order they should run: by priority, and
+    /// in order of appearance for equal priorities. Gives pairs which also
+    /// indicate the `Module` the constructor is defined in.
+    ///
+    /// Constructors which aren't defined in the `Project` are skipped, with a
+    /// warning.
+    pub fn global_ctors(&self) -> Vec<(&Function, &Module)> {
+        let mut ctors = vec![];
+        for module in &self.modules {
+            let entries = module.global_vars.iter()
+                .find(|var| var.name == Name::from("llvm.global_ctors"))
+                .and_then(|var| var.initializer.as_ref());
+            let entries = match entries {
+                Some(Constant::Array { elements, .. }) => elements,
+                _ => continue,
+            };
+            for entry in entries {
+                // each entry is `{ i32 priority, void ()* ctor, i8* data }`
+                let (priority, ctor) = match entry {
+                    Constant::Struct { values, .. } if values.len() >= 2 => match &values[0] {
+                        Constant::Int { value, .. } => (*value, &values[1]),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                let ctor = match ctor {
+                    Constant::BitCast(bc) => &bc.operand,
+                    ctor => ctor,
+                };
+                match ctor {
+                    Constant::GlobalReference { name: Name::Name(name), .. } => match module.get_func_by_name(name) {
+                        Some(func) => ctors.push((priority, func, module)),
+                        None => warn!("Global constructor {:?} in module {:?} isn't defined in the Project; skipping it", name, module.name),
+                    },
+                    Constant::Null(_) => {},
+                    _ => warn!("Skipping global constructor entry we don't understand in module {:?}: {:?}", module.name, ctor),
+                }
+            }
+        }
+        ctors.sort_by_key(|&(priority, _, _)| priority);  // stable, so equal priorities keep their order
+        ctors.into_iter().map(|(_, func, module)| (func, module)).collect()
+    }
+
     /// Iterate over all `GlobalAlias`es in the `Project`.
     /// Gives pairs which also indicate the `Module` the `GlobalAlias` comes from.
     pub fn all_global_aliases(&self) -> impl Iterator<Item = (&GlobalAlias, &Module)> {
@@ -130,6 +212,17 @@ impl Project {
         self.modules.iter().map(|m| &m.source_file_name)
     }
 
+    /// Get the byte order specified by the data layout of the `Project`'s
+    /// modules, or `None` if none of them specifies one. (We assume the
+    /// modules agree.)
+    pub fn byte_order(&self) -> Option<ByteOrder> {
+        self.modules.iter().find_map(|m| ByteOrder::from_data_layout(&m.data_layout))
+    }
+
+    pub(crate) fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
     /// Search the project for a function with the given name.
     /// If a matching function is found, return both it and the module it was
     /// found in.
@@ -261,7 +354,47 @@ impl Project {
         }
     }
 
-    fn modules_from_bc_dir(path: impl AsRef<Path>, extn: &str, exclude: impl Fn(&Path) -> bool) -> Result<Vec<Module>, io::Error> {
+    /// Get an [`InterfaceReport`](interface/struct.InterfaceReport.html)
+    /// describing the layouts of all the named struct types in the `Project`,
+    /// as well as all of its global variables and function signatures.
+    pub fn interface_report(&self) -> InterfaceReport {
+        InterfaceReport::new(self)
+    }
+
+    /// Link all of the modules in the `Project` into a single module, in the
+    /// manner of `llvm-link`, and return a new `Project` containing just that
+    /// module.
+    ///
+    /// Module-private symbols which clash with other symbols are renamed by
+    /// appending a numeric suffix (e.g., a second internal `helper` becomes
+    /// `helper.1`). Duplicate definitions with discardable linkage (such as
+    /// `linkonce_odr` or `weak`) are deduplicated, keeping a non-discardable
+    /// definition if there is one; declarations of global variables are
+    /// resolved against their definitions; and named struct types with
+    /// layout-compatible definitions are unified.
+    ///
+    /// Returns a [`LinkError`](link/struct.LinkError.html), giving the
+    /// locations of both definitions, if two modules contain genuinely
+    /// conflicting definitions: two non-discardable definitions of the same
+    /// symbol, `_odr` definitions of different types, or incompatible layouts
+    /// for the same named struct type.
+    #[allow(clippy::result_large_err)]  // link errors are rare and fatal, so there's no point boxing them
+    pub fn link(&self) -> Result<Project, LinkError> {
+        let (module, struct_type_defs) = link_modules(&self.modules)?;
+        Ok(Self {
+            modules: vec![module],
+            struct_type_defs: self.struct_type_defs.iter().cloned().chain(struct_type_defs).collect(),
+        })
+    }
+
+    fn modules_from_bc_dir(path: impl AsRef<Path>, extn: &str, exclude: impl Fn(&Path) -> bool, jobs: usize) -> Result<Vec<Module>, io::Error> {
+        let paths = Self::bc_paths_in_dir(path, extn, exclude)?;
+        parse_bc_files(&paths, jobs)
+    }
+
+    /// Get the paths of all files in the directory `path` which have the
+    /// extension `extn`, except those for which `exclude` returns `true`
+    fn bc_paths_in_dir(path: impl AsRef<Path>, extn: &str, exclude: impl Fn(&Path) -> bool) -> Result<Vec<PathBuf>, io::Error> {
         // warning, we use both `Iterator::map` and `Result::map` in here, and it's easy to get them confused
         path
             .as_ref()
@@ -279,8 +412,6 @@ impl Project {
                 },
                 Err(_) => true,  // leave in errors, because we want to know about those
             })
-            .map(|path| path.and_then(|path| Module::from_bc_path(path)
-                .map_err(|s| io::Error::new(io::ErrorKind::Other, s))))
             .collect()
     }
 
@@ -289,8 +420,79 @@ impl Project {
     pub(crate) fn from_module(module: Module) -> Self {
         Self {
             modules: vec![module],
+            struct_type_defs: vec![],
+        }
+    }
+
+    /// For testing only: construct a `Project` directly from several `Module`s
+    #[cfg(test)]
+    pub(crate) fn from_modules(modules: Vec<Module>) -> Self {
+        Self {
+            modules,
+            struct_type_defs: vec![],
+        }
+    }
+}
+
+/// Default number of threads for parsing bitcode files: one per available CPU
+#[cfg(feature = "parallel")]
+fn default_jobs() -> usize {
+    thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1)
+}
+
+/// Without the `parallel` feature, bitcode files are parsed on the calling thread
+#[cfg(not(feature = "parallel"))]
+fn default_jobs() -> usize {
+    1
+}
+
+/// Parse the given bitcode files, using at most `jobs` threads. The `Module`s
+/// are returned in the same order as `paths`. If any of the files fail to
+/// parse, the error lists every one that failed.
+fn parse_bc_files(paths: &[PathBuf], jobs: usize) -> Result<Vec<Module>, io::Error> {
+    let mut modules = Vec::with_capacity(paths.len());
+    let mut failures = vec![];
+    for (path, result) in paths.iter().zip(parse_each(paths, jobs)) {
+        match result {
+            Ok(module) => modules.push(module),
+            Err(e) => failures.push(format!("  {}: {}", path.display(), e)),
         }
     }
+    if failures.is_empty() {
+        Ok(modules)
+    } else {
+        Err(io::Error::new(io::ErrorKind::Other, format!("Failed to parse {} bitcode file(s):\n{}", failures.len(), failures.join("\n"))))
+    }
+}
+
+/// Parse each of the given bitcode files, using at most `jobs` threads, and
+/// return the results in the same order as `paths`
+#[cfg(feature = "parallel")]
+fn parse_each(paths: &[PathBuf], jobs: usize) -> Vec<Result<Module, String>> {
+    let jobs = jobs.clamp(1, paths.len().max(1));
+    let next = AtomicUsize::new(0);
+    let mut parsed: Vec<(usize, Result<Module, String>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0 .. jobs).map(|_| scope.spawn(|| {
+            let mut parsed = vec![];
+            loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                match paths.get(idx) {
+                    Some(path) => parsed.push((idx, Module::from_bc_path(path))),
+                    None => return parsed,
+                }
+            }
+        })).collect();
+        workers.into_iter().flat_map(|worker| worker.join().expect("bitcode parsing thread panicked")).collect()
+    });
+    parsed.sort_by_key(|(idx, _)| *idx);
+    parsed.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Without the `parallel` feature, the files are parsed one after another on
+/// the calling thread
+#[cfg(not(feature = "parallel"))]
+fn parse_each(paths: &[PathBuf], _jobs: usize) -> Vec<Result<Module, String>> {
+    paths.iter().map(Module::from_bc_path).collect()
 }
 
 /// Returns `Some(true)` if the entry is a directory, `Some(false)` if the entry
@@ -308,6 +510,9 @@ fn entry_is_dir(entry: &io::Result<DirEntry>) -> Option<bool> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::link::LinkErrorKind;
+    use crate::test_utils::*;
+    use llvm_ir::module::Linkage;
 
     #[test]
     fn single_file_project() {
@@ -351,4 +556,171 @@ mod tests {
         proj.get_func_by_name("while_loop").expect("Failed to find function while_loop, which should be present");
         assert!(proj.get_func_by_name("no_args_zero").is_none(), "Found function no_args_zero, which is from a file that should have been blacklisted out");
     }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_parsing_matches_sequential() {
+        let paths = Project::bc_paths_in_dir("tests/bcfiles", "bc", |_| false).unwrap();
+        let sequential = Project::from_bc_paths(&paths).unwrap_or_else(|e| panic!("Failed to create project: {}", e));
+        for jobs in &[1, 4] {
+            let parallel = Project::from_bc_dir_with_jobs("tests/bcfiles", "bc", *jobs).unwrap_or_else(|e| panic!("Failed to create project: {}", e));
+            assert_eq!(parallel.modules.len(), sequential.modules.len());
+            for (p, s) in parallel.modules.iter().zip(&sequential.modules) {
+                assert_eq!(p.name, s.name, "module order differs with {} jobs", jobs);
+                assert!(p.functions == s.functions && p.global_vars == s.global_vars, "contents of {} differ with {} jobs", p.name, jobs);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_errors_list_every_file() {
+        let dir = std::env::temp_dir().join(format!("haybale-bad-bitcode-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("bad1.bc"), "not bitcode").unwrap();
+        std::fs::write(dir.join("bad2.bc"), "not bitcode either").unwrap();
+        std::fs::copy("tests/bcfiles/basic.bc", dir.join("basic.bc")).unwrap();
+        let result = Project::from_bc_dir(&dir, "bc");
+        std::fs::remove_dir_all(&dir).unwrap();
+        let message = match result {
+            Ok(_) => panic!("Expected parsing to fail"),
+            Err(e) => e.to_string(),
+        };
+        assert!(message.starts_with("Failed to parse 2 bitcode file(s)"), "unexpected message {:?}", message);
+        assert!(message.contains("bad1.bc") && message.contains("bad2.bc"), "unexpected message {:?}", message);
+    }
+
+    /// Compare the time taken to parse the test directory with one thread and
+    /// with the default number. Run with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    #[cfg(feature = "parallel")]
+    fn parallel_parsing_timing() {
+        let time = |jobs| {
+            let start = std::time::Instant::now();
+            Project::from_bc_dir_with_jobs("tests/bcfiles", "bc", jobs).unwrap_or_else(|e| panic!("Failed to create project: {}", e));
+            start.elapsed()
+        };
+        let sequential = time(1);
+        let parallel = time(default_jobs());
+        println!("parsing tests/bcfiles: {:?} with 1 job, {:?} with {} jobs", sequential, parallel, default_jobs());
+    }
+
+    #[test]
+    fn link_multiple_files() {
+        let proj = Project::from_bc_paths(vec!["tests/bcfiles/call.bc", "tests/bcfiles/crossmod.bc", "tests/bcfiles/globals.bc"].into_iter().map(Path::new))
+            .unwrap_or_else(|e| panic!("Failed to create project: {}", e));
+        let linked = proj.link().unwrap_or_else(|e| panic!("Failed to link project: {}", e));
+        assert_eq!(linked.active_module_names().count(), 1);
+        assert_eq!(linked.all_functions().count(), proj.all_functions().count());
+        let (func, _) = linked.get_func_by_name("cross_module_simple_caller").expect("Failed to find function");
+        assert_eq!(&func.name, "cross_module_simple_caller");
+        linked.get_func_by_name("simple_callee").expect("Failed to find function");
+    }
+
+    #[test]
+    fn link_resolves_global_declarations() {
+        let proj = Project::from_bc_paths(vec!["tests/bcfiles/globals_initialization_1.bc", "tests/bcfiles/globals_initialization_2.bc"].into_iter().map(Path::new))
+            .unwrap_or_else(|e| panic!("Failed to create project: {}", e));
+        let linked = proj.link().unwrap_or_else(|e| panic!("Failed to link project: {}", e));
+        assert_eq!(linked.all_functions().count(), 2);
+        // 15 distinct globals, each defined in one module and possibly declared in the other
+        assert_eq!(linked.all_global_vars().count(), 15);
+        for (var, _) in linked.all_global_vars() {
+            assert!(var.initializer.is_some(), "Expected {} to have been resolved to its definition", var.name);
+        }
+        let (def, _) = linked.get_named_struct_type_by_name("struct.StructWithPointers").expect("Failed to find struct type");
+        assert!(def.is_some());
+    }
+
+    /// A function returning `i32` which either returns a constant or returns
+    /// the result of calling `callee` (which must also return `i32`)
+    fn func_returning(name: &str, linkage: Linkage, callee: Option<&str>) -> Function {
+        let mut func = blank_function(name, vec![llvm_ir::Name::from("entry")]);
+        func.linkage = linkage;
+        func.return_type = Type::i32();
+        let return_operand = match callee {
+            None => int_const(32, 3),
+            Some(callee) => {
+                func.basic_blocks[0].instrs.push(call(callee, Type::i32(), vec![], Some("r")));
+                local("r", Type::i32())
+            },
+        };
+        func.basic_blocks[0].term = ret(return_operand);
+        func
+    }
+
+    fn callee_of(func: &Function) -> &llvm_ir::Name {
+        match &func.basic_blocks[0].instrs[0] {
+            llvm_ir::Instruction::Call(call) => match &call.function {
+                either::Either::Right(llvm_ir::Operand::ConstantOperand(llvm_ir::Constant::GlobalReference { name, .. })) => name,
+                f => panic!("Expected a call to a global, got {:?}", f),
+            },
+            instr => panic!("Expected a call, got {:?}", instr),
+        }
+    }
+
+    #[test]
+    fn link_renames_internal_functions() {
+        let proj = Project {
+            modules: vec![
+                module_with_functions("mod_a", vec![func_returning("helper", Linkage::Internal, None), func_returning("caller_a", Linkage::External, Some("helper"))]),
+                module_with_functions("mod_b", vec![func_returning("helper", Linkage::Internal, None), func_returning("caller_b", Linkage::External, Some("helper"))]),
+            ],
+            struct_type_defs: vec![],
+        };
+        let linked = proj.link().unwrap_or_else(|e| panic!("Failed to link project: {}", e));
+        let mut names: Vec<&str> = linked.all_functions().map(|(f, _)| f.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["caller_a", "caller_b", "helper", "helper.1"]);
+        let (caller_a, _) = linked.get_func_by_name("caller_a").unwrap();
+        assert_eq!(callee_of(caller_a), &llvm_ir::Name::from("helper"));
+        let (caller_b, _) = linked.get_func_by_name("caller_b").unwrap();
+        assert_eq!(callee_of(caller_b), &llvm_ir::Name::from("helper.1"));
+    }
+
+    #[test]
+    fn link_dedupes_linkonce_definitions() {
+        let proj = Project {
+            modules: vec![
+                module_with_functions("mod_a", vec![func_returning("inline_fn", Linkage::LinkOnceODR, None)]),
+                module_with_functions("mod_b", vec![func_returning("inline_fn", Linkage::LinkOnceODR, None)]),
+                module_with_functions("mod_c", vec![func_returning("weak_fn", Linkage::WeakAny, None)]),
+                module_with_functions("mod_d", vec![func_returning("weak_fn", Linkage::External, None)]),
+            ],
+            struct_type_defs: vec![],
+        };
+        let linked = proj.link().unwrap_or_else(|e| panic!("Failed to link project: {}", e));
+        assert_eq!(linked.all_functions().count(), 2);
+        let (weak_fn, _) = linked.get_func_by_name("weak_fn").unwrap();
+        assert_eq!(weak_fn.linkage, Linkage::External);
+    }
+
+    #[test]
+    fn link_reports_conflicts() {
+        let proj = Project {
+            modules: vec![
+                module_with_functions("mod_a", vec![func_returning("f", Linkage::External, None)]),
+                module_with_functions("mod_b", vec![func_returning("f", Linkage::External, None)]),
+            ],
+            struct_type_defs: vec![],
+        };
+        let err = proj.link().err().expect("Expected a link error");
+        assert_eq!(err.kind, LinkErrorKind::DuplicateDefinition);
+        assert_eq!(err.symbol, "f");
+        assert_eq!(err.first.module, "mod_a");
+        assert_eq!(err.second.module, "mod_b");
+
+        let mut odr_b = func_returning("g", Linkage::LinkOnceODR, None);
+        odr_b.return_type = Type::i64();
+        let proj = Project {
+            modules: vec![
+                module_with_functions("mod_a", vec![func_returning("g", Linkage::LinkOnceODR, None)]),
+                module_with_functions("mod_b", vec![odr_b]),
+            ],
+            struct_type_defs: vec![],
+        };
+        let err = proj.link().err().expect("Expected a link error");
+        assert_eq!(err.kind, LinkErrorKind::OdrViolation);
+        assert_eq!(err.symbol, "g");
+    }
 }
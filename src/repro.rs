@@ -0,0 +1,422 @@
+//! Recording the analysis setup alongside results, so that differences in
+//! configuration, hooks, or inputs can be found when a result doesn't
+//! reproduce
+
+use crate::backend::Backend;
+use crate::config::Config;
+use crate::project::Project;
+use llvm_ir::Module;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Everything about an analysis which may affect its results, apart from the
+/// code of `haybale` itself: the `Config` settings, the hooks, checkers, and
+/// other user-supplied components, and the analyzed modules.
+///
+/// Capture one with `ReproBundle::capture()` and save it with the results
+/// (its `Display` form can be read back with `parse()`). To find out why a
+/// result doesn't reproduce, load the bundle and call `verify()` against the
+/// new setup.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReproBundle {
+    /// Version of the `haybale` crate
+    pub crate_version: String,
+    /// `Config.random_seed`
+    pub random_seed: u32,
+    /// The other `Config` settings which can be described as text, by field
+    /// name. Values are in `Debug` format.
+    pub config: BTreeMap<String, String>,
+    /// Hooks, checkers, and other user-supplied components, sorted
+    pub components: Vec<Component>,
+    /// Hash of each module in the `Project`, by module name
+    pub modules: BTreeMap<String, String>,
+}
+
+/// A user-supplied part of the `Config`, such as a hook or checker
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Component {
+    pub kind: ComponentKind,
+    /// For hooks, what the hook applies to (see
+    /// [`HookEntry`](../function_hooks/struct.HookEntry.html)); for checkers
+    /// and rewrite rules, their name; otherwise empty
+    pub name: String,
+    /// The identifier the component was given, if any; see
+    /// [`FunctionHooks::set_hook_id()`](../function_hooks/struct.FunctionHooks.html#method.set_hook_id)
+    /// and [`ViolationChecker::id()`](../checkers/trait.ViolationChecker.html#method.id)
+    pub id: Option<String>,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ComponentKind {
+    Hook,
+    Checker,
+    RewriteRule,
+    ConstraintMiddleware,
+    DescendFilter,
+    InstructionCallback,
+    TerminatorCallback,
+}
+
+/// A difference between a `ReproBundle` and the current setup; see
+/// [`ReproBundle::verify()`](struct.ReproBundle.html#method.verify)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Mismatch {
+    CrateVersion { bundle: String, current: String },
+    /// A `Config` setting differs. `None` means the setting wasn't recorded
+    /// on that side (for instance, by an older version of `haybale`).
+    ConfigField { field: String, bundle: Option<String>, current: Option<String> },
+    /// A component in the bundle which is not in the current setup
+    MissingComponent(Component),
+    /// A component in the current setup which is not in the bundle
+    ExtraComponent(Component),
+    /// A component present in both, but with a different `id`
+    ChangedComponent { bundle: Component, current: Component },
+    /// A module in the bundle which is not in the current `Project`
+    MissingModule(String),
+    /// A module in the current `Project` which is not in the bundle
+    ExtraModule(String),
+    /// A module whose contents differ
+    ModuleHash { module: String, bundle: String, current: String },
+}
+
+impl fmt::Display for ComponentKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            ComponentKind::Hook => "hook",
+            ComponentKind::Checker => "checker",
+            ComponentKind::RewriteRule => "rewrite rule",
+            ComponentKind::ConstraintMiddleware => "constraint middleware",
+            ComponentKind::DescendFilter => "descend filter",
+            ComponentKind::InstructionCallback => "instruction callback",
+            ComponentKind::TerminatorCallback => "terminator callback",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+impl FromStr for ComponentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        [
+            ComponentKind::Hook,
+            ComponentKind::Checker,
+            ComponentKind::RewriteRule,
+            ComponentKind::ConstraintMiddleware,
+            ComponentKind::DescendFilter,
+            ComponentKind::InstructionCallback,
+            ComponentKind::TerminatorCallback,
+        ].iter().copied()
+            .find(|kind| kind.to_string() == s)
+            .ok_or_else(|| format!("unknown component kind {:?}", s))
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if !self.name.is_empty() {
+            write!(f, " {:?}", self.name)?;
+        }
+        if let Some(id) = &self.id {
+            write!(f, " ({})", id)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let or_unset = |value: &Option<String>| value.clone().unwrap_or_else(|| "<not recorded>".to_owned());
+        match self {
+            Mismatch::CrateVersion { bundle, current } => write!(f, "haybale version was {}, now {}", bundle, current),
+            Mismatch::ConfigField { field, bundle, current } => write!(f, "config field {} was {}, now {}", field, or_unset(bundle), or_unset(current)),
+            Mismatch::MissingComponent(component) => write!(f, "missing {}", component),
+            Mismatch::ExtraComponent(component) => write!(f, "additional {}", component),
+            Mismatch::ChangedComponent { bundle, current } => write!(f, "{} was {}, now {}", bundle.kind, bundle, current),
+            Mismatch::MissingModule(module) => write!(f, "missing module {:?}", module),
+            Mismatch::ExtraModule(module) => write!(f, "additional module {:?}", module),
+            Mismatch::ModuleHash { module, bundle, current } => write!(f, "module {:?} has changed (hash was {}, now {})", module, bundle, current),
+        }
+    }
+}
+
+/// First line of the text form of a `ReproBundle`
+const HEADER: &str = "haybale repro bundle";
+
+impl ReproBundle {
+    /// Describe the given `Config` and `Project`
+    pub fn capture<'p, B: Backend>(config: &Config<'p, B>, project: &Project) -> Self {
+        let mut components: Vec<Component> = config.function_hooks.manifest().into_iter()
+            .map(|entry| Component { kind: ComponentKind::Hook, name: entry.hooked, id: entry.id })
+            .chain(config.extra_checkers.iter().map(|checker| Component { kind: ComponentKind::Checker, name: checker.name().to_owned(), id: checker.id().map(str::to_owned) }))
+            .chain(config.rewrite_rules.iter().map(|rule| Component { kind: ComponentKind::RewriteRule, name: rule.name().to_owned(), id: None }))
+            .chain(config.constraint_middleware.iter().map(|_| Component { kind: ComponentKind::ConstraintMiddleware, name: String::new(), id: None }))
+            .chain(config.descend_filter.iter().map(|_| Component { kind: ComponentKind::DescendFilter, name: String::new(), id: None }))
+            .chain(config.callbacks.instruction_callbacks.iter().map(|_| Component { kind: ComponentKind::InstructionCallback, name: String::new(), id: None }))
+            .chain(config.callbacks.terminator_callbacks.iter().map(|_| Component { kind: ComponentKind::TerminatorCallback, name: String::new(), id: None }))
+            .collect();
+        components.sort();
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+            random_seed: config.random_seed,
+            config: config_fields(config),
+            components,
+            modules: project.modules().iter()
+                .map(|module| (module.name.clone(), format!("{:016x}", module_hash(module))))
+                .collect(),
+        }
+    }
+
+    /// Compare this (previously captured) bundle against the given `Config`
+    /// and `Project`, returning every difference found
+    pub fn verify<'p, B: Backend>(&self, config: &Config<'p, B>, project: &Project) -> Vec<Mismatch> {
+        let current = Self::capture(config, project);
+        let mut mismatches = vec![];
+        if self.crate_version != current.crate_version {
+            mismatches.push(Mismatch::CrateVersion { bundle: self.crate_version.clone(), current: current.crate_version.clone() });
+        }
+        if self.random_seed != current.random_seed {
+            mismatches.push(Mismatch::ConfigField { field: "random_seed".to_owned(), bundle: Some(self.random_seed.to_string()), current: Some(current.random_seed.to_string()) });
+        }
+        for field in self.config.keys().chain(current.config.keys().filter(|field| !self.config.contains_key(*field))) {
+            let (bundle, current) = (self.config.get(field), current.config.get(field));
+            if bundle != current {
+                mismatches.push(Mismatch::ConfigField { field: field.clone(), bundle: bundle.cloned(), current: current.cloned() });
+            }
+        }
+        // components are matched up by kind and name; several unnamed
+        // components of the same kind are matched up in order
+        let key = |component: &Component| (component.kind, component.name.clone());
+        let mut unmatched: Vec<&Component> = current.components.iter().collect();
+        for component in &self.components {
+            match unmatched.iter().position(|c| key(c) == key(component)) {
+                Some(idx) => {
+                    let current = unmatched.remove(idx);
+                    if current.id != component.id {
+                        mismatches.push(Mismatch::ChangedComponent { bundle: component.clone(), current: current.clone() });
+                    }
+                },
+                None => mismatches.push(Mismatch::MissingComponent(component.clone())),
+            }
+        }
+        mismatches.extend(unmatched.into_iter().map(|component| Mismatch::ExtraComponent(component.clone())));
+        for (module, hash) in &self.modules {
+            match current.modules.get(module) {
+                None => mismatches.push(Mismatch::MissingModule(module.clone())),
+                Some(current_hash) if current_hash != hash => {
+                    mismatches.push(Mismatch::ModuleHash { module: module.clone(), bundle: hash.clone(), current: current_hash.clone() });
+                },
+                Some(_) => {},
+            }
+        }
+        mismatches.extend(current.modules.keys().filter(|module| !self.modules.contains_key(*module)).map(|module| Mismatch::ExtraModule(module.clone())));
+        mismatches
+    }
+
+    /// Read a bundle back from its `Display` form
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(format!("expected the first line to be {:?}", HEADER));
+        }
+        let mut bundle = Self {
+            crate_version: String::new(),
+            random_seed: 0,
+            config: BTreeMap::new(),
+            components: vec![],
+            modules: BTreeMap::new(),
+        };
+        for line in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields.as_slice() {
+                ["crate_version", version] => bundle.crate_version = version.to_string(),
+                ["random_seed", seed] => bundle.random_seed = seed.parse().map_err(|e| format!("invalid random_seed {:?}: {}", seed, e))?,
+                ["config", field, value] => {
+                    bundle.config.insert(field.to_string(), value.to_string());
+                },
+                ["component", kind, name, id] => bundle.components.push(Component {
+                    kind: kind.parse()?,
+                    name: name.to_string(),
+                    id: if id.is_empty() { None } else { Some(id.to_string()) },
+                }),
+                ["module", name, hash] => {
+                    bundle.modules.insert(name.to_string(), hash.to_string());
+                },
+                [""] => {},
+                _ => return Err(format!("unrecognized line {:?}", line)),
+            }
+        }
+        bundle.components.sort();
+        Ok(bundle)
+    }
+}
+
+/// The text form read by `ReproBundle::parse()`: a header line, then one
+/// tab-separated line per item
+impl fmt::Display for ReproBundle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "crate_version\t{}", self.crate_version)?;
+        writeln!(f, "random_seed\t{}", self.random_seed)?;
+        for (field, value) in &self.config {
+            writeln!(f, "config\t{}\t{}", field, value)?;
+        }
+        for component in &self.components {
+            writeln!(f, "component\t{}\t{}\t{}", component.kind, component.name, component.id.as_deref().unwrap_or(""))?;
+        }
+        for (name, hash) in &self.modules {
+            writeln!(f, "module\t{}\t{}", name, hash)?;
+        }
+        Ok(())
+    }
+}
+
+/// The settings in `config` which can be described as text (everything but
+/// `random_seed`, which is recorded separately, and the user-supplied
+/// components)
+fn config_fields<'p, B: Backend>(config: &Config<'p, B>) -> BTreeMap<String, String> {
+    let sorted = |mut entries: Vec<String>| {
+        entries.sort();
+        entries
+    };
+    let fields = vec![
+        ("loop_bound", format!("{:?}", config.loop_bound)),
+        ("max_loop_iterations", format!("{:?}", config.max_loop_iterations)),
+        ("max_callstack_depth", format!("{:?}", config.max_callstack_depth)),
+        ("stack_frame_overhead", format!("{:?}", config.stack_frame_overhead)),
+        ("solver_query_timeout", format!("{:?}", config.solver_query_timeout)),
+        ("backtracking_order", format!("{:?}", config.backtracking_order)),
+        ("checkpointing", format!("{:?}", config.checkpointing)),
+        ("approximation", format!("{:?}", config.approximation)),
+        ("null_pointer_checking", format!("{:?}", config.null_pointer_checking)),
+        ("undef_values", format!("{:?}", config.undef_values)),
+        ("byte_order", format!("{:?}", config.byte_order)),
+        ("invalid_address_ranges", format!("{:?}", config.invalid_address_ranges)),
+        ("mapped_address_ranges", format!("{:?}", config.mapped_address_ranges)),
+        ("concretize_memcpy_lengths", format!("{:?}", config.concretize_memcpy_lengths)),
+        ("max_memcpy_length", format!("{:?}", config.max_memcpy_length)),
+        ("address_resolution", format!("{:?}", config.address_resolution)),
+        ("malloc_can_fail", format!("{:?}", config.malloc_can_fail)),
+        ("realloc_always_moves", format!("{:?}", config.realloc_always_moves)),
+        ("detect_double_frees", format!("{:?}", config.detect_double_frees)),
+        ("report_memory_leaks", format!("{:?}", config.report_memory_leaks)),
+        ("detect_uninitialized_reads", format!("{:?}", config.detect_uninitialized_reads)),
+        ("max_function_ptr_targets", format!("{:?}", config.max_function_ptr_targets)),
+        ("max_time_step", format!("{:?}", config.max_time_step)),
+        ("squash_unsats", format!("{:?}", config.squash_unsats)),
+        ("trust_llvm_assumes", format!("{:?}", config.trust_llvm_assumes)),
+        ("diagnose_unsat_paths", format!("{:?}", config.diagnose_unsat_paths)),
+        ("exhaustive_threshold_bits", format!("{:?}", config.exhaustive_threshold_bits)),
+        ("preconditions", format!("{:?}", config.preconditions)),
+        ("enum_params", format!("{:?}", sorted(config.enum_params.iter().map(|(param, ty)| format!("{}: {:?}", param, ty)).collect()))),
+        ("shrink_witnesses", format!("{:?}", config.shrink_witnesses)),
+        ("violation_conditions", format!("{:?}", config.violation_conditions)),
+        ("run_global_ctors", format!("{:?}", config.run_global_ctors)),
+        ("global_ctor_paths", format!("{:?}", config.global_ctor_paths)),
+        ("global_ctor_instruction_budget", format!("{:?}", config.global_ctor_instruction_budget)),
+        ("report_filter", format!("{:?}", config.report_filter)),
+        ("iterative_deepening", format!("{:?}", config.iterative_deepening)),
+        ("check_rewrites", format!("{:?}", config.check_rewrites)),
+        ("simplify_constraints", format!("{:?}", config.simplify_constraints)),
+        ("cache_solver_results", format!("{:?}", config.cache_solver_results)),
+        ("initial_mem_watchpoints", format!("{:?}", sorted(config.initial_mem_watchpoints.iter().map(|(name, w)| format!("{}: {:?}", name, w)).collect()))),
+        ("collect_watchpoint_hits", format!("{:?}", config.collect_watchpoint_hits)),
+        ("demangling", format!("{:?}", config.demangling)),
+        ("print_source_info", format!("{:?}", config.print_source_info)),
+        ("print_module_name", format!("{:?}", config.print_module_name)),
+    ];
+    #[cfg(feature = "fp")]
+    let fields: Vec<_> = fields.into_iter().chain(vec![
+        ("fp_rounding_mode", format!("{:?}", config.fp_rounding_mode)),
+        ("fma_semantics", format!("{:?}", config.fma_semantics)),
+    ]).collect();
+    fields.into_iter().map(|(field, value)| (field.to_owned(), value)).collect()
+}
+
+/// Hash the contents of `module`
+fn module_hash(module: &Module) -> u64 {
+    // `named_struct_types` is a `HashMap`, whose `Debug` order isn't stable
+    let mut named_struct_types: Vec<String> = module.named_struct_types.iter().map(|entry| format!("{:?}", entry)).collect();
+    named_struct_types.sort();
+    let contents = format!("{:?}", (
+        &module.source_file_name,
+        &module.data_layout,
+        &module.target_triple,
+        &module.functions,
+        &module.global_vars,
+        &module.global_aliases,
+        named_struct_types,
+        &module.inline_assembly,
+    ));
+    fnv1a(contents.as_bytes())
+}
+
+/// 64-bit FNV-1a, which (unlike `DefaultHasher`) is stable across Rust
+/// versions and platforms
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::checkers::DivisionByZero;
+    use crate::function_hooks::generic_stub_hook;
+    use crate::test_utils::*;
+    use llvm_ir::Name;
+    use std::rc::Rc;
+
+    fn config<'p>() -> Config<'p, BtorBackend> {
+        let mut config = Config {
+            extra_checkers: vec![Rc::new(DivisionByZero)],
+            ..Config::default()
+        };
+        config.function_hooks.add("read_input", &generic_stub_hook);
+        config.function_hooks.set_hook_id("read_input", "stub v1");
+        config
+    }
+
+    #[test]
+    fn round_trip() {
+        let project = blank_project("test_mod", blank_function("f", vec![Name::from("entry")]));
+        let bundle = ReproBundle::capture(&config(), &project);
+        assert!(bundle.components.contains(&Component { kind: ComponentKind::Hook, name: "read_input".to_owned(), id: Some("stub v1".to_owned()) }));
+        assert!(bundle.components.contains(&Component { kind: ComponentKind::Checker, name: "division-by-zero".to_owned(), id: None }));
+        assert_eq!(bundle.config.get("loop_bound").map(String::as_str), Some("10"));
+        assert_eq!(ReproBundle::parse(&bundle.to_string()), Ok(bundle.clone()));
+        assert_eq!(bundle.verify(&config(), &project), vec![]);
+    }
+
+    #[test]
+    fn detect_changes() {
+        let project = blank_project("test_mod", blank_function("f", vec![Name::from("entry")]));
+        let bundle = ReproBundle::parse(&ReproBundle::capture(&config(), &project).to_string()).unwrap();
+
+        let mut changed = Config { loop_bound: 20, ..config() };
+        changed.function_hooks.remove("read_input");
+        changed.function_hooks.set_hook_id("malloc", "my malloc");
+        assert_eq!(bundle.verify(&changed, &project), vec![
+            Mismatch::ConfigField { field: "loop_bound".to_owned(), bundle: Some("10".to_owned()), current: Some("20".to_owned()) },
+            Mismatch::ChangedComponent {
+                bundle: Component { kind: ComponentKind::Hook, name: "malloc".to_owned(), id: None },
+                current: Component { kind: ComponentKind::Hook, name: "malloc".to_owned(), id: Some("my malloc".to_owned()) },
+            },
+            Mismatch::MissingComponent(Component { kind: ComponentKind::Hook, name: "read_input".to_owned(), id: Some("stub v1".to_owned()) }),
+        ]);
+
+        // a module with the same name but different contents
+        let swapped = blank_project("test_mod", blank_function("g", vec![Name::from("entry")]));
+        match bundle.verify(&config(), &swapped).as_slice() {
+            [Mismatch::ModuleHash { module, .. }] => assert_eq!(module, "test_mod"),
+            mismatches => panic!("Expected just a changed module, got {:?}", mismatches),
+        }
+    }
+}
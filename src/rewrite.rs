@@ -0,0 +1,292 @@
+//! Domain-specific simplifications applied while expressions are built; see
+//! [`Config.rewrite_rules`](../config/struct.Config.html#structfield.rewrite_rules).
+
+use crate::backend::{Backend, BV};
+use crate::state::State;
+
+/// The operation which produced a `BV`, with its operands. These are recorded
+/// (only while there are `rewrite_rules`) for the results of integer
+/// instructions and some intrinsics, and can be looked up with
+/// [`State::expr_of()`](../struct.State.html#method.expr_of) to match on the
+/// shape of an expression.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Expr<V> {
+    /// An integer binary operation
+    Binary(BinaryOpcode, V, V),
+    /// The bits `high` down to `low` (inclusive) of `operand`, as produced by
+    /// a `trunc`
+    Extract { operand: V, high: u32, low: u32 },
+    ZExt(V),
+    SExt(V),
+    /// An `llvm.bswap`
+    Bswap(V),
+    /// A load of `bits` bits from `addr`
+    Load { addr: V, bits: u32 },
+}
+
+/// The integer binary operations recorded in an `Expr`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BinaryOpcode {
+    Add,
+    Sub,
+    Mul,
+    UDiv,
+    SDiv,
+    URem,
+    SRem,
+    And,
+    Or,
+    Xor,
+    Shl,
+    LShr,
+    AShr,
+}
+
+/// What an `ExprRewriter` replaces an expression with
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Rewrite<V> {
+    /// Used in place of the original expression. Must be equal to it whenever
+    /// the `assertions` hold.
+    pub replacement: V,
+    /// (1-bit) facts which justify the replacement, for instance injectivity
+    /// of an uninterpreted function standing in for a table lookup. These
+    /// are asserted on the current path.
+    pub assertions: Vec<V>,
+}
+
+impl<V> Rewrite<V> {
+    /// A `Rewrite` which needs no `assertions`
+    pub fn to(replacement: V) -> Self {
+        Self { replacement, assertions: Vec::new() }
+    }
+}
+
+/// A rule for simplifying expressions of a particular shape, to be listed in
+/// [`Config.rewrite_rules`](../config/struct.Config.html#structfield.rewrite_rules).
+///
+/// A rule is offered each expression as it is built, and may return a
+/// `Rewrite` to use instead. Rules should be cheap when they don't apply.
+/// See [`BswapOfBswap`](struct.BswapOfBswap.html) for an example.
+pub trait ExprRewriter<'p, B: Backend> {
+    /// The rule's name, under which its applications are counted; see
+    /// [`State::rewrite_count()`](../struct.State.html#method.rewrite_count)
+    fn name(&self) -> &str;
+
+    /// `expr` describes the expression just built, and `result` is the `BV`
+    /// for it. Return `None` to leave it alone.
+    fn rewrite(&self, expr: &Expr<B::BV>, result: &B::BV, state: &State<'p, B>) -> Option<Rewrite<B::BV>>;
+}
+
+/// Match `bv` as `a ^ b`, returning `a` and `b`
+pub fn match_xor<'s, 'p, B: Backend>(state: &'s State<'p, B>, bv: &B::BV) -> Option<(&'s B::BV, &'s B::BV)> {
+    match state.expr_of(bv)? {
+        Expr::Binary(BinaryOpcode::Xor, a, b) => Some((a, b)),
+        _ => None,
+    }
+}
+
+/// Match `bv` as bits `high` down to `low` of some operand, returning the
+/// operand, `high`, and `low`
+pub fn match_extract<'s, 'p, B: Backend>(state: &'s State<'p, B>, bv: &B::BV) -> Option<(&'s B::BV, u32, u32)> {
+    match state.expr_of(bv)? {
+        Expr::Extract { operand, high, low } => Some((operand, *high, *low)),
+        _ => None,
+    }
+}
+
+/// Match `bv` as `llvm.bswap(x)`, returning `x`
+pub fn match_bswap<'s, 'p, B: Backend>(state: &'s State<'p, B>, bv: &B::BV) -> Option<&'s B::BV> {
+    match state.expr_of(bv)? {
+        Expr::Bswap(x) => Some(x),
+        _ => None,
+    }
+}
+
+/// A lookup in a table at a constant address, e.g. `crc8_table[i]`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TableSelect<'s, V> {
+    /// Address of the start of the table
+    pub table: u64,
+    /// Offset into the table, in bytes: the index scaled by the element size
+    pub offset: &'s V,
+    /// Size of the element loaded, in bits
+    pub bits: u32,
+}
+
+/// Match `bv` as a load from a constant address plus a symbolic offset, as
+/// produced by indexing a global table
+pub fn match_table_select<'s, 'p, B: Backend>(state: &'s State<'p, B>, bv: &B::BV) -> Option<TableSelect<'s, B::BV>> {
+    let (addr, bits) = match state.expr_of(bv)? {
+        Expr::Load { addr, bits } => (addr, *bits),
+        _ => return None,
+    };
+    let (table, offset) = match state.expr_of(addr)? {
+        Expr::Binary(BinaryOpcode::Add, a, b) => match (a.as_u64(), b.as_u64()) {
+            (Some(table), None) => (table, b),
+            (None, Some(table)) => (table, a),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(TableSelect { table, offset, bits })
+}
+
+/// Replaces `bswap(bswap(x))` with `x`.
+///
+/// Byte-order conversions on both sides of an interface otherwise leave
+/// chains of `slice`s and `concat`s which the solver has to see through on
+/// every query.
+pub struct BswapOfBswap;
+
+impl<'p, B: Backend> ExprRewriter<'p, B> for BswapOfBswap {
+    fn name(&self) -> &str {
+        "bswap-of-bswap"
+    }
+
+    fn rewrite(&self, expr: &Expr<B::BV>, _result: &B::BV, state: &State<'p, B>) -> Option<Rewrite<B::BV>> {
+        match expr {
+            Expr::Bswap(x) => match_bswap(state, x).map(|inner| Rewrite::to(inner.clone())),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::config::Config;
+    use crate::return_value::ReturnValue;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::*;
+    use std::rc::Rc;
+
+    fn local_i32(name: &str) -> Operand {
+        local(name, Type::i32())
+    }
+
+    /// A function of `x` which executes the given instructions, and returns `%ret`
+    fn function_of_x(instrs: Vec<Instruction>) -> Function {
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = instrs;
+        func.basic_blocks[0].term = ret(local_i32("ret"));
+        func
+    }
+
+    fn bswap(arg: &str, dest: &str) -> Instruction {
+        call("llvm.bswap.i32", Type::i32(), vec![local_i32(arg)], Some(dest))
+    }
+
+    fn xor(operand: &str, key: u64, dest: &str) -> Instruction {
+        Instruction::Xor(instruction::Xor {
+            operand0: local_i32(operand),
+            operand1: int_const(32, key),
+            dest: Name::from(dest),
+            debugloc: None,
+        })
+    }
+
+    fn returned_bv(em: &mut ExecutionManager<BtorBackend>) -> <BtorBackend as Backend>::BV {
+        match em.next().unwrap().unwrap() {
+            ReturnValue::Return(bv) => bv,
+            retval => panic!("unexpected return value {:?}", retval),
+        }
+    }
+
+    #[test]
+    fn bswap_of_bswap() {
+        let project = blank_project("test_mod", function_of_x(vec![bswap("x", "swapped"), bswap("swapped", "ret")]));
+        let config: Config<BtorBackend> = Config {
+            rewrite_rules: vec![Rc::new(BswapOfBswap)],
+            check_rewrites: true,
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        let ret = returned_bv(&mut em);
+        assert_eq!(ret.get_id(), em.param_bvs()[0].get_id());
+        assert_eq!(em.state().rewrite_count("bswap-of-bswap"), 1);
+
+        // the rule only applies to a pair of bswaps
+        let project = blank_project("test_mod", function_of_x(vec![bswap("x", "ret")]));
+        let config: Config<BtorBackend> = Config {
+            rewrite_rules: vec![Rc::new(BswapOfBswap)],
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        let ret = returned_bv(&mut em);
+        let x = em.param_bvs()[0].clone();
+        assert!(!em.state().bvs_must_be_equal(&ret, &x).unwrap());
+        assert_eq!(em.state().rewrite_count("bswap-of-bswap"), 0);
+    }
+
+    /// Replaces `(x ^ k) ^ k` with `x`
+    struct XorCancel;
+
+    impl<'p> ExprRewriter<'p, BtorBackend> for XorCancel {
+        fn name(&self) -> &str {
+            "xor-cancel"
+        }
+
+        fn rewrite(&self, expr: &Expr<<BtorBackend as Backend>::BV>, _result: &<BtorBackend as Backend>::BV, state: &State<'p, BtorBackend>) -> Option<Rewrite<<BtorBackend as Backend>::BV>> {
+            let (inner, key) = match expr {
+                Expr::Binary(BinaryOpcode::Xor, inner, key) => (inner, key.as_u64()?),
+                _ => return None,
+            };
+            let (x, inner_key) = match_xor(state, inner)?;
+            if inner_key.as_u64()? == key {
+                Some(Rewrite::to(x.clone()))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Unsoundly replaces any `xor` with `0`
+    struct XorIsZero;
+
+    impl<'p> ExprRewriter<'p, BtorBackend> for XorIsZero {
+        fn name(&self) -> &str {
+            "xor-is-zero"
+        }
+
+        fn rewrite(&self, expr: &Expr<<BtorBackend as Backend>::BV>, result: &<BtorBackend as Backend>::BV, state: &State<'p, BtorBackend>) -> Option<Rewrite<<BtorBackend as Backend>::BV>> {
+            match expr {
+                Expr::Binary(BinaryOpcode::Xor, ..) => Some(Rewrite::to(state.zero(result.get_width()))),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn user_rule_is_counted() {
+        let project = blank_project("test_mod", function_of_x(vec![xor("x", 0x5a, "masked"), xor("masked", 0x5a, "ret")]));
+        let config: Config<BtorBackend> = Config {
+            rewrite_rules: vec![Rc::new(XorCancel)],
+            check_rewrites: true,
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        let ret = returned_bv(&mut em);
+        assert_eq!(ret.get_id(), em.param_bvs()[0].get_id());
+        assert_eq!(em.state().rewrite_count("xor-cancel"), 1);
+        assert_eq!(em.state().rewrite_count("bswap-of-bswap"), 0);
+    }
+
+    #[test]
+    fn unsound_rule_is_caught() {
+        let project = blank_project("test_mod", function_of_x(vec![xor("x", 0x5a, "ret")]));
+        let config: Config<BtorBackend> = Config {
+            rewrite_rules: vec![Rc::new(XorIsZero)],
+            check_rewrites: true,
+            ..Config::default()
+        };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        match em.next().unwrap() {
+            Err(crate::Error::OtherError(msg)) => assert!(msg.starts_with("rewrite rule \"xor-is-zero\""), "unexpected message {:?}", msg),
+            other => panic!("expected the rewrite to be rejected, got {:?}", other),
+        }
+    }
+}
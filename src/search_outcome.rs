@@ -0,0 +1,188 @@
+use crate::error::Error;
+use crate::stats::SolverStats;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The result of a search such as
+/// [`find_zero_of_func()`](fn.find_zero_of_func.html) or
+/// [`get_possible_return_values_of_func()`](fn.get_possible_return_values_of_func.html):
+/// the answer, plus a summary of the exploration which produced it.
+///
+/// The summary is available whether or not an answer was found, so callers
+/// can tell "there is no answer" apart from "we gave up before finding one".
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SearchOutcome<T> {
+    /// The answer to the search
+    pub answer: T,
+    /// Summary of the exploration
+    pub summary: ExplorationSummary,
+}
+
+/// Summary of the paths explored during a search
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ExplorationSummary {
+    /// Number of paths which ran to completion (returned, threw, or aborted)
+    pub paths_completed: usize,
+    /// Number of paths which were cut off by a limit, such as the loop bound
+    pub paths_killed: usize,
+    /// Number of paths whose outcome couldn't be determined, because a solver
+    /// query failed (e.g., timed out)
+    pub paths_inconclusive: usize,
+    /// Each distinct limit which cut off at least one path
+    pub limits_hit: Vec<LimitHit>,
+    /// Assumptions made on the path which produced the answer (if any), which
+    /// the answer is only valid under
+    pub assumptions: Vec<String>,
+    /// Wall-clock time spent on the search
+    pub elapsed: Duration,
+    /// Statistics about the solver queries made during the search, across all
+    /// the paths explored
+    pub solver_stats: SolverStats,
+}
+
+/// A limit which cut off exploration of some path
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LimitHit {
+    /// The path exceeded the configured
+    /// [`loop_bound`](config/struct.Config.html#structfield.loop_bound),
+    /// which had the given value
+    LoopBound(usize),
+    /// A solver query was interrupted, timed out, or otherwise failed; see
+    /// [`Config.solver_query_timeout`](config/struct.Config.html#structfield.solver_query_timeout)
+    SolverQuery,
+    /// Calls were skipped because they would have exceeded the configured
+    /// [`max_callstack_depth`](config/struct.Config.html#structfield.max_callstack_depth),
+    /// which had the given value
+    CallstackDepth(usize),
+    /// The search ran out of time; see
+    /// [`DeepeningSchedule.time_budget`](config/struct.DeepeningSchedule.html#structfield.time_budget)
+    TimeBudget,
+}
+
+impl ExplorationSummary {
+    /// Returns `true` if no paths were cut off or inconclusive, and no limits
+    /// were hit, that is, if the search considered every path
+    pub fn is_complete(&self) -> bool {
+        self.paths_killed == 0 && self.paths_inconclusive == 0 && self.limits_hit.is_empty()
+    }
+
+    /// Record a path which ended with the given error.
+    /// Returns `true` if the error just ends that path, so the search can
+    /// continue with other paths, or `false` if the error should be reported
+    /// to the caller.
+    pub(crate) fn record_error(&mut self, e: &Error) -> bool {
+        match e {
+            Error::LoopBoundExceeded(bound) => {
+                self.paths_killed += 1;
+                self.hit(LimitHit::LoopBound(*bound));
+                true
+            },
+            Error::SolverError(_) => {
+                self.paths_inconclusive += 1;
+                self.hit(LimitHit::SolverQuery);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    pub(crate) fn hit(&mut self, limit: LimitHit) {
+        if !self.limits_hit.contains(&limit) {
+            self.limits_hit.push(limit);
+        }
+    }
+}
+
+impl fmt::Display for LimitHit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LimitHit::LoopBound(bound) => write!(f, "loop bound ({})", bound),
+            LimitHit::SolverQuery => write!(f, "solver query failure or timeout"),
+            LimitHit::CallstackDepth(depth) => write!(f, "max callstack depth ({})", depth),
+            LimitHit::TimeBudget => write!(f, "time budget"),
+        }
+    }
+}
+
+impl fmt::Display for ExplorationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "paths completed: {}", self.paths_completed)?;
+        writeln!(f, "paths killed: {}", self.paths_killed)?;
+        writeln!(f, "paths inconclusive: {}", self.paths_inconclusive)?;
+        if !self.limits_hit.is_empty() {
+            let limits: Vec<String> = self.limits_hit.iter().map(|l| l.to_string()).collect();
+            writeln!(f, "limits hit: {}", limits.join(", "))?;
+        }
+        for assumption in &self.assumptions {
+            writeln!(f, "assuming {}", assumption)?;
+        }
+        writeln!(f, "solver: {}", self.solver_stats)?;
+        writeln!(f, "elapsed: {:.3}s", self.elapsed.as_secs_f64())
+    }
+}
+
+/// Tracks the elapsed time of a search, for filling in
+/// `ExplorationSummary.elapsed`
+pub(crate) struct SearchTimer(Instant);
+
+impl SearchTimer {
+    pub(crate) fn start() -> Self {
+        SearchTimer(Instant::now())
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+
+    pub(crate) fn finish<T>(self, answer: T, mut summary: ExplorationSummary) -> SearchOutcome<T> {
+        summary.elapsed = self.0.elapsed();
+        SearchOutcome { answer, summary }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::{get_possible_return_values_of_func, Config};
+    use llvm_ir::{Constant, Name, Operand, Type};
+
+    #[test]
+    fn record_error() {
+        let mut summary = ExplorationSummary::default();
+        assert!(summary.is_complete());
+        assert!(summary.record_error(&Error::LoopBoundExceeded(3)));
+        assert!(summary.record_error(&Error::LoopBoundExceeded(3)));
+        assert!(summary.record_error(&Error::SolverError("timed out".to_owned())));
+        assert!(!summary.record_error(&Error::NullPointerDereference));
+        assert_eq!(summary.paths_killed, 2);
+        assert_eq!(summary.paths_inconclusive, 1);
+        assert_eq!(summary.limits_hit, vec![LimitHit::LoopBound(3), LimitHit::SolverQuery]);
+        assert!(!summary.is_complete());
+    }
+
+    #[test]
+    fn possible_return_values_errors() {
+        // a return value too wide to report
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.return_type = Type::IntegerType { bits: 128 };
+        func.basic_blocks[0].term = ret(int_const(128, 0));
+        let project = blank_project("test_mod", func);
+        let e = get_possible_return_values_of_func("f", vec![], &project, Config::default(), None, 2).unwrap_err();
+        assert!(e.contains("128-bit"), "unexpected message {:?}", e);
+
+        // a path ending in an error
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(load(Operand::ConstantOperand(Constant::Null(Type::pointer_to(Type::i32()))), "v"));
+        func.basic_blocks[0].term = ret(local("v", Type::i32()));
+        let project = blank_project("test_mod", func);
+        let e = get_possible_return_values_of_func("f", vec![], &project, Config::default(), None, 2).unwrap_err();
+        assert!(e.contains("null pointer"), "unexpected message {:?}", e);
+    }
+}
@@ -8,7 +8,6 @@ use crate::backend::SolverRef;
 use crate::error::*;
 use crate::solver_utils::bvs_can_be_equal;
 use log::debug;
-use reduce::Reduce;
 use std::rc::Rc;
 
 type BV = boolector::BV<Rc<Btor>>;
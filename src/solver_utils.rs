@@ -1,6 +1,6 @@
 //! Simple utilities for interacting with the solver
 
-use boolector::{Btor, BVSolution, SolverResult};
+use boolector::{Btor, BVSolution};
 use boolector::option::{BtorOption, ModelGen};
 use crate::backend::BV;
 use crate::error::*;
@@ -9,6 +9,8 @@ use std::collections::HashSet;
 use std::hash::Hash;
 use std::ops::Deref;
 
+pub use boolector::SolverResult;
+
 /// Returns `true` if current constraints are satisfiable, `false` if not.
 ///
 /// Returns `Error::SolverError` if the query failed (e.g., was interrupted or timed out).
@@ -20,6 +22,32 @@ pub fn sat(btor: &Btor) -> Result<bool> {
     }
 }
 
+/// Like `sat()`, but distinguishes a failed query from an unsatisfiable one:
+/// returns `SolverResult::Unknown` if the query was interrupted or timed out,
+/// rather than an error.
+pub fn check(btor: &Btor) -> SolverResult {
+    btor.sat()
+}
+
+/// Like `sat_with_extra_constraints()`, but returns `SolverResult::Unknown`
+/// rather than an error if the query was interrupted or timed out.
+///
+/// Does not permanently add the constraints in `conds` to the solver.
+pub fn check_with_extra_constraints<I, B>(btor: &Btor, constraints: impl IntoIterator<Item = I>) -> Result<SolverResult>
+    where I: Deref<Target = B>, B: BV
+{
+    btor.push(1);
+    for constraint in constraints {
+        if let Err(e) = constraint.assert() {
+            btor.pop(1);
+            return Err(e);
+        }
+    }
+    let retval = check(btor);
+    btor.pop(1);
+    Ok(retval)
+}
+
 /// Returns `true` if the current constraints plus the additional constraints `conds`
 /// are together satisfiable, or `false` if not.
 ///
@@ -38,6 +66,73 @@ pub fn sat_with_extra_constraints<I, B>(btor: &Btor, constraints: impl IntoItera
     retval
 }
 
+/// Run `f` with the additional constraints `conds` temporarily asserted, and
+/// return its result.
+///
+/// The constraints are removed again when `f` returns, and also if it panics.
+/// `f` must leave the solver's assertion stack as it found it (any `push()`
+/// it does, it must also `pop()`).
+///
+/// Returns `Err` (without running `f`) if asserting the constraints failed.
+pub fn with_extra_constraints<I, B, R>(btor: &Btor, constraints: impl IntoIterator<Item = I>, f: impl FnOnce() -> R) -> Result<R>
+    where I: Deref<Target = B>, B: BV
+{
+    btor.push(1);
+    let _guard = PopOnDrop(btor);
+    for constraint in constraints {
+        constraint.assert()?;
+    }
+    Ok(f())
+}
+
+/// Pops one level of the assertion stack when dropped, including on unwind
+struct PopOnDrop<'b>(&'b Btor);
+
+impl<'b> Drop for PopOnDrop<'b> {
+    fn drop(&mut self) {
+        self.0.pop(1);
+    }
+}
+
+/// Find which of the given labelled constraints conflict with each other (and
+/// with the current constraints), without permanently adding any of them to
+/// the solver.
+///
+/// Returns `Ok(None)` if the current constraints plus all of the labelled
+/// constraints are satisfiable. Otherwise, returns the labels of a minimal
+/// subset of the labelled constraints which, together with the current
+/// constraints, is unsatisfiable: dropping any one of them would make it
+/// satisfiable. (If the current constraints are unsatisfiable on their own,
+/// that subset is empty.) Labels are returned in the order they were given.
+///
+/// Returns `Error::SolverError` if a query failed (e.g., was interrupted or timed out).
+pub fn unsat_core<L: Clone, V: BV>(btor: &Btor, labelled: &[(L, V)]) -> Result<Option<Vec<L>>> {
+    // boolector gives us the failed assumptions, which are a core but not
+    // necessarily a minimal one
+    for (_, constraint) in labelled {
+        constraint.assume();
+    }
+    if sat(btor)? {
+        return Ok(None);
+    }
+    let mut core: Vec<&(L, V)> = labelled.iter().filter(|(_, constraint)| constraint.is_failed_assumption()).collect();
+    // then minimize by deletion
+    let mut i = 0;
+    while i < core.len() {
+        for (j, (_, constraint)) in core.iter().enumerate() {
+            if j != i {
+                constraint.assume();
+            }
+        }
+        if sat(btor)? {
+            i += 1;
+        } else {
+            core.remove(i);
+        }
+    }
+    Ok(Some(core.into_iter().map(|(label, _)| label.clone()).collect()))
+}
+
 /// Returns `true` if under the current constraints, `a` and `b` must have the
 /// same value. Returns `false` if `a` and `b` may have different values. (If the
 /// current constraints are themselves unsatisfiable, that will result in
@@ -127,6 +222,46 @@ impl<V: Eq + Hash> PossibleSolutions<V> {
     }
 }
 
+/// The result of evaluating a `BV` under the current constraints; see
+/// [`State::eval_bv()`](../struct.State.html#method.eval_bv).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Evaluated {
+    /// The solver's model gives the `BV` this value. Individual bits of the
+    /// `BVSolution` may still be don't-cares; see docs on
+    /// `boolector::BVSolution`.
+    Concrete(BVSolution),
+    /// The solver's model doesn't constrain the `BV` at all: every bit is a
+    /// don't-care, so any value is consistent with the rest of the model.
+    DontCare,
+    /// The current constraints are unsatisfiable, so there is no model.
+    NoModel,
+}
+
+impl Evaluated {
+    /// Classify a `BVSolution` obtained from the solver's model: solutions
+    /// consisting entirely of don't-care bits are `DontCare`, and all others
+    /// are `Concrete`.
+    pub fn from_solution(solution: BVSolution) -> Self {
+        if solution.as_01x_str().chars().all(|c| c == 'x') {
+            Evaluated::DontCare
+        } else {
+            Evaluated::Concrete(solution)
+        }
+    }
+
+    /// Get a `u64` value for the evaluated `BV`, choosing `0` for any
+    /// don't-care bits (and thus `0` for `DontCare`).
+    ///
+    /// Returns `None` for `NoModel`, or if the value does not fit in 64 bits.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Evaluated::Concrete(solution) => solution.as_u64(),
+            Evaluated::DontCare => Some(0),
+            Evaluated::NoModel => None,
+        }
+    }
+}
+
 /// Get a description of the possible solutions for the `BV`.
 ///
 /// `n`: Maximum number of distinct solutions to check for.
@@ -364,9 +499,12 @@ pub fn max_possible_solution_for_bv_as_binary_str<V: BV>(solver: V::SolverRef, b
     loop {
         let width = bv.get_width();
         if width <= 64 {
-            let max_for_remaining_bits = match max_possible_solution_for_bv_as_u64(solver.clone(), &bv)? {
-                Some(max) => max,
-                None => return Ok(None),
+            let max_for_remaining_bits = match max_possible_solution_for_bv_as_u64(solver.clone(), &bv) {
+                Ok(Some(max)) => max,
+                res => {
+                    solver.pop(1);
+                    return res.map(|_| None);
+                },
             };
             retval.push_str(&format!("{val:0width$b}", val = max_for_remaining_bits, width = width as usize));
             break;
@@ -375,9 +513,12 @@ pub fn max_possible_solution_for_bv_as_binary_str<V: BV>(solver: V::SolverRef, b
             let high_bits = bv.slice(top_bit, top_bit - 63);
             assert_eq!(high_bits.get_width(), 64);
             bv = bv.slice(top_bit - 64, 0);
-            let max_for_high_bits = match max_possible_solution_for_bv_as_u64(solver.clone(), &high_bits)? {
-                Some(max) => max,
-                None => return Ok(None),
+            let max_for_high_bits = match max_possible_solution_for_bv_as_u64(solver.clone(), &high_bits) {
+                Ok(Some(max)) => max,
+                res => {
+                    solver.pop(1);
+                    return res.map(|_| None);
+                },
             };
             retval.push_str(&format!("{:064b}", max_for_high_bits));
             // now (temporarily, thanks to the push() above) constrain that
@@ -407,12 +548,16 @@ pub fn min_possible_solution_for_bv_as_binary_str<V: BV>(solver: V::SolverRef, b
     let mut bv = bv.clone();
     let total_width = bv.get_width();
     let mut retval = String::with_capacity(total_width as usize);
+    solver.push(1);
     loop {
         let width = bv.get_width();
         if width <= 64 {
-            let min_for_remaining_bits = match min_possible_solution_for_bv_as_u64(solver.clone(), &bv)? {
-                Some(max) => max,
-                None => return Ok(None),
+            let min_for_remaining_bits = match min_possible_solution_for_bv_as_u64(solver.clone(), &bv) {
+                Ok(Some(min)) => min,
+                res => {
+                    solver.pop(1);
+                    return res.map(|_| None);
+                },
             };
             retval.push_str(&format!("{val:0width$b}", val = min_for_remaining_bits, width = width as usize));
             break;
@@ -421,9 +566,12 @@ pub fn min_possible_solution_for_bv_as_binary_str<V: BV>(solver: V::SolverRef, b
             let high_bits = bv.slice(top_bit, top_bit - 63);
             assert_eq!(high_bits.get_width(), 64);
             bv = bv.slice(top_bit - 64, 0);
-            let min_for_high_bits = match min_possible_solution_for_bv_as_u64(solver.clone(), &high_bits)? {
-                Some(min) => min,
-                None => return Ok(None),
+            let min_for_high_bits = match min_possible_solution_for_bv_as_u64(solver.clone(), &high_bits) {
+                Ok(Some(min)) => min,
+                res => {
+                    solver.pop(1);
+                    return res.map(|_| None);
+                },
             };
             retval.push_str(&format!("{:064b}", min_for_high_bits));
             // now (temporarily, thanks to the push() above) constrain that
@@ -432,10 +580,155 @@ pub fn min_possible_solution_for_bv_as_binary_str<V: BV>(solver: V::SolverRef, b
             high_bits._eq(&V::from_u64(solver.clone(), min_for_high_bits, 64)).assert()?;
         }
     }
+    solver.pop(1);
     assert_eq!(retval.len(), total_width as usize, "Should have a string of {} characters, but have one of {} characters: {:?}", total_width, retval.len(), retval);
     Ok(Some(retval))
 }
 
+/// Get the maximum possible solution for the `BV`: that is, the highest value
+/// for which the current set of constraints is still satisfiable.
+/// "Maximum" will be interpreted in a signed fashion.
+///
+/// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
+/// current set of constraints is unsatisfiable. Only returns `Err` if a solver
+/// query itself fails. Panics if the `BV` is wider than 64 bits.
+pub fn max_possible_solution_for_bv_as_i64<V: BV>(solver: V::SolverRef, bv: &V) -> Result<Option<i64>> {
+    let width = bv.get_width();
+    if width > 64 {
+        panic!("max_possible_solution_for_bv_as_i64 on a BV with width > 64");
+    }
+    // flipping the sign bit maps signed order onto unsigned order
+    let flipped = flip_sign_bit(solver.clone(), bv);
+    Ok(max_possible_solution_for_bv_as_u64(solver, &flipped)?.map(|u| unflip_sign_bit(u, width)))
+}
+
+/// Get the minimum possible solution for the `BV`: that is, the lowest value
+/// for which the current set of constraints is still satisfiable.
+/// "Minimum" will be interpreted in a signed fashion.
+///
+/// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
+/// current set of constraints is unsatisfiable. Only returns `Err` if a solver
+/// query itself fails. Panics if the `BV` is wider than 64 bits.
+pub fn min_possible_solution_for_bv_as_i64<V: BV>(solver: V::SolverRef, bv: &V) -> Result<Option<i64>> {
+    let width = bv.get_width();
+    if width > 64 {
+        panic!("min_possible_solution_for_bv_as_i64 on a BV with width > 64");
+    }
+    let flipped = flip_sign_bit(solver.clone(), bv);
+    Ok(min_possible_solution_for_bv_as_u64(solver, &flipped)?.map(|u| unflip_sign_bit(u, width)))
+}
+
+fn flip_sign_bit<V: BV>(solver: V::SolverRef, bv: &V) -> V {
+    let width = bv.get_width();
+    bv.xor(&V::from_u64(solver, 1 << (width - 1), width))
+}
+
+/// Undo `flip_sign_bit()` on a `width`-bit solution, and sign-extend the result
+fn unflip_sign_bit(u: u64, width: u32) -> i64 {
+    let unflipped = u ^ (1 << (width - 1));
+    let shift = 64 - width;
+    ((unflipped << shift) as i64) >> shift
+}
+
+/// Find simple values for the given `BV`s which are consistent with the
+/// current constraints, for presenting a witness (such as a set of function
+/// arguments) that is easier to read than an arbitrary solution.
+///
+/// The `BV`s are shrunk greedily, in order. Each is fixed to zero if possible;
+/// otherwise we take any solution and repeatedly try halving its magnitude
+/// (treating it as signed), keeping the last value which worked. Each `BV` is
+/// shrunk subject to the values already chosen for the earlier ones. At most
+/// `budget` solver queries are spent on shrinking; once the budget is used up,
+/// the remaining `BV`s just get some solution consistent with the values
+/// already chosen.
+///
+/// `BV`s wider than 64 bits are left unshrunk and unconstrained, and their
+/// entries in the result are `None`.
+///
+/// Returns `Ok(None)` if the current set of constraints is unsatisfiable.
+/// Only returns `Err` if a solver query itself fails.
+///
+/// Does not permanently add any constraints to the solver.
+/// [`State::shrink_witness()`](../struct.State.html#method.shrink_witness)
+/// does the same, making its queries through the `State`.
+pub fn shrink_witness<V: BV>(solver: V::SolverRef, bvs: &[V], budget: usize) -> Result<Option<Vec<Option<u64>>>> {
+    if !sat(&solver)? {
+        return Ok(None);
+    }
+    solver.push(1);
+    let retval = shrink_witness_values(
+        bvs,
+        budget,
+        |a, b| bvs_can_be_equal(&solver, a, b),
+        |bv| Ok(get_possible_solutions_for_bv(solver.clone(), bv, 0)?
+            .as_u64_solutions()
+            .and_then(|ps| match ps {
+                PossibleSolutions::AtLeast(s) | PossibleSolutions::Exactly(s) => s.into_iter().next(),
+            })),
+        |constraint| constraint.assert(),
+    );
+    solver.pop(1);
+    retval.map(Some)
+}
+
+/// Helper for `shrink_witness()` and `State::shrink_witness()`, which assumes
+/// the constraints are satisfiable, and that the caller will pop the
+/// constraints we add. The queries go through the given functions: whether
+/// two `BV`s can be equal, some solution for a `BV` (of at most 64 bits), and
+/// asserting a constraint.
+pub(crate) fn shrink_witness_values<V: BV>(
+    bvs: &[V],
+    budget: usize,
+    can_be_equal: impl Fn(&V, &V) -> Result<bool>,
+    a_solution: impl Fn(&V) -> Result<Option<u64>>,
+    assert: impl Fn(&V) -> Result<()>,
+) -> Result<Vec<Option<u64>>> {
+    let mut queries = 0;
+    let mut values = Vec::with_capacity(bvs.len());
+    for bv in bvs {
+        let width = bv.get_width();
+        if width > 64 {
+            values.push(None);
+            continue;
+        }
+        let solver = bv.get_solver();
+        let mask = if width == 64 { u64::MAX } else { (1 << width) - 1 };
+        let mut value = None;
+        if queries < budget {
+            queries += 1;
+            if can_be_equal(bv, &V::zero(solver.clone(), width))? {
+                value = Some(0);
+            }
+        }
+        let value = match value {
+            Some(value) => value,
+            None => {
+                // the values we've already fixed are satisfiable, so there must be a solution
+                queries += 1;
+                let mut value = a_solution(bv)?
+                    .ok_or_else(|| Error::OtherError("shrink_witness: expected a solution".to_owned()))?;
+                while queries < budget {
+                    let signed = if width == 64 { value as i64 } else { ((value << (64 - width)) as i64) >> (64 - width) };
+                    let halved = (signed / 2) as u64 & mask;
+                    if halved == value {
+                        break;
+                    }
+                    queries += 1;
+                    if can_be_equal(bv, &V::from_u64(solver.clone(), halved, width))? {
+                        value = halved;
+                    } else {
+                        break;
+                    }
+                }
+                value
+            },
+        };
+        assert(&bv._eq(&V::from_u64(solver, value, width)))?;
+        values.push(Some(value));
+    }
+    Ok(values)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -461,6 +754,16 @@ mod tests {
         assert_eq!(sat(&btor), Ok(true));
     }
 
+    #[test]
+    fn evaluated_from_solution() {
+        assert_eq!(Evaluated::from_solution(BVSolution::from_01x_str("xxxx")), Evaluated::DontCare);
+        assert_eq!(Evaluated::DontCare.as_u64(), Some(0));
+        let partial = Evaluated::from_solution(BVSolution::from_01x_str("1x01"));
+        assert_eq!(partial, Evaluated::Concrete(BVSolution::from_01x_str("1x01")));
+        assert_eq!(partial.as_u64(), Some(0b1001));
+        assert_eq!(Evaluated::NoModel.as_u64(), None);
+    }
+
     #[test]
     fn basic_unsat() {
         let btor = <Rc<Btor> as SolverRef>::new();
@@ -552,6 +855,27 @@ mod tests {
         assert_eq!(solutions, Some(PossibleSolutions::Exactly(HashSet::new())));
     }
 
+    #[test]
+    fn unsat_core_is_minimal() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        let x: BV = BV::new(btor.clone(), 64, Some("x"));
+        let y: BV = BV::new(btor.clone(), 64, Some("y"));
+        let labelled = vec![
+            ("x > 5", x.ugt(&BV::from_u64(btor.clone(), 5, 64))),
+            ("y < 10", y.ult(&BV::from_u64(btor.clone(), 10, 64))),
+            ("x < 3", x.ult(&BV::from_u64(btor.clone(), 3, 64))),
+            ("x < 7", x.ult(&BV::from_u64(btor.clone(), 7, 64))),
+        ];
+        assert_eq!(unsat_core(&btor, &labelled), Ok(Some(vec!["x > 5", "x < 3"])));
+        // nothing was permanently asserted
+        assert_eq!(sat(&btor), Ok(true));
+        assert_eq!(unsat_core(&btor, &labelled[1..]), Ok(None));
+
+        // constraints conflicting with the current constraints
+        y.ugt(&BV::from_u64(btor.clone(), 20, 64)).assert();
+        assert_eq!(unsat_core(&btor, &labelled[..2]), Ok(Some(vec!["y < 10"])));
+    }
+
     #[test]
     fn min_possible_solution() {
         let btor = <Rc<Btor> as SolverRef>::new();
@@ -606,6 +930,47 @@ mod tests {
         assert_eq!(max_possible_solution_for_bv_as_u64(btor.clone(), &x), Ok(None));
     }
 
+    #[test]
+    fn signed_min_max_possible_solution() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+
+        // constrain -5 <= x < 7 (signed, 32-bit x)
+        let x: BV = BV::new(btor.clone(), 32, Some("x"));
+        x.sgte(&BV::from_i64(btor.clone(), -5, 32)).assert();
+        x.slt(&BV::from_i64(btor.clone(), 7, 32)).assert();
+        assert_eq!(min_possible_solution_for_bv_as_i64(btor.clone(), &x), Ok(Some(-5)));
+        assert_eq!(max_possible_solution_for_bv_as_i64(btor.clone(), &x), Ok(Some(6)));
+        // unsigned, the negative values are the largest
+        assert_eq!(min_possible_solution_for_bv_as_u64(btor.clone(), &x), Ok(Some(0)));
+        assert_eq!(max_possible_solution_for_bv_as_u64(btor.clone(), &x), Ok(Some(0xffff_ffff)));
+
+        // unconstrained y: the extremes are the extremes of the type
+        let y: BV = BV::new(btor.clone(), 8, Some("y"));
+        assert_eq!(min_possible_solution_for_bv_as_i64(btor.clone(), &y), Ok(Some(-128)));
+        assert_eq!(max_possible_solution_for_bv_as_i64(btor.clone(), &y), Ok(Some(127)));
+        let z: BV = BV::new(btor.clone(), 64, Some("z"));
+        assert_eq!(min_possible_solution_for_bv_as_i64(btor.clone(), &z), Ok(Some(i64::MIN)));
+        assert_eq!(max_possible_solution_for_bv_as_i64(btor.clone(), &z), Ok(Some(i64::MAX)));
+
+        // unsat gives `None`
+        x.slt(&BV::from_i64(btor.clone(), -5, 32)).assert();
+        assert_eq!(max_possible_solution_for_bv_as_i64(btor.clone(), &x), Ok(None));
+        assert_eq!(min_possible_solution_for_bv_as_i64(btor.clone(), &x), Ok(None));
+    }
+
+    #[test]
+    fn max_possible_solution_str_unconstrained() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+
+        // unconstrained 96-bit x: the max is all ones, and the min all zeroes
+        let x: BV = BV::new(btor.clone(), 96, Some("x"));
+        assert_eq!(max_possible_solution_for_bv_as_binary_str(btor.clone(), &x), Ok(Some("1".repeat(96))));
+        assert_eq!(min_possible_solution_for_bv_as_binary_str(btor.clone(), &x), Ok(Some("0".repeat(96))));
+
+        // nothing was permanently asserted while finding those
+        assert!(bvs_can_be_equal(&btor, &x, &BV::from_u64(btor.clone(), 5, 96)).unwrap());
+    }
+
     #[test]
     fn min_possible_solution_str() {
         let btor = <Rc<Btor> as SolverRef>::new();
@@ -661,4 +1026,29 @@ mod tests {
         // The max possible (unsigned) solution should be -2
         assert_eq!(max_possible_solution_for_bv_as_u64(btor.clone(), &x), Ok(Some((-2_i64) as u64)));
     }
+
+    #[test]
+    fn shrink_witness() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        let x: BV = BV::new(btor.clone(), 32, Some("x"));
+        let y: BV = BV::new(btor.clone(), 32, Some("y"));
+        let z: BV = BV::new(btor.clone(), 32, Some("z"));
+        // x + y + z == 1000, with z > 100 (signed)
+        x.add(&y).add(&z)._eq(&BV::from_u32(btor.clone(), 1000, 32)).assert();
+        z.sgt(&BV::from_u32(btor.clone(), 100, 32)).assert();
+        let shrunk = super::shrink_witness(btor.clone(), &[x.clone(), y.clone(), z.clone()], 100).unwrap().unwrap();
+        assert_eq!(shrunk, vec![Some(0), Some(0), Some(1000)]);
+        // shrinking doesn't leave any constraints behind
+        assert_eq!(bvs_can_be_equal(&btor, &x, &BV::from_u32(btor.clone(), 7, 32)), Ok(true));
+
+        // with no budget, we still get a valid witness
+        let witness = super::shrink_witness(btor.clone(), &[x.clone(), y.clone(), z.clone()], 0).unwrap().unwrap();
+        let witness: Vec<u64> = witness.into_iter().map(Option::unwrap).collect();
+        assert_eq!(witness[0].wrapping_add(witness[1]).wrapping_add(witness[2]) & 0xffff_ffff, 1000);
+
+        // values wider than 64 bits are left alone, and the rest are still shrunk
+        let wide: BV = BV::new(btor.clone(), 128, Some("wide"));
+        let shrunk = super::shrink_witness(btor.clone(), &[wide, x, y, z], 100).unwrap().unwrap();
+        assert_eq!(shrunk, vec![None, Some(0), Some(0), Some(1000)]);
+    }
 }
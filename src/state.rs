@@ -4,27 +4,39 @@ use either::Either;
 use itertools::Itertools;
 use llvm_ir::*;
 use log::{debug, info, warn};
-use reduce::Reduce;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::ops::Deref;
+use std::ops::{Deref, Range};
+use std::rc::Rc;
 use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
 use crate::alloc::Alloc;
 use crate::backend::*;
-use crate::config::{Config, NullPointerChecking};
+use crate::config::{AddressResolution, BacktrackingOrder, Checkpointing, Config, GlobalCtorPaths, NullPointerChecking, UndefValues};
 use crate::demangling::Demangling;
+use crate::diagnosis::{PathCondition, UnsatDiagnosis, MAX_UNSAT_DIAGNOSES};
 use crate::error::*;
+#[cfg(feature = "fp")]
+use crate::float;
 use crate::function_hooks::{self, FunctionHooks};
 use crate::global_allocations::*;
+use crate::clocks::Clock;
+use crate::handles::{Handle, HandleState};
+use crate::heap_graph::{self, EdgeTarget, FieldValue, HeapEdge, HeapField, HeapGraph, HeapNode};
 use crate::hooks;
 use crate::layout::*;
+use crate::middleware::{ConstraintCtx, ConstraintOrigin, MiddlewareAction};
 use crate::project::Project;
-use crate::solver_utils::{self, PossibleSolutions};
+use crate::rewrite::Expr;
+use crate::solver_utils::{self, Evaluated, PossibleSolutions, SolverResult};
+use crate::stats::SolverStats;
+use crate::tags::Tag;
 use crate::varmap::{VarMap, RestoreInfo};
-use crate::watchpoints::{Watchpoint, Watchpoints};
+use crate::watchpoints::{Access, Watchpoint, WatchpointHit, Watchpoints};
+use crate::PointerTarget;
 
 /// A `State` describes the full program state at a given moment during symbolic
 /// execution.
@@ -40,8 +52,14 @@ pub struct State<'p, B: Backend> {
     // Private members
     varmap: VarMap<B::BV>,
     mem: RefCell<B::Memory>,
+    /// With `Config.detect_uninitialized_reads`, a shadow of `mem` in which
+    /// each bit is `1` if the corresponding bit of `mem` is uninitialized.
+    /// Anything the program never allocated counts as initialized.
+    shadow: Option<RefCell<B::Memory>>,
     alloc: Alloc,
     global_allocations: GlobalAllocations<'p, B>,
+    /// The `Project` being executed
+    project: &'p Project,
     /// Separate from the user-defined hooks in the `config`, these are built-in
     /// hooks for LLVM intrinsics. They can be overridden by hooks in the
     /// `config`; see notes on function resolution in function_hooks.rs.
@@ -66,6 +84,13 @@ pub struct State<'p, B: Backend> {
     /// backtracking will not touch the set of mem_watchpoints or their
     /// enabled statuses.
     mem_watchpoints: Watchpoints,
+    /// With `Config.collect_watchpoint_hits`, the accesses which triggered
+    /// `mem_watchpoints` so far, on all paths. Like the watchpoints, these
+    /// aren't touched by backtracking.
+    watchpoint_hits: RefCell<Vec<WatchpointHit<B::BV>>>,
+    /// Index of the path currently being explored, for `watchpoint_hits`: the
+    /// number of times `ExecutionManager::next()` was called before this one
+    pub(crate) path_index: usize,
     /// Empirically, solving with model-gen enabled can be very slow.
     /// In particular, given a `BV` representing a function pointer, solving for
     /// the concrete function pointer it represents can be slow.
@@ -82,6 +107,193 @@ pub struct State<'p, B: Backend> {
     /// anyway, and function pointers _probably_ resolve to the same value on
     /// multiple paths.
     function_ptr_cache: HashMap<Location<'p>, u64>,
+    /// Constant values which `BV`s are known to have on the current path,
+    /// because an equality between the `BV` and a constant has been asserted
+    /// (by an `llvm.assume`, a branch, or a concretization).
+    ///
+    /// Keyed by `BV` id. We also hold on to the `BV` itself, so that its id
+    /// can't be reused for a different node while it's in the map.
+    ///
+    /// `operand_to_bv()` substitutes these constants, so that conditions on
+    /// these values fold to constants without involving the solver.
+    /// Like the `varmap`, this is restored on backtracking.
+    known_constants: RefCell<HashMap<i32, (B::BV, u64)>>,
+    /// With a `Config.backtracking_order` other than `DepthFirst`: once the
+    /// first backtracking point has been saved, the number of
+    /// `path_constraints` asserted before it. Those are shared by every path,
+    /// and stay at the bottom level of the solver's assertion stack, while the
+    /// rest of each path's constraints are asserted in a single level above.
+    base_constraints_len: Cell<Option<usize>>,
+    /// Whether we're using `Checkpointing::AssumptionLiterals`: that is, it's
+    /// the `Config.checkpointing`, and there are no function hooks or
+    /// callbacks but `haybale`'s own
+    assumption_literals: bool,
+    /// With `Checkpointing::AssumptionLiterals`, the literals guarding the
+    /// constraints on the current path
+    guards: RefCell<PathGuards<B::BV>>,
+    /// Number of levels pushed onto the solver's assertion stack through this
+    /// `State` which haven't been popped yet
+    solver_depth: Cell<usize>,
+    /// State of the random number generator for `BacktrackingOrder::Random`.
+    /// Persists across backtracking.
+    rng_state: Cell<u64>,
+    /// Byte order of multi-byte values in memory; see `Config.byte_order`
+    byte_order: ByteOrder,
+    /// Results of `icmp eq` and `icmp ne` instructions comparing a `BV` with a
+    /// constant, keyed by the id of the result (and of its negation).
+    /// When one of these conditions is asserted, we learn a known constant.
+    /// As with `known_constants`, we hold on to the keyed `BV` itself.
+    ///
+    /// These are facts about the `BV`s themselves, not about the current
+    /// path, so they persist across backtracking.
+    equality_conditions: HashMap<i32, (B::BV, EqualityCondition<B::BV>)>,
+    /// Statistics about the solver queries made through this `State`'s
+    /// methods. Persists across backtracking.
+    solver_stats: RefCell<SolverStats>,
+    /// If `Config.cache_solver_results` is set, the result of the most recent
+    /// check of the current constraints, unless they've changed since
+    cached_check: Cell<Option<SolverResult>>,
+    /// If `Config.cache_solver_results` is set, results of checks of the
+    /// current constraints plus extra constraints, keyed by the sorted ids of
+    /// the extra constraints. As with `known_constants`, we hold on to the
+    /// extra constraints themselves. Cleared along with `cached_check`.
+    cached_checks_with_extra: RefCell<CachedChecks<B::BV>>,
+    /// Number of fresh values created for `undef`s so far, for naming them.
+    /// Persists across backtracking.
+    undefs: Cell<usize>,
+    /// Number of values bound to LLVM names (instruction results, parameters,
+    /// and the like) through this `State`. Persists across backtracking.
+    values_computed: usize,
+    /// The operations which produced `BV`s, keyed by the id of the result,
+    /// for matching by the `Config.rewrite_rules`; only recorded while there
+    /// are any. As with `equality_conditions`, we hold on to the keyed `BV`
+    /// itself, and these persist across backtracking.
+    exprs: HashMap<i32, (B::BV, Expr<B::BV>)>,
+    /// Number of times each of the `Config.rewrite_rules` has applied, by
+    /// name. Persists across backtracking.
+    rewrite_counts: HashMap<String, usize>,
+    /// Global variables whose initializers we couldn't evaluate, in the order
+    /// they were first referenced. Persists across backtracking, as does the
+    /// initialization itself.
+    global_init_fallbacks: RefCell<Vec<InitializerFallback>>,
+    /// Bytes of stack currently in use on this path: the `alloca`s of all
+    /// active frames, plus `Config.stack_frame_overhead` for each frame.
+    /// Dynamically-sized `alloca`s are counted in `dynamic_stack_usage`
+    /// instead.
+    stack_usage: u64,
+    /// The largest `stack_usage` at any point on this path so far
+    max_stack_usage: u64,
+    /// Total bytes of the dynamically-sized `alloca`s of all active frames,
+    /// as a 64-bit `BV`, or `None` if there aren't any
+    dynamic_stack_usage: Option<B::BV>,
+    /// The largest `stack_usage` plus `dynamic_stack_usage` at any point on
+    /// this path so far, as a 64-bit `BV`, or `None` if there haven't been
+    /// any dynamically-sized `alloca`s on this path
+    max_dynamic_stack_usage: Option<B::BV>,
+    /// Conditions imposed on the current path so far, if
+    /// `Config.diagnose_unsat_paths` is set (otherwise empty)
+    path_conditions: Vec<PathCondition>,
+    /// Constraints asserted via `assert_constraint()` on the current path so
+    /// far, each with the number of backtracking points pending when it was
+    /// asserted
+    path_constraints: Vec<(usize, B::BV)>,
+    /// If a constraint which is always `false` has been asserted on the
+    /// current path (see `Config.simplify_constraints`), the number of
+    /// backtracking points pending when it was asserted. While this is set,
+    /// the current constraints are known to be unsatisfiable.
+    known_unsat_depth: Option<usize>,
+    /// Diagnoses of infeasible conditions, if `Config.diagnose_unsat_paths` is
+    /// set. Persists across backtracking.
+    unsat_diagnoses: Vec<UnsatDiagnosis>,
+    /// `Tag`s attached to `BV`s, keyed by the id of the `BV`. As with
+    /// `equality_conditions`, we hold on to the keyed `BV` itself, and these
+    /// persist across backtracking.
+    value_tags: HashMap<i32, (B::BV, Tag)>,
+    /// Notes about things on the current path which were summarized rather
+    /// than executed (for instance, by stub hooks)
+    path_notes: Vec<String>,
+    /// Handles opened on the current path so far, open or closed
+    handles: Vec<Handle<B::BV>>,
+    /// Allocations which are no longer live on the current path, because they
+    /// were `free()`d or are stack slots which went out of scope, by start
+    /// address, with a description of where that happened
+    freed: HashMap<u64, String>,
+    /// Heap allocations made on the current path, by start address, along
+    /// with the pointer returned to the program for each (which may be `NULL`
+    /// if the allocation can fail)
+    heap_allocations: Vec<(u64, B::BV)>,
+    /// With `Config.max_loop_iterations`, the number of iterations on the
+    /// current path of each loop which is currently running, by callstack
+    /// depth, function, and loop header
+    loop_iterations: HashMap<(usize, FuncKey<'p>, &'p Name), usize>,
+    /// Back-edges of the control-flow graph of each function entered so far;
+    /// see `analyze::back_edges()`. Persists across backtracking.
+    back_edges: HashMap<FuncKey<'p>, HashSet<(&'p Name, &'p Name)>>,
+    /// Stack slots (`alloca`s) allocated by the active frames on the current
+    /// path, as pairs of callstack depth and address
+    stack_allocations: Vec<(usize, u64)>,
+    /// Addresses of all the stack slots allocated so far, on any path
+    stack_slots: HashSet<u64>,
+    /// The latest reading of each `Clock` on the current path
+    clocks: HashMap<Clock, B::BV>,
+    /// If the current path is still executing the global constructors (see
+    /// `Config.run_global_ctors`), the index of the current one in
+    /// `Project::global_ctors()`, and the number of instructions it has
+    /// executed so far on this path
+    global_ctor: Option<(usize, u64)>,
+    /// Number of times we've reverted to a backtracking point. Persists across
+    /// backtracking.
+    reverts: usize,
+    /// Number of backtracking points which were pending when the current path
+    /// began. Constraints asserted below that depth are shared with earlier
+    /// paths.
+    shared_depth: usize,
+    /// `Config.invalid_address_ranges`, minus the `Config.mapped_address_ranges`
+    invalid_address_ranges: Vec<Range<u64>>,
+    /// Is address `0` in one of the `Config.mapped_address_ranges`
+    null_is_mapped: bool,
+    /// Allocations at or above this address were made during execution, as
+    /// opposed to those for global variables and functions
+    dynamic_allocations_start: u64,
+    /// Allocations below this address are for global variables; those between
+    /// it and `dynamic_allocations_start` are for functions and function hooks
+    global_vars_end: u64,
+    /// Ranges of addresses from which the allocations made during execution on
+    /// the current path were taken, in order. The last range is open-ended.
+    /// Allocations in between the ranges were made on paths which were
+    /// abandoned by backtracking.
+    path_allocation_ranges: Vec<Range<u64>>,
+}
+
+/// Remove the `holes` from the `range`, which may split it into several ranges
+fn subtract_ranges(range: Range<u64>, holes: &[Range<u64>]) -> Vec<Range<u64>> {
+    let mut ranges = vec![range];
+    for hole in holes {
+        ranges = ranges.into_iter()
+            .flat_map(|range| vec![range.start .. std::cmp::min(range.end, hole.start), std::cmp::max(range.start, hole.end) .. range.end])
+            .filter(|range| !range.is_empty())
+            .collect();
+    }
+    ranges
+}
+
+/// A global variable whose initializer we couldn't evaluate, so whose initial
+/// contents we left unconstrained instead; see
+/// [`State::global_init_fallbacks()`](struct.State.html#method.global_init_fallbacks).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct InitializerFallback {
+    /// The global variable
+    pub global: Name,
+    /// Why its initializer couldn't be evaluated
+    pub reason: String,
+}
+
+/// A condition of the form `bv == value` (if `is_eq`) or `bv != value` (if not)
+#[derive(Clone)]
+struct EqualityCondition<V: BV> {
+    bv: V,
+    value: u64,
+    is_eq: bool,
 }
 
 /// Describes a location in LLVM IR in a format more suitable for printing - for
@@ -115,10 +327,56 @@ impl fmt::Display for BBInstrIndex {
     }
 }
 
+/// Where a variable in the `State` came from; see
+/// [`State::describe_var()`](struct.State.html#method.describe_var)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct VarOrigin<'p> {
+    /// Name of the function the variable belongs to
+    pub funcname: String,
+    /// The LLVM value the variable holds
+    pub name: Name,
+    /// The instruction which produces the value (or the terminator, for an
+    /// `invoke`), or `None` for function parameters and other values without
+    /// a defining instruction
+    pub loc: Option<LocationDescription<'p>>,
+}
+
+impl<'p> fmt::Display for VarOrigin<'p> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.name {
+            Name::Name(name) => write!(f, "%{}", name)?,
+            Name::Number(n) => write!(f, "%{}", n)?,
+        }
+        match &self.loc {
+            Some(loc) => write!(f, ", defined at {}", loc.to_string_no_module()),
+            None => write!(f, " in {}", self.funcname),
+        }
+    }
+}
+
 fn pretty_source_loc(source_loc: &DebugLoc) -> String {
     source_loc.to_string()
 }
 
+/// Forgets the `State`'s cached solver results when dropped, including on
+/// unwind
+struct ForgetCachedSolverResults<'s, 'p, B: Backend>(&'s State<'p, B>);
+
+impl<'s, 'p, B: Backend> Drop for ForgetCachedSolverResults<'s, 'p, B> {
+    fn drop(&mut self) {
+        self.0.forget_cached_solver_results();
+    }
+}
+
+/// What a satisfiability check returning `result` found, for the `SolverStats`
+fn sat_result(result: &Result<bool>) -> SolverResult {
+    match result {
+        Ok(true) => SolverResult::Sat,
+        Ok(false) => SolverResult::Unsat,
+        Err(_) => SolverResult::Unknown,
+    }
+}
+
 impl<'p> fmt::Debug for LocationDescription<'p> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.to_string_with_module())  // default to with-module, especially for a Debug representation
@@ -299,19 +557,76 @@ struct StackFrame<'p, V: BV> {
     /// This is necessary in the case of (direct or indirect) recursion.
     /// See notes on `VarMap.get_restore_info_for_fn()`.
     restore_info: RestoreInfo<V>,
+    /// Caller's `stack_usage` and `dynamic_stack_usage`, to be restored when
+    /// we return to the caller
+    stack_usage: u64,
+    dynamic_stack_usage: Option<V>,
+}
+
+/// A function in the `Project`, by the names of its module and of the function
+/// itself. (Function names alone may collide, e.g. `static` functions in
+/// different modules.)
+type FuncKey<'p> = (&'p str, &'p str);
+
+/// Cached results of checks with extra constraints, keyed by the sorted ids of
+/// the extra constraints; see `State.cached_checks_with_extra`
+type CachedChecks<V> = HashMap<Vec<i32>, (Vec<V>, SolverResult)>;
+
+/// With `Checkpointing::AssumptionLiterals`, the literals guarding the
+/// constraints on the current path; see `State.guards`
+#[derive(Clone)]
+struct PathGuards<V> {
+    /// The literal guarding the constraints asserted on the current path since
+    /// the last backtracking point was saved or resumed, or `None` before the
+    /// first backtracking point is saved (until then, constraints are shared
+    /// by every path, and are asserted unguarded)
+    current: Option<V>,
+    /// Whether `current` has been asserted on the current path's level yet.
+    /// Until it is, the literal it implies still is.
+    active: bool,
+    /// The guarded constraints, and the implications between literals, made
+    /// since the current path's level was pushed. That level is popped on
+    /// backtracking, so those guarded by literals before `current` (the first
+    /// `shared` of these) are then reasserted on the bottom level, as
+    /// backtracking points saved under those literals may still be resumed.
+    /// The rest are only needed by the abandoned path.
+    pending: Vec<V>,
+    shared: usize,
+    /// Depth of the solver's assertion stack with the current path's level on
+    /// top. Constraints asserted with temporary levels above it are asserted
+    /// unguarded, and go away with those levels.
+    level: usize,
+    /// Number of literals created so far, for naming them
+    created: usize,
+}
+
+impl<V> Default for PathGuards<V> {
+    fn default() -> Self {
+        Self {
+            current: None,
+            active: false,
+            pending: Vec::new(),
+            shared: 0,
+            level: 0,
+            created: 0,
+        }
+    }
 }
 
 #[derive(Clone)]
 struct BacktrackPoint<'p, B: Backend> {
-    /// Where to resume execution
-    loc: Location<'p>,
+    /// Alternatives not yet explored: where to resume execution, and the
+    /// constraint to add before doing so. (Intended use of the constraint is
+    /// to constrain the branch in that direction.) These are explored last
+    /// first; the `BacktrackPoint` is discarded once they're all explored.
+    /// With `Checkpointing::SolverFrames`, all of them share the
+    /// `BacktrackPoint`'s single solver frame, so e.g. a `switch` costs one
+    /// level of the solver's assertion stack, not one per case.
+    alternatives: Vec<(Location<'p>, B::BV)>,
     /// Call stack at the `BacktrackPoint`.
     /// This is a vector of `StackFrame`s where the first entry is the top-level
     /// caller, and the last entry is the caller of the `BacktrackPoint`'s function.
     stack: Vec<StackFrame<'p, B::BV>>,
-    /// Constraint to add before restarting execution at `next_bb`.
-    /// (Intended use of this is to constrain the branch in that direction.)
-    constraint: B::BV,
     /// `VarMap` representing the state of things at the `BacktrackPoint`.
     /// For now, we require making a full copy of the `VarMap` in order to revert
     /// later.
@@ -321,15 +636,109 @@ struct BacktrackPoint<'p, B: Backend> {
     /// pointer), so it's not a huge concern that we need a full copy here in
     /// order to revert later.
     mem: B::Memory,
+    /// `shadow` at the `BacktrackPoint`
+    shadow: Option<B::Memory>,
     /// The length of `path` at the `BacktrackPoint`.
     /// If we ever revert to this `BacktrackPoint`, we will truncate the `path` to
     /// its first `path_len` entries.
     path_len: usize,
+    /// Known constants at the `BacktrackPoint`; see notes on
+    /// `State.known_constants`.
+    known_constants: HashMap<i32, (B::BV, u64)>,
+    /// `stack_usage`, `max_stack_usage`, `dynamic_stack_usage`, and
+    /// `max_dynamic_stack_usage` at the `BacktrackPoint`
+    stack_usage: u64,
+    max_stack_usage: u64,
+    dynamic_stack_usage: Option<B::BV>,
+    max_dynamic_stack_usage: Option<B::BV>,
+    /// `path_conditions` at the `BacktrackPoint`
+    path_conditions: Vec<PathCondition>,
+    /// `path_notes` at the `BacktrackPoint`
+    path_notes: Vec<String>,
+    /// `handles` at the `BacktrackPoint`
+    handles: Vec<Handle<B::BV>>,
+    /// `freed`, `heap_allocations`, and `stack_allocations` at the `BacktrackPoint`
+    freed: HashMap<u64, String>,
+    heap_allocations: Vec<(u64, B::BV)>,
+    stack_allocations: Vec<(usize, u64)>,
+    /// `path_allocation_ranges` at the `BacktrackPoint`, with the last range
+    /// ending at the allocator's next address at that time
+    path_allocation_ranges: Vec<Range<u64>>,
+    /// `loop_iterations` at the `BacktrackPoint`
+    loop_iterations: HashMap<(usize, FuncKey<'p>, &'p Name), usize>,
+    /// For a `BacktrackPoint` saved by a branch, the block which is branching;
+    /// each alternative is an edge from it, counted for
+    /// `Config.max_loop_iterations` when the alternative is resumed
+    branch_from: Option<&'p Name>,
+    /// `clocks` at the `BacktrackPoint`
+    clocks: HashMap<Clock, B::BV>,
+    /// `global_ctor` at the `BacktrackPoint`
+    global_ctor: Option<(usize, u64)>,
+    /// With `Checkpointing::AssumptionLiterals`, the literal guarding the
+    /// constraints on the path up to the `BacktrackPoint` (`None` if they're
+    /// all shared by every path)
+    guard: Option<B::BV>,
+    /// With a `Config.backtracking_order` other than `DepthFirst`, the rest
+    /// of what's needed to resume the `BacktrackPoint` (otherwise `None`)
+    snapshot: Option<PathSnapshot<'p, B::BV>>,
+}
+
+/// What a `BacktrackPoint` needs in order to be resumed out of order, when the
+/// current path doesn't necessarily extend the one it was saved on
+#[derive(Clone)]
+struct PathSnapshot<'p, V> {
+    /// `path` at the `BacktrackPoint`
+    path: Vec<PathEntry<'p>>,
+    /// `path_constraints` at the `BacktrackPoint`, without their depths
+    constraints: Vec<V>,
 }
 
 impl<'p, B: Backend> fmt::Display for BacktrackPoint<'p, B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<BacktrackPoint to execute bb {} with constraint {:?} and {} frames on the callstack>", self.loc.bb.name, self.constraint, self.stack.len())
+        match self.alternatives.last() {
+            Some((loc, constraint)) => write!(f, "<BacktrackPoint to execute bb {} with constraint {:?} (and {} more alternatives) and {} frames on the callstack>", loc.bb.name, constraint, self.alternatives.len() - 1, self.stack.len()),
+            None => write!(f, "<BacktrackPoint with no alternatives left and {} frames on the callstack>", self.stack.len()),
+        }
+    }
+}
+
+/// Summarizes the `State`: the current location, the number of variables in
+/// the `VarMap`, the number of constraints on the current path, and each
+/// pending backtracking point. See the `Debug` impl for the constraints
+/// themselves.
+impl<'p, B: Backend> fmt::Display for State<'p, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let backtrack_points = self.backtrack_points.borrow();
+        write!(f, "<State at {:?}: {} variables, {} path constraints, {} backtracking points",
+            self.cur_loc,
+            self.varmap.num_vars(),
+            self.path_constraints.len(),
+            backtrack_points.len(),
+        )?;
+        for bp in backtrack_points.iter() {
+            write!(f, "\n  {}", bp)?;
+        }
+        write!(f, ">")
+    }
+}
+
+/// Like the `Display` impl, but the alternate form (`{:#?}`) also lists each
+/// constraint on the current path, rather than just counting them.
+impl<'p, B: Backend> fmt::Debug for State<'p, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let backtrack_points: Vec<String> = self.backtrack_points.borrow().iter().map(|bp| bp.to_string()).collect();
+        let alternate = f.alternate();
+        let mut dbg = f.debug_struct("State");
+        dbg.field("cur_loc", &self.cur_loc)
+            .field("variables", &self.varmap.num_vars())
+            .field("backtrack_points", &backtrack_points);
+        if alternate {
+            let constraints: Vec<&B::BV> = self.path_constraints.iter().map(|(_, c)| c).collect();
+            dbg.field("path_constraints", &constraints);
+        } else {
+            dbg.field("path_constraints", &self.path_constraints.len());
+        }
+        dbg.finish()
     }
 }
 
@@ -344,23 +753,28 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     ) -> Self {
         let solver = B::SolverRef::new();
         solver.set_opt(BtorOption::SolverTimeout(config.solver_query_timeout));
+        solver.set_opt(BtorOption::Seed(config.random_seed));
         if config.demangling.is_none() {
             config.demangling = Some(Demangling::autodetect(project));
         }
         let mut state = Self {
             cur_loc: start_loc.clone(),
             varmap: VarMap::new(solver.clone(), config.loop_bound),
-            mem: RefCell::new(Memory::new_uninitialized(
-                solver.clone(),
-                match config.null_pointer_checking {
-                    NullPointerChecking::Simple => true,
-                    NullPointerChecking::SplitPath => true,
-                    NullPointerChecking::None => false,
-                },
-                None)
-            ),
-            alloc: Alloc::new(),
+            // we check addresses against `invalid_address_ranges` ourselves,
+            // in `check_address()`, rather than having the `Memory` check for `NULL`
+            mem: RefCell::new(Memory::new_uninitialized(solver.clone(), false, None)),
+            shadow: if config.detect_uninitialized_reads {
+                Some(RefCell::new(Memory::new_zero_initialized(solver.clone(), false, Some("shadow"))))
+            } else {
+                None
+            },
+            alloc: Alloc::new(config.invalid_address_ranges.iter().chain(config.mapped_address_ranges.iter()).cloned().collect()),
+            invalid_address_ranges: config.invalid_address_ranges.iter()
+                .flat_map(|range| subtract_ranges(range.clone(), &config.mapped_address_ranges))
+                .collect(),
+            null_is_mapped: config.mapped_address_ranges.iter().any(|range| range.contains(&0)),
             global_allocations: GlobalAllocations::new(),
+            project,
             intrinsic_hooks: {
                 let mut intrinsic_hooks = FunctionHooks::new();
                 // we use "function names" that are clearly illegal, as an additional precaution to avoid collisions with actual function names
@@ -369,6 +783,8 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
                 intrinsic_hooks.add("intrinsic: llvm.bswap", &hooks::intrinsics::symex_bswap);
                 intrinsic_hooks.add("intrinsic: llvm.objectsize", &hooks::intrinsics::symex_objectsize);
                 intrinsic_hooks.add("intrinsic: llvm.assume", &hooks::intrinsics::symex_assume);
+                intrinsic_hooks.add("intrinsic: llvm.lifetime.start", &hooks::intrinsics::symex_lifetime_start);
+                intrinsic_hooks.add("intrinsic: llvm.lifetime.end", &hooks::intrinsics::symex_lifetime_end);
                 intrinsic_hooks.add("intrinsic: llvm.uadd.with.overflow", &hooks::intrinsics::symex_uadd_with_overflow);
                 intrinsic_hooks.add("intrinsic: llvm.sadd.with.overflow", &hooks::intrinsics::symex_sadd_with_overflow);
                 intrinsic_hooks.add("intrinsic: llvm.usub.with.overflow", &hooks::intrinsics::symex_usub_with_overflow);
@@ -379,6 +795,10 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
                 intrinsic_hooks.add("intrinsic: llvm.sadd.sat", &hooks::intrinsics::symex_sadd_sat);
                 intrinsic_hooks.add("intrinsic: llvm.usub.sat", &hooks::intrinsics::symex_usub_sat);
                 intrinsic_hooks.add("intrinsic: llvm.ssub.sat", &hooks::intrinsics::symex_ssub_sat);
+                #[cfg(feature = "fp")]
+                intrinsic_hooks.add("intrinsic: llvm.fma", &hooks::intrinsics::symex_fma);
+                #[cfg(feature = "fp")]
+                intrinsic_hooks.add("intrinsic: llvm.fmuladd", &hooks::intrinsics::symex_fmuladd);
                 intrinsic_hooks.add("intrinsic: generic_stub_hook", &function_hooks::generic_stub_hook);
                 intrinsic_hooks.add("intrinsic: abort_hook", &function_hooks::abort_hook);
                 intrinsic_hooks
@@ -387,7 +807,51 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
             backtrack_points: RefCell::new(Vec::new()),
             path: Vec::new(),
             mem_watchpoints: config.initial_mem_watchpoints.clone().into_iter().collect(),
+            watchpoint_hits: RefCell::new(Vec::new()),
+            path_index: 0,
             function_ptr_cache: HashMap::new(),
+            known_constants: RefCell::new(HashMap::new()),
+            base_constraints_len: Cell::new(None),
+            assumption_literals: config.checkpointing == Checkpointing::AssumptionLiterals
+                && !config.function_hooks.has_custom_hooks()
+                && config.callbacks.is_empty(),
+            guards: RefCell::new(PathGuards::default()),
+            solver_depth: Cell::new(0),
+            rng_state: Cell::new(u64::from(config.random_seed)),
+            byte_order: config.byte_order.or_else(|| project.byte_order()).unwrap_or(ByteOrder::LittleEndian),
+            equality_conditions: HashMap::new(),
+            solver_stats: RefCell::new(SolverStats::default()),
+            cached_check: Cell::new(None),
+            cached_checks_with_extra: RefCell::new(HashMap::new()),
+            undefs: Cell::new(0),
+            values_computed: 0,
+            exprs: HashMap::new(),
+            rewrite_counts: HashMap::new(),
+            global_init_fallbacks: RefCell::new(Vec::new()),
+            stack_usage: config.stack_frame_overhead,
+            max_stack_usage: config.stack_frame_overhead,
+            dynamic_stack_usage: None,
+            max_dynamic_stack_usage: None,
+            path_conditions: Vec::new(),
+            path_constraints: Vec::new(),
+            known_unsat_depth: None,
+            unsat_diagnoses: Vec::new(),
+            value_tags: HashMap::new(),
+            path_notes: Vec::new(),
+            handles: Vec::new(),
+            freed: HashMap::new(),
+            heap_allocations: Vec::new(),
+            loop_iterations: HashMap::new(),
+            back_edges: HashMap::new(),
+            stack_allocations: Vec::new(),
+            stack_slots: HashSet::new(),
+            clocks: HashMap::new(),
+            global_ctor: None,
+            reverts: 0,
+            shared_depth: 0,
+            dynamic_allocations_start: 0,
+            global_vars_end: 0,
+            path_allocation_ranges: Vec::new(),
 
             // listed last (out-of-order) so that they can be used above but moved in now
             solver,
@@ -432,22 +896,66 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
                 };
                 let addr = state.allocate(size_bits as u64);
                 debug!("Allocated {:?} at {:?}", var.name, addr);
+                let name = match &var.name {
+                    Name::Name(name) => format!("@{}", name),
+                    Name::Number(n) => format!("@{}", n),
+                };
+                state.alloc.label(addr.as_u64().unwrap(), name, Some((**pointee_type).clone()));
                 state.global_allocations.allocate_global_var(var, module, addr);
             } else {
                 panic!("Global variable has non-pointer type {:?}", &var.ty);
             }
         }
+        // Global variables which the `Project` only declares (e.g.,
+        // `__stack_chk_guard` or `errno`, normally defined in the C library)
+        // get their own allocation too, with unconstrained contents. This way
+        // the analyzed code can still read and write them, and e.g.
+        // stack-protector checks fail only when the stack actually gets
+        // overwritten.
+        debug!("Allocating declared-only global variables");
+        for (var, module) in project.all_global_vars().filter(|(var,_)| var.initializer.is_none()) {
+            // the same variable may be declared in several modules; the first
+            // declaration we see allocates it for all of them (unless it is
+            // actually defined somewhere, in which case it's already allocated)
+            if state.global_allocations.get_global_allocation(&var.name, module).is_some() {
+                continue;
+            }
+            if let Type::PointerType { pointee_type, .. } = &var.ty {
+                let size_bits = match size_opaque_aware(pointee_type, project) {
+                    Some(0) => 8,
+                    Some(size_bits) => size_bits as u32,
+                    None => {
+                        warn!("Not allocating declared global {:?}: its type is opaque in the entire Project", var.name);
+                        continue;
+                    },
+                };
+                let name = match &var.name {
+                    Name::Name(name) => format!("@{}", name),
+                    Name::Number(n) => format!("@{}", n),
+                };
+                let addr = state.allocate(size_bits as u64);
+                debug!("Allocated declared {:?} at {:?}", var.name, addr);
+                state.alloc.label(addr.as_u64().unwrap(), name.clone(), Some((**pointee_type).clone()));
+                let value = B::BV::new(state.solver.clone(), size_bits, Some(&name));
+                state.write(&addr, value).expect("Failed to initialize a declared global variable");
+                state.global_allocations.allocate_declared_global_var(var, module, addr);
+            } else {
+                panic!("Global variable has non-pointer type {:?}", &var.ty);
+            }
+        }
         // We also have to allocate (at least a tiny bit of) memory for each
         // `Function`, just so that we can have pointers to those `Function`s.
         // We can use `global_allocations.get_func_for_address()` to interpret
         // these function pointers.
         // Similarly, we allocate tiny bits of memory for each function hook,
         // so that we can have pointers to those hooks.
+        state.global_vars_end = state.alloc.next_address();
         debug!("Allocating functions");
         for (func, module) in project.all_functions() {
             let addr: u64 = state.alloc.alloc(64 as u64);  // we just allocate 64 bits for each function. No reason to allocate more.
             let addr_bv = state.bv_from_u64(addr, 64);
             debug!("Allocated {:?} at {:?}", func.name, addr_bv);
+            state.alloc.label(addr, format!("@{}", func.name), None);
             state.global_allocations.allocate_function(func, module, addr, addr_bv);
         }
         debug!("Allocating function hooks");
@@ -458,19 +966,74 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
             state.global_allocations.allocate_function_hook((*hook).clone(), addr, addr_bv);
         }
         debug!("Done allocating global variables and functions");
+        state.dynamic_allocations_start = state.alloc.next_address();
+        state.path_allocation_ranges = vec![Range { start: state.dynamic_allocations_start, end: u64::MAX }];
         state
     }
 
     /// Fully duplicate the `State`. Unlike with `clone()`, the `State` this
     /// function returns will have a fully separate (fully duplicated) solver
-    /// instance. (With `clone()`, the states will still share references to the
-    /// same solver instance.)
+    /// instance, with all the constraints of this one. (With `clone()`, the
+    /// states will still share references to the same solver instance.)
+    ///
+    /// The fork starts with no backtracking points: it can only explore the
+    /// current path onwards, leaving the pending alternatives to this `State`.
+    /// So, a worklist of forks can be explored in any order, each
+    /// independently of the others.
     pub fn fork(&self) -> Self {
         let mut cloned = self.clone();
         let new_solver = cloned.solver.duplicate();
         cloned.varmap.change_solver(new_solver.clone());
         cloned.mem.borrow_mut().change_solver(new_solver.clone());
+        if let Some(shadow) = &cloned.shadow {
+            shadow.borrow_mut().change_solver(new_solver.clone());
+        }
         cloned.global_allocations.change_solver(new_solver.clone());
+        for frame in cloned.stack.iter_mut() {
+            frame.restore_info.change_solver(new_solver.clone());
+            frame.dynamic_stack_usage = frame.dynamic_stack_usage.as_ref().map(|bv| new_solver.match_bv(bv).unwrap());
+        }
+        cloned.dynamic_stack_usage = cloned.dynamic_stack_usage.as_ref().map(|bv| new_solver.match_bv(bv).unwrap());
+        cloned.max_dynamic_stack_usage = cloned.max_dynamic_stack_usage.as_ref().map(|bv| new_solver.match_bv(bv).unwrap());
+        for reading in cloned.clocks.values_mut() {
+            *reading = new_solver.match_bv(reading).unwrap();
+        }
+        for handle in cloned.handles.iter_mut() {
+            handle.returned = new_solver.match_bv(&handle.returned).unwrap();
+        }
+        for (_, returned) in cloned.heap_allocations.iter_mut() {
+            *returned = new_solver.match_bv(returned).unwrap();
+        }
+        for hit in cloned.watchpoint_hits.get_mut().iter_mut() {
+            hit.value = new_solver.match_bv(&hit.value).unwrap();
+        }
+        cloned.value_tags = cloned.value_tags.values()
+            .map(|(bv, tag)| {
+                let bv = new_solver.match_bv(bv).unwrap();
+                (bv.get_id(), (bv, tag.clone()))
+            })
+            .collect();
+        // the backtracking points hold values from the old solver, and belong
+        // to this `State` anyway
+        cloned.backtrack_points.borrow_mut().clear();
+        cloned.base_constraints_len.set(None);
+        // the fork never backtracks past the current path, so its guarded
+        // constraints can just hold for good
+        let guards = cloned.guards.replace(PathGuards::default());
+        for guard in guards.pending.iter().chain(guards.current.iter()) {
+            new_solver.match_bv(guard).unwrap().assert().unwrap();
+        }
+        cloned.shared_depth = 0;
+        cloned.path_constraints = cloned.path_constraints.iter()
+            .map(|(_, constraint)| (0, new_solver.match_bv(constraint).unwrap()))
+            .collect();
+        cloned.known_unsat_depth = cloned.known_unsat_depth.map(|_| 0);
+        // these maps are keyed by ids of nodes in the old solver, and are only
+        // an optimization anyway, so we just start over
+        cloned.known_constants.borrow_mut().clear();
+        cloned.equality_conditions.clear();
+        cloned.exprs.clear();
+        cloned.forget_cached_solver_results();
         cloned.solver = new_solver;
         cloned
     }
@@ -479,7 +1042,33 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     ///
     /// Returns `Error::SolverError` if the query failed (e.g., was interrupted or timed out).
     pub fn sat(&self) -> Result<bool> {
-        solver_utils::sat(&self.solver)
+        if self.known_unsat() {
+            return Ok(false);
+        }
+        if let Some(result) = self.lookup_cached_check() {
+            return Ok(result == SolverResult::Sat);
+        }
+        let retval = self.record_check(|| solver_utils::sat(&self.solver), sat_result);
+        self.cache_check(sat_result(&retval));
+        retval
+    }
+
+    /// Like `sat()`, but for reading a model afterwards: the solver only has a
+    /// model to read if it was actually asked, so a cached `true` result (see
+    /// [`Config.cache_solver_results`](config/struct.Config.html#structfield.cache_solver_results))
+    /// doesn't count. Callers must enable model generation first, and must
+    /// only read the model if this returns `Ok(true)`.
+    fn sat_for_model(&self) -> Result<bool> {
+        if self.known_unsat() {
+            return Ok(false);
+        }
+        if self.cached_check.get() == Some(SolverResult::Unsat) {
+            self.solver_stats.borrow_mut().cache_hits += 1;
+            return Ok(false);
+        }
+        let retval = self.record_check(|| solver_utils::sat(&self.solver), sat_result);
+        self.cache_check(sat_result(&retval));
+        retval
     }
 
     /// Returns `true` if the current constraints plus the given additional constraints
@@ -489,7 +1078,90 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     ///
     /// Does not permanently add the given constraints to the solver.
     pub fn sat_with_extra_constraints<'b>(&'b self, constraints: impl IntoIterator<Item = &'b B::BV>) -> Result<bool> {
-        solver_utils::sat_with_extra_constraints(&self.solver, constraints)
+        if self.known_unsat() {
+            return Ok(false);
+        }
+        let constraints: Vec<&B::BV> = constraints.into_iter().collect();
+        let key = self.extra_constraints_key(&constraints);
+        if let Some(result) = self.lookup_cached_check_with_extra(&key) {
+            return Ok(result == SolverResult::Sat);
+        }
+        self.count_push_and_pop();
+        let retval = self.record_check(|| solver_utils::sat_with_extra_constraints(&self.solver, constraints.iter().copied()), sat_result);
+        self.cache_check_with_extra(key, &constraints, sat_result(&retval));
+        retval
+    }
+
+    /// Like `sat()`, but returns `SolverResult::Unknown` rather than an error if
+    /// the query was interrupted or timed out (see
+    /// [`Config.solver_query_timeout`](config/struct.Config.html#structfield.solver_query_timeout)),
+    /// so callers can tell "unsatisfiable" apart from "don't know".
+    pub fn check(&self) -> SolverResult {
+        if self.known_unsat() {
+            return SolverResult::Unsat;
+        }
+        if let Some(result) = self.lookup_cached_check() {
+            return result;
+        }
+        let result = self.record_check(|| solver_utils::check(&self.solver), |result| *result);
+        self.cache_check(result);
+        result
+    }
+
+    /// Like `sat_with_extra_constraints()`, but returns `SolverResult::Unknown`
+    /// rather than an error if the query was interrupted or timed out.
+    ///
+    /// Does not permanently add the given constraints to the solver.
+    pub fn check_with_extra_constraints<'b>(&'b self, constraints: impl IntoIterator<Item = &'b B::BV>) -> Result<SolverResult> {
+        if self.known_unsat() {
+            return Ok(SolverResult::Unsat);
+        }
+        let constraints: Vec<&B::BV> = constraints.into_iter().collect();
+        let key = self.extra_constraints_key(&constraints);
+        if let Some(result) = self.lookup_cached_check_with_extra(&key) {
+            return Ok(result);
+        }
+        self.count_push_and_pop();
+        let retval = self.record_check(
+            || solver_utils::check_with_extra_constraints(&self.solver, constraints.iter().copied()),
+            |result| *result.as_ref().unwrap_or(&SolverResult::Unknown),
+        );
+        self.cache_check_with_extra(key, &constraints, *retval.as_ref().unwrap_or(&SolverResult::Unknown));
+        retval
+    }
+
+    /// Run `f` on this `State` with the given constraints temporarily added to
+    /// the current constraints, e.g. to get solutions under those constraints.
+    /// The constraints are removed again when `f` returns, even if it panics.
+    ///
+    /// `f` shouldn't save or revert to backtracking points.
+    ///
+    /// Returns `Err` (without running `f`) if the constraints couldn't be asserted.
+    pub fn with_temporary_constraints<'b, R>(&'b self, constraints: impl IntoIterator<Item = &'b B::BV>, f: impl FnOnce(&Self) -> R) -> Result<R> {
+        self.count_push_and_pop();
+        // results cached under the temporary constraints don't hold once
+        // they're removed
+        self.forget_cached_solver_results();
+        solver_utils::with_extra_constraints(&self.solver, constraints, || {
+            let _forget = ForgetCachedSolverResults(self);
+            f(self)
+        })
+    }
+
+    /// Explain why a set of constraints can't all hold on the current path:
+    /// returns `Ok(None)` if the current constraints plus all of the labelled
+    /// constraints are satisfiable, or otherwise the labels of a minimal
+    /// conflicting subset of the labelled constraints. Labels can be anything
+    /// identifying the constraints, e.g. the instructions which generated them.
+    ///
+    /// Does not permanently add any of the labelled constraints to the solver.
+    /// See [`solver_utils::unsat_core()`](solver_utils/fn.unsat_core.html).
+    pub fn get_unsat_core<L: Clone>(&self, labelled: &[(L, B::BV)]) -> Result<Option<Vec<L>>> {
+        self.record_check(|| solver_utils::unsat_core(&self.solver, labelled), |result| match result {
+            Ok(None) => SolverResult::Sat,
+            Ok(Some(_)) => SolverResult::Unsat,
+            Err(_) => SolverResult::Unknown,
+        })
     }
 
     /// Returns `true` if under the current constraints, `a` and `b` must have the
@@ -506,7 +1178,16 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     /// not require full model generation. You should prefer this function or
     /// `bvs_can_be_equal()` if they are sufficient for your needs.
     pub fn bvs_must_be_equal(&self, a: &B::BV, b: &B::BV) -> Result<bool> {
-        solver_utils::bvs_must_be_equal(&self.solver, a, b)
+        if self.known_unsat() {
+            return Ok(true);
+        }
+        self.count_push_and_pop();
+        // `a` and `b` must be equal if it's unsatisfiable for them to differ
+        self.record_check(|| solver_utils::bvs_must_be_equal(&self.solver, a, b), |result| match result {
+            Ok(true) => SolverResult::Unsat,
+            Ok(false) => SolverResult::Sat,
+            Err(_) => SolverResult::Unknown,
+        })
     }
 
     /// Returns `true` if under the current constraints, `a` and `b` can have the
@@ -523,191 +1204,898 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     /// not require full model generation. You should prefer this function or
     /// `bvs_must_be_equal()` if they are sufficient for your needs.
     pub fn bvs_can_be_equal(&self, a: &B::BV, b: &B::BV) -> Result<bool> {
-        solver_utils::bvs_can_be_equal(&self.solver, a, b)
-    }
-
-    /// Get one possible concrete value for the `BV`.
-    /// Returns `Ok(None)` if no possible solution, or `Error::SolverError` if the solver query failed.
-    pub fn get_a_solution_for_bv(&self, bv: &B::BV) -> Result<Option<BVSolution>> {
-        // first check if the `bv` is a constant, if so, we can avoid a solve
-        match bv.as_binary_str() {
-            Some(bstr) => Ok(Some(BVSolution::from_01x_str(bstr))),
-            None => {
-                warn!("A call to get_a_solution_for_bv() is resulting in a call to sat() with model generation enabled. Experimentally, these types of calls can be very slow. The BV is {:?}", bv);
-                self.solver.set_opt(BtorOption::ModelGen(ModelGen::All));
-                let solution = if self.sat()? {
-                    bv.get_a_solution().map(Some)
-                } else {
-                    Ok(None)
-                };
-                self.solver.set_opt(BtorOption::ModelGen(ModelGen::Disabled));
-                solution
-            },
+        if self.known_unsat() {
+            return Ok(false);
         }
+        self.count_push_and_pop();
+        self.record_check(|| solver_utils::bvs_can_be_equal(&self.solver, a, b), sat_result)
     }
 
-    /// Get one possible concrete value for the given IR `Name` (from the given `Function` name).
-    /// Returns `Ok(None)` if no possible solution, or `Error::SolverError` if the solver query failed.
-    #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
-    pub fn get_a_solution_for_irname(&mut self, funcname: &String, name: &Name) -> Result<Option<BVSolution>> {
-        let bv = self.varmap.lookup_var(funcname, name);
-        self.get_a_solution_for_bv(bv)
+    /// Returns the number of satisfiability checks which have been made through
+    /// `sat()`, `sat_with_extra_constraints()`, `bvs_must_be_equal()`, and
+    /// `bvs_can_be_equal()` on this `State` (including calls made internally
+    /// during symbolic execution). This count is not reset by backtracking.
+    pub fn solver_query_count(&self) -> usize {
+        self.solver_stats.borrow().checks
     }
 
-    /// Get a description of the possible solutions for the `BV`.
-    ///
-    /// `n`: Maximum number of distinct solutions to check for.
-    /// If there are more than `n` possible solutions, this returns a
-    /// `PossibleSolutions::AtLeast` containing `n+1` solutions.
-    ///
-    /// These solutions will be disambiguated - see docs on `boolector::BVSolution`.
-    ///
-    /// If there are no possible solutions, this returns `Ok` with an empty
-    /// `PossibleSolutions`, rather than returning an `Err` with `Error::Unsat`.
-    pub fn get_possible_solutions_for_bv(&self, bv: &B::BV, n: usize) -> Result<PossibleSolutions<BVSolution>> {
-        solver_utils::get_possible_solutions_for_bv(self.solver.clone(), bv, n)
+    /// Returns statistics about the solver queries which have been made through
+    /// this `State`, including the ones counted by `solver_query_count()` and
+    /// the time they took. These are not reset by backtracking.
+    pub fn solver_stats(&self) -> SolverStats {
+        self.solver_stats.borrow().clone()
     }
 
-    /// Get a description of the possible solutions for the given IR `Name` (from the given `Function` name).
-    ///
-    /// `n`: Maximum number of distinct solutions to check for.
-    /// If there are more than `n` possible solutions, this returns a
-    /// `PossibleSolutions::AtLeast` containing `n+1` solutions.
-    ///
-    /// These solutions will be disambiguated - see docs on `boolector::BVSolution`.
-    ///
-    /// If there are no possible solutions, this returns `Ok` with an empty
-    /// `PossibleSolutions`, rather than returning an `Err` with `Error::Unsat`.
-    #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
-    pub fn get_possible_solutions_for_irname(&mut self, funcname: &String, name: &Name, n: usize) -> Result<PossibleSolutions<BVSolution>> {
-        let bv = self.varmap.lookup_var(funcname, name);
-        self.get_possible_solutions_for_bv(bv, n)
+    /// Run the satisfiability check `check`, recording it in the
+    /// `solver_stats`; `outcome` says what the check found, given its result
+    fn record_check<T>(&self, check: impl FnOnce() -> T, outcome: impl FnOnce(&T) -> SolverResult) -> T {
+        let start = Instant::now();
+        let retval = check();
+        self.solver_stats.borrow_mut().record_check(outcome(&retval), start.elapsed());
+        retval
     }
 
-    /// Get the maximum possible solution for the `BV`: that is, the highest value
-    /// for which the current set of constraints is still satisfiable.
-    /// "Maximum" will be interpreted in an unsigned fashion.
-    ///
-    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
-    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
-    /// query itself fails. Panics if the `BV` is wider than 64 bits.
-    pub fn max_possible_solution_for_bv_as_u64(&self, bv: &B::BV) -> Result<Option<u64>> {
-        solver_utils::max_possible_solution_for_bv_as_u64(self.solver.clone(), bv)
+    /// Forget the cached results of satisfiability checks (see
+    /// [`Config.cache_solver_results`](config/struct.Config.html#structfield.cache_solver_results)).
+    /// With that option, this must be called after asserting constraints
+    /// directly with `BV::assert()` rather than through `assert_constraint()`.
+    pub fn forget_cached_solver_results(&self) {
+        self.cached_check.set(None);
+        self.cached_checks_with_extra.borrow_mut().clear();
     }
 
-    /// Get the maximum possible solution for the given IR `Name` (from the given
-    /// `Function` name): that is, the highest value for which the current set of
-    /// constraints is still satisfiable.
-    /// "Maximum" will be interpreted in an unsigned fashion.
-    ///
-    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
-    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
-    /// query itself fails. Panics if the `BV` is wider than 64 bits.
-    #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
-    pub fn max_possible_solution_for_irname_as_u64(&mut self, funcname: &String, name: &Name) -> Result<Option<u64>> {
-        let bv = self.varmap.lookup_var(funcname, name);
-        solver_utils::max_possible_solution_for_bv_as_u64(self.solver.clone(), bv)
+    /// The cached result of checking the current constraints, if any
+    fn lookup_cached_check(&self) -> Option<SolverResult> {
+        let result = self.cached_check.get()?;
+        self.solver_stats.borrow_mut().cache_hits += 1;
+        Some(result)
     }
 
-    /// Get the minimum possible solution for the `BV`: that is, the lowest value
-    /// for which the current set of constraints is still satisfiable.
-    /// "Minimum" will be interpreted in an unsigned fashion.
-    ///
-    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
-    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
-    /// query itself fails. Panics if the `BV` is wider than 64 bits.
-    pub fn min_possible_solution_for_bv_as_u64(&self, bv: &B::BV) -> Result<Option<u64>> {
-        solver_utils::min_possible_solution_for_bv_as_u64(self.solver.clone(), bv)
+    /// Cache the result of checking the current constraints, if
+    /// `Config.cache_solver_results` is set
+    fn cache_check(&self, result: SolverResult) {
+        if self.config.cache_solver_results && result != SolverResult::Unknown {
+            self.cached_check.set(Some(result));
+        }
     }
 
-    /// Get the minimum possible solution for the given IR `Name` (from the given
-    /// `Function` name): that is, the lowest value for which the current set of
-    /// constraints is still satisfiable.
-    /// "Minimum" will be interpreted in an unsigned fashion.
-    ///
-    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
-    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
-    /// query itself fails. Panics if the `BV` is wider than 64 bits.
-    #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
-    pub fn min_possible_solution_for_irname_as_u64(&self, funcname: &String, name: &Name) -> Result<Option<u64>> {
-        let bv = self.varmap.lookup_var(funcname, name);
-        solver_utils::min_possible_solution_for_bv_as_u64(self.solver.clone(), bv)
+    /// The key for caching the result of a check with the given extra
+    /// constraints, or `None` if `Config.cache_solver_results` isn't set
+    fn extra_constraints_key(&self, constraints: &[&B::BV]) -> Option<Vec<i32>> {
+        if !self.config.cache_solver_results {
+            return None;
+        }
+        let mut ids: Vec<i32> = constraints.iter().map(|c| c.get_id()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        Some(ids)
     }
 
-    /// Create a `BV` constant representing the given `bool` (either constant
-    /// `true` or constant `false`).
-    /// The resulting `BV` will be either constant `0` or constant `1`, and will
-    /// have bitwidth `1`.
-    pub fn bv_from_bool(&self, b: bool) -> B::BV {
-        B::BV::from_bool(self.solver.clone(), b)
+    /// The cached result of a check with the extra constraints identified by
+    /// `key`, if any
+    fn lookup_cached_check_with_extra(&self, key: &Option<Vec<i32>>) -> Option<SolverResult> {
+        let result = self.cached_checks_with_extra.borrow().get(key.as_ref()?).map(|(_, result)| *result)?;
+        self.solver_stats.borrow_mut().cache_hits += 1;
+        Some(result)
     }
 
-    /// Create a `BV` representing the given constant `i32` value, with the given
-    /// bitwidth.
-    pub fn bv_from_i32(&self, i: i32, width: u32) -> B::BV {
-        B::BV::from_i32(self.solver.clone(), i, width)
+    /// Cache the result of a check with the given extra constraints, if
+    /// `key` is `Some`
+    fn cache_check_with_extra(&self, key: Option<Vec<i32>>, constraints: &[&B::BV], result: SolverResult) {
+        if let Some(key) = key {
+            if result != SolverResult::Unknown {
+                let constraints = constraints.iter().map(|&c| c.clone()).collect();
+                self.cached_checks_with_extra.borrow_mut().insert(key, (constraints, result));
+            }
+        }
     }
 
-    /// Create a `BV` representing the given constant `u32` value, with the given
-    /// bitwidth.
-    pub fn bv_from_u32(&self, u: u32, width: u32) -> B::BV {
-        B::BV::from_u32(self.solver.clone(), u, width)
+    /// Whether the current constraints are known to be unsatisfiable without
+    /// asking the solver; see `Config.simplify_constraints`
+    fn known_unsat(&self) -> bool {
+        self.known_unsat_depth.is_some()
     }
 
-    /// Create a `BV` representing the given constant `i64` value, with the given
-    /// bitwidth.
-    pub fn bv_from_i64(&self, i: i64, width: u32) -> B::BV {
-        B::BV::from_i64(self.solver.clone(), i, width)
+    /// Record a temporary level on the solver's assertion stack, pushed and
+    /// then popped within a single query
+    fn count_push_and_pop(&self) {
+        let mut stats = self.solver_stats.borrow_mut();
+        stats.pushes += 1;
+        stats.pops += 1;
+        stats.peak_assertion_depth = std::cmp::max(stats.peak_assertion_depth, self.solver_depth.get() + 1);
     }
 
-    /// Create a `BV` representing the given constant `u64` value, with the given
-    /// bitwidth.
-    pub fn bv_from_u64(&self, u: u64, width: u32) -> B::BV {
-        B::BV::from_u64(self.solver.clone(), u, width)
+    /// Push a level onto the solver's assertion stack
+    pub(crate) fn push_solver_frame(&self) {
+        self.forget_cached_solver_results();
+        self.solver.push(1);
+        self.solver_depth.set(self.solver_depth.get() + 1);
+        let mut stats = self.solver_stats.borrow_mut();
+        stats.pushes += 1;
+        stats.peak_assertion_depth = std::cmp::max(stats.peak_assertion_depth, self.solver_depth.get());
     }
 
-    /// Create a `BV` representing the constant `0` of the given bitwidth.
-    /// This is equivalent to `self.bv_from_i32(0, width)` but may be more
-    /// efficient.
-    pub fn zero(&self, width: u32) -> B::BV {
-        B::BV::zero(self.solver.clone(), width)
+    /// Pop a level off the solver's assertion stack
+    pub(crate) fn pop_solver_frame(&self) {
+        self.forget_cached_solver_results();
+        self.solver.pop(1);
+        self.solver_depth.set(self.solver_depth.get() - 1);
+        self.solver_stats.borrow_mut().pops += 1;
     }
 
-    /// Create a `BV` representing the constant `1` of the given bitwidth.
-    /// This is equivalent to `self.bv_from_i32(1, width)` but may be more
-    /// efficient.
-    pub fn one(&self, width: u32) -> B::BV {
-        B::BV::one(self.solver.clone(), width)
+    /// Returns the number of values which have been bound to LLVM names through
+    /// `new_bv_with_name()` and `assign_bv_to_name()` on this `State`, i.e.,
+    /// roughly the number of instruction results computed. This count is not
+    /// reset by backtracking.
+    pub fn values_computed(&self) -> usize {
+        self.values_computed
     }
 
-    /// Create a `BV` constant of the given width, where all bits are set to one.
-    /// This is equivalent to `self.bv_from_i32(-1, width)` but may be more
-    /// efficient.
-    pub fn ones(&self, width: u32) -> B::BV {
-        B::BV::ones(self.solver.clone(), width)
+    /// If the given `BV` is known to have a particular constant value on the
+    /// current path (because an equality between it and that constant was
+    /// asserted earlier on the path), returns that value.
+    ///
+    /// This is a purely syntactic lookup and never invokes the solver; `None`
+    /// doesn't mean the `BV` can have multiple values, only that we haven't
+    /// learned a constant for it.
+    pub fn known_constant(&self, bv: &B::BV) -> Option<u64> {
+        match bv.as_u64() {
+            Some(value) => Some(value),
+            None => self.known_constants.borrow().get(&bv.get_id()).map(|(_, value)| *value),
+        }
     }
 
-    /// Create a new (unconstrained) `BV` for the given `Name` (in the current function).
-    ///
-    /// This function performs uniquing, so if you call it twice
-    /// with the same `Name`-`Function` pair, you will get two different `BV`s.
-    ///
-    /// Returns the new `BV`, or `Err` if it can't be created.
-    ///
-    /// (As of this writing, the only `Err` that might be returned is
-    /// `Error::LoopBoundExceeded`, which is returned if creating the new `BV`
-    /// would exceed `max_versions_of_name` -- see
-    /// [`Config`](struct.Config.html).)
+    /// Record that `bv` is known to equal `value` on the current path.
+    /// The caller is responsible for having actually asserted this equality.
     ///
-    /// Also, we assume that no two `Function`s share the same name.
-    pub fn new_bv_with_name(&mut self, name: Name, bits: u32) -> Result<B::BV> {
-        self.varmap.new_bv_with_name(self.cur_loc.func.name.clone(), name, bits)
+    /// `BV`s wider than 64 bits are ignored.
+    pub fn note_known_constant(&self, bv: &B::BV, value: u64) {
+        if bv.is_const() || bv.get_width() > 64 {
+            return;
+        }
+        debug_assert!(
+            solver_utils::bvs_must_be_equal(&self.solver, bv, &self.bv_from_u64(value, bv.get_width())).unwrap_or(true),
+            "noted {:?} as known to be {}, but the solver disagrees", bv, value,
+        );
+        debug!("Noting that {:?} is known to be {} on this path", bv, value);
+        self.known_constants.borrow_mut().insert(bv.get_id(), (bv.clone(), value));
     }
 
-    /// Assign the given `BV` to the given `Name` (in the current function).
+    /// Record that the 1-bit condition `cond` is known to be `value` on the
+    /// current path. The caller is responsible for having actually asserted
+    /// this.
     ///
-    /// This function performs uniquing, so it creates a new version of the
-    /// variable represented by the `(String, Name)` pair rather than overwriting
+    /// If `cond` was produced by an `icmp eq` or `icmp ne` against a constant,
+    /// this may also teach us the constant value of the compared `BV`.
+    pub fn note_condition(&self, cond: &B::BV, value: bool) {
+        if cond.is_const() {
+            return;
+        }
+        self.note_known_constant(cond, if value { 1 } else { 0 });
+        if let Some((_, ec)) = self.equality_conditions.get(&cond.get_id()) {
+            if ec.is_eq == value {
+                self.note_known_constant(&ec.bv, ec.value);
+            }
+        }
+    }
+
+    /// If `Config.diagnose_unsat_paths` is set, record that the `i1` operand
+    /// `cond` was required to have the given `value` at the current location
+    /// (by the given `kind` of instruction, e.g. `"branch"`), for use in later
+    /// diagnoses
+    pub(crate) fn log_path_condition(&mut self, kind: &'static str, cond: &Operand, value: bool) {
+        if self.config.diagnose_unsat_paths {
+            self.path_conditions.push(PathCondition::new(kind, &self.cur_loc, cond, value));
+        }
+    }
+
+    /// Undo the most recent `log_path_condition()`
+    pub(crate) fn unlog_path_condition(&mut self) {
+        self.path_conditions.pop();
+    }
+
+    /// If `Config.diagnose_unsat_paths` is set, record an `UnsatDiagnosis`
+    /// explaining why the `i1` operand `cond` can't have the given `value` at
+    /// the current location
+    pub(crate) fn diagnose_infeasible_condition(&mut self, kind: &'static str, cond: &Operand, value: bool) {
+        if self.config.diagnose_unsat_paths && self.unsat_diagnoses.len() < MAX_UNSAT_DIAGNOSES {
+            let infeasible = PathCondition::new(kind, &self.cur_loc, cond, value);
+            let diagnosis = UnsatDiagnosis::new(infeasible, self.path_conditions.clone());
+            info!("Infeasible condition: {}", diagnosis);
+            self.unsat_diagnoses.push(diagnosis);
+        }
+    }
+
+    /// Get the diagnoses of infeasible conditions encountered so far, on this
+    /// and previous paths; see
+    /// [`Config.diagnose_unsat_paths`](config/struct.Config.html#structfield.diagnose_unsat_paths).
+    /// At most [`MAX_UNSAT_DIAGNOSES`](diagnosis/constant.MAX_UNSAT_DIAGNOSES.html)
+    /// are recorded.
+    pub fn unsat_diagnoses(&self) -> &[UnsatDiagnosis] {
+        &self.unsat_diagnoses
+    }
+
+    /// Assert the (1-bit) `constraint` on the current path, after passing it
+    /// through the
+    /// [`Config.constraint_middleware`](config/struct.Config.html#structfield.constraint_middleware),
+    /// if there is one. `origin` says why the constraint is being asserted.
+    ///
+    /// Returns `Ok(true)` if `constraint` itself was asserted, or `Ok(false)`
+    /// if the middleware dropped it or asserted something else instead.
+    pub fn assert_constraint(&mut self, origin: ConstraintOrigin, constraint: &B::BV) -> Result<bool> {
+        let middleware = match &self.config.constraint_middleware {
+            Some(middleware) => Rc::clone(middleware),
+            None => {
+                self.assert_and_record(origin, constraint.clone())?;
+                return Ok(true);
+            },
+        };
+        let ctx = ConstraintCtx {
+            origin,
+            location: match self.cur_loc.source_loc {
+                Some(source_loc) => source_loc.to_string(),
+                None => self.cur_loc.to_string_no_module(),
+            },
+            path: self.reverts,
+            depth: self.backtrack_points.borrow().len(),
+            shared_depth: self.shared_depth,
+        };
+        match middleware(&ctx, constraint) {
+            MiddlewareAction::PassThrough => {
+                self.assert_and_record(origin, constraint.clone())?;
+                Ok(true)
+            },
+            MiddlewareAction::Replace(replacement) => {
+                debug!("Constraint middleware replaced {:?} with {:?}", constraint, replacement);
+                self.assert_and_record(origin, replacement)?;
+                Ok(false)
+            },
+            MiddlewareAction::Drop => {
+                self.add_path_note(format!("constraint middleware dropped a {} constraint at {}", origin, ctx.location));
+                Ok(false)
+            },
+            MiddlewareAction::Abort(reason) => {
+                Err(Error::OtherError(format!("constraint middleware aborted the path at {}: {}", ctx.location, reason)))
+            },
+        }
+    }
+
+    fn assert_and_record(&mut self, origin: ConstraintOrigin, constraint: B::BV) -> Result<()> {
+        // violation checks are retracted right away, so they aren't part of the path
+        if self.config.simplify_constraints && origin != ConstraintOrigin::ViolationCheck {
+            match constraint.as_bool() {
+                Some(true) => {
+                    debug!("Not asserting a {} constraint which is always true", origin);
+                    return Ok(());
+                },
+                Some(false) if self.known_unsat_depth.is_none() => {
+                    debug!("Asserting a {} constraint which is always false; the path is now unsatisfiable", origin);
+                    self.known_unsat_depth = Some(self.backtrack_points.borrow().len());
+                },
+                _ => {},
+            }
+        }
+        if origin == ConstraintOrigin::ViolationCheck {
+            constraint.assert()?;
+            self.forget_cached_solver_results();
+            Ok(())
+        } else {
+            self.assert_on_path(constraint)
+        }
+    }
+
+    /// Assert `constraint` on the current path without passing it through the
+    /// `Config.constraint_middleware`. This is for constraints which are part
+    /// of the meaning of a value, such as the bounds on a clock reading,
+    /// rather than a decision about which path to take. Like the constraints
+    /// asserted with `assert_constraint()`, it's listed in `path_constraints()`.
+    pub(crate) fn assert_on_path(&mut self, constraint: B::BV) -> Result<()> {
+        self.assert_guarded(&constraint)?;
+        self.forget_cached_solver_results();
+        self.path_constraints.push((self.backtrack_points.borrow().len(), constraint));
+        let mut stats = self.solver_stats.borrow_mut();
+        stats.peak_path_constraints = std::cmp::max(stats.peak_path_constraints, self.path_constraints.len());
+        Ok(())
+    }
+
+    /// Assert `constraint`, guarded by the current path's literal if there is
+    /// one (see `Checkpointing::AssumptionLiterals`)
+    fn assert_guarded(&self, constraint: &B::BV) -> Result<()> {
+        let mut guards = self.guards.borrow_mut();
+        let literal = match &guards.current {
+            Some(literal) if guards.level == self.solver_depth.get() => literal.clone(),
+            _ => return constraint.assert(),
+        };
+        if !guards.active {
+            literal.assert()?;
+            guards.active = true;
+        }
+        let guarded = literal.implies(constraint);
+        guarded.assert()?;
+        guards.pending.push(guarded);
+        Ok(())
+    }
+
+    /// Get the constraints asserted on the current path via
+    /// [`assert_constraint()`](struct.State.html#method.assert_constraint),
+    /// which include all of the branch conditions, oldest first. Each is
+    /// paired with the number of backtracking points which were pending when
+    /// it was asserted. Constraints undone by backtracking aren't included.
+    ///
+    /// Constraints asserted directly with `BV::assert()` (e.g., by hooks) are
+    /// not tracked, and don't appear here.
+    pub fn path_constraints(&self) -> &[(usize, B::BV)] {
+        &self.path_constraints
+    }
+
+    /// Record a note about the current path, for instance that a hook
+    /// summarized some behavior rather than executing it
+    pub fn add_path_note(&mut self, note: impl Into<String>) {
+        self.path_notes.push(note.into());
+    }
+
+    /// Get the notes recorded on the current path so far (see
+    /// `add_path_note()`), oldest first
+    pub fn path_notes(&self) -> &[String] {
+        &self.path_notes
+    }
+
+    /// Start tracking a handle (such as a file descriptor) which was opened
+    /// on the current path; see [`Handle`](handles/struct.Handle.html).
+    ///
+    /// `returned` is the value returned to the program, which must be either
+    /// `value` or a value indicating failure. `site` describes where the
+    /// handle was opened, e.g. `"fopen at x.c:10"`.
+    pub fn open_handle(&mut self, returned: B::BV, value: u64, site: impl Into<String>) {
+        self.handles.push(Handle { returned, value, state: HandleState::Open, site: site.into() });
+    }
+
+    /// Mark the open handle which `handle` may refer to as closed. Does
+    /// nothing if `handle` can't refer to any open handle.
+    pub fn close_handle(&mut self, handle: &B::BV) -> Result<()> {
+        for i in 0 .. self.handles.len() {
+            if self.handles[i].state == HandleState::Open
+                && self.bvs_can_be_equal(handle, &self.bv_from_u64(self.handles[i].value, handle.get_width()))?
+            {
+                self.handles[i].state = HandleState::Closed;
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the handles opened on the current path so far (see
+    /// `open_handle()`), oldest first, including those which have been closed
+    pub fn handles(&self) -> &[Handle<B::BV>] {
+        &self.handles
+    }
+
+    /// Get the handles which are still open at this point on the current path
+    /// (excluding those whose opening failed on this path)
+    pub fn open_handles(&self) -> Result<Vec<&Handle<B::BV>>> {
+        let mut open = vec![];
+        for handle in &self.handles {
+            if handle.state == HandleState::Open
+                && self.bvs_can_be_equal(&handle.returned, &self.bv_from_u64(handle.value, handle.returned.get_width()))?
+            {
+                open.push(handle);
+            }
+        }
+        Ok(open)
+    }
+
+    /// Read the given `Clock` on the current path, returning a fresh 64-bit
+    /// `BV` constrained relative to the previous reading of the same clock on
+    /// this path (if any); see [`Clock`](clocks/enum.Clock.html) for the
+    /// constraints and units of each clock.
+    pub fn read_clock(&mut self, clock: Clock) -> Result<B::BV> {
+        let reading = self.new_bv_with_name(Name::from(format!("clock_{}", clock)), 64)?;
+        if let Some(prev) = self.clocks.get(&clock) {
+            let prev = prev.clone();
+            if clock.is_monotonic() {
+                self.assert_on_path(reading.ugte(&prev))?;
+            }
+            if let Some(max_time_step) = self.config.max_time_step.filter(|_| clock.is_bounded()) {
+                let max_time_step = max_time_step.as_nanos().min(u128::from(u64::MAX)) as u64;
+                self.assert_on_path(reading.sub(&prev).ulte(&self.bv_from_u64(max_time_step, 64)))?;
+            }
+        }
+        self.clocks.insert(clock, reading.clone());
+        Ok(reading)
+    }
+
+    /// If the current path is executing a global constructor (see
+    /// `Config.run_global_ctors`), its index in `Project::global_ctors()`
+    pub(crate) fn global_ctor(&self) -> Option<usize> {
+        self.global_ctor.map(|(idx, _)| idx)
+    }
+
+    /// Record that the current path is now executing the global constructor
+    /// with the given index in `Project::global_ctors()`, or `None` if it is
+    /// done with global constructors
+    pub(crate) fn set_global_ctor(&mut self, idx: Option<usize>) {
+        self.global_ctor = idx.map(|idx| (idx, 0));
+    }
+
+    /// Count an instruction executed by the current global constructor (if
+    /// any), returning an error if it exceeds
+    /// `Config.global_ctor_instruction_budget`
+    pub(crate) fn count_global_ctor_instruction(&mut self) -> Result<()> {
+        if let Some((idx, instrs)) = &mut self.global_ctor {
+            *instrs += 1;
+            if *instrs > self.config.global_ctor_instruction_budget {
+                return Err(Error::OtherError(format!(
+                    "global constructor #{} exceeded the configured global_ctor_instruction_budget, which was {}",
+                    idx,
+                    self.config.global_ctor_instruction_budget,
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Attach the given `Tag` to `bv`, replacing any tag it already had. Tags
+    /// are advisory, and have no effect on symbolic execution; see
+    /// [`Tag`](tags/struct.Tag.html).
+    pub fn tag_bv(&mut self, bv: &B::BV, tag: Tag) {
+        if bv.is_const() {
+            // constants are shared between unrelated values
+            return;
+        }
+        self.value_tags.insert(bv.get_id(), (bv.clone(), tag));
+    }
+
+    /// Get the `Tag` attached to `bv`, if any
+    pub fn get_tag(&self, bv: &B::BV) -> Option<&Tag> {
+        self.value_tags.get(&bv.get_id()).map(|(_, tag)| tag)
+    }
+
+    /// If `from` has a `Tag`, attach the same tag to `to` (unless `to`
+    /// already has one)
+    pub(crate) fn propagate_tag(&mut self, from: &B::BV, to: &B::BV) {
+        if let Some(tag) = self.get_tag(from).cloned() {
+            if self.get_tag(to).is_none() {
+                self.tag_bv(to, tag);
+            }
+        }
+    }
+
+    /// Describe each variable in the current function which has a `Tag`, with
+    /// a value for it consistent with the current path, e.g.
+    /// `%len: length = 17 (from strlen at x.c:10)`.
+    /// Variables are listed in order of `Name`.
+    pub fn tagged_values(&self) -> Result<Vec<String>> {
+        let mut described = vec![];
+        for (name, bv) in self.all_vars_in_cur_fn() {
+            if let Some(tag) = self.get_tag(bv) {
+                let value = match self.eval_bv(bv)? {
+                    Evaluated::DontCare => "any value".to_owned(),
+                    evaluated => evaluated.as_u64().map_or_else(|| "?".to_owned(), |value| value.to_string()),
+                };
+                let name = match name {
+                    Name::Name(name) => format!("%{}", name),
+                    Name::Number(n) => format!("%{}", n),
+                };
+                described.push(format!("{}: {}", name, tag.describe_value(value)));
+            }
+        }
+        Ok(described)
+    }
+
+    /// Get the operation which produced the given `BV`, if it was recorded;
+    /// see [`Expr`](rewrite/enum.Expr.html)
+    pub fn expr_of(&self, bv: &B::BV) -> Option<&Expr<B::BV>> {
+        self.exprs.get(&bv.get_id()).map(|(_, expr)| expr)
+    }
+
+    /// Get the global variables whose initializers couldn't be evaluated (for
+    /// instance, because they involve a constant expression we don't support),
+    /// so whose initial contents were left unconstrained, and why. Globals are
+    /// initialized on first reference, so this only includes globals
+    /// referenced so far. This list is not reset by backtracking.
+    pub fn global_init_fallbacks(&self) -> Vec<InitializerFallback> {
+        self.global_init_fallbacks.borrow().clone()
+    }
+
+    /// Get the number of times the rewrite rule with the given name has
+    /// applied so far; see
+    /// [`Config.rewrite_rules`](config/struct.Config.html#structfield.rewrite_rules).
+    /// This count is not reset by backtracking.
+    pub fn rewrite_count(&self, rule: &str) -> usize {
+        self.rewrite_counts.get(rule).copied().unwrap_or(0)
+    }
+
+    /// Record that `result` was produced by `expr`, and offer it to the
+    /// `Config.rewrite_rules`. Returns the `BV` to use for the expression:
+    /// either `result`, or the replacement from the first rule which applies.
+    pub(crate) fn rewrite(&mut self, expr: Expr<B::BV>, result: B::BV) -> Result<B::BV> {
+        if self.config.rewrite_rules.is_empty() || result.is_const() {
+            return Ok(result);
+        }
+        self.exprs.insert(result.get_id(), (result.clone(), expr.clone()));
+        let rules = self.config.rewrite_rules.clone();
+        for rule in rules.iter() {
+            let rewrite = match rule.rewrite(&expr, &result, self) {
+                Some(rewrite) => rewrite,
+                None => continue,
+            };
+            debug!("Rewrite rule {:?} replaced {:?} with {:?}", rule.name(), result, rewrite.replacement);
+            if self.config.check_rewrites {
+                let differs = result._ne(&rewrite.replacement);
+                if self.sat_with_extra_constraints(rewrite.assertions.iter().chain(std::iter::once(&differs)))? {
+                    return Err(Error::OtherError(format!(
+                        "rewrite rule {:?} produced a replacement which can differ from the original expression, at {}",
+                        rule.name(), self.cur_loc.to_string_no_module(),
+                    )));
+                }
+            }
+            for assertion in &rewrite.assertions {
+                if self.assert_constraint(ConstraintOrigin::Rewrite, assertion)? {
+                    self.note_condition(assertion, true);
+                }
+            }
+            *self.rewrite_counts.entry(rule.name().to_owned()).or_insert(0) += 1;
+            return Ok(rewrite.replacement);
+        }
+        Ok(result)
+    }
+
+    /// Record that `cond` is `bv == value` (if `is_eq`) or `bv != value` (if
+    /// not), so that when `cond` is later asserted (or its negation is), we can
+    /// learn the constant value of `bv`.
+    pub(crate) fn note_equality_condition(&mut self, cond: &B::BV, bv: &B::BV, value: u64, is_eq: bool) {
+        if bv.is_const() || bv.get_width() > 64 {
+            return;
+        }
+        let negated = cond.not();
+        for (cond, is_eq) in [(cond.clone(), is_eq), (negated, !is_eq)] {
+            self.equality_conditions.insert(cond.get_id(), (cond, EqualityCondition {
+                bv: bv.clone(),
+                value,
+                is_eq,
+            }));
+        }
+    }
+
+    /// Get one possible concrete value for the `BV`.
+    /// Returns `Ok(None)` if no possible solution, or `Error::SolverError` if the solver query failed.
+    pub fn get_a_solution_for_bv(&self, bv: &B::BV) -> Result<Option<BVSolution>> {
+        if self.known_unsat() {
+            return Ok(None);
+        }
+        // first check if the `bv` is a constant, if so, we can avoid a solve
+        match bv.as_binary_str() {
+            Some(bstr) => Ok(Some(BVSolution::from_01x_str(bstr))),
+            None => {
+                warn!("A call to get_a_solution_for_bv() is resulting in a call to sat() with model generation enabled. Experimentally, these types of calls can be very slow. The BV is {:?}", bv);
+                self.solver.set_opt(BtorOption::ModelGen(ModelGen::All));
+                let solution = if self.sat_for_model()? {
+                    bv.get_a_solution().map(Some)
+                } else {
+                    Ok(None)
+                };
+                self.solver.set_opt(BtorOption::ModelGen(ModelGen::Disabled));
+                solution
+            },
+        }
+    }
+
+    /// Get one possible concrete value for the `BV`, as a `u64`. Bits the
+    /// solver's model leaves unconstrained are taken to be `0`, so a `BV` which
+    /// is entirely unconstrained still produces a value.
+    /// Returns `Ok(None)` if no possible solution, `Error::SolverError` if the
+    /// solver query failed, or `Error::OtherError` if the `BV` is wider than 64 bits.
+    pub fn get_a_u64_solution_for_bv(&self, bv: &B::BV) -> Result<Option<u64>> {
+        match self.eval_bv(bv)? {
+            Evaluated::NoModel => Ok(None),
+            evaluated => evaluated.as_u64()
+                .map(Some)
+                .ok_or_else(|| Error::OtherError(format!("get_a_u64_solution_for_bv: BV is {} bits wide, more than 64", bv.get_width()))),
+        }
+    }
+
+    /// Get one possible concrete value for a one-bit `BV`, as a `bool`. As with
+    /// `get_a_u64_solution_for_bv()`, an unconstrained `BV` produces `false`.
+    /// Returns `Ok(None)` if no possible solution, `Error::SolverError` if the
+    /// solver query failed, or `Error::OtherError` if the `BV` isn't one bit wide.
+    pub fn get_a_bool_solution_for_bv(&self, bv: &B::BV) -> Result<Option<bool>> {
+        match self.get_a_solution_for_bv(bv)? {
+            None => Ok(None),
+            Some(solution) => solution.as_bool()
+                .map(Some)
+                .ok_or_else(|| Error::OtherError(format!("get_a_bool_solution_for_bv: BV is {} bits wide, expected 1", bv.get_width()))),
+        }
+    }
+
+    /// Evaluate the `BV` under the current constraints. Unlike
+    /// `get_a_solution_for_bv()`, this distinguishes a `BV` which the solver's
+    /// model leaves entirely unconstrained (`Evaluated::DontCare`) from one
+    /// which it gives a particular value, and reports unsatisfiable
+    /// constraints as `Evaluated::NoModel` rather than `None`.
+    ///
+    /// Returns `Error::SolverError` if the solver query failed.
+    pub fn eval_bv(&self, bv: &B::BV) -> Result<Evaluated> {
+        Ok(match self.get_a_solution_for_bv(bv)? {
+            None => Evaluated::NoModel,
+            Some(solution) => Evaluated::from_solution(solution),
+        })
+    }
+
+    /// Get the concrete contents of the `len` bytes of memory at `addr` in one
+    /// solution of the current constraints. A symbolic `addr` is evaluated in
+    /// the same solution, so the bytes are read from the address it takes
+    /// there. Bytes which the solution leaves unconstrained are `0`.
+    ///
+    /// Returns `Ok(None)` if the current constraints are unsatisfiable, or
+    /// `Error::SolverError` if the solver query failed.
+    pub fn get_bytes_from_model(&self, addr: &B::BV, len: usize) -> Result<Option<Vec<u8>>> {
+        let width = addr.get_width();
+        let bytes = (0 .. len as u64)
+            .map(|i| self.mem.borrow().read(&addr.add(&self.bv_from_u64(i, width)), 8))
+            .collect::<Result<Vec<_>>>()?;
+        self.solver.set_opt(BtorOption::ModelGen(ModelGen::All));
+        let values = match self.sat_for_model() {
+            Ok(true) => bytes.iter()
+                .map(|bv| Ok(Evaluated::from_solution(bv.get_a_solution()?).as_u64().unwrap_or(0) as u8))
+                .collect::<Result<Vec<u8>>>()
+                .map(Some),
+            Ok(false) => Ok(None),
+            Err(e) => Err(e),
+        };
+        self.solver.set_opt(BtorOption::ModelGen(ModelGen::Disabled));
+        values
+    }
+
+    /// Get the NUL-terminated C string at `addr` in one solution of the current
+    /// constraints, as for `get_bytes_from_model()`. The string ends at the
+    /// first NUL byte, or after `max_len` bytes if none of those is NUL; the
+    /// NUL isn't included.
+    ///
+    /// Returns the bytes of the string, along with the string they spell if
+    /// they're UTF-8 (otherwise, with the invalid sequences replaced by
+    /// `U+FFFD`). Returns `Ok(None)` if the current constraints are
+    /// unsatisfiable.
+    pub fn read_cstring_from_model(&self, addr: &B::BV, max_len: usize) -> Result<Option<(Vec<u8>, String)>> {
+        Ok(self.get_bytes_from_model(addr, max_len)?.map(|mut bytes| {
+            if let Some(nul) = bytes.iter().position(|&byte| byte == 0) {
+                bytes.truncate(nul);
+            }
+            let string = String::from_utf8_lossy(&bytes).into_owned();
+            (bytes, string)
+        }))
+    }
+
+    /// Get one possible concrete value for the given IR `Name` (from the given `Function` name).
+    /// Returns `Ok(None)` if no possible solution, or `Error::SolverError` if the solver query failed.
+    #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
+    pub fn get_a_solution_for_irname(&mut self, funcname: &String, name: &Name) -> Result<Option<BVSolution>> {
+        let bv = self.varmap.lookup_var(funcname, name)?;
+        self.get_a_solution_for_bv(bv)
+    }
+
+    /// Get a description of the possible solutions for the `BV`.
+    ///
+    /// `n`: Maximum number of distinct solutions to check for.
+    /// If there are more than `n` possible solutions, this returns a
+    /// `PossibleSolutions::AtLeast` containing `n+1` solutions.
+    ///
+    /// These solutions will be disambiguated - see docs on `boolector::BVSolution`.
+    ///
+    /// If there are no possible solutions, this returns `Ok` with an empty
+    /// `PossibleSolutions`, rather than returning an `Err` with `Error::Unsat`.
+    pub fn get_possible_solutions_for_bv(&self, bv: &B::BV, n: usize) -> Result<PossibleSolutions<BVSolution>> {
+        solver_utils::get_possible_solutions_for_bv(self.solver.clone(), bv, n)
+    }
+
+    /// Find simple values for the given `BV`s which are consistent with the
+    /// current constraints, for presenting a witness; see
+    /// [`solver_utils::shrink_witness()`](solver_utils/fn.shrink_witness.html).
+    /// This makes the same queries, but through the `State`, so that they use
+    /// its cached results and are counted in its `solver_stats()`.
+    ///
+    /// Does not permanently add any constraints to the solver.
+    pub fn shrink_witness(&self, bvs: &[B::BV], budget: usize) -> Result<Option<Vec<Option<u64>>>> {
+        if !self.sat()? {
+            return Ok(None);
+        }
+        self.push_solver_frame();
+        let retval = solver_utils::shrink_witness_values(
+            bvs,
+            budget,
+            |a, b| self.bvs_can_be_equal(a, b),
+            |bv| self.get_a_u64_solution_for_bv(bv),
+            |constraint| {
+                constraint.assert()?;
+                self.forget_cached_solver_results();
+                Ok(())
+            },
+        );
+        self.pop_solver_frame();
+        retval.map(Some)
+    }
+
+    /// Get a description of the possible solutions for the given IR `Name` (from the given `Function` name).
+    ///
+    /// `n`: Maximum number of distinct solutions to check for.
+    /// If there are more than `n` possible solutions, this returns a
+    /// `PossibleSolutions::AtLeast` containing `n+1` solutions.
+    ///
+    /// These solutions will be disambiguated - see docs on `boolector::BVSolution`.
+    ///
+    /// If there are no possible solutions, this returns `Ok` with an empty
+    /// `PossibleSolutions`, rather than returning an `Err` with `Error::Unsat`.
+    #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
+    pub fn get_possible_solutions_for_irname(&mut self, funcname: &String, name: &Name, n: usize) -> Result<PossibleSolutions<BVSolution>> {
+        let bv = self.varmap.lookup_var(funcname, name)?;
+        self.get_possible_solutions_for_bv(bv, n)
+    }
+
+    /// Get the maximum possible solution for the `BV`: that is, the highest value
+    /// for which the current set of constraints is still satisfiable.
+    /// "Maximum" will be interpreted in an unsigned fashion.
+    ///
+    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
+    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
+    /// query itself fails. Panics if the `BV` is wider than 64 bits.
+    pub fn max_possible_solution_for_bv_as_u64(&self, bv: &B::BV) -> Result<Option<u64>> {
+        solver_utils::max_possible_solution_for_bv_as_u64(self.solver.clone(), bv)
+    }
+
+    /// Get the maximum possible solution for the given IR `Name` (from the given
+    /// `Function` name): that is, the highest value for which the current set of
+    /// constraints is still satisfiable.
+    /// "Maximum" will be interpreted in an unsigned fashion.
+    ///
+    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
+    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
+    /// query itself fails. Panics if the `BV` is wider than 64 bits.
+    #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
+    pub fn max_possible_solution_for_irname_as_u64(&mut self, funcname: &String, name: &Name) -> Result<Option<u64>> {
+        let bv = self.varmap.lookup_var(funcname, name)?;
+        solver_utils::max_possible_solution_for_bv_as_u64(self.solver.clone(), bv)
+    }
+
+    /// Get the minimum possible solution for the `BV`: that is, the lowest value
+    /// for which the current set of constraints is still satisfiable.
+    /// "Minimum" will be interpreted in an unsigned fashion.
+    ///
+    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
+    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
+    /// query itself fails. Panics if the `BV` is wider than 64 bits.
+    pub fn min_possible_solution_for_bv_as_u64(&self, bv: &B::BV) -> Result<Option<u64>> {
+        solver_utils::min_possible_solution_for_bv_as_u64(self.solver.clone(), bv)
+    }
+
+    /// Get the minimum possible solution for the given IR `Name` (from the given
+    /// `Function` name): that is, the lowest value for which the current set of
+    /// constraints is still satisfiable.
+    /// "Minimum" will be interpreted in an unsigned fashion.
+    ///
+    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
+    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
+    /// query itself fails. Panics if the `BV` is wider than 64 bits.
+    #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
+    pub fn min_possible_solution_for_irname_as_u64(&self, funcname: &String, name: &Name) -> Result<Option<u64>> {
+        let bv = self.varmap.lookup_var(funcname, name)?;
+        solver_utils::min_possible_solution_for_bv_as_u64(self.solver.clone(), bv)
+    }
+
+    /// Get the maximum possible solution for the `BV`, interpreted in a signed
+    /// fashion.
+    ///
+    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
+    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
+    /// query itself fails. Panics if the `BV` is wider than 64 bits.
+    pub fn max_possible_solution_for_bv_as_i64(&self, bv: &B::BV) -> Result<Option<i64>> {
+        solver_utils::max_possible_solution_for_bv_as_i64(self.solver.clone(), bv)
+    }
+
+    /// Get the minimum possible solution for the `BV`, interpreted in a signed
+    /// fashion.
+    ///
+    /// Returns `Ok(None)` if there is no solution for the `BV`, that is, if the
+    /// current set of constraints is unsatisfiable. Only returns `Err` if a solver
+    /// query itself fails. Panics if the `BV` is wider than 64 bits.
+    pub fn min_possible_solution_for_bv_as_i64(&self, bv: &B::BV) -> Result<Option<i64>> {
+        solver_utils::min_possible_solution_for_bv_as_i64(self.solver.clone(), bv)
+    }
+
+    /// Like `max_possible_solution_for_bv_as_u64()`, but allows `BV`s of
+    /// arbitrary width; see
+    /// [`solver_utils::max_possible_solution_for_bv_as_binary_str()`](solver_utils/fn.max_possible_solution_for_bv_as_binary_str.html).
+    pub fn max_possible_solution_for_bv_as_binary_str(&self, bv: &B::BV) -> Result<Option<String>> {
+        solver_utils::max_possible_solution_for_bv_as_binary_str(self.solver.clone(), bv)
+    }
+
+    /// Like `min_possible_solution_for_bv_as_u64()`, but allows `BV`s of
+    /// arbitrary width; see
+    /// [`solver_utils::min_possible_solution_for_bv_as_binary_str()`](solver_utils/fn.min_possible_solution_for_bv_as_binary_str.html).
+    pub fn min_possible_solution_for_bv_as_binary_str(&self, bv: &B::BV) -> Result<Option<String>> {
+        solver_utils::min_possible_solution_for_bv_as_binary_str(self.solver.clone(), bv)
+    }
+
+    /// Create a `BV` constant representing the given `bool` (either constant
+    /// `true` or constant `false`).
+    /// The resulting `BV` will be either constant `0` or constant `1`, and will
+    /// have bitwidth `1`.
+    pub fn bv_from_bool(&self, b: bool) -> B::BV {
+        B::BV::from_bool(self.solver.clone(), b)
+    }
+
+    /// Create a `BV` representing the given constant `i32` value, with the given
+    /// bitwidth.
+    pub fn bv_from_i32(&self, i: i32, width: u32) -> B::BV {
+        B::BV::from_i32(self.solver.clone(), i, width)
+    }
+
+    /// Create a `BV` representing the given constant `u32` value, with the given
+    /// bitwidth.
+    pub fn bv_from_u32(&self, u: u32, width: u32) -> B::BV {
+        B::BV::from_u32(self.solver.clone(), u, width)
+    }
+
+    /// Create a `BV` representing the given constant `i64` value, with the given
+    /// bitwidth.
+    pub fn bv_from_i64(&self, i: i64, width: u32) -> B::BV {
+        B::BV::from_i64(self.solver.clone(), i, width)
+    }
+
+    /// Create a `BV` representing the given constant `u64` value, with the given
+    /// bitwidth.
+    pub fn bv_from_u64(&self, u: u64, width: u32) -> B::BV {
+        B::BV::from_u64(self.solver.clone(), u, width)
+    }
+
+    /// Create a `BV` representing the constant `0` of the given bitwidth.
+    /// This is equivalent to `self.bv_from_i32(0, width)` but may be more
+    /// efficient.
+    pub fn zero(&self, width: u32) -> B::BV {
+        B::BV::zero(self.solver.clone(), width)
+    }
+
+    /// Create a `BV` representing the constant `1` of the given bitwidth.
+    /// This is equivalent to `self.bv_from_i32(1, width)` but may be more
+    /// efficient.
+    pub fn one(&self, width: u32) -> B::BV {
+        B::BV::one(self.solver.clone(), width)
+    }
+
+    /// Create a `BV` constant of the given width, where all bits are set to one.
+    /// This is equivalent to `self.bv_from_i32(-1, width)` but may be more
+    /// efficient.
+    pub fn ones(&self, width: u32) -> B::BV {
+        B::BV::ones(self.solver.clone(), width)
+    }
+
+    /// Create a new (unconstrained) `BV` for the given `Name` (in the current function).
+    ///
+    /// This function performs uniquing, so if you call it twice
+    /// with the same `Name`-`Function` pair, you will get two different `BV`s.
+    ///
+    /// Returns the new `BV`, or `Err` if it can't be created.
+    ///
+    /// (As of this writing, the only `Err` that might be returned is
+    /// `Error::LoopBoundExceeded`, which is returned if creating the new `BV`
+    /// would exceed `max_versions_of_name` -- see
+    /// [`Config`](struct.Config.html).)
+    ///
+    /// Also, we assume that no two `Function`s share the same name.
+    pub fn new_bv_with_name(&mut self, name: Name, bits: u32) -> Result<B::BV> {
+        self.values_computed += 1;
+        self.varmap.new_bv_with_name(self.cur_loc.func.name.clone(), name, bits)
+    }
+
+    /// Create a new (unconstrained) `i1` for the given `Name` (in the current
+    /// function). Booleans are represented as one-bit `BV`s, so this is just
+    /// `new_bv_with_name()` with a width of 1.
+    pub fn new_bool_with_name(&mut self, name: Name) -> Result<B::BV> {
+        self.new_bv_with_name(name, 1)
+    }
+
+    /// Assign the given `BV` to the given `Name` (in the current function).
+    ///
+    /// This function performs uniquing, so it creates a new version of the
+    /// variable represented by the `(String, Name)` pair rather than overwriting
     /// the current version.
     ///
     /// Returns `Err` if the assignment can't be performed.
@@ -717,6 +2105,7 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     /// of the `BV` would exceed `max_versions_of_name` -- see
     /// [`Config`](struct.Config.html).)
     pub fn assign_bv_to_name(&mut self, name: Name, bv: B::BV) -> Result<()> {
+        self.values_computed += 1;
         self.varmap.assign_bv_to_name(self.cur_loc.func.name.clone(), name, bv)
     }
 
@@ -754,8 +2143,15 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     /// (All `Operand`s should be either a constant or a variable we previously added to the state.)
     pub fn operand_to_bv(&self, op: &Operand) -> Result<B::BV> {
         match op {
+            Operand::ConstantOperand(Constant::Undef(_)) if self.config.undef_values == UndefValues::Error => Err(Error::UndefUse),
             Operand::ConstantOperand(c) => self.const_to_bv(c),
-            Operand::LocalOperand { name, .. } => Ok(self.varmap.lookup_var(&self.cur_loc.func.name, name).clone()),
+            Operand::LocalOperand { name, .. } => {
+                let bv = self.varmap.lookup_var(&self.cur_loc.func.name, name)?;
+                match self.known_constant(bv) {
+                    Some(value) => Ok(self.bv_from_u64(value, bv.get_width())),
+                    None => Ok(bv.clone()),
+                }
+            },
             Operand::MetadataOperand => panic!("Can't convert {:?} to BV", op),
         }
     }
@@ -764,10 +2160,20 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     pub fn const_to_bv(&self, c: &Constant) -> Result<B::BV> {
         match c {
             Constant::Int { bits, value } => Ok(self.bv_from_u64(*value, *bits)),
+            #[cfg(feature = "fp")]
+            Constant::Float(f) => float::float_const(self.solver.clone(), f),
+            #[cfg(not(feature = "fp"))]
+            Constant::Float(f) => Err(Error::UnsupportedInstruction(format!("constant {:?} (floating-point support requires the `fp` feature)", f))),
             Constant::Null(ty)
             | Constant::AggregateZero(ty)
-            | Constant::Undef(ty)
                 => Ok(self.zero(size(ty) as u32)),
+            // each evaluation of an `undef` may produce a different value (so
+            // e.g. an `undef` pointer may point anywhere)
+            Constant::Undef(ty) => {
+                let n = self.undefs.get();
+                self.undefs.set(n + 1);
+                Ok(B::BV::new(self.solver.clone(), size(ty) as u32, Some(&format!("undef_{}", n))))
+            },
             Constant::Struct { values: elements, .. }
             | Constant::Array { elements, .. }
             | Constant::Vector(elements)
@@ -810,10 +2216,28 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
                             // initialize, but my initializer refers to your address so you try
                             // to initialize, but your initializer refers to my address so I try
                             // to initialize, etc.
+                            //
+                            // If we can't evaluate the initializer, we don't fail the path;
+                            // we assume nothing about the global's initial contents instead,
+                            // and record that we did so.
                             if !initialized.get() {
                                 debug!("Initializing {:?} with initializer {:?}", name, &initializer);
                                 initialized.set(true);
-                                let write_val = self.const_to_bv(initializer)?;
+                                let write_val = match self.const_to_bv(initializer) {
+                                    Ok(bv) => bv,
+                                    Err(Error::UnsupportedInstruction(reason)) | Err(Error::MalformedInstruction(reason)) => {
+                                        warn!("Couldn't evaluate the initializer of global {:?} ({}); leaving its initial contents unconstrained", name, reason);
+                                        self.global_init_fallbacks.borrow_mut().push(InitializerFallback { global: name.clone(), reason });
+                                        let bits = size(&initializer.get_type()) as u32;
+                                        let bvname = match name {
+                                            Name::Name(name) => format!("{}_initializer", name),
+                                            Name::Number(n) => format!("{}_initializer", n),
+                                        };
+                                        B::BV::new(self.solver.clone(), bits, Some(&bvname))
+                                    },
+                                    Err(e) => return Err(e),
+                                };
+                                let write_val = to_memory_layout(write_val, &initializer.get_type(), self.byte_order, self.project)?;
                                 self.write_without_mut(addr, write_val)?;
                             }
                             Ok(addr.clone())
@@ -873,16 +2297,9 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
             Constant::Trunc(t) => self.const_to_bv(&t.operand).map(|bv| bv.slice(size(&t.to_type) as u32 - 1, 0)),
             Constant::ZExt(z) => self.const_to_bv(&z.operand).map(|bv| bv.zero_extend_to_bits(size(&z.to_type) as u32)),
             Constant::SExt(s) => self.const_to_bv(&s.operand).map(|bv| bv.sign_extend_to_bits(size(&s.to_type) as u32)),
-            Constant::PtrToInt(pti) => {
-                let bv = self.const_to_bv(&pti.operand)?;
-                assert_eq!(bv.get_width(), size(&pti.to_type) as u32);
-                Ok(bv)  // just a cast, it's the same bits underneath
-            },
-            Constant::IntToPtr(itp) => {
-                let bv = self.const_to_bv(&itp.operand)?;
-                assert_eq!(bv.get_width(), size(&itp.to_type) as u32);
-                Ok(bv)  // just a cast, it's the same bits underneath
-            },
+            // these truncate or zero-extend if the integer type isn't pointer-sized
+            Constant::PtrToInt(pti) => self.const_to_bv(&pti.operand).map(|bv| bv.zero_extend_or_truncate_to_bits(size(&pti.to_type) as u32)),
+            Constant::IntToPtr(itp) => self.const_to_bv(&itp.operand).map(|bv| bv.zero_extend_or_truncate_to_bits(size(&itp.to_type) as u32)),
             Constant::BitCast(bc) => {
                 let bv = self.const_to_bv(&bc.operand)?;
                 assert_eq!(bv.get_width(), size(&bc.to_type) as u32);
@@ -912,12 +2329,13 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
             Constant::Select(s) => {
                 let b = self.const_to_bv(&s.condition)?;
                 match b.as_bool() {
-                    None => Err(Error::MalformedInstruction("Constant::Select: Expected a constant condition".to_owned())),
+                    // e.g., a comparison between the addresses of two globals
+                    None => Ok(b.cond_bv(&self.const_to_bv(&s.true_value)?, &self.const_to_bv(&s.false_value)?)),
                     Some(true) => self.const_to_bv(&s.true_value),
                     Some(false) => self.const_to_bv(&s.false_value),
                 }
             },
-            _ => unimplemented!("const_to_bv for {:?}", c),
+            _ => Err(Error::UnsupportedInstruction(format!("const_to_bv for {:?}", c))),
         }
     }
 
@@ -1050,6 +2468,69 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
         }
     }
 
+    /// Given a `BV` which is being called as a function pointer, if it has
+    /// multiple possible values (at most `n`), constrain it to the lowest of
+    /// them on the current path, and save a backtracking point for each of the
+    /// others, which will re-execute the current instruction with the `BV`
+    /// constrained to that value.
+    ///
+    /// Returns the number of possible values, or `Ok(None)` if there are more
+    /// than `n`.
+    pub(crate) fn split_on_function_ptr(&mut self, bv: &B::BV, n: usize) -> Result<Option<usize>> {
+        let mut addrs: Vec<u64> = match self.get_possible_solutions_for_bv(bv, n)?.as_u64_solutions().unwrap() {
+            PossibleSolutions::Exactly(v) => v.into_iter().collect(),
+            PossibleSolutions::AtLeast(_) => return Ok(None),
+        };
+        addrs.sort_unstable();
+        if addrs.len() > 1 {
+            debug!("Function pointer {:?} has {} possible targets; exploring each on its own path", bv, addrs.len());
+            for addr in addrs.iter().skip(1).rev() {
+                self.save_backtracking_point_at_location(
+                    self.cur_loc.clone(),
+                    bv._eq(&self.bv_from_u64(*addr, bv.get_width())),
+                );
+            }
+            self.assert_constraint(ConstraintOrigin::Concretization, &bv._eq(&self.bv_from_u64(addrs[0], bv.get_width())))?;
+        }
+        Ok(Some(addrs.len()))
+    }
+
+    /// Resolve the address of a `load` or `store` according to
+    /// `Config.address_resolution`. If the address has multiple possible values
+    /// and is to be concretized, constrain it to the lowest of the values
+    /// considered on the current path, and save a backtracking point for each
+    /// of the others, which will re-execute the current instruction with the
+    /// address constrained to that value.
+    ///
+    /// Returns the address to access.
+    pub(crate) fn resolve_address(&mut self, addr: &B::BV) -> Result<B::BV> {
+        let n = match self.config.address_resolution {
+            AddressResolution::Symbolic => return Ok(addr.clone()),
+            _ if addr.is_const() => return Ok(addr.clone()),
+            AddressResolution::ConcretizeToOne => 1,
+            AddressResolution::ConcretizeUpTo(n) => std::cmp::max(n, 1),
+        };
+        let mut addrs: Vec<u64> = match self.get_possible_solutions_for_bv(addr, n)?.as_u64_solutions().unwrap() {
+            PossibleSolutions::Exactly(v) => v.into_iter().collect(),
+            PossibleSolutions::AtLeast(v) => v.into_iter().collect(),
+        };
+        addrs.sort_unstable();
+        addrs.truncate(n);
+        let (&first, others) = addrs.split_first().ok_or(Error::Unsat)?;
+        if !others.is_empty() {
+            debug!("Address {:?} has multiple possible values; exploring {} of them, each on its own path", addr, addrs.len());
+        }
+        for other in others.iter().rev() {
+            self.save_backtracking_point_at_location(
+                self.cur_loc.clone(),
+                addr._eq(&self.bv_from_u64(*other, addr.get_width())),
+            );
+        }
+        let concrete = self.bv_from_u64(first, addr.get_width());
+        self.assert_constraint(ConstraintOrigin::Concretization, &addr._eq(&concrete))?;
+        Ok(concrete)
+    }
+
     /// Get a pointer to the given function name. The name must be the
     /// fully-mangled function name, as it appears in the LLVM. The name will be
     /// resolved in the current module; this means that it will first look for a
@@ -1090,25 +2571,58 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
             })
     }
 
+    /// Check that the address of a memory access can't be in one of the
+    /// invalid ranges (see `Config.invalid_address_ranges`), returning
+    /// `Error::NullPointerDereference` if it can.
+    fn check_address(&self, addr: &B::BV) -> Result<()> {
+        if self.config.null_pointer_checking == NullPointerChecking::None {
+            return Ok(());
+        }
+        if let Some(addr) = addr.as_u64() {
+            let invalid = (addr == 0 && !self.null_is_mapped)
+                || self.invalid_address_ranges.iter().any(|range| range.contains(&addr));
+            return if invalid { Err(Error::NullPointerDereference) } else { Ok(()) };
+        }
+        if self.invalid_address_ranges.is_empty() {
+            return Ok(());
+        }
+        let width = addr.get_width();
+        let invalid = self.invalid_address_ranges.iter()
+            .map(|range| addr.ugte(&self.bv_from_u64(range.start, width)).and(&addr.ult(&self.bv_from_u64(range.end, width))))
+            .fold(self.bv_from_bool(false), |acc, in_range| acc.or(&in_range));
+        if self.sat_with_extra_constraints(std::iter::once(&invalid))? {
+            if self.config.null_pointer_checking == NullPointerChecking::SplitPath {
+                // save a backtracking point to re-execute the current
+                // instruction with the address constrained to be valid, and
+                // continue from there
+                self.save_backtracking_point_at_location(self.cur_loc.clone(), invalid.not());
+            }
+            return Err(Error::NullPointerDereference);  // report the invalid access
+        }
+        Ok(())
+    }
+
     /// Read a value `bits` bits long from memory at `addr`.
     /// Note that `bits` can be arbitrarily large.
+    ///
+    /// With `Config.detect_uninitialized_reads`, this logs a warning if any of
+    /// the bits read may be uninitialized.
     pub fn read(&self, addr: &B::BV, bits: u32) -> Result<B::BV> {
-        let retval = match self.mem.borrow().read(addr, bits) {
-            Ok(val) => val,
-            e@Err(Error::NullPointerDereference) => {
-                if self.config.null_pointer_checking == NullPointerChecking::SplitPath {
-                    // save a backtracking point to re-execute the current
-                    // instruction with the address constrained to be non-null,
-                    // and continue from there
-                    self.save_backtracking_point_at_location(
-                        self.cur_loc.clone(),
-                        addr._ne(&self.zero(addr.get_width()))
-                    );
-                }
-                return e;  // report the null-pointer dereference
-            },
-            e@Err(_) => return e,  // propagate any other kind of error
-        };
+        let retval = self.read_possibly_uninitialized(addr, bits)?;
+        if let Some(uninitialized) = self.uninitialized_read_condition(addr, bits)? {
+            if self.sat_with_extra_constraints(std::iter::once(&uninitialized))? {
+                warn!("{} at {{{}}}", self.describe_uninitialized_read(addr, bits, &uninitialized)?, self.cur_loc.to_string_no_module());
+            }
+        }
+        Ok(retval)
+    }
+
+    /// Like `read()`, but without checking whether the bits read are
+    /// initialized. For copying memory (e.g. in `memcpy()`), where reading
+    /// uninitialized bytes isn't a bug in itself.
+    pub(crate) fn read_possibly_uninitialized(&self, addr: &B::BV, bits: u32) -> Result<B::BV> {
+        self.check_address(addr)?;
+        let retval = self.mem.borrow().read(addr, bits)?;
         for (name, watchpoint) in self.mem_watchpoints.get_triggered_watchpoints(addr, bits)? {
             let pretty_loc = if self.config.print_module_name {
                 self.cur_loc.to_string_with_module()
@@ -1116,6 +2630,7 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
                 self.cur_loc.to_string_no_module()
             };
             info!("Memory watchpoint {:?} {} read by {{{}}}", name, watchpoint, pretty_loc);
+            self.record_watchpoint_hit(name, watchpoint, Access::Read, pretty_loc, &retval);
         }
         Ok(retval)
     }
@@ -1126,33 +2641,119 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
         self.write_without_mut(addr, val)
     }
 
+    /// Read a value of the given `Type` from memory at `addr`, respecting the
+    /// `byte_order()`.
+    pub fn read_value(&self, addr: &B::BV, ty: &Type) -> Result<B::BV> {
+        let bits = memory_size(ty, self.byte_order, self.project)
+            .ok_or_else(|| Error::OtherError(format!("Can't read a value of type {:?}, which is opaque in the entire Project", ty)))?;
+        from_memory_layout(self.read(addr, bits as u32)?, ty, self.byte_order, self.project)
+    }
+
+    /// Write a value of the given `Type` into memory at `addr`, respecting the
+    /// `byte_order()`.
+    pub fn write_value(&mut self, addr: &B::BV, val: B::BV, ty: &Type) -> Result<()> {
+        let val = to_memory_layout(val, ty, self.byte_order, self.project)?;
+        self.write(addr, val)
+    }
+
+    /// Mark the `bits` bits of memory at `addr` as uninitialized, as though
+    /// they had just been allocated. Reading them before they are written
+    /// again is reported as an uninitialized read; see
+    /// `Config.detect_uninitialized_reads`. Their contents are unchanged.
+    ///
+    /// Memory the program didn't allocate itself, such as a buffer a function
+    /// parameter points to, counts as initialized unless marked with this.
+    ///
+    /// Does nothing if `Config.detect_uninitialized_reads` is `false`.
+    pub fn mark_uninitialized(&mut self, addr: &B::BV, bits: u32) -> Result<()> {
+        if let Some(shadow) = &self.shadow {
+            shadow.borrow_mut().write(addr, B::BV::ones(self.solver.clone(), bits))?;
+        }
+        Ok(())
+    }
+
+    /// Copy which of the `bits` bits of memory at `src` are initialized to the
+    /// bits at `dest`, e.g. after copying their contents with
+    /// `read_possibly_uninitialized()`.
+    pub(crate) fn copy_initialization(&self, dest: &B::BV, src: &B::BV, bits: u32) -> Result<()> {
+        if let Some(shadow) = &self.shadow {
+            let initialization = shadow.borrow().read(src, bits)?;
+            shadow.borrow_mut().write(dest, initialization)?;
+        }
+        Ok(())
+    }
+
+    /// Get a one-bit `BV` which is `1` exactly when any of the `bits` bits of
+    /// memory at `addr` are uninitialized; see `mark_uninitialized()`.
+    ///
+    /// Returns `None` if they are definitely all initialized, or if
+    /// `Config.detect_uninitialized_reads` is `false`.
+    pub fn uninitialized_read_condition(&self, addr: &B::BV, bits: u32) -> Result<Option<B::BV>> {
+        let shadow = match &self.shadow {
+            Some(shadow) => shadow.borrow().read(addr, bits)?,
+            None => return Ok(None),
+        };
+        let uninitialized = shadow.redor();
+        match uninitialized.as_bool() {
+            Some(false) => Ok(None),
+            _ => Ok(Some(uninitialized)),
+        }
+    }
+
+    /// Describe a read of `bits` bits at `addr` which is uninitialized when
+    /// `uninitialized` holds, naming the allocation and offset read from. A
+    /// symbolic `addr` is resolved to one of its values for which the read is
+    /// uninitialized.
+    pub(crate) fn describe_uninitialized_read(&self, addr: &B::BV, bits: u32, uninitialized: &B::BV) -> Result<String> {
+        let bytes = u64::from(bits).div_ceil(8);
+        let resolved = match addr.as_u64() {
+            Some(addr) => Some(addr),
+            None => self.with_temporary_constraints(std::iter::once(uninitialized), |state| state.get_a_u64_solution_for_bv(addr))??,
+        };
+        let target = match resolved {
+            Some(resolved) => match self.describe_pointer(resolved) {
+                Some(target) => target.to_string(),
+                None => format!("0x{:x}", resolved),
+            },
+            None => "a symbolic address".to_owned(),
+        };
+        let example = if resolved.is_some() && addr.as_u64().is_none() { " (for instance)" } else { "" };
+        Ok(format!("uninitialized read of {} bytes at {}{}", bytes, target, example))
+    }
+
+    /// Write `s` into memory at `addr` as a NUL-terminated C string, taking
+    /// `s.len() + 1` bytes.
+    pub fn write_cstring(&mut self, addr: &B::BV, s: &str) -> Result<()> {
+        // memory is little-endian, so the first byte goes in the low bits
+        let val = s.bytes().chain(std::iter::once(0))
+            .map(|byte| self.bv_from_u32(u32::from(byte), 8))
+            .reduce(|acc, byte| byte.concat(&acc))
+            .unwrap();
+        self.write(addr, val)
+    }
+
+    /// Get the byte order of multi-byte values in memory: `Config.byte_order`,
+    /// if set; otherwise, the byte order specified by the `Project`'s data
+    /// layout, or little-endian if it doesn't specify one.
+    ///
+    /// `read()` and `write()` always use little-endian; `read_value()` and
+    /// `write_value()` use this byte order.
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
     /// For internal use: since `self.mem` is a `RefCell`, we can write even
     /// without having a `&mut self` reference. This is necessary to support,
     /// for instance, lazy global initialization. But, we don't want to skip
     /// watchpoint checks by calling `self.mem.borrow_mut()` directly, so we
     /// have this
     fn write_without_mut(&self, addr: &B::BV, val: B::BV) -> Result<()> {
+        self.check_address(addr)?;
         let write_width = val.get_width();
-        let result = self.mem.borrow_mut().write(addr, val);
-        // we do this awkward `let result` / `match result` because it forces
-        // the mutable borrow of self.mem to end, which is necessary because
-        // save_backtracking_point_at_location requires a borrow of self.mem
-        match result {
-            Ok(()) => (),
-            e@Err(Error::NullPointerDereference) => {
-                if self.config.null_pointer_checking == NullPointerChecking::SplitPath {
-                    // save a backtracking point to re-execute the current
-                    // instruction with the address constrained to be non-null,
-                    // and continue from there
-                    self.save_backtracking_point_at_location(
-                        self.cur_loc.clone(),
-                        addr._ne(&self.zero(addr.get_width()))
-                    );
-                }
-                return e;  // report the null-pointer dereference
-            },
-            e@Err(_) => return e,  // propagate any other kind of error
-        };
+        self.mem.borrow_mut().write(addr, val.clone())?;
+        if let Some(shadow) = &self.shadow {
+            shadow.borrow_mut().write(addr, self.zero(write_width))?;
+        }
         for (name, watchpoint) in self.mem_watchpoints.get_triggered_watchpoints(addr, write_width)? {
             let pretty_loc = if self.config.print_module_name {
                 self.cur_loc.to_string_with_module()
@@ -1165,10 +2766,33 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
             let watchpoint_size_bits = (watchpoint.get_upper_bound() - watchpoint.get_lower_bound() + 1) * 8;
             let new_value = self.mem.borrow().read(&watchpoint_low, watchpoint_size_bits as u32)?;  // performs a read without using `state.read()` which would trigger watchpoints (we don't want to trigger watchpoints with this read)
             info!("Memory watchpoint {:?} {} written by {{{}}}; new value is {:?}", name, watchpoint, pretty_loc, new_value);
+            self.record_watchpoint_hit(name, watchpoint, Access::Write, pretty_loc, &val);
         }
         Ok(())
     }
 
+    /// With `Config.collect_watchpoint_hits`, record an access of `value`
+    /// which triggered the given watchpoint
+    fn record_watchpoint_hit(&self, name: &str, watchpoint: &Watchpoint, access: Access, location: String, value: &B::BV) {
+        if self.config.collect_watchpoint_hits {
+            self.watchpoint_hits.borrow_mut().push(WatchpointHit {
+                name: name.to_owned(),
+                watchpoint: watchpoint.clone(),
+                access,
+                location,
+                path: self.path_index,
+                value: value.clone(),
+            });
+        }
+    }
+
+    /// Get the reads and writes which triggered memory watchpoints so far, on
+    /// all paths, oldest first. Always empty unless
+    /// `Config.collect_watchpoint_hits` is `true`.
+    pub fn watchpoint_hits(&self) -> Vec<WatchpointHit<B::BV>> {
+        self.watchpoint_hits.borrow().clone()
+    }
+
     /// Add a memory watchpoint. It will be enabled unless/until
     /// `disable_watchpoint()` is called on it.
     ///
@@ -1210,10 +2834,182 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
         self.mem_watchpoints.enable(name)
     }
 
-    /// Allocate a value of size `bits`; return a pointer to the newly allocated object
-    pub fn allocate(&mut self, bits: impl Into<u64>) -> B::BV {
-        let raw_ptr = self.alloc.alloc(bits);
-        self.bv_from_u64(raw_ptr, 64)
+    /// Allocate a value of size `bits`; return a pointer to the newly allocated object
+    pub fn allocate(&mut self, bits: impl Into<u64>) -> B::BV {
+        let raw_ptr = self.alloc.alloc(bits);
+        self.bv_from_u64(raw_ptr, 64)
+    }
+
+    /// Record that the allocation at the given address has been `free()`d on
+    /// the current path. Freeing `NULL` does nothing.
+    ///
+    /// If `Config.detect_double_frees` is set, freeing an allocation which
+    /// may already be freed (or out of scope) on the current path results in
+    /// an `Error::DoubleFree`, and freeing an address which isn't the start of
+    /// a heap allocation results in an `Error::InvalidFree`. Here, any
+    /// allocation other than a global, function, or stack slot counts as a
+    /// heap allocation, since hooks may allocate memory however they like.
+    ///
+    /// Addresses which may have several values are only recorded if they have
+    /// just one value on the current path. When checking for double frees,
+    /// if such an address may be the start of an allocation which was already
+    /// freed, a backtracking point is saved to re-execute the current
+    /// instruction with the address constrained to that start (where the
+    /// double free will be reported), and the current path continues with the
+    /// address constrained not to be it.
+    pub fn free(&mut self, addr: &B::BV) -> Result<()> {
+        let site = format!("free at {}", self.describe_cur_loc());
+        let addr = match self.single_address(addr, "free()")? {
+            Some(addr) => addr,
+            None => {
+                if self.config.detect_double_frees {
+                    let mut freed: Vec<(u64, String)> = self.freed.iter().map(|(&start, first_free)| (start, first_free.clone())).collect();
+                    freed.sort_unstable();
+                    for (start, first_free) in freed {
+                        let is_start = addr._eq(&self.bv_from_u64(start, addr.get_width()));
+                        if !self.sat_with_extra_constraints(std::iter::once(&is_start))? {
+                            continue;
+                        }
+                        if !self.sat_with_extra_constraints(std::iter::once(&is_start.not()))? {
+                            return Err(Error::DoubleFree { addr: start, first_free, second_free: site });
+                        }
+                        debug!("Address {:?} passed to free() may be the already-freed 0x{:x}; exploring that on its own path", addr, start);
+                        self.save_backtracking_point_at_location(self.cur_loc.clone(), is_start.clone());
+                        self.assert_constraint(ConstraintOrigin::BranchRefinement, &is_start.not())?;
+                    }
+                }
+                return Ok(());
+            },
+        };
+        if addr == 0 {
+            return Ok(());
+        }
+        if self.config.detect_double_frees {
+            if addr < self.dynamic_allocations_start
+                || self.stack_slots.contains(&addr)
+                || self.alloc.get_allocation_size(addr).is_none()
+            {
+                return Err(Error::InvalidFree { addr, site });
+            }
+            if let Some(first_free) = self.freed.get(&addr) {
+                return Err(Error::DoubleFree { addr, first_free: first_free.clone(), second_free: site });
+            }
+        }
+        self.freed.insert(addr, site);
+        Ok(())
+    }
+
+    /// Record that the stack slot containing the given address went out of
+    /// scope, as at an `llvm.lifetime.end`. As with `free()`, addresses which
+    /// may have several values are only recorded if they have just one value
+    /// on the current path.
+    pub fn end_lifetime(&mut self, addr: &B::BV) -> Result<()> {
+        if let Some((start, _)) = self.single_address(addr, "llvm.lifetime.end")?.and_then(|addr| self.alloc.find_allocation(addr)) {
+            let site = format!("llvm.lifetime.end at {}", self.describe_cur_loc());
+            self.freed.insert(start, site);
+        }
+        Ok(())
+    }
+
+    /// Record that the stack slot containing the given address is in scope
+    /// again, as at an `llvm.lifetime.start`
+    pub fn start_lifetime(&mut self, addr: &B::BV) -> Result<()> {
+        if let Some((start, _)) = self.single_address(addr, "llvm.lifetime.start")?.and_then(|addr| self.alloc.find_allocation(addr)) {
+            self.freed.remove(&start);
+        }
+        Ok(())
+    }
+
+    /// Get the only possible value of `addr` on the current path, or `None`
+    /// if it has several (`what` is for logging)
+    fn single_address(&self, addr: &B::BV, what: &str) -> Result<Option<u64>> {
+        match addr.as_u64() {
+            Some(addr) => Ok(Some(addr)),
+            None => match self.get_possible_solutions_for_bv(addr, 1)?.as_u64_solutions() {
+                Some(PossibleSolutions::Exactly(v)) => Ok(Some(*v.iter().next().ok_or(Error::Unsat)?)),
+                _ => {
+                    debug!("Not recording {} of {:?}, which has multiple possible values", what, addr);
+                    Ok(None)
+                },
+            },
+        }
+    }
+
+    /// Description of the current location: the source location if
+    /// available, or else the LLVM location
+    fn describe_cur_loc(&self) -> String {
+        match self.cur_loc.source_loc {
+            Some(source_loc) => source_loc.to_string(),
+            None => self.cur_loc.to_string_no_module(),
+        }
+    }
+
+    /// Record that the heap allocation at `addr` was returned to the program as
+    /// `returned`, which is either `addr` or, if the allocation can fail,
+    /// possibly `NULL`. Does nothing if `addr` was already recorded on the
+    /// current path, e.g. by a `realloc()` which didn't move the allocation.
+    pub fn record_heap_allocation(&mut self, addr: &B::BV, returned: &B::BV) {
+        if let Some(addr) = addr.as_u64() {
+            if !self.heap_allocations.iter().any(|&(start, _)| start == addr) {
+                self.heap_allocations.push((addr, returned.clone()));
+            }
+        }
+    }
+
+    /// Get the start addresses of the heap allocations which are still live at
+    /// this point on the current path, oldest first: those recorded with
+    /// `record_heap_allocation()` on this path which haven't been `free()`d,
+    /// and which may have been returned to the program (rather than `NULL`).
+    pub fn live_heap_allocations(&self) -> Result<Vec<u64>> {
+        let mut live = vec![];
+        for (start, returned) in &self.heap_allocations {
+            if !self.freed.contains_key(start)
+                && self.bvs_can_be_equal(returned, &self.bv_from_u64(*start, returned.get_width()))?
+            {
+                live.push(*start);
+            }
+        }
+        Ok(live)
+    }
+
+    /// Has the allocation at the given address been `free()`d (or gone out of
+    /// scope) on the current path?
+    pub fn is_freed(&self, addr: u64) -> bool {
+        self.freed.contains_key(&addr)
+    }
+
+    /// Get the allocations which have been `free()`d or have gone out of
+    /// scope on the current path, in address order, as triples of start
+    /// address, size in bits, and a description of where they were freed
+    /// (e.g. `free at x.c:12`)
+    pub fn freed_allocations(&self) -> Vec<(u64, u64, &str)> {
+        let mut freed: Vec<_> = self.freed.iter()
+            .filter_map(|(&start, site)| Some((start, self.alloc.get_allocation_size(start)?, site.as_str())))
+            .collect();
+        freed.sort_unstable();
+        freed
+    }
+
+    /// Get the allocations which are live on the current path, in address
+    /// order, as pairs of start address and size in bits: the global
+    /// variables, and the allocations made on the current path which haven't
+    /// been `free()`d (or gone out of scope). Functions and function hooks
+    /// aren't included, and nor are allocations made on paths which were
+    /// abandoned by backtracking.
+    pub fn live_allocations(&self) -> Vec<(u64, u64)> {
+        self.alloc.allocations_from(0)
+            .take_while(|(start, _)| *start < self.global_vars_end)
+            .chain(self.path_allocation_ranges.iter().flat_map(|range| {
+                self.alloc.allocations_from(range.start).take_while(move |(start, _)| *start < range.end)
+            }))
+            .filter(|(start, _)| !self.freed.contains_key(start))
+            .collect()
+    }
+
+    /// Find the allocation containing the given address, as for
+    /// `describe_pointer()`, returning its start address and its size in bits
+    pub fn find_allocation(&self, addr: u64) -> Option<(u64, u64)> {
+        self.alloc.find_allocation(addr)
     }
 
     /// Get the size, in bits, of the allocation at the given address, or `None`
@@ -1238,6 +3034,111 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
         }
     }
 
+    /// Attach a description to the allocation at the given address (which
+    /// should be the result of `allocate()`), e.g. the name of the parameter
+    /// which points to it. If `ty` is given, pointers into the allocation will
+    /// also be described in terms of the fields and elements of that `Type`.
+    ///
+    /// `haybale` labels global variables (e.g. `@buf`), `alloca`s (e.g.
+    /// `%buf`), and allocations made by the default `malloc`-family hooks
+    /// (e.g. `malloc at x.c:10`) automatically; this overrides those labels.
+    pub fn label_allocation(&mut self, addr: &B::BV, label: impl Into<String>, ty: Option<Type>) -> Result<()> {
+        let addr = addr.as_u64()
+            .ok_or_else(|| Error::OtherError(format!("label_allocation: address is not a constant: {:?}", addr)))?;
+        self.alloc.label(addr, label.into(), ty);
+        Ok(())
+    }
+
+    /// Describe the given (concrete) address in terms of the allocation it
+    /// points into and the offset into that allocation, which unlike the raw
+    /// address is stable across runs. Pointers one past the end of an
+    /// allocation are described as such.
+    ///
+    /// Returns `None` if the address isn't inside any allocation, or the
+    /// allocation has no label (see `label_allocation()`).
+    pub fn describe_pointer(&self, addr: u64) -> Option<PointerTarget> {
+        let (start, _) = self.alloc.find_allocation(addr)?;
+        let (label, ty) = self.alloc.get_label(start)?;
+        let offset = addr - start;
+        Some(PointerTarget {
+            allocation: label.clone(),
+            offset,
+            field_path: ty.as_ref().and_then(|ty| field_path(ty, offset, self.project)),
+        })
+    }
+
+    /// Build a `HeapGraph` of the allocations made so far on this execution,
+    /// other than those for global variables and functions, giving their
+    /// contents in one solution of the current constraints and resolving the
+    /// pointers among them as `describe_pointer()` does.
+    ///
+    /// Since allocations are never freed, this includes allocations made on
+    /// paths abandoned by backtracking. Their contents are whatever the
+    /// current path implies.
+    ///
+    /// Returns `Error::Unsat` if the current constraints are unsatisfiable.
+    pub fn heap_graph(&self) -> Result<HeapGraph> {
+        // read all the fields first, so their values all come from one model
+        let mut reads = Vec::new();
+        for (start, bits) in self.alloc.allocations_from(self.dynamic_allocations_start) {
+            let (allocation, ty) = match self.alloc.get_label(start) {
+                Some((label, ty)) => (label.clone(), ty.as_ref()),
+                None => (format!("0x{:x}", start), None),
+            };
+            let (layout, truncated) = heap_graph::field_layout(ty, bits, self.project);
+            let bvs = layout.iter()
+                .map(|field| self.mem.borrow().read(&self.bv_from_u64(start + field.offset, POINTER_SIZE_BITS as u32), field.bits))
+                .collect::<Result<Vec<_>>>()?;
+            let node = HeapNode { allocation, bytes: bits.div_ceil(8), fields: Vec::new(), truncated };
+            reads.push((start, node, layout, bvs));
+        }
+        self.solver.set_opt(BtorOption::ModelGen(ModelGen::All));
+        let values = if self.sat_for_model()? {
+            reads.iter()
+                .map(|(_, _, _, bvs)| bvs.iter().map(|bv| Ok(Evaluated::from_solution(bv.get_a_solution()?).as_u64().unwrap_or(0))).collect())
+                .collect::<Result<Vec<Vec<u64>>>>()
+        } else {
+            Err(Error::Unsat)
+        };
+        self.solver.set_opt(BtorOption::ModelGen(ModelGen::Disabled));
+        let values = values?;
+
+        let node_indices: HashMap<u64, usize> = reads.iter().enumerate().map(|(i, (start, ..))| (*start, i)).collect();
+        let mut graph = HeapGraph { nodes: Vec::new(), edges: Vec::new() };
+        for (from, ((_, mut node, layout, _), values)) in reads.into_iter().zip(values).enumerate() {
+            for (field, (layout, value)) in layout.into_iter().zip(values).enumerate() {
+                let target = self.alloc.find_allocation(value).map(|(start, _)| {
+                    let target = self.describe_pointer(value).unwrap_or_else(|| PointerTarget {
+                        allocation: format!("0x{:x}", start),
+                        offset: value - start,
+                        field_path: None,
+                    });
+                    match node_indices.get(&start) {
+                        Some(&to) => EdgeTarget::Node(to, target),
+                        None => EdgeTarget::Outside(target),
+                    }
+                });
+                let value = match (layout.pointer, target) {
+                    (true, _) if value == 0 => FieldValue::Null,
+                    (true, None) => {
+                        graph.edges.push(HeapEdge { from, field, to: EdgeTarget::Dangling(value), cycle: false });
+                        FieldValue::Pointer(value)
+                    },
+                    // words of untyped allocations are taken to be pointers if they point into an allocation
+                    (pointer, Some(to)) if pointer || layout.path.is_none() && layout.bits == POINTER_SIZE_BITS as u32 => {
+                        graph.edges.push(HeapEdge { from, field, to, cycle: false });
+                        FieldValue::Pointer(value)
+                    },
+                    _ => FieldValue::Scalar(value),
+                };
+                node.fields.push(HeapField { offset: layout.offset, bits: layout.bits, path: layout.path, value });
+            }
+            graph.nodes.push(node);
+        }
+        graph.mark_cycles();
+        Ok(graph)
+    }
+
     /// Record the current location as a `PathEntry` in the current path.
     pub fn record_path_entry(&mut self) {
         let entry = PathEntry(self.cur_loc.clone());
@@ -1245,6 +3146,44 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
         self.path.push(entry);
     }
 
+    /// Branch from the current block to the start of the block with the given
+    /// name, in the same function. With `Config.max_loop_iterations`, this
+    /// returns `Error::LoopBoundExceeded` if the branch starts one too many
+    /// iterations of a loop.
+    pub(crate) fn branch_to_bb(&mut self, bbname: &Name) -> Result<()> {
+        let from = &self.cur_loc.bb.name;
+        self.cur_loc.move_to_start_of_bb_by_name(bbname);
+        self.count_loop_iteration(from)
+    }
+
+    /// With `Config.max_loop_iterations`, account for the edge from the block
+    /// named `from` to the current block, which we're at the start of: if it
+    /// is a back-edge, that's another iteration of the loop it heads;
+    /// otherwise, entering the block (re)starts any loop it heads.
+    fn count_loop_iteration(&mut self, from: &'p Name) -> Result<()> {
+        let max_iterations = match self.config.max_loop_iterations {
+            Some(max_iterations) => max_iterations,
+            None => return Ok(()),
+        };
+        let func = self.cur_loc.func;
+        let to = &self.cur_loc.bb.name;
+        let func_key = (self.cur_loc.module.name.as_str(), func.name.as_str());
+        let back_edges = self.back_edges.entry(func_key)
+            .or_insert_with(|| crate::analyze::back_edges(func));
+        let key = (self.stack.len(), func_key, to);
+        if back_edges.contains(&(from, to)) {
+            let iterations = self.loop_iterations.entry(key).or_insert(1);
+            *iterations += 1;
+            if *iterations > max_iterations {
+                info!("Loop at bb {} in function {:?} exceeded max_loop_iterations ({})", to, func.name, max_iterations);
+                return Err(Error::LoopBoundExceeded(max_iterations));
+            }
+        } else {
+            self.loop_iterations.remove(&key);
+        }
+        Ok(())
+    }
+
     /// Get the `PathEntry`s that have been recorded, in order
     pub fn get_path(&self) -> &Vec<PathEntry<'p>> {
         &self.path
@@ -1272,23 +3211,99 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
             // future we could check the LLVM 'norecurse' attribute to know when
             // this is not necessary.
             restore_info: self.varmap.get_restore_info_for_fn(self.cur_loc.func.name.clone()),
-        })
+            stack_usage: self.stack_usage,
+            dynamic_stack_usage: self.dynamic_stack_usage.clone(),
+        });
+        self.stack_usage += self.config.stack_frame_overhead;
+        self.update_max_stack_usage();
     }
 
     /// Record leaving the current function. Returns the `Callsite` at which the
     /// current function was called, or `None` if the current function was the
     /// top-level function.
     ///
-    /// Also restores the caller's local variables.
+    /// Also restores the caller's local variables, and records the current
+    /// function's stack slots as out of scope.
     pub fn pop_callsite(&mut self) -> Option<Callsite<'p>> {
-        if let Some(StackFrame { callsite, restore_info }) = self.stack.pop() {
+        if let Some(StackFrame { callsite, restore_info, stack_usage, dynamic_stack_usage }) = self.stack.pop() {
+            let site = format!("return from {}", self.cur_loc.func.name);
+            while let Some(&(depth, addr)) = self.stack_allocations.last() {
+                if depth <= self.stack.len() {
+                    break;
+                }
+                self.stack_allocations.pop();
+                self.freed.insert(addr, site.clone());
+            }
             self.varmap.restore_fn_vars(restore_info);
+            self.stack_usage = stack_usage;
+            self.dynamic_stack_usage = dynamic_stack_usage;
             Some(callsite)
         } else {
             None
         }
     }
 
+    /// Record a stack allocation (i.e., an `alloca`) at the given address, of
+    /// the given number of bytes and alignment, in the current frame
+    pub(crate) fn record_stack_allocation(&mut self, addr: u64, bytes: u64, alignment: u32) {
+        self.stack_allocations.push((self.stack.len(), addr));
+        self.stack_slots.insert(addr);
+        let alignment = std::cmp::max(u64::from(alignment), 1);
+        let padded = self.stack_usage.div_ceil(alignment) * alignment;
+        self.stack_usage = padded + bytes;
+        self.update_max_stack_usage();
+    }
+
+    /// Record a dynamically-sized stack allocation at the given address, whose
+    /// size in bytes is given by the 64-bit `BV` `bytes`, in the current frame
+    pub(crate) fn record_dynamic_stack_allocation(&mut self, addr: u64, bytes: &B::BV, alignment: u32) {
+        self.stack_allocations.push((self.stack.len(), addr));
+        self.stack_slots.insert(addr);
+        let alignment = std::cmp::max(u64::from(alignment), 1);
+        self.stack_usage = self.stack_usage.div_ceil(alignment) * alignment;
+        self.dynamic_stack_usage = Some(match &self.dynamic_stack_usage {
+            Some(dynamic) => dynamic.add(bytes),
+            None => bytes.clone(),
+        });
+        self.update_max_stack_usage();
+    }
+
+    fn update_max_stack_usage(&mut self) {
+        self.max_stack_usage = std::cmp::max(self.max_stack_usage, self.stack_usage);
+        if let Some(dynamic) = &self.dynamic_stack_usage {
+            let total = self.bv_from_u64(self.stack_usage, 64).add(dynamic);
+            self.max_dynamic_stack_usage = Some(match &self.max_dynamic_stack_usage {
+                Some(max) => total.ugt(max).cond_bv(&total, max),
+                None => total,
+            });
+        }
+    }
+
+    /// Get the number of bytes of stack currently in use on this path: the
+    /// total size of the `alloca`s in all active frames (with padding for
+    /// alignment), plus `Config.stack_frame_overhead` for each frame.
+    /// Dynamically-sized `alloca`s aren't included; see
+    /// `max_stack_usage_on_path_bv()`.
+    pub fn stack_usage(&self) -> u64 {
+        self.stack_usage
+    }
+
+    /// Get the largest value `stack_usage()` has had at any point on this path
+    pub fn max_stack_usage_on_path(&self) -> u64 {
+        self.max_stack_usage
+    }
+
+    /// Get the largest stack usage at any point on this path, counting
+    /// dynamically-sized `alloca`s, as a 64-bit `BV` whose value depends on
+    /// their sizes. Returns `None` if there haven't been any on this path, in
+    /// which case the answer is just `max_stack_usage_on_path()`.
+    pub fn max_stack_usage_on_path_bv(&self) -> Option<B::BV> {
+        self.max_dynamic_stack_usage.as_ref().map(|max_dynamic| {
+            let max_static = self.bv_from_u64(self.max_stack_usage, 64);
+            max_dynamic.ugt(&max_static).cond_bv(max_dynamic, &max_static)
+        })
+    }
+
     /// Returns the current callstack depth. `0` indicates we're in the toplevel
     /// function, `1` indicates we're in a function directly called by the
     /// toplevel function, etc.
@@ -1301,16 +3316,33 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     /// The constraint will be added only if we end up backtracking to this point, and only then.
     pub fn save_backtracking_point(&mut self, bb_to_enter: &Name, constraint: B::BV) {
         debug!("Saving a backtracking point, which would enter bb {:?} with constraint {:?}", bb_to_enter, constraint);
-        let bb_to_enter = self.cur_loc.func.get_bb_by_name(&bb_to_enter)
-            .unwrap_or_else(|| panic!("Failed to find bb named {} in function {:?}", bb_to_enter, self.cur_loc.func.name));
-        let backtrack_loc = Location {
+        let backtrack_loc = self.start_of_bb_in_cur_func(bb_to_enter);
+        self.save_backtracking_point_with_alternatives(vec![(backtrack_loc, constraint)], Some(&self.cur_loc.bb.name));
+    }
+
+    /// Like `save_backtracking_point()`, but for several alternative
+    /// `BasicBlock`s to enter (e.g., the cases of a `switch`), each with its own
+    /// constraint. Each will be explored on its own path, in reverse order.
+    /// Together they only count as one backtracking point for the solver,
+    /// rather than one each.
+    pub fn save_backtracking_points(&mut self, alternatives: Vec<(&Name, B::BV)>) {
+        debug!("Saving a backtracking point with {} alternatives", alternatives.len());
+        let alternatives = alternatives.into_iter()
+            .map(|(bb_to_enter, constraint)| (self.start_of_bb_in_cur_func(bb_to_enter), constraint))
+            .collect();
+        self.save_backtracking_point_with_alternatives(alternatives, Some(&self.cur_loc.bb.name));
+    }
+
+    fn start_of_bb_in_cur_func(&self, bbname: &Name) -> Location<'p> {
+        let bb = self.cur_loc.func.get_bb_by_name(bbname)
+            .unwrap_or_else(|| panic!("Failed to find bb named {} in function {:?}", bbname, self.cur_loc.func.name));
+        Location {
             module: self.cur_loc.module,
             func: self.cur_loc.func,
-            bb: bb_to_enter,
+            bb,
             instr: BBInstrIndex::Instr(0),
             source_loc: None,
-        };
-        self.save_backtracking_point_at_location(backtrack_loc, constraint);
+        }
     }
 
     /// Internal version of `save_backtracking_point()` which takes an arbitrary
@@ -1318,39 +3350,293 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     ///
     /// Also it doesn't require `&mut self`. This allows us to save backtracking
     /// points even when we're inside methods that only have `&self`.
-    fn save_backtracking_point_at_location(&self, loc_to_start_at: Location<'p>, constraint: B::BV) {
-        self.solver.push(1);
-        self.backtrack_points.borrow_mut().push(BacktrackPoint {
-            loc: loc_to_start_at,
+    pub(crate) fn save_backtracking_point_at_location(&self, loc_to_start_at: Location<'p>, constraint: B::BV) {
+        self.save_backtracking_point_with_alternatives(vec![(loc_to_start_at, constraint)], None);
+    }
+
+    fn save_backtracking_point_with_alternatives(&self, alternatives: Vec<(Location<'p>, B::BV)>, branch_from: Option<&'p Name>) {
+        if self.global_ctor.is_some() && self.config.global_ctor_paths == GlobalCtorPaths::Single {
+            debug!("Not saving a backtracking point, as we only follow one path through global constructors");
+            return;
+        }
+        if alternatives.is_empty() {
+            return;
+        }
+        let guard = if self.assumption_literals {
+            self.start_path_guard()
+        } else {
+            None
+        };
+        let snapshot = if self.config.backtracking_order == BacktrackingOrder::DepthFirst {
+            if !self.assumption_literals {
+                self.push_solver_frame();
+            }
+            None
+        } else {
+            if !self.assumption_literals && self.base_constraints_len.get().is_none() {
+                // from now on, each path's constraints go in their own level
+                self.base_constraints_len.set(Some(self.path_constraints.len()));
+                self.push_solver_frame();
+            }
+            Some(PathSnapshot {
+                path: self.path.clone(),
+                constraints: self.path_constraints.iter().map(|(_, constraint)| constraint.clone()).collect(),
+            })
+        };
+        let mut backtrack_points = self.backtrack_points.borrow_mut();
+        backtrack_points.push(BacktrackPoint {
+            alternatives,
             stack: self.stack.clone(),
-            constraint,
             varmap: self.varmap.clone(),
             mem: self.mem.borrow().clone(),
+            shadow: self.shadow.as_ref().map(|shadow| shadow.borrow().clone()),
             path_len: self.path.len(),
+            known_constants: self.known_constants.borrow().clone(),
+            stack_usage: self.stack_usage,
+            max_stack_usage: self.max_stack_usage,
+            dynamic_stack_usage: self.dynamic_stack_usage.clone(),
+            max_dynamic_stack_usage: self.max_dynamic_stack_usage.clone(),
+            path_conditions: self.path_conditions.clone(),
+            path_notes: self.path_notes.clone(),
+            handles: self.handles.clone(),
+            freed: self.freed.clone(),
+            heap_allocations: self.heap_allocations.clone(),
+            stack_allocations: self.stack_allocations.clone(),
+            path_allocation_ranges: {
+                let mut ranges = self.path_allocation_ranges.clone();
+                if let Some(last) = ranges.last_mut() {
+                    last.end = self.alloc.next_address();
+                }
+                ranges
+            },
+            loop_iterations: self.loop_iterations.clone(),
+            branch_from,
+            clocks: self.clocks.clone(),
+            global_ctor: self.global_ctor,
+            guard,
+            snapshot,
         });
     }
 
+    /// With `Checkpointing::AssumptionLiterals`, start guarding the constraints
+    /// on the current path with a fresh literal, as a backtracking point is
+    /// being saved. Returns the literal which guarded them until now.
+    fn start_path_guard(&self) -> Option<B::BV> {
+        let literal = self.new_path_guard();
+        let mut guards = self.guards.borrow_mut();
+        let parent = guards.current.take();
+        match &parent {
+            None => {
+                // from now on, the current path's constraints go in their own level
+                self.push_solver_frame();
+                guards.level = self.solver_depth.get();
+            },
+            Some(parent) => {
+                // `parent` is still asserted, so this only matters once it
+                // isn't, i.e., when resuming a point saved under `literal`
+                guards.shared = guards.pending.len();
+                guards.pending.push(literal.implies(parent));
+            },
+        }
+        guards.current = Some(literal);
+        guards.active = false;
+        parent
+    }
+
+    /// With `Checkpointing::AssumptionLiterals`, replace the current path's
+    /// level of the solver's assertion stack by one for a path resumed from a
+    /// backtracking point saved under `parent`
+    fn resume_path_guard(&self, parent: Option<&B::BV>) -> Result<()> {
+        let literal = self.new_path_guard();
+        let mut guards = self.guards.borrow_mut();
+        // this also drops anything asserted directly on the abandoned path
+        self.pop_solver_frame();
+        for guarded in &guards.pending[.. guards.shared] {
+            guarded.assert()?;
+        }
+        if let Some(parent) = parent {
+            literal.implies(parent).assert()?;
+        }
+        self.push_solver_frame();
+        literal.assert()?;
+        *guards = PathGuards {
+            current: Some(literal),
+            active: true,
+            pending: Vec::new(),
+            shared: 0,
+            level: self.solver_depth.get(),
+            created: guards.created,
+        };
+        Ok(())
+    }
+
+    /// A fresh literal for `PathGuards`
+    fn new_path_guard(&self) -> B::BV {
+        let mut guards = self.guards.borrow_mut();
+        guards.created += 1;
+        B::BV::new(self.solver.clone(), 1, Some(&format!("path_guard_{}", guards.created)))
+    }
+
     /// returns `Ok(true)` if the operation was successful, `Ok(false)` if there are
     /// no saved backtracking points, or `Err` for other errors
+    ///
+    /// Which backtracking point is reverted to depends on
+    /// [`Config.backtracking_order`](config/struct.Config.html#structfield.backtracking_order).
     pub fn revert_to_backtracking_point(&mut self) -> Result<bool> {
-        if let Some(bp) = self.backtrack_points.borrow_mut().pop() {
+        if self.config.backtracking_order != BacktrackingOrder::DepthFirst {
+            return self.revert_to_backtracking_point_out_of_order();
+        }
+        let bp = self.backtrack_points.borrow_mut().pop();
+        if let Some(mut bp) = bp {
             debug!("Reverting to backtracking point {}", bp);
-            self.solver.pop(1);
-            self.varmap = bp.varmap;
-            self.mem.replace(bp.mem);
-            self.stack = bp.stack;
+            if self.assumption_literals {
+                self.resume_path_guard(bp.guard.as_ref())?;
+            } else {
+                self.pop_solver_frame();
+            }
+            self.shared_depth = self.backtrack_points.borrow().len();
+            let shared_depth = self.shared_depth;
+            self.path_constraints.retain(|(depth, _)| *depth <= shared_depth);
+            if self.known_unsat_depth.is_some_and(|depth| depth > shared_depth) {
+                self.known_unsat_depth = None;
+            }
+            let (loc, constraint) = bp.alternatives.pop().expect("BacktrackPoint should have at least one alternative");
+            if !bp.alternatives.is_empty() {
+                // keep the point, and its solver frame, for the other alternatives
+                if !self.assumption_literals {
+                    self.push_solver_frame();
+                }
+                self.backtrack_points.borrow_mut().push(bp.clone());
+            }
             self.path.truncate(bp.path_len);
-            self.cur_loc = bp.loc;
-            bp.constraint.assert()?;
+            self.resume_backtracking_point(bp, loc, constraint)?;
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
-    /// returns the number of saved backtracking points
+    /// `revert_to_backtracking_point()` for orders other than `DepthFirst`,
+    /// where the point reverted to may not be the most recently saved one.
+    /// Rather than each point holding a level of the solver's assertion
+    /// stack, the current path's level is replaced by one with the
+    /// constraints of the resumed point's path (or, with
+    /// `Checkpointing::AssumptionLiterals`, the literal guarding them).
+    fn revert_to_backtracking_point_out_of_order(&mut self) -> Result<bool> {
+        let idx = match self.choose_backtracking_point() {
+            Some(idx) => idx,
+            None => return Ok(false),
+        };
+        let (bp, loc, constraint) = {
+            let mut backtrack_points = self.backtrack_points.borrow_mut();
+            debug!("Reverting to backtracking point {}", backtrack_points[idx]);
+            let (loc, constraint) = backtrack_points[idx].alternatives.pop().expect("BacktrackPoint should have at least one alternative");
+            let bp = if backtrack_points[idx].alternatives.is_empty() {
+                backtrack_points.remove(idx)
+            } else {
+                backtrack_points[idx].clone()
+            };
+            (bp, loc, constraint)
+        };
+        let snapshot = bp.snapshot.clone().expect("BacktrackPoint should have a snapshot when not reverting depth-first");
+        if self.assumption_literals {
+            self.resume_path_guard(bp.guard.as_ref())?;
+        } else {
+            let base_constraints_len = self.base_constraints_len.get().expect("base_constraints_len should be set once a BacktrackPoint is saved");
+            self.pop_solver_frame();
+            self.push_solver_frame();
+            for constraint in &snapshot.constraints[base_constraints_len ..] {
+                constraint.assert()?;
+            }
+        }
+        self.shared_depth = 0;
+        self.known_unsat_depth = if self.config.simplify_constraints && snapshot.constraints.iter().any(|c| c.as_bool() == Some(false)) {
+            Some(0)
+        } else {
+            None
+        };
+        self.path_constraints = snapshot.constraints.into_iter().map(|constraint| (0, constraint)).collect();
+        self.path = snapshot.path;
+        self.resume_backtracking_point(bp, loc, constraint)?;
+        Ok(true)
+    }
+
+    /// Choose which pending backtracking point to revert to next, according
+    /// to the `Config.backtracking_order`, returning its index in
+    /// `backtrack_points`
+    fn choose_backtracking_point(&self) -> Option<usize> {
+        let backtrack_points = self.backtrack_points.borrow();
+        if backtrack_points.is_empty() {
+            return None;
+        }
+        Some(match self.config.backtracking_order {
+            BacktrackingOrder::DepthFirst => backtrack_points.len() - 1,
+            BacktrackingOrder::BreadthFirst => 0,
+            BacktrackingOrder::Random => (self.next_random() % backtrack_points.len() as u64) as usize,
+            BacktrackingOrder::FewestConstraintsFirst => backtrack_points.iter().enumerate().rev()
+                .min_by_key(|(_, bp)| bp.snapshot.as_ref().map_or(0, |snapshot| snapshot.constraints.len()))
+                .map(|(idx, _)| idx)
+                .unwrap(),
+        })
+    }
+
+    /// The next number from the random number generator for
+    /// `BacktrackingOrder::Random` (SplitMix64)
+    fn next_random(&self) -> u64 {
+        let state = self.rng_state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        self.rng_state.set(state);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Restore the parts of the `State` which are saved in every
+    /// `BacktrackPoint`, and continue from the given alternative
+    fn resume_backtracking_point(&mut self, bp: BacktrackPoint<'p, B>, loc: Location<'p>, constraint: B::BV) -> Result<()> {
+        self.varmap = bp.varmap;
+        self.mem.replace(bp.mem);
+        if let (Some(shadow), Some(saved)) = (&self.shadow, bp.shadow) {
+            shadow.replace(saved);
+        }
+        self.stack = bp.stack;
+        self.cur_loc = loc;
+        self.stack_usage = bp.stack_usage;
+        self.max_stack_usage = bp.max_stack_usage;
+        self.dynamic_stack_usage = bp.dynamic_stack_usage;
+        self.max_dynamic_stack_usage = bp.max_dynamic_stack_usage;
+        self.known_constants.replace(bp.known_constants);
+        self.path_conditions = bp.path_conditions;
+        self.path_notes = bp.path_notes;
+        self.handles = bp.handles;
+        self.freed = bp.freed;
+        self.heap_allocations = bp.heap_allocations;
+        self.stack_allocations = bp.stack_allocations;
+        // allocations made since the `BacktrackPoint` belong to abandoned paths
+        self.path_allocation_ranges = bp.path_allocation_ranges;
+        let next_address = self.alloc.next_address();
+        match self.path_allocation_ranges.last_mut() {
+            Some(last) if last.end == next_address => last.end = u64::MAX,
+            _ => self.path_allocation_ranges.push(next_address .. u64::MAX),
+        }
+        self.loop_iterations = bp.loop_iterations;
+        self.clocks = bp.clocks;
+        self.global_ctor = bp.global_ctor;
+        self.reverts += 1;
+        if self.assert_constraint(ConstraintOrigin::BranchRefinement, &constraint)? {
+            self.note_condition(&constraint, true);
+        }
+        match bp.branch_from {
+            Some(from) => self.count_loop_iteration(from),
+            None => Ok(()),
+        }
+    }
+
+    /// returns the number of saved backtracking points, counting each
+    /// alternative of a point saved with `save_backtracking_points()`
+    /// separately
     pub fn count_backtracking_points(&self) -> usize {
-        self.backtrack_points.borrow().len()
+        self.backtrack_points.borrow().iter().map(|bp| bp.alternatives.len()).sum()
     }
 
     /// returns a `String` containing a formatted view of the current backtrace
@@ -1461,6 +3747,37 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
         locdescr.funcname = self.demangle(&locdescr.funcname);
     }
 
+    /// Describe where the given `BV` came from: the LLVM value it's currently
+    /// bound to, and the instruction which produces that value. Returns `None`
+    /// if the `BV` isn't bound to any LLVM value (e.g., it's a subexpression,
+    /// or its value has since been superseded by a newer version).
+    pub fn describe_var(&self, bv: &B::BV) -> Option<VarOrigin<'p>> {
+        let (funcname, name) = self.varmap.find_var(bv)?;
+        let loc = self.project.get_func_by_name(funcname).and_then(|(func, module)| {
+            func.basic_blocks.iter().find_map(|bb| {
+                let (instr, source_loc) = match bb.instrs.iter().position(|inst| inst.try_get_result() == Some(name)) {
+                    Some(i) => (BBInstrIndex::Instr(i), bb.instrs[i].get_debug_loc().as_ref()),
+                    None => match &bb.term {
+                        Terminator::Invoke(invoke) if &invoke.result == name => (BBInstrIndex::Terminator, invoke.debugloc.as_ref()),
+                        _ => return None,
+                    },
+                };
+                Some(LocationDescription {
+                    modname: module.name.clone(),
+                    funcname: func.name.clone(),
+                    bbname: bb.name.clone(),
+                    instr,
+                    source_loc,
+                })
+            })
+        });
+        Some(VarOrigin {
+            funcname: funcname.clone(),
+            name: name.clone(),
+            loc,
+        })
+    }
+
     /// Get the most recent `BV` created for each `Name` in the current function.
     /// Returns pairs of the `Name` and the `BV` assigned to that `Name`.
     ///
@@ -1472,7 +3789,7 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
     /// returns a `String` describing a set of satisfying assignments for all variables
     pub fn current_assignments_as_pretty_string(&self) -> Result<String> {
         self.solver.set_opt(BtorOption::ModelGen(ModelGen::All));
-        let string = if self.sat()? {
+        let string = if self.sat_for_model()? {
             let printed = self.solver.print_model();
             let sorted = itertools::sorted(printed.lines());
             sorted.fold(String::new(), |s, line| s + "\n" + line)
@@ -1520,7 +3837,10 @@ impl<'p, B: Backend> State<'p, B> where B: 'p {
             err_msg.push_str("\nLatest values of variables at time of error, in current function:\n");
             err_msg.push_str("(Ignore any values from past the point of error, they may be from other paths)\n\n");
             for (varname, value) in self.all_vars_in_cur_fn() {
-                err_msg.push_str(&format!("  {}: {:?}\n", varname, value));
+                match self.get_tag(value) {
+                    Some(tag) => err_msg.push_str(&format!("  {}: {:?} [{}]\n", varname, value, tag)),
+                    None => err_msg.push_str(&format!("  {}: {:?}\n", varname, value)),
+                }
             }
         } else {
             err_msg.push_str("\nnote: For a dump of variable values at time of error, rerun with `HAYBALE_DUMP_VARS=1` environment variable.\n");
@@ -1562,70 +3882,393 @@ impl PathDumpType {
            },
         }
     }
-}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::function_hooks::IsCall;
+    use crate::return_value::ReturnValue;
+    use crate::solver_utils::SolutionCount;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::types::FPType;
+
+    // we don't include tests here for Memory, Alloc, VarMap, or Watchpoints; those are tested in their own modules.
+    // Instead, here we just test the nontrivial functionality that `State` has itself.
+    // We do repeat many of the tests from the `solver_utils` module, making sure that they also pass when
+    // we use the `State` interfaces.
+
+    #[test]
+    fn sat() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        // empty state should be sat
+        assert_eq!(state.sat(), Ok(true));
+
+        // adding True constraint should still be sat
+        state.bv_from_bool(true).assert();
+        assert_eq!(state.sat(), Ok(true));
+
+        // adding x > 0 constraint should still be sat
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        x.sgt(&state.zero(64)).assert();
+        assert_eq!(state.sat(), Ok(true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn describe_pointers() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        // `char buf[16]`, as if pointed to by a parameter named `buf`
+        let buf = state.allocate(16 * 8_u64);
+        state.label_allocation(&buf, "buf", Some(Type::ArrayType { element_type: Box::new(Type::i8()), num_elements: 16 }))?;
+        let buf = buf.as_u64().unwrap();
+        let target = state.describe_pointer(buf + 5).expect("Expected &buf[5] to be described");
+        assert_eq!(target, PointerTarget { allocation: "buf".to_owned(), offset: 5, field_path: Some("[5]".to_owned()) });
+        assert_eq!(crate::SolutionValue::PtrInto(target).to_string(), "buf + 5 (buf[5])");
+        assert_eq!(state.describe_pointer(buf).unwrap().to_string(), "buf (buf[0])");
+        // one past the end
+        let end = state.describe_pointer(buf + 16).expect("Expected a pointer one past the end to be described");
+        assert_eq!((end.offset, end.field_path), (16, None));
+
+        // `struct { int a; short b[4]; }`, unlabeled
+        let s = state.allocate(96_u64);
+        assert_eq!(state.describe_pointer(s.as_u64().unwrap() + 8), None);
+        let struct_ty = Type::StructType { element_types: vec![Type::i32(), Type::ArrayType { element_type: Box::new(Type::i16()), num_elements: 4 }], is_packed: false };
+        state.label_allocation(&s, "s", Some(struct_ty))?;
+        assert_eq!(state.describe_pointer(s.as_u64().unwrap() + 8).unwrap().to_string(), "s + 8 (s.1[2])");
+
+        // addresses outside of any allocation
+        assert_eq!(state.describe_pointer(0x10), None);
+        Ok(())
+    }
+
+    /// a `State` for `test_func` in the given `Project`, with the given `Config`
+    fn state_with_config<'p>(project: &'p Project, config: Config<'p, BtorBackend>) -> State<'p, BtorBackend> {
+        let (func, module) = project.get_func_by_name("test_func").expect("Failed to find function");
+        let start_loc = Location {
+            module,
+            func,
+            bb: &func.basic_blocks[0],
+            instr: BBInstrIndex::Instr(0),
+            source_loc: None,
+        };
+        State::new(project, start_loc, config)
+    }
+
+    #[test]
+    fn firmware_address_ranges() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let config = Config {
+            invalid_address_ranges: vec![Range { start: 0, end: 0x2000_0000 }],
+            mapped_address_ranges: vec![Range { start: 0, end: 0x100 }],
+            ..Config::default()
+        };
+        let mut state = state_with_config(&project, config);
+
+        // the vector table at 0x0 - 0xFF is mapped, so accesses there are fine
+        state.write(&state.zero(64), state.bv_from_u32(0x2000_1000, 32))?;
+        assert_eq!(state.read(&state.zero(64), 32)?.as_u64(), Some(0x2000_1000));
+        state.read(&state.bv_from_u64(0xFC, 64), 32)?;
+        let addr = state.new_bv_with_name(Name::from("addr"), 64)?;
+        addr.ult(&state.bv_from_u64(0x100, 64)).assert();
+        state.read(&addr, 8)?;
+
+        // but the rest of the invalid range isn't
+        match state.read(&state.bv_from_u64(0x100, 64), 8) {
+            Err(Error::NullPointerDereference) => {},
+            r => panic!("Expected a NullPointerDereference, got {:?}", r),
+        }
+        let addr = state.new_bv_with_name(Name::from("addr2"), 64)?;
+        addr.ult(&state.bv_from_u64(0x200, 64)).assert();
+        match state.read(&addr, 8) {
+            Err(Error::NullPointerDereference) => {},
+            r => panic!("Expected a NullPointerDereference, got {:?}", r),
+        }
+
+        // and we never allocate in either kind of range
+        let allocated = state.allocate(64_u64).as_u64().unwrap();
+        assert!(allocated >= 0x2000_0000);
+        Ok(())
+    }
+
+    #[test]
+    fn no_invalid_address_ranges() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let config = Config {
+            invalid_address_ranges: vec![],
+            ..Config::default()
+        };
+        let mut state = state_with_config(&project, config);
+
+        // an address which may be `NULL` is no longer reported
+        let addr = state.new_bv_with_name(Name::from("addr"), 64)?;
+        state.read(&addr, 8)?;
+        // but a literal `NULL` still is
+        match state.read(&state.zero(64), 8) {
+            Err(Error::NullPointerDereference) => {},
+            r => panic!("Expected a NullPointerDereference, got {:?}", r),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_constant_is_an_error() {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let state = blank_state(&project, "test_func");
+
+        // a constant we don't support should produce an `Err`, not a panic
+        match state.const_to_bv(&Constant::BlockAddress) {
+            Err(Error::UnsupportedInstruction(_)) => {},
+            res => panic!("Expected an UnsupportedInstruction error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn unsat() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let state = blank_state(&project, "test_func");
+
+        // adding False constraint should be unsat
+        state.bv_from_bool(false).assert();
+        assert_eq!(state.sat(), Ok(false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unsat_with_extra_constraints() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        // adding x > 3 constraint should still be sat
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        x.ugt(&state.bv_from_u64(3, 64)).assert();
+        assert_eq!(state.sat(), Ok(true));
+
+        // adding x < 3 constraint should make us unsat
+        let bad_constraint = x.ult(&state.bv_from_u64(3, 64));
+        assert_eq!(state.sat_with_extra_constraints(std::iter::once(&bad_constraint)), Ok(false));
+
+        // the state itself should still be sat, extra constraints weren't permanently added
+        assert_eq!(state.sat(), Ok(true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_distinguishes_unsat() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        x.ugt(&state.bv_from_u64(3, 64)).assert();
+        assert_eq!(state.check(), SolverResult::Sat);
+
+        let bad_constraint = x.ult(&state.bv_from_u64(3, 64));
+        assert_eq!(state.check_with_extra_constraints(std::iter::once(&bad_constraint))?, SolverResult::Unsat);
+        assert_eq!(state.check(), SolverResult::Sat);
+
+        Ok(())
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::solver_utils::SolutionCount;
-    use crate::test_utils::*;
+    #[test]
+    fn extra_constraints_from_arrays_and_iterators() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
 
-    // we don't include tests here for Memory, Alloc, VarMap, or Watchpoints; those are tested in their own modules.
-    // Instead, here we just test the nontrivial functionality that `State` has itself.
-    // We do repeat many of the tests from the `solver_utils` module, making sure that they also pass when
-    // we use the `State` interfaces.
+        let params = [
+            state.new_bv_with_name(Name::from("a"), 64)?,
+            state.new_bv_with_name(Name::from("b"), 64)?,
+            state.new_bv_with_name(Name::from("c"), 64)?,
+        ];
+        let above_3: Vec<_> = params.iter().map(|p| (p, p.ugt(&state.bv_from_u64(3, 64)))).collect();
+        let below_3 = params[0].ult(&state.bv_from_u64(3, 64));
+
+        assert_eq!(state.check_with_extra_constraints([&above_3[0].1, &above_3[1].1])?, SolverResult::Sat);
+        assert_eq!(state.check_with_extra_constraints([&above_3[0].1, &below_3])?, SolverResult::Unsat);
+        assert_eq!(state.check_with_extra_constraints(above_3.iter().map(|(_, c)| c))?, SolverResult::Sat);
+        assert_eq!(state.check_with_extra_constraints(above_3.iter().map(|(_, c)| c).chain(std::iter::once(&below_3)))?, SolverResult::Unsat);
+        assert!(state.sat_with_extra_constraints(above_3.iter().skip(1).map(|(_, c)| c).chain(Some(&below_3)))?);
+        assert!(!state.with_temporary_constraints(above_3.iter().map(|(_, c)| c), |state| state.bvs_can_be_equal(&params[0], &state.bv_from_u64(3, 64)))??);
+        Ok(())
+    }
 
     #[test]
-    fn sat() -> Result<()> {
-        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+    fn solver_stats() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("bb_start"), Name::from("bb_target")]);
         let project = blank_project("test_mod", func);
         let mut state = blank_state(&project, "test_func");
+        state.record_path_entry();
+        assert_eq!(state.solver_stats(), SolverStats::default());
 
-        // empty state should be sat
-        assert_eq!(state.sat(), Ok(true));
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        state.assert_constraint(ConstraintOrigin::Assume, &x.ugt(&state.bv_from_u64(3, 64)))?;
+        assert!(state.sat()?);
+        let below_3 = x.ult(&state.bv_from_u64(3, 64));
+        assert_eq!(state.check_with_extra_constraints(std::iter::once(&below_3))?, SolverResult::Unsat);
+        assert!(!state.bvs_must_be_equal(&x, &state.bv_from_u64(4, 64))?);
+        state.save_backtracking_point(&Name::from("bb_target"), x._eq(&state.bv_from_u64(7, 64)));
+        state.assert_constraint(ConstraintOrigin::Assume, &x.ult(&state.bv_from_u64(10, 64)))?;
+        assert!(state.revert_to_backtracking_point()?);
+
+        let stats = state.solver_stats();
+        assert_eq!(stats.checks, 3);
+        assert_eq!(stats.checks, state.solver_query_count());
+        assert_eq!((stats.sat, stats.unsat, stats.unknown), (2, 1, 0));
+        // two for the queries with extra constraints, and the current path's
+        // level, which is replaced on reverting
+        assert_eq!((stats.pushes, stats.pops), (4, 3));
+        assert_eq!(stats.peak_assertion_depth, 1);
+        assert_eq!(stats.peak_path_constraints, 2);
+        assert!(stats.solver_time > std::time::Duration::from_secs(0));
+        Ok(())
+    }
 
-        // adding True constraint should still be sat
-        state.bv_from_bool(true).assert();
-        assert_eq!(state.sat(), Ok(true));
+    #[test]
+    fn simplified_true_constraint_not_asserted() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let config = Config {
+            simplify_constraints: true,
+            ..Config::default()
+        };
+        let mut state = state_with_config(&project, config);
 
-        // adding x > 0 constraint should still be sat
         let x = state.new_bv_with_name(Name::from("x"), 64)?;
-        x.sgt(&state.zero(64)).assert();
-        assert_eq!(state.sat(), Ok(true));
+        state.assert_constraint(ConstraintOrigin::Assume, &x._eq(&x))?;
+        assert!(state.path_constraints().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn simplified_false_constraint_skips_solver() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("bb_start"), Name::from("bb_target")]);
+        let project = blank_project("test_mod", func);
+        let config = Config {
+            simplify_constraints: true,
+            ..Config::default()
+        };
+        let mut state = state_with_config(&project, config);
+        state.record_path_entry();
 
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        state.save_backtracking_point(&Name::from("bb_target"), x.ugt(&state.bv_from_u64(3, 64)));
+        state.assert_constraint(ConstraintOrigin::Assume, &x._ne(&x))?;
+        let queries = state.solver_query_count();
+        assert_eq!(state.check(), SolverResult::Unsat);
+        assert!(!state.sat()?);
+        assert_eq!(state.solver_query_count(), queries);
+
+        // backtracking past the constraint makes the solver responsible again
+        assert!(state.revert_to_backtracking_point()?);
+        assert_eq!(state.check(), SolverResult::Sat);
+        assert_eq!(state.solver_query_count(), queries + 1);
         Ok(())
     }
 
     #[test]
-    fn unsat() -> Result<()> {
+    fn cached_solver_results() -> Result<()> {
         let func = blank_function("test_func", vec![Name::from("test_bb")]);
         let project = blank_project("test_mod", func);
-        let state = blank_state(&project, "test_func");
-
-        // adding False constraint should be unsat
-        state.bv_from_bool(false).assert();
-        assert_eq!(state.sat(), Ok(false));
+        let config = Config {
+            cache_solver_results: true,
+            ..Config::default()
+        };
+        let mut state = state_with_config(&project, config);
 
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        state.assert_constraint(ConstraintOrigin::Assume, &x.ugt(&state.bv_from_u64(3, 64)))?;
+        assert_eq!(state.check(), SolverResult::Sat);
+        assert_eq!(state.check(), SolverResult::Sat);
+        assert!(state.sat()?);
+        assert_eq!((state.solver_stats().checks, state.solver_stats().cache_hits), (1, 2));
+
+        let below_3 = x.ult(&state.bv_from_u64(3, 64));
+        let below_5 = x.ult(&state.bv_from_u64(5, 64));
+        assert_eq!(state.check_with_extra_constraints(vec![&below_3])?, SolverResult::Unsat);
+        assert_eq!(state.check_with_extra_constraints(vec![&below_5])?, SolverResult::Sat);
+        assert!(!state.sat_with_extra_constraints(vec![&below_3])?);
+        assert!(state.sat_with_extra_constraints(vec![&below_5, &below_5])?);
+        assert_eq!((state.solver_stats().checks, state.solver_stats().cache_hits), (3, 4));
+
+        // asserting a constraint invalidates the cache
+        state.assert_constraint(ConstraintOrigin::Assume, &below_5)?;
+        assert_eq!(state.check(), SolverResult::Sat);
+        assert_eq!(state.check_with_extra_constraints(vec![&x.ugt(&state.bv_from_u64(4, 64))])?, SolverResult::Unsat);
+        assert_eq!((state.solver_stats().checks, state.solver_stats().cache_hits), (5, 4));
+
+        // results under temporary constraints are forgotten with them
+        let is_4 = x._eq(&state.bv_from_u64(4, 64));
+        assert!(!state.with_temporary_constraints(vec![&is_4.not()], |state| state.sat())??);
+        assert!(state.sat()?);
         Ok(())
     }
 
     #[test]
-    fn unsat_with_extra_constraints() -> Result<()> {
+    fn solutions_need_a_satisfiable_state() -> Result<()> {
         let func = blank_function("test_func", vec![Name::from("test_bb")]);
         let project = blank_project("test_mod", func);
-        let mut state = blank_state(&project, "test_func");
+        let config = Config {
+            cache_solver_results: true,
+            ..Config::default()
+        };
+        let mut state = state_with_config(&project, config);
 
-        // adding x > 3 constraint should still be sat
+        // a cached `sat()` result still leaves the solver to produce a model
         let x = state.new_bv_with_name(Name::from("x"), 64)?;
-        x.ugt(&state.bv_from_u64(3, 64)).assert();
-        assert_eq!(state.sat(), Ok(true));
+        state.assert_constraint(ConstraintOrigin::Assume, &x._eq(&state.bv_from_u64(7, 64)))?;
+        assert!(state.sat()?);
+        assert!(state.sat()?);
+        assert_eq!(state.get_a_u64_solution_for_bv(&x)?, Some(7));
+        assert_ne!(state.current_assignments_as_pretty_string()?, "<state is unsatisfiable>");
+
+        // once unsat, there's no model to read
+        state.assert_constraint(ConstraintOrigin::Assume, &x._eq(&state.bv_from_u64(8, 64)))?;
+        assert!(!state.sat()?);
+        assert_eq!(state.get_a_solution_for_bv(&x)?, None);
+        assert_eq!(state.eval_bv(&x)?, Evaluated::NoModel);
+        assert_eq!(state.current_assignments_as_pretty_string()?, "<state is unsatisfiable>");
+        Ok(())
+    }
 
-        // adding x < 3 constraint should make us unsat
-        let bad_constraint = x.ult(&state.bv_from_u64(3, 64));
-        assert_eq!(state.sat_with_extra_constraints(std::iter::once(&bad_constraint)), Ok(false));
+    #[test]
+    fn check_reports_unknown_on_timeout() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+        state.solver.set_opt(BtorOption::SolverTimeout(Some(std::time::Duration::from_millis(1))));
 
-        // the state itself should still be sat, extra constraints weren't permanently added
-        assert_eq!(state.sat(), Ok(true));
+        // factoring a 64-bit number is far more than 1ms of work
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        let y = state.new_bv_with_name(Name::from("y"), 64)?;
+        let one = state.one(64);
+        let hard = x.mul(&y)._eq(&state.bv_from_u64(0xc6b4_db3f_2c8d_1b65, 64))
+            .and(&x.ugt(&one))
+            .and(&y.ugt(&one))
+            .and(&x.ult(&state.bv_from_u64(1 << 32, 64)))
+            .and(&y.ult(&state.bv_from_u64(1 << 32, 64)));
+        assert_eq!(state.check_with_extra_constraints(std::iter::once(&hard))?, SolverResult::Unknown);
+        // the plain `sat()` reports this as an error
+        match state.sat_with_extra_constraints(std::iter::once(&hard)) {
+            Err(Error::SolverError(_)) => {},
+            res => panic!("Expected a SolverError, got {:?}", res),
+        }
 
         Ok(())
     }
@@ -1647,6 +4290,149 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn get_a_u64_solution_unconstrained() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        // x is completely unconstrained; we should still get some solution
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        assert!(state.get_a_u64_solution_for_bv(&x)?.is_some());
+        let b = state.new_bv_with_name(Name::from("b"), 1)?;
+        assert!(state.get_a_bool_solution_for_bv(&b)?.is_some());
+
+        // wrong widths are errors, not panics
+        let wide = state.new_bv_with_name(Name::from("wide"), 128)?;
+        assert!(state.get_a_u64_solution_for_bv(&wide).is_err());
+        assert!(state.get_a_bool_solution_for_bv(&x).is_err());
+
+        // and unsat constraints give `None`
+        x.ugt(&state.bv_from_u64(3, 64)).assert();
+        x.ult(&state.bv_from_u64(3, 64)).assert();
+        assert_eq!(state.get_a_u64_solution_for_bv(&x)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_bytes_from_model() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        // constrain a 4-byte buffer to spell a known pattern
+        let buf = state.allocate(32_u64);
+        let contents = state.new_bv_with_name(Name::from("contents"), 32)?;
+        state.write(&buf, contents.clone())?;
+        contents._eq(&state.bv_from_u32(u32::from_le_bytes(*b"hay!"), 32)).assert();
+        assert_eq!(state.get_bytes_from_model(&buf, 4)?, Some(b"hay!".to_vec()));
+
+        // a symbolic address is evaluated along with the bytes it points to
+        let addr = state.new_bv_with_name(Name::from("addr"), 64)?;
+        addr._eq(&buf.add(&state.bv_from_u64(1, 64))).assert();
+        assert_eq!(state.get_bytes_from_model(&addr, 3)?, Some(b"ay!".to_vec()));
+
+        // once the constraints are unsat, there is no model
+        contents._eq(&state.zero(32)).assert();
+        assert_eq!(state.get_bytes_from_model(&buf, 4)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cstrings() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        let buf = state.allocate(64_u64);
+        state.write_cstring(&buf, "hi")?;
+        assert_eq!(state.read(&buf, 24)?.as_u64(), Some(0x00_69_68));
+        assert_eq!(state.read_cstring_from_model(&buf, 8)?, Some((b"hi".to_vec(), "hi".to_owned())));
+        // without a NUL in the first `max_len` bytes, the string is cut off there
+        assert_eq!(state.read_cstring_from_model(&buf, 1)?, Some((b"h".to_vec(), "h".to_owned())));
+
+        // bytes which aren't UTF-8 are kept, but replaced in the `String`
+        state.write(&buf, state.bv_from_u32(0x00_ff_61, 24))?;
+        assert_eq!(state.read_cstring_from_model(&buf, 8)?, Some((vec![0x61, 0xff], "a\u{fffd}".to_owned())));
+        Ok(())
+    }
+
+    /// `strcmp()` for strings of fewer than 8 bytes: `0` if they're equal,
+    /// otherwise `1`
+    fn strcmp_hook<'p>(_proj: &'p Project, state: &mut State<'p, BtorBackend>, call: &'p dyn IsCall) -> Result<ReturnValue<<BtorBackend as Backend>::BV>> {
+        let a = state.operand_to_bv(&call.get_arguments()[0].0)?;
+        let b = state.operand_to_bv(&call.get_arguments()[1].0)?;
+        // each byte has to match, up to and including the first NUL
+        let mut equal = state.bv_from_bool(true);
+        let mut ended = state.bv_from_bool(false);
+        for i in 0 .. 8 {
+            let offset = state.bv_from_u64(i, 64);
+            let (x, y) = (state.read(&a.add(&offset), 8)?, state.read(&b.add(&offset), 8)?);
+            equal = equal.and(&ended.or(&x._eq(&y)));
+            ended = ended.or(&x._eq(&state.zero(8)));
+        }
+        Ok(ReturnValue::Return(equal.cond_bv(&state.zero(32), &state.bv_from_u32(1, 32))))
+    }
+
+    /// `int f() { char buf[8]; return strcmp(buf, "abc"); }`
+    fn strcmp_with_uninitialized_buffer() -> Project {
+        let alloca = |name: &str, bytes: u64| alloca(Type::ArrayType { element_type: Box::new(Type::i8()), num_elements: bytes as usize }, int_const(32, 1), name);
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            alloca("buf", 8),
+            alloca("abc", 4),
+            store(local("abc", Type::pointer_to(Type::i32())), int_const(32, u64::from(u32::from_le_bytes(*b"abc\0")))),
+            call("strcmp", Type::i32(), vec![local("buf", i8_ptr.clone()), local("abc", i8_ptr)], Some("cmp")),
+        ];
+        func.basic_blocks[0].term = ret(local("cmp", Type::i32()));
+        blank_project("test_mod", func)
+    }
+
+    #[test]
+    fn cstring_from_strcmp() -> Result<()> {
+        let project = strcmp_with_uninitialized_buffer();
+        let mut config = Config::default();
+        config.function_hooks.add("strcmp", &strcmp_hook);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        let cmp = match em.next() {
+            Some(Ok(ReturnValue::Return(cmp))) => cmp,
+            other => panic!("Expected a return value, got {:?}", other),
+        };
+        let state = em.mut_state();
+        cmp._eq(&state.zero(32)).assert();
+        let buf = state.get_a_solution_for_irname(&"f".to_owned(), &Name::from("buf"))?.unwrap().as_u64().unwrap();
+        let buf = state.bv_from_u64(buf, 64);
+        assert_eq!(state.read_cstring_from_model(&buf, 8)?, Some((b"abc".to_vec(), "abc".to_owned())));
+        Ok(())
+    }
+
+    #[test]
+    fn eval_bv() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        // constrain x == 5; x should evaluate to exactly 5
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        x._eq(&state.bv_from_u64(5, 64)).assert();
+        assert_eq!(state.eval_bv(&x)?, Evaluated::Concrete(BVSolution::from_01x_str(format!("{:064b}", 5))));
+
+        // y appears in no constraint, so the model leaves every bit of it a don't-care
+        let y = state.new_bv_with_name(Name::from("y"), 64)?;
+        assert_eq!(state.eval_bv(&y)?, Evaluated::DontCare);
+        assert_eq!(state.get_a_u64_solution_for_bv(&y)?, Some(0));
+
+        // once the constraints are unsat, there is no model
+        x._eq(&state.bv_from_u64(6, 64)).assert();
+        assert_eq!(state.eval_bv(&x)?, Evaluated::NoModel);
+
+        Ok(())
+    }
+
     #[test]
     fn possible_solutions() -> Result<()> {
         let func = blank_function("test_func", vec![Name::from("test_bb")]);
@@ -1706,6 +4492,106 @@ mod tests {
         assert_eq!(state.operand_to_bv(&op2), Ok(var2));
     }
 
+    fn global(name: &str, ty: Type, initializer: Constant) -> module::GlobalVariable {
+        module::GlobalVariable {
+            linkage: module::Linkage::Internal,
+            alignment: 8,
+            ..global_var(name, Type::pointer_to(ty), Some(initializer))
+        }
+    }
+
+    /// A `Project` with the given global variables, and a blank `test_func`
+    fn project_with_test_func(global_vars: Vec<module::GlobalVariable>) -> Project {
+        project_with_globals("test_mod", vec![blank_function("test_func", vec![Name::from("test_bb")])], global_vars)
+    }
+
+    fn global_ref(name: &str, ty: Type) -> Constant {
+        Constant::GlobalReference { name: Name::from(name), ty: Type::pointer_to(ty) }
+    }
+
+    #[test]
+    fn global_initializers() -> Result<()> {
+        let pair_ty = Type::StructType { element_types: vec![Type::i32(), Type::i32()], is_packed: false };
+        let ptr_ty = Type::pointer_to(Type::i8());
+        let project = project_with_test_func(vec![
+            // `struct { int a, b; } pair = { 1, 2 }; int *second = &pair.b;`
+            global("pair", pair_ty.clone(), Constant::Struct {
+                name: None,
+                values: vec![Constant::Int { bits: 32, value: 1 }, Constant::Int { bits: 32, value: 2 }],
+                is_packed: false,
+            }),
+            global("second", Type::pointer_to(Type::i32()), Constant::GetElementPtr(Box::new(constant::GetElementPtr {
+                address: global_ref("pair", pair_ty.clone()),
+                indices: vec![Constant::Int { bits: 32, value: 0 }, Constant::Int { bits: 32, value: 1 }],
+                in_bounds: true,
+            }))),
+            // `void *ping = &pong; void *pong = &ping;`
+            global("ping", ptr_ty.clone(), Constant::BitCast(Box::new(constant::BitCast {
+                operand: global_ref("pong", ptr_ty.clone()),
+                to_type: ptr_ty.clone(),
+            }))),
+            global("pong", ptr_ty.clone(), Constant::BitCast(Box::new(constant::BitCast {
+                operand: global_ref("ping", ptr_ty.clone()),
+                to_type: ptr_ty.clone(),
+            }))),
+        ]);
+        let state = blank_state(&project, "test_func");
+
+        let second = state.const_to_bv(&global_ref("second", Type::pointer_to(Type::i32())))?;
+        let pair = state.const_to_bv(&global_ref("pair", pair_ty))?;
+        assert_eq!(state.read(&second, 64)?.as_u64(), Some(pair.as_u64().unwrap() + 4));
+        assert_eq!(state.read(&state.read(&second, 64)?, 32)?.as_u64(), Some(2));
+
+        let ping = state.const_to_bv(&global_ref("ping", ptr_ty.clone()))?;
+        let pong = state.const_to_bv(&global_ref("pong", ptr_ty))?;
+        assert_eq!(state.read(&ping, 64)?.as_u64(), pong.as_u64());
+        assert_eq!(state.read(&pong, 64)?.as_u64(), ping.as_u64());
+
+        assert!(state.global_init_fallbacks().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn unevaluable_global_initializer() -> Result<()> {
+        let project = project_with_test_func(vec![
+            global("scale", Type::FPType(FPType::X86_FP80), Constant::Float(constant::Float::X86_FP80)),
+        ]);
+        let state = blank_state(&project, "test_func");
+
+        // referring to the global works, but its contents are unconstrained
+        let scale = state.const_to_bv(&global_ref("scale", Type::FPType(FPType::X86_FP80)))?;
+        let contents = state.read(&scale, 80)?;
+        assert!(!state.bvs_must_be_equal(&contents, &state.zero(80))?);
+        let fallbacks = state.global_init_fallbacks();
+        assert_eq!(fallbacks.len(), 1);
+        assert_eq!(fallbacks[0].global, Name::from("scale"));
+        Ok(())
+    }
+
+    #[test]
+    fn declared_only_globals() -> Result<()> {
+        // `extern int errno;`, with no definition anywhere in the `Project`
+        let errno = module::GlobalVariable {
+            linkage: module::Linkage::External,
+            initializer: None,
+            ..global("errno", Type::i32(), Constant::Int { bits: 32, value: 0 })
+        };
+        let project = project_with_test_func(vec![errno]);
+        let mut state = blank_state(&project, "test_func");
+
+        // referring to the global works, and its contents are unconstrained
+        let errno = state.const_to_bv(&global_ref("errno", Type::i32()))?;
+        let contents = state.read(&errno, 32)?;
+        assert!(!state.bvs_must_be_equal(&contents, &state.zero(32))?);
+        assert!(!state.bvs_must_be_equal(&contents, &state.bv_from_u32(1, 32))?);
+
+        // but writes to it stick
+        state.write(&errno, state.bv_from_u32(22, 32))?;
+        let contents = state.read(&errno, 32)?;
+        assert!(state.bvs_must_be_equal(&contents, &state.bv_from_u32(22, 32))?);
+        Ok(())
+    }
+
     #[test]
     fn const_bv() {
         let func = blank_function("test_func", vec![Name::from("test_bb")]);
@@ -1818,6 +4704,303 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn path_constraints() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("bb_start"), Name::from("bb_target")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+        state.record_path_entry();
+
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        let above_3 = x.ugt(&state.bv_from_u64(3, 64));
+        let below_10 = x.ult(&state.bv_from_u64(10, 64));
+        let is_5 = x._eq(&state.bv_from_u64(5, 64));
+        let is_7 = x._eq(&state.bv_from_u64(7, 64));
+        state.assert_constraint(ConstraintOrigin::Assume, &above_3)?;
+        state.assert_constraint(ConstraintOrigin::Assume, &below_10)?;
+        state.save_backtracking_point(&Name::from("bb_target"), is_7.clone());
+        state.assert_constraint(ConstraintOrigin::BranchRefinement, &is_5)?;
+        assert_eq!(state.path_constraints(), [(0, above_3.clone()), (0, below_10.clone()), (1, is_5)]);
+
+        // backtracking drops the constraint asserted after the backtracking
+        // point, and adds the one it was saved with
+        assert!(state.revert_to_backtracking_point()?);
+        assert_eq!(state.path_constraints(), [(0, above_3), (0, below_10), (0, is_7)]);
+        Ok(())
+    }
+
+    #[test]
+    fn describe_vars() -> Result<()> {
+        let mut func = blank_function("test_func", vec![Name::from("test_bb")]);
+        func.basic_blocks[0].instrs.push(Instruction::Add(instruction::Add {
+            operand0: local("x", Type::i32()),
+            operand1: int_const(32, 1),
+            dest: Name::from(7),
+            debugloc: None,
+        }));
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        let x = state.new_bv_with_name(Name::from("x"), 32)?;
+        let sum = x.add(&state.one(32));
+        state.assign_bv_to_name(Name::from(7), sum.clone())?;
+
+        // `x` has no defining instruction
+        let origin = state.describe_var(&x).unwrap();
+        assert_eq!((origin.funcname.as_str(), &origin.name, &origin.loc), ("test_func", &Name::from("x"), &None));
+        assert_eq!(origin.to_string(), "%x in test_func");
+
+        let origin = state.describe_var(&sum).unwrap();
+        let loc = origin.loc.as_ref().unwrap();
+        assert_eq!((&loc.bbname, loc.instr), (&Name::from("test_bb"), BBInstrIndex::Instr(0)));
+        assert_eq!(origin.to_string(), "%7, defined at {test_func, bb test_bb, instr 0}");
+
+        // subexpressions aren't bound to any value
+        assert_eq!(state.describe_var(&x.not()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn display_and_debug() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("bb_start"), Name::from("bb_target")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        let above_3 = x.ugt(&state.bv_from_u64(3, 64));
+        state.assert_constraint(ConstraintOrigin::Assume, &above_3)?;
+        assert_eq!(
+            state.to_string(),
+            "<State at {test_mod: test_func, bb bb_start, instr 0}: 1 variables, 1 path constraints, 0 backtracking points>",
+        );
+        assert_eq!(
+            format!("{:?}", state),
+            "State { cur_loc: {test_mod: test_func, bb bb_start, instr 0}, variables: 1, backtrack_points: [], path_constraints: 1 }",
+        );
+
+        // backtracking points are listed using their own `Display`
+        state.save_backtracking_point(&Name::from("bb_target"), x._eq(&state.bv_from_u64(7, 64)));
+        let bp = state.backtrack_points.borrow()[0].to_string();
+        assert_eq!(
+            state.to_string(),
+            format!("<State at {{test_mod: test_func, bb bb_start, instr 0}}: 1 variables, 1 path constraints, 1 backtracking points\n  {}>", bp),
+        );
+
+        // only the alternate form shows the constraints themselves
+        let pretty = format!("{:#?}", state);
+        assert!(pretty.contains(&format!("{:#?}", above_3)));
+        assert!(!format!("{:?}", state).contains(&format!("{:?}", above_3)));
+        Ok(())
+    }
+
+    #[test]
+    fn temporary_constraints() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        let is_5 = x._eq(&state.bv_from_u64(5, 64));
+        let is_7 = x._eq(&state.bv_from_u64(7, 64));
+        let solutions = state.with_temporary_constraints([&is_5], |state| state.get_possible_solutions_for_bv(&x, 2))??;
+        assert_eq!(solutions.as_u64_solutions(), Some(PossibleSolutions::Exactly(vec![5].into_iter().collect())));
+        assert!(state.sat_with_extra_constraints(std::iter::once(&is_7))?);
+
+        // the constraint is also removed if the closure panics
+        let caught = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            state.with_temporary_constraints(std::iter::once(&is_5), |_| panic!("closure panicked"))
+        }));
+        assert!(caught.is_err());
+        assert!(state.sat()?);
+        assert!(state.sat_with_extra_constraints(std::iter::once(&is_7))?);
+        Ok(())
+    }
+
+    #[test]
+    fn backtracking_alternatives() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("bb_start"), Name::from("bb_a"), Name::from("bb_b")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+        state.record_path_entry();
+
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        let pushes = state.solver_stats().pushes;
+        state.save_backtracking_points(vec![
+            (&Name::from("bb_a"), x._eq(&state.bv_from_u64(1, 64))),
+            (&Name::from("bb_b"), x._eq(&state.bv_from_u64(2, 64))),
+        ]);
+        assert_eq!(state.count_backtracking_points(), 2);
+        // both alternatives share one level of the solver's assertion stack
+        assert_eq!(state.solver_stats().pushes, pushes + 1);
+
+        // the last alternative is explored first
+        assert!(state.revert_to_backtracking_point()?);
+        assert_eq!(state.cur_loc.bb.name, Name::from("bb_b"));
+        assert!(state.bvs_must_be_equal(&x, &state.bv_from_u64(2, 64))?);
+        assert_eq!(state.count_backtracking_points(), 1);
+        // a constraint on this path ...
+        x.ugt(&state.bv_from_u64(1, 64)).assert();
+
+        // ... doesn't carry over to the other alternative
+        assert!(state.revert_to_backtracking_point()?);
+        assert_eq!(state.cur_loc.bb.name, Name::from("bb_a"));
+        assert!(state.bvs_must_be_equal(&x, &state.bv_from_u64(1, 64))?);
+        assert!(state.sat()?);
+
+        assert!(!state.revert_to_backtracking_point()?);
+        Ok(())
+    }
+
+    #[test]
+    fn nested_backtracking_points() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("bb_start"), Name::from("bb_a"), Name::from("bb_b")]);
+        let project = blank_project("test_mod", func);
+        for &checkpointing in &[Checkpointing::SolverFrames, Checkpointing::AssumptionLiterals] {
+            let config = Config { checkpointing, ..Config::default() };
+            let mut state = state_with_config(&project, config);
+            state.record_path_entry();
+
+            let x = state.new_bv_with_name(Name::from("x"), 64)?;
+            let ten = state.bv_from_u64(10, 64);
+            let twenty = state.bv_from_u64(20, 64);
+            state.save_backtracking_point(&Name::from("bb_a"), x.ult(&ten));
+            state.assert_constraint(ConstraintOrigin::Assume, &x.ugte(&ten))?;
+            state.save_backtracking_point(&Name::from("bb_b"), x.ugt(&twenty));
+            state.assert_constraint(ConstraintOrigin::Assume, &x.ulte(&twenty))?;
+            assert!(!state.bvs_can_be_equal(&x, &state.bv_from_u64(30, 64))?);
+
+            // the constraint between the points still applies ...
+            assert!(state.revert_to_backtracking_point()?);
+            assert_eq!(state.cur_loc.bb.name, Name::from("bb_b"));
+            assert!(state.bvs_can_be_equal(&x, &state.bv_from_u64(30, 64))?);
+            assert!(!state.bvs_can_be_equal(&x, &state.bv_from_u64(5, 64))?);
+
+            // ... but not before them
+            assert!(state.revert_to_backtracking_point()?);
+            assert_eq!(state.cur_loc.bb.name, Name::from("bb_a"));
+            assert!(state.bvs_can_be_equal(&x, &state.bv_from_u64(5, 64))?);
+            assert!(!state.bvs_can_be_equal(&x, &state.bv_from_u64(15, 64))?);
+            assert!(!state.revert_to_backtracking_point()?);
+
+            // each query adds a temporary level on top of one per point, or
+            // of the one for the current path
+            let expected_depth = match checkpointing {
+                Checkpointing::SolverFrames => 3,
+                Checkpointing::AssumptionLiterals => 2,
+            };
+            assert_eq!(state.solver_stats().peak_assertion_depth, expected_depth, "with {:?}", checkpointing);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn backtracking_restores_memory() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("bb_start"), Name::from("bb_target")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+        state.record_path_entry();
+
+        let addr = state.allocate(32_u64);
+        state.write(&addr, state.bv_from_u32(7, 32))?;
+        state.save_backtracking_point(&Name::from("bb_target"), state.bv_from_bool(true));
+
+        // a store on the abandoned path ...
+        state.write(&addr, state.bv_from_u32(1, 32))?;
+        assert!(state.bvs_must_be_equal(&state.read(&addr, 32)?, &state.bv_from_u32(1, 32))?);
+
+        // ... isn't visible after backtracking
+        assert!(state.revert_to_backtracking_point()?);
+        assert!(state.bvs_must_be_equal(&state.read(&addr, 32)?, &state.bv_from_u32(7, 32))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn live_allocations() -> Result<()> {
+        let project = project_with_test_func(vec![global("g", Type::i32(), Constant::Int { bits: 32, value: 0 })]);
+        let mut state = blank_state(&project, "test_func");
+        state.record_path_entry();
+        let g = state.const_to_bv(&global_ref("g", Type::i32()))?.as_u64().unwrap();
+        let allocated = |state: &State<BtorBackend>| state.live_allocations().into_iter().map(|(start, _)| start).collect::<Vec<u64>>();
+        // just the global; not `test_func`
+        assert_eq!(allocated(&state), vec![g]);
+
+        let kept = state.allocate(32_u64).as_u64().unwrap();
+        state.save_backtracking_point(&Name::from("test_bb"), state.bv_from_bool(true));
+        let abandoned = state.allocate(32_u64).as_u64().unwrap();
+        let freed = state.allocate(32_u64);
+        state.free(&freed)?;
+        assert_eq!(allocated(&state), vec![g, kept, abandoned]);
+
+        // allocations from the abandoned path aren't live after backtracking,
+        // but new ones are
+        assert!(state.revert_to_backtracking_point()?);
+        let new = state.allocate(32_u64).as_u64().unwrap();
+        assert_eq!(allocated(&state), vec![g, kept, new]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_and_write_symbolic_addrs() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("test_bb")]);
+        let project = blank_project("test_mod", func);
+        let config = Config {
+            null_pointer_checking: NullPointerChecking::None,
+            ..Config::default()
+        };
+        let mut state = state_with_config(&project, config);
+
+        // a store to a symbolic address can be read back from that address ...
+        let a = state.new_bv_with_name(Name::from("a"), 64)?;
+        let b = state.new_bv_with_name(Name::from("b"), 64)?;
+        let val = state.new_bv_with_name(Name::from("val"), 32)?;
+        state.write(&a, val.clone())?;
+        assert!(state.bvs_must_be_equal(&state.read(&a, 32)?, &val)?);
+
+        // ... but says nothing about a different address
+        state.assert_constraint(ConstraintOrigin::Assume, &a.sub(&b).ugte(&state.bv_from_u64(4, 64)))?;
+        state.assert_constraint(ConstraintOrigin::Assume, &b.sub(&a).ugte(&state.bv_from_u64(4, 64)))?;
+        let other = state.read(&b, 32)?;
+        assert!(!state.bvs_must_be_equal(&other, &val)?);
+        assert!(state.bvs_can_be_equal(&other, &state.bv_from_u32(0xdead_beef, 32))?);
+        Ok(())
+    }
+
+    #[test]
+    fn fork_is_independent() -> Result<()> {
+        let func = blank_function("test_func", vec![Name::from("bb_start"), Name::from("bb_target")]);
+        let project = blank_project("test_mod", func);
+        let mut state = blank_state(&project, "test_func");
+        state.record_path_entry();
+
+        let x = state.new_bv_with_name(Name::from("x"), 64)?;
+        state.assert_constraint(ConstraintOrigin::Assume, &x.ugt(&state.bv_from_u64(3, 64)))?;
+        state.save_backtracking_point(&Name::from("bb_target"), x.ugt(&state.bv_from_u64(10, 64)));
+        state.config.collect_watchpoint_hits = true;
+        state.record_watchpoint_hit("w", &Watchpoint::new(0x1000, 8), Access::Write, "somewhere".to_owned(), &x);
+        let mut forked = state.fork();
+
+        // the fork has the constraints, but not the backtracking point
+        assert_eq!(forked.count_backtracking_points(), 0);
+        assert_eq!(forked.path_constraints().len(), 1);
+        let x_2 = forked.operand_to_bv(&local("x", Type::i64()))?;
+        forked.assert_constraint(ConstraintOrigin::Assume, &x_2.ult(&forked.bv_from_u64(2, 64)))?;
+        assert!(!forked.sat()?);
+        assert!(!forked.revert_to_backtracking_point()?);
+
+        // the fork's watchpoint hits hold its own copies of the values
+        let hits = forked.watchpoint_hits();
+        assert_eq!(hits.len(), 1);
+        assert!(Rc::ptr_eq(&hits[0].value.get_solver(), &forked.solver));
+
+        // the original is unaffected, and can still backtrack
+        assert!(state.sat()?);
+        assert!(state.revert_to_backtracking_point()?);
+        assert!(state.sat()?);
+        Ok(())
+    }
+
     #[test]
     fn fork() {
         let func = blank_function("test_func", vec![Name::from("test_bb")]);
@@ -1836,8 +5019,8 @@ mod tests {
         let mut state_2 = state.fork();
 
         // get the copies of `x` and `y` in each state, via operand lookups
-        let op_x = Operand::LocalOperand { name: Name::from("x"), ty: Type::i64() };
-        let op_y = Operand::LocalOperand { name: Name::from("y"), ty: Type::i64() };
+        let op_x = local("x", Type::i64());
+        let op_y = local("y", Type::i64());
         let x_1 = state.operand_to_bv(&op_x).unwrap();
         let x_2 = state_2.operand_to_bv(&op_x).unwrap();
         let y_1 = state.operand_to_bv(&op_y).unwrap();
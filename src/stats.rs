@@ -0,0 +1,322 @@
+//! Statistics about symbolic execution, such as how many times each
+//! instruction was executed
+
+use crate::project::Project;
+use crate::solver_utils::SolverResult;
+use crate::state::BBInstrIndex;
+use llvm_ir::{BasicBlock, Function, Name};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+/// Statistics collected by an `ExecutionManager` across all the paths it has
+/// explored; see
+/// [`ExecutionManager::stats()`](../struct.ExecutionManager.html#method.stats)
+#[derive(Clone)]
+pub struct Stats<'p> {
+    /// Every basic block in the `Project`, along with its function
+    blocks: Vec<(&'p Function, &'p BasicBlock)>,
+    /// For each entry in `blocks`, the index in `counts` of its first
+    /// instruction. Its terminator comes right after its last instruction.
+    bases: Vec<usize>,
+    /// Map from the address of each `BasicBlock` to its index in `blocks`
+    block_idxs: HashMap<usize, usize>,
+    /// Execution count of each instruction and terminator in the `Project`
+    counts: Vec<u64>,
+    /// Number of paths which completed (returned), not counting paths which
+    /// ended in an error
+    paths: usize,
+    /// See `State::solver_stats()`
+    solver_stats: SolverStats,
+    /// See `State::values_computed()`
+    values_computed: usize,
+}
+
+/// Statistics about the solver queries made through a `State`; see
+/// [`State::solver_stats()`](../struct.State.html#method.solver_stats).
+/// These persist across backtracking, so they cover all the paths explored
+/// with the `State` so far.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SolverStats {
+    /// Number of satisfiability checks (see
+    /// [`State::solver_query_count()`](../struct.State.html#method.solver_query_count))
+    pub checks: usize,
+    /// Number of checks which found the constraints satisfiable
+    pub sat: usize,
+    /// Number of checks which found the constraints unsatisfiable
+    pub unsat: usize,
+    /// Number of checks which were interrupted, timed out, or otherwise failed
+    pub unknown: usize,
+    /// Number of checks answered from the cache, without querying the solver
+    /// (see
+    /// [`Config.cache_solver_results`](../config/struct.Config.html#structfield.cache_solver_results));
+    /// these aren't included in `checks`
+    pub cache_hits: usize,
+    /// Total wall-clock time spent in checks
+    pub solver_time: Duration,
+    /// Number of levels pushed onto the solver's assertion stack
+    pub pushes: usize,
+    /// Number of levels popped off the solver's assertion stack
+    pub pops: usize,
+    /// The most levels on the solver's assertion stack at once so far,
+    /// including the temporary ones for queries with extra constraints (see
+    /// [`Config.checkpointing`](../config/struct.Config.html#structfield.checkpointing))
+    pub peak_assertion_depth: usize,
+    /// The most constraints on any one path so far (see
+    /// [`State::path_constraints()`](../struct.State.html#method.path_constraints))
+    pub peak_path_constraints: usize,
+}
+
+impl SolverStats {
+    /// Record a check which had the given result and took the given time
+    pub(crate) fn record_check(&mut self, result: SolverResult, time: Duration) {
+        self.checks += 1;
+        match result {
+            SolverResult::Sat => self.sat += 1,
+            SolverResult::Unsat => self.unsat += 1,
+            SolverResult::Unknown => self.unknown += 1,
+        }
+        self.solver_time += time;
+    }
+}
+
+impl fmt::Display for SolverStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} solver checks ({} sat, {} unsat, {} unknown) in {:.3}s, plus {} answered from the cache; {} pushes, {} pops, at most {} levels deep; at most {} constraints on a path",
+            self.checks, self.sat, self.unsat, self.unknown, self.solver_time.as_secs_f64(), self.cache_hits, self.pushes, self.pops, self.peak_assertion_depth, self.peak_path_constraints,
+        )
+    }
+}
+
+/// How many times an instruction (or terminator) was executed
+#[derive(Clone, Debug)]
+pub struct InstructionCount<'p> {
+    pub func: &'p Function,
+    pub bb: &'p BasicBlock,
+    pub instr: BBInstrIndex,
+    pub count: u64,
+}
+
+/// How many instructions (including the terminator) were executed in a basic
+/// block, in total
+#[derive(Clone, Debug)]
+pub struct BlockCount<'p> {
+    pub func: &'p Function,
+    pub bb: &'p BasicBlock,
+    pub count: u64,
+}
+
+impl<'p> Stats<'p> {
+    pub(crate) fn new(project: &'p Project) -> Self {
+        let mut blocks = vec![];
+        let mut bases = vec![];
+        let mut block_idxs = HashMap::new();
+        let mut num_counts = 0;
+        for (func, _) in project.all_functions() {
+            for bb in &func.basic_blocks {
+                block_idxs.insert(bb as *const BasicBlock as usize, blocks.len());
+                blocks.push((func, bb));
+                bases.push(num_counts);
+                num_counts += bb.instrs.len() + 1;
+            }
+        }
+        Self { blocks, bases, block_idxs, counts: vec![0; num_counts], paths: 0, solver_stats: SolverStats::default(), values_computed: 0 }
+    }
+
+    /// Get the index in `counts` of the first instruction of the given
+    /// `BasicBlock`, which must be part of the `Project`
+    pub(crate) fn block_base(&self, bb: &BasicBlock) -> Option<usize> {
+        self.block_idxs.get(&(bb as *const BasicBlock as usize)).map(|&idx| self.bases[idx])
+    }
+
+    /// Record an execution of the instruction with the given index in `counts`
+    pub(crate) fn record(&mut self, idx: usize) {
+        self.counts[idx] += 1;
+    }
+
+    /// Record the end of a path, and the work done so far by the `State`
+    pub(crate) fn record_path(&mut self, completed: bool, solver_stats: &SolverStats, values_computed: usize) {
+        if completed {
+            self.paths += 1;
+        }
+        self.solver_stats = solver_stats.clone();
+        self.values_computed = values_computed;
+    }
+
+    /// Get the number of paths which completed so far, not counting paths
+    /// which ended in an error
+    pub fn paths(&self) -> usize {
+        self.paths
+    }
+
+    /// Get the number of solver queries made so far (see
+    /// [`State::solver_query_count()`](../struct.State.html#method.solver_query_count)),
+    /// as of the end of the most recent path
+    pub fn solver_queries(&self) -> usize {
+        self.solver_stats.checks
+    }
+
+    /// Get the statistics about solver queries made so far (see
+    /// [`State::solver_stats()`](../struct.State.html#method.solver_stats)),
+    /// as of the end of the most recent path
+    pub fn solver_stats(&self) -> &SolverStats {
+        &self.solver_stats
+    }
+
+    /// Get the number of values computed so far (see
+    /// [`State::values_computed()`](../struct.State.html#method.values_computed)),
+    /// as of the end of the most recent path
+    pub fn values_computed(&self) -> usize {
+        self.values_computed
+    }
+
+    fn instr_range(&self, block_idx: usize) -> std::ops::Range<usize> {
+        let base = self.bases[block_idx];
+        base .. base + self.blocks[block_idx].1.instrs.len() + 1
+    }
+
+    /// Get the execution count of every instruction and terminator which was
+    /// executed at least once, on any path (including paths which were later
+    /// found to be infeasible, or ended in an error)
+    pub fn instruction_counts(&self) -> Vec<InstructionCount<'p>> {
+        let mut retval = vec![];
+        for (block_idx, &(func, bb)) in self.blocks.iter().enumerate() {
+            for (i, idx) in self.instr_range(block_idx).enumerate() {
+                if self.counts[idx] > 0 {
+                    let instr = if i < bb.instrs.len() { BBInstrIndex::Instr(i) } else { BBInstrIndex::Terminator };
+                    retval.push(InstructionCount { func, bb, instr, count: self.counts[idx] });
+                }
+            }
+        }
+        retval
+    }
+
+    /// Get the execution count of the given instruction (or terminator) in the
+    /// given function and basic block. Returns 0 if there is no such
+    /// instruction.
+    pub fn count(&self, funcname: &str, bbname: &Name, instr: BBInstrIndex) -> u64 {
+        self.blocks.iter()
+            .position(|(func, bb)| func.name == funcname && &bb.name == bbname)
+            .and_then(|block_idx| {
+                let range = self.instr_range(block_idx);
+                let idx = match instr {
+                    BBInstrIndex::Instr(i) => range.start + i,
+                    BBInstrIndex::Terminator => range.end - 1,
+                };
+                if range.contains(&idx) { Some(self.counts[idx]) } else { None }
+            })
+            .unwrap_or(0)
+    }
+
+    /// Get the (at most) `n` basic blocks with the most instructions executed,
+    /// hottest first
+    pub fn hottest_blocks(&self, n: usize) -> Vec<BlockCount<'p>> {
+        let mut blocks: Vec<BlockCount<'p>> = self.blocks.iter().enumerate()
+            .map(|(block_idx, &(func, bb))| BlockCount { func, bb, count: self.counts[self.instr_range(block_idx)].iter().sum() })
+            .filter(|block| block.count > 0)
+            .collect();
+        blocks.sort_by_key(|block| std::cmp::Reverse(block.count));
+        blocks.truncate(n);
+        blocks
+    }
+
+    /// Get a listing of the LLVM IR of the function with the given name, with
+    /// each instruction annotated with its execution count, in the style of
+    /// `llvm-cov show`. Returns `None` if there is no such function.
+    pub fn annotated_listing(&self, funcname: &str) -> Option<String> {
+        let mut listing = String::new();
+        let mut found = false;
+        for (block_idx, &(func, bb)) in self.blocks.iter().enumerate() {
+            if func.name != funcname {
+                continue;
+            }
+            if !found {
+                writeln!(listing, "{}:", func.name).unwrap();
+                found = true;
+            }
+            writeln!(listing, "{:>10}|bb {}:", "", bb.name).unwrap();
+            let range = self.instr_range(block_idx);
+            for (inst, idx) in bb.instrs.iter().zip(range.clone()) {
+                writeln!(listing, "{:>10}|  {:?}", self.counts[idx], inst).unwrap();
+            }
+            writeln!(listing, "{:>10}|  {:?}", self.counts[range.end - 1], bb.term).unwrap();
+        }
+        if found { Some(listing) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::config::Config;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::*;
+
+    /// `if (x > 5) { a } else { b }; for (i = 0; i < 3; i++) {}; return 0;`
+    fn branch_then_loop() -> Function {
+        let i32_const = |value| int_const(32, value);
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("a"), Name::from("b"), Name::from("loop"), Name::from("exit")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::UGT, local("x", Type::i32()), i32_const(5), "big"));
+        func.basic_blocks[0].term = condbr("big", "a", "b");
+        func.basic_blocks[1].term = br("loop");
+        func.basic_blocks[2].term = br("loop");
+        func.basic_blocks[3].instrs = vec![
+            Instruction::Phi(instruction::Phi {
+                incoming_values: vec![(i32_const(0), Name::from("a")), (i32_const(0), Name::from("b")), (local("next", Type::i32()), Name::from("loop"))],
+                dest: Name::from("i"),
+                to_type: Type::i32(),
+                debugloc: None,
+            }),
+            Instruction::Add(instruction::Add {
+                operand0: local("i", Type::i32()),
+                operand1: i32_const(1),
+                dest: Name::from("next"),
+                debugloc: None,
+            }),
+            icmp(IntPredicate::ULT, local("next", Type::i32()), i32_const(3), "more"),
+        ];
+        func.basic_blocks[3].term = condbr("more", "loop", "exit");
+        func.basic_blocks[4].term = ret(i32_const(0));
+        func
+    }
+
+    #[test]
+    fn loop_body_counts() {
+        let project = blank_project("test_mod", branch_then_loop());
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, Config::default());
+        let mut paths = 0;
+        for retval in em.by_ref() {
+            retval.unwrap();
+            paths += 1;
+        }
+        assert_eq!(paths, 2);
+        let stats = em.stats();
+        assert_eq!(stats.paths(), 2);
+        assert!(stats.solver_queries() > 0);
+        assert_eq!(stats.solver_queries(), em.state().solver_query_count());
+        assert!(stats.values_computed() >= 6);
+        // 2 paths, each with 3 iterations of the loop
+        assert_eq!(stats.count("f", &Name::from("loop"), BBInstrIndex::Instr(1)), 6);
+        assert_eq!(stats.count("f", &Name::from("loop"), BBInstrIndex::Terminator), 6);
+        assert_eq!(stats.count("f", &Name::from("entry"), BBInstrIndex::Instr(0)), 1);
+        assert_eq!(stats.count("f", &Name::from("a"), BBInstrIndex::Terminator), 1);
+        assert_eq!(stats.count("f", &Name::from("exit"), BBInstrIndex::Terminator), 2);
+
+        let hottest = stats.hottest_blocks(1);
+        assert_eq!(hottest.len(), 1);
+        assert_eq!(hottest[0].bb.name, Name::from("loop"));
+        assert_eq!(hottest[0].count, 4 * 6);
+
+        let listing = stats.annotated_listing("f").unwrap();
+        assert!(listing.starts_with("f:\n"));
+        assert_eq!(listing.lines().filter(|line| line.trim_start().starts_with("6|")).count(), 4);
+        assert!(stats.annotated_listing("g").is_none());
+    }
+}
@@ -1,21 +1,29 @@
 use llvm_ir::*;
 use llvm_ir::instruction::{BinaryOp, InlineAssembly};
-use log::{debug, info};
+use log::{debug, info, warn};
 use either::Either;
-use reduce::Reduce;
 use std::convert::TryInto;
+use std::collections::HashSet;
 use std::fmt;
 use std::sync::{Arc, RwLock};
 
-pub use crate::state::{State, BBInstrIndex, Location, LocationDescription, PathEntry};
+pub use crate::state::{State, BBInstrIndex, InitializerFallback, Location, LocationDescription, PathEntry, VarOrigin};
+use crate::alloc_utils;
 use crate::backend::*;
+use crate::checkers::{CustomViolation, Violation, ViolationChecker};
 use crate::config::*;
+use crate::diagnosis::UnsatDiagnosis;
 use crate::error::*;
+#[cfg(feature = "fp")]
+use crate::float::{self, FloatFormat};
 use crate::function_hooks::*;
 use crate::layout::*;
-use crate::solver_utils::PossibleSolutions;
+use crate::middleware::ConstraintOrigin;
+use crate::solver_utils::{PossibleSolutions, SolverResult};
+use crate::stats::Stats;
 use crate::project::Project;
 use crate::return_value::*;
+use crate::rewrite::{BinaryOpcode, Expr};
 
 /// Begin symbolic execution of the function named `funcname`, obtaining an
 /// `ExecutionManager`. The function's parameters will start completely
@@ -74,16 +82,93 @@ pub struct ExecutionManager<'p, B: Backend> {
     fresh: bool,
     /// The `squash_unsats` setting from `Config`
     squash_unsats: bool,
+    /// If we are enumerating concrete inputs (see
+    /// `Config.exhaustive_threshold_bits`), the number of feasible concrete
+    /// inputs run so far
+    exhaustive_inputs: Option<u64>,
+    /// The enumeration of concrete inputs in progress, if any
+    concrete_inputs: Option<ConcreteInputs<B::BV>>,
+    /// The largest stack usage over the paths explored so far
+    max_stack_usage: Option<StackUsage<'p>>,
+    /// Whether any path so far ended in an error (e.g., exceeding the loop
+    /// bound), so that it may have used more stack had it continued
+    paths_cut_off: bool,
+    /// Whether all paths have been explored, i.e., `next()` has returned `None`
+    exhausted: bool,
+    /// Violations found by the `Config.extra_checkers` so far
+    violations: Vec<Violation>,
+    /// Violations which were found, but not reported due to
+    /// `Config.report_filter`, identified by checker, location, and message
+    suppressed_violations: HashSet<(String, String, String)>,
+    /// Statistics about the paths explored so far
+    stats: Stats<'p>,
+    /// Number of calls skipped so far due to `Config.max_callstack_depth`
+    callstack_depth_hits: usize,
+    /// Global constructors to execute before the entry function, if
+    /// `Config.run_global_ctors` is set
+    global_ctors: Vec<(&'p Function, &'p Module)>,
+    /// Start of the entry function
+    entry_loc: Location<'p>,
+}
+
+/// An enumeration of the concrete inputs to the entry function; see
+/// `Config.exhaustive_threshold_bits`
+struct ConcreteInputs<V: BV> {
+    /// The entry function's symbolic parameters
+    params: Vec<V>,
+    /// The next input to run, as the concatenation of the values of the
+    /// `params`, with the first parameter in the lowest bits
+    next: u64,
+    /// The number of inputs, i.e., `2^n` for `n` total bits of `params`
+    count: u64,
+    /// The number of backtracking points which were pending before the
+    /// enumeration started. The one saved at the start of the entry function,
+    /// for starting each input afresh, is just above these.
+    base_points: usize,
+}
+
+/// The largest stack usage over the paths explored by an `ExecutionManager`;
+/// see [`ExecutionManager::max_stack_usage()`](struct.ExecutionManager.html#method.max_stack_usage)
+#[derive(Clone, Debug)]
+pub struct StackUsage<'p> {
+    /// Stack usage in bytes: the total size of the `alloca`s in all frames
+    /// active at the deepest point of the path (with padding for alignment),
+    /// plus [`Config.stack_frame_overhead`](config/struct.Config.html#structfield.stack_frame_overhead)
+    /// for each frame
+    pub bytes: u64,
+    /// The path which reaches this stack usage
+    pub path: Vec<PathEntry<'p>>,
+    /// Values of the function's parameters which lead down `path`. Entries
+    /// are `None` for parameters wider than 64 bits, or if the solver failed
+    /// to produce a value.
+    pub inputs: Vec<Option<u64>>,
+    /// `true` if some paths have not been explored, or were cut off (e.g., by
+    /// the loop bound), in which case the true maximum may be larger than
+    /// `bytes`
+    pub lower_bound_only: bool,
 }
 
 impl<'p, B: Backend> ExecutionManager<'p, B> {
     fn new(state: State<'p, B>, project: &'p Project, bvparams: Vec<B::BV>, squash_unsats: bool) -> Self {
+        let global_ctors = if state.config.run_global_ctors { project.global_ctors() } else { Vec::new() };
+        let entry_loc = state.cur_loc.clone();
         Self {
             state,
             project,
             bvparams,
             fresh: true,
             squash_unsats,
+            exhaustive_inputs: None,
+            concrete_inputs: None,
+            max_stack_usage: None,
+            paths_cut_off: false,
+            exhausted: false,
+            violations: Vec::new(),
+            suppressed_violations: HashSet::new(),
+            stats: Stats::new(project),
+            callstack_depth_hits: 0,
+            global_ctors,
+            entry_loc,
         }
     }
 
@@ -107,6 +192,218 @@ impl<'p, B: Backend> ExecutionManager<'p, B> {
     pub fn param_bvs(&self) -> &Vec<B::BV> {
         &self.bvparams
     }
+
+    /// If this `ExecutionManager` is enumerating every concrete input to the
+    /// function (see
+    /// [`Config.exhaustive_threshold_bits`](config/struct.Config.html#structfield.exhaustive_threshold_bits)),
+    /// returns the number of feasible concrete inputs it has run so far; once
+    /// `next()` has returned `None`, that is all of them. Otherwise, returns
+    /// `None`.
+    ///
+    /// The decision is made on entering the entry function (after any global
+    /// constructors), so this always returns `None` before the first call to
+    /// `next()`.
+    pub fn exhaustive_input_count(&self) -> Option<u64> {
+        self.exhaustive_inputs
+    }
+
+    /// Get diagnoses of the conditions found to be infeasible on the paths
+    /// explored so far, if
+    /// [`Config.diagnose_unsat_paths`](config/struct.Config.html#structfield.diagnose_unsat_paths)
+    /// is set. This is the same as `state().unsat_diagnoses()`.
+    pub fn unsat_diagnoses(&self) -> &[UnsatDiagnosis] {
+        self.state.unsat_diagnoses()
+    }
+
+    /// Get the violations found so far by the
+    /// [`Config.extra_checkers`](config/struct.Config.html#structfield.extra_checkers),
+    /// in the order they were found. Each violation is reported once per
+    /// location, even if it is reachable on several paths.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Get the number of distinct violations found so far which aren't in
+    /// `violations()` because
+    /// [`Config.report_filter`](config/struct.Config.html#structfield.report_filter)
+    /// filtered them out
+    pub fn suppressed_violations(&self) -> usize {
+        self.suppressed_violations.len()
+    }
+
+    /// Get the number of calls which were skipped (treated as returning an
+    /// unconstrained value) so far, on all paths, because entering them would
+    /// have exceeded
+    /// [`Config.max_callstack_depth`](config/struct.Config.html#structfield.max_callstack_depth)
+    pub fn calls_skipped_at_max_callstack_depth(&self) -> usize {
+        self.callstack_depth_hits
+    }
+
+    /// Get statistics about all the paths explored so far, such as how many
+    /// times each instruction was executed. Instructions on paths which were
+    /// later abandoned (e.g., found to be unsat) are counted too.
+    pub fn stats(&self) -> &Stats<'p> {
+        &self.stats
+    }
+
+    /// Get the largest stack usage on any path explored so far (see
+    /// [`StackUsage`](struct.StackUsage.html)), along with a path and inputs
+    /// which reach it. For paths with dynamically-sized `alloca`s, this is the
+    /// largest their sizes can be on the path, and the inputs are ones which
+    /// give those sizes.
+    ///
+    /// To get the worst case over all paths, call `next()` until it returns
+    /// `None` first. Returns `None` if no paths have been explored.
+    pub fn max_stack_usage(&self) -> Option<StackUsage<'p>> {
+        self.max_stack_usage.clone().map(|usage| StackUsage {
+            lower_bound_only: self.paths_cut_off || !self.exhausted,
+            ..usage
+        })
+    }
+
+    /// Update `max_stack_usage` with the path which just ended
+    fn record_stack_usage(&mut self) -> Result<()> {
+        let usage_bv = self.state.max_stack_usage_on_path_bv();
+        let bytes = match &usage_bv {
+            Some(usage_bv) => self.state.max_possible_solution_for_bv_as_u64(usage_bv)?.ok_or(Error::Unsat)?,
+            None => self.state.max_stack_usage_on_path(),
+        };
+        if let Some(best) = &self.max_stack_usage {
+            if best.bytes >= bytes {
+                return Ok(());
+            }
+        }
+        let get_inputs = |state: &State<'p, B>| self.bvparams.iter()
+            .map(|bv| state.get_a_u64_solution_for_bv(bv).ok().flatten())
+            .collect();
+        let inputs = match usage_bv {
+            Some(usage_bv) => {
+                let reaches_max = usage_bv._eq(&self.state.bv_from_u64(bytes, 64));
+                self.state.with_temporary_constraints(std::iter::once(&reaches_max), get_inputs)?
+            },
+            None => get_inputs(&self.state),
+        };
+        self.max_stack_usage = Some(StackUsage {
+            bytes,
+            path: self.state.get_path().clone(),
+            inputs,
+            lower_bound_only: false,
+        });
+        Ok(())
+    }
+
+    /// Add a path note for each handle which is still open at the end of the
+    /// path which just returned
+    fn note_leaked_handles(&mut self) -> Result<()> {
+        let notes: Vec<String> = self.state.open_handles()?.iter()
+            .map(|handle| format!("resource leak: {} is never closed", handle))
+            .collect();
+        for note in notes {
+            self.state.add_path_note(note);
+        }
+        Ok(())
+    }
+
+    /// With `Config.report_memory_leaks`, add a path note for each heap
+    /// allocation which is still live at the end of the path which just
+    /// returned
+    fn note_leaked_memory(&mut self) -> Result<()> {
+        if !self.state.config.report_memory_leaks {
+            return Ok(());
+        }
+        let notes: Vec<String> = self.state.live_heap_allocations()?.into_iter()
+            .map(|addr| match self.state.describe_pointer(addr) {
+                Some(target) => format!("memory leak: {} is never freed", target.allocation),
+                None => format!("memory leak: the allocation at 0x{:x} is never freed", addr),
+            })
+            .collect();
+        for note in notes {
+            self.state.add_path_note(note);
+        }
+        Ok(())
+    }
+
+    /// Run the `check` on each of the `Config.extra_checkers`, recording any
+    /// violations which are possible on the current path
+    fn run_checkers(&mut self, check: impl Fn(&dyn ViolationChecker<'p, B>, &State<'p, B>) -> Result<Option<CustomViolation<B::BV>>>) -> Result<()> {
+        if self.state.config.extra_checkers.is_empty() {
+            return Ok(());
+        }
+        let checkers = self.state.config.extra_checkers.clone();
+        for checker in &checkers {
+            if let Some(violation) = check(checker.as_ref(), &self.state)? {
+                self.record_violation(checker.name(), violation)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Values of the parameters which satisfy the current constraints, shrunk
+    /// if `Config.shrink_witnesses` says so. Parameters wider than 64 bits get
+    /// `None`.
+    fn witness(&self) -> Result<Vec<Option<u64>>> {
+        match self.state.config.shrink_witnesses {
+            Some(budget) => Ok(self.state.shrink_witness(&self.bvparams, budget)?.unwrap_or_else(|| vec![None; self.bvparams.len()])),
+            None => self.bvparams.iter()
+                .map(|bv| if bv.get_width() > 64 { Ok(None) } else { self.state.get_a_u64_solution_for_bv(bv) })
+                .collect(),
+        }
+    }
+
+    /// Record the `violation` at the current location, if it is possible on
+    /// the current path and hasn't already been recorded
+    fn record_violation(&mut self, checker: &str, violation: CustomViolation<B::BV>) -> Result<()> {
+        let location = match self.state.cur_loc.source_loc {
+            Some(source_loc) => source_loc.to_string(),
+            None => self.state.cur_loc.to_string_no_module(),
+        };
+        if self.violations.iter().any(|v| v.checker == checker && v.location == location && v.message == violation.message) {
+            return Ok(());
+        }
+        if self.suppressed_violations.contains(&(checker.to_owned(), location.clone(), violation.message.clone())) {
+            return Ok(());
+        }
+        if !self.state.sat_with_extra_constraints(std::iter::once(&violation.condition))? {
+            return Ok(());
+        }
+        let funcname = &self.state.cur_loc.func.name;
+        let demangled = self.state.demangle(funcname);
+        let source_file = self.state.cur_loc.source_loc.map(|source_loc| source_loc.filename.as_str());
+        if !self.state.config.report_filter.allows(&[funcname, &demangled], source_file) {
+            info!("Checker {:?} found a violation at {}, but Config.report_filter suppresses it", checker, location);
+            self.suppressed_violations.insert((checker.to_owned(), location, violation.message));
+            return Ok(());
+        }
+        info!("Checker {:?} found a violation at {}: {}", checker, location, violation.message);
+        // get inputs which trigger the violation, without constraining the rest of the path.
+        // The frame is popped even if this fails.
+        self.state.push_solver_frame();
+        let witness = self.state.assert_constraint(ConstraintOrigin::ViolationCheck, &violation.condition)
+            .and_then(|_| self.witness());
+        self.state.pop_solver_frame();
+        let witness = witness?;
+        let condition = match self.state.config.violation_conditions {
+            None => None,
+            Some(_) => {
+                let config = self.state.config.clone();
+                crate::analyze::violation_condition(self.project, config, self.state.get_path(), &self.state.cur_loc, checker, &violation.message, &witness)
+                    .unwrap_or_else(|e| {
+                        warn!("Failed to compute the condition for a violation at {}: {}", location, e);
+                        None
+                    })
+            },
+        };
+        self.violations.push(Violation {
+            checker: checker.to_owned(),
+            message: violation.message,
+            location,
+            witness_enumerators: self.witness_enumerators(&witness),
+            witness,
+            approximate: self.state.config.approximation != Approximation::Precise,
+            condition,
+        });
+        Ok(())
+    }
 }
 
 impl<'p, B: Backend> Iterator for ExecutionManager<'p, B> where B: 'p {
@@ -116,16 +413,247 @@ impl<'p, B: Backend> Iterator for ExecutionManager<'p, B> where B: 'p {
         let retval = if self.fresh {
             self.fresh = false;
             info!("Beginning symex in function {:?}", self.state.cur_loc.func.name);
-            self.symex_from_cur_loc_through_end_of_function()
+            if let Err(e) = self.check_backtracking_order() {
+                return Some(Err(e));
+            }
+            match self.assert_preconditions() {
+                Err(e) => return Some(Err(e)),
+                Ok(false) => {
+                    info!("The preconditions are unsatisfiable, so there are no paths");
+                    self.exhausted = true;
+                    return None;
+                },
+                Ok(true) => {},
+            }
+            if !self.global_ctors.is_empty() {
+                info!("Running {} global constructor(s) first", self.global_ctors.len());
+                self.enter_global_ctor(0);
+                self.symex_from_cur_loc()
+            } else {
+                match self.start_concrete_inputs() {
+                    Err(e) => Err(e),
+                    Ok(false) => self.symex_from_cur_loc_through_end_of_function(),
+                    Ok(true) => self.backtrack_and_continue(),
+                }
+            }
         } else {
             debug!("ExecutionManager: requesting next path");
+            self.state.path_index += 1;
             self.backtrack_and_continue()
         };
+        let retval = match self.continue_after_global_ctors(retval) {
+            Ok(Some(retval @ ReturnValue::Return(_))) | Ok(Some(retval @ ReturnValue::ReturnVoid)) => {
+                self.note_leaked_handles()
+                    .and_then(|()| self.note_leaked_memory())
+                    .map(|()| Some(retval))
+            },
+            retval => retval,
+        };
+        let retval = match retval {
+            Ok(None) => {
+                self.exhausted = true;
+                Ok(None)
+            },
+            Ok(Some(retval)) => self.record_stack_usage().map(|()| Some(retval)),
+            Err(e) => {
+                self.paths_cut_off = true;
+                // the path's own error is the one to report
+                if let Err(usage_err) = self.record_stack_usage() {
+                    debug!("Failed to record the stack usage of a path which ended in an error: {}", usage_err);
+                }
+                Err(e)
+            },
+        };
+        let completed = matches!(retval, Ok(Some(_)));
+        self.stats.record_path(completed, &self.state.solver_stats(), self.state.values_computed());
         retval.transpose()
     }
 }
 
 impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
+    /// Assert the `Config.preconditions` and `Config.enum_params`. Returns
+    /// `Ok(false)` if the preconditions (together with any constraints already
+    /// added to the `State`) are unsatisfiable.
+    /// Resuming a backtracking point out of order only re-asserts the
+    /// constraints recorded with `State::assert_constraint()`, so refuse to
+    /// do that if user hooks or callbacks could have called `BV::assert()`
+    fn check_backtracking_order(&self) -> Result<()> {
+        let config = &self.state.config;
+        if config.backtracking_order == BacktrackingOrder::DepthFirst {
+            return Ok(());
+        }
+        if config.function_hooks.has_custom_hooks() || !config.callbacks.is_empty() {
+            return Err(Error::OtherError(format!(
+                "BacktrackingOrder::{:?} can't be used with custom function hooks or callbacks: resuming a backtracking point out of order only re-asserts the constraints recorded by State::assert_constraint(), so any asserted with BV::assert() would be lost. Use BacktrackingOrder::DepthFirst instead",
+                config.backtracking_order,
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn assert_preconditions(&mut self) -> Result<bool> {
+        if self.state.config.preconditions.is_empty() && self.state.config.enum_params.is_empty() {
+            return Ok(true);
+        }
+        for precondition in self.state.config.preconditions.clone() {
+            debug!("Asserting precondition {:?}", precondition);
+            let bv = crate::precondition::precondition_to_bv(&self.state, &precondition)?;
+            if self.state.assert_constraint(ConstraintOrigin::Precondition, &bv)? {
+                self.state.note_condition(&bv, true);
+            }
+        }
+        for (param_name, enum_type) in self.state.config.enum_params.clone() {
+            let func = self.entry_loc.func;
+            let bvparam = match func.parameters.iter().position(|param| param.name == Name::from(param_name.as_str())) {
+                Some(idx) => self.bvparams[idx].clone(),
+                None => return Err(Error::OtherError(format!("Config.enum_params refers to {:?}, which is not a parameter of {:?}", param_name, func.name))),
+            };
+            debug!("Constraining parameter {:?} to the values of {}", param_name, enum_type.name);
+            self.state.assert_constraint(ConstraintOrigin::Precondition, &enum_type.membership_bv(&bvparam))?;
+        }
+        self.state.sat()
+    }
+
+    /// Describe the witness value of each parameter of the entry function by
+    /// its enumerator name(s), for the parameters in `Config.enum_params`
+    fn witness_enumerators(&self, witness: &[Option<u64>]) -> Vec<Option<String>> {
+        self.entry_loc.func.parameters.iter().zip(witness)
+            .map(|(param, value)| {
+                let name = match &param.name {
+                    Name::Name(name) => name,
+                    Name::Number(_) => return None,
+                };
+                let enum_type = self.state.config.enum_params.get(name.as_str())?;
+                enum_type.describe((*value)?)
+            })
+            .collect()
+    }
+
+    /// Move to the start of the global constructor with the given index in
+    /// `self.global_ctors`
+    fn enter_global_ctor(&mut self, idx: usize) {
+        let (func, module) = self.global_ctors[idx];
+        info!("Running global constructor {:?}", func.name);
+        self.state.cur_loc = Location {
+            module,
+            func,
+            bb: func.basic_blocks.first().expect("Failed to get entry basic block of global constructor"),
+            instr: BBInstrIndex::Instr(0),
+            source_loc: None,
+        };
+        self.state.set_global_ctor(Some(idx));
+    }
+
+    /// Given the result of symexing a path, if that path only finished a
+    /// global constructor, continue the path through the remaining global
+    /// constructors (if any) and then the entry function, and return the
+    /// result of that
+    fn continue_after_global_ctors(&mut self, mut retval: Result<Option<ReturnValue<B::BV>>>) -> Result<Option<ReturnValue<B::BV>>> {
+        while let Some(idx) = self.state.global_ctor() {
+            match retval {
+                Ok(Some(ReturnValue::Return(_))) | Ok(Some(ReturnValue::ReturnVoid)) => {},
+                _ => break,  // errors, or exceptions escaping the constructor, end the path as usual
+            }
+            retval = if idx + 1 < self.global_ctors.len() {
+                self.enter_global_ctor(idx + 1);
+                self.symex_from_cur_loc()
+            } else {
+                info!("Done with global constructors; beginning symex in function {:?}", self.entry_loc.func.name);
+                self.state.set_global_ctor(None);
+                self.state.cur_loc = self.entry_loc.clone();
+                match self.start_concrete_inputs() {
+                    Err(e) => Err(e),
+                    Ok(false) => self.symex_from_cur_loc(),
+                    Ok(true) => self.backtrack_and_continue(),
+                }
+            };
+        }
+        retval
+    }
+
+    /// If the entry function qualifies for exhaustive enumeration of its
+    /// inputs (see `Config.exhaustive_threshold_bits`), start enumerating them
+    /// and return `Ok(true)`; `backtrack_and_continue()` will then run the
+    /// first feasible input. Otherwise, do nothing and return `Ok(false)`.
+    ///
+    /// This must be called at the start of the entry function.
+    fn start_concrete_inputs(&mut self) -> Result<bool> {
+        let threshold = self.state.config.exhaustive_threshold_bits;
+        if threshold == 0 {
+            return Ok(false);
+        }
+        let func = self.state.cur_loc.func;
+        if func.parameters.iter().any(|param| matches!(param.ty, Type::PointerType { .. })) {
+            debug!("Not enumerating concrete inputs, as function {:?} has pointer parameters", func.name);
+            return Ok(false);
+        }
+        if self.state.config.backtracking_order != BacktrackingOrder::DepthFirst {
+            debug!("Not enumerating concrete inputs, as the backtracking order is {:?}", self.state.config.backtracking_order);
+            return Ok(false);
+        }
+        // parameters which are already concrete don't need to be enumerated
+        let mut params = vec![];
+        for param in &func.parameters {
+            let bv = self.state.operand_to_bv(&Operand::LocalOperand { name: param.name.clone(), ty: param.ty.clone() })?;
+            if bv.as_binary_str().is_none() {
+                params.push(bv);
+            }
+        }
+        let total_bits: u32 = params.iter().map(|bv| bv.get_width()).sum();
+        if total_bits == 0 || total_bits > threshold || total_bits >= 64 {
+            return Ok(false);
+        }
+        info!("Enumerating all {} concrete inputs", 1u64 << total_bits);
+        self.concrete_inputs = Some(ConcreteInputs {
+            params,
+            next: 0,
+            count: 1 << total_bits,
+            base_points: self.state.count_backtracking_points(),
+        });
+        self.exhaustive_inputs.get_or_insert(0);
+        // each input starts from here
+        self.state.save_backtracking_point(&func.basic_blocks[0].name, self.state.bv_from_bool(true));
+        Ok(true)
+    }
+
+    /// Revert to the start of the entry function, and set up the next feasible
+    /// concrete input there: each parameter is constrained to its value, and
+    /// noted as a known constant, so that the function executes concretely
+    /// rather than building up symbolic expressions for the solver.
+    ///
+    /// Returns `Ok(false)`, and ends the enumeration, if no inputs remain.
+    fn next_concrete_input(&mut self) -> Result<bool> {
+        loop {
+            self.state.revert_to_backtracking_point()?;
+            let inputs = self.concrete_inputs.as_mut().expect("Should be enumerating concrete inputs");
+            if inputs.next == inputs.count {
+                debug!("Done enumerating concrete inputs");
+                self.concrete_inputs = None;
+                return Ok(false);
+            }
+            let input = inputs.next;
+            inputs.next += 1;
+            let params = inputs.params.clone();
+            self.state.save_backtracking_point(&self.entry_loc.bb.name, self.state.bv_from_bool(true));
+            debug!("Running concrete input {:#x}", input);
+            let mut shift = 0;
+            for param in &params {
+                let width = param.get_width();
+                let value = (input >> shift) & ((1 << width) - 1);
+                shift += width;
+                let constraint = param._eq(&self.state.bv_from_u64(value, width));
+                if self.state.assert_constraint(ConstraintOrigin::Concretization, &constraint)? {
+                    self.state.note_known_constant(param, value);
+                }
+            }
+            if self.state.sat()? {
+                *self.exhaustive_inputs.get_or_insert(0) += 1;
+                return Ok(true);
+            }
+            debug!("Concrete input {:#x} is infeasible", input);
+        }
+    }
+
     /// Symex from the current `Location` through the rest of the function.
     /// Returns the `ReturnValue` representing the return value of the function,
     /// or `Ok(None)` if no possible paths were found.
@@ -153,9 +681,14 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
             },
             BBInstrIndex::Terminator => num_insts,  // skip all the instructions, go right to the terminator
         };
+        let block_base = self.stats.block_base(self.state.cur_loc.bb);
         let mut first_iter = true;  // is it the first iteration of the for loop
         for (instnum, inst) in self.state.cur_loc.bb.instrs.iter().enumerate().skip(insts_to_skip) {
             self.state.cur_loc.instr = BBInstrIndex::Instr(instnum);
+            if let Some(base) = block_base {
+                self.stats.record(base + instnum);
+            }
+            self.state.count_global_ctor_instruction()?;
             self.state.cur_loc.source_loc = inst.get_debug_loc().as_ref();
             if first_iter {
                 first_iter = false;
@@ -164,11 +697,24 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
             for callback in &self.state.config.callbacks.instruction_callbacks {
                 callback(inst, &self.state)?;
             }
+            self.run_checkers(|checker, state| checker.check_instruction(inst, state))?;
+            if let Instruction::Call(call) = inst {
+                self.run_checkers(|checker, state| checker.check_call(call, state))?;
+            }
             let result = if let Ok(binop) = inst.clone().try_into() {
                 self.symex_binop(&binop)
             } else {
                 match inst {
                     Instruction::ICmp(icmp) => self.symex_icmp(icmp),
+                    Instruction::FCmp(_)
+                    | Instruction::FNeg(_)
+                    | Instruction::FPToSI(_)
+                    | Instruction::FPToUI(_)
+                    | Instruction::SIToFP(_)
+                    | Instruction::UIToFP(_)
+                    | Instruction::FPExt(_)
+                    | Instruction::FPTrunc(_)
+                        => self.symex_fp_instruction(inst),
                     Instruction::Load(load) => self.symex_load(load),
                     Instruction::Store(store) => self.symex_store(store),
                     Instruction::GetElementPtr(gep) => self.symex_gep(gep),
@@ -181,8 +727,8 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                     Instruction::ZExt(zext) => self.symex_zext(zext),
                     Instruction::SExt(sext) => self.symex_sext(sext),
                     Instruction::Trunc(trunc) => self.symex_trunc(trunc),
-                    Instruction::PtrToInt(pti) => self.symex_cast_op(pti),
-                    Instruction::IntToPtr(itp) => self.symex_cast_op(itp),
+                    Instruction::PtrToInt(pti) => self.symex_ptr_int_cast(pti),
+                    Instruction::IntToPtr(itp) => self.symex_ptr_int_cast(itp),
                     Instruction::BitCast(bitcast) => self.symex_cast_op(bitcast),
                     Instruction::Phi(phi) => self.symex_phi(phi),
                     Instruction::Select(select) => self.symex_select(select),
@@ -209,6 +755,10 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
         let term = &self.state.cur_loc.bb.term;
         self.state.cur_loc.instr = BBInstrIndex::Terminator;
         self.state.cur_loc.source_loc = term.get_debug_loc().as_ref();
+        if let Some(base) = block_base {
+            self.stats.record(base + num_insts);
+        }
+        self.state.count_global_ctor_instruction()?;
         if first_iter {
             // in this case, we did 0 iterations of the for loop, and still need to record the path entry
             self.state.record_path_entry();
@@ -221,6 +771,7 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
             Terminator::Br(br) => self.symex_br(br),
             Terminator::CondBr(condbr) => self.symex_condbr(condbr),
             Terminator::Switch(switch) => self.symex_switch(switch),
+            Terminator::IndirectBr(indirectbr) => self.symex_indirectbr(indirectbr),
             Terminator::Invoke(invoke) => self.symex_invoke(invoke),
             Terminator::Resume(resume) => self.symex_resume(resume),
             Terminator::Unreachable(_) => Err(Error::UnreachableInstruction),
@@ -235,6 +786,15 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
     /// Returns the `ReturnValue` representing the final return value, or
     /// `Ok(None)` if no possible paths were found.
     fn backtrack_and_continue(&mut self) -> Result<Option<ReturnValue<B::BV>>> {
+        if let Some(inputs) = &self.concrete_inputs {
+            if self.state.count_backtracking_points() == inputs.base_points + 1 {
+                // all that's left of the current input is the point at the
+                // start of the entry function, so move on to the next input
+                if self.next_concrete_input()? {
+                    return self.symex_from_cur_loc();
+                }
+            }
+        }
         if self.state.revert_to_backtracking_point()? {
             info!("Reverted to backtrack point; {} more backtrack points available", self.state.count_backtracking_points());
             info!("Continuing in bb {} in function {:?}{}",
@@ -366,7 +926,7 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                             ReturnValue::Abort => panic!("This case should have been handled above"),
                         };
                         // Continue execution in caller, at the normal-return label of the `Invoke` instruction
-                        self.state.cur_loc.move_to_start_of_bb_by_name(&invoke.return_label);
+                        self.state.branch_to_bb(&invoke.return_label)?;
                         self.symex_from_cur_loc()
                     }
                 },
@@ -382,6 +942,42 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
         }
     }
 
+    /// If the given instruction is a pure computation on its operands
+    /// (arithmetic, comparisons, extensions, truncations, casts, or `select`),
+    /// symex it and return `Ok(true)`. For any other instruction, do nothing and
+    /// return `Ok(false)`.
+    ///
+    /// This lets analyses which don't follow paths (such as
+    /// [`analyze::block_reaching_conditions()`](analyze/fn.block_reaching_conditions.html))
+    /// use the same translation of instructions to `BV`s as full symex.
+    pub(crate) fn symex_pure_instruction(&mut self, inst: &'p Instruction) -> Result<bool> {
+        if let Ok(binop) = inst.clone().try_into() {
+            self.symex_binop(&binop)?;
+            return Ok(true);
+        }
+        match inst {
+            Instruction::ICmp(icmp) => self.symex_icmp(icmp)?,
+            Instruction::FCmp(_)
+            | Instruction::FNeg(_)
+            | Instruction::FPToSI(_)
+            | Instruction::FPToUI(_)
+            | Instruction::SIToFP(_)
+            | Instruction::UIToFP(_)
+            | Instruction::FPExt(_)
+            | Instruction::FPTrunc(_)
+                => self.symex_fp_instruction(inst)?,
+            Instruction::ZExt(zext) => self.symex_zext(zext)?,
+            Instruction::SExt(sext) => self.symex_sext(sext)?,
+            Instruction::Trunc(trunc) => self.symex_trunc(trunc)?,
+            Instruction::PtrToInt(pti) => self.symex_ptr_int_cast(pti)?,
+            Instruction::IntToPtr(itp) => self.symex_ptr_int_cast(itp)?,
+            Instruction::BitCast(bitcast) => self.symex_cast_op(bitcast)?,
+            Instruction::Select(select) => self.symex_select(select)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
     fn binop_to_bvbinop<'a, V: BV + 'a>(bop: &instruction::groups::BinaryOp) -> Result<Box<dyn for<'b> Fn(&'b V, &'b V) -> V + 'a>> {
         match bop {
             // TODO: how to not clone the inner instruction here
@@ -398,10 +994,47 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
             instruction::groups::BinaryOp::Shl(_) => Ok(Box::new(V::sll)),
             instruction::groups::BinaryOp::LShr(_) => Ok(Box::new(V::srl)),
             instruction::groups::BinaryOp::AShr(_) => Ok(Box::new(V::sra)),
+            #[cfg(not(feature = "fp"))]
+            instruction::groups::BinaryOp::FAdd(_)
+            | instruction::groups::BinaryOp::FSub(_)
+            | instruction::groups::BinaryOp::FMul(_)
+            | instruction::groups::BinaryOp::FDiv(_)
+                => Err(Error::UnsupportedInstruction(format!("BinaryOp {:?} (floating-point support requires the `fp` feature)", bop))),
             _ => Err(Error::UnsupportedInstruction(format!("BinaryOp {:?}", bop))),
         }
     }
 
+    #[cfg(feature = "fp")]
+    #[allow(clippy::type_complexity)]
+    fn binop_to_fpbinop(bop: &instruction::groups::BinaryOp) -> Option<fn(&B::BV, &B::BV, FloatFormat, RoundingMode) -> B::BV> {
+        match bop {
+            instruction::groups::BinaryOp::FAdd(_) => Some(float::fadd),
+            instruction::groups::BinaryOp::FSub(_) => Some(float::fsub),
+            instruction::groups::BinaryOp::FMul(_) => Some(float::fmul),
+            instruction::groups::BinaryOp::FDiv(_) => Some(float::fdiv),
+            _ => None,
+        }
+    }
+
+    fn binop_to_opcode(bop: &instruction::groups::BinaryOp) -> Option<BinaryOpcode> {
+        match bop {
+            instruction::groups::BinaryOp::Add(_) => Some(BinaryOpcode::Add),
+            instruction::groups::BinaryOp::Sub(_) => Some(BinaryOpcode::Sub),
+            instruction::groups::BinaryOp::Mul(_) => Some(BinaryOpcode::Mul),
+            instruction::groups::BinaryOp::UDiv(_) => Some(BinaryOpcode::UDiv),
+            instruction::groups::BinaryOp::SDiv(_) => Some(BinaryOpcode::SDiv),
+            instruction::groups::BinaryOp::URem(_) => Some(BinaryOpcode::URem),
+            instruction::groups::BinaryOp::SRem(_) => Some(BinaryOpcode::SRem),
+            instruction::groups::BinaryOp::And(_) => Some(BinaryOpcode::And),
+            instruction::groups::BinaryOp::Or(_) => Some(BinaryOpcode::Or),
+            instruction::groups::BinaryOp::Xor(_) => Some(BinaryOpcode::Xor),
+            instruction::groups::BinaryOp::Shl(_) => Some(BinaryOpcode::Shl),
+            instruction::groups::BinaryOp::LShr(_) => Some(BinaryOpcode::LShr),
+            instruction::groups::BinaryOp::AShr(_) => Some(BinaryOpcode::AShr),
+            _ => None,
+        }
+    }
+
     fn intpred_to_bvpred(pred: IntPredicate) -> Box<dyn Fn(&B::BV, &B::BV) -> B::BV + 'p> {
         match pred {
             IntPredicate::EQ => Box::new(B::BV::_eq),
@@ -453,7 +1086,8 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
 
     fn symex_binop(&mut self, bop: &instruction::groups::BinaryOp) -> Result<()> {
         debug!("Symexing binop {:?}", bop);
-        // We expect these binops to only operate on integers or vectors of integers
+        // We expect these binops to only operate on integers or vectors of integers,
+        // except for the floating-point ones, which operate on floats or vectors of floats
         let op0 = &bop.get_operand0();
         let op1 = &bop.get_operand1();
         let op0_type = op0.get_type();
@@ -464,10 +1098,25 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
         let op_type = op0_type;
         let bvop0 = self.state.operand_to_bv(op0)?;
         let bvop1 = self.state.operand_to_bv(op1)?;
+        #[cfg(feature = "fp")]
+        if let Some(fpoperation) = Self::binop_to_fpbinop(bop) {
+            let rm = self.state.config.fp_rounding_mode;
+            let (fmt, num_elements) = Self::fp_format_of(&op_type)?;
+            let result = match num_elements {
+                None => fpoperation(&bvop0, &bvop1, fmt, rm),
+                Some(num_elements) => Self::binary_on_vector(&bvop0, &bvop1, num_elements, |a, b| fpoperation(a, b, fmt, rm))?,
+            };
+            return self.state.record_bv_result(bop, result);
+        }
         let bvoperation = Self::binop_to_bvbinop(bop)?;
         match op_type {
             Type::IntegerType { .. } => {
-                self.state.record_bv_result(bop, bvoperation(&bvop0, &bvop1))
+                let result = bvoperation(&bvop0, &bvop1);
+                let result = match Self::binop_to_opcode(bop) {
+                    Some(opcode) => self.state.rewrite(Expr::Binary(opcode, bvop0, bvop1), result)?,
+                    None => result,
+                };
+                self.state.record_bv_result(bop, result)
             },
             Type::VectorType { element_type, num_elements } => {
                 match *element_type {
@@ -494,7 +1143,20 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
         match icmp.get_type() {
             Type::IntegerType { bits } if bits == 1 => match op0_type {
                 Type::IntegerType { .. } | Type::VectorType { .. } | Type::PointerType { .. } => {
-                    self.state.record_bv_result(icmp, bvpred(&bvfirstop, &bvsecondop))
+                    let bvresult = bvpred(&bvfirstop, &bvsecondop);
+                    let is_eq = match icmp.predicate {
+                        IntPredicate::EQ => Some(true),
+                        IntPredicate::NE => Some(false),
+                        _ => None,
+                    };
+                    if let Some(is_eq) = is_eq {
+                        match (bvfirstop.as_u64(), bvsecondop.as_u64()) {
+                            (None, Some(value)) => self.state.note_equality_condition(&bvresult, &bvfirstop, value, is_eq),
+                            (Some(value), None) => self.state.note_equality_condition(&bvresult, &bvsecondop, value, is_eq),
+                            _ => {},
+                        }
+                    }
+                    self.state.record_bv_result(icmp, bvresult)
                 },
                 ty => Err(Error::MalformedInstruction(format!("Expected ICmp to have operands of type integer, pointer, or vector of integers, but got type {:?}", ty))),
             },
@@ -514,6 +1176,118 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
         }
     }
 
+    /// Symex `fcmp`, `fneg`, or a conversion to or from a floating-point type
+    #[cfg(feature = "fp")]
+    fn symex_fp_instruction(&mut self, inst: &'p Instruction) -> Result<()> {
+        match inst {
+            Instruction::FCmp(fcmp) => self.symex_fcmp(fcmp),
+            Instruction::FNeg(fneg) => self.symex_fneg(fneg),
+            Instruction::FPToSI(fptosi) => self.symex_fp_to_int(fptosi),
+            Instruction::FPToUI(fptoui) => self.symex_fp_to_int(fptoui),
+            Instruction::SIToFP(sitofp) => self.symex_int_to_fp(sitofp, true),
+            Instruction::UIToFP(uitofp) => self.symex_int_to_fp(uitofp, false),
+            Instruction::FPExt(fpext) => self.symex_fp_convert(fpext),
+            Instruction::FPTrunc(fptrunc) => self.symex_fp_convert(fptrunc),
+            _ => Err(Error::OtherError(format!("symex_fp_instruction: not a floating-point instruction: {:?}", inst))),
+        }
+    }
+
+    #[cfg(not(feature = "fp"))]
+    fn symex_fp_instruction(&mut self, inst: &'p Instruction) -> Result<()> {
+        Err(Error::UnsupportedInstruction(format!("instruction {:?} (floating-point support requires the `fp` feature)", inst)))
+    }
+
+    /// Get the format of the given floating-point type, or of the elements of
+    /// the given vector of floating-point values, along with the number of
+    /// elements for vectors
+    #[cfg(feature = "fp")]
+    fn fp_format_of(ty: &Type) -> Result<(FloatFormat, Option<u32>)> {
+        match ty {
+            Type::FPType(fpt) => Ok((FloatFormat::from_fptype(*fpt)?, None)),
+            Type::VectorType { element_type, num_elements } => match &**element_type {
+                Type::FPType(fpt) => Ok((FloatFormat::from_fptype(*fpt)?, Some(*num_elements as u32))),
+                ty => Err(Error::MalformedInstruction(format!("Expected a vector of floating-point values, but elements are type {:?}", ty))),
+            },
+            ty => Err(Error::MalformedInstruction(format!("Expected a floating-point type or vector of floating-point values, but got type {:?}", ty))),
+        }
+    }
+
+    /// Apply the given scalar operation to `bv`, or to each of its elements if
+    /// it's a vector of `num_elements` elements
+    #[cfg(feature = "fp")]
+    fn unary_on_scalar_or_vector<F>(bv: &B::BV, num_elements: Option<u32>, mut op: F) -> Result<B::BV>
+        where F: FnMut(&B::BV) -> B::BV
+    {
+        match num_elements {
+            None => Ok(op(bv)),
+            Some(num_elements) => Self::unary_on_vector(bv, num_elements, op),
+        }
+    }
+
+    #[cfg(feature = "fp")]
+    fn symex_fcmp(&mut self, fcmp: &'p instruction::FCmp) -> Result<()> {
+        debug!("Symexing fcmp {:?}", fcmp);
+        let op0_type = fcmp.operand0.get_type();
+        let op1_type = fcmp.operand1.get_type();
+        if op0_type != op1_type {
+            return Err(Error::MalformedInstruction(format!("Expected fcmp to compare two operands of same type, but have types {:?} and {:?}", op0_type, op1_type)));
+        }
+        let (fmt, num_elements) = Self::fp_format_of(&op0_type)?;
+        let bvfirstop = self.state.operand_to_bv(&fcmp.operand0)?;
+        let bvsecondop = self.state.operand_to_bv(&fcmp.operand1)?;
+        let result = match num_elements {
+            None => float::fcmp(&bvfirstop, &bvsecondop, fmt, fcmp.predicate),
+            Some(num_elements) => Self::binary_on_vector(&bvfirstop, &bvsecondop, num_elements, |a, b| float::fcmp(a, b, fmt, fcmp.predicate))?,
+        };
+        self.state.record_bv_result(fcmp, result)
+    }
+
+    #[cfg(feature = "fp")]
+    fn symex_fneg(&mut self, fneg: &'p instruction::FNeg) -> Result<()> {
+        debug!("Symexing fneg {:?}", fneg);
+        let (fmt, num_elements) = Self::fp_format_of(&fneg.operand.get_type())?;
+        let bvop = self.state.operand_to_bv(&fneg.operand)?;
+        let result = Self::unary_on_scalar_or_vector(&bvop, num_elements, |el| float::fneg(el, fmt))?;
+        self.state.record_bv_result(fneg, result)
+    }
+
+    /// `fptosi` and `fptoui`
+    #[cfg(feature = "fp")]
+    fn symex_fp_to_int(&mut self, cast: &'p impl instruction::UnaryOp) -> Result<()> {
+        debug!("Symexing fp-to-int cast {:?}", cast);
+        let (fmt, num_elements) = Self::fp_format_of(&cast.get_operand().get_type())?;
+        let bits = match cast.get_type() {
+            Type::VectorType { element_type, .. } => size(&element_type) as u32,
+            ty => size(&ty) as u32,
+        };
+        let bvop = self.state.operand_to_bv(cast.get_operand())?;
+        let result = Self::unary_on_scalar_or_vector(&bvop, num_elements, |el| float::fp_to_int(el, fmt, bits))?;
+        self.state.record_bv_result(cast, result)
+    }
+
+    /// `sitofp` and `uitofp`
+    #[cfg(feature = "fp")]
+    fn symex_int_to_fp(&mut self, cast: &'p impl instruction::UnaryOp, signed: bool) -> Result<()> {
+        debug!("Symexing int-to-fp cast {:?}", cast);
+        let rm = self.state.config.fp_rounding_mode;
+        let (fmt, num_elements) = Self::fp_format_of(&cast.get_type())?;
+        let bvop = self.state.operand_to_bv(cast.get_operand())?;
+        let result = Self::unary_on_scalar_or_vector(&bvop, num_elements, |el| float::int_to_fp(el, signed, fmt, rm))?;
+        self.state.record_bv_result(cast, result)
+    }
+
+    /// `fpext` and `fptrunc`
+    #[cfg(feature = "fp")]
+    fn symex_fp_convert(&mut self, cast: &'p impl instruction::UnaryOp) -> Result<()> {
+        debug!("Symexing fp conversion {:?}", cast);
+        let rm = self.state.config.fp_rounding_mode;
+        let (from, num_elements) = Self::fp_format_of(&cast.get_operand().get_type())?;
+        let (to, _) = Self::fp_format_of(&cast.get_type())?;
+        let bvop = self.state.operand_to_bv(cast.get_operand())?;
+        let result = Self::unary_on_scalar_or_vector(&bvop, num_elements, |el| float::fp_convert(el, from, to, rm))?;
+        self.state.record_bv_result(cast, result)
+    }
+
     fn symex_zext(&mut self, zext: &'p instruction::ZExt) -> Result<()> {
         debug!("Symexing zext {:?}", zext);
         match zext.operand.get_type() {
@@ -521,7 +1295,10 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                 let bvop = self.state.operand_to_bv(&zext.operand)?;
                 let source_size = bits;
                 let dest_size = size(&zext.get_type()) as u32;
-                self.state.record_bv_result(zext, bvop.zext(dest_size - source_size))
+                let result = bvop.zext(dest_size - source_size);
+                let result = self.state.rewrite(Expr::ZExt(bvop.clone()), result)?;
+                self.state.propagate_tag(&bvop, &result);
+                self.state.record_bv_result(zext, result)
             },
             Type::VectorType { element_type, num_elements } => {
                 let in_vector = self.state.operand_to_bv(&zext.operand)?;
@@ -551,7 +1328,10 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                 let bvop = self.state.operand_to_bv(&sext.operand)?;
                 let source_size = bits;
                 let dest_size = size(&sext.get_type()) as u32;
-                self.state.record_bv_result(sext, bvop.sext(dest_size - source_size))
+                let result = bvop.sext(dest_size - source_size);
+                let result = self.state.rewrite(Expr::SExt(bvop.clone()), result)?;
+                self.state.propagate_tag(&bvop, &result);
+                self.state.record_bv_result(sext, result)
             },
             Type::VectorType { element_type, num_elements } => {
                 let in_vector = self.state.operand_to_bv(&sext.operand)?;
@@ -580,7 +1360,10 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
             Type::IntegerType { .. } => {
                 let bvop = self.state.operand_to_bv(&trunc.operand)?;
                 let dest_size = size(&trunc.get_type()) as u32;
-                self.state.record_bv_result(trunc, bvop.slice(dest_size-1, 0))
+                let result = bvop.slice(dest_size-1, 0);
+                let result = self.state.rewrite(Expr::Extract { operand: bvop.clone(), high: dest_size-1, low: 0 }, result)?;
+                self.state.propagate_tag(&bvop, &result);
+                self.state.record_bv_result(trunc, result)
             },
             Type::VectorType { num_elements, .. } => {
                 let in_vector = self.state.operand_to_bv(&trunc.operand)?;
@@ -607,18 +1390,45 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
         self.state.record_bv_result(cast, bvop)  // from Boolector's perspective a cast is simply a no-op; the bit patterns are equal
     }
 
+    /// `ptrtoint` and `inttoptr` are no-ops too, except that they truncate or
+    /// zero-extend if the integer type isn't pointer-sized
+    fn symex_ptr_int_cast(&mut self, cast: &'p impl instruction::UnaryOp) -> Result<()> {
+        debug!("Symexing ptr/int cast {:?}", cast);
+        let bvop = self.state.operand_to_bv(cast.get_operand())?;
+        let ty = cast.get_type();
+        let bits = size(&ty) as u32;
+        if let Type::VectorType { .. } = ty {
+            if bits != bvop.get_width() {
+                return Err(Error::UnsupportedInstruction(format!("{:?} on a vector of non-pointer-sized integers", cast)));
+            }
+        }
+        self.state.record_bv_result(cast, bvop.zero_extend_or_truncate_to_bits(bits))
+    }
+
     fn symex_load(&mut self, load: &'p instruction::Load) -> Result<()> {
         debug!("Symexing load {:?}", load);
-        let bvaddr = self.state.operand_to_bv(&load.address)?;
         let dest_size = size(&load.get_type());
-        self.state.record_bv_result(load, self.state.read(&bvaddr, dest_size as u32)?)
+        if self.state.config.approximation == Approximation::HavocMemory {
+            return self.state.new_bv_with_name(load.dest.clone(), dest_size as u32).map(|_| ());
+        }
+        let bvaddr = self.state.operand_to_bv(&load.address)?;
+        let bvaddr = self.state.resolve_address(&bvaddr)?;
+        self.run_checkers(|checker, state| checker.check_memory_access(&bvaddr, dest_size as u32, None, state))?;
+        let result = self.state.read_value(&bvaddr, &load.get_type())?;
+        let result = self.state.rewrite(Expr::Load { addr: bvaddr, bits: dest_size as u32 }, result)?;
+        self.state.record_bv_result(load, result)
     }
 
     fn symex_store(&mut self, store: &'p instruction::Store) -> Result<()> {
         debug!("Symexing store {:?}", store);
+        if self.state.config.approximation == Approximation::HavocMemory {
+            return Ok(());
+        }
         let bvval = self.state.operand_to_bv(&store.value)?;
         let bvaddr = self.state.operand_to_bv(&store.address)?;
-        self.state.write(&bvaddr, bvval)
+        let bvaddr = self.state.resolve_address(&bvaddr)?;
+        self.run_checkers(|checker, state| checker.check_memory_access(&bvaddr, bvval.get_width(), Some(&bvval), state))?;
+        self.state.write_value(&bvaddr, bvval, &store.value.get_type())
     }
 
     fn symex_gep(&mut self, gep: &'p instruction::GetElementPtr) -> Result<()> {
@@ -627,7 +1437,9 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
             Type::PointerType { .. } => {
                 let bvbase = self.state.operand_to_bv(&gep.address)?;
                 let offset = Self::get_offset_recursive(&self.state, gep.indices.iter(), &gep.address.get_type(), bvbase.get_width())?;
-                self.state.record_bv_result(gep, bvbase.add(&offset))
+                let result = bvbase.add(&offset);
+                let result = self.state.rewrite(Expr::Binary(BinaryOpcode::Add, bvbase, offset), result)?;
+                self.state.record_bv_result(gep, result)
             },
             Type::VectorType { .. } => Err(Error::UnsupportedInstruction("GEP calculating a vector of pointers".to_owned())),
             ty => Err(Error::MalformedInstruction(format!("Expected GEP result type to be pointer or vector of pointers; got {:?}", ty))),
@@ -680,24 +1492,71 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
 
     fn symex_alloca(&mut self, alloca: &'p instruction::Alloca) -> Result<()> {
         debug!("Symexing alloca {:?}", alloca);
-        match &alloca.num_elements {
-            Operand::ConstantOperand(Constant::Int { value: num_elements, .. }) => {
-                let allocation_size_bits = {
-                    let element_size_bits = size_opaque_aware(&alloca.allocated_type, self.project)
-                        .expect("Alloca with type which is opaque in the entire Project");
-                    element_size_bits as u64 * num_elements
-                };
-                let allocation_size_bits = if allocation_size_bits == 0 {
-                    debug!("Alloca is for something of size 0 bits; we'll give it 8 bits anyway");
-                    8
-                } else {
-                    allocation_size_bits
-                };
-                let allocated = self.state.allocate(allocation_size_bits);
-                self.state.record_bv_result(alloca, allocated)
+        let element_size_bits = size_opaque_aware(&alloca.allocated_type, self.project)
+            .expect("Alloca with type which is opaque in the entire Project") as u64;
+        let (num_elements, dynamic_num_elements) = match &alloca.num_elements {
+            Operand::ConstantOperand(Constant::Int { value: num_elements, .. }) => (*num_elements, None),
+            op => self.alloca_num_elements(op, element_size_bits)?,
+        };
+        let allocation_size_bits = element_size_bits * num_elements;
+        let allocation_size_bits = if allocation_size_bits == 0 {
+            debug!("Alloca is for something of size 0 bits; we'll give it 8 bits anyway");
+            8
+        } else {
+            allocation_size_bits
+        };
+        let allocated = self.state.allocate(allocation_size_bits);
+        let label = match &alloca.dest {
+            Name::Name(name) => format!("%{}", name),
+            Name::Number(n) => format!("%{}", n),
+        };
+        let ty = match num_elements {
+            1 => alloca.allocated_type.clone(),
+            _ => Type::ArrayType { element_type: Box::new(alloca.allocated_type.clone()), num_elements: num_elements as usize },
+        };
+        self.state.label_allocation(&allocated, label, Some(ty))?;
+        match dynamic_num_elements {
+            None => self.state.record_stack_allocation(allocated.as_u64().unwrap(), allocation_size_bits.div_ceil(8), alloca.alignment),
+            Some(num_elements) => {
+                // the stack usage is the actual size, though we allocate enough for the largest
+                let bits = num_elements.mul(&self.state.bv_from_u64(element_size_bits, 64));
+                let bytes = bits.add(&self.state.bv_from_u64(7, 64)).udiv(&self.state.bv_from_u64(8, 64));
+                self.state.record_dynamic_stack_allocation(allocated.as_u64().unwrap(), &bytes, alloca.alignment);
             },
-            op => Err(Error::UnsupportedInstruction(format!("Alloca with num_elements not a constant int: {:?}", op))),
         }
+        self.state.mark_uninitialized(&allocated, allocation_size_bits.div_ceil(8) as u32 * 8)?;
+        self.state.record_bv_result(alloca, allocated)
+    }
+
+    /// Get the number of elements to allocate space for, for an `alloca` with
+    /// the given (non-constant) `num_elements`: the largest number it may be.
+    /// As for `malloc`s of unknown size, we assume allocations don't exceed
+    /// `alloc_utils::MAX_ALLOCATION_SIZE_BYTES`, constraining `num_elements`
+    /// accordingly if necessary.
+    ///
+    /// Also returns `num_elements` itself, zero-extended to 64 bits, if it may
+    /// have more than one value.
+    fn alloca_num_elements(&mut self, num_elements: &Operand, element_size_bits: u64) -> Result<(u64, Option<B::BV>)> {
+        let num_elements = self.state.operand_to_bv(num_elements)?;
+        if let Some(n) = num_elements.as_u64() {
+            return Ok((n, None));
+        }
+        if num_elements.get_width() > 64 {
+            return Err(Error::UnsupportedInstruction(format!("alloca with a {}-bit number of elements", num_elements.get_width())));
+        }
+        let num_elements = num_elements.zero_extend_to_bits(64);
+        let max_elements = alloc_utils::MAX_ALLOCATION_SIZE_BYTES * 8 / std::cmp::max(element_size_bits, 1);
+        let max_elements_bv = self.state.bv_from_u64(max_elements, 64);
+        let in_bounds = num_elements.ulte(&max_elements_bv);
+        if !self.state.sat_with_extra_constraints(std::iter::once(&in_bounds))? {
+            return Err(Error::OtherError(format!("Encountered an alloca whose number of elements is always more than {}, the most which fit in {} bytes", max_elements, alloc_utils::MAX_ALLOCATION_SIZE_BYTES)));
+        }
+        if self.state.sat_with_extra_constraints(std::iter::once(&in_bounds.not()))? {
+            warn!("Encountered an alloca whose number of elements may be more than {}, the most which fit in {} bytes. Constraining it to be at most {}.", max_elements, alloc_utils::MAX_ALLOCATION_SIZE_BYTES, max_elements);
+            self.state.assert_constraint(ConstraintOrigin::Concretization, &in_bounds)?;
+        }
+        let max = self.state.max_possible_solution_for_bv_as_u64(&num_elements)?.ok_or(Error::Unsat)?;
+        Ok((max, Some(num_elements)))
     }
 
     fn symex_extractelement(&mut self, ee: &'p instruction::ExtractElement) -> Result<()> {
@@ -764,17 +1623,22 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
         };
         match op_type {
             Type::VectorType { element_type, num_elements } => {
-                let mask: Vec<u32> = match &sv.mask {
+                // `None` for `undef` mask entries, whose result elements are `undef`
+                let mask: Vec<Option<u32>> = match &sv.mask {
                     Constant::Vector(mask) => mask.iter()
                         .map(|c| match c {
-                            Constant::Int { value: idx, .. } => Ok(*idx as u32),
-                            Constant::Undef(_) => Ok(0),
+                            Constant::Int { value: idx, .. } => Ok(Some(*idx as u32)),
+                            Constant::Undef(_) => Ok(None),
                             _ => Err(Error::UnsupportedInstruction(format!("ShuffleVector with a mask entry which is not a Constant::Int or Constant::Undef but instead {:?}", c))),
                         })
-                        .collect::<Result<Vec<u32>>>()?,
-                    Constant::AggregateZero(ty) | Constant::Undef(ty) => match ty {
-                        Type::VectorType { num_elements, .. } => itertools::repeat_n(0, *num_elements).collect(),
-                        _ => return Err(Error::MalformedInstruction(format!("Expected ShuffleVector mask (which is an AggregateZero or Undef) to have vector type, but its type is {:?}", ty))),
+                        .collect::<Result<Vec<Option<u32>>>>()?,
+                    Constant::AggregateZero(ty) => match ty {
+                        Type::VectorType { num_elements, .. } => itertools::repeat_n(Some(0), *num_elements).collect(),
+                        _ => return Err(Error::MalformedInstruction(format!("Expected ShuffleVector mask (which is an AggregateZero) to have vector type, but its type is {:?}", ty))),
+                    },
+                    Constant::Undef(ty) => match ty {
+                        Type::VectorType { num_elements, .. } => itertools::repeat_n(None, *num_elements).collect(),
+                        _ => return Err(Error::MalformedInstruction(format!("Expected ShuffleVector mask (which is an Undef) to have vector type, but its type is {:?}", ty))),
                     },
                     c => return Err(Error::MalformedInstruction(format!("Expected ShuffleVector mask to be a Constant::Vector, Constant::AggregateZero, or Constant::Undef, but got {:?}", c))),
                 };
@@ -787,14 +1651,17 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                 let num_elements = num_elements as u32;
                 assert_eq!(op0.get_width(), el_size * num_elements);
                 let final_bv = mask.into_iter()
-                    .map(|idx| {
-                        if idx < num_elements {
-                            op0.slice((idx+1) * el_size - 1, idx * el_size)
-                        } else {
+                    .map(|idx| match idx {
+                        None => self.state.const_to_bv(&Constant::Undef((*element_type).clone())),
+                        Some(idx) if idx < num_elements => Ok(op0.slice((idx+1) * el_size - 1, idx * el_size)),
+                        Some(idx) if idx < 2 * num_elements => {
                             let idx = idx - num_elements;
-                            op1.slice((idx+1) * el_size - 1, idx * el_size)
-                        }
+                            Ok(op1.slice((idx+1) * el_size - 1, idx * el_size))
+                        },
+                        Some(idx) => Err(Error::MalformedInstruction(format!("ShuffleVector mask entry {} out of range for two vectors of {} elements", idx, num_elements))),
                     })
+                    .collect::<Result<Vec<_>>>()?
+                    .into_iter()
                     .reduce(|a,b| b.concat(&a)).ok_or_else(|| Error::MalformedInstruction("ShuffleVector mask had 0 elements".to_owned()))?;
                 self.state.record_bv_result(sv, final_bv)
             },
@@ -943,8 +1810,19 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                     Some(max_depth) => self.state.current_callstack_depth() >= max_depth,
                     None => false,
                 };
-                if at_max_callstack_depth {
-                    info!("Ignoring a call to function {:?} due to max_callstack_len setting (current callstack depth is {}, max is {})", called_funcname, self.state.current_callstack_depth(), self.state.config.max_callstack_depth.unwrap());
+                let callee = self.state.get_func_by_name(called_funcname);
+                let descent_blocked = match (callee, &self.state.config.descend_filter) {
+                    (Some((callee, callee_mod)), Some(descend_filter)) => !descend_filter(callee, callee_mod),
+                    _ => false,
+                };
+                if descent_blocked {
+                    info!("Not descending into function {:?} due to Config.descend_filter; treating it as external", called_funcname);
+                }
+                if at_max_callstack_depth || (descent_blocked && self.state.config.function_hooks.get_default_hook().is_none()) {
+                    if at_max_callstack_depth {
+                        info!("Ignoring a call to function {:?} due to max_callstack_len setting (current callstack depth is {}, max is {})", called_funcname, self.state.current_callstack_depth(), self.state.config.max_callstack_depth.unwrap());
+                        self.callstack_depth_hits += 1;
+                    }
                     match call.get_type() {
                         Type::VoidType => {},
                         ty => {
@@ -954,7 +1832,7 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                         },
                     }
                     Ok(None)
-                } else if let Some((callee, callee_mod)) = self.state.get_func_by_name(called_funcname) {
+                } else if let Some((callee, callee_mod)) = callee.filter(|_| !descent_blocked) {
                     if call.arguments.len() != callee.parameters.len() {
                         if callee.is_var_arg {
                             return Err(Error::UnsupportedInstruction(format!("Call of a function named {:?} which is variadic", callee.name)));
@@ -1046,7 +1924,20 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
             Either::Right(Operand::ConstantOperand(Constant::GlobalReference { name: Name::Name(name), .. })) => Either::Left(name),
             Either::Right(Operand::ConstantOperand(Constant::GlobalReference { name, .. })) => panic!("Function with a numbered name: {:?}", name),
             Either::Right(operand) => {
-                match self.state.interpret_as_function_ptr(self.state.operand_to_bv(&operand)?, 1)? {
+                let bv = self.state.operand_to_bv(&operand)?;
+                let callables = match self.state.interpret_as_function_ptr(bv.clone(), 1)? {
+                    PossibleSolutions::AtLeast(_) => {
+                        // The function pointer has multiple possible targets (e.g., a virtual call where
+                        // the object's dynamic type is symbolic); explore each target on its own path
+                        let max_targets = self.state.config.max_function_ptr_targets;
+                        if max_targets <= 1 || self.state.split_on_function_ptr(&bv, max_targets)?.is_none() {
+                            return Err(Error::OtherError(format!("calling a function pointer which has more than {} possible targets (see Config.max_function_ptr_targets)", max_targets)));
+                        }
+                        self.state.interpret_as_function_ptr(bv, 1)?
+                    },
+                    callables => callables,
+                };
+                match callables {
                     PossibleSolutions::AtLeast(_) => return Err(Error::OtherError("calling a function pointer which has multiple possible targets".to_owned())),  // there must be at least 2 targets since we passed n==1 to `interpret_as_function_ptr`
                     PossibleSolutions::Exactly(v) => match v.iter().next() {
                         None => return Err(Error::Unsat),  // no valid solutions for the function pointer
@@ -1158,8 +2049,32 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                             hook: self.state.intrinsic_hooks.get_hook_for("intrinsic: generic_stub_hook").cloned().expect("Failed to find intrinsic generic stub hook"),
                             hooked_thing: HookedThing::Intrinsic(funcname),
                         })
-                    } else if funcname.starts_with("llvm.lifetime")
-                        || funcname.starts_with("llvm.invariant")
+                    } else if funcname.starts_with("llvm.fmuladd")
+                        || funcname.starts_with("llvm.fma.")
+                    {
+                        let hook_name = if funcname.starts_with("llvm.fmuladd") { "intrinsic: llvm.fmuladd" } else { "intrinsic: llvm.fma" };
+                        match self.state.intrinsic_hooks.get_hook_for(hook_name) {
+                            Some(hook) => Ok(ResolvedFunction::HookActive {
+                                hook: hook.clone(),
+                                hooked_thing: HookedThing::Intrinsic(funcname),
+                            }),
+                            // these hooks only exist with the `fp` feature
+                            None => Err(Error::UnsupportedInstruction(format!("call of {} (floating-point support requires the `fp` feature)", funcname))),
+                        }
+                    } else if funcname.starts_with("llvm.coro.") {
+                        // We don't model coroutine frames or suspension; see notes on `interface::is_coroutine()`
+                        Err(Error::UnsupportedInstruction(format!("call of {} (coroutines are not supported)", funcname)))
+                    } else if funcname.starts_with("llvm.lifetime.start") {
+                        Ok(ResolvedFunction::HookActive {
+                            hook: self.state.intrinsic_hooks.get_hook_for("intrinsic: llvm.lifetime.start").cloned().expect("Failed to find LLVM intrinsic lifetime.start hook"),
+                            hooked_thing: HookedThing::Intrinsic(funcname),
+                        })
+                    } else if funcname.starts_with("llvm.lifetime.end") {
+                        Ok(ResolvedFunction::HookActive {
+                            hook: self.state.intrinsic_hooks.get_hook_for("intrinsic: llvm.lifetime.end").cloned().expect("Failed to find LLVM intrinsic lifetime.end hook"),
+                            hooked_thing: HookedThing::Intrinsic(funcname),
+                        })
+                    } else if funcname.starts_with("llvm.invariant")
                         || funcname.starts_with("llvm.launder.invariant")
                         || funcname.starts_with("llvm.strip.invariant")
                         || funcname.starts_with("llvm.dbg")
@@ -1169,6 +2084,32 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                             hook: self.state.intrinsic_hooks.get_hook_for("intrinsic: generic_stub_hook").cloned().expect("Failed to find intrinsic generic stub hook"),
                             hooked_thing: HookedThing::Intrinsic(funcname),
                         })
+                    } else if funcname == "__asan_memcpy" || funcname == "__asan_memmove" {
+                        Ok(ResolvedFunction::HookActive {
+                            hook: self.state.intrinsic_hooks.get_hook_for("intrinsic: llvm.memcpy/memmove").cloned().expect("Failed to find LLVM intrinsic memcpy/memmove hook"),
+                            hooked_thing: HookedThing::Function(funcname),
+                        })
+                    } else if funcname == "__asan_memset" {
+                        Ok(ResolvedFunction::HookActive {
+                            hook: self.state.intrinsic_hooks.get_hook_for("intrinsic: llvm.memset").cloned().expect("Failed to find LLVM intrinsic memset hook"),
+                            hooked_thing: HookedThing::Function(funcname),
+                        })
+                    } else if funcname.starts_with("__asan_")
+                        || (funcname.starts_with("__ubsan_handle_") && !funcname.ends_with("_abort"))
+                    {
+                        // The sanitizers' own checks are ordinary code, which we execute; their runtime
+                        // calls only report or bookkeep, so they can be no-ops. Reaching a UBSan handler
+                        // is reported by the `checkers::SanitizerChecks` checker, if it's enabled.
+                        Ok(ResolvedFunction::HookActive {
+                            hook: self.state.intrinsic_hooks.get_hook_for("intrinsic: generic_stub_hook").cloned().expect("Failed to find intrinsic generic stub hook"),
+                            hooked_thing: HookedThing::Function(funcname),
+                        })
+                    } else if funcname == "__stack_chk_fail" || funcname.starts_with("__ubsan_handle_") {
+                        // these don't return
+                        Ok(ResolvedFunction::HookActive {
+                            hook: self.state.intrinsic_hooks.get_hook_for("intrinsic: abort_hook").cloned().expect("Failed to find intrinsic abort hook"),
+                            hooked_thing: HookedThing::Function(funcname),
+                        })
                     } else {
                         // No hook currently defined for this function, and none of our intrinsic hooks apply
                         Ok(ResolvedFunction::NoHookActive { called_funcname: funcname })
@@ -1232,7 +2173,7 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
     /// function), or `Ok(None)` if no possible paths were found.
     fn symex_br(&mut self, br: &'p terminator::Br) -> Result<Option<ReturnValue<B::BV>>> {
         debug!("Symexing br {:?}", br);
-        self.state.cur_loc.move_to_start_of_bb_by_name(&br.dest);
+        self.state.branch_to_bb(&br.dest)?;
         self.symex_from_cur_loc_through_end_of_function()
     }
 
@@ -1243,24 +2184,57 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
     fn symex_condbr(&mut self, condbr: &'p terminator::CondBr) -> Result<Option<ReturnValue<B::BV>>> {
         debug!("Symexing condbr {:?}", condbr);
         let bvcond = self.state.operand_to_bv(&condbr.condition)?;
-        let true_feasible = self.state.sat_with_extra_constraints(std::iter::once(&bvcond))?;
-        let false_feasible = self.state.sat_with_extra_constraints(std::iter::once(&bvcond.not()))?;
+        if let Some(b) = bvcond.as_bool() {
+            // The condition folded to a constant, e.g. because it depends on
+            // values with known constants on this path. No need to ask the solver.
+            debug!("the branch condition is known to be {}", b);
+            let dest = if b { &condbr.true_dest } else { &condbr.false_dest };
+            self.state.branch_to_bb(dest)?;
+            return self.symex_from_cur_loc_through_end_of_function();
+        }
+        // If the solver can't tell whether a side is feasible (e.g., the query
+        // timed out), explore it anyway rather than pruning a path which may exist
+        let feasible = |result: SolverResult| match result {
+            SolverResult::Sat => true,
+            SolverResult::Unsat => false,
+            SolverResult::Unknown => {
+                warn!("solver couldn't determine feasibility of a branch in {:?}; exploring it conservatively", self.state.cur_loc.func.name);
+                true
+            },
+        };
+        let true_feasible = feasible(self.state.check_with_extra_constraints(std::iter::once(&bvcond))?);
+        let false_feasible = feasible(self.state.check_with_extra_constraints(std::iter::once(&bvcond.not()))?);
         if true_feasible && false_feasible {
             debug!("both true and false branches are feasible");
             // for now we choose to explore true first, and backtrack to false if necessary
+            self.state.log_path_condition("branch", &condbr.condition, false);
             self.state.save_backtracking_point(&condbr.false_dest, bvcond.not());
-            bvcond.assert()?;
-            self.state.cur_loc.move_to_start_of_bb_by_name(&condbr.true_dest);
+            self.state.unlog_path_condition();
+            if self.state.assert_constraint(ConstraintOrigin::BranchRefinement, &bvcond)? {
+                self.state.note_condition(&bvcond, true);
+            }
+            self.state.log_path_condition("branch", &condbr.condition, true);
+            self.state.branch_to_bb(&condbr.true_dest)?;
             self.symex_from_cur_loc_through_end_of_function()
         } else if true_feasible {
             debug!("only the true branch is feasible");
-            bvcond.assert()?;  // unnecessary, but may help Boolector more than it hurts?
-            self.state.cur_loc.move_to_start_of_bb_by_name(&condbr.true_dest);
+            self.state.diagnose_infeasible_condition("branch", &condbr.condition, false);
+            // unnecessary, but may help Boolector more than it hurts?
+            if self.state.assert_constraint(ConstraintOrigin::BranchRefinement, &bvcond)? {
+                self.state.note_condition(&bvcond, true);
+            }
+            self.state.log_path_condition("branch", &condbr.condition, true);
+            self.state.branch_to_bb(&condbr.true_dest)?;
             self.symex_from_cur_loc_through_end_of_function()
         } else if false_feasible {
             debug!("only the false branch is feasible");
-            bvcond.not().assert()?;  // unnecessary, but may help Boolector more than it hurts?
-            self.state.cur_loc.move_to_start_of_bb_by_name(&condbr.false_dest);
+            self.state.diagnose_infeasible_condition("branch", &condbr.condition, true);
+            // unnecessary, but may help Boolector more than it hurts?
+            if self.state.assert_constraint(ConstraintOrigin::BranchRefinement, &bvcond.not())? {
+                self.state.note_condition(&bvcond, false);
+            }
+            self.state.log_path_condition("branch", &condbr.condition, false);
+            self.state.branch_to_bb(&condbr.false_dest)?;
             self.symex_from_cur_loc_through_end_of_function()
         } else {
             debug!("neither branch is feasible");
@@ -1282,6 +2256,16 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                     .map(|c| (c,n))
             })
             .collect::<Result<Vec<(B::BV, &Name)>>>()?;
+        if let Some(value) = switchval.as_u64() {
+            // The switch operand folded to a constant, so we know exactly where to go
+            debug!("the switch operand is known to be {}", value);
+            let dest = dests.iter()
+                .find(|(c,_)| c.as_u64() == Some(value))
+                .map(|(_,n)| *n)
+                .unwrap_or(&switch.default_dest);
+            self.state.branch_to_bb(dest)?;
+            return self.symex_from_cur_loc_through_end_of_function();
+        }
         let feasible_dests: Vec<_> = dests.iter()
             .map(|(c,n)| {
                 self.state.bvs_can_be_equal(&c, &switchval).map(|b| (c,*n,b))
@@ -1293,29 +2277,53 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
             .collect::<Vec<(&B::BV, &Name)>>();
         if feasible_dests.is_empty() {
             // none of the dests are feasible, we will always end up in the default dest
-            self.state.cur_loc.move_to_start_of_bb_by_name(&switch.default_dest);
+            self.state.branch_to_bb(&switch.default_dest)?;
             self.symex_from_cur_loc_through_end_of_function()
         } else {
-            // make backtracking points for all but the first destination
-            for (val, name) in feasible_dests.iter().skip(1) {
-                self.state.save_backtracking_point(name, val._eq(&switchval));
-            }
+            // make backtracking points for all but the first destination.
+            // These share a single solver frame, however many cases there are.
+            let mut alternatives: Vec<(&Name, B::BV)> = feasible_dests.iter()
+                .skip(1)
+                .map(|(val, name)| (*name, val._eq(&switchval)))
+                .collect();
             // if the default dest is feasible, make a backtracking point for it
             let default_dest_constraint = dests.iter()
                 .map(|(c,_)| c._eq(&switchval).not())
                 .reduce(|a,b| a.and(&b))
                 .unwrap_or_else(|| self.state.bv_from_bool(true));  // if `dests` was empty, that's weird, but the default dest is definitely feasible
             if self.state.sat_with_extra_constraints(std::iter::once(&default_dest_constraint))? {
-                self.state.save_backtracking_point(&switch.default_dest, default_dest_constraint);
+                alternatives.push((&switch.default_dest, default_dest_constraint));
             }
+            self.state.save_backtracking_points(alternatives);
             // follow the first destination
             let (val, name) = &feasible_dests[0];
-            val._eq(&switchval).assert()?;  // unnecessary, but may help Boolector more than it hurts?
-            self.state.cur_loc.move_to_start_of_bb_by_name(name);
+            self.state.assert_constraint(ConstraintOrigin::BranchRefinement, &val._eq(&switchval))?;  // unnecessary, but may help Boolector more than it hurts?
+            self.state.branch_to_bb(name)?;
             self.symex_from_cur_loc_through_end_of_function()
         }
     }
 
+    /// Continues to the possible targets of the `IndirectBr` (saving
+    /// backtracking points for all but the first) and eventually returns the
+    /// new `ReturnValue` representing the return value of the function (when it
+    /// reaches the end of the function), or `Ok(None)` if no possible paths were
+    /// found.
+    ///
+    /// `blockaddress` constants don't record which block they refer to, and
+    /// aren't supported by `State::const_to_bv()`, so rather than evaluating
+    /// the address operand we explore all of the possible destinations.
+    fn symex_indirectbr(&mut self, indirectbr: &'p terminator::IndirectBr) -> Result<Option<ReturnValue<B::BV>>> {
+        debug!("Symexing indirectbr {:?}", indirectbr);
+        let (first, rest) = indirectbr.possible_dests.split_first()
+            .ok_or_else(|| Error::MalformedInstruction("indirectbr with no possible destinations".to_owned()))?;
+        let alternatives = rest.iter()
+            .map(|name| (name, self.state.bv_from_bool(true)))
+            .collect();
+        self.state.save_backtracking_points(alternatives);
+        self.state.branch_to_bb(first)?;
+        self.symex_from_cur_loc_through_end_of_function()
+    }
+
     /// Continues to the target of the `Invoke` and eventually returns the new
     /// `ReturnValue` representing the return value of the function (when it
     /// reaches the end of the function), or `Ok(None)` if no possible paths were
@@ -1351,7 +2359,7 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                 };
                 let old_bb_name = &self.state.cur_loc.bb.name;
                 // We had a normal return, so continue at the `return_label`
-                self.state.cur_loc.move_to_start_of_bb_by_name(&invoke.return_label);
+                self.state.branch_to_bb(&invoke.return_label)?;
                 let log_level = if quiet { log::Level::Debug } else { log::Level::Info };
                 log::log!(log_level, "Done processing hook for {}; continuing in function {:?}{} (hook was for the invoke in bb {}, now in bb {})",
                     pretty_hookedthing,
@@ -1373,6 +2381,7 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                 };
                 if at_max_callstack_depth {
                     info!("Ignoring a call to function {:?} due to max_callstack_len setting (current callstack depth is {}, max is {})", called_funcname, self.state.current_callstack_depth(), self.state.config.max_callstack_depth.unwrap());
+                    self.callstack_depth_hits += 1;
                     match invoke.get_type() {
                         Type::VoidType => {},
                         ty => {
@@ -1381,7 +2390,7 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                             self.state.assign_bv_to_name(invoke.result.clone(), bv)?;
                         },
                     }
-                    self.state.cur_loc.move_to_start_of_bb_by_name(&invoke.return_label);
+                    self.state.branch_to_bb(&invoke.return_label)?;
                     self.symex_from_cur_loc_through_end_of_function()
                 } else if let Some((callee, callee_mod)) = self.state.get_func_by_name(called_funcname) {
                     if invoke.arguments.len() != callee.parameters.len() {
@@ -1432,7 +2441,7 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
                                 ReturnValue::Abort => return Ok(Some(ReturnValue::Abort)),
                             }
                             // Returned normally, so continue at the `return_label`
-                            self.state.cur_loc.move_to_start_of_bb_by_name(&invoke.return_label);
+                            self.state.branch_to_bb(&invoke.return_label)?;
                             debug!("Completed ordinary return from invoke");
                             info!("Leaving function {:?}, continuing in caller {:?}{} (finished the invoke in bb {}, now in bb {})",
                                 called_funcname,
@@ -1522,7 +2531,7 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
     /// `bbname`: `Name` of the `landingpad` block which should catch the exception if appropriate
     fn catch_with_type_index(&mut self, thrown_ptr: &B::BV, type_index: &B::BV, bbname: &Name) -> Result<Option<ReturnValue<B::BV>>> {
         debug!("Catching exception {{{:?}, {:?}}} at bb {}", thrown_ptr, type_index, bbname);
-        self.state.cur_loc.move_to_start_of_bb_by_name(bbname);
+        self.state.branch_to_bb(bbname)?;
         let mut found_landingpad = false;
         let mut first_iter = true;  // is it the first iteration of the for loop
         for (instnum, inst) in self.state.cur_loc.bb.instrs.iter().enumerate() {
@@ -1589,18 +2598,44 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
         self.state.record_bv_result(lp, type_index.concat(thrown_ptr))
     }
 
+    /// The phis at the head of a block all read their operands before any of
+    /// them is assigned, which matters when they refer to each other. So the
+    /// first phi of a block assigns all of them, and the rest do nothing.
     fn symex_phi(&mut self, phi: &'p instruction::Phi) -> Result<()> {
         debug!("Symexing phi {:?}", phi);
+        let instrs = &self.state.cur_loc.bb.instrs;
+        let instnum = match self.state.cur_loc.instr {
+            BBInstrIndex::Instr(instnum) => instnum,
+            BBInstrIndex::Terminator => return Err(Error::MalformedInstruction("Phi in terminator position".to_owned())),
+        };
+        if instnum > 0 && matches!(instrs[instnum - 1], Instruction::Phi(_)) {
+            return Ok(());  // already assigned along with the first phi of the block
+        }
         let path = self.state.get_path();
         let prev_bb = match path.len() {
             0|1 => panic!("not yet implemented: starting in a block with Phi instructions. or error: didn't expect a Phi in function entry block"),
             len => &path[len - 2].0.bb.name,  // the last entry is our current block, so we want the one before
         };
-        let chosen_value = phi.incoming_values.iter()
-            .find(|&(_, bbname)| bbname == prev_bb)
-            .map(|(op, _)| op)
-            .ok_or_else(|| Error::OtherError(format!("Failed to find a Phi member matching previous BasicBlock. Phi incoming_values are {:?} but we were looking for {:?}", phi.incoming_values, prev_bb)))?;
-        self.state.record_bv_result(phi, self.state.operand_to_bv(&chosen_value)?)
+        let phis: Vec<&'p instruction::Phi> = instrs[instnum ..].iter()
+            .take_while(|inst| matches!(inst, Instruction::Phi(_)))
+            .filter_map(|inst| match inst {
+                Instruction::Phi(phi) => Some(phi),
+                _ => None,
+            })
+            .collect();
+        let values = phis.iter()
+            .map(|phi| {
+                let chosen_value = phi.incoming_values.iter()
+                    .find(|&(_, bbname)| bbname == prev_bb)
+                    .map(|(op, _)| op)
+                    .ok_or_else(|| Error::OtherError(format!("Failed to find a Phi member matching previous BasicBlock. Phi incoming_values are {:?} but we were looking for {:?}", phi.incoming_values, prev_bb)))?;
+                self.state.operand_to_bv(chosen_value)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (phi, value) in phis.into_iter().zip(values) {
+            self.state.record_bv_result(phi, value)?;
+        }
+        Ok(())
     }
 
     fn symex_select(&mut self, select: &'p instruction::Select) -> Result<()> {
@@ -1700,9 +2735,9 @@ impl<'p, B: Backend> ExecutionManager<'p, B> where B: 'p {
         let expected = self.state.operand_to_bv(&cmpxchg.expected)?;
         let replacement = self.state.operand_to_bv(&cmpxchg.replacement)?;
 
-        let read_value = self.state.read(&addr, expected.get_width())?;
+        let read_value = self.state.read_value(&addr, &main_ty)?;
         let match_flag = read_value._eq(&expected);
-        self.state.write(&addr, match_flag.cond_bv(&replacement, &read_value))?;
+        self.state.write_value(&addr, match_flag.cond_bv(&replacement, &read_value), &main_ty)?;
 
         self.state.record_bv_result(cmpxchg, match_flag.concat(&read_value))
     }
@@ -1755,6 +2790,7 @@ mod tests {
     //! specific solutions for function parameters and return values.
 
     use super::*;
+    use crate::test_utils::*;
     use std::fmt;
 
     type Result<T> = std::result::Result<T, String>;
@@ -3061,4 +4097,1461 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn unsupported_instruction_is_an_error() {
+        // an instruction we don't support should produce an `Err`, not a panic,
+        // so that the caller can move on to other functions or paths
+        init_logging();
+        let mut func = blank_function("test_func", vec![Name::from("test_bb")]);
+        func.basic_blocks[0].instrs.push(Instruction::VAArg(instruction::VAArg {
+            arg_list: Operand::ConstantOperand(Constant::Null(Type::pointer_to(Type::i8()))),
+            cur_type: Type::i32(),
+            dest: Name::from("x"),
+            debugloc: None,
+        }));
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, Config::default());
+        match em.next() {
+            Some(Err(Error::UnsupportedInstruction(_))) => {},
+            res => panic!("Expected an UnsupportedInstruction error, got {:?}", res),
+        }
+    }
+
+    /// `int f(int *p) { return p == NULL ? 0 : 1; }`, but comparing `ptr`
+    /// against `NULL` instead of `p`
+    fn null_check(ptr: Operand) -> Function {
+        let ret_i32 = |value| ret(int_const(32, value));
+        let mut func = blank_function("null_check", vec![Name::from("entry"), Name::from("null"), Name::from("nonnull")]);
+        func.parameters = vec![param("p", Type::pointer_to(Type::i32()))];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::EQ, ptr, Operand::ConstantOperand(Constant::Null(Type::pointer_to(Type::i32()))), "is_null"));
+        func.basic_blocks[0].term = condbr("is_null", "null", "nonnull");
+        func.basic_blocks[1].term = ret_i32(0);
+        func.basic_blocks[2].term = ret_i32(1);
+        func
+    }
+
+    #[test]
+    fn null_pointer_constants() {
+        init_logging();
+        let returned_values = |ptr| {
+            let proj = blank_project("test_mod", null_check(ptr));
+            let mut em: ExecutionManager<BtorBackend> = symex_function("null_check", &proj, Config::default());
+            let mut values: Vec<u64> = em.by_ref().map(|retval| match retval {
+                Ok(ReturnValue::Return(bv)) => bv.as_u64().unwrap(),
+                r => panic!("Unexpected return value {:?}", r),
+            }).collect();
+            values.sort_unstable();
+            values
+        };
+        // both branches are feasible for a parameter or an `undef` pointer
+        assert_eq!(returned_values(local("p", Type::pointer_to(Type::i32()))), vec![0, 1]);
+        assert_eq!(returned_values(Operand::ConstantOperand(Constant::Undef(Type::pointer_to(Type::i32())))), vec![0, 1]);
+        // but `null` is always `null`
+        assert_eq!(returned_values(Operand::ConstantOperand(Constant::Null(Type::pointer_to(Type::i32())))), vec![0]);
+    }
+
+    #[test]
+    fn branch_on_undef() {
+        init_logging();
+        // `br i1 undef, ...`
+        let mut func = null_check(Operand::ConstantOperand(Constant::Null(Type::pointer_to(Type::i32()))));
+        func.basic_blocks[0].instrs.clear();
+        if let llvm_ir::Terminator::CondBr(condbr) = &mut func.basic_blocks[0].term {
+            condbr.condition = Operand::ConstantOperand(Constant::Undef(Type::bool()));
+        }
+        let proj = blank_project("test_mod", func);
+
+        // both directions are possible
+        let mut em: ExecutionManager<BtorBackend> = symex_function("null_check", &proj, Config::default());
+        let mut values: Vec<u64> = em.by_ref().map(|retval| match retval {
+            Ok(ReturnValue::Return(bv)) => bv.as_u64().unwrap(),
+            r => panic!("Unexpected return value {:?}", r),
+        }).collect();
+        values.sort_unstable();
+        assert_eq!(values, vec![0, 1]);
+
+        // or, the use of `undef` is an error
+        let config = Config { undef_values: UndefValues::Error, ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("null_check", &proj, config);
+        assert_eq!(em.next(), Some(Err(Error::UndefUse)));
+        assert!(em.next().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "fp")]
+    fn fmuladd_semantics() {
+        init_logging();
+        // `double f() { return fmuladd(0.1, 10.0, -1.0); }`, where the product
+        // rounds to exactly 1.0, but isn't exactly 1.0
+        let double = |f: f64| Operand::ConstantOperand(Constant::Float(constant::Float::Double(f)));
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.return_type = Type::double();
+        func.basic_blocks[0].instrs.push(call("llvm.fmuladd.f64", Type::double(), vec![double(0.1), double(10.0), double(-1.0)], Some("x")));
+        func.basic_blocks[0].term = ret(local("x", Type::double()));
+        let proj = blank_project("test_mod", func);
+        let result = |fma_semantics| {
+            let config = Config { fma_semantics, ..Config::default() };
+            let mut em: ExecutionManager<BtorBackend> = symex_function("f", &proj, config);
+            match em.next() {
+                Some(Ok(ReturnValue::Return(bv))) => {
+                    let solution = em.state().get_a_solution_for_bv(&bv).unwrap().expect("Expected a solution");
+                    f64::from_bits(solution.as_u64().unwrap())
+                },
+                res => panic!("Expected a return value, got {:?}", res),
+            }
+        };
+        assert_eq!(result(FmaSemantics::Fused), 0.1_f64.mul_add(10.0, -1.0));
+        assert_eq!(result(FmaSemantics::Separate), 0.0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "fp"))]
+    fn floating_point_needs_fp_feature() {
+        // `double f(double x) { return -x; }`
+        init_logging();
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.parameters = vec![param("x", Type::double())];
+        func.return_type = Type::double();
+        func.basic_blocks[0].instrs.push(Instruction::FNeg(instruction::FNeg { operand: local("x", Type::double()), dest: Name::from("neg"), debugloc: None }));
+        func.basic_blocks[0].term = ret(local("neg", Type::double()));
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &proj, Config::default());
+        match em.next() {
+            Some(Err(Error::UnsupportedInstruction(msg))) => assert!(msg.contains("`fp` feature"), "unexpected message {:?}", msg),
+            res => panic!("Expected an UnsupportedInstruction error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fp")]
+    fn zero_only_at_nan() {
+        // `int f(double x) { return x == x; }`, which is zero only when `x` is
+        // NaN. llvm-ir doesn't expose fast-math flags, so an `nnan` on the
+        // `fcmp` couldn't rule this out.
+        init_logging();
+        let x = local("x", Type::double());
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.parameters = vec![param("x", Type::double())];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            Instruction::FCmp(instruction::FCmp { predicate: FPPredicate::OEQ, operand0: x.clone(), operand1: x, dest: Name::from("eq"), debugloc: None }),
+            Instruction::ZExt(instruction::ZExt { operand: local("eq", Type::bool()), to_type: Type::i32(), dest: Name::from("ret"), debugloc: None }),
+        ];
+        func.basic_blocks[0].term = ret(local("ret", Type::i32()));
+        let proj = blank_project("test_mod", func);
+        let args = crate::find_zero_of_func_simple("f", &proj, Config::default())
+            .unwrap_or_else(|r| panic!("{}", r))
+            .expect("Failed to find zero of the function");
+        assert_eq!(args.len(), 1);
+        assert!(args[0].unwrap_to_f64().is_nan(), "expected a NaN, got {:?}", args[0]);
+    }
+
+    /// `%dest = call i32 @funcname(i32 %arg)`
+    fn call_i32_func(funcname: &str, arg: Operand, dest: &str) -> Instruction {
+        call(funcname, Type::i32(), vec![arg], Some(dest))
+    }
+
+    #[test]
+    fn same_callee_called_twice() {
+        // `int double_it(int y) { return y + y; }`
+        // `int caller(int x) { int a = double_it(x); int b = double_it(x + 1); return b - a; }`
+        init_logging();
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let mut callee = blank_function("double_it", vec![Name::from("entry")]);
+        callee.parameters = vec![param("y", Type::i32())];
+        callee.return_type = Type::i32();
+        callee.basic_blocks[0].instrs.push(Instruction::Add(instruction::Add { operand0: local_i32("y"), operand1: local_i32("y"), dest: Name::from("sum"), debugloc: None }));
+        callee.basic_blocks[0].term = ret(local_i32("sum"));
+
+        let mut caller = blank_function("caller", vec![Name::from("entry")]);
+        caller.parameters = vec![param("x", Type::i32())];
+        caller.return_type = Type::i32();
+        caller.basic_blocks[0].instrs = vec![
+            call_i32_func("double_it", local_i32("x"), "a"),
+            Instruction::Add(instruction::Add { operand0: local_i32("x"), operand1: int_const(32, 1), dest: Name::from("x1"), debugloc: None }),
+            call_i32_func("double_it", local_i32("x1"), "b"),
+            Instruction::Sub(instruction::Sub { operand0: local_i32("b"), operand1: local_i32("a"), dest: Name::from("diff"), debugloc: None }),
+        ];
+        caller.basic_blocks[0].term = ret(local_i32("diff"));
+
+        let proj = project_with_functions("test_mod", vec![caller, callee]);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("caller", &proj, Config::default());
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(bv))) => bv,
+            r => panic!("Unexpected return value {:?}", r),
+        };
+        // each call bound `%y` to its own argument, so the results differ by exactly 2
+        let state = em.state();
+        assert!(state.bvs_must_be_equal(&retval, &state.bv_from_u32(2, 32)).unwrap());
+        assert!(em.next().is_none());
+    }
+
+    #[test]
+    fn find_zero_through_call() {
+        // `int g(int y) { return y - 5; }`
+        // `int f(int x) { return g(x) + 1; }`
+        init_logging();
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let i32_const = |value| int_const(32, value);
+        let mut callee = blank_function("g", vec![Name::from("entry")]);
+        callee.parameters = vec![param("y", Type::i32())];
+        callee.return_type = Type::i32();
+        callee.basic_blocks[0].instrs.push(Instruction::Sub(instruction::Sub { operand0: local_i32("y"), operand1: i32_const(5), dest: Name::from("diff"), debugloc: None }));
+        callee.basic_blocks[0].term = ret(local_i32("diff"));
+
+        let mut caller = blank_function("f", vec![Name::from("entry")]);
+        caller.parameters = vec![param("x", Type::i32())];
+        caller.return_type = Type::i32();
+        caller.basic_blocks[0].instrs = vec![
+            call_i32_func("g", local_i32("x"), "g_x"),
+            Instruction::Add(instruction::Add { operand0: local_i32("g_x"), operand1: i32_const(1), dest: Name::from("sum"), debugloc: None }),
+        ];
+        caller.basic_blocks[0].term = ret(local_i32("sum"));
+
+        let proj = project_with_functions("test_mod", vec![caller, callee]);
+        let args = crate::find_zero_of_func_simple("f", &proj, Config::default())
+            .unwrap_or_else(|r| panic!("{}", r))
+            .expect("Failed to find zero of the function");
+        assert_eq!(args, vec![crate::SolutionValue::I32(4)]);
+    }
+
+    #[test]
+    fn branch_inside_callee() {
+        // `int sign(int y) { return y > 0 ? 1 : 2; }`
+        // `int caller(int x) { return sign(x) + 10; }`
+        init_logging();
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let mut callee = null_check(local_i32("y"));
+        callee.name = "sign".to_owned();
+        callee.parameters = vec![param("y", Type::i32())];
+        callee.basic_blocks[0].instrs = vec![icmp(IntPredicate::SGT, local_i32("y"), int_const(32, 0), "is_null")];
+        for (bb, value) in callee.basic_blocks.iter_mut().skip(1).zip(vec![1, 2]) {
+            bb.term = ret(int_const(32, value));
+        }
+
+        let mut caller = blank_function("caller", vec![Name::from("entry")]);
+        caller.parameters = vec![param("x", Type::i32())];
+        caller.return_type = Type::i32();
+        caller.basic_blocks[0].instrs = vec![
+            call_i32_func("sign", local_i32("x"), "s"),
+            Instruction::Add(instruction::Add { operand0: local_i32("s"), operand1: int_const(32, 10), dest: Name::from("r"), debugloc: None }),
+        ];
+        caller.basic_blocks[0].term = ret(local_i32("r"));
+
+        // after backtracking into the callee, its other return also goes back
+        // to the caller, rather than ending the path
+        let proj = project_with_functions("test_mod", vec![caller, callee]);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("caller", &proj, Config::default());
+        let mut values: Vec<u64> = em.by_ref().map(|retval| match retval {
+            Ok(ReturnValue::Return(bv)) => bv.as_u64().unwrap(),
+            r => panic!("Unexpected return value {:?}", r),
+        }).collect();
+        values.sort_unstable();
+        assert_eq!(values, vec![11, 12]);
+    }
+
+    #[test]
+    fn switch_cases_share_a_backtracking_point() {
+        // `switch (x) { case 0: return 1; ... case 4: return 5; default: return 0; }`
+        init_logging();
+        let mut bb_names = vec![Name::from("entry"), Name::from("default")];
+        bb_names.extend((0 .. 5).map(|i| Name::from(format!("case{}", i))));
+        let mut func = blank_function("test_func", bb_names);
+        func.parameters = vec![param("x", Type::i32())];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].term = llvm_ir::Terminator::Switch(terminator::Switch {
+            operand: local("x", Type::i32()),
+            dests: (0 .. 5).map(|i| (Constant::Int { bits: 32, value: i }, Name::from(format!("case{}", i)))).collect(),
+            default_dest: Name::from("default"),
+            debugloc: None,
+        });
+        for (i, bb) in func.basic_blocks.iter_mut().enumerate().skip(1) {
+            let value = i as u64 - 1;
+            bb.term = ret(int_const(32, value));
+        }
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, Config::default());
+        let mut values: Vec<u64> = em.by_ref().map(|retval| match retval {
+            Ok(ReturnValue::Return(bv)) => bv.as_u64().unwrap(),
+            r => panic!("Unexpected return value {:?}", r),
+        }).collect();
+        values.sort_unstable();
+        // each case, and the default, exactly once
+        assert_eq!(values, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    /// `if (x > 5) { if (x > 10) return 2; else return 3; } else return 1;`
+    fn nested_branches() -> Function {
+        let i32_const = |value| int_const(32, value);
+        let mut func = blank_function("test_func", vec![Name::from("entry"), Name::from("a"), Name::from("b"), Name::from("c"), Name::from("d")]);
+        func.parameters = vec![param("x", Type::i32())];
+        func.return_type = Type::i32();
+        for (bb, (threshold, cond, true_dest, false_dest)) in func.basic_blocks.iter_mut().zip(vec![(5, "above_5", "a", "b"), (10, "above_10", "c", "d")]) {
+            bb.instrs.push(icmp(IntPredicate::UGT, local("x", Type::i32()), i32_const(threshold), cond));
+            bb.term = condbr(cond, true_dest, false_dest);
+        }
+        for (bb, value) in func.basic_blocks.iter_mut().skip(2).zip(vec![1, 2, 3]) {
+            bb.term = ret(i32_const(value));
+        }
+        func
+    }
+
+    /// The values returned by `nested_branches()`, in the order its paths are
+    /// explored with the given `BacktrackingOrder`. The order doesn't depend
+    /// on the `Checkpointing`.
+    fn nested_branches_order(order: BacktrackingOrder) -> Vec<u64> {
+        let proj = blank_project("test_mod", nested_branches());
+        let run = |checkpointing| {
+            let config = Config { backtracking_order: order, checkpointing, ..Config::default() };
+            let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+            em.by_ref().map(|retval| match retval {
+                Ok(ReturnValue::Return(bv)) => bv.as_u64().unwrap(),
+                r => panic!("Unexpected return value {:?}", r),
+            }).collect::<Vec<u64>>()
+        };
+        let values = run(Checkpointing::AssumptionLiterals);
+        assert_eq!(run(Checkpointing::SolverFrames), values, "with {:?}", order);
+        values
+    }
+
+    #[test]
+    fn backtracking_orders() {
+        init_logging();
+        // the `x > 10` branch's other side (3) is explored before the `x > 5`
+        // branch's (1) depth-first, and after it breadth-first
+        assert_eq!(nested_branches_order(BacktrackingOrder::DepthFirst), vec![2, 3, 1]);
+        assert_eq!(nested_branches_order(BacktrackingOrder::BreadthFirst), vec![2, 1, 3]);
+        // `x <= 5` is fewer constraints than `x > 5 && x <= 10`
+        assert_eq!(nested_branches_order(BacktrackingOrder::FewestConstraintsFirst), vec![2, 1, 3]);
+        let mut random = nested_branches_order(BacktrackingOrder::Random);
+        random.sort_unstable();
+        assert_eq!(random, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn out_of_order_backtracking_with_custom_hooks() {
+        init_logging();
+        let proj = blank_project("test_mod", nested_branches());
+
+        // `haybale`'s own hooks are fine
+        let mut config = Config { backtracking_order: BacktrackingOrder::BreadthFirst, ..Config::default() };
+        config.function_hooks.add_time_hooks();
+        let em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+        assert_eq!(em.count(), 3);
+
+        let mut config = Config { backtracking_order: BacktrackingOrder::BreadthFirst, ..Config::default() };
+        config.function_hooks.add("some_func", &generic_stub_hook);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+        match em.next() {
+            Some(Err(Error::OtherError(msg))) => assert!(msg.contains("BreadthFirst"), "Unexpected message {:?}", msg),
+            r => panic!("Expected an OtherError, got {:?}", r),
+        }
+
+        let mut config = Config { backtracking_order: BacktrackingOrder::Random, ..Config::default() };
+        config.callbacks.add_terminator_callback(|_, _| Ok(()));
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+        assert!(matches!(em.next(), Some(Err(Error::OtherError(_)))));
+
+        // and depth-first, anything goes
+        let mut config = Config::default();
+        config.function_hooks.add("some_func", &generic_stub_hook);
+        let em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+        assert_eq!(em.count(), 3);
+    }
+
+    /// `int f(unsigned x) { if (x >= n) return -1; unsigned i = 0; while (x != i) i++; return i; }`,
+    /// which has a backtracking point for each iteration
+    fn deep_branches(n: u64) -> Function {
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let i32_const = |value| int_const(32, value);
+        let mut func = blank_function("test_func", vec![Name::from("entry"), Name::from("loop"), Name::from("exit"), Name::from("out_of_range")]);
+        func.parameters = vec![param("x", Type::i32())];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![icmp(IntPredicate::ULT, local_i32("x"), i32_const(n), "in_range")];
+        func.basic_blocks[0].term = condbr("in_range", "loop", "out_of_range");
+        func.basic_blocks[1].instrs = vec![
+            Instruction::Phi(instruction::Phi {
+                incoming_values: vec![(i32_const(0), Name::from("entry")), (local_i32("next"), Name::from("loop"))],
+                dest: Name::from("i"),
+                to_type: Type::i32(),
+                debugloc: None,
+            }),
+            Instruction::Add(instruction::Add { operand0: local_i32("i"), operand1: i32_const(1), dest: Name::from("next"), debugloc: None }),
+            icmp(IntPredicate::NE, local_i32("x"), local_i32("i"), "more"),
+        ];
+        func.basic_blocks[1].term = condbr("more", "loop", "exit");
+        func.basic_blocks[2].term = ret(local_i32("i"));
+        func.basic_blocks[3].term = ret(i32_const(u64::from(u32::MAX)));
+        func
+    }
+
+    /// The sorted values returned by `deep_branches(n)`, and the deepest the
+    /// solver's assertion stack got while exploring them
+    fn deep_branches_run(n: u64, checkpointing: Checkpointing) -> (Vec<u64>, usize) {
+        let proj = blank_project("test_mod", deep_branches(n));
+        let config = Config { loop_bound: n as usize + 1, checkpointing, ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+        let mut values: Vec<u64> = em.by_ref().map(|retval| match retval {
+            Ok(ReturnValue::Return(bv)) => bv.as_u64().unwrap(),
+            r => panic!("Unexpected return value {:?}", r),
+        }).collect();
+        values.sort_unstable();
+        (values, em.state().solver_stats().peak_assertion_depth)
+    }
+
+    #[test]
+    fn assumption_literals_bound_the_assertion_stack() {
+        init_logging();
+        let n = 300;
+        let expected: Vec<u64> = (0 .. n).chain(std::iter::once(u64::from(u32::MAX))).collect();
+        let (values, frames_depth) = deep_branches_run(n, Checkpointing::SolverFrames);
+        assert_eq!(values, expected);
+        // one level per pending backtracking point
+        assert!(frames_depth > n as usize, "peak depth {}", frames_depth);
+        let (values, literals_depth) = deep_branches_run(n, Checkpointing::AssumptionLiterals);
+        assert_eq!(values, expected);
+        // the current path's level, plus one for a query
+        assert_eq!(literals_depth, 2);
+    }
+
+    #[test]
+    fn ten_thousand_backtracking_points() {
+        init_logging();
+        let n = 10_000;
+        let (values, depth) = deep_branches_run(n, Checkpointing::AssumptionLiterals);
+        assert_eq!(values.len(), n as usize + 1);
+        assert!(values.iter().take(n as usize).copied().eq(0 .. n));
+        assert_eq!(depth, 2);
+    }
+
+    /// Compare the two kinds of `Checkpointing` with 10k backtracking points.
+    /// Run with `cargo test --release -- --ignored --nocapture checkpointing_timing`.
+    #[test]
+    #[ignore]
+    fn checkpointing_timing() {
+        init_logging();
+        for &checkpointing in &[Checkpointing::SolverFrames, Checkpointing::AssumptionLiterals] {
+            let start = std::time::Instant::now();
+            let (values, depth) = deep_branches_run(10_000, checkpointing);
+            println!("{:?}: {} paths in {:?}, assertion stack at most {} levels deep", checkpointing, values.len(), start.elapsed(), depth);
+        }
+    }
+
+    #[test]
+    fn seeded_runs_are_reproducible() {
+        init_logging();
+        let proj = blank_project("test_mod", nested_branches());
+        // the value returned on each path, and the value of `x` found for it
+        let run = |seed| -> Vec<(u64, Option<u64>)> {
+            let config = Config { backtracking_order: BacktrackingOrder::Random, random_seed: seed, ..Config::default() };
+            let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+            let mut results = vec![];
+            while let Some(retval) = em.next() {
+                let retval = match retval {
+                    Ok(ReturnValue::Return(bv)) => bv.as_u64().unwrap(),
+                    r => panic!("Unexpected return value {:?}", r),
+                };
+                let x = em.param_bvs()[0].clone();
+                results.push((retval, em.state().get_a_u64_solution_for_bv(&x).unwrap()));
+            }
+            results
+        };
+        let first = run(1234);
+        assert_eq!(first.len(), 3);
+        assert_eq!(run(1234), first);
+        assert_eq!(run(5678), run(5678));
+    }
+
+    #[test]
+    fn bool_params_in_solutions() {
+        // `int f(bool b, bool unused) { return b ? 0 : 1; }`
+        init_logging();
+        let mut func = blank_function("test_func", vec![Name::from("entry"), Name::from("zero"), Name::from("one")]);
+        func.parameters = vec![
+            param("b", Type::bool()),
+            param("unused", Type::bool()),
+        ];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].term = condbr("b", "zero", "one");
+        for (bb, value) in func.basic_blocks.iter_mut().skip(1).zip(vec![0, 1]) {
+            bb.term = ret(int_const(32, value));
+        }
+        let proj = blank_project("test_mod", func);
+        let args = crate::find_zero_of_func_simple("test_func", &proj, Config::default())
+            .unwrap()
+            .expect("Failed to find zero of the function");
+        // the model doesn't need to mention `unused` at all
+        assert_eq!(args, vec![crate::SolutionValue::Bool(true), crate::SolutionValue::DontCare]);
+        assert_eq!(args[0].to_string(), "true");
+    }
+
+    #[test]
+    fn ignored_param_is_dont_care() {
+        // `int f(int x, int unused) { return x == 3 ? 0 : 1; }`
+        init_logging();
+        let mut func = blank_function("test_func", vec![Name::from("entry"), Name::from("zero"), Name::from("one")]);
+        func.parameters = vec![
+            param("x", Type::i32()),
+            param("unused", Type::i32()),
+        ];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::EQ, local("x", Type::i32()), int_const(32, 3), "cond"));
+        func.basic_blocks[0].term = condbr("cond", "zero", "one");
+        for (bb, value) in func.basic_blocks.iter_mut().skip(1).zip(vec![0, 1]) {
+            bb.term = ret(int_const(32, value));
+        }
+        let proj = blank_project("test_mod", func);
+        let args = crate::find_zero_of_func_simple("test_func", &proj, Config::default())
+            .unwrap()
+            .expect("Failed to find zero of the function");
+        // `unused` is reported as unconstrained, rather than as some arbitrary value
+        assert_eq!(args, vec![crate::SolutionValue::I32(3), crate::SolutionValue::DontCare]);
+        assert_eq!(args[1].to_string(), "any value");
+    }
+
+    #[test]
+    fn allocas_dont_interfere() {
+        // `%a = alloca i32; %b = alloca i32; %c = alloca i32, i64 %n`, storing
+        // 1, 2, and 3 to them, then returning `*a * 100 + *b * 10 + *c`
+        init_logging();
+        let i32_const = |value| int_const(32, value);
+        let ptr = Type::pointer_to(Type::i32());
+        let mut func = blank_function("test_func", vec![Name::from("test_bb")]);
+        func.parameters = vec![param("n", Type::i64())];
+        func.return_type = Type::i32();
+        let mut instrs = Vec::new();
+        for (slot, num_elements, value) in [("a", int_const(64, 1), 1), ("b", int_const(64, 1), 2), ("c", local("n", Type::i64()), 3)] {
+            instrs.push(alloca(Type::i32(), num_elements, slot));
+            instrs.push(store(local(slot, ptr.clone()), i32_const(value)));
+        }
+        for slot in ["a", "b", "c"] {
+            instrs.push(load(local(slot, ptr.clone()), format!("{}_val", slot)));
+        }
+        instrs.push(Instruction::Mul(instruction::Mul { operand0: local("a_val", Type::i32()), operand1: i32_const(100), dest: Name::from("a_100"), debugloc: None }));
+        instrs.push(Instruction::Mul(instruction::Mul { operand0: local("b_val", Type::i32()), operand1: i32_const(10), dest: Name::from("b_10"), debugloc: None }));
+        instrs.push(Instruction::Add(instruction::Add { operand0: local("a_100", Type::i32()), operand1: local("b_10", Type::i32()), dest: Name::from("ab"), debugloc: None }));
+        instrs.push(Instruction::Add(instruction::Add { operand0: local("ab", Type::i32()), operand1: local("c_val", Type::i32()), dest: Name::from("abc"), debugloc: None }));
+        func.basic_blocks[0].instrs = instrs;
+        func.basic_blocks[0].term = ret(local("abc", Type::i32()));
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, Config::default());
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(bv))) => bv,
+            r => panic!("Unexpected return value {:?}", r),
+        };
+        let state = em.state();
+        assert!(state.bvs_must_be_equal(&retval, &state.bv_from_u32(123, 32)).unwrap());
+        // the symbolic-length `alloca` limited `%n` to what fits
+        let n = &em.param_bvs()[0];
+        assert!(!state.bvs_can_be_equal(n, &state.bv_from_u64(1 << 40, 64)).unwrap());
+    }
+
+    /// `int f(u64 i) { int a[2] = {10, 20}; return i < 2 ? a[i] : 0; }`
+    fn symbolic_index() -> Function {
+        let int_const = |bits, value| int_const(bits, value);
+        let array_ty = Type::ArrayType { element_type: Box::new(Type::i32()), num_elements: 2 };
+        let gep = |dest: &str, index| Instruction::GetElementPtr(instruction::GetElementPtr {
+            address: local("a", Type::pointer_to(array_ty.clone())),
+            indices: vec![int_const(64, 0), index],
+            dest: Name::from(dest),
+            in_bounds: true,
+            debugloc: None,
+        });
+        let store_i32 = |addr: &str, value| store(local(addr, Type::pointer_to(Type::i32())), int_const(32, value));
+        let mut func = blank_function("test_func", vec![Name::from("entry"), Name::from("in_bounds"), Name::from("out_of_bounds")]);
+        func.parameters = vec![param("i", Type::i64())];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            alloca(array_ty.clone(), int_const(64, 1), "a"),
+            gep("a0", int_const(64, 0)),
+            store_i32("a0", 10),
+            gep("a1", int_const(64, 1)),
+            store_i32("a1", 20),
+            icmp(IntPredicate::ULT, local("i", Type::i64()), int_const(64, 2), "cond"),
+        ];
+        func.basic_blocks[0].term = condbr("cond", "in_bounds", "out_of_bounds");
+        func.basic_blocks[1].instrs = vec![
+            gep("ai", local("i", Type::i64())),
+            load(local("ai", Type::pointer_to(Type::i32())), "val"),
+        ];
+        func.basic_blocks[1].term = ret(local("val", Type::i32()));
+        func.basic_blocks[2].term = ret(int_const(32, 0));
+        func
+    }
+
+    /// The possible return values on each path through `symbolic_index()`
+    /// with the given `AddressResolution`, sorted
+    fn symbolic_index_results(address_resolution: AddressResolution) -> Vec<Vec<u64>> {
+        let proj = blank_project("test_mod", symbolic_index());
+        let config = Config { address_resolution, ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+        let mut results = Vec::new();
+        while let Some(retval) = em.next() {
+            let bv = match retval {
+                Ok(ReturnValue::Return(bv)) => bv,
+                r => panic!("Unexpected return value {:?}", r),
+            };
+            let mut values: Vec<u64> = match em.state().get_possible_solutions_for_bv(&bv, 3).unwrap().as_u64_solutions().unwrap() {
+                PossibleSolutions::Exactly(v) => v.into_iter().collect(),
+                PossibleSolutions::AtLeast(v) => panic!("Too many possible return values: {:?}", v),
+            };
+            values.sort_unstable();
+            results.push(values);
+        }
+        results.sort();
+        results
+    }
+
+    #[test]
+    fn address_resolution() {
+        init_logging();
+        assert_eq!(symbolic_index_results(AddressResolution::Symbolic), vec![vec![0], vec![10, 20]]);
+        assert_eq!(symbolic_index_results(AddressResolution::ConcretizeUpTo(2)), vec![vec![0], vec![10], vec![20]]);
+        assert_eq!(symbolic_index_results(AddressResolution::ConcretizeUpTo(8)), vec![vec![0], vec![10], vec![20]]);
+        // only one of the elements is considered
+        let one = symbolic_index_results(AddressResolution::ConcretizeToOne);
+        assert!(one == vec![vec![0], vec![10]] || one == vec![vec![0], vec![20]], "{:?}", one);
+    }
+
+    /// Store the `stored_bits`-bit `value` to an `alloca`, then load
+    /// `loaded_bits` bits from the same address, with the given `ByteOrder`
+    fn store_then_load(byte_order: ByteOrder, stored_bits: u32, value: u64, loaded_bits: u32) -> u64 {
+        let stored_ty = Type::IntegerType { bits: stored_bits };
+        let loaded_ty = Type::IntegerType { bits: loaded_bits };
+        let mut func = blank_function("test_func", vec![Name::from("test_bb")]);
+        func.return_type = loaded_ty.clone();
+        func.basic_blocks[0].instrs = vec![
+            Instruction::Alloca(instruction::Alloca { allocated_type: Type::i64(), num_elements: int_const(64, 1), dest: Name::from("p"), alignment: 8, debugloc: None }),
+            Instruction::BitCast(instruction::BitCast { operand: local("p", Type::pointer_to(Type::i64())), to_type: Type::pointer_to(stored_ty.clone()), dest: Name::from("stored_p"), debugloc: None }),
+            Instruction::Store(instruction::Store {
+                address: local("stored_p", Type::pointer_to(stored_ty.clone())),
+                value: int_const(stored_bits, value),
+                volatile: false,
+                atomicity: None,
+                alignment: 8,
+                debugloc: None,
+            }),
+            Instruction::BitCast(instruction::BitCast { operand: local("p", Type::pointer_to(Type::i64())), to_type: Type::pointer_to(loaded_ty.clone()), dest: Name::from("loaded_p"), debugloc: None }),
+            Instruction::Load(instruction::Load { address: local("loaded_p", Type::pointer_to(loaded_ty.clone())), dest: Name::from("loaded"), volatile: false, atomicity: None, alignment: 8, debugloc: None }),
+        ];
+        func.basic_blocks[0].term = ret(local("loaded", loaded_ty));
+        let proj = blank_project("test_mod", func);
+        let config = Config { byte_order: Some(byte_order), ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+        match em.next() {
+            Some(Ok(ReturnValue::Return(bv))) => bv.as_u64().unwrap(),
+            r => panic!("Unexpected return value {:?}", r),
+        }
+    }
+
+    #[test]
+    fn byte_orders() {
+        init_logging();
+        assert_eq!(store_then_load(ByteOrder::LittleEndian, 32, 0xAABB_CCDD, 8), 0xDD);
+        assert_eq!(store_then_load(ByteOrder::BigEndian, 32, 0xAABB_CCDD, 8), 0xAA);
+        assert_eq!(store_then_load(ByteOrder::BigEndian, 32, 0xAABB_CCDD, 16), 0xAABB);
+        // values which aren't a whole number of bytes round-trip, and with
+        // big-endian, occupy a whole number of bytes
+        for byte_order in [ByteOrder::LittleEndian, ByteOrder::BigEndian] {
+            assert_eq!(store_then_load(byte_order, 12, 0xABC, 12), 0xABC);
+            assert_eq!(store_then_load(byte_order, 3, 0x5, 3), 0x5);
+            assert_eq!(store_then_load(byte_order, 1, 1, 1), 1);
+        }
+        assert_eq!(store_then_load(ByteOrder::BigEndian, 12, 0xABC, 8), 0x0A);
+        assert_eq!(ByteOrder::from_data_layout("E-m:e-i64:64-n32:64-S128"), Some(ByteOrder::BigEndian));
+        assert_eq!(ByteOrder::from_data_layout("e-m:e-i64:64-f80:128-n8:16:32:64-S128"), Some(ByteOrder::LittleEndian));
+        assert_eq!(ByteOrder::from_data_layout(""), None);
+    }
+
+    #[test]
+    fn loop_iterations_keep_their_values() {
+        // `int f(int x) { int i = x; for (int count = 0; count < 2; count++) i++; ... }`,
+        // returning the value `i` had at the start of the second iteration
+        init_logging();
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let i32_const = |value| int_const(32, value);
+        let mut func = blank_function("test_func", vec![Name::from("entry"), Name::from("loop"), Name::from("exit")]);
+        func.parameters = vec![param("x", Type::i32())];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].term = br("loop");
+        func.basic_blocks[1].instrs = vec![
+            Instruction::Phi(instruction::Phi {
+                incoming_values: vec![(local_i32("x"), Name::from("entry")), (local_i32("next"), Name::from("loop"))],
+                dest: Name::from("i"),
+                to_type: Type::i32(),
+                debugloc: None,
+            }),
+            Instruction::Phi(instruction::Phi {
+                incoming_values: vec![(i32_const(0), Name::from("entry")), (local_i32("count_next"), Name::from("loop"))],
+                dest: Name::from("count"),
+                to_type: Type::i32(),
+                debugloc: None,
+            }),
+            Instruction::Add(instruction::Add { operand0: local_i32("i"), operand1: i32_const(1), dest: Name::from("next"), debugloc: None }),
+            Instruction::Add(instruction::Add { operand0: local_i32("count"), operand1: i32_const(1), dest: Name::from("count_next"), debugloc: None }),
+            icmp(IntPredicate::ULT, local_i32("count_next"), i32_const(2), "more"),
+        ];
+        func.basic_blocks[1].term = condbr("more", "loop", "exit");
+        func.basic_blocks[2].term = ret(local_i32("i"));
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, Config::default());
+        let x = em.param_bvs()[0].clone();
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(bv))) => bv,
+            r => panic!("Unexpected return value {:?}", r),
+        };
+        // the second iteration's `i` is the first iteration's `next`, which
+        // re-executing the block must not have replaced with the second
+        // iteration's `next`
+        let state = em.state();
+        assert!(state.bvs_must_be_equal(&retval, &x.add(&state.bv_from_u32(1, 32))).unwrap());
+        assert!(em.next().is_none());
+    }
+
+    #[test]
+    fn phis_read_old_values() {
+        // `int f(int x, int y) { int a = x, b = y; for (int n = 0; n < 1; n++) { int t = a; a = b; b = t; } return a - b; }`,
+        // where the swap is done by two phis which refer to each other
+        init_logging();
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let i32_const = |value| int_const(32, value);
+        let mut func = blank_function("test_func", vec![Name::from("entry"), Name::from("loop"), Name::from("exit")]);
+        func.parameters = vec![
+            param("x", Type::i32()),
+            param("y", Type::i32()),
+        ];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].term = br("loop");
+        let phi = |dest: &str, from_entry: Operand, from_loop: Operand| Instruction::Phi(instruction::Phi {
+            incoming_values: vec![(from_entry, Name::from("entry")), (from_loop, Name::from("loop"))],
+            dest: Name::from(dest),
+            to_type: Type::i32(),
+            debugloc: None,
+        });
+        func.basic_blocks[1].instrs = vec![
+            phi("a", local_i32("x"), local_i32("b")),
+            phi("b", local_i32("y"), local_i32("a")),
+            phi("n", i32_const(0), local_i32("n_next")),
+            Instruction::Add(instruction::Add { operand0: local_i32("n"), operand1: i32_const(1), dest: Name::from("n_next"), debugloc: None }),
+            icmp(IntPredicate::ULT, local_i32("n"), i32_const(1), "more"),
+        ];
+        func.basic_blocks[1].term = condbr("more", "loop", "exit");
+        func.basic_blocks[2].instrs = vec![
+            Instruction::Sub(instruction::Sub { operand0: local_i32("a"), operand1: local_i32("b"), dest: Name::from("diff"), debugloc: None }),
+        ];
+        func.basic_blocks[2].term = ret(local_i32("diff"));
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, Config::default());
+        let (x, y) = (em.param_bvs()[0].clone(), em.param_bvs()[1].clone());
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(bv))) => bv,
+            r => panic!("Unexpected return value {:?}", r),
+        };
+        // after one swap, `a` is `y` and `b` is `x`; had `b` read the new `a`,
+        // both would be `y`
+        let state = em.state();
+        assert!(state.bvs_must_be_equal(&retval, &y.sub(&x)).unwrap());
+        assert!(em.next().is_none());
+    }
+
+    #[test]
+    fn shufflevector_undef_lanes() {
+        // `shufflevector <2 x i32> <i32 1, i32 2>, <2 x i32> <i32 3, i32 4>, <2 x i32> <i32 3, i32 undef>`
+        init_logging();
+        let vec_ty = Type::VectorType { element_type: Box::new(Type::i32()), num_elements: 2 };
+        let ints = |a, b| Constant::Vector(vec![Constant::Int { bits: 32, value: a }, Constant::Int { bits: 32, value: b }]);
+        let mut func = blank_function("test_func", vec![Name::from("test_bb")]);
+        func.return_type = vec_ty.clone();
+        func.basic_blocks[0].instrs.push(Instruction::ShuffleVector(instruction::ShuffleVector {
+            operand0: Operand::ConstantOperand(ints(1, 2)),
+            operand1: Operand::ConstantOperand(ints(3, 4)),
+            dest: Name::from("shuffled"),
+            mask: Constant::Vector(vec![Constant::Int { bits: 32, value: 3 }, Constant::Undef(Type::i32())]),
+            debugloc: None,
+        }));
+        func.basic_blocks[0].term = ret(local("shuffled", vec_ty));
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, Config::default());
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(bv))) => bv,
+            r => panic!("Unexpected return value {:?}", r),
+        };
+        let state = em.state();
+        // the first element is element 1 of the second vector
+        assert!(state.bvs_must_be_equal(&retval.slice(31, 0), &state.bv_from_u32(4, 32)).unwrap());
+        // the `undef` element could be anything
+        assert!(state.bvs_can_be_equal(&retval.slice(63, 32), &state.bv_from_u32(0, 32)).unwrap());
+        assert!(state.bvs_can_be_equal(&retval.slice(63, 32), &state.bv_from_u32(17, 32)).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "fp")]
+    fn float_square_root() {
+        // `int f(float x) { return x * x == 4.0f ? 0 : 1; }`
+        init_logging();
+        let x = local("x", Type::single());
+        let mut func = blank_function("square", vec![Name::from("entry"), Name::from("equal"), Name::from("not_equal")]);
+        func.parameters = vec![param("x", Type::single())];
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            Instruction::FMul(instruction::FMul { operand0: x.clone(), operand1: x, dest: Name::from("sq"), debugloc: None }),
+            Instruction::FCmp(instruction::FCmp {
+                predicate: FPPredicate::OEQ,
+                operand0: local("sq", Type::single()),
+                operand1: Operand::ConstantOperand(Constant::Float(constant::Float::Single(4.0))),
+                dest: Name::from("is_four"),
+                debugloc: None,
+            }),
+        ];
+        func.basic_blocks[0].term = condbr("is_four", "equal", "not_equal");
+        func.basic_blocks[1].term = ret(int_const(32, 0));
+        func.basic_blocks[2].term = ret(int_const(32, 1));
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("square", &proj, Config::default());
+        let param = em.param_bvs()[0].clone();
+        let mut found = false;
+        while let Some(retval) = em.next() {
+            match retval {
+                Ok(ReturnValue::Return(bv)) if bv.as_u64() == Some(0) => {
+                    let solution = em.state().get_a_u64_solution_for_bv(&param).unwrap().expect("Expected a solution");
+                    assert_eq!(f32::from_bits(solution as u32).abs(), 2.0);
+                    found = true;
+                },
+                Ok(ReturnValue::Return(_)) => {},
+                r => panic!("Unexpected return value {:?}", r),
+            }
+        }
+        assert!(found);
+    }
+
+    #[test]
+    #[cfg(feature = "fp")]
+    fn find_zero_of_double_func() {
+        // `double f(double x) { return x * x - 4.0; }`
+        init_logging();
+        let x = local("x", Type::double());
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.parameters = vec![param("x", Type::double())];
+        func.return_type = Type::double();
+        func.basic_blocks[0].instrs = vec![
+            Instruction::FMul(instruction::FMul { operand0: x.clone(), operand1: x, dest: Name::from("sq"), debugloc: None }),
+            Instruction::FSub(instruction::FSub {
+                operand0: local("sq", Type::double()),
+                operand1: Operand::ConstantOperand(Constant::Float(constant::Float::Double(4.0))),
+                dest: Name::from("diff"),
+                debugloc: None,
+            }),
+        ];
+        func.basic_blocks[0].term = ret(local("diff", Type::double()));
+        let proj = blank_project("test_mod", func);
+        let args = crate::find_zero_of_func_simple("f", &proj, Config::default())
+            .unwrap_or_else(|r| panic!("{}", r))
+            .expect("Failed to find zero of the function");
+        assert_eq!(args.len(), 1);
+        assert_eq!(args[0].unwrap_to_f64().abs(), 2.0);
+    }
+
+    #[test]
+    fn stack_usage_of_deep_branch() {
+        // `int f(int x) { int y; if (x == 42) { char buf[256]; ... return 1; } return 0; }`
+        init_logging();
+        let alloca = |ty: Type, dest: &str, alignment| Instruction::Alloca(instruction::Alloca {
+            allocated_type: ty,
+            num_elements: int_const(32, 1),
+            dest: Name::from(dest),
+            alignment,
+            debugloc: None,
+        });
+        let ret_i32 = |value| ret(int_const(32, value));
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("big"), Name::from("small")]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(alloca(Type::i32(), "y", 4));
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::EQ, local("x", Type::i32()), int_const(32, 42), "cond"));
+        func.basic_blocks[0].term = condbr("cond", "big", "small");
+        func.basic_blocks[1].instrs.push(alloca(Type::ArrayType { element_type: Box::new(Type::i8()), num_elements: 256 }, "buf", 16));
+        func.basic_blocks[1].term = ret_i32(1);
+        func.basic_blocks[2].term = ret_i32(0);
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &proj, Config::default());
+        assert!(em.max_stack_usage().is_none());
+        assert!(matches!(em.next(), Some(Ok(_))));
+        assert!(em.max_stack_usage().expect("Expected a stack usage after one path").lower_bound_only);
+        while em.next().is_some() {}
+
+        let usage = em.max_stack_usage().expect("Expected a stack usage");
+        // 16 bytes of frame overhead, then `y` (4 bytes), then `buf` aligned to 16 bytes
+        assert_eq!(usage.bytes, 32 + 256);
+        assert_eq!(usage.inputs, vec![Some(42)]);
+        assert!(usage.path.iter().any(|entry| entry.0.bb.name == Name::from("big")));
+        assert!(!usage.lower_bound_only);
+    }
+
+    #[test]
+    fn stack_usage_of_dynamic_alloca() {
+        // `void f(unsigned char n) { int buf[n]; }`
+        init_logging();
+        let mut func = blank_function("f", vec![Name::from("entry")]);
+        func.parameters.push(param("n", Type::i8()));
+        func.basic_blocks[0].instrs.push(alloca(Type::i32(), local("n", Type::i8()), "buf"));
+        func.basic_blocks[0].term = ret_void();
+        let proj = blank_project("test_mod", func);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &proj, Config::default());
+        match em.next() {
+            Some(Ok(ReturnValue::ReturnVoid)) => {},
+            res => panic!("Expected a void return, got {:?}", res),
+        }
+        assert!(em.next().is_none(), "Expected only one path");
+
+        // the 8-bit count can be up to 255, all of which fit
+        let usage = em.max_stack_usage().expect("Expected a stack usage");
+        assert_eq!(usage.bytes, 16 + 255 * 4);
+        assert_eq!(usage.inputs, vec![Some(255)]);
+    }
+
+    #[test]
+    fn virtual_call_with_symbolic_dynamic_type() {
+        // Models a C++ virtual call `obj->get()` where `obj` is an `A` or a `B`
+        // depending on `flag`: the "constructor" stores one of two vtables into
+        // the object, and the call loads the vtable pointer from the object and
+        // the function pointer from the vtable slot. Both overrides should be
+        // explored, each under the right constraint on `flag`.
+        init_logging();
+        let fn_ty = Type::FuncType { result_type: Box::new(Type::i32()), param_types: vec![], is_var_arg: false };
+        let fn_ptr_ty = Type::pointer_to(fn_ty.clone());
+        let vtable_ty = Type::ArrayType { element_type: Box::new(fn_ptr_ty.clone()), num_elements: 1 };
+        let vtable_ptr_ty = Type::pointer_to(vtable_ty.clone());
+        let global_ref = |name: &str, ty: &Type| Operand::ConstantOperand(Constant::GlobalReference { name: Name::from(name), ty: ty.clone() });
+        let local = |name: &str, ty: &Type| local(name, ty.clone());
+        let i64_const = |value| int_const(64, value);
+
+        let returns_const = |name: &str, value| {
+            let mut func = blank_function(name, vec![Name::from("entry")]);
+            func.return_type = Type::i32();
+            func.basic_blocks[0].term = ret(int_const(32, value));
+            func
+        };
+        let vtable = |name: &str, method: &str| module::GlobalVariable {
+            linkage: module::Linkage::Private,
+            is_constant: true,
+            alignment: 8,
+            ..global_var(name, vtable_ptr_ty.clone(), Some(Constant::Array {
+                element_type: fn_ptr_ty.clone(),
+                elements: vec![Constant::GlobalReference { name: Name::from(method), ty: fn_ptr_ty.clone() }],
+            }))
+        };
+
+        let mut func = blank_function("call_virtual", vec![Name::from("entry")]);
+        func.parameters.push(param("flag", Type::bool()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            Instruction::Alloca(instruction::Alloca {
+                allocated_type: vtable_ptr_ty.clone(),
+                num_elements: int_const(32, 1),
+                dest: Name::from("obj"),
+                alignment: 8,
+                debugloc: None,
+            }),
+            Instruction::Select(instruction::Select {
+                condition: local("flag", &Type::bool()),
+                true_value: global_ref("vtable.A", &vtable_ptr_ty),
+                false_value: global_ref("vtable.B", &vtable_ptr_ty),
+                dest: Name::from("vt"),
+                debugloc: None,
+            }),
+            Instruction::Store(instruction::Store {
+                address: local("obj", &Type::pointer_to(vtable_ptr_ty.clone())),
+                value: local("vt", &vtable_ptr_ty),
+                volatile: false,
+                atomicity: None,
+                alignment: 8,
+                debugloc: None,
+            }),
+            Instruction::Load(instruction::Load {
+                address: local("obj", &Type::pointer_to(vtable_ptr_ty.clone())),
+                dest: Name::from("vptr"),
+                volatile: false,
+                atomicity: None,
+                alignment: 8,
+                debugloc: None,
+            }),
+            Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: local("vptr", &vtable_ptr_ty),
+                indices: vec![i64_const(0), i64_const(0)],
+                dest: Name::from("slot"),
+                in_bounds: true,
+                debugloc: None,
+            }),
+            Instruction::Load(instruction::Load {
+                address: local("slot", &Type::pointer_to(fn_ptr_ty.clone())),
+                dest: Name::from("method"),
+                volatile: false,
+                atomicity: None,
+                alignment: 8,
+                debugloc: None,
+            }),
+            Instruction::Call(instruction::Call {
+                function: Either::Right(local("method", &fn_ptr_ty)),
+                arguments: vec![],
+                return_attributes: vec![],
+                dest: Some(Name::from("r")),
+                function_attributes: vec![],
+                is_tail_call: false,
+                calling_convention: function::CallingConvention::C,
+                debugloc: None,
+            }),
+        ];
+        func.basic_blocks[0].term = ret(local("r", &Type::i32()));
+
+        let proj = project_with_globals("test_mod", vec![func, returns_const("A_get", 1), returns_const("B_get", 2)], vec![vtable("vtable.A", "A_get"), vtable("vtable.B", "B_get")]);
+
+        let mut em: ExecutionManager<BtorBackend> = symex_function("call_virtual", &proj, Config::default());
+        let flag = em.param_bvs()[0].clone();
+        let mut results = vec![];
+        while let Some(res) = em.next() {
+            match res {
+                Ok(ReturnValue::Return(r)) => {
+                    let state = em.state();
+                    let r = state.get_a_solution_for_bv(&r).unwrap().unwrap().as_u64().unwrap();
+                    let flag_must_be_true = state.bvs_must_be_equal(&flag, &state.bv_from_bool(true)).unwrap();
+                    let flag_must_be_false = state.bvs_must_be_equal(&flag, &state.bv_from_bool(false)).unwrap();
+                    results.push((r, flag_must_be_true, flag_must_be_false));
+                },
+                res => panic!("Unexpected result {:?}", res),
+            }
+        }
+        results.sort_unstable();
+        assert_eq!(results, vec![(1, true, false), (2, false, true)]);
+    }
+
+    #[test]
+    fn known_constants_decide_branches() {
+        // after `llvm.assume(x == 5)`, branches which depend only on `x` should
+        // be decided without any solver queries, and give the same results
+        init_logging();
+        let x = local("x", Type::i32());
+        let five = int_const(32, 5);
+        let icmp = |predicate, dest: &str, operand1: &Operand| Instruction::ICmp(instruction::ICmp {
+            predicate,
+            operand0: x.clone(),
+            operand1: operand1.clone(),
+            dest: Name::from(dest),
+            debugloc: None,
+        });
+
+        let mut func = blank_function("test_func", vec![
+            Name::from("entry"), Name::from("bb1"), Name::from("bb2"), Name::from("good"), Name::from("bad"),
+        ]);
+        func.parameters.push(param("x", Type::i32()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::EQ, "c", &five));
+        func.basic_blocks[0].instrs.push(call("llvm.assume", Type::VoidType, vec![local("c", Type::bool())], None));
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::EQ, "d", &five));
+        func.basic_blocks[0].term = condbr("d", "bb1", "bad");
+        func.basic_blocks[1].instrs.push(icmp(IntPredicate::NE, "e", &five));
+        func.basic_blocks[1].term = condbr("e", "bad", "bb2");
+        func.basic_blocks[2].instrs.push(icmp(IntPredicate::UGT, "f", &int_const(32, 3)));
+        func.basic_blocks[2].term = condbr("f", "good", "bad");
+        func.basic_blocks[3].term = ret(x.clone());
+        func.basic_blocks[4].term = ret(int_const(32, 0));
+        let proj = blank_project("test_mod", func);
+
+        let mut em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, Config::default());
+        match em.next() {
+            Some(Ok(ReturnValue::Return(bv))) => assert_eq!(bv.as_u64(), Some(5)),
+            res => panic!("Expected a return value of 5, got {:?}", res),
+        }
+        assert!(em.next().is_none(), "Expected only one path");
+        assert_eq!(em.state().solver_query_count(), 0);
+    }
+
+    #[test]
+    fn loop_iterations_per_module() {
+        // `m1` and `m2` each have a private `count` function. In `m1`'s, `b -> a`
+        // is a back-edge; in `m2`'s it's a forward edge, and must not be
+        // counted as a loop iteration just because `m1`'s `count` ran first.
+        init_logging();
+        let mut count1 = blank_function("count", vec![Name::from("a"), Name::from("b"), Name::from("exit")]);
+        count1.linkage = module::Linkage::Private;
+        count1.basic_blocks[0].term = br("b");
+        count1.basic_blocks[1].term = llvm_ir::Terminator::CondBr(terminator::CondBr {
+            condition: int_const(1, 0),
+            true_dest: Name::from("a"),
+            false_dest: Name::from("exit"),
+            debugloc: None,
+        });
+        count1.basic_blocks[2].term = ret_void();
+        let mut f = blank_function("f", vec![Name::from("entry")]);
+        f.basic_blocks[0].instrs = vec![call("count", Type::VoidType, vec![], None), call("g", Type::VoidType, vec![], None)];
+        f.basic_blocks[0].term = ret_void();
+
+        let mut count2 = blank_function("count", vec![Name::from("b"), Name::from("a")]);
+        count2.linkage = module::Linkage::Private;
+        count2.basic_blocks[0].term = br("a");
+        count2.basic_blocks[1].term = ret_void();
+        let mut g = blank_function("g", vec![Name::from("entry")]);
+        g.basic_blocks[0].instrs = vec![call("count", Type::VoidType, vec![], None)];
+        g.basic_blocks[0].term = ret_void();
+
+        let proj = Project::from_modules(vec![module_with_functions("m1", vec![f, count1]), module_with_functions("m2", vec![g, count2])]);
+        let config = Config { max_loop_iterations: Some(1), ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &proj, config);
+        match em.next() {
+            Some(Ok(ReturnValue::ReturnVoid)) => {},
+            res => panic!("Expected a void return, got {:?}", res),
+        }
+        assert!(em.next().is_none(), "Expected only one path");
+    }
+
+    #[test]
+    fn indirectbr_loop_iterations() {
+        // `entry: br loop; loop: indirectbr [loop, exit]; exit: ret void`
+        init_logging();
+        let mut func = blank_function("test_func", vec![Name::from("entry"), Name::from("loop"), Name::from("exit")]);
+        func.basic_blocks[0].term = br("loop");
+        func.basic_blocks[1].term = llvm_ir::Terminator::IndirectBr(terminator::IndirectBr {
+            operand: Operand::ConstantOperand(Constant::BlockAddress),
+            possible_dests: vec![Name::from("loop"), Name::from("exit")],
+            debugloc: None,
+        });
+        func.basic_blocks[2].term = ret_void();
+        let proj = blank_project("test_mod", func);
+        let config = Config { max_loop_iterations: Some(3), ..Config::default() };
+        let em: ExecutionManager<BtorBackend> = symex_function("test_func", &proj, config);
+        // leaving after each of the 3 allowed iterations, then exceeding the limit
+        let mut returns = 0;
+        let mut exceeded = 0;
+        for res in em {
+            match res {
+                Ok(ReturnValue::ReturnVoid) => returns += 1,
+                Err(Error::LoopBoundExceeded(3)) => exceeded += 1,
+                res => panic!("Unexpected result {:?}", res),
+            }
+        }
+        assert_eq!((returns, exceeded), (3, 1));
+    }
+
+    /// `int table[4];` with a global constructor which sets `table[i] = i * i`,
+    /// `int lookup(int idx) { return table[idx]; }`, and `lookup_small()`,
+    /// which is the same but takes a 2-bit `idx`
+    fn lookup_table_project() -> Project {
+        use llvm_ir::module::*;
+        let i32_const = |value| int_const(32, value);
+        let table_ty = Type::ArrayType { element_type: Box::new(Type::i32()), num_elements: 4 };
+        let table = Operand::ConstantOperand(Constant::GlobalReference { name: Name::from("table"), ty: Type::pointer_to(table_ty.clone()) });
+        let global = |name: &str, ty: Type, initializer: Constant| GlobalVariable {
+            linkage: Linkage::Internal,
+            ..global_var(name, Type::pointer_to(ty), Some(initializer))
+        };
+
+        let mut init = blank_function("init", vec![Name::from("entry"), Name::from("loop"), Name::from("exit")]);
+        init.basic_blocks[0].term = br("loop");
+        init.basic_blocks[1].instrs = vec![
+            Instruction::Phi(instruction::Phi {
+                incoming_values: vec![(i32_const(0), Name::from("entry")), (local("next", Type::i32()), Name::from("loop"))],
+                dest: Name::from("i"),
+                to_type: Type::i32(),
+                debugloc: None,
+            }),
+            Instruction::Mul(instruction::Mul { operand0: local("i", Type::i32()), operand1: local("i", Type::i32()), dest: Name::from("sq"), debugloc: None }),
+            Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: table.clone(),
+                indices: vec![i32_const(0), local("i", Type::i32())],
+                dest: Name::from("ptr"),
+                in_bounds: true,
+                debugloc: None,
+            }),
+            store(local("ptr", Type::pointer_to(Type::i32())), local("sq", Type::i32())),
+            Instruction::Add(instruction::Add { operand0: local("i", Type::i32()), operand1: i32_const(1), dest: Name::from("next"), debugloc: None }),
+            icmp(IntPredicate::ULT, local("next", Type::i32()), i32_const(4), "more"),
+        ];
+        init.basic_blocks[1].term = condbr("more", "loop", "exit");
+        init.basic_blocks[2].term = ret_void();
+
+        let mut lookup = blank_function("lookup", vec![Name::from("entry")]);
+        lookup.parameters.push(param("idx", Type::i32()));
+        lookup.return_type = Type::i32();
+        lookup.basic_blocks[0].instrs = vec![
+            Instruction::GetElementPtr(instruction::GetElementPtr {
+                address: table,
+                indices: vec![i32_const(0), local("idx", Type::i32())],
+                dest: Name::from("ptr"),
+                in_bounds: true,
+                debugloc: None,
+            }),
+            load(local("ptr", Type::pointer_to(Type::i32())), "val"),
+        ];
+        lookup.basic_blocks[0].term = ret(local("val", Type::i32()));
+
+        let mut lookup_small = lookup.clone();
+        lookup_small.name = "lookup_small".to_owned();
+        lookup_small.parameters = vec![param("small_idx", Type::IntegerType { bits: 2 })];
+        lookup_small.basic_blocks[0].instrs.insert(0, Instruction::ZExt(instruction::ZExt {
+            operand: local("small_idx", Type::IntegerType { bits: 2 }),
+            to_type: Type::i32(),
+            dest: Name::from("idx"),
+            debugloc: None,
+        }));
+
+        let ctor_fn_ty = Type::FuncType { result_type: Box::new(Type::VoidType), param_types: vec![], is_var_arg: false };
+        let ctor_entry_ty = Type::StructType { element_types: vec![Type::i32(), Type::pointer_to(ctor_fn_ty.clone()), Type::pointer_to(Type::i8())], is_packed: false };
+        let ctors = Constant::Array {
+            element_type: ctor_entry_ty.clone(),
+            elements: vec![Constant::Struct {
+                name: None,
+                values: vec![
+                    Constant::Int { bits: 32, value: 65535 },
+                    Constant::GlobalReference { name: Name::from("init"), ty: Type::pointer_to(ctor_fn_ty) },
+                    Constant::Null(Type::pointer_to(Type::i8())),
+                ],
+                is_packed: false,
+            }],
+        };
+        project_with_globals("test_mod", vec![init, lookup, lookup_small], vec![
+            global("table", table_ty.clone(), Constant::AggregateZero(table_ty)),
+            global("llvm.global_ctors", Type::ArrayType { element_type: Box::new(ctor_entry_ty), num_elements: 1 }, ctors),
+        ])
+    }
+
+    /// `const int table[4] = { 10, 20, 30, 40 };`, with
+    /// `int third() { return table[2]; }` and
+    /// `int stride() { return (int)&table[1] - (int)&table[0]; }`,
+    /// all through constant expressions
+    fn const_expr_project() -> Project {
+        use llvm_ir::module::*;
+        let i32_const = |value| Constant::Int { bits: 32, value };
+        let i64_const = |value| Constant::Int { bits: 64, value };
+        let table_ty = Type::ArrayType { element_type: Box::new(Type::i32()), num_elements: 4 };
+        let table = Constant::GlobalReference { name: Name::from("table"), ty: Type::pointer_to(table_ty.clone()) };
+        let element = |idx| Constant::GetElementPtr(Box::new(constant::GetElementPtr {
+            address: table.clone(),
+            indices: vec![i64_const(0), i64_const(idx)],
+            in_bounds: true,
+        }));
+        let as_i32 = |ptr| Constant::PtrToInt(Box::new(constant::PtrToInt { operand: ptr, to_type: Type::i32() }));
+
+        let mut third = blank_function("third", vec![Name::from("entry")]);
+        third.return_type = Type::i32();
+        third.basic_blocks[0].instrs = vec![
+            load(Operand::ConstantOperand(element(2)), "val"),
+        ];
+        third.basic_blocks[0].term = ret(local("val", Type::i32()));
+
+        let mut stride = blank_function("stride", vec![Name::from("entry")]);
+        stride.return_type = Type::i32();
+        stride.basic_blocks[0].term = ret(Operand::ConstantOperand(Constant::Sub(Box::new(constant::Sub {
+            operand0: as_i32(element(1)),
+            operand1: as_i32(element(0)),
+        }))));
+
+        project_with_globals("test_mod", vec![third, stride], vec![GlobalVariable {
+            linkage: Linkage::Internal,
+            is_constant: true,
+            ..global_var("table", Type::pointer_to(table_ty.clone()), Some(Constant::Array {
+                element_type: Type::i32(),
+                elements: vec![i32_const(10), i32_const(20), i32_const(30), i32_const(40)],
+            }))
+        }])
+    }
+
+    #[test]
+    fn const_exprs_into_globals() {
+        init_logging();
+        let proj = const_expr_project();
+        for &(funcname, expected) in &[("third", 30), ("stride", 4)] {
+            let mut em: ExecutionManager<BtorBackend> = symex_function(funcname, &proj, Config::default());
+            match em.next() {
+                Some(Ok(ReturnValue::Return(bv))) => {
+                    let expected = em.state().bv_from_u64(expected, 32);
+                    assert_eq!(em.state().bvs_must_be_equal(&bv, &expected), Ok(true), "{}", funcname);
+                },
+                res => panic!("Expected a return value from {}, got {:?}", funcname, res),
+            }
+            assert!(em.next().is_none(), "Expected only one path");
+        }
+    }
+
+    /// Symex `lookup(3)` and return the result
+    fn lookup_3<'p>(proj: &'p Project, config: Config<'p, BtorBackend>) -> Option<u64> {
+        let mut em: ExecutionManager<BtorBackend> = symex_function("lookup", proj, config);
+        let idx = em.param_bvs()[0].clone();
+        let retval = match em.next() {
+            Some(Ok(ReturnValue::Return(bv))) => bv,
+            res => panic!("Expected a return value, got {:?}", res),
+        };
+        idx._eq(&em.state().bv_from_u32(3, 32)).assert();
+        let solution = em.state().get_a_solution_for_bv(&retval).unwrap().expect("Expected a solution");
+        assert!(em.next().is_none(), "Expected only one path");
+        solution.as_u64()
+    }
+
+    #[test]
+    fn global_ctors() {
+        init_logging();
+        let proj = lookup_table_project();
+        assert_eq!(proj.global_ctors().iter().map(|(f, _)| f.name.as_str()).collect::<Vec<_>>(), vec!["init"]);
+        // by default, the constructor doesn't run
+        assert_eq!(lookup_3(&proj, Config::default()), Some(0));
+        let config = Config { run_global_ctors: true, ..Config::default() };
+        assert_eq!(lookup_3(&proj, config), Some(9));
+        // a budget too small for the constructor's loop cuts off the path
+        let config = Config { run_global_ctors: true, global_ctor_instruction_budget: 10, ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("lookup", &proj, config);
+        match em.next() {
+            Some(Err(Error::OtherError(msg))) => assert!(msg.contains("global_ctor_instruction_budget"), "unexpected error message: {}", msg),
+            res => panic!("Expected an error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn concrete_inputs_after_global_ctors() {
+        init_logging();
+        let proj = lookup_table_project();
+        let config = Config { run_global_ctors: true, exhaustive_threshold_bits: 2, ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("lookup_small", &proj, config);
+        let idx = em.param_bvs()[0].clone();
+        let mut results = vec![];
+        while let Some(res) = em.next() {
+            match res {
+                Ok(ReturnValue::Return(bv)) => {
+                    let input = em.state().get_a_solution_for_bv(&idx).unwrap().expect("Expected a solution").as_u64().unwrap();
+                    results.push((input, bv.as_u64().expect("Expected a concrete return value")));
+                },
+                res => panic!("Unexpected result {:?}", res),
+            }
+        }
+        // each input runs once, in order, and sees the table the constructor filled in
+        assert_eq!(results, vec![(0, 0), (1, 1), (2, 4), (3, 9)]);
+        assert_eq!(em.exhaustive_input_count(), Some(4));
+    }
+
+    /// `uint8_t f(uint8_t x) { return x < 10 ? x * 3 : x - 10; }`
+    fn small_input_function() -> Function {
+        let x = local("x", Type::i8());
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("small"), Name::from("large")]);
+        func.parameters = vec![param("x", Type::i8())];
+        func.return_type = Type::i8();
+        func.basic_blocks[0].instrs.push(icmp(IntPredicate::ULT, x.clone(), int_const(8, 10), "is_small"));
+        func.basic_blocks[0].term = condbr("is_small", "small", "large");
+        func.basic_blocks[1].instrs.push(Instruction::Mul(instruction::Mul { operand0: x.clone(), operand1: int_const(8, 3), dest: Name::from("tripled"), debugloc: None }));
+        func.basic_blocks[1].term = ret(local("tripled", Type::i8()));
+        func.basic_blocks[2].instrs.push(Instruction::Sub(instruction::Sub { operand0: x, operand1: int_const(8, 10), dest: Name::from("reduced"), debugloc: None }));
+        func.basic_blocks[2].term = ret(local("reduced", Type::i8()));
+        func
+    }
+
+    #[test]
+    fn exhaustive_concrete_inputs() {
+        init_logging();
+        let proj = blank_project("test_mod", small_input_function());
+        let native = |x: u64| if x < 10 { x * 3 } else { x - 10 };
+
+        // the return values the symbolic run can produce
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &proj, Config::default());
+        let mut symbolic_retvals = HashSet::new();
+        while let Some(res) = em.next() {
+            match res {
+                Ok(ReturnValue::Return(bv)) => match em.state().get_possible_solutions_for_bv(&bv, 256).unwrap() {
+                    PossibleSolutions::Exactly(solutions) => symbolic_retvals.extend(solutions.iter().map(|s| s.as_u64().unwrap())),
+                    PossibleSolutions::AtLeast(_) => panic!("Expected an exact set of solutions"),
+                },
+                res => panic!("Unexpected result {:?}", res),
+            }
+        }
+        assert_eq!(em.exhaustive_input_count(), None);
+
+        // with the inputs enumerated, each of the 256 inputs gets its own
+        // path, which computes the same result as the native function, as a
+        // constant
+        let config = Config { exhaustive_threshold_bits: 8, ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &proj, config);
+        let x = em.param_bvs()[0].clone();
+        let mut inputs = vec![];
+        let mut concrete_retvals = HashSet::new();
+        while let Some(res) = em.next() {
+            match res {
+                Ok(ReturnValue::Return(bv)) => {
+                    let input = em.state().get_a_solution_for_bv(&x).unwrap().expect("Expected a solution").as_u64().unwrap();
+                    let retval = bv.as_u64().expect("Expected a concrete return value");
+                    assert_eq!(retval, native(input), "wrong result for input {}", input);
+                    inputs.push(input);
+                    concrete_retvals.insert(retval);
+                },
+                res => panic!("Unexpected result {:?}", res),
+            }
+        }
+        assert_eq!(inputs, (0 .. 256).collect::<Vec<u64>>());
+        assert_eq!(em.exhaustive_input_count(), Some(256));
+        assert_eq!(concrete_retvals, symbolic_retvals);
+    }
+
+    /// LLVM's semantics for each binary operation on `i4`s, as a reference for
+    /// the solver's operators. `None` means the result is undefined (immediate
+    /// UB or poison), so any result is acceptable.
+    fn llvm_i4_binop(opcode: BinaryOpcode, a: u64, b: u64) -> Option<u64> {
+        let signed = |x: u64| ((x as i64) << 60) >> 60;
+        let (sa, sb) = (signed(a), signed(b));
+        let result = match opcode {
+            BinaryOpcode::Add => a + b,
+            BinaryOpcode::Sub => a.wrapping_sub(b),
+            BinaryOpcode::Mul => a * b,
+            BinaryOpcode::UDiv if b != 0 => a / b,
+            BinaryOpcode::URem if b != 0 => a % b,
+            // Rust's `/` and `%` also round toward zero, with the remainder taking the sign of the dividend
+            BinaryOpcode::SDiv if b != 0 && !(sa == -8 && sb == -1) => (sa / sb) as u64,
+            BinaryOpcode::SRem if b != 0 && !(sa == -8 && sb == -1) => (sa % sb) as u64,
+            BinaryOpcode::And => a & b,
+            BinaryOpcode::Or => a | b,
+            BinaryOpcode::Xor => a ^ b,
+            BinaryOpcode::Shl if b < 4 => a << b,
+            BinaryOpcode::LShr if b < 4 => a >> b,
+            BinaryOpcode::AShr if b < 4 => (sa >> b) as u64,
+            _ => return None,
+        };
+        Some(result & 0xf)
+    }
+
+    /// Check the operator we use for each binary operation against LLVM's
+    /// semantics, for all pairs of `i4` operands
+    #[test]
+    fn binops_match_llvm_semantics() {
+        type BV = <BtorBackend as Backend>::BV;
+        let btor = <<BtorBackend as Backend>::SolverRef as SolverRef>::new();
+        let operand = |name: &str| local(name, Type::IntegerType { bits: 4 });
+        let (operand0, operand1, dest) = (operand("a"), operand("b"), Name::from("c"));
+        let insts = vec![
+            Instruction::Add(instruction::Add { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::Sub(instruction::Sub { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::Mul(instruction::Mul { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::UDiv(instruction::UDiv { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::SDiv(instruction::SDiv { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::URem(instruction::URem { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::SRem(instruction::SRem { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::And(instruction::And { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::Or(instruction::Or { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::Xor(instruction::Xor { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::Shl(instruction::Shl { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::LShr(instruction::LShr { operand0: operand0.clone(), operand1: operand1.clone(), dest: dest.clone(), debugloc: None }),
+            Instruction::AShr(instruction::AShr { operand0, operand1, dest, debugloc: None }),
+        ];
+        for inst in insts {
+            let bop: instruction::groups::BinaryOp = inst.try_into().unwrap();
+            let opcode = ExecutionManager::<BtorBackend>::binop_to_opcode(&bop).unwrap();
+            let op = ExecutionManager::<BtorBackend>::binop_to_bvbinop::<BV>(&bop).unwrap();
+            for a in 0 .. 16 {
+                for b in 0 .. 16 {
+                    if let Some(expected) = llvm_i4_binop(opcode, a, b) {
+                        let result = op(&BV::from_u64(btor.clone(), a, 4), &BV::from_u64(btor.clone(), b, 4));
+                        assert_eq!(
+                            crate::solver_utils::bvs_must_be_equal(&btor, &result, &BV::from_u64(btor.clone(), expected, 4)),
+                            Ok(true),
+                            "{:?} of {} and {} should be {}", opcode, a, b, expected,
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,123 @@
+//! Advisory tags describing what a value means (e.g., "this is a length,
+//! returned by `strlen`"), for more readable reports
+
+use crate::state::Location;
+use std::fmt;
+
+/// Semantic metadata about a value, which hooks can attach to the `BV`s they
+/// produce with [`State::tag_bv()`](../struct.State.html#method.tag_bv).
+///
+/// Tags propagate through casts (`zext`, `sext`, `trunc`, `bitcast`, etc) and
+/// through copies such as `phi`s, but not through arithmetic. They have no
+/// effect on the semantics of symbolic execution; they only appear in
+/// reports, such as
+/// [`State::tagged_values()`](../struct.State.html#method.tagged_values).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Tag {
+    /// What kind of value this is, e.g. `"length"` or `"errno"`
+    pub kind: String,
+    /// Where the value came from, e.g. `"strlen at x.c:10"`
+    pub origin: String,
+}
+
+impl Tag {
+    pub fn new(kind: impl Into<String>, origin: impl Into<String>) -> Self {
+        Self { kind: kind.into(), origin: origin.into() }
+    }
+
+    /// A tag for a value of the given `kind`, produced by `what` (e.g., the
+    /// name of a hooked function) at the given `Location`
+    pub fn at(kind: impl Into<String>, what: &str, loc: &Location) -> Self {
+        let loc = match loc.source_loc {
+            Some(source_loc) => source_loc.to_string(),
+            None => loc.to_string_no_module(),
+        };
+        Self::new(kind, format!("{} at {}", what, loc))
+    }
+
+    /// Format a (concrete) value which has this tag, e.g.
+    /// `length = 17 (from strlen at x.c:10)`
+    pub fn describe_value(&self, value: impl fmt::Display) -> String {
+        format!("{} = {} (from {})", self.kind, value, self.origin)
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (from {})", self.kind, self.origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{Backend, BtorBackend};
+    use crate::config::Config;
+    use crate::error::Result;
+    use crate::function_hooks::IsCall;
+    use crate::project::Project;
+    use crate::return_value::ReturnValue;
+    use crate::state::State;
+    use crate::symex::{symex_function, ExecutionManager};
+    use crate::test_utils::*;
+    use llvm_ir::*;
+
+    fn strlen_hook<'p>(_proj: &'p Project, state: &mut State<'p, BtorBackend>, _call: &'p dyn IsCall) -> Result<ReturnValue<<BtorBackend as Backend>::BV>> {
+        let len = state.new_bv_with_name(Name::from("strlen_retval"), 64)?;
+        let tag = Tag::at("length", "strlen", &state.cur_loc);
+        state.tag_bv(&len, tag);
+        Ok(ReturnValue::Return(len))
+    }
+
+    /// `int len32 = strlen(s); int plus = len32 + 1; return len32 == 17;`
+    fn uses_strlen() -> Function {
+        let ret_i32 = |value| ret(int_const(32, value));
+        let i8_ptr = Type::pointer_to(Type::i8());
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("yes"), Name::from("no")]);
+        func.parameters.push(param("s", i8_ptr.clone()));
+        func.return_type = Type::i32();
+        func.basic_blocks[0].instrs = vec![
+            call("strlen", Type::i64(), vec![local("s", i8_ptr)], Some("len")),
+            Instruction::Trunc(instruction::Trunc {
+                operand: local("len", Type::i64()),
+                to_type: Type::i32(),
+                dest: Name::from("len32"),
+                debugloc: None,
+            }),
+            Instruction::Add(instruction::Add {
+                operand0: local("len32", Type::i32()),
+                operand1: int_const(32, 1),
+                dest: Name::from("plus"),
+                debugloc: None,
+            }),
+            icmp(IntPredicate::EQ, local("len32", Type::i32()), int_const(32, 17), "is17"),
+        ];
+        func.basic_blocks[0].term = condbr("is17", "yes", "no");
+        func.basic_blocks[1].term = ret_i32(1);
+        func.basic_blocks[2].term = ret_i32(0);
+        func
+    }
+
+    #[test]
+    fn tagged_strlen_result() -> Result<()> {
+        let project = blank_project("test_mod", uses_strlen());
+        let mut config = Config::default();
+        config.function_hooks.add("strlen", &strlen_hook);
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        let mut found = false;
+        while let Some(retval) = em.next() {
+            match retval? {
+                ReturnValue::Return(bv) if bv.as_u64() == Some(1) => {
+                    found = true;
+                    assert_eq!(em.state().tagged_values()?, vec![
+                        "%len: length = 17 (from strlen at f, bb entry, instr 0)".to_owned(),
+                        "%len32: length = 17 (from strlen at f, bb entry, instr 0)".to_owned(),
+                    ]);
+                },
+                _ => {},
+            }
+        }
+        assert!(found, "expected a path returning 1");
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+//! Declarative taint queries: "can data from this source reach that sink?"
+//!
+//! A [`FlowQuery`](struct.FlowQuery.html) names a source (e.g., the return
+//! value of `recv`), a sink (e.g., the third argument of `memcpy`), and the
+//! entry function to explore. [`run_flow_queries()`](fn.run_flow_queries.html)
+//! explores the paths through each entry function once, no matter how many
+//! queries share it, and reports each flow found along with inputs which
+//! lead down a path exhibiting it.
+//!
+//! Taint is tracked through the data flow of LLVM values in the entry
+//! function: the result of any instruction with a tainted operand is tainted.
+//! A load is tainted if a tainted value was stored through the same pointer
+//! (or a pointer derived from it by `getelementptr` or a cast) earlier on the
+//! path. The results of calls with tainted arguments are tainted. The bodies
+//! of callees are not analyzed.
+//!
+//! With the `serde` feature, queries can be deserialized, e.g. from a
+//! configuration file.
+
+use crate::backend::Backend;
+use crate::config::Config;
+use crate::error::*;
+use crate::project::Project;
+use crate::state::{BBInstrIndex, PathEntry};
+use crate::symex::{symex_function, ExecutionManager};
+use either::Either;
+use llvm_ir::{Constant, Function, Instruction, Name, Operand, Terminator};
+use llvm_ir::HasDebugLoc;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Where tainted data comes from
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FlowSource {
+    /// The return value of any call to the named function
+    Return { function: String },
+    /// The parameter of the entry function with the given (0-based) index
+    Parameter { index: usize },
+    /// Any value loaded directly from the named global
+    GlobalRead { global: String },
+}
+
+/// Where tainted data must not (or may) end up
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FlowSink {
+    /// The argument with the given (0-based) index of any call to the named
+    /// function
+    Argument { function: String, index: usize },
+    /// Any value stored directly to the named global
+    GlobalStore { global: String },
+    /// The condition of any conditional branch or `switch`
+    BranchCondition,
+}
+
+/// A query for flows from a `FlowSource` to a `FlowSink`
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FlowQuery {
+    /// A name identifying this query in reports
+    pub name: String,
+    pub from: FlowSource,
+    pub to: FlowSink,
+    /// The name of the entry function to explore
+    pub within: String,
+}
+
+/// A flow found by a `FlowQuery`
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Flow {
+    /// Where the tainted data reaches the sink: the source location if
+    /// available, or else the LLVM location
+    pub location: String,
+    /// Values of the entry function's parameters which lead down a path
+    /// exhibiting the flow. Entries are `None` for parameters wider than 64
+    /// bits, or if the solver failed to produce a value.
+    pub witness: Vec<Option<u64>>,
+}
+
+/// The result of a `FlowQuery`
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FlowQueryOutcome {
+    /// The `name` of the query
+    pub query: String,
+    /// The flows found, at most one per sink location
+    pub flows: Vec<Flow>,
+    /// Number of paths which were cut off by an error (e.g., exceeding the
+    /// loop bound). Those paths were checked only up to the point of the
+    /// error.
+    ///
+    /// If `flows` is empty, no flow exists within the bounds of the
+    /// exploration (e.g., `Config.loop_bound`); if additionally this is 0, no
+    /// flow exists at all (except possibly through the bodies of callees).
+    pub paths_cut_off: usize,
+}
+
+/// Run the given `FlowQuery`s, exploring each distinct entry function once.
+///
+/// Returns one `FlowQueryOutcome` per query, in the same order as `queries`.
+pub fn run_flow_queries<'p, B: Backend>(
+    queries: &[FlowQuery],
+    project: &'p Project,
+    config: Config<'p, B>,
+) -> Result<Vec<FlowQueryOutcome>> {
+    let mut outcomes: Vec<FlowQueryOutcome> = queries.iter()
+        .map(|query| FlowQueryOutcome { query: query.name.clone(), flows: vec![], paths_cut_off: 0 })
+        .collect();
+    let mut by_entry: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut entries = vec![];
+    for (i, query) in queries.iter().enumerate() {
+        if project.get_func_by_name(&query.within).is_none() {
+            return Err(Error::OtherError(format!("Query {:?}: failed to find function named {:?}", query.name, query.within)));
+        }
+        by_entry.entry(&query.within).or_insert_with(|| { entries.push(query.within.as_str()); vec![] }).push(i);
+    }
+
+    for entry in entries {
+        let query_idxs = &by_entry[entry];
+        let mut em: ExecutionManager<B> = symex_function(entry, project, config.clone());
+        let func: &'p Function = em.state().cur_loc.func;
+        loop {
+            let cut_off = match em.next() {
+                None => break,
+                Some(Ok(_)) => false,
+                Some(Err(e)) => {
+                    log::info!("Path ended in error; checking it up to the point of the error: {}", e);
+                    true
+                },
+            };
+            let path = em.state().get_path().clone();
+            let mut witness = None;
+            for &i in query_idxs {
+                if cut_off {
+                    outcomes[i].paths_cut_off += 1;
+                }
+                for location in sink_locations_on_path(&queries[i], func, &path) {
+                    if outcomes[i].flows.iter().any(|flow| flow.location == location) {
+                        continue;
+                    }
+                    if witness.is_none() {
+                        witness = Some(em.param_bvs().iter()
+                            .map(|bv| em.state().get_a_u64_solution_for_bv(bv).ok().flatten())
+                            .collect::<Vec<_>>());
+                    }
+                    outcomes[i].flows.push(Flow { location, witness: witness.clone().unwrap() });
+                }
+            }
+        }
+    }
+    Ok(outcomes)
+}
+
+/// Replay the parts of the `path` in `func`, tracking taint from the query's
+/// source, and return the locations where taint reaches the query's sink
+fn sink_locations_on_path(query: &FlowQuery, func: &Function, path: &[PathEntry]) -> Vec<String> {
+    let mut taint = Taint::default();
+    if let FlowSource::Parameter { index } = query.from {
+        if let Some(param) = func.parameters.get(index) {
+            taint.values.insert(param.name.clone());
+        }
+    }
+    let entries: Vec<&PathEntry> = path.iter().filter(|entry| entry.0.func.name == func.name).collect();
+    let mut locations = vec![];
+    for (i, entry) in entries.iter().enumerate() {
+        let bb = entry.0.bb;
+        let start = match entry.0.instr {
+            BBInstrIndex::Instr(start) => start,
+            BBInstrIndex::Terminator => bb.instrs.len(),
+        };
+        // if we come back to this block later in the middle (after returning
+        // from a callee), this entry ends at that point
+        let (end, includes_term) = match entries.get(i + 1) {
+            Some(next) if next.0.bb.name == bb.name => match next.0.instr {
+                BBInstrIndex::Instr(resume) if resume > start => (resume, false),
+                _ => (bb.instrs.len(), true),
+            },
+            _ => (bb.instrs.len(), true),
+        };
+        for (instnum, inst) in bb.instrs.iter().enumerate().take(end).skip(start) {
+            if taint.step(query, inst) {
+                locations.push(describe_location(func, &bb.name, BBInstrIndex::Instr(instnum), inst.get_debug_loc()));
+            }
+        }
+        if includes_term {
+            let cond = match &bb.term {
+                Terminator::CondBr(condbr) => Some(&condbr.condition),
+                Terminator::Switch(switch) => Some(&switch.operand),
+                _ => None,
+            };
+            if let (FlowSink::BranchCondition, Some(_)) = (&query.to, cond.filter(|cond| taint.is_tainted(cond))) {
+                locations.push(describe_location(func, &bb.name, BBInstrIndex::Terminator, bb.term.get_debug_loc()));
+            }
+        }
+    }
+    locations
+}
+
+fn describe_location(func: &Function, bbname: &Name, instr: BBInstrIndex, debugloc: &Option<llvm_ir::DebugLoc>) -> String {
+    match debugloc {
+        Some(debugloc) => debugloc.to_string(),
+        None => format!("{}, bb {}, {}", func.name, bbname, instr),
+    }
+}
+
+/// Taint state on one path through the entry function
+#[derive(Default)]
+struct Taint {
+    /// Tainted LLVM values
+    values: HashSet<Name>,
+    /// Pointers through which tainted values have been stored
+    pointees: HashSet<Name>,
+}
+
+impl Taint {
+    fn is_tainted(&self, op: &Operand) -> bool {
+        match op {
+            Operand::LocalOperand { name, .. } => self.values.contains(name),
+            _ => false,
+        }
+    }
+
+    /// Propagate taint through the instruction, returning `true` if it is a
+    /// sink of the query receiving tainted data
+    fn step(&mut self, query: &FlowQuery, inst: &Instruction) -> bool {
+        let mut reaches_sink = false;
+        let mut tainted = operands(inst).into_iter().any(|op| self.is_tainted(op));
+        match inst {
+            Instruction::Load(load) => {
+                // loading *through* a tainted pointer doesn't taint the result;
+                // only loading tainted data does
+                tainted = operand_name(&load.address).is_some_and(|name| self.pointees.contains(name));
+                if let FlowSource::GlobalRead { global } = &query.from {
+                    tainted |= global_name(&load.address) == Some(global);
+                }
+            },
+            Instruction::Store(store) if self.is_tainted(&store.value) => {
+                if let Operand::LocalOperand { name, .. } = &store.address {
+                    self.pointees.insert(name.clone());
+                }
+                if let FlowSink::GlobalStore { global } = &query.to {
+                    reaches_sink = global_name(&store.address) == Some(global);
+                }
+            },
+            Instruction::GetElementPtr(gep) => self.propagate_pointee(&gep.address, &gep.dest),
+            Instruction::BitCast(bc) => self.propagate_pointee(&bc.operand, &bc.dest),
+            Instruction::Call(call) => {
+                let callee = match &call.function {
+                    Either::Right(op) => global_name(op),
+                    Either::Left(_) => None,
+                };
+                if let FlowSink::Argument { function, index } = &query.to {
+                    if callee == Some(function) {
+                        reaches_sink = call.arguments.get(*index).map(|(arg, _)| self.is_tainted(arg)).unwrap_or(false);
+                    }
+                }
+                if let FlowSource::Return { function } = &query.from {
+                    tainted |= callee == Some(function);
+                }
+            },
+            _ => {},
+        }
+        if tainted {
+            if let Some(dest) = inst.try_get_result() {
+                self.values.insert(dest.clone());
+            }
+        }
+        reaches_sink
+    }
+
+    fn propagate_pointee(&mut self, from: &Operand, to: &Name) {
+        if let Some(from) = operand_name(from) {
+            if self.pointees.contains(from) {
+                self.pointees.insert(to.clone());
+            }
+        }
+    }
+}
+
+fn operand_name(op: &Operand) -> Option<&Name> {
+    match op {
+        Operand::LocalOperand { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+fn global_name(op: &Operand) -> Option<&String> {
+    match op {
+        Operand::ConstantOperand(Constant::GlobalReference { name: Name::Name(name), .. }) => Some(name),
+        _ => None,
+    }
+}
+
+/// The operands of the instruction whose taint flows to its result
+fn operands(inst: &Instruction) -> Vec<&Operand> {
+    match inst {
+        Instruction::Add(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Sub(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Mul(i) => vec![&i.operand0, &i.operand1],
+        Instruction::UDiv(i) => vec![&i.operand0, &i.operand1],
+        Instruction::SDiv(i) => vec![&i.operand0, &i.operand1],
+        Instruction::URem(i) => vec![&i.operand0, &i.operand1],
+        Instruction::SRem(i) => vec![&i.operand0, &i.operand1],
+        Instruction::And(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Or(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Xor(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Shl(i) => vec![&i.operand0, &i.operand1],
+        Instruction::LShr(i) => vec![&i.operand0, &i.operand1],
+        Instruction::AShr(i) => vec![&i.operand0, &i.operand1],
+        Instruction::ICmp(i) => vec![&i.operand0, &i.operand1],
+        Instruction::ZExt(i) => vec![&i.operand],
+        Instruction::SExt(i) => vec![&i.operand],
+        Instruction::Trunc(i) => vec![&i.operand],
+        Instruction::PtrToInt(i) => vec![&i.operand],
+        Instruction::IntToPtr(i) => vec![&i.operand],
+        Instruction::BitCast(i) => vec![&i.operand],
+        Instruction::Select(i) => vec![&i.condition, &i.true_value, &i.false_value],
+        Instruction::Phi(i) => i.incoming_values.iter().map(|(op, _)| op).collect(),
+        Instruction::GetElementPtr(i) => std::iter::once(&i.address).chain(i.indices.iter()).collect(),
+        Instruction::ExtractValue(i) => vec![&i.aggregate],
+        Instruction::InsertValue(i) => vec![&i.aggregate, &i.element],
+        Instruction::ExtractElement(i) => vec![&i.vector, &i.index],
+        Instruction::InsertElement(i) => vec![&i.vector, &i.element, &i.index],
+        Instruction::Call(i) => i.arguments.iter().map(|(op, _)| op).collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BtorBackend;
+    use crate::function_hooks;
+    use crate::test_utils::*;
+    use llvm_ir::*;
+
+    /// `void handle_packet(int flags) { int n = recv(); if (flags > 100) process(n + 4, 7); }`
+    fn handle_packet() -> Function {
+        let local_i32 = |name: &str| local(name, Type::i32());
+        let i32_const = |value| int_const(32, value);
+        let mut func = blank_function("handle_packet", vec![Name::from("entry"), Name::from("copy"), Name::from("done")]);
+        func.parameters.push(param("flags", Type::i32()));
+        func.basic_blocks[0].instrs = vec![
+            call("recv", Type::i32(), vec![], Some("n")),
+            icmp(IntPredicate::SGT, local_i32("flags"), i32_const(100), "big"),
+        ];
+        func.basic_blocks[0].term = condbr("big", "copy", "done");
+        func.basic_blocks[1].instrs = vec![
+            Instruction::Add(instruction::Add {
+                operand0: local_i32("n"),
+                operand1: i32_const(4),
+                dest: Name::from("len"),
+                debugloc: None,
+            }),
+            call("process", Type::VoidType, vec![local_i32("len"), i32_const(7)], None),
+        ];
+        func.basic_blocks[1].term = br("done");
+        func.basic_blocks[2].term = ret_void();
+        func
+    }
+
+    #[test]
+    fn one_found_one_absent() -> Result<()> {
+        let project = blank_project("test_mod", handle_packet());
+        let mut config = Config::default();
+        config.function_hooks.add("recv", &function_hooks::generic_stub_hook);
+        config.function_hooks.add("process", &function_hooks::generic_stub_hook);
+        let queries = vec![
+            FlowQuery {
+                name: "recv to process".to_owned(),
+                from: FlowSource::Return { function: "recv".to_owned() },
+                to: FlowSink::Argument { function: "process".to_owned(), index: 0 },
+                within: "handle_packet".to_owned(),
+            },
+            FlowQuery {
+                name: "flags to process".to_owned(),
+                from: FlowSource::Parameter { index: 0 },
+                to: FlowSink::Argument { function: "process".to_owned(), index: 0 },
+                within: "handle_packet".to_owned(),
+            },
+        ];
+        let outcomes = run_flow_queries::<BtorBackend>(&queries, &project, config)?;
+        assert_eq!(outcomes.len(), 2);
+
+        assert_eq!(outcomes[0].query, "recv to process");
+        assert_eq!(outcomes[0].flows.len(), 1);
+        assert_eq!(outcomes[0].flows[0].location, "handle_packet, bb copy, instr 1");
+        let flags = outcomes[0].flows[0].witness[0].expect("Expected a witness for flags") as u32 as i32;
+        assert!(flags > 100);
+
+        assert_eq!(outcomes[1].query, "flags to process");
+        assert!(outcomes[1].flows.is_empty());
+        assert_eq!(outcomes[1].paths_cut_off, 0);
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{BBInstrIndex, Config, Location, Project, State};
 use crate::backend::BtorBackend;
+use either::Either;
 use llvm_ir::*;
+use llvm_ir::module::{DLLStorageClass, GlobalVariable, Linkage, ThreadLocalMode, Visibility};
 use std::collections::HashMap;
 
 /// utility to initialize a `State` out of a `Project` and a function name
@@ -20,17 +22,61 @@ pub fn blank_state<'p>(project: &'p Project, funcname: &str) -> State<'p, BtorBa
 /// The `Project` will contain a single `Module` (with the given name) which contains
 /// a single function (given).
 pub fn blank_project(modname: impl Into<String>, func: Function) -> Project {
-    Project::from_module(Module {
+    project_with_functions(modname, vec![func])
+}
+
+/// Like `blank_project()`, but the `Module` contains all of the given functions
+pub fn project_with_functions(modname: impl Into<String>, functions: Vec<Function>) -> Project {
+    project_with_globals(modname, functions, vec![])
+}
+
+/// Like `project_with_functions()`, but the `Module` also contains the given
+/// global variables
+pub fn project_with_globals(modname: impl Into<String>, functions: Vec<Function>, global_vars: Vec<GlobalVariable>) -> Project {
+    Project::from_module(module_with_globals(modname, functions, global_vars))
+}
+
+/// utility that creates a `Module` for testing, containing the given functions
+/// and nothing else
+pub fn module_with_functions(modname: impl Into<String>, functions: Vec<Function>) -> Module {
+    module_with_globals(modname, functions, vec![])
+}
+
+/// utility that creates a `Module` for testing, containing the given functions
+/// and global variables, with an empty data layout and no other contents
+pub fn module_with_globals(modname: impl Into<String>, functions: Vec<Function>, global_vars: Vec<GlobalVariable>) -> Module {
+    Module {
         name: modname.into(),
         source_file_name: String::new(),
         data_layout: String::new(),
         target_triple: None,
-        functions: vec![func],
-        global_vars: vec![],
+        functions,
+        global_vars,
         global_aliases: vec![],
         named_struct_types: HashMap::new(),
         inline_assembly: String::new(),
-    })
+    }
+}
+
+/// utility that creates an externally visible, mutable `GlobalVariable` for
+/// testing. Use struct update syntax to change any of the other fields.
+pub fn global_var(name: impl Into<Name>, ty: Type, initializer: Option<Constant>) -> GlobalVariable {
+    GlobalVariable {
+        name: name.into(),
+        linkage: Linkage::External,
+        visibility: Visibility::Default,
+        is_constant: false,
+        ty,
+        addr_space: 0,
+        dll_storage_class: DLLStorageClass::Default,
+        thread_local_mode: ThreadLocalMode::NotThreadLocal,
+        unnamed_addr: None,
+        initializer,
+        section: None,
+        comdat: None,
+        alignment: 4,
+        debugloc: None,
+    }
 }
 
 /// utility that creates a technically valid (but functionally useless)
@@ -45,3 +91,83 @@ pub fn blank_function(name: impl Into<String>, bbnames: Vec<Name>) -> Function {
     }
     func
 }
+
+/// utility that creates a function `Parameter` with no attributes
+pub fn param(name: impl Into<Name>, ty: Type) -> function::Parameter {
+    function::Parameter { name: name.into(), ty, attributes: vec![] }
+}
+
+/// `Operand` referring to the local variable with the given name and type
+pub fn local(name: impl Into<Name>, ty: Type) -> Operand {
+    Operand::LocalOperand { name: name.into(), ty }
+}
+
+/// `Operand` for an integer constant of the given width
+pub fn int_const(bits: u32, value: u64) -> Operand {
+    Operand::ConstantOperand(Constant::Int { bits, value })
+}
+
+/// `ret` of the given operand
+pub fn ret(operand: Operand) -> Terminator {
+    Terminator::Ret(terminator::Ret { return_operand: Some(operand), debugloc: None })
+}
+
+/// `ret void`
+pub fn ret_void() -> Terminator {
+    Terminator::Ret(terminator::Ret { return_operand: None, debugloc: None })
+}
+
+/// `br` to the block with the given name
+pub fn br(dest: impl Into<Name>) -> Terminator {
+    Terminator::Br(terminator::Br { dest: dest.into(), debugloc: None })
+}
+
+/// `br` conditioned on the `i1` local variable with the given name
+pub fn condbr(cond: impl Into<Name>, true_dest: impl Into<Name>, false_dest: impl Into<Name>) -> Terminator {
+    Terminator::CondBr(terminator::CondBr {
+        condition: local(cond, Type::bool()),
+        true_dest: true_dest.into(),
+        false_dest: false_dest.into(),
+        debugloc: None,
+    })
+}
+
+/// `icmp` of the given operands, into the local variable with the given name
+pub fn icmp(predicate: IntPredicate, operand0: Operand, operand1: Operand, dest: impl Into<Name>) -> Instruction {
+    Instruction::ICmp(instruction::ICmp { predicate, operand0, operand1, dest: dest.into(), debugloc: None })
+}
+
+/// `alloca` of `num_elements` values of the given type
+pub fn alloca(allocated_type: Type, num_elements: Operand, dest: impl Into<Name>) -> Instruction {
+    Instruction::Alloca(instruction::Alloca { allocated_type, num_elements, dest: dest.into(), alignment: 4, debugloc: None })
+}
+
+/// non-volatile, non-atomic `load` from the given address
+pub fn load(address: Operand, dest: impl Into<Name>) -> Instruction {
+    Instruction::Load(instruction::Load { address, dest: dest.into(), volatile: false, atomicity: None, alignment: 4, debugloc: None })
+}
+
+/// non-volatile, non-atomic `store` of the given value to the given address
+pub fn store(address: Operand, value: Operand) -> Instruction {
+    Instruction::Store(instruction::Store { address, value, volatile: false, atomicity: None, alignment: 4, debugloc: None })
+}
+
+/// `call` of the (non-variadic) function with the given name, taking operands
+/// of the types of `arguments` and returning `result_type`. `dest` should be
+/// `None` if and only if `result_type` is `void`.
+pub fn call(funcname: impl Into<Name>, result_type: Type, arguments: Vec<Operand>, dest: Option<&str>) -> Instruction {
+    let param_types = arguments.iter().map(Typed::get_type).collect();
+    Instruction::Call(instruction::Call {
+        function: Either::Right(Operand::ConstantOperand(Constant::GlobalReference {
+            name: funcname.into(),
+            ty: Type::pointer_to(Type::FuncType { result_type: Box::new(result_type), param_types, is_var_arg: false }),
+        })),
+        arguments: arguments.into_iter().map(|arg| (arg, vec![])).collect(),
+        return_attributes: vec![],
+        dest: dest.map(Name::from),
+        function_attributes: vec![],
+        is_tail_call: false,
+        calling_convention: function::CallingConvention::C,
+        debugloc: None,
+    })
+}
@@ -9,6 +9,9 @@ use itertools::Itertools;
 use log::debug;
 
 use llvm_ir::Name;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 
 #[derive(Clone)]
 pub struct VarMap<V: BV> {
@@ -36,6 +39,15 @@ pub struct VarMap<V: BV> {
     /// counters for this purpose - they can each have versions up to the
     /// `max_version_num`.
     max_version_num: usize,
+    /// Maps each symbol we've given to the solver to the `(String, Name)` pair
+    /// it was created for. Versioned names alone aren't unique (e.g. `x` in
+    /// function `f_name_a` and `a_name_x` in function `f`), and the solver
+    /// rejects duplicate symbols, so symbols for a different pair get a
+    /// counter appended.
+    ///
+    /// This is shared between clones, so symbols aren't forgotten when an
+    /// older `VarMap` is restored on backtracking.
+    symbols: Rc<RefCell<HashMap<String, (String, Name)>>>,
 }
 
 impl<V: BV> VarMap<V> {
@@ -53,6 +65,7 @@ impl<V: BV> VarMap<V> {
             active_version: DoubleKeyedMap::new(),
             version_num: DoubleKeyedMap::new(),
             max_version_num: max_versions_of_name - 1,  // because 0 is a version
+            symbols: Rc::new(RefCell::new(HashMap::new())),
         }
     }
 
@@ -102,15 +115,47 @@ impl<V: BV> VarMap<V> {
         }
     }
 
+    /// The number of variables (across all functions) which currently have an
+    /// active version.
+    pub fn num_vars(&self) -> usize {
+        self.active_version.len()
+    }
+
     /// Look up the most recent `BV` created for the given `(String, Name)` pair.
     #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
-    pub fn lookup_var(&self, funcname: &String, name: &Name) -> &V {
+    ///
+    /// Returns `Error::UnmappedValue` if no `BV` has been created for it.
+    pub fn lookup_var(&self, funcname: &String, name: &Name) -> Result<&V> {
+        self.active_version.get(funcname, name).ok_or_else(|| Error::UnmappedValue {
+            name: match name {
+                Name::Name(name) => format!("%{}", name),
+                Name::Number(n) => format!("%{}", n),
+            },
+            funcname: funcname.clone(),
+        })
+    }
+
+    /// Like `lookup_var()`, but panics if no `BV` has been created for the
+    /// given `(String, Name)` pair, listing all the pairs which do have one.
+    /// For troubleshooting only.
+    #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
+    pub fn lookup_var_or_panic(&self, funcname: &String, name: &Name) -> &V {
         self.active_version.get(funcname, name).unwrap_or_else(|| {
             let keys: Vec<(&String, &Name)> = self.active_version.keys().collect();
             panic!("Failed to find var {:?} from function {:?} in map with keys {:?}", name, funcname, keys);
         })
     }
 
+    /// Find the `(String, Name)` pair whose active version is `bv`, if any.
+    /// If there are several (e.g., because of a `bitcast`), returns the least.
+    pub fn find_var(&self, bv: &V) -> Option<(&String, &Name)> {
+        let id = bv.get_id();
+        self.active_version.iter()
+            .filter(|(_, _, v)| v.get_id() == id)
+            .map(|(f, n, _)| (f, n))
+            .min()
+    }
+
     /// Overwrite the latest version of the given `(String, Name)` pair to instead be `bv`.
     /// The `(String, Name)` pair must have already been previously assigned a value.
     #[allow(clippy::ptr_arg)]  // as of this writing, clippy warns that the &String argument should be &str; but it actually needs to be &String here
@@ -139,10 +184,33 @@ impl<V: BV> VarMap<V> {
         let new_version_num = self.version_num.entry(funcname.to_owned(), name.clone())
             .and_modify(|v| *v += 1)  // increment if it already exists in map
             .or_insert(0);  // insert a 0 if it didn't exist in map
-        if *new_version_num > self.max_version_num {
+        let new_version_num = *new_version_num;
+        if new_version_num > self.max_version_num {
             Err(Error::LoopBoundExceeded(self.max_version_num))
         } else {
-            Ok(Self::build_versioned_name(funcname, name, *new_version_num))
+            Ok(self.unique_symbol(Self::build_versioned_name(funcname, name, new_version_num), funcname, name))
+        }
+    }
+
+    /// Given the versioned name for a `Name` (from a particular function),
+    /// get a symbol which no other `(String, Name)` pair has used, by
+    /// appending a counter if necessary.
+    fn unique_symbol(&self, versioned_name: String, funcname: &str, name: &Name) -> String {
+        let mut symbols = self.symbols.borrow_mut();
+        let mut symbol = versioned_name.clone();
+        let mut counter = 0;
+        loop {
+            match symbols.get(&symbol) {
+                None => {
+                    symbols.insert(symbol.clone(), (funcname.to_owned(), name.clone()));
+                    return symbol;
+                },
+                Some((f, n)) if f == funcname && n == name => return symbol,
+                Some(_) => {
+                    counter += 1;
+                    symbol = format!("{}#{}", versioned_name, counter);
+                },
+            }
         }
     }
 
@@ -212,12 +280,21 @@ pub struct RestoreInfo<V: BV> {
     pairs_to_restore: Vec<(Name, V)>,
 }
 
+impl<V: BV> RestoreInfo<V> {
+    /// Adapt the `RestoreInfo` to a new solver instance, as with
+    /// `VarMap::change_solver()`
+    pub fn change_solver(&mut self, new_solver: V::SolverRef) {
+        for (_, v) in self.pairs_to_restore.iter_mut() {
+            *v = new_solver.match_bv(v).unwrap();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use boolector::Btor;
     use crate::solver_utils;
-    use std::rc::Rc;
 
     type BV = boolector::BV<Rc<Btor>>;
 
@@ -236,8 +313,66 @@ mod tests {
         let var2 = varmap.new_bv_with_name(funcname.clone(), name2.clone(), 1).unwrap();  // these clone()s wouldn't normally be necessary but we want to compare against the original values later
 
         // check that looking up the llvm-ir values gives the correct BV ones
-        assert_eq!(varmap.lookup_var(&funcname, &name1), &var1);
-        assert_eq!(varmap.lookup_var(&funcname, &name2), &var2);
+        assert_eq!(varmap.lookup_var(&funcname, &name1), Ok(&var1));
+        assert_eq!(varmap.lookup_var(&funcname, &name2), Ok(&var2));
+    }
+
+    #[test]
+    fn find_vars() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        let mut varmap: VarMap<BV> = VarMap::new(btor.clone(), 20);
+        let funcname = "foo".to_owned();
+
+        let x = varmap.new_bv_with_name(funcname.clone(), Name::from("x"), 8).unwrap();
+        varmap.assign_bv_to_name(funcname.clone(), Name::from(3), x.clone()).unwrap();
+        varmap.assign_bv_to_name("bar".to_owned(), Name::from("y"), x.clone()).unwrap();
+        assert_eq!(varmap.find_var(&x), Some((&"bar".to_owned(), &Name::from("y"))));
+
+        // only active versions are found
+        let x2 = varmap.new_bv_with_name(funcname.clone(), Name::from("x"), 8).unwrap();
+        assert_eq!(varmap.find_var(&x2), Some((&funcname, &Name::from("x"))));
+        assert_eq!(varmap.find_var(&BV::zero(btor, 8)), None);
+    }
+
+    #[test]
+    fn lookup_unmapped_var() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        let mut varmap: VarMap<BV> = VarMap::new(btor, 20);
+        let funcname = "foo".to_owned();
+        varmap.new_bv_with_name(funcname.clone(), Name::from("val"), 64).unwrap();
+
+        // a missing value is an error, not a panic; and neither is the right
+        // name in the wrong function
+        assert_eq!(varmap.lookup_var(&funcname, &Name::from("missing")), Err(Error::UnmappedValue {
+            name: "%missing".to_owned(),
+            funcname: "foo".to_owned(),
+        }));
+        assert!(varmap.lookup_var(&"bar".to_owned(), &Name::from("val")).is_err());
+    }
+
+    #[test]
+    fn bools_are_1_bit_bvs() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        let mut varmap: VarMap<BV> = VarMap::new(btor.clone(), 20);
+        let funcname = "foo".to_owned();
+
+        // the result of a comparison, stored as an `i1` ...
+        let x = varmap.new_bv_with_name(funcname.clone(), Name::from("x"), 8).unwrap();
+        let cmp = x._eq(&BV::from_u64(btor.clone(), 5, 8));
+        varmap.assign_bv_to_name(funcname.clone(), Name::from("cmp"), cmp).unwrap();
+
+        // ... can be used as an integer, e.g. zero-extended to `i8`
+        let widened = varmap.lookup_var(&funcname, &Name::from("cmp")).unwrap().uext(7);
+        assert_eq!(widened.get_width(), 8);
+        widened._eq(&BV::one(btor.clone(), 8)).assert();
+        assert_eq!(solver_utils::bvs_must_be_equal(&btor, &x, &BV::from_u64(btor.clone(), 5, 8)), Ok(true));
+
+        // and an `i1` computed arithmetically can be used as a condition
+        let bit = x.slice(0, 0);
+        varmap.assign_bv_to_name(funcname.clone(), Name::from("bit"), bit).unwrap();
+        let bit = varmap.lookup_var(&funcname, &Name::from("bit")).unwrap();
+        let selected = bit.cond_bv(&BV::from_u64(btor.clone(), 10, 8), &BV::from_u64(btor.clone(), 20, 8));
+        assert_eq!(solver_utils::bvs_must_be_equal(&btor, &selected, &BV::from_u64(btor.clone(), 10, 8)), Ok(true));
     }
 
     #[test]
@@ -276,6 +411,29 @@ mod tests {
         assert_eq!(solver_utils::sat(&btor), Ok(true));
     }
 
+    #[test]
+    fn colliding_versioned_names() {
+        let btor = <Rc<Btor> as SolverRef>::new();
+        let mut varmap: VarMap<BV> = VarMap::new(btor.clone(), 20);
+
+        // both of these would naively be `@f_name_a_name_x_0`
+        let x1 = varmap.new_bv_with_name("f_name_a".to_owned(), Name::from("x"), 8).unwrap();
+        let x2 = varmap.new_bv_with_name("f".to_owned(), Name::from("a_name_x"), 8).unwrap();
+        assert_eq!(x1.get_symbol(), Some("@f_name_a_name_x_0"));
+        assert_eq!(x2.get_symbol(), Some("@f_name_a_name_x_0#1"));
+
+        // and they're different variables
+        x1._eq(&BV::from_u64(btor.clone(), 1, 8)).assert();
+        x2._eq(&BV::from_u64(btor.clone(), 2, 8)).assert();
+        assert_eq!(solver_utils::sat(&btor), Ok(true));
+
+        // later versions are disambiguated the same way
+        let x1 = varmap.new_bv_with_name("f_name_a".to_owned(), Name::from("x"), 8).unwrap();
+        let x2 = varmap.new_bv_with_name("f".to_owned(), Name::from("a_name_x"), 8).unwrap();
+        assert_eq!(x1.get_symbol(), Some("@f_name_a_name_x_1"));
+        assert_eq!(x2.get_symbol(), Some("@f_name_a_name_x_1#1"));
+    }
+
     #[test]
     fn enforces_max_version() {
         let btor = <Rc<Btor> as SolverRef>::new();
@@ -319,11 +477,11 @@ mod tests {
         let foo2 = varmap.new_bv_with_name("func".to_owned(), fooname.clone(), 64).unwrap();
 
         // check that a lookup gives the most recent var
-        assert_eq!(varmap.lookup_var(&"func".to_owned(), &fooname), &foo2);
+        assert_eq!(varmap.lookup_var(&"func".to_owned(), &fooname), Ok(&foo2));
 
         // restore, and check that a lookup now gives the first var
         varmap.restore_fn_vars(rinfo);
-        assert_eq!(varmap.lookup_var(&"func".to_owned(), &fooname), &foo1);
+        assert_eq!(varmap.lookup_var(&"func".to_owned(), &fooname), Ok(&foo1));
     }
 
     #[test]
@@ -342,8 +500,8 @@ mod tests {
         let foo2 = varmap.new_bv_with_name("func".to_owned(), fooname.clone(), 64).unwrap();
 
         // restore function "blah", and check that lookups in function "func" are unaffected
-        assert_eq!(varmap.lookup_var(&"func".to_owned(), &fooname), &foo2);
+        assert_eq!(varmap.lookup_var(&"func".to_owned(), &fooname), Ok(&foo2));
         varmap.restore_fn_vars(rinfo_blah);
-        assert_eq!(varmap.lookup_var(&"func".to_owned(), &fooname), &foo2);
+        assert_eq!(varmap.lookup_var(&"func".to_owned(), &fooname), Ok(&foo2));
     }
 }
@@ -46,6 +46,33 @@ impl fmt::Display for Watchpoint {
     }
 }
 
+/// Whether a memory access was a read or a write
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// A memory access which triggered a watchpoint; see
+/// [`State::watchpoint_hits()`](../struct.State.html#method.watchpoint_hits).
+#[derive(Clone, Debug)]
+pub struct WatchpointHit<V> {
+    /// Name of the watchpoint which was triggered
+    pub name: String,
+    /// The watchpoint which was triggered
+    pub watchpoint: Watchpoint,
+    /// Whether the access was a read or a write
+    pub access: Access,
+    /// The location of the instruction which made the access
+    pub location: String,
+    /// Which path the access was made on: `0` for the path explored by the
+    /// first call to `ExecutionManager::next()`, `1` for the second, and so on
+    pub path: usize,
+    /// The value read or written. This is the whole access, which may extend
+    /// past the watched segment of memory.
+    pub value: V,
+}
+
 /// Stores information about watchpoints and performs operations with them.
 ///
 /// External users (that is, `haybale` users) probably don't want to use this
@@ -186,8 +213,10 @@ impl Watchpoints {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::BtorBackend;
     use crate::test_utils::*;
-    use llvm_ir::Name;
+    use crate::{symex_function, Config, ExecutionManager, Project};
+    use llvm_ir::*;
 
     #[test]
     fn watchpoints() -> Result<()> {
@@ -271,4 +300,35 @@ mod tests {
 
         Ok(())
     }
+
+    /// `int g = 0; void f(bool c) { if (c) g = 5; }`
+    fn conditional_global_write() -> Project {
+        let mut func = blank_function("f", vec![Name::from("entry"), Name::from("write"), Name::from("done")]);
+        func.parameters.push(param("c", Type::bool()));
+        func.basic_blocks[0].term = condbr("c", "write", "done");
+        func.basic_blocks[1].instrs.push(store(Operand::ConstantOperand(Constant::GlobalReference { name: Name::from("g"), ty: Type::i32() }), int_const(32, 5)));
+        func.basic_blocks[1].term = br("done");
+        func.basic_blocks[2].term = ret_void();
+        project_with_globals("test_mod", vec![func], vec![global_var("g", Type::pointer_to(Type::i32()), Some(Constant::Int { bits: 32, value: 0 }))])
+    }
+
+    #[test]
+    fn watchpoint_hits() -> Result<()> {
+        let project = conditional_global_write();
+        let config = Config { collect_watchpoint_hits: true, ..Config::default() };
+        let mut em: ExecutionManager<BtorBackend> = symex_function("f", &project, config);
+        // taking the address initializes `g`, so the initializer doesn't count as a hit
+        let g = em.state().const_to_bv(&Constant::GlobalReference { name: Name::from("g"), ty: Type::i32() })?;
+        let g = g.as_u64().unwrap();
+        em.mut_state().add_mem_watchpoint("g", Watchpoint::new(g, 4));
+        while em.next().is_some() {}
+
+        let hits = em.state().watchpoint_hits();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].name, "g");
+        assert_eq!(hits[0].access, Access::Write);
+        assert_eq!(hits[0].location, "test_mod: f, bb write, instr 0");
+        assert_eq!(hits[0].value.as_u64(), Some(5));
+        Ok(())
+    }
 }
@@ -0,0 +1,62 @@
+use haybale::*;
+use haybale::analyze::block_reaching_conditions;
+use haybale::backend::BtorBackend;
+use llvm_ir::Name;
+use std::collections::HashSet;
+use std::path::Path;
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+fn get_project(modname: &str) -> Project {
+    Project::from_bc_path(Path::new(modname))
+        .unwrap_or_else(|e| panic!("Failed to parse module {:?}: {}", modname, e))
+}
+
+/// Explore every path through the function (up to the default loop bound),
+/// and return the names of all basic blocks which were visited on some path
+fn blocks_visited_by_exploration(funcname: &str, project: &Project) -> HashSet<Name> {
+    let mut em: ExecutionManager<BtorBackend> = symex_function(funcname, project, Config::default());
+    let mut visited = HashSet::new();
+    // on error (e.g., exceeding the loop bound) we still get the path up to the error
+    while em.next().is_some() {
+        visited.extend(em.state().get_path().iter()
+            .filter(|entry| entry.0.func.name == funcname)
+            .map(|entry| entry.0.bb.name.clone()));
+    }
+    visited
+}
+
+/// Compute the set of blocks `block_reaching_conditions()` considers live
+fn live_blocks(funcname: &str, project: &Project) -> HashSet<Name> {
+    block_reaching_conditions::<BtorBackend>(funcname, project, Config::default())
+        .unwrap_or_else(|e| panic!("Failed to compute reaching conditions for {:?}: {}", funcname, e))
+        .into_iter()
+        .filter(|(_, rc)| !rc.dead)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+#[test]
+fn acyclic_functions_match_exploration() {
+    init_logging();
+    let proj = get_project("tests/bcfiles/basic.bc");
+    for funcname in &["conditional_true", "conditional_false", "conditional_nozero", "conditional_with_and", "has_switch"] {
+        let visited = blocks_visited_by_exploration(funcname, &proj);
+        let live = live_blocks(funcname, &proj);
+        assert_eq!(live, visited, "classification of blocks in {:?} differs from exploration", funcname);
+    }
+}
+
+#[test]
+fn loops_are_sound_wrt_exploration() {
+    init_logging();
+    let proj = get_project("tests/bcfiles/loop.bc");
+    for funcname in &["while_loop", "for_loop", "loop_zero_iterations", "loop_with_cond", "loop_inside_cond", "nested_loop"] {
+        let visited = blocks_visited_by_exploration(funcname, &proj);
+        let live = live_blocks(funcname, &proj);
+        assert!(visited.is_subset(&live), "in {:?}, exploration visited blocks {:?} which were classified dead", funcname, visited.difference(&live).collect::<Vec<_>>());
+    }
+}
@@ -18,7 +18,7 @@ fn no_args_nozero() {
     let funcname = "no_args_nozero";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r));
     assert_eq!(args, None);
 }
@@ -28,7 +28,7 @@ fn no_args_zero() {
     let funcname = "no_args_zero";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 0);
@@ -39,7 +39,7 @@ fn one_arg() {
     let funcname = "one_arg";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -52,7 +52,7 @@ fn two_args() {
     let funcname = "two_args";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -65,7 +65,7 @@ fn three_args() {
     let funcname = "three_args";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 3);
@@ -78,7 +78,7 @@ fn four_args() {
     let funcname = "four_args";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 4);
@@ -91,7 +91,7 @@ fn five_args() {
     let funcname = "five_args";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 5);
@@ -99,12 +99,26 @@ fn five_args() {
     assert_eq!(sum, 3);
 }
 
+#[test]
+fn five_args_shrunk() {
+    let funcname = "five_args";
+    init_logging();
+    let proj = get_project();
+    let mut config = Config::default();
+    config.shrink_witnesses = Some(100);
+    let args = find_zero_of_func_simple(funcname, &proj, config)
+            .unwrap_or_else(|r| panic!("{}", r))
+            .expect("Failed to find zero of the function");
+    // only the last argument needs to be nonzero
+    assert_eq!(args, vec![SolutionValue::I32(0), SolutionValue::I32(0), SolutionValue::I32(0), SolutionValue::I32(0), SolutionValue::I32(3)]);
+}
+
 #[test]
 fn binops() {
     let funcname = "binops";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -117,12 +131,38 @@ fn binops() {
     assert_eq!((e >> (d.0 as usize)).0, 0);
 }
 
+#[test]
+fn binops_reproducible() {
+    let funcname = "binops";
+    init_logging();
+    let proj = get_project();
+    let run = |seed| {
+        let mut config = Config::default();
+        config.random_seed = seed;
+        find_zero_of_func_simple(funcname, &proj, config)
+            .unwrap_or_else(|r| panic!("{}", r))
+            .expect("Failed to find zero of the function")
+    };
+    // the same seed gives exactly the same solution
+    let first = run(1234);
+    let second = run(1234);
+    assert_eq!(format!("{:?}", first), format!("{:?}", second));
+    // a different seed may give a different solution, but it must still be valid
+    let other = run(5678);
+    let a = Wrapping(other[0].unwrap_to_i32());
+    let b = Wrapping(other[1].unwrap_to_i32());
+    let c = a + b - (Wrapping(77) * a) + Wrapping(1);
+    let d = (c & Wrapping(23)) / (a | Wrapping(99));
+    let e = (d ^ a) % (c << 3);
+    assert_eq!((e >> (d.0 as usize)).0, 0);
+}
+
 #[test]
 fn conditional_true() {
     let funcname = "conditional_true";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -138,7 +178,7 @@ fn conditional_false() {
     let funcname = "conditional_false";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -155,7 +195,7 @@ fn conditional_nozero() {
     let funcname = "conditional_nozero";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r));
     assert_eq!(args, None);
 }
@@ -165,7 +205,7 @@ fn conditional_with_and() {
     let funcname = "conditional_with_and";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -181,7 +221,7 @@ fn switch() {
     let funcname = "has_switch";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -197,7 +237,7 @@ fn int8t() {
     let funcname = "int8t";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -210,7 +250,7 @@ fn int16t() {
     let funcname = "int16t";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -223,7 +263,7 @@ fn int32t() {
     let funcname = "int32t";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -236,7 +276,7 @@ fn int64t() {
     let funcname = "int64t";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -249,7 +289,7 @@ fn mixed_bitwidths() {
     let funcname = "mixed_bitwidths";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 4);
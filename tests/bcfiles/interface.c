@@ -0,0 +1,10 @@
+// Defines what interface.h declares, so that clang emits all of its types
+
+#include "interface.h"
+
+struct Packet packets[4] = { 0 };
+union Value last_value = { 0 };
+
+int32_t packet_kind(int32_t i) {
+  return packets[i].header.kind;
+}
@@ -0,0 +1,38 @@
+// Types for the interface report tests: nested structs, a union, and
+// bitfields, as they might appear in a C library's public header
+
+#include <stdint.h>
+
+struct Flags {
+  unsigned ready : 1;
+  unsigned mode : 3;
+  unsigned count : 12;
+  uint8_t tag;
+  uint32_t id;
+};
+
+union Value {
+  int32_t i;
+  double d;
+  char bytes[12];
+};
+
+struct Header {
+  uint16_t kind;
+  struct Flags flags;
+};
+
+struct Packet {
+  struct Header header;
+  union Value value;
+  struct {
+    uint8_t len;
+    uint32_t crc;
+  } trailer;
+  struct Packet* next;
+};
+
+extern struct Packet packets[4];
+extern union Value last_value;
+
+int32_t packet_kind(int32_t i);
@@ -0,0 +1,38 @@
+; ModuleID = 'interface.c'
+source_filename = "interface.c"
+target datalayout = "e-m:o-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-apple-macosx10.14.0"
+
+%struct.Packet = type { %struct.Header, %union.Value, %struct.anon, %struct.Packet* }
+%struct.Header = type { i16, %struct.Flags }
+%struct.Flags = type { i16, i8, i32 }
+%union.Value = type { double, [8 x i8] }
+%struct.anon = type { i8, i32 }
+
+@packets = global [4 x %struct.Packet] zeroinitializer, align 16
+@last_value = global %union.Value zeroinitializer, align 8
+
+; Function Attrs: norecurse nounwind readonly ssp uwtable
+define i32 @packet_kind(i32) local_unnamed_addr #0 {
+  %2 = sext i32 %0 to i64
+  %3 = getelementptr inbounds [4 x %struct.Packet], [4 x %struct.Packet]* @packets, i64 0, i64 %2, i32 0, i32 0
+  %4 = load i16, i16* %3, align 16, !tbaa !3
+  %5 = zext i16 %4 to i32
+  ret i32 %5
+}
+
+attributes #0 = { norecurse nounwind readonly ssp uwtable "correctly-rounded-divide-sqrt-fp-math"="false" "disable-tail-calls"="false" "less-precise-fpmad"="false" "min-legal-vector-width"="0" "no-frame-pointer-elim"="true" "no-frame-pointer-elim-non-leaf" "no-infs-fp-math"="false" "no-jump-tables"="false" "no-nans-fp-math"="false" "no-signed-zeros-fp-math"="false" "no-trapping-math"="false" "stack-protector-buffer-size"="8" "target-cpu"="penryn" "target-features"="+cx16,+cx8,+fxsr,+mmx,+sahf,+sse,+sse2,+sse3,+sse4.1,+ssse3,+x87" "unsafe-fp-math"="false" "use-soft-float"="false" }
+
+!llvm.module.flags = !{!0, !1}
+!llvm.ident = !{!2}
+
+!0 = !{i32 1, !"wchar_size", i32 4}
+!1 = !{i32 7, !"PIC Level", i32 2}
+!2 = !{!"clang version 9.0.0 (tags/RELEASE_900/final)"}
+!3 = !{!4, !5, i64 0}
+!4 = !{!"Header", !5, i64 0, !8, i64 4}
+!5 = !{!"short", !6, i64 0}
+!6 = !{!"omnipotent char", !7, i64 0}
+!7 = !{!"Simple C/C++ TBAA"}
+!8 = !{!"Flags", !9, i64 0, !9, i64 0, !9, i64 0, !6, i64 2, !9, i64 4}
+!9 = !{!"int", !6, i64 0}
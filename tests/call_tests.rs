@@ -18,7 +18,7 @@ fn simple_call() {
     let funcname = "simple_caller";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -33,7 +33,7 @@ fn cross_module_simple_call() {
     init_logging();
     let proj = Project::from_bc_paths(vec![callee_modname, caller_modname].into_iter().map(std::path::Path::new))
         .unwrap_or_else(|e| panic!("Failed to parse modules: {}", e));
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -45,7 +45,7 @@ fn conditional_call() {
     let funcname = "conditional_caller";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -58,7 +58,7 @@ fn call_twice() {
     let funcname = "twice_caller";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -73,7 +73,7 @@ fn cross_module_call_twice() {
     init_logging();
     let proj = Project::from_bc_paths(vec![callee_modname, caller_modname].into_iter().map(std::path::Path::new))
         .unwrap_or_else(|e| panic!("Failed to parse modules: {}", e));
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -85,7 +85,7 @@ fn nested_call() {
     let funcname = "nested_caller";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -103,7 +103,7 @@ fn cross_module_nested_near_call() {
     init_logging();
     let proj = Project::from_bc_paths(vec![callee_modname, caller_modname].into_iter().map(std::path::Path::new))
         .unwrap_or_else(|e| panic!("Failed to parse modules: {}", e));
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -121,7 +121,7 @@ fn cross_module_nested_far_call() {
     init_logging();
     let proj = Project::from_bc_paths(vec![callee_modname, caller_modname].into_iter().map(std::path::Path::new))
         .unwrap_or_else(|e| panic!("Failed to parse modules: {}", e));
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -136,7 +136,7 @@ fn call_of_loop() {
     let funcname = "caller_of_loop";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -148,7 +148,7 @@ fn call_in_loop() {
     let funcname = "caller_with_loop";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -160,7 +160,7 @@ fn recursive_simple() {
     let funcname = "recursive_simple";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -184,7 +184,7 @@ fn recursive_double() {
     let funcname = "recursive_double";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -196,7 +196,7 @@ fn recursive_not_tail() {
     let funcname = "recursive_not_tail";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -223,7 +223,7 @@ fn recursive_and_normal_call() {
     let funcname = "recursive_and_normal_caller";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -235,7 +235,7 @@ fn mutually_recursive_functions() {
     let funcname = "mutually_recursive_a";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
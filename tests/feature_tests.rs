@@ -0,0 +1,57 @@
+//! Checks that the public API matches the enabled Cargo features. Run these
+//! with different feature combinations, e.g. `--no-default-features` and
+//! `--no-default-features --features serde-results`; `check-features.sh`
+//! builds them in each of the combinations we support.
+
+use haybale::backend::BtorBackend;
+use haybale::*;
+
+#[test]
+fn core_api_always_available() {
+    let config: Config<BtorBackend> = Config::default();
+    assert!(config.extra_checkers.is_empty());
+    let _checker: &dyn checkers::ViolationChecker<BtorBackend> = &checkers::DivisionByZero;
+    let _hooks = function_hooks::FunctionHooks::<BtorBackend>::default();
+}
+
+#[cfg(feature = "fp")]
+#[test]
+fn fp_available() {
+    let config: Config<BtorBackend> = Config::default();
+    assert_eq!(config.fp_rounding_mode, config::RoundingMode::NearestTiesToEven);
+    assert_eq!(config.fma_semantics, config::FmaSemantics::Fused);
+    let format = float::FloatFormat::from_fptype(llvm_ir::types::FPType::Double).unwrap();
+    assert_eq!(format.width(), 64);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_available() {
+    let project = Project::from_bc_dir_with_jobs("tests/bcfiles", "bc", 2)
+        .unwrap_or_else(|e| panic!("Failed to create project: {}", e));
+    assert!(project.get_func_by_name("no_args_zero").is_some());
+}
+
+#[cfg(feature = "taint")]
+#[test]
+fn taint_available() {
+    let query = taint::FlowQuery {
+        name: "q".to_owned(),
+        from: taint::FlowSource::Parameter { index: 0 },
+        to: taint::FlowSink::BranchCondition,
+        within: "f".to_owned(),
+    };
+    assert_eq!(query.clone(), query);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_results_available() {
+    fn assert_serde<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+    assert_serde::<SearchOutcome<u64>>();
+    assert_serde::<interface::FunctionSignature>();
+    assert_serde::<SolutionValue>();
+    assert_serde::<PointerTarget>();
+    #[cfg(feature = "taint")]
+    assert_serde::<taint::FlowQuery>();
+}
@@ -0,0 +1,76 @@
+use haybale::*;
+use std::path::Path;
+
+fn get_project() -> Project {
+    let modname = "tests/bcfiles/interface.bc";
+    Project::from_bc_path(Path::new(modname))
+        .unwrap_or_else(|e| panic!("Failed to parse module {:?}: {}", modname, e))
+}
+
+#[test]
+fn c_header_layouts() {
+    let proj = get_project();
+    let report = proj.interface_report();
+
+    // the bitfields share one i16 storage unit, followed by `tag` and `id`
+    let flags = report.get_struct("struct.Flags").expect("Failed to find struct.Flags");
+    let offsets: Vec<Option<usize>> = flags.fields.iter().map(|f| f.offset_bits).collect();
+    assert_eq!(offsets, vec![Some(0), Some(16), Some(32)]);
+    assert_eq!(flags.size_bits, Some(64));
+
+    // the union is lowered to its most-aligned member plus padding, and sized for `bytes`
+    let value = report.get_struct("union.Value").expect("Failed to find union.Value");
+    assert!(value.is_union);
+    assert_eq!(value.size_bits, Some(16 * 8));
+
+    // `{ i8, i32 }` isn't mistaken for a union
+    let anon = report.get_struct("struct.anon").expect("Failed to find struct.anon");
+    assert!(!anon.is_union);
+
+    let packet = report.get_struct("struct.Packet").expect("Failed to find struct.Packet");
+    assert_eq!(packet.size_bits, Some(48 * 8));
+}
+
+#[test]
+fn c_header_display_snapshot() {
+    // nested structs are expanded one level, with offsets relative to the outermost struct
+    let proj = get_project();
+    let report = proj.interface_report();
+    assert_eq!(format!("{}", report), "\
+Types:
+struct %struct.Flags: 8 bytes
+  [0] +0     i16 (2 bytes)
+  [1] +2     i8 (1 byte)
+  [2] +4     i32 (4 bytes)
+struct %struct.Header: 12 bytes
+  [0] +0     i16 (2 bytes)
+  [1] +4     %struct.Flags (8 bytes)
+    [0] +4     i16 (2 bytes)
+    [1] +6     i8 (1 byte)
+    [2] +8     i32 (4 bytes)
+struct %struct.Packet: 48 bytes
+  [0] +0     %struct.Header (12 bytes)
+    [0] +0     i16 (2 bytes)
+    [1] +4     %struct.Flags (8 bytes)
+  [1] +16    %union.Value (16 bytes)
+    [0] +16    double (8 bytes)
+    [1] +24    [8 x i8] (8 bytes)
+  [2] +32    %struct.anon (8 bytes)
+    [0] +32    i8 (1 byte)
+    [1] +36    i32 (4 bytes)
+  [3] +40    %struct.Packet* (8 bytes)
+struct %struct.anon: 8 bytes
+  [0] +0     i8 (1 byte)
+  [1] +4     i32 (4 bytes)
+union %union.Value: 16 bytes
+  [0] +0     double (8 bytes)
+  [1] +8     [8 x i8] (8 bytes)
+
+Globals:
+  @packets: [4 x %struct.Packet] (192 bytes), linkage External (module tests/bcfiles/interface.bc)
+  @last_value: %union.Value (16 bytes), linkage External (module tests/bcfiles/interface.bc)
+
+Functions:
+  packet_kind(i32 %0) -> i32, linkage External (module tests/bcfiles/interface.bc)
+");
+}
@@ -17,19 +17,79 @@ fn while_loop() {
     let funcname = "while_loop";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
     assert_eq!(args[0], SolutionValue::I32(3));
 }
 
+#[test]
+fn while_loop_summary_with_answer() {
+    let funcname = "while_loop";
+    init_logging();
+    let proj = get_project();
+    let mut config = Config::default();
+    config.loop_bound = 4;
+    let outcome = find_zero_of_func(funcname, &proj, config)
+            .unwrap_or_else(|r| panic!("{}", r));
+    assert_eq!(outcome.answer, Some(vec![SolutionValue::I32(3)]));
+    // the first path tries to loop forever, so it hits the loop bound before we find the answer
+    assert!(outcome.summary.paths_killed >= 1);
+    assert!(outcome.summary.paths_completed >= 1);
+    assert_eq!(outcome.summary.paths_inconclusive, 0);
+    assert_eq!(outcome.summary.limits_hit, vec![LimitHit::LoopBound(4)]);
+    assert!(outcome.summary.assumptions.is_empty());
+    assert!(!outcome.summary.is_complete());
+}
+
+#[test]
+fn while_loop_summary_without_answer() {
+    let funcname = "while_loop";
+    init_logging();
+    let proj = get_project();
+    // returning 0 requires 3 iterations, which this loop bound doesn't allow
+    let mut config = Config::default();
+    config.loop_bound = 2;
+    let outcome = find_zero_of_func(funcname, &proj, config)
+            .unwrap_or_else(|r| panic!("{}", r));
+    assert_eq!(outcome.answer, None);
+    assert!(outcome.summary.paths_killed >= 1);
+    assert!(outcome.summary.paths_completed >= 1);
+    assert_eq!(outcome.summary.paths_inconclusive, 0);
+    assert_eq!(outcome.summary.limits_hit, vec![LimitHit::LoopBound(2)]);
+    assert!(!outcome.summary.is_complete());
+}
+
+#[test]
+fn while_loop_max_iterations() {
+    let funcname = "while_loop";
+    init_logging();
+    let proj = get_project();
+    let mut config = Config::default();
+    config.max_loop_iterations = Some(3);
+    let args = find_zero_of_func_simple(funcname, &proj, config)
+            .unwrap_or_else(|r| panic!("{}", r))
+            .expect("Failed to find zero of the function");
+    assert_eq!(args.len(), 1);
+    assert_eq!(args[0], SolutionValue::I32(3));
+
+    // returning 0 requires 3 iterations, which this limit doesn't allow
+    let mut config = Config::default();
+    config.max_loop_iterations = Some(2);
+    let outcome = find_zero_of_func(funcname, &proj, config)
+            .unwrap_or_else(|r| panic!("{}", r));
+    assert_eq!(outcome.answer, None);
+    assert!(outcome.summary.paths_killed >= 1);
+    assert_eq!(outcome.summary.limits_hit, vec![LimitHit::LoopBound(2)]);
+}
+
 #[test]
 fn for_loop() {
     let funcname = "for_loop";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -41,7 +101,7 @@ fn loop_zero_iterations() {
     let funcname = "loop_zero_iterations";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -53,7 +113,7 @@ fn loop_with_cond() {
     let funcname = "loop_with_cond";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -65,7 +125,7 @@ fn loop_inside_cond() {
     let funcname = "loop_inside_cond";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -77,7 +137,7 @@ fn loop_over_array() {
     let funcname = "loop_over_array";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -89,7 +149,7 @@ fn sum_of_array() {
     let funcname = "sum_of_array";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -101,7 +161,7 @@ fn search_array() {
     let funcname = "search_array";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -115,9 +175,34 @@ fn nested_loop() {
     let proj = get_project();
     let mut config = Config::default();
     config.loop_bound = 50;
-    let args = find_zero_of_func(funcname, &proj, config)
+    let args = find_zero_of_func_simple(funcname, &proj, config)
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
     assert_eq!(args[0], SolutionValue::I32(3));
 }
+
+#[test]
+fn nested_loop_max_iterations() {
+    let funcname = "nested_loop";
+    init_logging();
+    let proj = get_project();
+    // the inner loop's iterations are counted separately for each iteration
+    // of the outer loop, so 10 is enough even though it runs 30 times in all
+    let mut config = Config::default();
+    config.loop_bound = 50;
+    config.max_loop_iterations = Some(10);
+    let args = find_zero_of_func_simple(funcname, &proj, config)
+            .unwrap_or_else(|r| panic!("{}", r))
+            .expect("Failed to find zero of the function");
+    assert_eq!(args.len(), 1);
+    assert_eq!(args[0], SolutionValue::I32(3));
+
+    let mut config = Config::default();
+    config.loop_bound = 50;
+    config.max_loop_iterations = Some(9);
+    let outcome = find_zero_of_func(funcname, &proj, config)
+            .unwrap_or_else(|r| panic!("{}", r));
+    assert_eq!(outcome.answer, None);
+    assert_eq!(outcome.summary.limits_hit, vec![LimitHit::LoopBound(9)]);
+}
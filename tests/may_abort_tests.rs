@@ -23,7 +23,7 @@ fn get_panic_project() -> Project {
 fn may_exit() {
     let funcname = "may_exit";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_abort_project(),
@@ -41,7 +41,7 @@ fn may_exit() {
 fn may_panic() {
     let funcname = "panic::may_panic";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_panic_project(),
@@ -18,7 +18,7 @@ fn load_and_store() {
     let funcname = "load_and_store";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -30,7 +30,7 @@ fn local_ptr() {
     let funcname = "local_ptr";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -42,7 +42,7 @@ fn overwrite() {
     let funcname = "overwrite";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -54,7 +54,7 @@ fn load_and_store_mult() {
     let funcname = "load_and_store_mult";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -68,7 +68,7 @@ fn array() {
     let proj = get_project();
     let mut config = Config::default();
     config.null_pointer_checking = NullPointerChecking::None;  // otherwise this test fails, as ptr[10] could be NULL for the correct value of ptr
-    let args = find_zero_of_func(funcname, &proj, config)
+    let args = find_zero_of_func_simple(funcname, &proj, config)
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -82,7 +82,7 @@ fn pointer_arith() {
     let proj = get_project();
     let mut config = Config::default();
     config.null_pointer_checking = NullPointerChecking::None;  // otherwise this test fails, as e.g. ptr[2] or ptr[5] or something could be NULL, for the correct value of ptr
-    let args = find_zero_of_func(funcname, &proj, config)
+    let args = find_zero_of_func_simple(funcname, &proj, config)
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 2);
@@ -94,7 +94,7 @@ fn pointer_compare() {
     let funcname = "pointer_compare";
     init_logging();
     let proj = get_project();
-    let args = find_zero_of_func(funcname, &proj, Config::default())
+    let args = find_zero_of_func_simple(funcname, &proj, Config::default())
             .unwrap_or_else(|r| panic!("{}", r))
             .expect("Failed to find zero of the function");
     assert_eq!(args.len(), 1);
@@ -17,7 +17,7 @@ fn get_project() -> Project {
 fn doesnt_throw() {
     let funcname = "doesnt_throw";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_project(),
@@ -44,7 +44,7 @@ fn doesnt_throw() {
 fn throw_uncaught() {
     let funcname = "throw_uncaught";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_project(),
@@ -62,7 +62,7 @@ fn throw_uncaught() {
 fn throw_multiple_values() {
     let funcname = "throw_multiple_values";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_project(),
@@ -82,7 +82,7 @@ fn throw_multiple_values() {
 fn throw_uncaught_wrongtype() {
     let funcname = "throw_uncaught_wrongtype";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_project(),
@@ -104,7 +104,7 @@ fn throw_uncaught_wrongtype() {
 fn throw_uncaught_caller() {
     let funcname = "throw_uncaught_caller";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_project(),
@@ -122,7 +122,7 @@ fn throw_uncaught_caller() {
 fn throw_and_catch_wildcard() {
     let funcname = "throw_and_catch_wildcard";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_project(),
@@ -140,7 +140,7 @@ fn throw_and_catch_wildcard() {
 fn throw_and_catch_val() {
     let funcname = "throw_and_catch_val";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_project(),
@@ -162,7 +162,7 @@ fn throw_and_catch_val() {
 fn throw_and_catch_in_caller() {
     let funcname = "throw_and_catch_in_caller";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_project(),
@@ -186,7 +186,7 @@ fn throw_and_catch_in_caller() {
 fn throw_and_rethrow_in_caller() {
     let funcname = "throw_and_rethrow_in_caller";
     init_logging();
-    let rvals = get_possible_return_values_of_func(
+    let rvals = get_possible_return_values_of_func_simple(
         funcname,
         std::iter::once(None),
         &get_project(),